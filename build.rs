@@ -0,0 +1,15 @@
+use std::env;
+
+/// Embeds the application manifest in the Windows executables, which opts them in to paths
+/// longer than `MAX_PATH` on systems where long paths are enabled. The manifest is compiled into
+/// a resource and linked into every binary, which works with any version of Cargo and with both
+/// the MSVC and GNU toolchains.
+fn main() {
+    println!("cargo:rerun-if-changed=volta.rc");
+    println!("cargo:rerun-if-changed=volta.manifest");
+
+    let target = env::var("TARGET").unwrap_or_default();
+    if target.contains("windows") {
+        embed_resource::compile("volta.rc");
+    }
+}
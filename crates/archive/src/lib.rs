@@ -1,7 +1,9 @@
 //! This crate provides types for fetching and unpacking compressed
-//! archives in tarball or zip format.
+//! archives in tarball (gzip, xz, or zstd) or zip format, downloading them
+//! no faster than the rate set with `limit_rate`.
 
 mod tarball;
+mod throttle;
 mod zip;
 
 use failure::Fail;
@@ -12,11 +14,93 @@ pub struct HttpError {
     pub code: ::reqwest::StatusCode,
 }
 
-pub use crate::tarball::Tarball;
+/// Thrown when a download ends before as much data as the server announced with its
+/// `Content-Length` header has been received.
+#[derive(Fail, Debug)]
+#[fail(display = "download ended after {} of {} bytes", received, expected)]
+pub struct IncompleteDownloadError {
+    pub expected: u64,
+    pub received: u64,
+}
+
+pub use crate::tarball::{Compression, Tarball};
+pub use crate::throttle::{limit_rate, Throttle};
 pub use crate::zip::Zip;
 
 use std::fs::File;
+use std::io::copy;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+
+/// The timeout of requests in seconds, or 0 for the default timeout of the HTTP client.
+static TIMEOUT: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets how long the requests that are made from now on wait for the server before they fail, or
+/// restores the default timeout if there is none.
+pub fn set_timeout(timeout: Option<Duration>) {
+    let secs = timeout
+        .map(|timeout| timeout.as_secs() as usize)
+        .unwrap_or(0);
+    TIMEOUT.store(secs, Ordering::Relaxed);
+}
+
+lazy_static! {
+    /// The proxy that requests are sent through, if any.
+    static ref PROXY: RwLock<Option<String>> = RwLock::new(None);
+}
+
+/// Sets the proxy that the requests that are made from now on are sent through, or sends them
+/// directly if there is none.
+pub fn set_proxy(proxy: Option<String>) {
+    *PROXY.write().unwrap() = proxy;
+}
+
+/// Creates an HTTP client with the timeout set with `set_timeout` and the proxy set with
+/// `set_proxy`.
+pub fn client() -> reqwest::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = PROXY.read().unwrap().as_ref() {
+        builder = builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
+    }
+    match TIMEOUT.load(Ordering::Relaxed) {
+        0 => builder.build(),
+        secs => builder.timeout(Duration::from_secs(secs as u64)).build(),
+    }
+}
+
+/// Requests a URL with the timeout set with `set_timeout` and the proxy set with `set_proxy`.
+pub fn get(url: &str) -> reqwest::Result<reqwest::Response> {
+    client()?.get(url).send()
+}
+
+/// Downloads a file from the specified URL into the specified path, no faster than the rate set
+/// with `limit_rate`. Files that aren't archives, like checksum listings, are fetched this way too.
+pub fn download(url: &str, file: &Path) -> Result<(), failure::Error> {
+    let response = get(url)?;
+
+    if !response.status().is_success() {
+        Err(HttpError {
+            code: response.status(),
+        })?;
+    }
+
+    let expected = response.content_length();
+    let mut file = File::create(file)?;
+    let received = copy(&mut Throttle::new(response), &mut file);
+    match (expected, received) {
+        (Some(expected), Ok(received)) if received < expected => {
+            Err(IncompleteDownloadError { expected, received })?
+        }
+        (_, received) => {
+            received?;
+        }
+    }
+    Ok(())
+}
 
 /// Metadata describing whether an archive comes from a local or remote origin.
 #[derive(Copy, Clone)]
@@ -25,7 +109,9 @@ pub enum Origin {
     Remote,
 }
 
-pub trait Archive {
+/// An archive that can be unpacked. Archives are `Send`, so that several can be unpacked at once
+/// on different threads.
+pub trait Archive: Send {
     fn compressed_size(&self) -> u64;
     fn uncompressed_size(&self) -> Option<u64>;
 
@@ -39,6 +125,40 @@ pub trait Archive {
     fn origin(&self) -> Origin;
 }
 
+/// The format of an archive file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    Tarball(Compression),
+    Zip,
+}
+
+impl Format {
+    /// The extension of an archive's file name in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Tarball(compression) => compression.extension(),
+            Format::Zip => "zip",
+        }
+    }
+
+    /// Load an archive in this format from the specified file.
+    pub fn load(self, source: File) -> Result<Box<Archive>, failure::Error> {
+        match self {
+            Format::Tarball(compression) => Tarball::load_compressed(source, compression),
+            Format::Zip => Zip::load(source),
+        }
+    }
+
+    /// Fetch a remote archive in this format from the specified URL and store its
+    /// results at the specified file path.
+    pub fn fetch(self, url: &str, cache_file: &Path) -> Result<Box<Archive>, failure::Error> {
+        match self {
+            Format::Tarball(compression) => Tarball::fetch_compressed(url, cache_file, compression),
+            Format::Zip => Zip::fetch(url, cache_file),
+        }
+    }
+}
+
 cfg_if::cfg_if! {
     if #[cfg(unix)] {
         /// Load an archive in the native OS-preferred format from the specified file.
@@ -57,6 +177,22 @@ cfg_if::cfg_if! {
         pub fn fetch_native(url: &str, cache_file: &Path) -> Result<Box<Archive>, failure::Error> {
             Tarball::fetch(url, cache_file)
         }
+
+        /// The native OS-preferred archive format.
+        ///
+        /// On Windows, the preferred format is zip. On Unixes, the preferred format
+        /// is tarball.
+        pub fn native_format() -> Format {
+            Format::Tarball(Compression::Gzip)
+        }
+
+        /// Formats that are usually smaller than the native format, in order of preference,
+        /// which can be tried before falling back to the native format.
+        ///
+        /// On Windows, there are none. On Unixes, tarballs compressed with xz or zstd.
+        pub fn preferred_formats() -> &'static [Format] {
+            &[Format::Tarball(Compression::Xz), Format::Tarball(Compression::Zstd)]
+        }
     } else if #[cfg(windows)] {
         /// Load an archive in the native OS-preferred format from the specified file.
         ///
@@ -74,6 +210,22 @@ cfg_if::cfg_if! {
         pub fn fetch_native(url: &str, cache_file: &Path) -> Result<Box<Archive>, failure::Error> {
             Zip::fetch(url, cache_file)
         }
+
+        /// The native OS-preferred archive format.
+        ///
+        /// On Windows, the preferred format is zip. On Unixes, the preferred format
+        /// is tarball.
+        pub fn native_format() -> Format {
+            Format::Zip
+        }
+
+        /// Formats that are usually smaller than the native format, in order of preference,
+        /// which can be tried before falling back to the native format.
+        ///
+        /// On Windows, there are none. On Unixes, tarballs compressed with xz or zstd.
+        pub fn preferred_formats() -> &'static [Format] {
+            &[]
+        }
     } else {
         compile_error!("Unsupported OS (expected 'unix' or 'windows').");
     }
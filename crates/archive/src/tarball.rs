@@ -1,9 +1,12 @@
 //! Provides types and functions for fetching and unpacking a Node installation
 //! tarball in Unix operating systems.
 
-use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
-use std::path::Path;
+use std::ffi::OsString;
+use std::fs::{rename, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use failure::{self, Fail};
 use flate2::read::GzDecoder;
@@ -14,16 +17,127 @@ use reqwest::hyper_011::header::{AcceptRanges, ByteRangeSpec, ContentLength, Ran
 use reqwest::Response;
 use tar;
 use tee::TeeReader;
+use xz2::read::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
 
 use super::Archive;
+use super::IncompleteDownloadError;
 use super::Origin;
+use super::Throttle;
+
+/// The compression formats that tarballs can be fetched in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// The extension of a tarball's file name in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "tar.gz",
+            Compression::Xz => "tar.xz",
+            Compression::Zstd => "tar.zst",
+        }
+    }
+
+    fn decode<'a, R: Read + 'a>(self, data: R) -> Result<Box<Read + 'a>, failure::Error> {
+        Ok(match self {
+            Compression::Gzip => Box::new(GzDecoder::new(data)),
+            Compression::Xz => Box::new(XzDecoder::new(data)),
+            Compression::Zstd => Box::new(ZstdDecoder::new(data)?),
+        })
+    }
+}
 
 /// A Node installation tarball.
 pub struct Tarball {
+    compression: Compression,
     compressed_size: u64,
-    uncompressed_size: u64,
-    data: Box<Read>,
+    /// The uncompressed size, which is unknown for remote tarballs on servers that don't accept
+    /// byte range requests.
+    uncompressed_size: Option<u64>,
+    data: Box<Read + Send>,
     origin: Origin,
+    /// For a remote tarball, where the download is being saved.
+    download: Option<Download>,
+}
+
+/// A remote tarball that is saved to a partial file as it streams, which is only moved to the
+/// cache file once the whole tarball has been received. An interrupted download therefore never
+/// leaves a truncated tarball in the cache.
+struct Download {
+    partial_file: PathBuf,
+    cache_file: PathBuf,
+    /// The number of bytes that the server announced.
+    expected: u64,
+    received: Arc<Received>,
+}
+
+/// How much of a download has been received so far, and whether the server has stopped sending
+/// it, either by closing the connection or with an error.
+#[derive(Default)]
+struct Received {
+    bytes: AtomicUsize,
+    ended: AtomicBool,
+}
+
+impl Download {
+    fn new(cache_file: &Path, expected: u64) -> Self {
+        let mut partial_file = OsString::from(cache_file.as_os_str());
+        partial_file.push(".partial");
+        Download {
+            partial_file: PathBuf::from(partial_file),
+            cache_file: cache_file.to_path_buf(),
+            expected,
+            received: Arc::new(Received::default()),
+        }
+    }
+
+    /// Fails with an `IncompleteDownloadError` if the server stopped sending the download early,
+    /// which takes the place of whatever error unpacking the cut off tarball ran into.
+    fn check_received(&self) -> Result<(), failure::Error> {
+        let received = self.received.bytes.load(Ordering::SeqCst) as u64;
+        if self.received.ended.load(Ordering::SeqCst) && received < self.expected {
+            Err(IncompleteDownloadError {
+                expected: self.expected,
+                received,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn complete(self) -> Result<(), failure::Error> {
+        rename(&self.partial_file, &self.cache_file)?;
+        Ok(())
+    }
+}
+
+/// Counts the bytes that are read from a download.
+struct Counted<R> {
+    inner: R,
+    received: Arc<Received>,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.inner.read(buf) {
+            Ok(0) if !buf.is_empty() => {
+                self.received.ended.store(true, Ordering::SeqCst);
+                Ok(0)
+            }
+            Ok(read) => {
+                self.received.bytes.fetch_add(read, Ordering::SeqCst);
+                Ok(read)
+            }
+            Err(error) => {
+                self.received.ended.store(true, Ordering::SeqCst);
+                Err(error)
+            }
+        }
+    }
 }
 
 #[derive(Fail, Debug)]
@@ -48,23 +162,48 @@ fn content_length(response: &Response) -> Result<u64, failure::Error> {
 }
 
 impl Tarball {
-    /// Loads a tarball from the specified file.
-    pub fn load(mut source: File) -> Result<Box<Archive>, failure::Error> {
-        let uncompressed_size = load_uncompressed_size(&mut source)?;
+    /// Loads a gzip tarball from the specified file.
+    pub fn load(source: File) -> Result<Box<Archive>, failure::Error> {
+        Tarball::load_compressed(source, Compression::Gzip)
+    }
+
+    /// Loads a tarball in the specified compression format from the specified file.
+    pub fn load_compressed(
+        mut source: File,
+        compression: Compression,
+    ) -> Result<Box<Archive>, failure::Error> {
+        // Only gzip records the uncompressed size where it can be read without decompressing
+        let uncompressed_size = match compression {
+            Compression::Gzip => Some(load_uncompressed_size(&mut source)?),
+            Compression::Xz | Compression::Zstd => None,
+        };
         let compressed_size = source.metadata()?.len();
         Ok(Box::new(Tarball {
+            compression,
             uncompressed_size,
             compressed_size,
             data: Box::new(source),
             origin: Origin::Local,
+            download: None,
         }))
     }
 
-    /// Initiate fetching of a tarball from the given URL, returning a
-    /// tarball that can be streamed (and that tees its data to a local
-    /// file as it streams).
+    /// Initiate fetching of a gzip tarball from the given URL, returning a
+    /// tarball that is unpacked as it streams (and that tees its data to a
+    /// local file as it streams, which is moved to the cache file once the
+    /// whole tarball has been received).
     pub fn fetch(url: &str, cache_file: &Path) -> Result<Box<Archive>, failure::Error> {
-        let response = reqwest::get(url)?;
+        Tarball::fetch_compressed(url, cache_file, Compression::Gzip)
+    }
+
+    /// Initiate fetching of a tarball in the specified compression format from
+    /// the given URL, in the same way as `fetch`.
+    pub fn fetch_compressed(
+        url: &str,
+        cache_file: &Path,
+        compression: Compression,
+    ) -> Result<Box<Archive>, failure::Error> {
+        let response = super::get(url)?;
 
         if !response.status().is_success() {
             Err(super::HttpError {
@@ -74,18 +213,31 @@ impl Tarball {
 
         let compressed_size = content_length(&response)?;
 
-        ensure_accepts_byte_ranges(&response)?;
-
-        let uncompressed_size = fetch_uncompressed_size(url, compressed_size)?;
-
-        let file = File::create(cache_file)?;
-        let data = Box::new(TeeReader::new(response, file));
+        // The size of a gzip tarball is recorded at its end, which can only be fetched up front
+        // with a byte range request. Otherwise, the size can't be known until the tarball has
+        // been unpacked, so progress is measured against the compressed size instead
+        let uncompressed_size =
+            if compression == Compression::Gzip && accepts_byte_ranges(&response) {
+                Some(fetch_uncompressed_size(url, compressed_size)?)
+            } else {
+                None
+            };
+
+        let download = Download::new(cache_file, compressed_size);
+        let file = File::create(&download.partial_file)?;
+        let counted = Counted {
+            inner: Throttle::new(response),
+            received: download.received.clone(),
+        };
+        let data = Box::new(TeeReader::new(counted, file));
 
         Ok(Box::new(Tarball {
+            compression,
             uncompressed_size,
             compressed_size,
             data,
             origin: Origin::Remote,
+            download: Some(download),
         }))
     }
 }
@@ -95,23 +247,62 @@ impl Archive for Tarball {
         self.compressed_size
     }
     fn uncompressed_size(&self) -> Option<u64> {
-        Some(self.uncompressed_size)
+        self.uncompressed_size
     }
     fn unpack(
         self: Box<Self>,
         dest: &Path,
         progress: &mut FnMut(&(), usize),
     ) -> Result<(), failure::Error> {
-        let decoded = GzDecoder::new(self.data);
-        let mut tarball = tar::Archive::new(ProgressRead::new(decoded, (), progress));
-        tarball.unpack(dest)?;
-        Ok(())
+        let Tarball {
+            compression,
+            uncompressed_size,
+            mut data,
+            download,
+            ..
+        } = *self;
+
+        let unpacked = unpack_data(compression, uncompressed_size, &mut data, dest, progress);
+
+        match download {
+            Some(download) => {
+                // The end of the tar archive can come before the end of the gzip stream, so the
+                // rest of the download is read to make sure that the cached tarball is complete
+                let unpacked = unpacked.and_then(|()| {
+                    io::copy(&mut data, &mut io::sink())?;
+                    Ok(())
+                });
+                drop(data);
+                download.check_received()?;
+                unpacked?;
+                download.complete()
+            }
+            None => unpacked,
+        }
     }
     fn origin(&self) -> Origin {
         self.origin
     }
 }
 
+fn unpack_data(
+    compression: Compression,
+    uncompressed_size: Option<u64>,
+    data: &mut Box<Read + Send>,
+    dest: &Path,
+    progress: &mut FnMut(&(), usize),
+) -> Result<(), failure::Error> {
+    // Progress is reported in the same units as the size the caller was given
+    if uncompressed_size.is_some() {
+        let decoded = compression.decode(data)?;
+        tar::Archive::new(ProgressRead::new(decoded, (), progress)).unpack(dest)?;
+    } else {
+        let decoded = compression.decode(ProgressRead::new(data, (), progress))?;
+        tar::Archive::new(decoded).unpack(dest)?;
+    }
+    Ok(())
+}
+
 // From http://www.gzip.org/zlib/rfc-gzip.html#member-format
 //
 //   0   1   2   3   4   5   6   7
@@ -143,7 +334,7 @@ struct UnexpectedContentLengthError {
 /// downloading the entire gzip file. For very small files it's unlikely to be
 /// more efficient than simply downloading the entire file up front.
 fn fetch_isize(url: &str, len: u64) -> Result<[u8; 4], failure::Error> {
-    let client = reqwest::Client::new();
+    let client = super::client()?;
     let mut response = client
         .get(url)
         .header_011(Range::Bytes(vec![ByteRangeSpec::FromTo(len - 4, len - 1)]))
@@ -178,20 +369,13 @@ fn load_isize(file: &mut File) -> Result<[u8; 4], failure::Error> {
     Ok(buf)
 }
 
-#[derive(Fail, Debug)]
-#[fail(display = "HTTP server does not accept byte range requests")]
-struct ByteRangesNotAcceptedError;
-
-fn ensure_accepts_byte_ranges(response: &Response) -> Result<(), failure::Error> {
-    if !response
+/// Determines whether the server accepts byte range requests for the response's URL.
+fn accepts_byte_ranges(response: &Response) -> bool {
+    response
         .headers()
         .get_011::<AcceptRanges>()
         .map(|v| v.iter().any(|unit| *unit == RangeUnit::Bytes))
         .unwrap_or(false)
-    {
-        Err(ByteRangesNotAcceptedError)?;
-    }
-    Ok(())
 }
 
 /// Determines the uncompressed size of a gzip file hosted at the specified
@@ -213,9 +397,14 @@ fn load_uncompressed_size(file: &mut File) -> Result<u64, failure::Error> {
 #[cfg(test)]
 pub mod tests {
 
-    use crate::tarball::Tarball;
-    use std::fs::File;
+    use crate::tarball::{Compression, Counted, Download, Tarball};
+    use crate::{Archive, IncompleteDownloadError, Origin};
+    use std::env;
+    use std::fs::{create_dir_all, metadata, read_to_string, remove_dir_all, File};
+    use std::io::Read;
     use std::path::PathBuf;
+    use std::process;
+    use tee::TeeReader;
 
     fn fixture_path(fixture_dir: &str) -> PathBuf {
         let mut cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -234,4 +423,111 @@ pub mod tests {
         assert_eq!(tarball.uncompressed_size(), Some(10240));
         assert_eq!(tarball.compressed_size(), 402);
     }
+
+    #[test]
+    fn test_unpack_compressed() {
+        let dir = env::temp_dir().join(format!("volta-archive-compressed-{}", process::id()));
+
+        for &compression in &[Compression::Gzip, Compression::Xz, Compression::Zstd] {
+            let mut test_file_path = fixture_path("tarballs");
+            test_file_path.push(format!("test-file.{}", compression.extension()));
+            let test_file = File::open(test_file_path).expect("Couldn't open test file");
+            let tarball =
+                Tarball::load_compressed(test_file, compression).expect("Failed to load tarball");
+
+            let dest = dir.join(compression.extension());
+            tarball
+                .unpack(&dest, &mut |_, _| ())
+                .expect("Failed to unpack tarball");
+            assert_eq!(
+                read_to_string(dest.join("test-file.txt")).unwrap(),
+                read_to_string(dir.join("tar.gz").join("test-file.txt")).unwrap()
+            );
+        }
+
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unpack_completes_download() {
+        let dir = env::temp_dir().join(format!("volta-archive-test-{}", process::id()));
+        create_dir_all(&dir).unwrap();
+        let cache_file = dir.join("test-file.tar.gz");
+
+        let mut test_file_path = fixture_path("tarballs");
+        test_file_path.push("test-file.tar.gz");
+        let source = File::open(test_file_path).expect("Couldn't open test file");
+
+        let download = Download::new(&cache_file, 402);
+        let partial_file = download.partial_file.clone();
+        let counted = Counted {
+            inner: source,
+            received: download.received.clone(),
+        };
+        let tarball = Box::new(Tarball {
+            compression: Compression::Gzip,
+            compressed_size: 402,
+            uncompressed_size: None,
+            data: Box::new(TeeReader::new(
+                counted,
+                File::create(&partial_file).unwrap(),
+            )),
+            origin: Origin::Remote,
+            download: Some(download),
+        });
+
+        let mut read = 0;
+        tarball
+            .unpack(&dir.join("unpacked"), &mut |_, bytes| read += bytes)
+            .expect("Failed to unpack tarball");
+
+        // progress is measured in compressed bytes when the uncompressed size is unknown
+        assert_eq!(read, 402);
+        assert_eq!(metadata(&cache_file).unwrap().len(), 402);
+        assert!(!partial_file.exists());
+
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unpack_incomplete_download() {
+        let dir = env::temp_dir().join(format!("volta-archive-test-short-{}", process::id()));
+        create_dir_all(&dir).unwrap();
+        let cache_file = dir.join("test-file.tar.gz");
+
+        let mut test_file_path = fixture_path("tarballs");
+        test_file_path.push("test-file.tar.gz");
+        let source = File::open(test_file_path).expect("Couldn't open test file");
+
+        // the server announced all 402 bytes, but the connection closes after 200
+        let download = Download::new(&cache_file, 402);
+        let partial_file = download.partial_file.clone();
+        let counted = Counted {
+            inner: source.take(200),
+            received: download.received.clone(),
+        };
+        let tarball = Box::new(Tarball {
+            compression: Compression::Gzip,
+            compressed_size: 402,
+            uncompressed_size: None,
+            data: Box::new(TeeReader::new(
+                counted,
+                File::create(&partial_file).unwrap(),
+            )),
+            origin: Origin::Remote,
+            download: Some(download),
+        });
+
+        let error = tarball
+            .unpack(&dir.join("unpacked"), &mut |_, _| {})
+            .expect_err("Unpacked an incomplete tarball");
+        let incomplete = error
+            .downcast_ref::<IncompleteDownloadError>()
+            .expect("Expected an incomplete download");
+        assert_eq!(incomplete.expected, 402);
+        assert_eq!(incomplete.received, 200);
+        assert!(!cache_file.exists());
+
+        remove_dir_all(&dir).unwrap();
+    }
 }
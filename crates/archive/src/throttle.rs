@@ -0,0 +1,108 @@
+//! Provides a reader that limits how fast archives are downloaded, so that downloading a tool
+//! doesn't saturate a slow or metered connection.
+
+use std::cmp::{max, min};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+/// The download rate limit in bytes per second, or 0 for no limit.
+static RATE_LIMIT: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    /// When the bandwidth that has already been handed out will have been used up. It's shared
+    /// by every download, so that downloads on several threads are limited together.
+    static ref BUDGET: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// Limits the archives that are downloaded from now on to the specified number of bytes per
+/// second altogether, or lifts the limit if there is none.
+pub fn limit_rate(bytes_per_second: Option<u64>) {
+    let limit = bytes_per_second.map(|limit| limit as usize).unwrap_or(0);
+    RATE_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// A reader that reads no faster than the rate set with `limit_rate`.
+pub struct Throttle<R: Read> {
+    source: R,
+}
+
+impl<R: Read> Throttle<R> {
+    pub fn new(source: R) -> Self {
+        Throttle { source }
+    }
+}
+
+impl<R: Read> Read for Throttle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let limit = match RATE_LIMIT.load(Ordering::Relaxed) {
+            0 => return self.source.read(buf),
+            limit => limit as u64,
+        };
+
+        // Reading a tenth of a second's worth at a time keeps the rate steady
+        let chunk = min(max(limit / 10, 1) as usize, buf.len());
+        let len = self.source.read(&mut buf[..chunk])?;
+
+        let now = Instant::now();
+        let wait = {
+            let mut budget = BUDGET.lock().unwrap_or_else(PoisonError::into_inner);
+            reserve(&mut budget, len, limit, now)
+        };
+        thread::sleep(wait);
+
+        Ok(len)
+    }
+}
+
+/// Hands out the bandwidth for `bytes` after whatever has already been handed out, returning how
+/// long to wait before the bytes have been "sent" at the limit.
+fn reserve(budget: &mut Option<Instant>, bytes: usize, limit: u64, now: Instant) -> Duration {
+    // Bandwidth that went unused while nothing was downloading can't be saved up for later
+    let start = match *budget {
+        Some(end) if end > now => end,
+        _ => now,
+    };
+    let end = start + Duration::from_nanos(bytes as u64 * 1_000_000_000 / limit);
+    *budget = Some(end);
+    end - now
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::reserve;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_reserve_shares_bandwidth() {
+        let now = Instant::now();
+        let mut budget = None;
+
+        assert_eq!(
+            reserve(&mut budget, 50, 100, now),
+            Duration::from_millis(500)
+        );
+        // a second read at the same time waits for the first one's share to be used up
+        assert_eq!(
+            reserve(&mut budget, 50, 100, now),
+            Duration::from_millis(1000)
+        );
+    }
+
+    #[test]
+    fn test_reserve_does_not_save_up_bandwidth() {
+        let now = Instant::now();
+        let mut budget = Some(now);
+
+        let later = now + Duration::from_secs(10);
+        assert_eq!(
+            reserve(&mut budget, 50, 100, later),
+            Duration::from_millis(500)
+        );
+    }
+}
@@ -1,12 +1,11 @@
-//! Provides types and functions for fetching and unpacking a Node installation
-//! zip file in Windows operating systems.
+//! Provides types and functions for fetching and unpacking zip files, such as a
+//! Node installation in Windows operating systems or a Deno release.
 
 use std::fs::{create_dir_all, File};
 use std::io::copy;
-use std::path::Path;
+use std::path::{Path, MAIN_SEPARATOR};
 
 use progress_read::ProgressRead;
-use reqwest;
 use verbatim::PathExt;
 use zip_rs::ZipArchive;
 
@@ -36,18 +35,7 @@ impl Zip {
     /// Initiate fetching of a Node zip archive from the given URL, returning
     /// a `Remote` data source.
     pub fn fetch(url: &str, cache_file: &Path) -> Result<Box<Archive>, failure::Error> {
-        let mut response = reqwest::get(url)?;
-
-        if !response.status().is_success() {
-            Err(super::HttpError {
-                code: response.status(),
-            })?;
-        }
-
-        {
-            let mut file = File::create(cache_file)?;
-            copy(&mut response, &mut file)?;
-        }
+        super::download(url, cache_file)?;
 
         let file = File::open(cache_file)?;
         let compressed_size = file.metadata()?.len();
@@ -82,10 +70,10 @@ impl Archive for Zip {
             let (is_dir, subpath) = {
                 let name = entry.name();
 
-                // Verbatim paths aren't normalized so we have to use correct r"\" separators.
+                // Verbatim paths aren't normalized so we have to use the platform's separators.
                 (
                     name.ends_with('/'),
-                    Path::new(&name.replace('/', r"\")).to_path_buf(),
+                    Path::new(&name.replace('/', &MAIN_SEPARATOR.to_string())).to_path_buf(),
                 )
             };
 
@@ -11,5 +11,6 @@ macro_rules! ok_or_panic {
 }
 
 pub mod matchers;
+pub mod mock_server;
 pub mod paths;
 pub mod process;
@@ -0,0 +1,344 @@
+//! A mock HTTP server that stands in for the public Node, Yarn, and npm servers, so that tests can
+//! fetch tools without the network, and can make the servers fail in the ways that real ones do:
+//! with error statuses, with responses that are cut off partway, or by never responding at all.
+//!
+//! Builds of Volta with the `mock-network` feature send their requests to the server that
+//! `VOLTA_MOCK_SERVER` points to, so each test can start its own server with `MockServer::start`
+//! and point Volta at its `url`. The paths that Volta requests are the same as with mockito, e.g.
+//! `/node-dist/index.json` for the Node index and `/registry/<package>` for package metadata.
+
+use std::cmp::min;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Map};
+use sha1::{Digest, Sha1};
+
+/// The longest that a request which hangs is held open, in case the client never gives up.
+const HANG_LIMIT: Duration = Duration::from_secs(120);
+
+/// How the server answers a request.
+#[derive(Clone)]
+pub struct Reply {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Duration,
+    fault: Option<Fault>,
+}
+
+#[derive(Clone, Copy)]
+enum Fault {
+    /// Closes the connection after sending only this many bytes of the body.
+    Truncate(usize),
+    /// Never responds.
+    Hang,
+}
+
+impl Reply {
+    /// Responds with `200 OK` and a body.
+    pub fn ok<B: Into<Vec<u8>>>(body: B) -> Self {
+        Reply {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+            delay: Duration::from_secs(0),
+            fault: None,
+        }
+    }
+
+    /// Responds with `200 OK` and a JSON body.
+    pub fn json(body: &str) -> Self {
+        Reply::ok(body).header("Content-Type", "application/json")
+    }
+
+    /// Responds with an error status, like `500` or `429`, and no body.
+    pub fn status(status: u16) -> Self {
+        Reply {
+            status,
+            ..Reply::ok(Vec::new())
+        }
+    }
+
+    /// Accepts the request but never responds, so that the client times out.
+    pub fn hang() -> Self {
+        Reply {
+            fault: Some(Fault::Hang),
+            ..Reply::ok(Vec::new())
+        }
+    }
+
+    /// Adds a header to the response, like `Retry-After`.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Waits before responding.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sends the length of the whole body, but closes the connection after only `len` bytes of it.
+    pub fn truncate(mut self, len: usize) -> Self {
+        self.fault = Some(Fault::Truncate(len));
+        self
+    }
+}
+
+struct Route {
+    method: String,
+    path: String,
+    replies: Vec<Reply>,
+    hits: usize,
+}
+
+/// A mock HTTP server, which runs until it is dropped.
+pub struct MockServer {
+    url: String,
+    routes: Arc<Mutex<Vec<Route>>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    /// Starts a server on a free port of the loopback interface.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("could not start the mock server");
+        let address = listener
+            .local_addr()
+            .expect("could not find the address of the mock server");
+
+        let routes = Arc::new(Mutex::new(Vec::new()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        {
+            let routes = routes.clone();
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    // Each request gets its own thread, so that one that hangs doesn't hold up
+                    // the others
+                    if let Ok(stream) = stream {
+                        let routes = routes.clone();
+                        let shutdown = shutdown.clone();
+                        thread::spawn(move || {
+                            let _ = serve(stream, &routes, &shutdown);
+                        });
+                    }
+                }
+            });
+        }
+
+        MockServer {
+            url: format!("http://{}", address),
+            routes,
+            shutdown,
+        }
+    }
+
+    /// The URL of the server, for `VOLTA_MOCK_SERVER`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Answers requests for a path with a reply. Paths that haven't been mocked get a `404`. If
+    /// a path is mocked more than once, the latest mock is used.
+    pub fn mock(&self, method: &str, path: &str, reply: Reply) -> &Self {
+        self.mock_sequence(method, path, vec![reply])
+    }
+
+    /// Answers successive requests for a path with successive replies, repeating the last one
+    /// after they run out, e.g. to fail twice before succeeding.
+    pub fn mock_sequence(&self, method: &str, path: &str, replies: Vec<Reply>) -> &Self {
+        assert!(!replies.is_empty(), "a mock needs at least one reply");
+        self.routes().push(Route {
+            method: method.to_string(),
+            path: path.to_string(),
+            replies,
+            hits: 0,
+        });
+        self
+    }
+
+    /// Returns how many requests for a path the server has received, e.g. to check that a
+    /// request was retried.
+    pub fn hits(&self, path: &str) -> usize {
+        self.routes()
+            .iter()
+            .filter(|route| route.path == path)
+            .map(|route| route.hits)
+            .sum()
+    }
+
+    /// Serves the index of Node versions, in the format of `https://nodejs.org/dist/index.json`.
+    pub fn node_index(&self, body: &str) -> &Self {
+        self.mock("GET", "/node-dist/index.json", Reply::json(body))
+    }
+
+    /// Serves the list of Yarn releases, in the format of the GitHub releases API.
+    pub fn yarn_releases(&self, body: &str) -> &Self {
+        self.mock("GET", "/yarn-releases/index.json", Reply::json(body))
+    }
+
+    /// Serves the latest version of Yarn.
+    pub fn yarn_latest(&self, version: &str) -> &Self {
+        self.mock("GET", "/yarn-latest", Reply::ok(version))
+    }
+
+    /// Serves a package from the mock registry, with a tarball for each of its versions. The
+    /// metadata lists each version with the SHA-1 of its tarball, and tags the last one as
+    /// `latest`.
+    pub fn package(&self, name: &str, versions: &[(&str, &[u8])]) -> &Self {
+        let basename = name.rsplit('/').next().unwrap_or(name);
+        let mut entries = Map::new();
+
+        for &(version, tarball) in versions {
+            let tarball_path = format!("/registry/{}/-/{}-{}.tgz", name, basename, version);
+            entries.insert(
+                version.to_string(),
+                json!({
+                    "name": name,
+                    "version": version,
+                    "dist": {
+                        "shasum": hex::encode(&Sha1::digest(tarball)),
+                        "tarball": format!("{}{}", self.url, tarball_path),
+                    },
+                }),
+            );
+            self.mock("GET", &tarball_path, Reply::ok(tarball));
+        }
+
+        let metadata = json!({
+            "name": name,
+            "dist-tags": { "latest": versions.last().map(|&(version, _)| version) },
+            "versions": entries,
+        });
+        let path = format!("/registry/{}", name.replace('/', "%2f"));
+        self.mock("GET", &path, Reply::json(&metadata.to_string()))
+    }
+
+    fn routes(&self) -> MutexGuard<'_, Vec<Route>> {
+        self.routes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the listener up, so that it sees that the server has shut down
+        let _ = TcpStream::connect(self.url.trim_start_matches("http://"));
+    }
+}
+
+fn serve(stream: TcpStream, routes: &Mutex<Vec<Route>>, shutdown: &AtomicBool) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts
+        .next()
+        .and_then(|target| target.split('?').next())
+        .unwrap_or("")
+        .to_string();
+
+    // The request has to be read in full before responding, or the client may not see the
+    // response
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        let mut header = line.splitn(2, ':');
+        if let (Some(name), Some(value)) = (header.next(), header.next()) {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    io::copy(&mut reader.by_ref().take(content_length), &mut io::sink())?;
+
+    let reply = next_reply(routes, &method, &path).unwrap_or_else(|| Reply::status(404));
+    respond(stream, reply, shutdown)
+}
+
+fn next_reply(routes: &Mutex<Vec<Route>>, method: &str, path: &str) -> Option<Reply> {
+    let mut routes = routes.lock().unwrap_or_else(PoisonError::into_inner);
+    let route = routes
+        .iter_mut()
+        .rev()
+        .find(|route| route.method == method && route.path == path)?;
+
+    let reply = route.replies[min(route.hits, route.replies.len() - 1)].clone();
+    route.hits += 1;
+    Some(reply)
+}
+
+fn respond(mut stream: TcpStream, reply: Reply, shutdown: &AtomicBool) -> io::Result<()> {
+    thread::sleep(reply.delay);
+
+    let sent = match reply.fault {
+        Some(Fault::Hang) => return hang(stream, shutdown),
+        Some(Fault::Truncate(len)) => min(len, reply.body.len()),
+        None => reply.body.len(),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n",
+        reply.status,
+        reason(reply.status)
+    )?;
+    for (name, value) in &reply.headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+    write!(
+        stream,
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        reply.body.len()
+    )?;
+    stream.write_all(&reply.body[..sent])?;
+    stream.flush()?;
+    stream.shutdown(Shutdown::Write)
+}
+
+/// Holds the connection open without responding, until the client gives up, the server shuts
+/// down, or `HANG_LIMIT` has passed.
+fn hang(mut stream: TcpStream, shutdown: &AtomicBool) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_millis(100)))?;
+    let started = Instant::now();
+    let mut buf = [0; 512];
+
+    while started.elapsed() < HANG_LIMIT && !shutdown.load(Ordering::SeqCst) {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(ref error)
+                if error.kind() == io::ErrorKind::WouldBlock
+                    || error.kind() == io::ErrorKind::TimedOut => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Mock",
+    }
+}
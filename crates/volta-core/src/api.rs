@@ -0,0 +1,139 @@
+//! A stable API for embedding Volta's project and toolchain resolution in other Rust tools,
+//! such as build systems and language servers.
+//!
+//! Everything exported from this module follows semantic versioning: it will only change in
+//! incompatible ways in a new major version of Volta. The rest of `volta-core` exists to support
+//! the Volta executables and may change in any release, so none of its types are exposed here;
+//! the results are plain data that is owned by the caller.
+
+use std::path::{Path, PathBuf};
+
+use crate::platform::{self, PlatformSpec};
+use crate::project;
+use crate::session::Session;
+use crate::tool::ToolSpec;
+
+pub use volta_fail::{Fallible, VoltaError};
+
+/// A Node project, identified by the directory containing its package.json.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Project {
+    /// The root directory of the project.
+    pub root: PathBuf,
+    /// The platform that the project pins, if it pins Node.
+    pub pinned: Option<Platform>,
+}
+
+/// The versions of the tools that make up a platform.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Platform {
+    /// The version of Node.
+    pub node: String,
+    /// The version of npm, if not the one bundled with Node.
+    pub npm: Option<String>,
+    /// The version of Yarn, if any.
+    pub yarn: Option<String>,
+    /// The architecture of Node, if not the native one.
+    pub arch: Option<String>,
+}
+
+/// Where a tool of a resolved platform comes from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Source {
+    /// The project pins the tool.
+    Project,
+    /// The user's default platform provides the tool.
+    Default,
+}
+
+/// The platform that Volta would run tools with, along with where each of its tools comes from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolvedPlatform {
+    pub platform: Platform,
+    pub node: Source,
+    pub npm: Source,
+    pub yarn: Source,
+}
+
+impl Platform {
+    fn from_spec(spec: &PlatformSpec) -> Self {
+        Platform {
+            node: spec.node_runtime.to_string(),
+            npm: spec.npm.as_ref().map(ToString::to_string),
+            yarn: spec.yarn.as_ref().map(ToString::to_string),
+            arch: spec.arch.clone(),
+        }
+    }
+}
+
+impl From<platform::Source> for Source {
+    fn from(source: platform::Source) -> Self {
+        match source {
+            platform::Source::Project => Source::Project,
+            platform::Source::Default => Source::Default,
+        }
+    }
+}
+
+/// Finds the Node project containing the specified directory, if any.
+pub fn resolve_project(dir: &Path) -> Fallible<Option<Project>> {
+    Ok(project::Project::for_dir(dir)?.map(|project| Project {
+        root: project.project_root().to_path_buf(),
+        pinned: project
+            .platform()
+            .map(|platform| Platform::from_spec(&platform)),
+    }))
+}
+
+/// Determines the platform Volta would use in the specified directory, exactly as when running a
+/// tool there: the tools that the project containing it pins, over the user's default platform.
+pub fn resolve_platform(dir: &Path) -> Fallible<Option<ResolvedPlatform>> {
+    let session = Session::for_dir(dir);
+    Ok(session.merged_platform()?.map(|merged| ResolvedPlatform {
+        platform: Platform::from_spec(&merged.spec),
+        node: merged.node.into(),
+        npm: merged.npm.into(),
+        yarn: merged.yarn.into(),
+    }))
+}
+
+/// Installs a tool in the user's toolchain, exactly as `volta install` would. The tool is
+/// specified the same way as on the command line, e.g. `node@12`, `yarn`, or `typescript@^3.5`.
+pub fn install_tool(tool: &str) -> Fallible<()> {
+    let mut session = Session::new();
+    ToolSpec::try_from_str(tool)?.install(&mut session)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{resolve_platform, resolve_project, Platform, Source};
+    use std::path::PathBuf;
+
+    fn fixture_path(fixture_dir: &str) -> PathBuf {
+        let mut cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        cargo_manifest_dir.push("fixtures");
+        cargo_manifest_dir.push(fixture_dir);
+        cargo_manifest_dir
+    }
+
+    #[test]
+    fn resolves_pinned_project() {
+        let project_dir = fixture_path("basic");
+        let nested_dir = project_dir.join("subdir");
+        let pinned = Platform {
+            node: "6.11.1".to_string(),
+            npm: Some("3.10.10".to_string()),
+            yarn: Some("1.2.0".to_string()),
+            arch: None,
+        };
+
+        let project = resolve_project(&nested_dir).unwrap().unwrap();
+        assert_eq!(project.root, project_dir);
+        assert_eq!(project.pinned, Some(pinned.clone()));
+
+        let resolved = resolve_platform(&nested_dir).unwrap().unwrap();
+        assert_eq!(resolved.platform, pinned);
+        assert_eq!(resolved.node, Source::Project);
+        assert_eq!(resolved.yarn, Source::Project);
+    }
+}
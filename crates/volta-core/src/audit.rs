@@ -0,0 +1,272 @@
+//! Checks installed packages, and the packages they depend on, against the security advisories
+//! published by the npm registry.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ErrorDetails;
+use crate::inventory::public_package_registry_root;
+use crate::npmrc::Npmrc;
+use crate::version::VersionSpec;
+use volta_fail::{Fallible, ResultExt};
+
+/// The versions of each package in a dependency tree, by name.
+pub type PackageVersions = BTreeMap<String, BTreeSet<String>>;
+
+/// An advisory about a vulnerability in some versions of a package.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Advisory {
+    pub id: u64,
+    pub title: String,
+    pub url: String,
+    pub severity: String,
+    pub vulnerable_versions: String,
+    /// The versions that fix the vulnerability, which not every registry reports.
+    #[serde(default)]
+    pub patched_versions: Option<String>,
+}
+
+/// A version of a package in a dependency tree that an advisory applies to.
+#[derive(Clone, Debug, Serialize)]
+pub struct Vulnerability {
+    pub name: String,
+    pub version: String,
+    pub advisory: Advisory,
+}
+
+#[derive(Deserialize)]
+struct PackageManifest {
+    name: Option<String>,
+    version: Option<String>,
+}
+
+/// Lists the packages in the dependency tree of an installed package, including the package
+/// itself.
+pub fn dependency_tree(image_dir: &Path) -> Fallible<PackageVersions> {
+    let mut packages = PackageVersions::new();
+    add_package_dir(image_dir, &mut packages)?;
+    Ok(packages)
+}
+
+fn add_package_dir(dir: &Path, packages: &mut PackageVersions) -> Fallible<()> {
+    let manifest = read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<PackageManifest>(&json).ok());
+    match manifest {
+        Some(PackageManifest {
+            name: Some(name),
+            version: Some(version),
+        }) => {
+            packages.entry(name).or_default().insert(version);
+        }
+        _ => debug!(
+            "Skipping {}, which has no package name or version",
+            dir.display()
+        ),
+    }
+
+    let modules_dir = dir.join("node_modules");
+    for module_dir in package_dirs(&modules_dir)? {
+        add_package_dir(&module_dir, packages)?;
+    }
+
+    Ok(())
+}
+
+/// Lists the package directories in a `node_modules` directory, looking inside the directories of
+/// scopes. Links (e.g. from `npm link`) aren't followed, so that a tree can't contain itself.
+fn package_dirs(modules_dir: &Path) -> Fallible<Vec<PathBuf>> {
+    if !modules_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let read_error = |_: &io::Error| ErrorDetails::ReadImageError {
+        dir: modules_dir.to_path_buf(),
+    };
+
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(modules_dir).with_context(read_error)? {
+        let entry = entry.with_context(read_error)?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if name.starts_with('.') || !entry.file_type().with_context(read_error)?.is_dir() {
+            continue;
+        }
+
+        if name.starts_with('@') {
+            dirs.extend(package_dirs(&entry.path())?);
+        } else {
+            dirs.push(entry.path());
+        }
+    }
+
+    Ok(dirs)
+}
+
+/// Finds the vulnerable packages among the given packages, using the bulk advisory endpoint of
+/// the registry configured in `.npmrc`, or the public registry.
+pub fn find_vulnerabilities(packages: &PackageVersions) -> Fallible<Vec<Vulnerability>> {
+    if packages.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let npmrc = Npmrc::current()?;
+    let registry = npmrc
+        .registry_for("")
+        .unwrap_or_else(public_package_registry_root);
+    let url = format!("{}/-/npm/v1/security/advisories/bulk", registry);
+
+    let mut request = reqwest::Client::new().post(&url).json(packages);
+    if let Some(authorization) = npmrc.authorization_for(&registry) {
+        request = request.header(reqwest::header::AUTHORIZATION, authorization);
+    }
+
+    let response_text = request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.text())
+        .with_context(|_| ErrorDetails::AdvisoriesFetchError {
+            from_url: url.clone(),
+        })?;
+    let advisories: HashMap<String, Vec<Advisory>> = serde_json::from_str(&response_text)
+        .with_context(|_| ErrorDetails::ParseAdvisoriesError {
+            from_url: url.clone(),
+        })?;
+
+    Ok(match_advisories(packages, &advisories))
+}
+
+/// Matches the versions of each package with the advisories about it. The registry reports every
+/// advisory about a package it was asked about, so versions that aren't affected are filtered out
+/// here.
+fn match_advisories(
+    packages: &PackageVersions,
+    advisories: &HashMap<String, Vec<Advisory>>,
+) -> Vec<Vulnerability> {
+    let mut vulnerabilities = Vec::new();
+
+    for (name, versions) in packages {
+        for advisory in advisories.get(name).into_iter().flatten() {
+            for version in versions {
+                if is_affected(version, &advisory.vulnerable_versions) {
+                    vulnerabilities.push(Vulnerability {
+                        name: name.clone(),
+                        version: version.clone(),
+                        advisory: advisory.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    vulnerabilities
+}
+
+/// Determines whether a version is in the range of vulnerable versions. Versions or ranges that
+/// can't be parsed are assumed to be affected, since it's safer to report too much than too little.
+fn is_affected(version: &str, vulnerable_versions: &str) -> bool {
+    match (
+        Version::parse(version),
+        VersionSpec::parse_requirements(vulnerable_versions),
+    ) {
+        (Ok(version), Ok(range)) => range.matches(&version),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use tempfile::tempdir;
+
+    fn write_manifest(dir: &Path, name: &str, version: &str) {
+        create_dir_all(dir).unwrap();
+        write(
+            dir.join("package.json"),
+            format!(r#"{{ "name": "{}", "version": "{}" }}"#, name, version),
+        )
+        .unwrap();
+    }
+
+    fn advisory(vulnerable_versions: &str) -> Advisory {
+        Advisory {
+            id: 1,
+            title: "Prototype Pollution".to_string(),
+            url: "https://github.com/advisories/1".to_string(),
+            severity: "high".to_string(),
+            vulnerable_versions: vulnerable_versions.to_string(),
+            patched_versions: None,
+        }
+    }
+
+    #[test]
+    fn lists_dependency_tree() {
+        let image = tempdir().unwrap();
+        let root = image.path();
+        write_manifest(root, "cowsay", "1.4.0");
+        write_manifest(
+            &root.join("node_modules").join("minimist"),
+            "minimist",
+            "1.2.0",
+        );
+        write_manifest(
+            &root.join("node_modules").join("@types").join("node"),
+            "@types/node",
+            "12.0.0",
+        );
+        write_manifest(
+            &root
+                .join("node_modules")
+                .join("yargs")
+                .join("node_modules")
+                .join("minimist"),
+            "minimist",
+            "0.0.8",
+        );
+        write_manifest(&root.join("node_modules").join("yargs"), "yargs", "13.0.0");
+        create_dir_all(root.join("node_modules").join(".bin")).unwrap();
+
+        let tree = dependency_tree(root).unwrap();
+        let names: Vec<&String> = tree.keys().collect();
+        assert_eq!(names, vec!["@types/node", "cowsay", "minimist", "yargs"]);
+        assert_eq!(
+            tree["minimist"].iter().collect::<Vec<_>>(),
+            vec!["0.0.8", "1.2.0"]
+        );
+    }
+
+    #[test]
+    fn reports_only_affected_versions() {
+        let mut packages = PackageVersions::new();
+        packages
+            .entry("minimist".to_string())
+            .or_default()
+            .extend(vec!["0.0.8".to_string(), "1.2.6".to_string()]);
+        packages
+            .entry("yargs".to_string())
+            .or_default()
+            .insert("13.0.0".to_string());
+
+        let mut advisories = HashMap::new();
+        advisories.insert("minimist".to_string(), vec![advisory("<1.2.6")]);
+
+        let vulnerabilities = match_advisories(&packages, &advisories);
+        assert_eq!(vulnerabilities.len(), 1);
+        assert_eq!(vulnerabilities[0].name, "minimist");
+        assert_eq!(vulnerabilities[0].version, "0.0.8");
+    }
+
+    #[test]
+    fn assumes_unparseable_ranges_are_affected() {
+        assert!(is_affected("1.0.0", "not a range"));
+        assert!(!is_affected("2.0.0", "<1.2.6"));
+    }
+}
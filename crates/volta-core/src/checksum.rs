@@ -0,0 +1,463 @@
+//! Records a checksum of every file in an image when it is installed, so that `volta verify` can
+//! detect images that have been tampered with or corrupted since. The checksum of the archive that
+//! the image was installed from is recorded too, so that projects can pin it.
+
+use std::collections::BTreeMap;
+use std::fs::{self, read_to_string, remove_file, write, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use semver::Version;
+use sha2::{Digest, Sha256};
+
+use crate::error::ErrorDetails;
+use crate::path;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// The file in the root of an image that lists the checksums of its files. It is left out of the
+/// checksums itself.
+const MANIFEST_FILE: &str = ".volta-checksums.json";
+
+/// The file in the root of an image that records the SHA-256 of the archive it was installed from.
+/// It is left out of the checksums as well.
+const ARCHIVE_HASH_FILE: &str = ".volta-archive.sha256";
+
+/// The checksums of the files in an image, by their paths relative to the image, which always
+/// use `/` as the separator.
+type Checksums = BTreeMap<String, String>;
+
+/// The SHA-256s that a project pins for the archives of a version of a tool, by their file names,
+/// like `"node-v18.0.0-linux-x64.tar.xz"`, since each platform installs from a different archive.
+pub type ArchiveHashes = BTreeMap<String, String>;
+
+/// The SHA-256 of the archive that an image was installed from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchiveHash {
+    pub file_name: String,
+    pub sha256: String,
+}
+
+/// An installed image of a tool.
+pub struct InstalledImage {
+    /// The name of the tool, e.g. "node".
+    pub tool: &'static str,
+    pub version: Version,
+    pub dir: PathBuf,
+}
+
+/// The differences between an image and the checksums recorded when it was installed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Mismatches {
+    /// Files whose contents have changed.
+    pub changed: Vec<String>,
+    /// Files that have been removed.
+    pub missing: Vec<String>,
+    /// Files that have been added.
+    pub added: Vec<String>,
+}
+
+impl Mismatches {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.missing.is_empty() && self.added.is_empty()
+    }
+}
+
+/// Records the checksums of the files in a newly installed image.
+pub(crate) fn write_manifest(image_dir: &Path) -> Fallible<()> {
+    let checksums = checksum_tree(image_dir).with_context(|_| ErrorDetails::ReadImageError {
+        dir: image_dir.to_path_buf(),
+    })?;
+    let manifest_file = image_dir.join(MANIFEST_FILE);
+    let json = serde_json::to_string_pretty(&checksums).unknown()?;
+
+    write(&manifest_file, json).with_context(|_| ErrorDetails::WriteChecksumsError {
+        file: manifest_file.clone(),
+    })
+}
+
+/// Checks the archive that an image was just unpacked from against the SHA-256 that the project
+/// pins for it, if any, and returns the SHA-256 of the archive. An archive that doesn't match is
+/// removed, so that it isn't installed from later.
+pub(crate) fn check_archive(
+    archive: &Path,
+    expected: Option<&str>,
+    tool: &str,
+) -> Fallible<String> {
+    let actual = sha256_file(archive)?;
+
+    if let Some(expected) = expected {
+        if !expected.eq_ignore_ascii_case(&actual) {
+            let _ = remove_file(archive);
+            throw!(ErrorDetails::ArchiveHashMismatch {
+                tool: tool.to_string(),
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+    }
+
+    Ok(actual)
+}
+
+/// Checks an archive that an image was just unpacked from against the SHA-256 that the project
+/// pins for its file name, if any (see `check_archive`).
+pub(crate) fn check_pinned_archive(
+    archive: &Path,
+    pinned: &ArchiveHashes,
+    tool: &str,
+) -> Fallible<ArchiveHash> {
+    let file_name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let sha256 = check_archive(archive, pinned.get(&file_name).map(String::as_str), tool)?;
+
+    Ok(ArchiveHash { file_name, sha256 })
+}
+
+/// Checks the archive that an image was already installed from against the SHA-256 that the
+/// project pins for its file name, so that an image installed before the pin can't get around it.
+/// Images that didn't record their archive can't be checked.
+pub(crate) fn check_installed_archive(
+    image_dir: &Path,
+    pinned: &ArchiveHashes,
+    tool: &str,
+) -> Fallible<()> {
+    if pinned.is_empty() {
+        return Ok(());
+    }
+
+    match read_archive_hash(image_dir)? {
+        Some(ArchiveHash { file_name, sha256 }) => match pinned.get(&file_name) {
+            Some(expected) if !expected.eq_ignore_ascii_case(&sha256) => {
+                throw!(ErrorDetails::InstalledArchiveHashMismatch {
+                    tool: tool.to_string(),
+                    expected: expected.to_string(),
+                    actual: sha256,
+                    image: image_dir.to_path_buf(),
+                })
+            }
+            _ => Ok(()),
+        },
+        None => {
+            debug!(
+                "Not checking {} against its pinned SHA-256, since its archive wasn't recorded",
+                tool
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Records the SHA-256 of the archive that a newly installed image was installed from, along with
+/// the archive's file name, in the format of `sha256sum`.
+pub(crate) fn write_archive_hash(image_dir: &Path, hash: &ArchiveHash) -> Fallible<()> {
+    let hash_file = image_dir.join(ARCHIVE_HASH_FILE);
+    write(&hash_file, format!("{}  {}\n", hash.sha256, hash.file_name)).with_context(|_| {
+        ErrorDetails::WriteChecksumsError {
+            file: hash_file.clone(),
+        }
+    })
+}
+
+/// Reads the SHA-256 of the archive that an image was installed from. Returns `None` if it wasn't
+/// recorded with the archive's file name, e.g. because the image was installed by an older
+/// version of Volta.
+pub(crate) fn read_archive_hash(image_dir: &Path) -> Fallible<Option<ArchiveHash>> {
+    let hash_file = image_dir.join(ARCHIVE_HASH_FILE);
+    if !hash_file.is_file() {
+        return Ok(None);
+    }
+
+    let contents = read_to_string(&hash_file)
+        .with_context(|_| ErrorDetails::ReadChecksumsError { file: hash_file })?;
+    let mut words = contents.split_whitespace();
+    Ok(match (words.next(), words.next()) {
+        (Some(sha256), Some(file_name)) => Some(ArchiveHash {
+            file_name: file_name.to_string(),
+            sha256: sha256.to_string(),
+        }),
+        _ => None,
+    })
+}
+
+/// Calculates the SHA-256 of a file, like an archive that a tool can be installed from.
+pub(crate) fn sha256_file(file: &Path) -> Fallible<String> {
+    checksum_file(file).with_context(|_| ErrorDetails::ReadInventoryDirError {
+        dir: file.parent().unwrap_or(file).to_path_buf(),
+    })
+}
+
+/// Compares an image with the checksums recorded when it was installed. Returns `None` if no
+/// checksums were recorded, e.g. because the image was installed by an older version of Volta.
+pub fn verify_image(image_dir: &Path) -> Fallible<Option<Mismatches>> {
+    let manifest_file = image_dir.join(MANIFEST_FILE);
+    if !manifest_file.is_file() {
+        return Ok(None);
+    }
+
+    let json =
+        read_to_string(&manifest_file).with_context(|_| ErrorDetails::ReadChecksumsError {
+            file: manifest_file.clone(),
+        })?;
+    let recorded: Checksums =
+        serde_json::from_str(&json).with_context(|_| ErrorDetails::ReadChecksumsError {
+            file: manifest_file.clone(),
+        })?;
+    let actual = checksum_tree(image_dir).with_context(|_| ErrorDetails::ReadImageError {
+        dir: image_dir.to_path_buf(),
+    })?;
+
+    Ok(Some(compare(&recorded, &actual)))
+}
+
+/// Lists the installed Node and Yarn images, in order of tool and version.
+pub fn installed_images() -> Fallible<Vec<InstalledImage>> {
+    let mut images = Vec::new();
+
+    // Node images are kept in a directory for each npm version, within a directory for each
+    // Node version
+    for (version, node_dir) in versioned_dirs(&path::node_image_root_dir()?)? {
+        for (_, dir) in versioned_dirs(&node_dir)? {
+            images.push(InstalledImage {
+                tool: "node",
+                version: version.clone(),
+                dir,
+            });
+        }
+    }
+
+    for (version, dir) in versioned_dirs(&path::yarn_image_root_dir()?)? {
+        images.push(InstalledImage {
+            tool: "yarn",
+            version,
+            dir,
+        });
+    }
+
+    Ok(images)
+}
+
+/// Lists the subdirectories of a directory that are named for a version, in order of version.
+fn versioned_dirs(dir: &Path) -> Fallible<Vec<(Version, PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(dir).with_context(|_| ErrorDetails::ReadImageError {
+        dir: dir.to_path_buf(),
+    })?;
+    let mut dirs: Vec<(Version, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let version = Version::parse(entry.file_name().to_str()?).ok()?;
+            Some((version, entry.path()))
+        })
+        .collect();
+    dirs.sort();
+
+    Ok(dirs)
+}
+
+fn compare(recorded: &Checksums, actual: &Checksums) -> Mismatches {
+    let mut mismatches = Mismatches::default();
+
+    for (file, checksum) in recorded {
+        match actual.get(file) {
+            Some(actual_checksum) if actual_checksum == checksum => {}
+            Some(_) => mismatches.changed.push(file.clone()),
+            None => mismatches.missing.push(file.clone()),
+        }
+    }
+
+    mismatches.added = actual
+        .keys()
+        .filter(|file| !recorded.contains_key(*file))
+        .cloned()
+        .collect();
+
+    mismatches
+}
+
+fn checksum_tree(root: &Path) -> io::Result<Checksums> {
+    let mut checksums = Checksums::new();
+    checksum_dir(root, "", &mut checksums)?;
+    checksums.remove(MANIFEST_FILE);
+    checksums.remove(ARCHIVE_HASH_FILE);
+    Ok(checksums)
+}
+
+fn checksum_dir(dir: &Path, prefix: &str, checksums: &mut Checksums) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            checksum_dir(&entry.path(), &format!("{}/", name), checksums)?;
+        } else if file_type.is_symlink() && entry.path().is_dir() {
+            // A link to a directory is recorded by where it points, rather than followed
+            let target = fs::read_link(entry.path())?;
+            checksums.insert(name, format!("link:{}", target.display()));
+        } else {
+            checksums.insert(name, checksum_file(&entry.path())?);
+        }
+    }
+
+    Ok(())
+}
+
+fn checksum_file(file: &Path) -> io::Result<String> {
+    let mut file = File::open(file)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 8192];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.input(&buffer[..read]);
+    }
+
+    Ok(hex::encode(&hasher.result()))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::fs::create_dir_all;
+    use tempfile::tempdir;
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn detects_changed_files() {
+        let image = tempdir().unwrap();
+        create_dir_all(image.path().join("bin")).unwrap();
+        write(image.path().join("bin").join("node"), "node").unwrap();
+        write(image.path().join("README.md"), "readme").unwrap();
+
+        write_manifest(image.path()).unwrap();
+        assert_eq!(
+            verify_image(image.path()).unwrap(),
+            Some(Mismatches::default())
+        );
+
+        write(image.path().join("bin").join("node"), "tampered").unwrap();
+        fs::remove_file(image.path().join("README.md")).unwrap();
+        write(image.path().join("extra"), "extra").unwrap();
+
+        assert_eq!(
+            verify_image(image.path()).unwrap(),
+            Some(Mismatches {
+                changed: vec!["bin/node".to_string()],
+                missing: vec!["README.md".to_string()],
+                added: vec!["extra".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn checks_archives_against_pinned_hashes() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("node-v12.0.0-linux-x64.tar.gz");
+        write(&archive, "hello").unwrap();
+
+        assert_eq!(
+            check_archive(&archive, None, "node@12.0.0").unwrap(),
+            HELLO_SHA256
+        );
+        assert_eq!(
+            check_archive(&archive, Some(&HELLO_SHA256.to_uppercase()), "node@12.0.0").unwrap(),
+            HELLO_SHA256
+        );
+
+        assert!(check_archive(&archive, Some("0000"), "node@12.0.0").is_err());
+        assert!(!archive.exists());
+    }
+
+    #[test]
+    fn checks_archives_against_hashes_pinned_for_their_file_name() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("node-v12.0.0-linux-x64.tar.gz");
+        write(&archive, "hello").unwrap();
+
+        let mut pinned = ArchiveHashes::new();
+        pinned.insert(
+            "node-v12.0.0-darwin-x64.tar.gz".to_string(),
+            "0000".to_string(),
+        );
+        assert_eq!(
+            check_pinned_archive(&archive, &pinned, "node@12.0.0").unwrap(),
+            ArchiveHash {
+                file_name: "node-v12.0.0-linux-x64.tar.gz".to_string(),
+                sha256: HELLO_SHA256.to_string(),
+            }
+        );
+
+        pinned.insert(
+            "node-v12.0.0-linux-x64.tar.gz".to_string(),
+            "0000".to_string(),
+        );
+        assert!(check_pinned_archive(&archive, &pinned, "node@12.0.0").is_err());
+    }
+
+    #[test]
+    fn checks_installed_images_against_pinned_hashes() {
+        let image = tempdir().unwrap();
+        let mut pinned = ArchiveHashes::new();
+        pinned.insert(
+            "node-v12.0.0-linux-x64.tar.gz".to_string(),
+            "0000".to_string(),
+        );
+
+        // Without a recorded archive, there is nothing to check
+        assert!(check_installed_archive(image.path(), &pinned, "node@12.0.0").is_ok());
+
+        let hash = ArchiveHash {
+            file_name: "node-v12.0.0-linux-x64.tar.gz".to_string(),
+            sha256: HELLO_SHA256.to_string(),
+        };
+        write_archive_hash(image.path(), &hash).unwrap();
+        assert!(check_installed_archive(image.path(), &pinned, "node@12.0.0").is_err());
+        assert!(
+            check_installed_archive(image.path(), &ArchiveHashes::new(), "node@12.0.0").is_ok()
+        );
+
+        pinned.insert(hash.file_name.clone(), HELLO_SHA256.to_uppercase());
+        assert!(check_installed_archive(image.path(), &pinned, "node@12.0.0").is_ok());
+    }
+
+    #[test]
+    fn records_archive_hashes_outside_checksums() {
+        let image = tempdir().unwrap();
+        write(image.path().join("README.md"), "readme").unwrap();
+        assert_eq!(read_archive_hash(image.path()).unwrap(), None);
+
+        write_manifest(image.path()).unwrap();
+        let hash = ArchiveHash {
+            file_name: "node-v12.0.0-linux-x64.tar.gz".to_string(),
+            sha256: HELLO_SHA256.to_string(),
+        };
+        write_archive_hash(image.path(), &hash).unwrap();
+
+        assert_eq!(read_archive_hash(image.path()).unwrap(), Some(hash));
+        assert_eq!(
+            verify_image(image.path()).unwrap(),
+            Some(Mismatches::default())
+        );
+
+        // Older versions of Volta recorded the SHA-256 without the archive's file name
+        write(image.path().join(ARCHIVE_HASH_FILE), HELLO_SHA256).unwrap();
+        assert_eq!(read_archive_hash(image.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn skips_images_without_checksums() {
+        let image = tempdir().unwrap();
+        assert_eq!(verify_image(image.path()).unwrap(), None);
+    }
+}
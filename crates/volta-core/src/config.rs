@@ -0,0 +1,550 @@
+//! Provides the user's settings file, `config.toml` in the Volta home directory, which can set
+//! the toggles that are otherwise only set with environment variables, like `VOLTA_NO_PROGRESS`
+//! or `VOLTA_LIMIT_RATE`, in one place:
+//!
+//! ```toml
+//! version = 1
+//! progress = false
+//! limit-rate = "500k"
+//! timeout = 30
+//! passthrough = "warn"
+//! ```
+//!
+//! Each setting is applied by setting its environment variable for the rest of the process and
+//! for the tools that it launches, unless the variable is already set, so the environment always
+//! takes precedence over the file.
+//!
+//! The file is TOML, with every setting at the top of the file rather than in a table. It is
+//! checked strictly, so that a typo in a setting is reported along with the line it is on,
+//! rather than quietly ignored. The file starts with the version of its schema, so that a version
+//! of Volta that is too old to understand a file says so.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::distro;
+use crate::env::{
+    BACKGROUND_FETCH, INSTALL_PERMISSIONS, KEEP_ARCHIVES, LIMIT_RATE, LINK_IMAGES, NETWORK_TIMEOUT,
+    NO_PROGRESS, PARTIAL_PINS, PASSTHROUGH, POLICY, PROXY, SCRIPT_POLICY, TMP_DIR, TRANSITIVE_BINS,
+    UNOFFICIAL_BUILDS,
+};
+use crate::error::ErrorDetails;
+use crate::fs::{read_file_opt, write_atomic};
+use crate::path;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// The version of the schema of `config.toml` that this version of Volta writes and understands.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// The type of value that a setting takes, and how it maps to its environment variable.
+#[derive(Clone, Copy)]
+enum Kind {
+    /// `true` sets the variable, as if it had been set to `1`.
+    Flag,
+    /// `false` sets the variable, for variables that turn something off, like
+    /// `VOLTA_NO_PROGRESS`.
+    NegatedFlag,
+    /// Any string, which the variable is set to.
+    Text,
+    /// One of a list of strings, which the variable is set to.
+    Choice(&'static [&'static str]),
+    /// A positive whole number of seconds, which the variable is set to.
+    Seconds,
+}
+
+/// A setting that can be set in `config.toml`.
+pub struct Setting {
+    pub name: &'static str,
+    /// The environment variable that the setting stands in for.
+    pub var: &'static str,
+    kind: Kind,
+}
+
+static SETTINGS: &[Setting] = &[
+    Setting {
+        name: "background-fetch",
+        var: BACKGROUND_FETCH,
+        kind: Kind::Flag,
+    },
+    Setting {
+        name: "install-permissions",
+        var: INSTALL_PERMISSIONS,
+        kind: Kind::Choice(&["shared", "private"]),
+    },
+    Setting {
+        name: "keep-archives",
+        var: KEEP_ARCHIVES,
+        kind: Kind::Text,
+    },
+    Setting {
+        name: "limit-rate",
+        var: LIMIT_RATE,
+        kind: Kind::Text,
+    },
+    Setting {
+        name: "link-images",
+        var: LINK_IMAGES,
+        kind: Kind::Flag,
+    },
+    Setting {
+        name: "partial-pins",
+        var: PARTIAL_PINS,
+        kind: Kind::Choice(&["allow", "warn", "deny"]),
+    },
+    Setting {
+        name: "passthrough",
+        var: PASSTHROUGH,
+        kind: Kind::Choice(&["allow", "warn", "deny"]),
+    },
+    Setting {
+        name: "policy",
+        var: POLICY,
+        kind: Kind::Text,
+    },
+    Setting {
+        name: "progress",
+        var: NO_PROGRESS,
+        kind: Kind::NegatedFlag,
+    },
+    Setting {
+        name: "proxy",
+        var: PROXY,
+        kind: Kind::Text,
+    },
+    Setting {
+        name: "script-policy",
+        var: SCRIPT_POLICY,
+        kind: Kind::Choice(&["allow", "deny", "prompt"]),
+    },
+    Setting {
+        name: "timeout",
+        var: NETWORK_TIMEOUT,
+        kind: Kind::Seconds,
+    },
+    Setting {
+        name: "tmp-dir",
+        var: TMP_DIR,
+        kind: Kind::Text,
+    },
+    Setting {
+        name: "transitive-bins",
+        var: TRANSITIVE_BINS,
+        kind: Kind::Flag,
+    },
+    Setting {
+        name: "unofficial-builds",
+        var: UNOFFICIAL_BUILDS,
+        kind: Kind::Flag,
+    },
+];
+
+impl Setting {
+    /// Looks up a setting by its name.
+    pub fn find(name: &str) -> Fallible<&'static Setting> {
+        match SETTINGS.iter().find(|setting| setting.name == name) {
+            Some(setting) => Ok(setting),
+            None => throw!(ErrorDetails::UnknownConfigKey {
+                key: name.to_string(),
+            }),
+        }
+    }
+
+    /// Every setting, in the order that they are listed.
+    pub fn all() -> &'static [Setting] {
+        SETTINGS
+    }
+
+    /// Checks that a value is of the type that the setting takes, returning why it isn't if not.
+    fn check(&self, value: &Value) -> Result<(), String> {
+        match (self.kind, value) {
+            (Kind::Flag, Value::Bool(_)) | (Kind::NegatedFlag, Value::Bool(_)) => Ok(()),
+            (Kind::Flag, _) | (Kind::NegatedFlag, _) => Err("expected true or false".to_string()),
+            (Kind::Text, Value::Text(_)) => Ok(()),
+            (Kind::Text, _) => Err("expected a string".to_string()),
+            (Kind::Choice(choices), Value::Text(text)) if choices.contains(&text.as_str()) => {
+                Ok(())
+            }
+            (Kind::Choice(choices), _) => Err(format!("expected {}", describe_choices(choices))),
+            (Kind::Seconds, Value::Integer(secs)) if *secs > 0 => Ok(()),
+            (Kind::Seconds, _) => Err("expected a whole number of seconds".to_string()),
+        }
+    }
+
+    /// Parses a value given on the command line, where strings aren't quoted.
+    fn parse_arg(&self, arg: &str) -> Fallible<Value> {
+        let value = match self.kind {
+            Kind::Flag | Kind::NegatedFlag => match arg {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => Value::Text(arg.to_string()),
+            },
+            Kind::Text | Kind::Choice(_) => Value::Text(arg.to_string()),
+            Kind::Seconds => match arg.parse() {
+                Ok(secs) => Value::Integer(secs),
+                Err(_) => Value::Text(arg.to_string()),
+            },
+        };
+
+        match self.check(&value) {
+            Ok(()) => Ok(value),
+            Err(reason) => throw!(ErrorDetails::InvalidConfigValue {
+                key: self.name.to_string(),
+                value: arg.to_string(),
+                reason,
+            }),
+        }
+    }
+
+    /// Converts a value read from the file, returning why it isn't of the type that the setting
+    /// takes if not. Numbers are accepted for settings that take strings, like `limit-rate`.
+    fn read_value(&self, value: toml::Value) -> Result<Value, String> {
+        let value = match (self.kind, value) {
+            (_, toml::Value::Boolean(value)) => Value::Bool(value),
+            (_, toml::Value::String(text)) => Value::Text(text),
+            (Kind::Seconds, toml::Value::Integer(integer)) if integer >= 0 => {
+                Value::Integer(integer as u64)
+            }
+            (_, toml::Value::Integer(integer)) => Value::Text(integer.to_string()),
+            (_, other) => return Err(format!("unsupported {}", other.type_str())),
+        };
+        self.check(&value)?;
+        Ok(value)
+    }
+
+    /// The value that the setting's environment variable is set to, if the value sets it at all.
+    fn env_value(&self, value: &Value) -> Option<String> {
+        match (self.kind, value) {
+            (Kind::Flag, Value::Bool(true)) | (Kind::NegatedFlag, Value::Bool(false)) => {
+                Some("1".to_string())
+            }
+            (_, Value::Text(text)) => Some(text.clone()),
+            (_, Value::Integer(integer)) => Some(integer.to_string()),
+            _ => None,
+        }
+    }
+}
+
+fn describe_choices(choices: &[&str]) -> String {
+    let quoted: Vec<String> = choices
+        .iter()
+        .map(|choice| format!("\"{}\"", choice))
+        .collect();
+    match quoted.split_last() {
+        Some((last, rest)) if rest.len() > 1 => format!("{}, or {}", rest.join(", "), last),
+        Some((last, rest)) if rest.len() == 1 => format!("{} or {}", rest[0], last),
+        _ => quoted.join(""),
+    }
+}
+
+/// The value of a setting.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Integer(u64),
+    Text(String),
+}
+
+impl fmt::Display for Value {
+    /// Formats the value as it is written in `config.toml`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Integer(integer) => write!(f, "{}", integer),
+            Value::Text(text) => write!(f, "{}", toml::Value::String(text.clone())),
+        }
+    }
+}
+
+/// The settings in `config.toml`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    settings: BTreeMap<&'static str, Value>,
+}
+
+impl Config {
+    /// Loads the settings from `config.toml`, if it exists.
+    pub fn current() -> Fallible<Self> {
+        let file = path::config_file()?;
+        let src = read_file_opt(&file)
+            .with_context(|_| ErrorDetails::ReadConfigError { file: file.clone() })?;
+
+        match src {
+            Some(src) => Config::parse(&src, &file),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Returns the value of a setting, if it is set.
+    pub fn get(&self, setting: &Setting) -> Option<&Value> {
+        self.settings.get(setting.name)
+    }
+
+    /// Sets a setting to a value given on the command line.
+    pub fn set(&mut self, setting: &'static Setting, arg: &str) -> Fallible<()> {
+        let value = setting.parse_arg(arg)?;
+        self.settings.insert(setting.name, value);
+        Ok(())
+    }
+
+    /// Writes the settings to `config.toml`, replacing the file all at once so that shims that
+    /// start in the meantime never read a partly written file.
+    pub fn save(&self) -> Fallible<()> {
+        let file = path::config_file()?;
+        write_atomic(&file, self.to_string().as_bytes())
+            .with_context(|_| ErrorDetails::WriteConfigError { file: file.clone() })
+    }
+
+    /// Sets the environment variable of each setting that isn't already set in the environment.
+    pub fn apply(&self) {
+        for setting in SETTINGS {
+            if env::var_os(setting.var).is_some() {
+                continue;
+            }
+            if let Some(value) = self.get(setting).and_then(|value| setting.env_value(value)) {
+                env::set_var(setting.var, value);
+            }
+        }
+    }
+
+    fn parse(src: &str, file: &Path) -> Fallible<Self> {
+        let invalid =
+            |line: usize, key: Option<&str>, reason: String| ErrorDetails::InvalidConfig {
+                file: file.to_path_buf(),
+                line,
+                key: key.map(str::to_string),
+                reason,
+            };
+
+        let table: BTreeMap<String, toml::Value> = toml::from_str(src).map_err(|error| {
+            let line = error.line_col().map_or(1, |(line, _)| line + 1);
+            // The position is reported separately, so it's left out of the reason
+            let reason = error.to_string();
+            let reason = match reason.rfind(" at line ") {
+                Some(index) => reason[..index].to_string(),
+                None => reason,
+            };
+            invalid(line, None, reason)
+        })?;
+
+        let mut config = Config::default();
+        let mut version = None;
+
+        for (key, value) in table {
+            let number = line_of(src, &key);
+
+            if key == "version" {
+                let found = match value {
+                    toml::Value::Integer(found) if found > 0 => found as u64,
+                    _ => throw!(invalid(
+                        number,
+                        Some(&key),
+                        "expected the version of the schema, e.g. `version = 1`".to_string()
+                    )),
+                };
+                if found > CONFIG_VERSION as u64 {
+                    throw!(ErrorDetails::ConfigTooNew {
+                        file: file.to_path_buf(),
+                        version: found,
+                    });
+                }
+                version = Some(found);
+                continue;
+            }
+
+            if value.is_table() {
+                throw!(invalid(
+                    number,
+                    Some(&key),
+                    "tables aren't supported, so every setting goes at the top of the file"
+                        .to_string()
+                ));
+            }
+            let setting = match SETTINGS.iter().find(|setting| setting.name == key) {
+                Some(setting) => setting,
+                None => throw!(invalid(number, Some(&key), "unknown setting".to_string())),
+            };
+            let value = setting
+                .read_value(value)
+                .map_err(|reason| invalid(number, Some(&key), reason))?;
+            config.settings.insert(setting.name, value);
+        }
+
+        if version.is_none() && !config.settings.is_empty() {
+            throw!(invalid(
+                1,
+                Some("version"),
+                format!(
+                    "the file has to set the version of its schema, e.g. `version = {}`",
+                    CONFIG_VERSION
+                )
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+impl fmt::Display for Config {
+    /// Formats the settings as the contents of `config.toml`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# Volta settings (see `volta config list`)")?;
+        writeln!(f, "version = {}", CONFIG_VERSION)?;
+        for (name, value) in &self.settings {
+            writeln!(f, "{} = {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies the settings in `config.toml` to this process and the tools that it launches.
+pub fn apply() -> Fallible<()> {
+    Config::current()?.apply();
+    // The network settings are read when the session starts, before the file is loaded
+    distro::apply_network_settings();
+    Ok(())
+}
+
+/// Finds the line that a top-level key is on, since the parsed values don't keep their position.
+fn line_of(src: &str, key: &str) -> usize {
+    let quoted = [
+        key.to_string(),
+        format!("\"{}\"", key),
+        format!("'{}'", key),
+    ];
+
+    src.lines()
+        .position(|line| {
+            let line = line.trim_start();
+            let line = if line.starts_with('[') {
+                line.trim_start_matches('[').trim_start()
+            } else {
+                line
+            };
+            quoted.iter().any(|name| {
+                line.starts_with(name.as_str())
+                    && line[name.len()..]
+                        .trim_start()
+                        .starts_with(|c: char| c == '=' || c == ']' || c == '.')
+            })
+        })
+        .map_or(1, |index| index + 1)
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::{Config, Setting, Value};
+    use crate::error::ErrorDetails;
+    use std::path::Path;
+    use volta_fail::Fallible;
+
+    fn parse(src: &str) -> Fallible<Config> {
+        Config::parse(src, Path::new("/home/user/.volta/config.toml"))
+    }
+
+    fn assert_invalid(src: &str, line: usize, key: Option<&str>) {
+        let error = parse(src).unwrap_err();
+        match error.downcast_ref::<ErrorDetails>() {
+            Some(ErrorDetails::InvalidConfig {
+                line: found_line,
+                key: found_key,
+                ..
+            }) => assert_eq!(
+                (*found_line, found_key.as_ref().map(String::as_str)),
+                (line, key),
+                "{:?}",
+                src
+            ),
+            other => panic!("unexpected error for {:?}: {:?}", src, other),
+        }
+    }
+
+    #[test]
+    fn parses_settings() {
+        let config = parse(
+            "# Quieter downloads\nversion = 1\nprogress = false # no bars in CI\nlimit-rate = \"500k\"\ntmp-dir = \"C:\\\\Temp\"\npassthrough = 'warn'\ntimeout = 30\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.get(Setting::find("progress").unwrap()),
+            Some(&Value::Bool(false))
+        );
+        assert_eq!(
+            config.get(Setting::find("limit-rate").unwrap()),
+            Some(&Value::Text("500k".to_string()))
+        );
+        assert_eq!(
+            config.get(Setting::find("tmp-dir").unwrap()),
+            Some(&Value::Text("C:\\Temp".to_string()))
+        );
+        assert_eq!(
+            config.get(Setting::find("passthrough").unwrap()),
+            Some(&Value::Text("warn".to_string()))
+        );
+        assert_eq!(
+            config.get(Setting::find("timeout").unwrap()),
+            Some(&Value::Integer(30))
+        );
+        assert_eq!(config.get(Setting::find("proxy").unwrap()), None);
+    }
+
+    #[test]
+    fn reports_the_key_and_line_of_invalid_settings() {
+        assert_invalid("version = 1\nprogres = false\n", 2, Some("progres"));
+        assert_invalid("version = 1\n\nprogress = \"no\"\n", 3, Some("progress"));
+        assert_invalid(
+            "version = 1\npassthrough = \"sometimes\"\n",
+            2,
+            Some("passthrough"),
+        );
+        assert_invalid("version = 1\ntimeout = \"soon\"\n", 2, Some("timeout"));
+        assert_invalid("version = 1\ntimeout = 0\n", 2, Some("timeout"));
+        assert_invalid("version = 1\n[volta]\nprogress = false\n", 2, Some("volta"));
+        assert_invalid("progress = false\n", 1, Some("version"));
+        // Syntax errors are reported by the line they are on, without a key
+        assert_invalid(
+            "version = 1\nlimit-rate = \"1m\"\nlimit-rate = \"2m\"\n",
+            3,
+            None,
+        );
+        assert_invalid("version = 1\ntmp-dir = \"/tmp\n", 2, None);
+        assert_invalid("version = 1\nprogress\n", 2, None);
+    }
+
+    #[test]
+    fn rejects_newer_schemas() {
+        match parse("version = 2\n")
+            .unwrap_err()
+            .downcast_ref::<ErrorDetails>()
+        {
+            Some(ErrorDetails::ConfigTooNew { version, .. }) => assert_eq!(*version, 2),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        let mut config = Config::default();
+        config
+            .set(Setting::find("progress").unwrap(), "false")
+            .unwrap();
+        config
+            .set(Setting::find("tmp-dir").unwrap(), "C:\\Temp \"scratch\"")
+            .unwrap();
+        assert!(config
+            .set(Setting::find("partial-pins").unwrap(), "sometimes")
+            .is_err());
+        config.set(Setting::find("timeout").unwrap(), "30").unwrap();
+        assert!(config.set(Setting::find("timeout").unwrap(), "-1").is_err());
+        assert!(Setting::find("offline").is_err());
+
+        let src = config.to_string();
+        assert!(src.contains("progress = false\n"));
+        assert!(src.contains("timeout = 30\n"));
+        assert!(src.contains("tmp-dir = \"C:\\\\Temp \\\"scratch\\\"\"\n"));
+        assert_eq!(parse(&src).unwrap(), config);
+    }
+}
@@ -0,0 +1,115 @@
+//! Detects the package manager shims that corepack creates. Corepack ships with Node 16.9 and
+//! later, and `corepack enable` puts `yarn` and `pnpm` shims next to `node`, where they run
+//! instead of the versions that Volta selects.
+
+use std::env;
+use std::fs::{canonicalize, metadata, read_to_string};
+use std::path::{Path, PathBuf};
+
+use crate::platform::System;
+use crate::session::Session;
+use cfg_if::cfg_if;
+use volta_fail::Fallible;
+
+/// The executables that corepack provides shims for.
+const SHIM_NAMES: [&str; 4] = ["yarn", "yarnpkg", "pnpm", "pnpx"];
+
+/// The largest file that is read to check whether it is a corepack script.
+const MAX_SCRIPT_LEN: u64 = 4096;
+
+cfg_if! {
+    if #[cfg(windows)] {
+        /// The extensions of the shims that corepack writes on Windows.
+        const SHIM_EXTENSIONS: [&str; 3] = ["", ".cmd", ".ps1"];
+    } else {
+        const SHIM_EXTENSIONS: [&str; 1] = [""];
+    }
+}
+
+/// Finds the corepack shims that can run instead of the tools Volta manages: those in the
+/// current platform's Node image, and those elsewhere on the `PATH`.
+pub fn find_corepack_shims(session: &Session) -> Fallible<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    if let Some(image) = session
+        .current_platform()?
+        .and_then(|platform| platform.resolve_image())
+    {
+        dirs.extend(image.bins()?);
+    }
+    dirs.extend(env::split_paths(&System::path()?));
+
+    let mut shims = Vec::new();
+    for dir in dirs {
+        for name in SHIM_NAMES.iter() {
+            for extension in SHIM_EXTENSIONS.iter() {
+                let file = dir.join(format!("{}{}", name, extension));
+                if is_corepack_shim(&file) && !shims.contains(&file) {
+                    shims.push(file);
+                }
+            }
+        }
+    }
+
+    Ok(shims)
+}
+
+fn is_corepack_shim(file: &Path) -> bool {
+    if !file.is_file() {
+        return false;
+    }
+
+    // On Unix, the shims are links to corepack's own scripts
+    if let Ok(target) = canonicalize(file) {
+        if target
+            .components()
+            .any(|component| component.as_os_str() == "corepack")
+        {
+            return true;
+        }
+    }
+
+    // On Windows, the shims are small scripts that run corepack
+    match metadata(file) {
+        Ok(ref info) if info.len() <= MAX_SCRIPT_LEN => read_to_string(file)
+            .map(|script| script.contains("corepack"))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::is_corepack_shim;
+    use std::fs::{create_dir_all, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn detects_corepack_scripts() {
+        let dir = tempdir().unwrap();
+        let shim = dir.path().join("yarn.cmd");
+        write(
+            &shim,
+            "@\"%~dp0\\node.exe\" \"%~dp0\\node_modules\\corepack\\dist\\yarn.js\" %*\r\n",
+        )
+        .unwrap();
+        let yarn = dir.path().join("yarn");
+        write(&yarn, "#!/usr/bin/env node\nrequire('./lib/cli');\n").unwrap();
+
+        assert!(is_corepack_shim(&shim));
+        assert!(!is_corepack_shim(&yarn));
+        assert!(!is_corepack_shim(&dir.path().join("pnpm")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detects_corepack_links() {
+        let dir = tempdir().unwrap();
+        let dist = dir.path().join("lib/node_modules/corepack/dist");
+        create_dir_all(&dist).unwrap();
+        write(dist.join("pnpm.js"), "").unwrap();
+        let shim = dir.path().join("pnpm");
+        std::os::unix::fs::symlink(dist.join("pnpm.js"), &shim).unwrap();
+
+        assert!(is_corepack_shim(&shim));
+    }
+}
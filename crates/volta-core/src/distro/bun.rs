@@ -0,0 +1,184 @@
+//! Provides the `BunDistro` type, which represents a provisioned Bun distribution.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+
+use volta_fail::{throw, Fallible};
+
+use super::github::{GitHubReleaseDistro, GitHubReleaseTool};
+use crate::error::ErrorDetails;
+use crate::fs::{read_dir_eager, rename};
+use crate::path;
+use crate::tool::ToolSpec;
+use crate::version::VersionSpec;
+
+#[cfg(unix)]
+use std::os::unix::fs::symlink;
+
+#[cfg(feature = "mock-network")]
+use crate::env::mock_server_url;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_bun_server_root() -> String {
+            format!("{}/bun-releases", mock_server_url())
+        }
+    } else {
+        fn public_bun_server_root() -> String {
+            "https://github.com/oven-sh/bun/releases/download".to_string()
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_bun_version_index() -> String {
+            format!("{}/bun-releases/index.json", mock_server_url())
+        }
+    } else {
+        /// Return the URL of the index of available Bun versions on the public git repository.
+        fn public_bun_version_index() -> String {
+            "https://api.github.com/repos/oven-sh/bun/releases?per_page=100".to_string()
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(target_os = "linux", target_arch = "x86_64"))] {
+        /// The platform name that Bun release archives are published under for this platform.
+        const BUN_TARGET: Option<&'static str> = Some("linux-x64");
+    } else if #[cfg(all(target_os = "linux", target_arch = "aarch64"))] {
+        /// The platform name that Bun release archives are published under for this platform.
+        const BUN_TARGET: Option<&'static str> = Some("linux-aarch64");
+    } else if #[cfg(all(target_os = "macos", target_arch = "x86_64"))] {
+        /// The platform name that Bun release archives are published under for this platform.
+        const BUN_TARGET: Option<&'static str> = Some("darwin-x64");
+    } else if #[cfg(all(target_os = "macos", target_arch = "aarch64"))] {
+        /// The platform name that Bun release archives are published under for this platform.
+        const BUN_TARGET: Option<&'static str> = Some("darwin-aarch64");
+    } else if #[cfg(all(windows, target_arch = "x86_64"))] {
+        /// The platform name that Bun release archives are published under for this platform.
+        const BUN_TARGET: Option<&'static str> = Some("windows-x64");
+    } else {
+        /// Bun doesn't publish release archives for this platform.
+        const BUN_TARGET: Option<&'static str> = None;
+    }
+}
+
+/// Determines the platform name that Bun release archives are published under.
+fn bun_target() -> Fallible<&'static str> {
+    match BUN_TARGET {
+        Some(target) => Ok(target),
+        None => throw!(ErrorDetails::BunPlatformNotSupported),
+    }
+}
+
+/// Bun runs as `bunx` when invoked under that name, so `bunx` is provided as a link to (or, on
+/// Windows, a copy of) the `bun` executable.
+#[cfg(unix)]
+fn create_bunx(bin_dir: &Path) -> io::Result<()> {
+    symlink("bun", bin_dir.join("bunx"))
+}
+
+/// Bun runs as `bunx` when invoked under that name, so `bunx` is provided as a link to (or, on
+/// Windows, a copy of) the `bun` executable.
+#[cfg(windows)]
+fn create_bunx(bin_dir: &Path) -> io::Result<()> {
+    std::fs::copy(bin_dir.join("bun.exe"), bin_dir.join("bunx.exe")).map(|_| ())
+}
+
+/// Bun, which is published on GitHub as a zip archive containing a directory named for the
+/// platform, which holds the executable.
+pub struct Bun;
+
+/// A provisioned Bun distribution.
+pub type BunDistro = GitHubReleaseDistro<Bun>;
+
+impl GitHubReleaseTool for Bun {
+    const NAME: &'static str = "bun";
+    const DISPLAY_NAME: &'static str = "Bun";
+    const TAG_PREFIX: &'static str = "bun-v";
+
+    fn public_index_url() -> String {
+        public_bun_version_index()
+    }
+
+    fn public_url(version: &Version) -> Fallible<String> {
+        Ok(format!(
+            "{}/bun-v{}/bun-{}.zip",
+            public_bun_server_root(),
+            version,
+            bun_target()?
+        ))
+    }
+
+    fn tool_spec(version: &Version) -> ToolSpec {
+        ToolSpec::Bun(VersionSpec::exact(version))
+    }
+
+    fn version_not_found(matching: String) -> ErrorDetails {
+        ErrorDetails::BunVersionNotFound { matching }
+    }
+
+    fn parse_index_error(from_url: String) -> ErrorDetails {
+        ErrorDetails::ParseBunIndexError { from_url }
+    }
+
+    fn inventory_dir() -> Fallible<PathBuf> {
+        path::bun_inventory_dir()
+    }
+
+    fn image_root_dir() -> Fallible<PathBuf> {
+        path::bun_image_root_dir()
+    }
+
+    fn image_dir(version: &str) -> Fallible<PathBuf> {
+        path::bun_image_dir(version)
+    }
+
+    fn distro_file_name(version: &str) -> String {
+        path::bun_distro_file_name(version)
+    }
+
+    /// The archive contains a single directory holding the executable, named for the platform,
+    /// which becomes the `bin` directory of the image.
+    fn create_bin_dir(unpacked: &Path, bin_dir: &Path) -> io::Result<()> {
+        let dir = read_dir_eager(unpacked)?
+            .find(|(_, metadata)| metadata.is_dir())
+            .map(|(entry, _)| entry.path())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "the archive doesn't contain a directory",
+                )
+            })?;
+        rename(dir, bin_dir)?;
+        create_bunx(bin_dir)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::Bun;
+    use crate::distro::github::parse_releases;
+    use semver::Version;
+
+    const URL: &str = "http://localhost/bun/index/";
+
+    #[test]
+    fn parses_github_index() {
+        let index = r#"[
+            { "tag_name": "canary" },
+            { "tag_name": "bun-v1.0.25" },
+            { "tag_name": "bun-v1.0.3" }
+        ]"#;
+        let (count, versions) = parse_releases::<Bun>(index, URL).expect("parses");
+        assert_eq!(count, 3);
+        assert_eq!(
+            versions.into_iter().collect::<Vec<_>>(),
+            vec![Version::new(1, 0, 3), Version::new(1, 0, 25)]
+        );
+    }
+}
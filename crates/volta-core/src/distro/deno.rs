@@ -0,0 +1,128 @@
+//! Provides the `DenoDistro` type, which represents a provisioned Deno distribution.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+
+use volta_fail::{throw, Fallible};
+
+use super::github::{GitHubReleaseDistro, GitHubReleaseTool};
+use crate::error::ErrorDetails;
+use crate::fs::rename;
+use crate::path;
+use crate::tool::ToolSpec;
+use crate::version::VersionSpec;
+
+#[cfg(feature = "mock-network")]
+use crate::env::mock_server_url;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_deno_server_root() -> String {
+            format!("{}/deno-releases", mock_server_url())
+        }
+    } else {
+        fn public_deno_server_root() -> String {
+            "https://github.com/denoland/deno/releases/download".to_string()
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_deno_version_index() -> String {
+            format!("{}/deno-releases/index.json", mock_server_url())
+        }
+    } else {
+        /// Return the URL of the index of available Deno versions on the public git repository.
+        fn public_deno_version_index() -> String {
+            "https://api.github.com/repos/denoland/deno/releases?per_page=100".to_string()
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(target_os = "linux", target_arch = "x86_64"))] {
+        /// The target triple that Deno release archives are published under for this platform.
+        const DENO_TARGET: Option<&'static str> = Some("x86_64-unknown-linux-gnu");
+    } else if #[cfg(all(target_os = "linux", target_arch = "aarch64"))] {
+        /// The target triple that Deno release archives are published under for this platform.
+        const DENO_TARGET: Option<&'static str> = Some("aarch64-unknown-linux-gnu");
+    } else if #[cfg(all(target_os = "macos", target_arch = "x86_64"))] {
+        /// The target triple that Deno release archives are published under for this platform.
+        const DENO_TARGET: Option<&'static str> = Some("x86_64-apple-darwin");
+    } else if #[cfg(all(target_os = "macos", target_arch = "aarch64"))] {
+        /// The target triple that Deno release archives are published under for this platform.
+        const DENO_TARGET: Option<&'static str> = Some("aarch64-apple-darwin");
+    } else if #[cfg(all(windows, target_arch = "x86_64"))] {
+        /// The target triple that Deno release archives are published under for this platform.
+        const DENO_TARGET: Option<&'static str> = Some("x86_64-pc-windows-msvc");
+    } else {
+        /// Deno doesn't publish release archives for this platform.
+        const DENO_TARGET: Option<&'static str> = None;
+    }
+}
+
+/// Deno, which is published on GitHub as a zip archive containing only the executable.
+pub struct Deno;
+
+/// A provisioned Deno distribution.
+pub type DenoDistro = GitHubReleaseDistro<Deno>;
+
+impl GitHubReleaseTool for Deno {
+    const NAME: &'static str = "deno";
+    const DISPLAY_NAME: &'static str = "Deno";
+    const TAG_PREFIX: &'static str = "v";
+
+    fn public_index_url() -> String {
+        public_deno_version_index()
+    }
+
+    fn public_url(version: &Version) -> Fallible<String> {
+        let target = match DENO_TARGET {
+            Some(target) => target,
+            None => throw!(ErrorDetails::DenoPlatformNotSupported),
+        };
+        Ok(format!(
+            "{}/v{}/deno-{}.zip",
+            public_deno_server_root(),
+            version,
+            target
+        ))
+    }
+
+    fn tool_spec(version: &Version) -> ToolSpec {
+        ToolSpec::Deno(VersionSpec::exact(version))
+    }
+
+    fn version_not_found(matching: String) -> ErrorDetails {
+        ErrorDetails::DenoVersionNotFound { matching }
+    }
+
+    fn parse_index_error(from_url: String) -> ErrorDetails {
+        ErrorDetails::ParseDenoIndexError { from_url }
+    }
+
+    fn inventory_dir() -> Fallible<PathBuf> {
+        path::deno_inventory_dir()
+    }
+
+    fn image_root_dir() -> Fallible<PathBuf> {
+        path::deno_image_root_dir()
+    }
+
+    fn image_dir(version: &str) -> Fallible<PathBuf> {
+        path::deno_image_dir(version)
+    }
+
+    fn distro_file_name(version: &str) -> String {
+        path::deno_distro_file_name(version)
+    }
+
+    /// The archive contains only the executable, so everything unpacked becomes the `bin`
+    /// directory of the image.
+    fn create_bin_dir(unpacked: &Path, bin_dir: &Path) -> io::Result<()> {
+        rename(unpacked, bin_dir)
+    }
+}
@@ -0,0 +1,311 @@
+//! Provides the `GitHubReleaseDistro` type, which represents a provisioned distribution of a tool
+//! that is published as zip archives attached to its GitHub releases, like Deno.
+
+use std::collections::BTreeSet;
+use std::fs::{create_dir_all, File};
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use semver::Version;
+use serde::Deserialize;
+
+use archive::{Archive, Zip};
+use volta_fail::{Fallible, ResultExt};
+
+use super::{
+    download_tool_error, limit_download_rate, prune_archives, unpack_archive_error, Distro, Fetched,
+};
+use crate::error::ErrorDetails;
+use crate::fs::{
+    create_staging_dir, ensure_containing_dir_exists, ensure_dir_does_not_exist, rename,
+};
+use crate::hook::ToolHooks;
+use crate::inventory::Collection;
+use crate::path;
+use crate::permissions;
+use crate::plan::Change;
+use crate::style::{progress_bar, tool_version};
+use crate::tool::ToolSpec;
+
+#[cfg(unix)]
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+/// A tool that is published as zip archives attached to its GitHub releases.
+pub trait GitHubReleaseTool: Sized + Send + 'static {
+    /// The name of the tool's executable, which also names its directories in the inventory and
+    /// the images.
+    const NAME: &'static str;
+
+    /// The name of the tool in messages, e.g. "Deno".
+    const DISPLAY_NAME: &'static str;
+
+    /// The part of each release's tag that comes before its version, e.g. "v" for "v1.40.0".
+    const TAG_PREFIX: &'static str;
+
+    /// Returns the URL of the public listing of the tool's releases, on the GitHub API.
+    fn public_index_url() -> String;
+
+    /// Returns the URL that the archive of a version is published at.
+    fn public_url(version: &Version) -> Fallible<String>;
+
+    /// Produces the spec for an exact version of the tool.
+    fn tool_spec(version: &Version) -> ToolSpec;
+
+    /// Produces the error for when no version matches the requested one.
+    fn version_not_found(matching: String) -> ErrorDetails;
+
+    /// Produces the error for when the listing of releases can't be parsed.
+    fn parse_index_error(from_url: String) -> ErrorDetails;
+
+    /// Returns the directory that the tool's archives are kept in.
+    fn inventory_dir() -> Fallible<PathBuf>;
+
+    /// Returns the directory that holds the tool's images.
+    fn image_root_dir() -> Fallible<PathBuf>;
+
+    /// Returns the directory of the tool's image for a version.
+    fn image_dir(version: &str) -> Fallible<PathBuf>;
+
+    /// Returns the file name that the archive of a version is kept under.
+    fn distro_file_name(version: &str) -> String;
+
+    /// Makes `bin_dir` out of the files that were unpacked from an archive into `unpacked`.
+    fn create_bin_dir(unpacked: &Path, bin_dir: &Path) -> io::Result<()>;
+}
+
+/// A release in the listing from the GitHub API.
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+/// Parses one page of the listing of a tool's GitHub releases, returning how many releases were
+/// on the page along with their versions. Releases whose tags aren't versions (such as `canary`)
+/// are skipped.
+pub fn parse_releases<T>(text: &str, from_url: &str) -> Fallible<(usize, BTreeSet<Version>)>
+where
+    T: GitHubReleaseTool,
+{
+    let releases: Vec<Release> = serde_json::de::from_str(text)
+        .with_context(|_| T::parse_index_error(from_url.to_string()))?;
+    let versions = releases
+        .iter()
+        .filter(|release| release.tag_name.starts_with(T::TAG_PREFIX))
+        .filter_map(|release| Version::parse(&release.tag_name[T::TAG_PREFIX.len()..]).ok())
+        .collect();
+
+    Ok((releases.len(), versions))
+}
+
+/// Removes every version of a tool that has been fetched, along with their archives.
+pub fn remove_versions<T: GitHubReleaseTool>() -> Fallible<()> {
+    ensure_dir_does_not_exist(&T::image_root_dir()?)?;
+    ensure_dir_does_not_exist(&T::inventory_dir()?)
+}
+
+/// Lists the changes that removing every fetched version of a tool would make (see
+/// `remove_versions`).
+pub fn plan_remove_versions<T: GitHubReleaseTool>() -> Fallible<Vec<Change>> {
+    let mut changes = Vec::new();
+    for dir in vec![T::image_root_dir()?, T::inventory_dir()?] {
+        if dir.exists() {
+            changes.push(Change::Remove(dir));
+        }
+    }
+    Ok(changes)
+}
+
+/// A provisioned distribution of a tool that is published on GitHub.
+pub struct GitHubReleaseDistro<T> {
+    archive: Box<dyn Archive>,
+    version: Version,
+    tool: PhantomData<T>,
+}
+
+/// Return the archive if it is valid. It may have been corrupted or interrupted in the middle of
+/// downloading.
+// ISSUE(#134) - verify checksum
+fn load_cached_distro(file: &PathBuf) -> Option<Box<dyn Archive>> {
+    if file.is_file() {
+        let file = File::open(file).ok()?;
+        Zip::load(file).ok()
+    } else {
+        None
+    }
+}
+
+impl<T: GitHubReleaseTool> GitHubReleaseDistro<T> {
+    /// Provision a distribution from a remote distributor.
+    fn remote(version: Version, url: &str) -> Fallible<Self> {
+        let distro_file = T::inventory_dir()?.join(T::distro_file_name(&version.to_string()));
+
+        if let Some(archive) = load_cached_distro(&distro_file) {
+            debug!(
+                "Loading {}@{} from cached archive at {}",
+                T::NAME,
+                version,
+                distro_file.display()
+            );
+            return Ok(GitHubReleaseDistro {
+                archive,
+                version,
+                tool: PhantomData,
+            });
+        }
+
+        ensure_containing_dir_exists(&distro_file)?;
+        limit_download_rate()?;
+        debug!("Downloading {}@{} from {}", T::NAME, version, url);
+
+        Ok(GitHubReleaseDistro {
+            archive: Zip::fetch(url, &distro_file)
+                .with_context(download_tool_error(T::tool_spec(&version), url))?,
+            version,
+            tool: PhantomData,
+        })
+    }
+}
+
+impl<T: GitHubReleaseTool> Distro for GitHubReleaseDistro<T> {
+    type VersionDetails = Version;
+    type ResolvedVersion = Version;
+
+    /// Provisions a new Distro based on the Version and possible Hooks
+    fn new(
+        _name: &str,
+        version: Self::ResolvedVersion,
+        hooks: Option<&ToolHooks<Self>>,
+    ) -> Fallible<Self> {
+        match hooks {
+            Some(&ToolHooks {
+                distro: Some(ref hook),
+                ..
+            }) => {
+                debug!("Using {}.distro hook to determine download URL", T::NAME);
+                let url = hook.resolve(&version, &T::distro_file_name(&version.to_string()))?;
+                GitHubReleaseDistro::remote(version, &url)
+            }
+            _ => {
+                let url = T::public_url(&version)?;
+                GitHubReleaseDistro::remote(version, &url)
+            }
+        }
+    }
+
+    /// Produces a reference to this distro's version.
+    fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Fetches this version of the tool. (It is left to the responsibility of the `Collection`
+    /// to update its state after fetching succeeds.)
+    fn fetch(self, collection: &Collection<Self>) -> Fallible<Fetched<Version>> {
+        if collection.contains(&self.version) {
+            debug!(
+                "{}@{} has already been fetched, skipping download",
+                T::NAME,
+                &self.version
+            );
+            return Ok(Fetched::Already(self.version));
+        }
+
+        path::ensure_writable_home()?;
+        let temp = create_staging_dir()?;
+        debug!("Unpacking {} into {}", T::NAME, temp.path().display());
+
+        let bar = progress_bar(
+            self.archive.origin(),
+            &tool_version(T::NAME, &self.version),
+            self.archive
+                .uncompressed_size()
+                .unwrap_or(self.archive.compressed_size()),
+        );
+        let version_string = self.version.to_string();
+
+        let unpacked = temp.path().join("unpacked");
+        self.archive
+            .unpack(&unpacked, &mut |_, read| {
+                bar.inc(read as u64);
+            })
+            .with_context(unpack_archive_error(
+                T::DISPLAY_NAME,
+                version_string.as_str(),
+            ))?;
+
+        let image_root = temp.path().join("image");
+        let bin_dir = image_root.join("bin");
+        create_dir_all(&image_root)
+            .and_then(|_| T::create_bin_dir(&unpacked, &bin_dir))
+            .with_context(|_| ErrorDetails::SetupToolImageError {
+                tool: T::DISPLAY_NAME.to_string(),
+                version: version_string.clone(),
+                dir: bin_dir.clone(),
+            })?;
+
+        // Zip archives don't preserve the executable bit, so it needs to be restored
+        #[cfg(unix)]
+        {
+            let bin = bin_dir.join(T::NAME);
+            fs::metadata(&bin)
+                .and_then(|metadata| {
+                    let mut permissions = metadata.permissions();
+                    permissions.set_mode(0o755);
+                    fs::set_permissions(&bin, permissions)
+                })
+                .with_context(|_| ErrorDetails::ExecutablePermissionsError {
+                    bin: T::NAME.to_string(),
+                })?;
+        }
+
+        let dest = T::image_dir(&version_string)?;
+
+        ensure_containing_dir_exists(&dest)?;
+        permissions::apply_to_tree(&image_root)?;
+
+        rename(&image_root, &dest).with_context(|_| ErrorDetails::SetupToolImageError {
+            tool: T::DISPLAY_NAME.to_string(),
+            version: version_string.clone(),
+            dir: dest.clone(),
+        })?;
+        prune_archives(&T::inventory_dir()?)?;
+
+        bar.finish_and_clear();
+
+        // Note: We write this after the progress bar is finished to avoid display bugs with re-renders of the progress
+        debug!("Installing {} in {}", T::NAME, dest.display());
+        Ok(Fetched::Now(self.version))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::parse_releases;
+    use crate::distro::deno::Deno;
+    use semver::Version;
+
+    const URL: &str = "http://localhost/deno/index/";
+
+    #[test]
+    fn parses_a_page_of_releases() {
+        let page = r#"[
+            { "tag_name": "v1.40.0" },
+            { "tag_name": "canary" },
+            { "tag_name": "v1.39.4" }
+        ]"#;
+        let (count, versions) = parse_releases::<Deno>(page, URL).expect("parses");
+        assert_eq!(count, 3);
+        assert_eq!(
+            versions.into_iter().collect::<Vec<_>>(),
+            vec![Version::new(1, 39, 4), Version::new(1, 40, 0)]
+        );
+    }
+
+    #[test]
+    fn rejects_listings_that_are_not_releases() {
+        assert!(parse_releases::<Deno>(r#"{ "message": "Not Found" }"#, URL).is_err());
+    }
+}
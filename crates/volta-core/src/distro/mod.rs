@@ -1,15 +1,30 @@
 //! Provides types for fetching tool distributions into the local inventory.
 
+pub mod bun;
+pub mod deno;
+pub mod github;
 pub mod node;
 pub mod package;
+pub mod package_source;
 pub mod yarn;
 
+use std::env;
+use std::fs::{create_dir_all, remove_file};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::env::{flag_enabled, KEEP_ARCHIVES, LIMIT_RATE, LINK_IMAGES, NETWORK_TIMEOUT, PROXY};
 use crate::error::ErrorDetails;
-use crate::hook::ToolHooks;
+use crate::fs::{ensure_containing_dir_exists, link_farm, read_dir_eager, rename};
+use crate::hook::{HookConfig, KeepArchives, RateLimit, ToolHooks};
 use crate::inventory::Collection;
+use crate::path::{self, extended_length_path};
+use crate::plan::Change;
 use crate::tool::ToolSpec;
+use log::{debug, warn};
 use semver::Version;
-use volta_fail::Fallible;
+use volta_fail::{Fallible, ResultExt};
 
 /// The result of a requested installation.
 #[derive(Debug)]
@@ -61,10 +76,407 @@ pub trait Distro: Sized {
     fn fetch(self, collection: &Collection<Self>) -> Fallible<Fetched<Self::VersionDetails>>;
 }
 
+/// Moves an unpacked distro into its image directory. When `VOLTA_LINK_IMAGES` is set, the distro
+/// is instead kept unchanged in the store, and the image is built as a symlink farm of it, so that
+/// the image can be rebuilt without unpacking the distro again.
+fn install_image(unpacked: &Path, store: &Path, image: &Path) -> io::Result<()> {
+    if !flag_enabled(LINK_IMAGES) {
+        return rename(unpacked, image);
+    }
+
+    // The store is never modified once written, so an existing copy can be linked as it is
+    if !store.is_dir() {
+        if let Some(parent) = store.parent() {
+            create_dir_all(parent)?;
+        }
+        rename(unpacked, store)?;
+    }
+
+    debug!("Linking {} to {}", image.display(), store.display());
+    link_farm(&extended_length_path(store), &extended_length_path(image))
+}
+
+/// The extensions of the archives that tools are downloaded as.
+const ARCHIVE_EXTENSIONS: &[&str] = &[".tar.gz", ".tar.xz", ".tar.zst", ".tgz", ".zip"];
+
+/// Reads the policy for keeping archives from `VOLTA_KEEP_ARCHIVES`, or from `keepArchives` in the
+/// `volta` section of the user hooks, keeping every archive if neither is set.
+fn keep_archives() -> Fallible<KeepArchives> {
+    match env::var(KEEP_ARCHIVES) {
+        Ok(ref value) if !value.is_empty() => Ok(value.parse()?),
+        _ => Ok(HookConfig::user_keep_archives()?.unwrap_or_default()),
+    }
+}
+
+/// Limits the downloads that follow to the rate set by `VOLTA_LIMIT_RATE` (or `volta --limit-rate`),
+/// or by `limitRate` in the `volta` section of the user hooks. Tools are downloaded as fast as the
+/// connection allows if neither is set.
+fn limit_download_rate() -> Fallible<()> {
+    let limit = match env::var(LIMIT_RATE) {
+        Ok(ref value) if !value.is_empty() => Some(value.parse::<RateLimit>()?),
+        _ => HookConfig::user_limit_rate()?,
+    };
+    if let Some(RateLimit(bytes_per_second)) = limit {
+        debug!(
+            "Limiting downloads to {} bytes per second",
+            bytes_per_second
+        );
+    }
+    archive::limit_rate(limit.map(|RateLimit(bytes_per_second)| bytes_per_second));
+    Ok(())
+}
+
+/// Sets how long requests wait for the server before they fail, from `VOLTA_NETWORK_TIMEOUT` in
+/// seconds, and the proxy that they are sent through, from `VOLTA_PROXY`. Requests wait as long
+/// as the HTTP client does by default, and go directly to the server, if these aren't set.
+pub(crate) fn apply_network_settings() {
+    let timeout = match env::var(NETWORK_TIMEOUT) {
+        Ok(ref value) if !value.is_empty() => match value.parse::<u64>() {
+            Ok(secs) if secs > 0 => Some(Duration::from_secs(secs)),
+            _ => {
+                warn!(
+                    "Ignoring {}, which must be a whole number of seconds, found '{}'",
+                    NETWORK_TIMEOUT, value
+                );
+                None
+            }
+        },
+        _ => None,
+    };
+    archive::set_timeout(timeout);
+
+    let proxy = match env::var(PROXY) {
+        Ok(ref value) if !value.is_empty() => match reqwest::Proxy::all(value.as_str()) {
+            Ok(_) => Some(value.clone()),
+            Err(_) => {
+                warn!("Ignoring {}, which must be a URL, found '{}'", PROXY, value);
+                None
+            }
+        },
+        _ => None,
+    };
+    archive::set_proxy(proxy);
+}
+
+/// Downloads a file that accompanies a tool but isn't its archive, like a checksum listing, with
+/// the same rate limit and errors as the tool's archive.
+fn fetch_file(tool: ToolSpec, url: &str, file: &Path) -> Fallible<()> {
+    ensure_containing_dir_exists(file)?;
+    limit_download_rate()?;
+    debug!("Downloading {} from {}", file.display(), url);
+    archive::download(url, file).with_context(download_tool_error(tool, url))
+}
+
+/// Removes the archives in an inventory directory that the archive policy doesn't keep, once a
+/// tool has been installed from one of them.
+///
+/// The collections know which versions are fetched from their images, so a pruned archive is
+/// only downloaded again if its image is missing. Archives that are still downloading aren't in
+/// the directory yet, since they're only moved there once they're complete, and an archive that
+/// can't be removed (e.g. because another process is reading it) is left for the next install.
+fn prune_archives(inventory_dir: &Path) -> Fallible<()> {
+    let keep = match kept_archives(keep_archives()?) {
+        Some(keep) => keep,
+        None => return Ok(()),
+    };
+
+    for archive in archives_beyond(archives_in(inventory_dir), keep) {
+        debug!("Removing cached archive {}", archive.display());
+        if let Err(error) = remove_file(&archive) {
+            debug!("Could not remove {}: {}", archive.display(), error);
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the removal of each cached archive that a policy doesn't keep, or that the configured
+/// policy doesn't keep if none is given, from every inventory directory that archives are pruned
+/// from after installs (see `volta gc`).
+pub(crate) fn plan_prune_archives(keep: Option<KeepArchives>) -> Fallible<Vec<Change>> {
+    let keep = match keep {
+        Some(keep) => keep,
+        None => keep_archives()?,
+    };
+    let keep = match kept_archives(keep) {
+        Some(keep) => keep,
+        None => return Ok(Vec::new()),
+    };
+
+    let inventory_dirs = vec![
+        path::node_inventory_dir()?,
+        path::node_prerelease_inventory_dir()?,
+        path::yarn_inventory_dir()?,
+        path::deno_inventory_dir()?,
+        path::bun_inventory_dir()?,
+    ];
+    Ok(inventory_dirs
+        .iter()
+        .flat_map(|dir| archives_beyond(archives_in(dir), keep))
+        .map(Change::Remove)
+        .collect())
+}
+
+/// The number of archives of each tool that a policy keeps, if it doesn't keep all of them.
+fn kept_archives(keep: KeepArchives) -> Option<usize> {
+    match keep {
+        KeepArchives::Always => None,
+        KeepArchives::Never => Some(0),
+        KeepArchives::Last(count) => Some(count),
+    }
+}
+
+/// Lists the archives in a directory, with when each was downloaded.
+fn archives_in(dir: &Path) -> Vec<(SystemTime, PathBuf)> {
+    let entries = match read_dir_eager(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter(|(entry, metadata)| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            metadata.is_file()
+                && ARCHIVE_EXTENSIONS
+                    .iter()
+                    .any(|extension| name.ends_with(extension))
+        })
+        .map(|(entry, metadata)| {
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            (modified, entry.path())
+        })
+        .collect()
+}
+
+/// Picks the archives other than the `keep` most recently downloaded.
+fn archives_beyond(mut archives: Vec<(SystemTime, PathBuf)>, keep: usize) -> Vec<PathBuf> {
+    // Newest first
+    archives.sort_by(|left, right| right.cmp(left));
+    archives
+        .into_iter()
+        .skip(keep)
+        .map(|(_, archive)| archive)
+        .collect()
+}
+
 fn download_tool_error(
     tool: ToolSpec,
     from_url: impl AsRef<str>,
 ) -> impl FnOnce(&failure::Error) -> ErrorDetails {
     let from_url = from_url.as_ref().to_string();
-    |_| ErrorDetails::DownloadToolNetworkError { tool, from_url }
+    |error| match classify_download_error(error) {
+        DownloadFailure::NotFound => ErrorDetails::DownloadToolNotFound { tool, from_url },
+        DownloadFailure::Forbidden => ErrorDetails::DownloadToolForbidden { tool, from_url },
+        DownloadFailure::Dns => ErrorDetails::DownloadToolDnsError { tool, from_url },
+        DownloadFailure::Tls => ErrorDetails::DownloadToolTlsError { tool, from_url },
+        DownloadFailure::DiskFull => ErrorDetails::DownloadToolDiskFull { tool },
+        DownloadFailure::Network => ErrorDetails::DownloadToolNetworkError { tool, from_url },
+    }
+}
+
+/// Describes a failure to unpack a tool's archive, which is a network error when the archive was
+/// cut off while it was being downloaded.
+fn unpack_archive_error(
+    tool: impl Into<String>,
+    version: impl Into<String>,
+) -> impl FnOnce(&failure::Error) -> ErrorDetails {
+    let tool = tool.into();
+    let version = version.into();
+    |error| {
+        if error
+            .downcast_ref::<archive::IncompleteDownloadError>()
+            .is_some()
+        {
+            ErrorDetails::IncompleteDownload { tool, version }
+        } else {
+            ErrorDetails::UnpackArchiveError { tool, version }
+        }
+    }
+}
+
+/// The reason a download failed, as far as it can be told from the underlying error.
+#[derive(Debug, PartialEq)]
+enum DownloadFailure {
+    NotFound,
+    Forbidden,
+    Dns,
+    Tls,
+    DiskFull,
+    Network,
+}
+
+fn classify_download_error(error: &failure::Error) -> DownloadFailure {
+    if let Some(http) = error.downcast_ref::<archive::HttpError>() {
+        return classify_status(http.code.as_u16());
+    }
+
+    if error
+        .downcast_ref::<archive::IncompleteDownloadError>()
+        .is_some()
+    {
+        return DownloadFailure::Network;
+    }
+
+    if let Some(io) = error.downcast_ref::<io::Error>() {
+        return classify_io_error(io);
+    }
+
+    if let Some(request) = error.downcast_ref::<reqwest::Error>() {
+        if let Some(status) = request.status() {
+            return classify_status(status.as_u16());
+        }
+
+        if let Some(io) = request
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<io::Error>())
+        {
+            if classify_io_error(io) == DownloadFailure::DiskFull {
+                return DownloadFailure::DiskFull;
+            }
+        }
+
+        return classify_message(&request.to_string());
+    }
+
+    DownloadFailure::Network
+}
+
+fn classify_status(code: u16) -> DownloadFailure {
+    match code {
+        404 | 410 => DownloadFailure::NotFound,
+        401 | 403 => DownloadFailure::Forbidden,
+        _ => DownloadFailure::Network,
+    }
+}
+
+/// ENOSPC
+#[cfg(unix)]
+const DISK_FULL_CODES: &[i32] = &[28];
+
+/// ERROR_HANDLE_DISK_FULL and ERROR_DISK_FULL
+#[cfg(windows)]
+const DISK_FULL_CODES: &[i32] = &[39, 112];
+
+fn classify_io_error(error: &io::Error) -> DownloadFailure {
+    match error.raw_os_error() {
+        Some(code) if DISK_FULL_CODES.contains(&code) => DownloadFailure::DiskFull,
+        _ => DownloadFailure::Network,
+    }
+}
+
+/// Request errors don't expose the failures of name resolution or the TLS handshake as types of
+/// their own, so those are recognized by their messages.
+fn classify_message(message: &str) -> DownloadFailure {
+    let message = message.to_lowercase();
+
+    let dns = [
+        "dns error",
+        "failed to lookup address",
+        "no such host",
+        "name or service not known",
+    ];
+    let tls = ["certificate", "tls", "ssl"];
+
+    if dns.iter().any(|needle| message.contains(needle)) {
+        DownloadFailure::Dns
+    } else if tls.iter().any(|needle| message.contains(needle)) {
+        DownloadFailure::Tls
+    } else {
+        DownloadFailure::Network
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::fs::write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn lists_only_complete_archives() {
+        let dir = tempdir().unwrap();
+        write(dir.path().join("node-v10.0.0-linux-x64.tar.gz"), "").unwrap();
+        write(dir.path().join("node-v12.0.0-linux-x64.tar.xz"), "").unwrap();
+        write(dir.path().join("node-v14.0.0-linux-x64.tar.gz.partial"), "").unwrap();
+        write(dir.path().join("node-v10.0.0-npm"), "6.0.0").unwrap();
+
+        let mut archives: Vec<PathBuf> = archives_in(dir.path())
+            .into_iter()
+            .map(|(_, archive)| archive)
+            .collect();
+        archives.sort();
+        assert_eq!(
+            archives,
+            vec![
+                dir.path().join("node-v10.0.0-linux-x64.tar.gz"),
+                dir.path().join("node-v12.0.0-linux-x64.tar.xz"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_newest_archives() {
+        let downloaded = |days: u64| UNIX_EPOCH + Duration::from_secs(days * 86_400);
+        let archives = vec![
+            (downloaded(2), PathBuf::from("yarn-v1.1.0.tar.gz")),
+            (downloaded(3), PathBuf::from("yarn-v1.2.0.tar.gz")),
+            (downloaded(1), PathBuf::from("yarn-v1.0.0.tar.gz")),
+        ];
+
+        assert_eq!(
+            archives_beyond(archives.clone(), 1),
+            vec![
+                PathBuf::from("yarn-v1.1.0.tar.gz"),
+                PathBuf::from("yarn-v1.0.0.tar.gz"),
+            ]
+        );
+        assert_eq!(archives_beyond(archives.clone(), 0).len(), 3);
+        assert!(archives_beyond(archives, 3).is_empty());
+    }
+
+    #[test]
+    fn classifies_http_status() {
+        assert_eq!(classify_status(404), DownloadFailure::NotFound);
+        assert_eq!(classify_status(403), DownloadFailure::Forbidden);
+        assert_eq!(classify_status(401), DownloadFailure::Forbidden);
+        assert_eq!(classify_status(500), DownloadFailure::Network);
+    }
+
+    #[test]
+    fn classifies_http_errors() {
+        let error: failure::Error = archive::HttpError {
+            code: reqwest::StatusCode::NOT_FOUND,
+        }
+        .into();
+        assert_eq!(classify_download_error(&error), DownloadFailure::NotFound);
+    }
+
+    #[test]
+    fn classifies_disk_full() {
+        let code = if cfg!(windows) { 112 } else { 28 };
+        let error: failure::Error = io::Error::from_raw_os_error(code).into();
+        assert_eq!(classify_download_error(&error), DownloadFailure::DiskFull);
+
+        let error: failure::Error = io::Error::new(io::ErrorKind::Other, "oops").into();
+        assert_eq!(classify_download_error(&error), DownloadFailure::Network);
+    }
+
+    #[test]
+    fn classifies_messages() {
+        assert_eq!(
+            classify_message(
+                "error trying to connect: dns error: failed to lookup address information"
+            ),
+            DownloadFailure::Dns
+        );
+        assert_eq!(
+            classify_message("error trying to connect: invalid certificate: UnknownIssuer"),
+            DownloadFailure::Tls
+        );
+        assert_eq!(
+            classify_message("connection reset by peer"),
+            DownloadFailure::Network
+        );
+    }
 }
@@ -1,47 +1,154 @@
 //! Provides the `NodeDistro` type, which represents a provisioned Node distribution.
 
-use std::fs::{read_to_string, rename, write, File};
+use std::collections::HashMap;
+use std::fs::{read_to_string, File};
+use std::iter;
 use std::path::{Path, PathBuf};
 use std::string::ToString;
 
-use archive::{self, Archive};
+use archive::{self, Archive, Compression, Format};
 use serde::Deserialize;
-use tempfile::tempdir_in;
 
-use super::{download_tool_error, Distro, Fetched};
+use super::{
+    download_tool_error, fetch_file, install_image, limit_download_rate, prune_archives,
+    unpack_archive_error, Distro, Fetched,
+};
+use crate::checksum::{self, ArchiveHash, ArchiveHashes};
+use crate::env::{flag_enabled, UNOFFICIAL_BUILDS};
 use crate::error::ErrorDetails;
-use crate::fs::ensure_containing_dir_exists;
+use crate::fs::{create_staging_dir, ensure_containing_dir_exists, rename, write_atomic};
+use crate::hook::tool::DistroHook;
 use crate::hook::ToolHooks;
 use crate::inventory::NodeCollection;
 use crate::path;
+use crate::permissions;
 use crate::style::{progress_bar, tool_version};
 use crate::tool::ToolSpec;
-use crate::version::VersionSpec;
+use crate::version::{ReleaseChannel, VersionSpec};
 
 use log::debug;
 use semver::Version;
-use volta_fail::{Fallible, ResultExt};
+use volta_fail::{throw, Fallible, ResultExt};
 
 #[cfg(feature = "mock-network")]
-use mockito;
+use crate::env::mock_server_url;
 use serde_json;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "mock-network")] {
         fn public_node_server_root() -> String {
-            mockito::SERVER_URL.to_string()
+            mock_server_url()
+        }
+        fn public_node_download_root() -> String {
+            format!("{}/node-download", mock_server_url())
+        }
+        fn unofficial_node_server_root() -> String {
+            format!("{}/node-unofficial", mock_server_url())
         }
     } else {
         fn public_node_server_root() -> String {
             "https://nodejs.org/dist".to_string()
         }
+        fn public_node_download_root() -> String {
+            "https://nodejs.org/download".to_string()
+        }
+        fn unofficial_node_server_root() -> String {
+            "https://unofficial-builds.nodejs.org/download/release".to_string()
+        }
+    }
+}
+
+/// Determines whether Node should be fetched from the unofficial builds server, either because
+/// there are no official builds for this platform (e.g. Linux with musl) or because
+/// `VOLTA_UNOFFICIAL_BUILDS` is set.
+pub fn use_unofficial_builds() -> bool {
+    path::LIBC.is_some() || flag_enabled(UNOFFICIAL_BUILDS)
+}
+
+/// Returns the URL of the index of available Node versions on the unofficial builds server.
+pub fn unofficial_node_version_index() -> String {
+    format!("{}/index.json", unofficial_node_server_root())
+}
+
+/// Returns the directory of the public Node server which hosts the builds for a release channel.
+fn channel_dir_name(channel: ReleaseChannel) -> &'static str {
+    match channel {
+        ReleaseChannel::Rc => "rc",
+        ReleaseChannel::Nightly => "nightly",
+        ReleaseChannel::Canary => "v8-canary",
+    }
+}
+
+/// Returns the URL of the index of builds on a release channel of the public Node server.
+pub fn public_node_channel_index(channel: ReleaseChannel) -> String {
+    format!(
+        "{}/{}/index.json",
+        public_node_download_root(),
+        channel_dir_name(channel)
+    )
+}
+
+/// Determines the release channel that published a Node version, based on its pre-release tag
+/// (e.g. `20.0.0-rc.2` or `21.0.0-nightly20230801d396a041f7`). Stable releases have no channel.
+pub fn release_channel(version: &Version) -> Option<ReleaseChannel> {
+    let version = version.to_string();
+    let pre = version.splitn(2, '-').nth(1)?;
+
+    if pre.starts_with("rc") {
+        Some(ReleaseChannel::Rc)
+    } else if pre.starts_with("nightly") {
+        Some(ReleaseChannel::Nightly)
+    } else if pre.starts_with("v8-canary") {
+        Some(ReleaseChannel::Canary)
+    } else {
+        None
+    }
+}
+
+/// The architectures that Node builds are published for, any of which can be selected in
+/// place of the native architecture (e.g. x64 on Apple Silicon, to run under Rosetta).
+const NODE_ARCHES: &[&str] = &["x64", "x86", "arm64", "armv7l", "ppc64le", "s390x"];
+
+/// Validates an architecture override, from either `volta install --arch` or the `volta.arch`
+/// key in package.json.
+pub fn parse_arch(arch: &str) -> Fallible<String> {
+    if !NODE_ARCHES.contains(&arch) {
+        throw!(ErrorDetails::InvalidNodeArch {
+            arch: arch.to_string()
+        });
+    }
+    Ok(arch.to_string())
+}
+
+/// Determines whether a Node version has already been unpacked for the specified architecture.
+///
+/// The `NodeCollection` only tracks builds for the native architecture, so for any other
+/// architecture this checks for the image directly.
+pub fn is_fetched_for_arch(version: &Version, arch: &str) -> bool {
+    image_exists(version, Some(arch))
+}
+
+/// Determines whether the image of a Node version exists, for the specified architecture or for
+/// the native one if no architecture is specified. Unlike checking the `NodeCollection`, this
+/// doesn't need the inventory to be loaded.
+pub fn image_exists(version: &Version, arch: Option<&str>) -> bool {
+    match load_default_npm_version(version) {
+        Ok(npm) => path::node_image_dir(&version.to_string(), &npm.to_string(), arch)
+            .map(|dir| dir.is_dir())
+            .unwrap_or(false),
+        Err(_) => false,
     }
 }
 
 /// A provisioned Node distribution.
 pub struct NodeDistro {
     archive: Box<dyn Archive>,
+    /// The file that the archive is cached in, which is complete once it has been unpacked.
+    file: PathBuf,
     version: Version,
+    arch: Option<String>,
+    /// The SHA-256s that the project pins for the archives of this version, by file name.
+    sha256: ArchiveHashes,
 }
 
 /// A full Node version including not just the version of Node itself
@@ -53,6 +160,8 @@ pub struct NodeVersion {
     pub runtime: Version,
     /// The npm version globally installed with the Node distro.
     pub npm: Version,
+    /// The architecture of the Node distro, if not the native one.
+    pub arch: Option<String>,
 }
 
 /// Load the local npm version file to determine the default npm version for a given version of Node
@@ -66,10 +175,11 @@ pub fn load_default_npm_version(node: &Version) -> Fallible<Version> {
     VersionSpec::parse_version(npm_version)
 }
 
-/// Save the default npm version to the filesystem for a given version of Node
+/// Save the default npm version to the filesystem for a given version of Node, all at once so that
+/// an interrupted fetch never leaves a partial version behind
 fn save_default_npm_version(node: &Version, npm: &Version) -> Fallible<()> {
     let npm_version_file_path = path::node_npm_version_file(&node.to_string())?;
-    write(&npm_version_file_path, npm.to_string().as_bytes()).with_context(|_| {
+    write_atomic(&npm_version_file_path, npm.to_string().as_bytes()).with_context(|_| {
         ErrorDetails::WriteDefaultNpmError {
             file: npm_version_file_path,
         }
@@ -79,10 +189,10 @@ fn save_default_npm_version(node: &Version, npm: &Version) -> Fallible<()> {
 /// Return the archive if it is valid. It may have been corrupted or interrupted in the middle of
 /// downloading.
 // ISSUE(#134) - verify checksum
-fn load_cached_distro(file: &PathBuf) -> Option<Box<dyn Archive>> {
+fn load_cached_distro(file: &PathBuf, format: Format) -> Option<Box<dyn Archive>> {
     if file.is_file() {
         if let Ok(file) = File::open(file) {
-            if let Ok(archive) = archive::load_native(file) {
+            if let Ok(archive) = format.load(file) {
                 return Some(archive);
             }
         }
@@ -107,33 +217,99 @@ impl Manifest {
 }
 
 impl NodeDistro {
-    /// Provision a Node distribution from the public Node distributor (`https://nodejs.org`).
-    fn public(version: Version) -> Fallible<Self> {
-        let distro_file_name = path::node_distro_file_name(&version.to_string());
-        let url = format!(
-            "{}/v{}/{}",
-            public_node_server_root(),
-            version,
-            &distro_file_name
-        );
-        NodeDistro::remote(version, &url)
+    /// Provision a Node distribution from the public Node distributor (`https://nodejs.org`), or
+    /// from the unofficial builds server on platforms that it doesn't support.
+    fn public(version: Version, arch: Option<String>) -> Fallible<Self> {
+        let server_root = node_server_root(&version);
+        NodeDistro::preferred(version, arch, |version, file_name| {
+            Ok(format!("{}/v{}/{}", server_root, version, file_name))
+        })
     }
 
-    /// Provision a Node distribution from a remote distributor.
-    fn remote(version: Version, url: &str) -> Fallible<Self> {
-        let distro_file_name = path::node_distro_file_name(&version.to_string());
-        let distro_file = path::node_inventory_dir()?.join(&distro_file_name);
+    /// Provision a Node distribution from a distributor that publishes each archive under its
+    /// file name, given a function that builds the URL of a file. Archive formats that are
+    /// smaller than the native one are used when the distributor has them.
+    fn preferred<F>(version: Version, arch: Option<String>, url_for: F) -> Fallible<Self>
+    where
+        F: Fn(&Version, &str) -> Fallible<String>,
+    {
+        let distro_file_name = path::node_distro_file_name(&version.to_string(), arch_str(&arch));
+        let root_name = path::node_archive_root_dir_name(&version.to_string(), arch_str(&arch));
+        let inventory_dir = inventory_dir(&version)?;
+
+        for &format in archive::preferred_formats() {
+            let distro_file = inventory_dir.join(format!("{}.{}", root_name, format.extension()));
+            if let Some(archive) = load_cached_distro(&distro_file, format) {
+                debug!(
+                    "Loading node@{} from cached archive at {}",
+                    version,
+                    distro_file.display()
+                );
+                return Ok(NodeDistro {
+                    archive,
+                    file: distro_file,
+                    version,
+                    arch,
+                    sha256: ArchiveHashes::new(),
+                });
+            }
+        }
+
+        // A cached archive in the native format is loaded by `remote`
+        let native_file = inventory_dir.join(&distro_file_name);
+        if !native_file.is_file() {
+            ensure_containing_dir_exists(&native_file)?;
+            limit_download_rate()?;
+
+            // Not every server publishes every format, so each is tried in turn before falling
+            // back to the native format
+            for &format in archive::preferred_formats() {
+                let file_name = format!("{}.{}", root_name, format.extension());
+                let url = url_for(&version, &file_name)?;
+                debug!("Downloading node@{} from {}", version, url);
+
+                let file = inventory_dir.join(&file_name);
+                match format.fetch(&url, &file) {
+                    Ok(archive) => {
+                        return Ok(NodeDistro {
+                            archive,
+                            file,
+                            version,
+                            arch,
+                            sha256: ArchiveHashes::new(),
+                        })
+                    }
+                    Err(error) => debug!("Could not download {}: {}", url, error),
+                }
+            }
+        }
 
-        if let Some(archive) = load_cached_distro(&distro_file) {
+        let url = url_for(&version, &distro_file_name)?;
+        NodeDistro::remote(version, arch, &url)
+    }
+
+    /// Provision a Node distribution in the native archive format from a remote distributor.
+    fn remote(version: Version, arch: Option<String>, url: &str) -> Fallible<Self> {
+        let distro_file_name = path::node_distro_file_name(&version.to_string(), arch_str(&arch));
+        let distro_file = inventory_dir(&version)?.join(&distro_file_name);
+
+        if let Some(archive) = load_cached_distro(&distro_file, archive::native_format()) {
             debug!(
                 "Loading node@{} from cached archive at {}",
                 version,
                 distro_file.display()
             );
-            return Ok(NodeDistro { archive, version });
+            return Ok(NodeDistro {
+                archive,
+                file: distro_file,
+                version,
+                arch,
+                sha256: ArchiveHashes::new(),
+            });
         }
 
         ensure_containing_dir_exists(&distro_file)?;
+        limit_download_rate()?;
         debug!("Downloading node@{} from {}", version, url);
 
         Ok(NodeDistro {
@@ -141,9 +317,288 @@ impl NodeDistro {
                 ToolSpec::Node(VersionSpec::exact(&version)),
                 url,
             ))?,
+            file: distro_file,
             version: version,
+            arch,
+            sha256: ArchiveHashes::new(),
+        })
+    }
+
+    /// Provisions a Node distribution for the specified architecture, or for the native one if
+    /// no architecture is specified.
+    pub fn for_arch(
+        version: Version,
+        arch: Option<&str>,
+        hooks: Option<&ToolHooks<Self>>,
+    ) -> Fallible<Self> {
+        let arch = arch.map(str::to_string);
+        match hooks {
+            Some(&ToolHooks {
+                distro: Some(ref hook),
+                ..
+            }) => {
+                debug!("Using node.distro hook to determine download URL");
+                match hook {
+                    // A prefix is followed by the file name, so it can point at any format
+                    DistroHook::Prefix(_) => {
+                        NodeDistro::preferred(version, arch, |version, file| {
+                            hook.resolve(version, file)
+                        })
+                    }
+                    // Templates and binaries produce the URL of a single archive, which is
+                    // always in the native format
+                    _ => {
+                        let distro_file_name =
+                            path::node_distro_file_name(&version.to_string(), arch_str(&arch));
+                        let url = hook.resolve(&version, &distro_file_name)?;
+                        NodeDistro::remote(version, arch, &url)
+                    }
+                }
+            }
+            _ => NodeDistro::public(version, arch),
+        }
+    }
+
+    /// Requires the archive to match the SHA-256 that a project pins for its file name, if any.
+    pub fn with_sha256(self, sha256: &ArchiveHashes) -> Self {
+        NodeDistro {
+            sha256: sha256.clone(),
+            ..self
+        }
+    }
+}
+
+/// Finds the SHA-256 of the archive that a fetched version of Node was installed from, so that a
+/// project can pin it. Images installed by older versions of Volta didn't record it, so for those
+/// the archive is hashed instead, if it is still in the inventory.
+pub fn archive_sha256(version: &Version, arch: Option<&str>) -> Fallible<ArchiveHash> {
+    let version_string = version.to_string();
+    let image = image_dir(version, arch)?;
+    if let Some(hash) = checksum::read_archive_hash(&image)? {
+        return Ok(hash);
+    }
+
+    let inventory_dir = inventory_dir(version)?;
+    let root_name = path::node_archive_root_dir_name(&version_string, arch);
+    let file_names = archive::preferred_formats()
+        .iter()
+        .map(|format| format!("{}.{}", root_name, format.extension()))
+        .chain(iter::once(path::node_distro_file_name(
+            &version_string,
+            arch,
+        )));
+    for file_name in file_names {
+        let file = inventory_dir.join(&file_name);
+        if file.is_file() {
+            return Ok(ArchiveHash {
+                sha256: checksum::sha256_file(&file)?,
+                file_name,
+            });
+        }
+    }
+
+    throw!(ErrorDetails::ArchiveHashUnknown {
+        tool: tool_version("node", version),
+        image,
+    })
+}
+
+/// Checks that a fetched version of Node was installed from an archive that matches the SHA-256
+/// that a project pins for it, if any.
+pub fn check_fetched_archive(
+    version: &Version,
+    arch: Option<&str>,
+    sha256: &ArchiveHashes,
+) -> Fallible<()> {
+    if sha256.is_empty() {
+        return Ok(());
+    }
+
+    checksum::check_installed_archive(
+        &image_dir(version, arch)?,
+        sha256,
+        &tool_version("node", version),
+    )
+}
+
+/// The image directory of a fetched version of Node.
+fn image_dir(version: &Version, arch: Option<&str>) -> Fallible<PathBuf> {
+    let npm = load_default_npm_version(version)?;
+    path::node_image_dir(&version.to_string(), &npm.to_string(), arch)
+}
+
+/// The server that publishes the public builds of a version of Node, which depends on its release
+/// channel and on whether the official builds support this platform.
+fn node_server_root(version: &Version) -> String {
+    match release_channel(version) {
+        Some(channel) => format!(
+            "{}/{}",
+            public_node_download_root(),
+            channel_dir_name(channel)
+        ),
+        None if use_unofficial_builds() => unofficial_node_server_root(),
+        None => public_node_server_root(),
+    }
+}
+
+/// The file that a Node server publishes the SHA-256 of each file of a version in, alongside them.
+const CHECKSUMS_FILE_NAME: &str = "SHASUMS256.txt";
+
+/// Builds the URL of a file that is published alongside the archives of a version of Node, like
+/// its headers, using the `node.distro` hook if there is one. A prefix is followed by the file
+/// name, as for an archive. Templates and binaries produce the URL of the native archive, so the
+/// file is expected next to it.
+fn sibling_url(version: &Version, file_name: &str, hook: Option<&DistroHook>) -> Fallible<String> {
+    match hook {
+        Some(hook @ DistroHook::Prefix(_)) => hook.resolve(version, file_name),
+        Some(hook) => {
+            let distro_file_name = path::node_distro_file_name(&version.to_string(), None);
+            let url = hook.resolve(version, &distro_file_name)?;
+            let dir = url.rfind('/').map_or("", |index| &url[..=index]);
+            Ok(format!("{}{}", dir, file_name))
+        }
+        None => Ok(format!(
+            "{}/v{}/{}",
+            node_server_root(version),
+            version,
+            file_name
+        )),
+    }
+}
+
+/// Parses the SHA-256 checksums that a Node server publishes for a version, by file name.
+fn parse_checksums(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(sha256), Some(file_name)) => {
+                    Some((file_name.to_string(), sha256.to_string()))
+                }
+                _ => None,
+            }
         })
+        .collect()
+}
+
+/// Fetches the SHA-256 checksums that are published for the files of a version of Node.
+fn fetch_checksums(
+    version: &Version,
+    hook: Option<&DistroHook>,
+) -> Fallible<HashMap<String, String>> {
+    let url = sibling_url(version, CHECKSUMS_FILE_NAME, hook)?;
+    let temp = create_staging_dir()?;
+    let file = temp.path().join(CHECKSUMS_FILE_NAME);
+    fetch_file(ToolSpec::Node(VersionSpec::exact(version)), &url, &file)?;
+
+    let text = read_to_string(&file).with_context(|_| ErrorDetails::ReadInventoryDirError {
+        dir: temp.path().to_path_buf(),
+    })?;
+    Ok(parse_checksums(&text))
+}
+
+/// Fetches the headers of a version of Node, which node-gyp needs to build native modules, so
+/// that builds with that version don't download them (see `ToolCommand::exec`). They are fetched
+/// from the same server as the archives, and checked against the checksums it publishes.
+pub fn fetch_headers(version: &Version, hooks: Option<&ToolHooks<NodeDistro>>) -> Fallible<()> {
+    let version_string = version.to_string();
+    let dest = path::node_headers_dir(&version_string)?;
+    if dest.is_dir() {
+        debug!("The headers of node@{} have already been fetched", version);
+        return Ok(());
+    }
+
+    path::ensure_writable_home()?;
+
+    let hook = hooks.and_then(|hooks| hooks.distro.as_ref());
+    let format = Format::Tarball(Compression::Gzip);
+    let file_name = path::node_headers_file_name(&version_string);
+    let inventory_dir = inventory_dir(version)?;
+    let archive_file = inventory_dir.join(&file_name);
+    let (archive, downloaded) = match load_cached_distro(&archive_file, format) {
+        Some(archive) => (archive, false),
+        None => {
+            ensure_containing_dir_exists(&archive_file)?;
+            limit_download_rate()?;
+            let url = sibling_url(version, &file_name, hook)?;
+            debug!("Downloading the headers of node@{} from {}", version, url);
+            let archive = format
+                .fetch(&url, &archive_file)
+                .with_context(download_tool_error(
+                    ToolSpec::Node(VersionSpec::exact(version)),
+                    &url,
+                ))?;
+            (archive, true)
+        }
+    };
+
+    let temp = create_staging_dir()?;
+    archive
+        .unpack(temp.path(), &mut |_, _| {})
+        .with_context(unpack_archive_error(
+            "Node headers",
+            version_string.as_str(),
+        ))?;
+
+    // As with a distro, a downloaded archive is only complete once it has been unpacked
+    if downloaded {
+        let checksums = fetch_checksums(version, hook)?;
+        checksum::check_archive(
+            &archive_file,
+            checksums.get(&file_name).map(String::as_str),
+            &tool_version("node", version),
+        )?;
     }
+
+    // Unlike a distro's, the root directory of the headers isn't named for a platform
+    let unpacked = temp.path().join(format!("node-v{}", version_string));
+    #[cfg(windows)]
+    fetch_node_lib(version, hook, &unpacked)?;
+    permissions::apply_to_tree(&unpacked)?;
+
+    ensure_containing_dir_exists(&dest)?;
+    rename(&unpacked, &dest).with_context(|_| ErrorDetails::SetupToolImageError {
+        tool: String::from("Node headers"),
+        version: version_string.clone(),
+        dir: dest.clone(),
+    })?;
+    prune_archives(&inventory_dir)
+}
+
+/// Fetches `node.lib`, which node-gyp links native modules with on Windows, and which is published
+/// separately from the headers. With a `nodedir`, node-gyp looks for it in the directory of the
+/// build configuration.
+#[cfg(windows)]
+fn fetch_node_lib(
+    version: &Version,
+    hook: Option<&DistroHook>,
+    headers_dir: &Path,
+) -> Fallible<()> {
+    let checksums = fetch_checksums(version, hook)?;
+    let file_name = format!("win-{}/node.lib", path::ARCH);
+    let url = sibling_url(version, &file_name, hook)?;
+    let lib_file = headers_dir.join("Release").join("node.lib");
+
+    fetch_file(ToolSpec::Node(VersionSpec::exact(version)), &url, &lib_file)?;
+    checksum::check_archive(
+        &lib_file,
+        checksums.get(&file_name).map(String::as_str),
+        &tool_version("node", version),
+    )?;
+    Ok(())
+}
+
+/// The inventory directory that archives of the specified version of Node are kept in.
+pub(crate) fn inventory_dir(version: &Version) -> Fallible<PathBuf> {
+    if version.is_prerelease() {
+        path::node_prerelease_inventory_dir()
+    } else {
+        path::node_inventory_dir()
+    }
+}
+
+fn arch_str(arch: &Option<String>) -> Option<&str> {
+    arch.as_ref().map(String::as_str)
 }
 
 impl Distro for NodeDistro {
@@ -156,18 +611,7 @@ impl Distro for NodeDistro {
         version: Self::ResolvedVersion,
         hooks: Option<&ToolHooks<Self>>,
     ) -> Fallible<Self> {
-        match hooks {
-            Some(&ToolHooks {
-                distro: Some(ref hook),
-                ..
-            }) => {
-                debug!("Using node.distro hook to determine download URL");
-                let url =
-                    hook.resolve(&version, &path::node_distro_file_name(&version.to_string()))?;
-                NodeDistro::remote(version, &url)
-            }
-            _ => NodeDistro::public(version),
-        }
+        NodeDistro::for_arch(version, None, hooks)
     }
 
     /// Produces a reference to this distribution's Node version.
@@ -178,7 +622,12 @@ impl Distro for NodeDistro {
     /// Fetches this version of Node. (It is left to the responsibility of the `NodeCollection`
     /// to update its state after fetching succeeds.)
     fn fetch(self, collection: &NodeCollection) -> Fallible<Fetched<NodeVersion>> {
-        if collection.contains(&self.version) {
+        let fetched = match self.arch {
+            Some(ref arch) => is_fetched_for_arch(&self.version, arch),
+            None => collection.contains(&self.version),
+        };
+
+        if fetched {
             let npm = load_default_npm_version(&self.version)?;
 
             debug!(
@@ -188,12 +637,12 @@ impl Distro for NodeDistro {
             return Ok(Fetched::Already(NodeVersion {
                 runtime: self.version,
                 npm,
+                arch: self.arch,
             }));
         }
 
-        let tmp_root = path::tmp_dir()?;
-        let temp = tempdir_in(&tmp_root)
-            .with_context(|_| ErrorDetails::CreateTempDirError { in_dir: tmp_root })?;
+        path::ensure_writable_home()?;
+        let temp = create_staging_dir()?;
         debug!("Unpacking node into {}", temp.path().display());
 
         let bar = progress_bar(
@@ -205,32 +654,44 @@ impl Distro for NodeDistro {
         );
 
         let version_string = self.version.to_string();
+        let arch = arch_str(&self.arch);
 
         self.archive
             .unpack(temp.path(), &mut |_, read| {
                 bar.inc(read as u64);
             })
-            .with_context(|_| ErrorDetails::UnpackArchiveError {
-                tool: String::from("Node"),
-                version: version_string.clone(),
-            })?;
+            .with_context(unpack_archive_error("Node", version_string.as_str()))?;
 
-        let npm_package_json = temp
-            .path()
-            .join(path::node_archive_npm_package_json_path(&version_string));
+        // The archive is only complete once it has been unpacked, so it is checked before the
+        // image is installed rather than before unpacking
+        let archive_hash = checksum::check_pinned_archive(
+            &self.file,
+            &self.sha256,
+            &tool_version("node", &self.version),
+        )?;
+
+        let npm_package_json = temp.path().join(path::node_archive_npm_package_json_path(
+            &version_string,
+            arch,
+        ));
 
         let npm = Manifest::version(&npm_package_json)?;
 
         // Save the npm version number in the npm version file for this distro:
         save_default_npm_version(&self.version, &npm)?;
 
-        let dest = path::node_image_dir(&version_string, &npm.to_string())?;
+        let dest = path::node_image_dir(&version_string, &npm.to_string(), arch)?;
 
         ensure_containing_dir_exists(&dest)?;
 
-        rename(
-            temp.path()
-                .join(path::node_archive_root_dir_name(&version_string)),
+        let unpacked = temp
+            .path()
+            .join(path::node_archive_root_dir_name(&version_string, arch));
+        permissions::apply_to_tree(&unpacked)?;
+
+        install_image(
+            &unpacked,
+            &path::node_store_dir(&version_string, arch)?,
             &dest,
         )
         .with_context(|_| ErrorDetails::SetupToolImageError {
@@ -238,6 +699,9 @@ impl Distro for NodeDistro {
             version: version_string.clone(),
             dir: dest.clone(),
         })?;
+        checksum::write_manifest(&dest)?;
+        checksum::write_archive_hash(&dest, &archive_hash)?;
+        prune_archives(&inventory_dir(&self.version)?)?;
 
         bar.finish_and_clear();
 
@@ -247,6 +711,80 @@ impl Distro for NodeDistro {
         Ok(Fetched::Now(NodeVersion {
             runtime: self.version,
             npm,
+            arch: self.arch,
         }))
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_channel() {
+        let channel_of =
+            |version: &str| release_channel(&VersionSpec::parse_version(version).unwrap());
+
+        assert_eq!(channel_of("20.0.0"), None);
+        assert_eq!(channel_of("20.0.0-rc.2"), Some(ReleaseChannel::Rc));
+        assert_eq!(
+            channel_of("21.0.0-nightly20230801d396a041f7"),
+            Some(ReleaseChannel::Nightly)
+        );
+        assert_eq!(
+            channel_of("21.0.0-v8-canary20230801d396a041f7"),
+            Some(ReleaseChannel::Canary)
+        );
+        assert_eq!(channel_of("20.0.0-beta.1"), None);
+    }
+
+    #[test]
+    fn test_parse_arch() {
+        assert_eq!(parse_arch("x64").unwrap(), "x64".to_string());
+        assert_eq!(parse_arch("arm64").unwrap(), "arm64".to_string());
+        assert!(parse_arch("amd64").is_err());
+    }
+
+    #[test]
+    fn test_sibling_url() {
+        let version = Version::new(10, 16, 0);
+        let file_name = "node-v10.16.0-headers.tar.gz";
+
+        let prefix = DistroHook::Prefix("http://localhost/node/".to_string());
+        assert_eq!(
+            sibling_url(&version, file_name, Some(&prefix)).unwrap(),
+            "http://localhost/node/node-v10.16.0-headers.tar.gz"
+        );
+
+        let template =
+            DistroHook::Template("http://localhost/v{{version}}/node-{{os}}.tar.gz".to_string());
+        assert_eq!(
+            sibling_url(&version, file_name, Some(&template)).unwrap(),
+            "http://localhost/v10.16.0/node-v10.16.0-headers.tar.gz"
+        );
+
+        assert!(sibling_url(&version, file_name, None)
+            .unwrap()
+            .ends_with("/v10.16.0/node-v10.16.0-headers.tar.gz"));
+    }
+
+    #[test]
+    fn test_parse_checksums() {
+        let checksums = parse_checksums(
+            "abc123  node-v10.16.0-headers.tar.gz\n\
+             def456  win-x64/node.lib\n\
+             \n",
+        );
+        assert_eq!(checksums.len(), 2);
+        assert_eq!(
+            checksums
+                .get("node-v10.16.0-headers.tar.gz")
+                .map(String::as_str),
+            Some("abc123")
+        );
+        assert_eq!(
+            checksums.get("win-x64/node.lib").map(String::as_str),
+            Some("def456")
+        );
+    }
+}
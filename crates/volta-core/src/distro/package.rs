@@ -1,57 +1,82 @@
 //! Provides types for installing packages to the user toolchain.
 
 use std::collections::HashMap;
-use std::ffi::OsStr;
-use std::fs::{self, rename, write, File};
-use std::io::{self, Read, Seek, SeekFrom};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, remove_file, write, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use atty::Stream;
-use cfg_if::cfg_if;
+use base64;
+use chrono::Utc;
+use cmdline_words_parser::StrExt;
+use headers_011::Headers011;
 use hex;
-use log::{debug, info};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use reqwest;
+use reqwest::hyper_011::header::RetryAfter;
 use semver::Version;
 use sha1::{Digest, Sha1};
-use tempfile::tempdir_in;
+use sha2::{Sha256, Sha384, Sha512};
 
 use crate::command::create_command;
-use crate::distro::{download_tool_error, Distro, Fetched};
+use crate::distro::node;
+use crate::distro::package_source::PackageSource;
+use crate::distro::{
+    download_tool_error, limit_download_rate, unpack_archive_error, Distro, Fetched,
+};
+use crate::env::{flag_enabled, SCRIPT_POLICY, SERIAL_REGISTRY};
 use crate::error::ErrorDetails;
 use crate::fs::{
-    delete_dir_error, dir_entry_match, ensure_containing_dir_exists, ensure_dir_does_not_exist,
-    read_dir_eager, read_file_opt,
+    create_staging_dir, dir_entry_match, ensure_containing_dir_exists, ensure_dir_does_not_exist,
+    read_dir_eager, read_file_opt, rename,
 };
 use crate::hook::ToolHooks;
 use crate::inventory::Collection;
 use crate::manifest::Manifest;
 use crate::path;
+use crate::permissions;
+use crate::plan::Change;
 use crate::platform::{Image, PlatformSpec};
 use crate::session::Session;
 use crate::shim;
 use crate::style::{progress_bar, progress_spinner, tool_version};
 use crate::tool::ToolSpec;
 use crate::version::VersionSpec;
-use archive::{Archive, Tarball};
-
-cfg_if! {
-    if #[cfg(windows)] {
-        use cmdline_words_parser::StrExt;
-        use regex::Regex;
-        use std::io::{BufRead, BufReader};
-    } else if #[cfg(unix)] {
-        use std::os::unix::fs::PermissionsExt;
-    }
-}
+use archive::{Archive, Origin, Tarball, Throttle};
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
 
 use volta_fail::{throw, Fallible, ResultExt};
 
+/// How many times a request that the registry rate-limited is retried before giving up.
+const MAX_REGISTRY_RETRIES: u32 = 5;
+
+/// The longest Volta will wait before retrying a rate-limited request, however long the registry
+/// asks it to wait.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    /// Held for the whole of each registry request when `VOLTA_SERIAL_REGISTRY` is set, so that
+    /// fetches running at the same time don't send their requests in a burst.
+    static ref REGISTRY_LOCK: Mutex<()> = Mutex::new(());
+}
+
 /// A provisioned Package distribution.
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct PackageDistro {
     pub name: String,
     pub shasum: String,
+    pub integrity: Option<String>,
+    pub authorization: Option<String>,
     pub tarball_url: String,
     pub version: Version,
     pub image_dir: PathBuf,
@@ -75,6 +100,60 @@ enum Installer {
     Yarn,
 }
 
+/// Whether lifecycle scripts (like `postinstall`) are run when installing the dependencies of a
+/// package.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptPolicy {
+    Allow,
+    Deny,
+    /// Ask before installing each package, denying if there is no terminal to ask with.
+    Prompt,
+}
+
+impl ScriptPolicy {
+    /// Reads the policy from the `VOLTA_SCRIPT_POLICY` environment variable, allowing scripts
+    /// if it isn't set.
+    pub fn from_env() -> Fallible<Self> {
+        match env::var(SCRIPT_POLICY) {
+            Ok(ref value) if value == "allow" => Ok(ScriptPolicy::Allow),
+            Ok(ref value) if value == "deny" => Ok(ScriptPolicy::Deny),
+            Ok(ref value) if value == "prompt" => Ok(ScriptPolicy::Prompt),
+            Ok(ref value) if value.is_empty() => Ok(ScriptPolicy::Allow),
+            Ok(value) => throw!(ErrorDetails::InvalidScriptPolicy { value }),
+            Err(_) => Ok(ScriptPolicy::Allow),
+        }
+    }
+
+    /// Determines whether scripts should run while installing the specified package.
+    fn allows_scripts(self, name: &str, version: &Version) -> bool {
+        match self {
+            ScriptPolicy::Allow => true,
+            ScriptPolicy::Deny => false,
+            ScriptPolicy::Prompt => {
+                if !atty::is(Stream::Stdin) || !atty::is(Stream::Stderr) {
+                    debug!("Not running scripts, since there is no terminal to prompt with");
+                    return false;
+                }
+
+                eprint!(
+                    "Allow lifecycle scripts for {} and its dependencies to run? [y/N] ",
+                    tool_version(name, version)
+                );
+                let _ = io::stderr().flush();
+
+                let mut answer = String::new();
+                match io::stdin().read_line(&mut answer) {
+                    Ok(_) => {
+                        let answer = answer.trim().to_lowercase();
+                        answer == "y" || answer == "yes"
+                    }
+                    Err(_) => false,
+                }
+            }
+        }
+    }
+}
+
 /// Configuration information about an installed package.
 ///
 /// This information will be stored in ~/.volta/tools/user/packages/<package>.json.
@@ -94,7 +173,9 @@ enum Installer {
 ///   "bins": [
 ///     "cowsay",
 ///     "cowthink"
-///   ]
+///   ],
+///   "spec": "cowsay@^1.4",
+///   "node_abi": 67
 /// }
 pub struct PackageConfig {
     /// The package name
@@ -105,6 +186,12 @@ pub struct PackageConfig {
     pub platform: PlatformSpec,
     /// The binaries installed by this package
     pub bins: Vec<String>,
+    /// The tool spec the package was installed from, if known
+    pub spec: Option<String>,
+    /// The Node ABI version that the package's native modules were built against, if known
+    pub node_abi: Option<u32>,
+    /// When the package was installed, as an RFC 3339 timestamp, if known
+    pub installed: Option<String>,
 }
 
 /// Configuration information about an installed binary from a package.
@@ -128,7 +215,8 @@ pub struct PackageConfig {
 ///       "exe": "node",
 ///       "args": []
 ///     }
-///   }
+///   },
+///   "node_abi": 67
 /// }
 pub struct BinConfig {
     /// The binary name
@@ -143,17 +231,23 @@ pub struct BinConfig {
     pub platform: PlatformSpec,
     /// The loader information for the script, if any
     pub loader: Option<BinLoader>,
+    /// The Node ABI version that the package's native modules were built against, if known
+    pub node_abi: Option<u32>,
 }
 
 /// Information about the Shebang script loader (e.g. `#!/usr/bin/env node`)
 ///
-/// Only important for Windows at the moment, as Windows does not natively understand script
-/// loaders, so we need to provide that behavior when calling a script that uses one
+/// Windows does not natively understand script loaders, and on Unix the loader would be resolved
+/// against whatever `PATH` the OS sees, so we provide that behavior ourselves when calling a
+/// script that uses one
+#[derive(Debug, PartialEq)]
 pub struct BinLoader {
     /// The command used to run a script
     pub command: String,
-    /// Any additional arguments specified for the loader
-    pub args: Vec<String>,
+    /// Any additional arguments specified for the loader, which may include paths
+    pub args: Vec<OsString>,
+    /// Environment variables that the shebang sets for the script, with `env VAR=value`
+    pub env: HashMap<String, String>,
 }
 
 impl Distro for PackageDistro {
@@ -169,6 +263,8 @@ impl Distro for PackageDistro {
         Ok(PackageDistro {
             name: name.to_string(),
             shasum: entry.shasum,
+            integrity: entry.integrity,
+            authorization: entry.authorization,
             version: version.clone(),
             tarball_url: entry.tarball,
             image_dir: path::package_image_dir(name, &version.to_string())?,
@@ -190,9 +286,8 @@ impl Distro for PackageDistro {
 
         let archive = self.load_or_fetch_archive()?;
 
-        let tmp_root = path::tmp_dir()?;
-        let temp = tempdir_in(&tmp_root)
-            .with_context(|_| ErrorDetails::CreateTempDirError { in_dir: tmp_root })?;
+        path::ensure_writable_home()?;
+        let temp = create_staging_dir()?;
         self.log_unpacking(&temp.path().display());
 
         let bar = progress_bar(
@@ -207,10 +302,10 @@ impl Distro for PackageDistro {
             .unpack(temp.path(), &mut |_, read| {
                 bar.inc(read as u64);
             })
-            .with_context(|_| ErrorDetails::UnpackArchiveError {
-                tool: self.name.clone(),
-                version: self.version.to_string(),
-            })?;
+            .with_context(unpack_archive_error(
+                self.name.clone(),
+                self.version.to_string(),
+            ))?;
 
         // ensure that the dir where this will be unpacked exists
         ensure_containing_dir_exists(&self.image_dir)?;
@@ -235,6 +330,16 @@ impl Distro for PackageDistro {
             }
         })?;
 
+        if self.name == "npm" {
+            replace_npm_launchers(&self.image_dir).with_context(|_| {
+                ErrorDetails::SetupToolImageError {
+                    tool: self.name.clone(),
+                    version: self.version.to_string(),
+                    dir: self.image_dir.clone(),
+                }
+            })?;
+        }
+
         bar.finish_and_clear();
 
         // Note: We write this after the progress bar is finished to avoid display bugs with re-renders of the progress
@@ -252,7 +357,8 @@ impl Distro for PackageDistro {
 }
 
 impl PackageDistro {
-    /// Loads the package tarball from disk, or fetches from URL.
+    /// Loads the package tarball from disk, or fetches from URL. A fetched tarball is verified
+    /// against the checksums reported by the registry before it is unpacked.
     fn load_or_fetch_archive(&self) -> Fallible<Box<Archive>> {
         // try to use existing downloaded package
         if let Some(archive) = self.load_cached_archive() {
@@ -265,19 +371,67 @@ impl PackageDistro {
         } else {
             // otherwise have to download
             ensure_containing_dir_exists(&self.distro_file)?;
+            limit_download_rate()?;
             debug!(
                 "Downloading {} from {}",
                 tool_version(&self.name, &self.version),
                 &self.tarball_url
             );
 
-            Tarball::fetch(&self.tarball_url, &self.distro_file).with_context(download_tool_error(
+            download_file(
+                &self.tarball_url,
+                &self.distro_file,
+                self.authorization.as_ref().map(String::as_str),
+                &tool_version(&self.name, &self.version),
+            )
+            .with_context(download_tool_error(
                 ToolSpec::Package(self.name.to_string(), VersionSpec::exact(&self.version)),
                 self.tarball_url.to_string(),
-            ))
+            ))?;
+
+            self.verify_download()?;
+
+            File::open(&self.distro_file)
+                .map_err(failure::Error::from)
+                .and_then(Tarball::load)
+                .with_context(|_| ErrorDetails::UnpackArchiveError {
+                    tool: self.name.clone(),
+                    version: self.version.to_string(),
+                })
         }
     }
 
+    /// Verifies the downloaded tarball against the `shasum` and `integrity` reported by the
+    /// registry, removing it if it doesn't match so that it won't be loaded from the cache later.
+    fn verify_download(&self) -> Fallible<()> {
+        let contents =
+            fs::read(&self.distro_file).with_context(|_| ErrorDetails::PackageIntegrityError {
+                package: self.name.clone(),
+                version: self.version.to_string(),
+                from_url: self.tarball_url.clone(),
+            })?;
+
+        if !verify_checksums(
+            &contents,
+            &self.shasum,
+            self.integrity.as_ref().map(String::as_str),
+        ) {
+            debug!(
+                "Removing {} which failed integrity verification",
+                self.distro_file.display()
+            );
+            // The error about the mismatch is more useful than one about failing to clean up
+            let _ = remove_file(&self.distro_file);
+            throw!(ErrorDetails::PackageIntegrityError {
+                package: self.name.clone(),
+                version: self.version.to_string(),
+                from_url: self.tarball_url.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
     /// Verify downloaded package, returning an Archive if it is ok.
     fn load_cached_archive(&self) -> Option<Box<dyn Archive>> {
         let mut distro = File::open(&self.distro_file).ok()?;
@@ -286,13 +440,7 @@ impl PackageDistro {
         let mut buffer = Vec::new();
         distro.read_to_end(&mut buffer).ok()?;
 
-        // calculate the shasum
-        let mut hasher = Sha1::new();
-        hasher.input(buffer);
-        let result = hasher.result();
-        let calculated_shasum = hex::encode(&result);
-
-        if stored_shasum != calculated_shasum {
+        if stored_shasum != sha1_hex(&buffer) {
             return None;
         }
 
@@ -311,32 +459,32 @@ impl PackageDistro {
         false
     }
 
-    fn generate_bin_map(&self) -> Fallible<HashMap<String, String>> {
-        let pkg_info = Manifest::for_dir(&self.image_dir)?;
-        let bin_map = pkg_info.bin;
-        if bin_map.is_empty() {
-            throw!(ErrorDetails::NoPackageExecutables);
-        }
-
-        for (bin_name, _bin_path) in bin_map.iter() {
-            // check for conflicts with installed bins
-            // some packages may install bins with the same name
-            let bin_config_file = path::user_tool_bin_config(&bin_name)?;
-            if bin_config_file.exists() {
-                let bin_config = BinConfig::from_file(bin_config_file)?;
-                // if the bin was installed by the package that is currently being installed,
-                // that's ok - otherwise it's an error
-                if self.name != bin_config.package {
-                    throw!(ErrorDetails::BinaryAlreadyInstalled {
-                        bin_name: bin_name.to_string(),
-                        existing_package: bin_config.package,
-                        new_package: self.name.clone(),
-                    });
-                }
-            }
+    /// Lists the changes that installing this package would make, unless it is installed already
+    /// (see `volta --dry-run`). Which executables it has isn't known until it is unpacked, so
+    /// the directories that their configs and shims go in are listed instead.
+    pub(crate) fn plan_install(&self) -> Fallible<Vec<Change>> {
+        if self.is_installed() {
+            return Ok(Vec::new());
         }
 
-        Ok(bin_map)
+        let mut changes = Vec::new();
+        if self.load_cached_archive().is_none() {
+            changes.push(Change::Download {
+                tool: tool_version(&self.name, &self.version),
+                url: Some(self.tarball_url.clone()),
+                into: self.distro_file.clone(),
+            });
+        }
+        changes.push(Change::Write(self.image_dir.clone()));
+        changes.push(Change::Write(self.shasum_file.clone()));
+        changes.push(Change::Write(path::user_package_config_file(&self.name)?));
+        changes.push(Change::Write(path::user_bin_dir()?));
+        changes.push(Change::Write(path::shim_dir()?));
+        Ok(changes)
+    }
+
+    fn generate_bin_map(&self) -> Fallible<HashMap<String, String>> {
+        generate_bin_map(&self.image_dir)
     }
 
     fn log_unpacking<D>(&self, path: &D)
@@ -359,9 +507,27 @@ impl PackageDistro {
     }
 }
 
+/// Reads the map of binary names to locations for a package that is being installed from
+/// `image_dir`. A package that doesn't provide any executables can't be installed.
+///
+/// Executables that other installed packages already provide aren't an error: the package takes
+/// them over when it is installed (see `PackageVersion::warn_if_bin_conflicts`).
+pub(crate) fn generate_bin_map(image_dir: &Path) -> Fallible<HashMap<String, String>> {
+    let bin_map = read_bin_map(image_dir)?;
+    if bin_map.is_empty() {
+        throw!(ErrorDetails::NoPackageExecutables);
+    }
+    Ok(bin_map)
+}
+
+/// Reads the map of binary names to locations for the package unpacked in `image_dir`.
+fn read_bin_map(image_dir: &Path) -> Fallible<HashMap<String, String>> {
+    Ok(Manifest::for_dir(image_dir)?.bin)
+}
+
 // Figure out the unpacked package directory name dynamically, because
 // packages typically extract to a "package" directory, but not always
-fn find_unpack_dir(in_dir: &Path) -> Fallible<PathBuf> {
+pub(crate) fn find_unpack_dir(in_dir: &Path) -> Fallible<PathBuf> {
     let dirs: Vec<_> = read_dir_eager(in_dir)
         .with_context(|_| ErrorDetails::PackageUnpackError)?
         .collect();
@@ -387,6 +553,12 @@ impl PackageVersion {
         })
     }
 
+    /// Removes the installed dependencies of the package, so that the next install builds them
+    /// again from scratch.
+    pub(crate) fn remove_dependencies(&self) -> Fallible<()> {
+        ensure_dir_does_not_exist(&self.image_dir.join("node_modules"))
+    }
+
     // parse the "engines" string to a VersionSpec, for matching against available Node versions
     pub fn engines_spec(&self) -> Fallible<VersionSpec> {
         let manifest = Manifest::for_dir(&self.image_dir)?;
@@ -412,7 +584,24 @@ impl PackageVersion {
         Ok(VersionSpec::Semver(spec))
     }
 
-    pub fn install(&self, platform: &PlatformSpec, session: &mut Session) -> Fallible<()> {
+    /// Installs the dependencies of the package and records it as installed from the specified
+    /// tool spec, which is used to upgrade it later.
+    pub fn install(
+        &self,
+        platform: &PlatformSpec,
+        spec: &str,
+        session: &mut Session,
+    ) -> Fallible<()> {
+        let ignore_scripts = !session
+            .script_policy()?
+            .allows_scripts(&self.name, &self.version);
+        if ignore_scripts {
+            debug!(
+                "Not running lifecycle scripts for {}",
+                tool_version(&self.name, &self.version)
+            );
+        }
+
         let image = platform.checkout(session)?;
         // use yarn if it is installed, otherwise default to npm
         let installer = if image.yarn.is_some() {
@@ -421,8 +610,12 @@ impl PackageVersion {
             Installer::Npm
         };
 
-        let mut command =
-            install_command_for(installer, self.image_dir.as_os_str(), &image.path()?);
+        let mut command = install_command_for(
+            installer,
+            self.image_dir.as_os_str(),
+            &image.path()?,
+            ignore_scripts,
+        );
         self.log_installing_dependencies(&command);
 
         let spinner = progress_spinner(&format!(
@@ -441,12 +634,22 @@ impl PackageVersion {
             throw!(ErrorDetails::PackageInstallFailed);
         }
 
-        self.write_config_and_shims(&platform)?;
+        // The dependencies are set along with the package, since npm and Yarn create them with
+        // the permissions of the umask
+        permissions::apply_to_tree(&self.image_dir)?;
+
+        let node_abi = session.node_abi(&platform.node_runtime)?;
+        self.write_config_and_shims(&platform, Some(spec), node_abi)?;
 
         Ok(())
     }
 
-    fn package_config(&self, platform_spec: &PlatformSpec) -> PackageConfig {
+    fn package_config(
+        &self,
+        platform_spec: &PlatformSpec,
+        spec: Option<&str>,
+        node_abi: Option<u32>,
+    ) -> PackageConfig {
         PackageConfig {
             name: self.name.to_string(),
             version: self.version.clone(),
@@ -456,6 +659,9 @@ impl PackageVersion {
                 .iter()
                 .map(|(name, _path)| name.to_string())
                 .collect(),
+            spec: spec.map(str::to_string),
+            node_abi,
+            installed: Some(Utc::now().to_rfc3339()),
         }
     }
 
@@ -465,44 +671,220 @@ impl PackageVersion {
         bin_path: String,
         platform_spec: PlatformSpec,
         loader: Option<BinLoader>,
+        node_abi: Option<u32>,
     ) -> BinConfig {
         BinConfig {
             name: bin_name,
             package: self.name.to_string(),
             version: self.version.clone(),
             path: bin_path,
+            node_abi,
             platform: platform_spec,
             loader,
         }
     }
 
-    fn write_config_and_shims(&self, platform_spec: &PlatformSpec) -> Fallible<()> {
-        self.package_config(&platform_spec).to_serial().write()?;
-        for (bin_name, bin_path) in self.bins.iter() {
-            let full_path = bin_full_path(&self.name, &self.version, bin_name, bin_path)?;
-            let loader = determine_script_loader(bin_name, &full_path)?;
-            self.bin_config(
-                bin_name.to_string(),
-                bin_path.to_string(),
-                platform_spec.clone(),
-                loader,
-            )
+    fn write_config_and_shims(
+        &self,
+        platform_spec: &PlatformSpec,
+        spec: Option<&str>,
+        node_abi: Option<u32>,
+    ) -> Fallible<()> {
+        self.package_config(&platform_spec, spec, node_abi)
             .to_serial()
             .write()?;
-            // create a link to the shim executable
-            shim::create(&bin_name)?;
-
-            // On Unix, ensure the executable file has correct permissions
-            #[cfg(unix)]
-            set_executable_permissions(&full_path).with_context(|_| {
-                ErrorDetails::ExecutablePermissionsError {
-                    bin: bin_name.clone(),
-                }
+        for (bin_name, bin_path) in self.bins.iter() {
+            self.warn_if_bin_conflicts(bin_name)?;
+            self.write_bin_config(bin_name, bin_path, platform_spec, node_abi)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the config for one of the package's executables, so that the package provides it,
+    /// and creates its shim.
+    fn write_bin_config(
+        &self,
+        bin_name: &str,
+        bin_path: &str,
+        platform_spec: &PlatformSpec,
+        node_abi: Option<u32>,
+    ) -> Fallible<()> {
+        let full_path = bin_full_path(&self.name, &self.version, bin_name, bin_path)?;
+        let loader = determine_script_loader(bin_name, &full_path)?;
+        self.bin_config(
+            bin_name.to_string(),
+            bin_path.to_string(),
+            platform_spec.clone(),
+            loader,
+            node_abi,
+        )
+        .to_serial()
+        .write()?;
+        // create a link to the shim executable
+        shim::create(&bin_name)?;
+
+        // On Unix, ensure the executable file has correct permissions
+        #[cfg(unix)]
+        set_executable_permissions(&full_path).with_context(|_| {
+            ErrorDetails::ExecutablePermissionsError {
+                bin: bin_name.to_string(),
+            }
+        })?;
+        permissions::apply(&full_path)?;
+
+        Ok(())
+    }
+
+    /// Warns when an executable of the package is currently provided by another installed
+    /// package, which this package is about to take over.
+    fn warn_if_bin_conflicts(&self, bin_name: &str) -> Fallible<()> {
+        if let Some(owner) = bin_owner(bin_name)? {
+            if owner != self.name && path::user_package_config_file(&owner)?.exists() {
+                warn!(
+                    "Executable '{}' is also provided by '{}', and will now run from '{}'.
+To switch back, run `volta prefer {} {}`",
+                    bin_name, owner, self.name, owner, bin_name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the native modules of an installed package against the specified platform, and
+    /// records that platform as the one the package and its executables run with.
+    pub fn rebuild(name: &str, platform: &PlatformSpec, session: &mut Session) -> Fallible<()> {
+        let package_config = installed_package_config(name)?;
+        let package_version = PackageVersion::from_config(&package_config)?;
+        let image_dir = package_version.image_dir.clone();
+
+        let image = platform.checkout(session)?;
+        let mut command = create_command("npm");
+        command
+            .arg("rebuild")
+            .current_dir(&image_dir)
+            .env("PATH", image.path()?);
+        package_version.log_installing_dependencies(&command);
+
+        let spinner = progress_spinner(&format!(
+            "Rebuilding native modules for {}",
+            tool_version(name, &package_config.version)
+        ));
+        let output = command
+            .output()
+            .with_context(|_| ErrorDetails::PackageRebuildFailed {
+                package: name.to_string(),
             })?;
+        spinner.finish_and_clear();
+
+        package_version.log_dependency_install_stderr(&output.stderr);
+        package_version.log_dependency_install_stdout(&output.stdout);
+
+        if !output.status.success() {
+            throw!(ErrorDetails::PackageRebuildFailed {
+                package: name.to_string(),
+            });
         }
+
+        let node_abi = session.node_abi(&platform.node_runtime)?;
+        let mut rebuilt_config = package_version.package_config(
+            platform,
+            package_config.spec.as_ref().map(String::as_str),
+            node_abi,
+        );
+        // rebuilding a package doesn't install it again
+        rebuilt_config.installed = package_config.installed.clone();
+        rebuilt_config.to_serial().write()?;
+
+        // executables that another package has taken over keep running with that package
+        for (bin_name, bin_path) in package_version.bins.iter() {
+            if bin_owner(bin_name)?.as_ref().map(String::as_str) == Some(name) {
+                package_version.write_bin_config(bin_name, bin_path, platform, node_abi)?;
+            }
+        }
+
         Ok(())
     }
 
+    /// Makes an installed package the provider of the specified executables (or of all of its
+    /// executables, if none are specified) in place of any other packages that provide them.
+    /// Returns the names of the executables.
+    pub fn prefer(name: &str, bin_names: &[String]) -> Fallible<Vec<String>> {
+        let package_config = installed_package_config(name)?;
+        let package_version = PackageVersion::from_config(&package_config)?;
+        let bin_names = if bin_names.is_empty() {
+            package_config.bins.clone()
+        } else {
+            bin_names.to_vec()
+        };
+
+        for bin_name in &bin_names {
+            match package_version.bins.get(bin_name) {
+                Some(bin_path) => package_version.write_bin_config(
+                    bin_name,
+                    bin_path,
+                    &package_config.platform,
+                    package_config.node_abi,
+                )?,
+                None => throw!(ErrorDetails::PackageBinNotFound {
+                    package: name.to_string(),
+                    bin: bin_name.to_string(),
+                }),
+            }
+        }
+
+        Ok(bin_names)
+    }
+
+    /// Links a package under development into the user toolchain. The image of the package is a
+    /// link to its source directory, so its executables always run the live source. Like
+    /// `npm link`, this replaces any installed version of the package.
+    pub fn link(dir: &Path, platform: &PlatformSpec, node_abi: Option<u32>) -> Fallible<Self> {
+        let (name, version) =
+            PackageSource::Directory(dir.to_path_buf()).read_name_and_version(dir)?;
+
+        if path::user_package_config_file(&name)?.exists() {
+            PackageVersion::uninstall(&name)?;
+        }
+
+        let image_dir = path::package_image_dir(&name, &version.to_string())?;
+        ensure_containing_dir_exists(&image_dir)?;
+        ensure_dir_does_not_exist(&image_dir)?;
+        path::create_dir_symlink(dir.to_path_buf(), image_dir.clone()).with_context(|_| {
+            ErrorDetails::LinkPackageError {
+                package: name.clone(),
+                dir: dir.to_path_buf(),
+            }
+        })?;
+
+        let bins = generate_bin_map(&image_dir)?;
+        let package_version = PackageVersion::new(name, version, bins)?;
+        package_version.write_config_and_shims(platform, None, node_abi)?;
+        Ok(package_version)
+    }
+
+    /// Removes a linked package from the user toolchain, leaving its source directory alone.
+    pub fn unlink(name: &str) -> Fallible<()> {
+        let package_config = installed_package_config(name)?;
+        if linked_dir(&package_config)?.is_none() {
+            throw!(ErrorDetails::PackageNotLinked {
+                package: name.to_string(),
+            });
+        }
+
+        PackageVersion::uninstall(name)
+    }
+
+    /// Loads an installed package from its config.
+    fn from_config(package_config: &PackageConfig) -> Fallible<Self> {
+        let image_dir =
+            path::package_image_dir(&package_config.name, &package_config.version.to_string())?;
+        PackageVersion::new(
+            package_config.name.clone(),
+            package_config.version.clone(),
+            read_bin_map(&image_dir)?,
+        )
+    }
+
     /// Uninstall the specified package.
     ///
     /// This removes:
@@ -515,8 +897,15 @@ impl PackageVersion {
         if package_config_file.exists() {
             let package_config = PackageConfig::from_file(&package_config_file)?;
 
+            // the image of a linked package is a link to its source directory, so only the link
+            // is removed, before the rest of the images
+            ensure_dir_does_not_exist(&path::package_image_dir(
+                name,
+                &package_config.version.to_string(),
+            )?)?;
+
             for bin_name in package_config.bins {
-                PackageVersion::remove_config_and_shim(&bin_name, name)?;
+                PackageVersion::release_bin(&bin_name, name)?;
             }
 
             fs::remove_file(&package_config_file)
@@ -533,16 +922,93 @@ impl PackageVersion {
         }
 
         // if any unpacked and initialized packages exists, remove them
-        let package_image_dir = path::package_image_root_dir()?.join(name);
-        if package_image_dir.exists() {
-            fs::remove_dir_all(&package_image_dir)
-                .with_context(delete_dir_error(&package_image_dir))?;
-        }
+        ensure_dir_does_not_exist(&path::package_image_root_dir()?.join(name))?;
 
         Ok(())
     }
 
-    fn remove_config_and_shim(bin_name: &str, name: &str) -> Fallible<()> {
+    /// Lists the changes that uninstalling a package would make, without making them (see
+    /// `volta --dry-run`).
+    pub fn plan_uninstall(name: &str) -> Fallible<Vec<Change>> {
+        let mut changes = Vec::new();
+        let package_config_file = path::user_package_config_file(name)?;
+        if package_config_file.exists() {
+            let package_config = PackageConfig::from_file(&package_config_file)?;
+
+            for bin_name in package_config.bins {
+                changes.extend(PackageVersion::plan_release_bin(&bin_name, name)?);
+            }
+
+            changes.push(Change::Remove(package_config_file));
+        } else if path::user_bin_dir()?.exists() {
+            for bin_name in binaries_from_package(name)? {
+                changes.extend(plan_remove_config_and_shim(&bin_name)?);
+            }
+        }
+
+        let image_dir = path::package_image_root_dir()?.join(name);
+        if image_dir.exists() {
+            changes.push(Change::Remove(image_dir));
+        }
+
+        Ok(changes)
+    }
+
+    /// Lists the changes that releasing an executable would make (see `release_bin`).
+    fn plan_release_bin(bin_name: &str, name: &str) -> Fallible<Vec<Change>> {
+        match bin_owner(bin_name)? {
+            Some(ref owner) if owner != name => return Ok(Vec::new()),
+            _ => (),
+        }
+
+        let successor = packages_with_bin(bin_name)?
+            .into_iter()
+            .find(|package_config| package_config.name != name);
+
+        if let Some(package_config) = successor {
+            let package_version = PackageVersion::from_config(&package_config)?;
+            if package_version.bins.contains_key(bin_name) {
+                return Ok(vec![Change::Write(path::user_tool_bin_config(bin_name)?)]);
+            }
+        }
+        plan_remove_config_and_shim(bin_name)
+    }
+
+    /// Removes an executable of a package that is being uninstalled. If another installed package
+    /// also provides the executable, it is handed over to that package instead.
+    fn release_bin(bin_name: &str, name: &str) -> Fallible<()> {
+        match bin_owner(bin_name)? {
+            Some(ref owner) if owner != name => return Ok(()),
+            _ => (),
+        }
+
+        let successor = packages_with_bin(bin_name)?
+            .into_iter()
+            .find(|package_config| package_config.name != name);
+
+        match successor {
+            Some(package_config) => {
+                let package_version = PackageVersion::from_config(&package_config)?;
+                if let Some(bin_path) = package_version.bins.get(bin_name) {
+                    package_version.write_bin_config(
+                        bin_name,
+                        bin_path,
+                        &package_config.platform,
+                        package_config.node_abi,
+                    )?;
+                    info!(
+                        "Executable '{}' is now provided by '{}'",
+                        bin_name, package_config.name
+                    );
+                    return Ok(());
+                }
+                PackageVersion::remove_config_and_shim(bin_name, name)
+            }
+            None => PackageVersion::remove_config_and_shim(bin_name, name),
+        }
+    }
+
+    pub(crate) fn remove_config_and_shim(bin_name: &str, name: &str) -> Fallible<()> {
         shim::delete(bin_name)?;
         let config_file = path::user_tool_bin_config(&bin_name)?;
         fs::remove_file(&config_file).with_context(delete_file_error(&config_file))?;
@@ -568,6 +1034,14 @@ fn delete_file_error(file: &PathBuf) -> impl FnOnce(&io::Error) -> ErrorDetails
     |_| ErrorDetails::DeleteFileError { file }
 }
 
+/// The changes that removing the config and shim of an executable would make.
+fn plan_remove_config_and_shim(bin_name: &str) -> Fallible<Vec<Change>> {
+    Ok(vec![
+        Change::Remove(path::shim_file(bin_name)?),
+        Change::Remove(path::user_tool_bin_config(bin_name)?),
+    ])
+}
+
 /// Reads the contents of a directory and returns a Vec containing the names of
 /// all the binaries installed by the input package.
 pub fn binaries_from_package(package: &str) -> Fallible<Vec<String>> {
@@ -586,6 +1060,111 @@ pub fn binaries_from_package(package: &str) -> Fallible<Vec<String>> {
     })
 }
 
+/// Returns the names of all the packages installed in the user toolchain.
+pub fn installed_packages() -> Fallible<Vec<String>> {
+    let package_dir = path::user_package_dir()?;
+    if !package_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = package_names_in(&package_dir)?;
+    names.sort();
+    Ok(names)
+}
+
+/// Loads the configs of every installed package, in order of name.
+pub fn installed_package_configs() -> Fallible<Vec<PackageConfig>> {
+    installed_packages()?
+        .iter()
+        .map(|name| installed_package_config(name))
+        .collect()
+}
+
+/// Loads the config of an installed package.
+pub(crate) fn installed_package_config(name: &str) -> Fallible<PackageConfig> {
+    let package_config_file = path::user_package_config_file(name)?;
+    if !package_config_file.exists() {
+        throw!(ErrorDetails::PackageNotInstalled {
+            package: name.to_string(),
+        });
+    }
+    PackageConfig::from_file(&package_config_file)
+}
+
+/// Returns the source directory of an installed package, if it was linked with `volta link`.
+pub(crate) fn linked_dir(package_config: &PackageConfig) -> Fallible<Option<PathBuf>> {
+    let image_dir =
+        path::package_image_dir(&package_config.name, &package_config.version.to_string())?;
+    match fs::symlink_metadata(&image_dir) {
+        Ok(ref metadata) if metadata.file_type().is_symlink() => Ok(fs::read_link(&image_dir).ok()),
+        _ => Ok(None),
+    }
+}
+
+/// Returns the configs of all the installed packages that provide the specified executable.
+fn packages_with_bin(bin_name: &str) -> Fallible<Vec<PackageConfig>> {
+    let mut package_configs = Vec::new();
+    for name in installed_packages()? {
+        let package_config = installed_package_config(&name)?;
+        if package_config.bins.iter().any(|bin| bin == bin_name) {
+            package_configs.push(package_config);
+        }
+    }
+    Ok(package_configs)
+}
+
+/// Returns the name of the package that currently provides the specified executable, if any.
+pub(crate) fn bin_owner(bin_name: &str) -> Fallible<Option<String>> {
+    let bin_config_file = path::user_tool_bin_config(bin_name)?;
+    if !bin_config_file.exists() {
+        return Ok(None);
+    }
+    Ok(BinConfig::from_file(bin_config_file)
+        .ok()
+        .map(|bin_config| bin_config.package))
+}
+
+/// Returns the paths to the specified executable in every installed package that provides it,
+/// starting with the package that currently provides it.
+pub fn package_bin_paths(bin_name: &str) -> Fallible<Vec<PathBuf>> {
+    let owner = bin_owner(bin_name)?;
+    let mut package_configs = packages_with_bin(bin_name)?;
+    package_configs.sort_by_key(|package_config| Some(&package_config.name) != owner.as_ref());
+
+    let mut paths = Vec::new();
+    for package_config in package_configs {
+        let package_version = PackageVersion::from_config(&package_config)?;
+        if let Some(bin_path) = package_version.bins.get(bin_name) {
+            paths.push(bin_full_path(
+                &package_config.name,
+                &package_config.version,
+                bin_name,
+                bin_path,
+            )?);
+        }
+    }
+    Ok(paths)
+}
+
+fn package_names_in(dir: &Path) -> Fallible<Vec<String>> {
+    let entries =
+        read_dir_eager(dir).with_context(|_| ErrorDetails::ReadPackageConfigDirError {
+            dir: dir.to_path_buf(),
+        })?;
+
+    let mut names = Vec::new();
+    for (entry, metadata) in entries {
+        let path = entry.path();
+        if metadata.is_dir() {
+            // the configs for scoped packages are stored in a directory for the scope
+            names.extend(package_names_in(&path)?);
+        } else if path.extension() == Some(OsStr::new("json")) {
+            names.push(PackageConfig::from_file(&path)?.name);
+        }
+    }
+    Ok(names)
+}
+
 impl Installer {
     pub fn cmd(&self) -> Command {
         match self {
@@ -648,6 +1227,8 @@ impl UserTool {
             }
         };
 
+        warn_if_rebuild_needed(&bin_config.package, &platform);
+
         Ok(UserTool {
             bin_path,
             image: platform.checkout(session)?,
@@ -666,6 +1247,20 @@ impl UserTool {
     }
 }
 
+/// Suggests running `volta rebuild` when the Node a package was installed with is no longer
+/// installed, since it will otherwise be fetched again. This only checks for the image, so that
+/// running a package's executables doesn't load the inventory.
+fn warn_if_rebuild_needed(package: &str, platform: &PlatformSpec) {
+    let runtime = &platform.node_runtime;
+    if !node::image_exists(runtime, platform.arch.as_ref().map(String::as_str)) {
+        warn!(
+            "{} uses Node {}, which is no longer installed and will be fetched again.
+To use your default Node instead, run `volta rebuild {}`",
+            package, runtime, package
+        );
+    }
+}
+
 fn bin_full_path<P>(
     package: &str,
     version: &Version,
@@ -684,6 +1279,47 @@ where
         })
 }
 
+/// The launchers that npm ships look for `npm-cli.js` and `npx-cli.js` next to the `node` that runs
+/// them, which finds the npm bundled with Node rather than this one. They are replaced with
+/// launchers that run the scripts next to them, so that `npm`, `npx`, and `npm help` all use the
+/// pinned npm when its `bin` directory is on the `PATH` (see `Image::bins`).
+fn replace_npm_launchers(image_dir: &Path) -> io::Result<()> {
+    let bin_dir = image_dir.join("bin");
+    for &name in ["npm", "npx"].iter() {
+        // npx was only included with npm 5.2.0 and higher
+        if bin_dir.join(format!("{}-cli.js", name)).is_file() {
+            write_npm_launcher(&bin_dir, name)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn write_npm_launcher(bin_dir: &Path, name: &str) -> io::Result<()> {
+    let launcher = bin_dir.join(name);
+    write(
+        &launcher,
+        format!(
+            "#!/bin/sh\nexec node \"$(dirname \"$0\")/{}-cli.js\" \"$@\"\n",
+            name
+        ),
+    )?;
+    set_executable_permissions(&launcher)
+}
+
+#[cfg(windows)]
+fn write_npm_launcher(bin_dir: &Path, name: &str) -> io::Result<()> {
+    // PowerShell runs npm's own `.ps1` launchers ahead of the `.cmd` ones
+    let powershell_launcher = bin_dir.join(format!("{}.ps1", name));
+    if powershell_launcher.exists() {
+        remove_file(powershell_launcher)?;
+    }
+    write(
+        bin_dir.join(format!("{}.cmd", name)),
+        format!("@node \"%~dp0\\{}-cli.js\" %*\r\n", name),
+    )
+}
+
 /// Ensure that a given binary has 'executable' permissions on Unix, otherwise we won't be able to call it
 /// On Windows, this isn't a concern as there is no concept of 'executable' permissions
 #[cfg(unix)]
@@ -699,47 +1335,312 @@ fn set_executable_permissions(bin: &Path) -> io::Result<()> {
     }
 }
 
-/// On Unix, shebang loaders work correctly, so we don't need to bother storing loader information
-#[cfg(unix)]
-fn determine_script_loader(_bin_name: &str, _full_path: &Path) -> Fallible<Option<BinLoader>> {
-    Ok(None)
-}
-
-/// On Windows, we need to read the executable and try to find a shebang loader
-/// If it exists, we store the loader in the BinConfig so that the shim can execute it correctly
-#[cfg(windows)]
+/// Reads the executable and tries to find a shebang loader (e.g. `#!/usr/bin/env node`)
+/// If it exists, we store the loader in the BinConfig so that the shim can invoke the interpreter
+/// from the tool's platform directly, rather than relying on the OS to interpret the shebang
 fn determine_script_loader(bin_name: &str, full_path: &Path) -> Fallible<Option<BinLoader>> {
+    // Some packages ship their binaries as native executables or as `.cmd` / `.ps1` wrappers
+    // instead of shebang scripts, so check the extension before trying to read a shebang
+    #[cfg(windows)]
+    {
+        if let Some(loader) = BinLoader::for_extension(full_path) {
+            return Ok(loader);
+        }
+    }
+
     let script =
         File::open(full_path).with_context(|_| ErrorDetails::DetermineBinaryLoaderError {
             bin: bin_name.to_string(),
         })?;
-    if let Some(Ok(first_line)) = BufReader::new(script).lines().next() {
-        // Note: Regex adapted from @zkochan/cmd-shim package used by Yarn
-        // https://github.com/pnpm/cmd-shim/blob/bac160cc554e5157e4c5f5e595af30740be3519a/index.js#L42
-        let re = Regex::new(r#"^#!\s*(?:/usr/bin/env)?\s*(?P<exe>[^ \t]+) ?(?P<args>.*)$"#)
-            .expect("Regex is valid");
-        if let Some(caps) = re.captures(&first_line) {
-            let args = caps["args"]
-                .to_string()
-                .parse_cmdline_words()
-                .map(|word| word.to_string())
-                .collect();
-            return Ok(Some(BinLoader {
-                command: caps["exe"].to_string(),
-                args,
-            }));
-        }
-    }
-    Ok(None)
+    match BufReader::new(script).lines().next() {
+        Some(Ok(first_line)) => Ok(parse_shebang(&first_line)),
+        _ => Ok(None),
+    }
+}
+
+/// Parses a shebang line into the loader needed to run the script.
+///
+/// This supports both interpreter paths (`#!/usr/bin/python3 -u`) and `env` invocations,
+/// including `env -S` for passing multiple arguments to the interpreter. Interpreter paths are
+/// reduced to their file name, so that the interpreter is looked up on the `PATH` of the tool's
+/// platform, which also makes the loader work the same way on every operating system.
+fn parse_shebang(line: &str) -> Option<BinLoader> {
+    if !line.starts_with("#!") {
+        return None;
+    }
+
+    let mut words: Vec<String> = line[2..]
+        .trim()
+        .to_string()
+        .parse_cmdline_words()
+        .map(|word| word.to_string())
+        .collect();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut command = words.remove(0);
+    let mut env = HashMap::new();
+    if interpreter_name(&command) == "env" {
+        let (env_words, assignments) = env_command_words(words);
+        words = env_words;
+        env = assignments;
+        if words.is_empty() {
+            return None;
+        }
+        command = words.remove(0);
+    }
+
+    Some(BinLoader {
+        command: interpreter_name(&command),
+        args: words.into_iter().map(OsString::from).collect(),
+        env,
+    })
+}
+
+/// Separates the arguments passed to `env` into the command with its arguments, and the
+/// variables that are assigned for it. Options are dropped, and since the whole line has
+/// already been split into words, `-S` (split string) only needs to be removed.
+fn env_command_words(args: Vec<String>) -> (Vec<String>, HashMap<String, String>) {
+    let mut args = args.into_iter();
+    let mut words = Vec::new();
+    let mut env = HashMap::new();
+
+    while let Some(arg) = args.next() {
+        // the split string may be attached to the option, e.g. `-Snode`
+        let arg = if arg.starts_with("--split-string=") {
+            arg["--split-string=".len()..].to_string()
+        } else if arg.starts_with("-S") && arg != "-S" {
+            arg[2..].to_string()
+        } else {
+            arg
+        };
+
+        if arg == "-u" || arg == "--unset" || arg == "-C" || arg == "--chdir" {
+            // these options take a value as the following word
+            args.next();
+        } else if arg.is_empty() || arg.starts_with('-') {
+            continue;
+        } else if let Some(index) = arg.find('=') {
+            env.insert(arg[..index].to_string(), arg[index + 1..].to_string());
+        } else {
+            words.push(arg);
+            break;
+        }
+    }
+
+    words.extend(args);
+    (words, env)
+}
+
+/// Reduces an interpreter path (e.g. `/usr/local/bin/node`) to the name of the interpreter
+fn interpreter_name(command: &str) -> String {
+    Path::new(command)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| command.to_string())
+}
+
+#[cfg(windows)]
+impl BinLoader {
+    /// Determines how to launch a binary on Windows based on its file extension, mirroring the
+    /// variants that npm's own shims produce:
+    ///
+    /// - `Some(None)` for `.exe`, `.cmd`, and `.bat` files, which `cmd.exe /C` runs directly
+    /// - `Some(Some(loader))` for `.ps1` files, which need to be run by PowerShell
+    /// - `None` if the extension doesn't tell us anything, e.g. for shebang scripts
+    pub fn for_extension(path: &Path) -> Option<Option<BinLoader>> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "exe" | "cmd" | "bat" => Some(None),
+            "ps1" => Some(Some(BinLoader {
+                command: "powershell".to_string(),
+                args: vec![
+                    OsString::from("-NoProfile"),
+                    OsString::from("-ExecutionPolicy"),
+                    OsString::from("Bypass"),
+                    OsString::from("-File"),
+                ],
+                env: HashMap::new(),
+            })),
+            _ => None,
+        }
+    }
 }
 
 /// Build a package install command using the specified directory and path
-fn install_command_for(installer: Installer, in_dir: &OsStr, path_var: &OsStr) -> Command {
+fn install_command_for(
+    installer: Installer,
+    in_dir: &OsStr,
+    path_var: &OsStr,
+    ignore_scripts: bool,
+) -> Command {
     let mut command = installer.cmd();
     command.current_dir(in_dir).env("PATH", path_var);
+    if ignore_scripts {
+        // both npm and yarn support this flag
+        command.arg("--ignore-scripts");
+    }
     command
 }
 
+/// Downloads the file at `url` to `file`, overwriting anything already there.
+fn download_file(
+    url: &str,
+    file: &Path,
+    authorization: Option<&str>,
+    details: &str,
+) -> Result<(), failure::Error> {
+    let response = registry_get(url, authorization)?;
+    let bar = progress_bar(
+        Origin::Remote,
+        details,
+        response.content_length().unwrap_or(0),
+    );
+    let mut response = Throttle::new(response);
+    let mut file = File::create(file)?;
+    let mut buffer = [0; 8192];
+
+    loop {
+        let read = response.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        bar.inc(read as u64);
+    }
+
+    bar.finish_and_clear();
+    Ok(())
+}
+
+/// Sends a GET request to a package registry, sending the `Authorization` header if there is
+/// one. If the registry rate-limits the request, it is retried after the delay the registry asks
+/// for with `Retry-After`, or otherwise after a jittered exponential backoff.
+pub(crate) fn registry_get(
+    url: &str,
+    authorization: Option<&str>,
+) -> reqwest::Result<reqwest::Response> {
+    let _guard = if flag_enabled(SERIAL_REGISTRY) {
+        Some(REGISTRY_LOCK.lock().unwrap_or_else(PoisonError::into_inner))
+    } else {
+        None
+    };
+
+    let client = archive::client()?;
+    let mut attempt = 0;
+
+    loop {
+        let mut request = client.get(url);
+        if let Some(authorization) = authorization {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+        let response = request.send()?;
+
+        if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS
+            || attempt >= MAX_REGISTRY_RETRIES
+        {
+            return response.error_for_status();
+        }
+
+        let delay = retry_delay(
+            response.headers().get_011::<RetryAfter>(),
+            attempt,
+            SystemTime::now(),
+        );
+        debug!(
+            "Registry rate-limited the request for {}, retrying in {}ms",
+            url,
+            delay.as_millis()
+        );
+        thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// Determines how long to wait before retrying a rate-limited request, preferring the registry's
+/// `Retry-After` header to Volta's own backoff.
+fn retry_delay(retry_after: Option<RetryAfter>, attempt: u32, now: SystemTime) -> Duration {
+    let delay = match retry_after {
+        Some(RetryAfter::Delay(delay)) => delay,
+        Some(RetryAfter::DateTime(date)) => SystemTime::from(date)
+            .duration_since(now)
+            .unwrap_or_default(),
+        None => backoff(attempt, jitter()),
+    };
+
+    delay.min(MAX_RETRY_DELAY)
+}
+
+/// The exponential backoff for the given attempt, starting at one second. Only `jitter` (between
+/// 0 and 1) of the second half of each delay is used, so that clients that were rate-limited
+/// together don't all retry together.
+fn backoff(attempt: u32, jitter: f64) -> Duration {
+    let ceiling = Duration::from_secs(1 << attempt.min(6)).min(MAX_RETRY_DELAY);
+    let half = ceiling / 2;
+    half + Duration::from_millis((half.as_millis() as f64 * jitter) as u64)
+}
+
+/// A number between 0 and 1 that is different enough between processes and attempts to spread
+/// retries out, which is all the randomness backoff needs.
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.subsec_nanos())
+        .unwrap_or(0);
+    f64::from(nanos % 1000) / 1000.0
+}
+
+/// Calculates the hex-encoded SHA-1 digest of `contents`, which is the format of the registry's
+/// `shasum` field.
+fn sha1_hex(contents: &[u8]) -> String {
+    hex::encode(&Sha1::digest(contents))
+}
+
+/// Checks `contents` against the registry's `shasum` and, if there is one, its `integrity`
+/// string.
+fn verify_checksums(contents: &[u8], shasum: &str, integrity: Option<&str>) -> bool {
+    if sha1_hex(contents) != shasum.to_lowercase() {
+        return false;
+    }
+
+    match integrity {
+        Some(integrity) => matches_integrity(contents, integrity),
+        None => true,
+    }
+}
+
+/// Checks `contents` against a Subresource Integrity string (`<algorithm>-<base64 digest>`,
+/// possibly several separated by whitespace). Any one of the hashes using an algorithm we
+/// recognize matching is enough; hashes using other algorithms are ignored, so if none are
+/// recognized the check fails, rather than letting an integrity string we can't check through.
+fn matches_integrity(contents: &[u8], integrity: &str) -> bool {
+    for hash in integrity.split_whitespace() {
+        let mut parts = hash.splitn(2, '-');
+        let (algorithm, expected) = match (parts.next(), parts.next()) {
+            (Some(algorithm), Some(expected)) => (algorithm, expected),
+            _ => continue,
+        };
+        // Ignore any options following the digest
+        let expected = expected.split('?').next().unwrap_or("");
+
+        let actual = match algorithm {
+            "sha512" => Sha512::digest(contents).to_vec(),
+            "sha384" => Sha384::digest(contents).to_vec(),
+            "sha256" => Sha256::digest(contents).to_vec(),
+            "sha1" => Sha1::digest(contents).to_vec(),
+            _ => continue,
+        };
+
+        if base64::decode(expected).ok().as_ref() == Some(&actual) {
+            return true;
+        }
+    }
+
+    false
+}
+
 /// Index of versions of a specific package.
 pub struct PackageIndex {
     pub latest: Version,
@@ -751,4 +1652,262 @@ pub struct PackageEntry {
     pub version: Version,
     pub tarball: String,
     pub shasum: String,
+    pub integrity: Option<String>,
+    /// The `Authorization` header to send when downloading the tarball, if any
+    pub authorization: Option<String>,
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{
+        backoff, matches_integrity, parse_shebang, replace_npm_launchers, retry_delay,
+        verify_checksums, BinLoader, ScriptPolicy, MAX_RETRY_DELAY,
+    };
+    use crate::env::SCRIPT_POLICY;
+    use reqwest::hyper_011::header::{HttpDate, RetryAfter};
+    use std::collections::HashMap;
+    use std::env;
+    use std::ffi::OsString;
+    use std::time::{Duration, SystemTime};
+
+    const HELLO_SHA1: &str = "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d";
+    const HELLO_SHA512: &str = "sha512-m3HSJL1i83hdltRq0+o9czGb+8KJDKra4t/3JRlnPKcjI8PZm6XBHXx6zG4UuMXaDEZjR1wuXDre9G9zvN7AQw==";
+    const HELLO_SHA256: &str = "sha256-LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+    const GOODBYE_SHA512: &str = "sha512-3iwDIM3/NycQSd+oy4Nf/VQgAhYlOh37rXWhrlG9MLtJnhTjf+mTui6le4Y/xWME3pQHPYgMnBjrCkac3iEdAg==";
+
+    fn loader(command: &str, args: &[&str]) -> Option<BinLoader> {
+        Some(BinLoader {
+            command: command.to_string(),
+            args: args.iter().map(OsString::from).collect(),
+            env: HashMap::new(),
+        })
+    }
+
+    fn loader_with_env(command: &str, env: &[(&str, &str)]) -> Option<BinLoader> {
+        Some(BinLoader {
+            command: command.to_string(),
+            args: Vec::new(),
+            env: env
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn parses_env_shebangs() {
+        assert_eq!(parse_shebang("#!/usr/bin/env node"), loader("node", &[]));
+        assert_eq!(
+            parse_shebang("#!/usr/bin/env python3"),
+            loader("python3", &[])
+        );
+        assert_eq!(
+            parse_shebang("#! /usr/bin/env node --harmony"),
+            loader("node", &["--harmony"])
+        );
+    }
+
+    #[test]
+    fn parses_env_split_strings() {
+        assert_eq!(
+            parse_shebang("#!/usr/bin/env -S node --experimental-modules --no-warnings"),
+            loader("node", &["--experimental-modules", "--no-warnings"])
+        );
+        assert_eq!(
+            parse_shebang("#!/usr/bin/env -Snode --inspect"),
+            loader("node", &["--inspect"])
+        );
+        assert_eq!(
+            parse_shebang("#!/usr/bin/env -u HOME -S node"),
+            loader("node", &[])
+        );
+    }
+
+    #[test]
+    fn keeps_env_assignments() {
+        assert_eq!(
+            parse_shebang("#!/usr/bin/env -S NODE_ENV=production node"),
+            loader_with_env("node", &[("NODE_ENV", "production")])
+        );
+        assert_eq!(
+            parse_shebang("#!/usr/bin/env -SDEBUG=* FORCE_COLOR= node"),
+            loader_with_env("node", &[("DEBUG", "*"), ("FORCE_COLOR", "")])
+        );
+        assert_eq!(
+            parse_shebang("#!/usr/bin/env NODE_OPTIONS=--max-old-space-size=4096 node"),
+            loader_with_env("node", &[("NODE_OPTIONS", "--max-old-space-size=4096")])
+        );
+    }
+
+    #[test]
+    fn reduces_interpreter_paths() {
+        assert_eq!(parse_shebang("#!/usr/local/bin/node"), loader("node", &[]));
+        assert_eq!(
+            parse_shebang("#!/usr/bin/python3 -u"),
+            loader("python3", &["-u"])
+        );
+    }
+
+    #[test]
+    fn ignores_non_shebangs() {
+        assert_eq!(parse_shebang("'use strict';"), None);
+        assert_eq!(parse_shebang("#!"), None);
+        assert_eq!(parse_shebang("#!/usr/bin/env"), None);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn picks_loaders_by_extension() {
+        use std::path::Path;
+
+        assert_eq!(
+            BinLoader::for_extension(Path::new("bin\\tsc.exe")),
+            Some(None)
+        );
+        assert_eq!(
+            BinLoader::for_extension(Path::new("bin\\tsc.CMD")),
+            Some(None)
+        );
+        assert_eq!(
+            BinLoader::for_extension(Path::new("bin\\tsc.bat")),
+            Some(None)
+        );
+        assert_eq!(
+            BinLoader::for_extension(Path::new("bin\\tsc.ps1")),
+            Some(loader(
+                "powershell",
+                &["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"]
+            ))
+        );
+        assert_eq!(BinLoader::for_extension(Path::new("bin\\tsc.js")), None);
+        assert_eq!(BinLoader::for_extension(Path::new("bin\\tsc")), None);
+    }
+
+    #[test]
+    fn verifies_shasum() {
+        assert!(verify_checksums(b"hello", HELLO_SHA1, None));
+        assert!(verify_checksums(b"hello", &HELLO_SHA1.to_uppercase(), None));
+        assert!(!verify_checksums(b"goodbye", HELLO_SHA1, None));
+    }
+
+    #[test]
+    fn verifies_integrity() {
+        assert!(verify_checksums(b"hello", HELLO_SHA1, Some(HELLO_SHA512)));
+        assert!(!verify_checksums(
+            b"hello",
+            HELLO_SHA1,
+            Some(GOODBYE_SHA512)
+        ));
+    }
+
+    #[test]
+    fn matches_any_recognized_integrity_hash() {
+        assert!(matches_integrity(b"hello", HELLO_SHA256));
+        assert!(matches_integrity(
+            b"hello",
+            &format!("{} {}", GOODBYE_SHA512, HELLO_SHA256)
+        ));
+        assert!(matches_integrity(
+            b"hello",
+            &format!("{}?some-option", HELLO_SHA512)
+        ));
+        assert!(!matches_integrity(b"hello", "sha512-not-base64"));
+    }
+
+    #[test]
+    fn rejects_unrecognized_integrity_hashes() {
+        assert!(!matches_integrity(b"hello", "md5-XUFAKrxLKna5cZ2REBfFkg=="));
+        assert!(!matches_integrity(b"hello", ""));
+        assert!(!matches_integrity(
+            b"hello",
+            &format!("md5-XUFAKrxLKna5cZ2REBfFkg== {}", GOODBYE_SHA512)
+        ));
+        assert!(matches_integrity(
+            b"hello",
+            &format!("md5-XUFAKrxLKna5cZ2REBfFkg== {}", HELLO_SHA512)
+        ));
+    }
+
+    #[test]
+    fn reads_script_policy_from_env() {
+        env::remove_var(SCRIPT_POLICY);
+        assert_eq!(ScriptPolicy::from_env().unwrap(), ScriptPolicy::Allow);
+
+        env::set_var(SCRIPT_POLICY, "deny");
+        assert_eq!(ScriptPolicy::from_env().unwrap(), ScriptPolicy::Deny);
+
+        env::set_var(SCRIPT_POLICY, "prompt");
+        assert_eq!(ScriptPolicy::from_env().unwrap(), ScriptPolicy::Prompt);
+
+        env::set_var(SCRIPT_POLICY, "sometimes");
+        assert!(ScriptPolicy::from_env().is_err());
+
+        env::remove_var(SCRIPT_POLICY);
+    }
+
+    #[test]
+    fn backs_off_exponentially_with_jitter() {
+        assert_eq!(backoff(0, 0.0), Duration::from_millis(500));
+        assert_eq!(backoff(0, 1.0), Duration::from_secs(1));
+        assert_eq!(backoff(3, 0.0), Duration::from_secs(4));
+        assert_eq!(backoff(3, 0.5), Duration::from_secs(6));
+        assert_eq!(backoff(20, 1.0), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn honors_retry_after() {
+        let now = SystemTime::now();
+
+        assert_eq!(
+            retry_delay(Some(RetryAfter::Delay(Duration::from_secs(7))), 0, now),
+            Duration::from_secs(7)
+        );
+        assert_eq!(
+            retry_delay(Some(RetryAfter::Delay(Duration::from_secs(3600))), 0, now),
+            MAX_RETRY_DELAY
+        );
+
+        let later = HttpDate::from(now + Duration::from_secs(30));
+        let delay = retry_delay(Some(RetryAfter::DateTime(later)), 0, now);
+        // HTTP dates only have a precision of one second
+        assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30));
+
+        let earlier = HttpDate::from(now - Duration::from_secs(30));
+        assert_eq!(
+            retry_delay(Some(RetryAfter::DateTime(earlier)), 0, now),
+            Duration::from_secs(0)
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn replaces_npm_launchers() {
+        use std::fs::{create_dir_all, read_to_string, write};
+        use std::os::unix::fs::PermissionsExt;
+        use tempfile::tempdir;
+
+        let image = tempdir().unwrap();
+        let bin_dir = image.path().join("bin");
+        create_dir_all(&bin_dir).unwrap();
+        write(bin_dir.join("npm-cli.js"), "").unwrap();
+        write(
+            bin_dir.join("npm"),
+            "NPM_CLI_JS=\"$CLI_BASEDIR/node_modules/npm/bin/npm-cli.js\"",
+        )
+        .unwrap();
+
+        replace_npm_launchers(image.path()).unwrap();
+
+        let launcher = bin_dir.join("npm");
+        assert_eq!(
+            read_to_string(&launcher).unwrap(),
+            "#!/bin/sh\nexec node \"$(dirname \"$0\")/npm-cli.js\" \"$@\"\n"
+        );
+        assert_eq!(
+            launcher.metadata().unwrap().permissions().mode() & 0o111,
+            0o111
+        );
+        // npm versions before 5.2.0 don't include npx
+        assert!(!bin_dir.join("npx").exists());
+    }
 }
@@ -0,0 +1,350 @@
+//! Provides the `PackageSource` type, which represents a package installed directly from a git
+//! repository or a local path rather than from the package registry.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, create_dir_all, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::debug;
+use semver::Version;
+use tempfile::tempdir_in;
+
+use super::package::{find_unpack_dir, generate_bin_map, PackageVersion};
+use crate::command::create_command;
+use crate::error::ErrorDetails;
+use crate::fs::{ensure_containing_dir_exists, ensure_dir_does_not_exist, rename};
+use crate::manifest::serial;
+use crate::path;
+use crate::style::{progress_spinner, tool_version};
+use archive::Tarball;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// Directories that are left out when copying a package from a local directory.
+const SKIPPED_DIRS: [&str; 2] = [".git", "node_modules"];
+
+/// A location that a package can be installed from, other than the package registry.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub enum PackageSource {
+    /// A git repository, with an optional commit, branch, or tag to check out.
+    Git {
+        url: String,
+        committish: Option<String>,
+    },
+    /// A package directory on the local filesystem.
+    Directory(PathBuf),
+    /// A package tarball on the local filesystem.
+    Tarball(PathBuf),
+}
+
+impl PackageSource {
+    /// Recognizes git URLs (`git+https://...`, `git://...`, or `github:owner/repo`, each
+    /// optionally followed by `#<commit-ish>`) and local paths (`./dir`, `/path/to/package.tgz`,
+    /// or `file:...`). Returns `None` for anything else, which should be treated as the name of
+    /// a package in the registry.
+    pub fn parse(spec: &str) -> Option<Self> {
+        if spec.starts_with("github:") {
+            let (repo, committish) = split_committish(&spec["github:".len()..]);
+            return Some(PackageSource::Git {
+                url: format!("https://github.com/{}.git", repo),
+                committish,
+            });
+        }
+
+        if spec.starts_with("git+") || spec.starts_with("git://") {
+            let (url, committish) = split_committish(spec);
+            let url = if url.starts_with("git+") {
+                &url["git+".len()..]
+            } else {
+                url
+            };
+            return Some(PackageSource::Git {
+                url: url.to_string(),
+                committish,
+            });
+        }
+
+        let path = if spec.starts_with("file:") {
+            &spec["file:".len()..]
+        } else if spec.starts_with('.') || Path::new(spec).is_absolute() {
+            spec
+        } else {
+            return None;
+        };
+
+        if path.ends_with(".tgz") || path.ends_with(".tar.gz") {
+            Some(PackageSource::Tarball(PathBuf::from(path)))
+        } else {
+            Some(PackageSource::Directory(PathBuf::from(path)))
+        }
+    }
+
+    /// Returns a spec that parses back into this source. Local paths are made absolute, so the
+    /// spec can be used again from any directory.
+    pub fn to_spec(&self) -> String {
+        match self {
+            PackageSource::Git {
+                url,
+                committish: Some(committish),
+            } => format!("git+{}#{}", url, committish),
+            PackageSource::Git {
+                url,
+                committish: None,
+            } => format!("git+{}", url),
+            PackageSource::Directory(path) | PackageSource::Tarball(path) => {
+                let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+                format!("file:{}", path.display())
+            }
+        }
+    }
+
+    /// Clones or unpacks the package as needed, then moves it into its image directory.
+    pub fn fetch(&self) -> Fallible<PackageVersion> {
+        path::ensure_writable_home()?;
+        let tmp_root = path::tmp_dir()?;
+        let temp = tempdir_in(&tmp_root)
+            .with_context(|_| ErrorDetails::CreateTempDirError { in_dir: tmp_root })?;
+        let staging_dir = temp.path().join("package");
+
+        match self {
+            PackageSource::Git { url, committish } => {
+                clone_repository(url, committish.as_ref(), &staging_dir)?
+            }
+            PackageSource::Directory(dir) => {
+                debug!("Copying package from {}", dir.display());
+                copy_dir(dir, &staging_dir).with_context(self.read_error())?;
+            }
+            PackageSource::Tarball(file) => {
+                debug!("Unpacking package from {}", file.display());
+                let unpack_dir = temp.path().join("unpack");
+                File::open(file)
+                    .map_err(failure::Error::from)
+                    .and_then(Tarball::load)
+                    .and_then(|archive| archive.unpack(&unpack_dir, &mut |_, _| ()))
+                    .with_context(self.read_error())?;
+                rename(find_unpack_dir(&unpack_dir)?, &staging_dir)
+                    .with_context(self.read_error())?;
+            }
+        }
+
+        let (name, version) = self.read_name_and_version(&staging_dir)?;
+        let image_dir = path::package_image_dir(&name, &version.to_string())?;
+
+        // the contents of a source can change without its version changing, so any existing
+        // image of the same version is always replaced
+        ensure_containing_dir_exists(&image_dir)?;
+        ensure_dir_does_not_exist(&image_dir)?;
+        rename(&staging_dir, &image_dir).with_context(|_| ErrorDetails::SetupToolImageError {
+            tool: name.clone(),
+            version: version.to_string(),
+            dir: image_dir.clone(),
+        })?;
+        debug!(
+            "Installing {} from {} in {}",
+            tool_version(&name, &version),
+            self,
+            image_dir.display()
+        );
+
+        let bins = generate_bin_map(&image_dir)?;
+        PackageVersion::new(name, version, bins)
+    }
+
+    pub(crate) fn read_name_and_version(&self, dir: &Path) -> Fallible<(String, Version)> {
+        let package_file = dir.join("package.json");
+        let file = File::open(&package_file).with_context(|_| ErrorDetails::PackageReadError {
+            file: package_file.clone(),
+        })?;
+        let manifest: serial::Manifest = serde_json::de::from_reader(file)
+            .with_context(|_| ErrorDetails::PackageParseError { file: package_file })?;
+
+        let version = manifest
+            .version
+            .and_then(|version| Version::parse(&version).ok());
+        match (manifest.name, version) {
+            (Some(name), Some(version)) => Ok((name, version)),
+            _ => throw!(ErrorDetails::PackageSourceManifestError {
+                source: self.to_string(),
+            }),
+        }
+    }
+
+    fn read_error<E>(&self) -> impl FnOnce(&E) -> ErrorDetails {
+        let source = self.to_string();
+        |_| ErrorDetails::ReadPackageSourceError { source }
+    }
+}
+
+impl Display for PackageSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            PackageSource::Git {
+                url,
+                committish: Some(committish),
+            } => write!(f, "{}#{}", url, committish),
+            PackageSource::Git {
+                url,
+                committish: None,
+            } => f.write_str(url),
+            PackageSource::Directory(path) | PackageSource::Tarball(path) => {
+                write!(f, "{}", path.display())
+            }
+        }
+    }
+}
+
+fn split_committish(spec: &str) -> (&str, Option<String>) {
+    let mut parts = spec.splitn(2, '#');
+    let url = parts.next().unwrap_or(spec);
+    let committish = parts
+        .next()
+        .filter(|committish| !committish.is_empty())
+        .map(|committish| committish.to_string());
+    (url, committish)
+}
+
+fn clone_repository(url: &str, committish: Option<&String>, dest: &Path) -> Fallible<()> {
+    let spinner = progress_spinner(&format!("Cloning {}", url));
+
+    let mut clone = create_command("git");
+    clone.arg("clone").arg("--quiet").arg(url).arg(dest);
+    if !run_git(clone) {
+        spinner.finish_and_clear();
+        throw!(ErrorDetails::GitCloneError {
+            url: url.to_string()
+        });
+    }
+
+    if let Some(committish) = committish {
+        let mut checkout = create_command("git");
+        checkout
+            .arg("checkout")
+            .arg("--quiet")
+            .arg(committish)
+            .current_dir(dest);
+        if !run_git(checkout) {
+            spinner.finish_and_clear();
+            throw!(ErrorDetails::GitCheckoutError {
+                url: url.to_string(),
+                committish: committish.to_string(),
+            });
+        }
+    }
+
+    spinner.finish_and_clear();
+
+    // the repository history isn't part of the package
+    ensure_dir_does_not_exist(&dest.join(".git"))
+}
+
+/// Runs a git command, returning whether it succeeded.
+fn run_git(mut command: Command) -> bool {
+    debug!("Running {:?}", command);
+    match command.output() {
+        Ok(output) => {
+            debug!("[git stderr]\n{}", String::from_utf8_lossy(&output.stderr));
+            output.status.success()
+        }
+        Err(error) => {
+            debug!("Could not run git: {}", error);
+            false
+        }
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let dest = to.join(&file_name);
+
+        if entry.file_type()?.is_dir() {
+            if !SKIPPED_DIRS.iter().any(|skipped| file_name == *skipped) {
+                copy_dir(&entry.path(), &dest)?;
+            }
+        } else {
+            fs::copy(entry.path(), dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::PackageSource;
+    use std::path::PathBuf;
+
+    fn git(url: &str, committish: Option<&str>) -> Option<PackageSource> {
+        Some(PackageSource::Git {
+            url: url.to_string(),
+            committish: committish.map(|committish| committish.to_string()),
+        })
+    }
+
+    #[test]
+    fn parses_git_urls() {
+        assert_eq!(
+            PackageSource::parse("git+https://example.com/org/cli.git#v1.2.3"),
+            git("https://example.com/org/cli.git", Some("v1.2.3"))
+        );
+        assert_eq!(
+            PackageSource::parse("git+ssh://git@example.com/org/cli.git"),
+            git("ssh://git@example.com/org/cli.git", None)
+        );
+        assert_eq!(
+            PackageSource::parse("git://example.com/org/cli.git#main"),
+            git("git://example.com/org/cli.git", Some("main"))
+        );
+    }
+
+    #[test]
+    fn parses_github_shorthand() {
+        assert_eq!(
+            PackageSource::parse("github:org/cli"),
+            git("https://github.com/org/cli.git", None)
+        );
+        assert_eq!(
+            PackageSource::parse("github:org/cli#abc1234"),
+            git("https://github.com/org/cli.git", Some("abc1234"))
+        );
+    }
+
+    #[test]
+    fn parses_local_paths() {
+        assert_eq!(
+            PackageSource::parse("./tools/cli"),
+            Some(PackageSource::Directory(PathBuf::from("./tools/cli")))
+        );
+        assert_eq!(
+            PackageSource::parse("../cli-1.0.0.tgz"),
+            Some(PackageSource::Tarball(PathBuf::from("../cli-1.0.0.tgz")))
+        );
+        assert_eq!(
+            PackageSource::parse("file:cli.tar.gz"),
+            Some(PackageSource::Tarball(PathBuf::from("cli.tar.gz")))
+        );
+    }
+
+    #[test]
+    fn round_trips_git_specs() {
+        for spec in &[
+            "github:org/cli#v1.2.3",
+            "git+ssh://git@example.com/org/cli.git",
+            "git://example.com/org/cli.git#main",
+        ] {
+            let source = PackageSource::parse(spec).unwrap();
+            assert_eq!(PackageSource::parse(&source.to_spec()), Some(source));
+        }
+    }
+
+    #[test]
+    fn ignores_registry_packages() {
+        assert_eq!(PackageSource::parse("cowsay"), None);
+        assert_eq!(PackageSource::parse("@myorg/cli@1.2.3"), None);
+        assert_eq!(PackageSource::parse("typescript@next"), None);
+    }
+}
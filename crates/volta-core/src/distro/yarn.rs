@@ -1,45 +1,115 @@
 //! Provides the `YarnDistro` type, which represents a provisioned Yarn distribution.
 
-use std::fs::{rename, File};
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::string::ToString;
 
 use log::debug;
 use semver::Version;
-use tempfile::tempdir_in;
 
 use archive::{Archive, Tarball};
-use volta_fail::{Fallible, ResultExt};
+use volta_fail::{throw, Fallible, ResultExt};
 
-use super::{download_tool_error, Distro, Fetched};
+use super::{
+    download_tool_error, install_image, limit_download_rate, prune_archives, unpack_archive_error,
+    Distro, Fetched,
+};
+use crate::checksum::{self, ArchiveHash, ArchiveHashes};
 use crate::error::ErrorDetails;
-use crate::fs::ensure_containing_dir_exists;
+use crate::fs::{create_staging_dir, ensure_containing_dir_exists};
+use crate::hook::tool::IndexFormat;
 use crate::hook::ToolHooks;
-use crate::inventory::YarnCollection;
+use crate::inventory::{serial, YarnCollection};
 use crate::path;
+use crate::permissions;
 use crate::style::{progress_bar, tool_version};
 use crate::tool::ToolSpec;
 use crate::version::VersionSpec;
 
 #[cfg(feature = "mock-network")]
-use mockito;
+use crate::env::mock_server_url;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "mock-network")] {
         fn public_yarn_server_root() -> String {
-            mockito::SERVER_URL.to_string()
+            mock_server_url()
+        }
+        fn public_yarn_berry_server_root() -> String {
+            format!("{}/registry", mock_server_url())
         }
     } else {
         fn public_yarn_server_root() -> String {
             "https://github.com/yarnpkg/yarn/releases/download".to_string()
         }
+        fn public_yarn_berry_server_root() -> String {
+            "https://registry.npmjs.org".to_string()
+        }
+    }
+}
+
+/// The npm package that Yarn 2+ ("Berry") releases are published as. Unlike Yarn 1, these are
+/// not attached to GitHub releases as tarballs.
+pub const YARN_BERRY_PACKAGE: &'static str = "@yarnpkg/cli-dist";
+
+/// Determines whether a Yarn version is part of the Yarn 2+ ("Berry") line.
+pub fn is_berry(version: &Version) -> bool {
+    version.major >= 2
+}
+
+/// Determines the root directory inside an unpacked Yarn archive. The Berry releases are npm
+/// package tarballs, which unpack into a `package` directory, as do Yarn 1 tarballs fetched
+/// from an npm-style mirror.
+fn archive_root_dir(unpack_dir: &Path, version: &Version) -> PathBuf {
+    let release_dir = unpack_dir.join(path::yarn_archive_root_dir_name(&version.to_string()));
+    if is_berry(version) || !release_dir.is_dir() {
+        unpack_dir.join("package")
+    } else {
+        release_dir
+    }
+}
+
+/// Parses the listing returned by a Yarn index URL into the set of available Yarn versions,
+/// according to the format of the index.
+pub fn parse_index(format: IndexFormat, text: &str, from_url: &str) -> Fallible<BTreeSet<Version>> {
+    let parse_error = |_: &serde_json::Error| ErrorDetails::ParseYarnIndexError {
+        from_url: from_url.to_string(),
+        format: format.to_string(),
+    };
+
+    match format {
+        IndexFormat::Github => {
+            let releases: serial::YarnIndex =
+                serde_json::de::from_str(text).with_context(parse_error)?;
+            Ok(releases.into_index()?.entries)
+        }
+        IndexFormat::Npm => {
+            let metadata: serial::PackageMetadata =
+                serde_json::de::from_str(text).with_context(parse_error)?;
+            Ok(metadata
+                .versions
+                .into_iter()
+                .map(|(_, info)| info.version)
+                .collect())
+        }
+        IndexFormat::SimpleJson => {
+            let versions: Vec<String> = serde_json::de::from_str(text).with_context(parse_error)?;
+            versions
+                .into_iter()
+                .map(|version| VersionSpec::parse_version(version))
+                .collect()
+        }
     }
 }
 
 /// A provisioned Yarn distribution.
 pub struct YarnDistro {
     archive: Box<dyn Archive>,
+    /// The file that the archive is cached in, which is complete once it has been unpacked.
+    file: PathBuf,
     version: Version,
+    /// The SHA-256s that the project pins for the archives of this version, by file name.
+    sha256: ArchiveHashes,
 }
 
 /// Return the archive if it is valid. It may have been corrupted or interrupted in the middle of
@@ -56,15 +126,26 @@ fn load_cached_distro(file: &PathBuf) -> Option<Box<dyn Archive>> {
 
 impl YarnDistro {
     /// Provision a Yarn distribution from the public distributor (`https://yarnpkg.com`).
+    ///
+    /// Yarn 2+ releases are provisioned from the public npm registry instead.
     fn public(version: Version) -> Fallible<Self> {
         let version_str = version.to_string();
-        let distro_file_name = path::yarn_distro_file_name(&version_str);
-        let url = format!(
-            "{}/v{}/{}",
-            public_yarn_server_root(),
-            version_str,
-            distro_file_name
-        );
+        let url = if is_berry(&version) {
+            format!(
+                "{}/{}/-/cli-dist-{}.tgz",
+                public_yarn_berry_server_root(),
+                YARN_BERRY_PACKAGE,
+                version_str
+            )
+        } else {
+            let distro_file_name = path::yarn_distro_file_name(&version_str);
+            format!(
+                "{}/v{}/{}",
+                public_yarn_server_root(),
+                version_str,
+                distro_file_name
+            )
+        };
         YarnDistro::remote(version, &url)
     }
 
@@ -79,10 +160,16 @@ impl YarnDistro {
                 version,
                 distro_file.display()
             );
-            return Ok(YarnDistro { archive, version });
+            return Ok(YarnDistro {
+                archive,
+                file: distro_file,
+                version,
+                sha256: ArchiveHashes::new(),
+            });
         }
 
         ensure_containing_dir_exists(&distro_file)?;
+        limit_download_rate()?;
         debug!("Downloading yarn@{} from {}", version, url);
 
         Ok(YarnDistro {
@@ -90,9 +177,58 @@ impl YarnDistro {
                 ToolSpec::Yarn(VersionSpec::exact(&version)),
                 url,
             ))?,
+            file: distro_file,
             version: version,
+            sha256: ArchiveHashes::new(),
         })
     }
+
+    /// Requires the archive to match the SHA-256 that a project pins for its file name, if any.
+    pub fn with_sha256(self, sha256: &ArchiveHashes) -> Self {
+        YarnDistro {
+            sha256: sha256.clone(),
+            ..self
+        }
+    }
+}
+
+/// Finds the SHA-256 of the archive that a fetched version of Yarn was installed from, so that a
+/// project can pin it. Images installed by older versions of Volta didn't record it, so for those
+/// the archive is hashed instead, if it is still in the inventory.
+pub fn archive_sha256(version: &Version) -> Fallible<ArchiveHash> {
+    let version_string = version.to_string();
+    let image = path::yarn_image_dir(&version_string)?;
+    if let Some(hash) = checksum::read_archive_hash(&image)? {
+        return Ok(hash);
+    }
+
+    let file_name = path::yarn_distro_file_name(&version_string);
+    let file = path::yarn_inventory_dir()?.join(&file_name);
+    if file.is_file() {
+        return Ok(ArchiveHash {
+            sha256: checksum::sha256_file(&file)?,
+            file_name,
+        });
+    }
+
+    throw!(ErrorDetails::ArchiveHashUnknown {
+        tool: tool_version("yarn", version),
+        image,
+    })
+}
+
+/// Checks that a fetched version of Yarn was installed from an archive that matches the SHA-256
+/// that a project pins for it, if any.
+pub fn check_fetched_archive(version: &Version, sha256: &ArchiveHashes) -> Fallible<()> {
+    if sha256.is_empty() {
+        return Ok(());
+    }
+
+    checksum::check_installed_archive(
+        &path::yarn_image_dir(&version.to_string())?,
+        sha256,
+        &tool_version("yarn", version),
+    )
 }
 
 impl Distro for YarnDistro {
@@ -135,9 +271,8 @@ impl Distro for YarnDistro {
             return Ok(Fetched::Already(self.version));
         }
 
-        let tmp_root = path::tmp_dir()?;
-        let temp = tempdir_in(&tmp_root)
-            .with_context(|_| ErrorDetails::CreateTempDirError { in_dir: tmp_root })?;
+        path::ensure_writable_home()?;
+        let temp = create_staging_dir()?;
         debug!("Unpacking yarn into {}", temp.path().display());
 
         let bar = progress_bar(
@@ -153,25 +288,33 @@ impl Distro for YarnDistro {
             .unpack(temp.path(), &mut |_, read| {
                 bar.inc(read as u64);
             })
-            .with_context(|_| ErrorDetails::UnpackArchiveError {
-                tool: String::from("Yarn"),
-                version: version_string.clone(),
-            })?;
+            .with_context(unpack_archive_error("Yarn", version_string.as_str()))?;
+
+        // The archive is only complete once it has been unpacked, so it is checked before the
+        // image is installed rather than before unpacking
+        let archive_hash = checksum::check_pinned_archive(
+            &self.file,
+            &self.sha256,
+            &tool_version("yarn", &self.version),
+        )?;
 
         let dest = path::yarn_image_dir(&version_string)?;
 
         ensure_containing_dir_exists(&dest)?;
 
-        rename(
-            temp.path()
-                .join(path::yarn_archive_root_dir_name(&version_string)),
-            &dest,
-        )
-        .with_context(|_| ErrorDetails::SetupToolImageError {
-            tool: String::from("Yarn"),
-            version: version_string.clone(),
-            dir: dest.clone(),
-        })?;
+        let unpacked = archive_root_dir(temp.path(), &self.version);
+        permissions::apply_to_tree(&unpacked)?;
+
+        install_image(&unpacked, &path::yarn_store_dir(&version_string)?, &dest).with_context(
+            |_| ErrorDetails::SetupToolImageError {
+                tool: String::from("Yarn"),
+                version: version_string.clone(),
+                dir: dest.clone(),
+            },
+        )?;
+        checksum::write_manifest(&dest)?;
+        checksum::write_archive_hash(&dest, &archive_hash)?;
+        prune_archives(&path::yarn_inventory_dir()?)?;
 
         bar.finish_and_clear();
 
@@ -180,3 +323,59 @@ impl Distro for YarnDistro {
         Ok(Fetched::Now(self.version))
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::parse_index;
+    use crate::hook::tool::IndexFormat;
+    use semver::Version;
+
+    const URL: &str = "http://localhost/yarn/index/";
+
+    #[test]
+    fn parses_github_index() {
+        let index = r#"[
+            { "tag_name": "v1.16.0", "assets": [{ "name": "yarn-v1.16.0.tar.gz" }] },
+            { "tag_name": "v1.15.1", "assets": [{ "name": "yarn-1.15.1.msi" }] }
+        ]"#;
+        let versions = parse_index(IndexFormat::Github, index, URL).expect("parses");
+        assert_eq!(
+            versions.into_iter().collect::<Vec<_>>(),
+            vec![Version::new(1, 16, 0)]
+        );
+    }
+
+    #[test]
+    fn parses_npm_index() {
+        let index = r#"{
+            "name": "yarn",
+            "description": null,
+            "dist-tags": { "latest": "1.16.0" },
+            "versions": {
+                "1.15.2": { "version": "1.15.2", "dist": { "shasum": "abc", "tarball": "http://localhost/yarn-1.15.2.tgz" } },
+                "1.16.0": { "version": "1.16.0", "dist": { "shasum": "def", "tarball": "http://localhost/yarn-1.16.0.tgz" } }
+            }
+        }"#;
+        let versions = parse_index(IndexFormat::Npm, index, URL).expect("parses");
+        assert_eq!(
+            versions.into_iter().collect::<Vec<_>>(),
+            vec![Version::new(1, 15, 2), Version::new(1, 16, 0)]
+        );
+    }
+
+    #[test]
+    fn parses_simple_json_index() {
+        let index = r#"["1.16.0", "1.3.2"]"#;
+        let versions = parse_index(IndexFormat::SimpleJson, index, URL).expect("parses");
+        assert_eq!(
+            versions.into_iter().collect::<Vec<_>>(),
+            vec![Version::new(1, 3, 2), Version::new(1, 16, 0)]
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_format() {
+        let index = r#"["1.16.0", "1.3.2"]"#;
+        assert!(parse_index(IndexFormat::Npm, index, URL).is_err());
+    }
+}
@@ -1,9 +1,53 @@
 //! Provides utilities for extracting standard Volta environment variables.
 
 use std::env;
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 
 pub const UNSAFE_GLOBAL: &'static str = "VOLTA_UNSAFE_GLOBAL";
+pub const TRANSITIVE_BINS: &'static str = "VOLTA_TRANSITIVE_BINS";
+pub const UNOFFICIAL_BUILDS: &'static str = "VOLTA_UNOFFICIAL_BUILDS";
+pub const SCRIPT_POLICY: &'static str = "VOLTA_SCRIPT_POLICY";
+pub const OUTPUT: &'static str = "VOLTA_OUTPUT";
+pub const EVENT_LOG: &'static str = "VOLTA_EVENT_LOG";
+pub const NO_PROGRESS: &'static str = "VOLTA_NO_PROGRESS";
+pub const PROFILE: &'static str = "VOLTA_PROFILE";
+pub const SHARED_HOME: &'static str = "VOLTA_SHARED_HOME";
+pub const NO_UPDATE_NOTIFIER: &'static str = "VOLTA_NO_UPDATE_NOTIFIER";
+pub const LINK_IMAGES: &'static str = "VOLTA_LINK_IMAGES";
+pub const SERIAL_REGISTRY: &'static str = "VOLTA_SERIAL_REGISTRY";
+pub const TMP_DIR: &'static str = "VOLTA_TMPDIR";
+pub const INSTALL_PERMISSIONS: &'static str = "VOLTA_INSTALL_PERMISSIONS";
+pub const KEEP_ARCHIVES: &'static str = "VOLTA_KEEP_ARCHIVES";
+pub const LIMIT_RATE: &'static str = "VOLTA_LIMIT_RATE";
+pub const NETWORK_TIMEOUT: &'static str = "VOLTA_NETWORK_TIMEOUT";
+pub const PROXY: &'static str = "VOLTA_PROXY";
+pub const BACKGROUND_FETCH: &'static str = "VOLTA_BACKGROUND_FETCH";
+pub const POLICY: &'static str = "VOLTA_POLICY";
+pub const MOCK_SERVER: &'static str = "VOLTA_MOCK_SERVER";
+pub const TRACE: &'static str = "VOLTA_TRACE";
+pub const PASSTHROUGH: &'static str = "VOLTA_PASSTHROUGH";
+pub const PARTIAL_PINS: &'static str = "VOLTA_PARTIAL_PINS";
+
+// Set by Volta for the tools it runs, describing how they were launched
+pub const PROJECT_ROOT: &'static str = "VOLTA_PROJECT_ROOT";
+pub const NODE_VERSION: &'static str = "VOLTA_NODE_VERSION";
+pub const TOOL_SOURCE: &'static str = "VOLTA_TOOL_SOURCE";
+pub const PLATFORM: &'static str = "VOLTA_PLATFORM";
+pub const PREVIOUS_PLATFORM: &'static str = "VOLTA_PREVIOUS_PLATFORM";
+pub const NPM_CONFIG_NODEDIR: &'static str = "npm_config_nodedir";
+pub(crate) const SHIM_DEPTH: &'static str = "_VOLTA_SHIM_DEPTH";
+
+/// Determines whether a flag such as `VOLTA_UNSAFE_GLOBAL` is turned on. Setting it to any value
+/// turns it on, except for an empty value, `0`, or `false`, which leave it off.
+pub(crate) fn flag_enabled(var: &str) -> bool {
+    env::var_os(var).map_or(false, |value| is_enabled_value(&value))
+}
+
+fn is_enabled_value(value: &OsStr) -> bool {
+    let value = value.to_string_lossy();
+    !(value.is_empty() || value == "0" || value.eq_ignore_ascii_case("false"))
+}
 
 pub(crate) fn shell_name() -> Option<String> {
     env::var_os("VOLTA_SHELL").map(|s| s.to_string_lossy().into_owned())
@@ -15,6 +59,17 @@ pub fn postscript_path() -> Option<PathBuf> {
         .map(|ref s| Path::new(s).to_path_buf())
 }
 
+/// Returns the server that stands in for the public Node, Yarn, and npm servers in builds with the
+/// `mock-network` feature: the one that `VOLTA_MOCK_SERVER` points to, so that each test can run
+/// its own, or else mockito's.
+#[cfg(feature = "mock-network")]
+pub(crate) fn mock_server_url() -> String {
+    match env::var(MOCK_SERVER) {
+        Ok(ref url) if !url.is_empty() => url.trim_end_matches('/').to_string(),
+        _ => mockito::SERVER_URL.to_string(),
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
 
@@ -28,6 +83,17 @@ pub mod tests {
         assert_eq!(shell_name().unwrap(), "bash".to_string());
     }
 
+    #[test]
+    fn test_is_enabled_value() {
+        assert!(is_enabled_value(OsStr::new("1")));
+        assert!(is_enabled_value(OsStr::new("true")));
+        assert!(is_enabled_value(OsStr::new("yes")));
+        assert!(!is_enabled_value(OsStr::new("")));
+        assert!(!is_enabled_value(OsStr::new("0")));
+        assert!(!is_enabled_value(OsStr::new("false")));
+        assert!(!is_enabled_value(OsStr::new("FALSE")));
+    }
+
     #[test]
     fn test_postscript_path() {
         env::set_var("VOLTA_POSTSCRIPT", "/some/path");
@@ -35,11 +35,32 @@ impl fmt::Display for CreatePostscriptErrorPath {
 
 #[derive(Debug, Fail, PartialEq)]
 pub enum ErrorDetails {
-    /// Thrown when package tries to install a binary that is already installed.
-    BinaryAlreadyInstalled {
-        bin_name: String,
-        existing_package: String,
-        new_package: String,
+    /// Thrown when the hooks declare an activity kind with the name of a built-in one.
+    ActivityKindExists {
+        name: String,
+    },
+
+    /// Thrown when the security advisories for packages could not be fetched
+    AdvisoriesFetchError {
+        from_url: String,
+    },
+
+    /// Thrown when `--arch` is given for a tool other than Node
+    ArchRequiresNode {
+        tool: String,
+    },
+
+    /// Thrown when the archive of a tool doesn't match the SHA-256 that the project pins for it
+    ArchiveHashMismatch {
+        tool: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// Thrown when the SHA-256 of the archive that a tool was installed from can't be determined
+    ArchiveHashUnknown {
+        tool: String,
+        image: PathBuf,
     },
 
     /// Thrown when executing an external binary fails
@@ -53,21 +74,47 @@ pub enum ErrorDetails {
     /// Thrown when building the virtual environment path fails
     BuildPathError,
 
+    /// Thrown when Bun doesn't publish builds for the current platform
+    BunPlatformNotSupported,
+
+    BunVersionNotFound {
+        matching: String,
+    },
+
+    /// Thrown when a tool can't be found on the system PATH in a directory that bypasses Volta
+    BypassedBinaryNotFound {
+        name: String,
+    },
+
     /// Thrown when a user tries to `volta pin` something other than node/yarn/npm.
     CannotPinPackage {
         package: String,
     },
 
+    /// Thrown when trying to set a package as a default with `volta default`
+    CannotSetPackageAsDefault {
+        package: String,
+    },
+
     /// Thrown when the Completions out-dir is not a directory
     CompletionsOutFileError {
         path: PathBuf,
     },
 
+    /// Thrown when the settings file uses a newer schema than this version of Volta supports
+    ConfigTooNew {
+        file: PathBuf,
+        version: u64,
+    },
+
     /// Thrown when the containing directory could not be determined
     ContainingDirError {
         path: PathBuf,
     },
 
+    /// Thrown when `corepack enable` would replace the Yarn that Volta manages
+    CorepackEnableConflict,
+
     CouldNotDetermineTool,
 
     CreateDirError {
@@ -84,11 +131,6 @@ pub enum ErrorDetails {
         in_dir: PathBuf,
     },
 
-    /// Thrown when creating a temporary file fails
-    CreateTempFileError {
-        in_dir: PathBuf,
-    },
-
     CurrentDirError,
 
     /// Thrown when deleting a directory fails
@@ -101,6 +143,13 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when Deno doesn't publish builds for the current platform
+    DenoPlatformNotSupported,
+
+    DenoVersionNotFound {
+        matching: String,
+    },
+
     DeprecatedCommandError {
         command: String,
         advice: String,
@@ -111,11 +160,47 @@ pub enum ErrorDetails {
         bin: String,
     },
 
+    /// Thrown when a tool's download runs out of disk space
+    DownloadToolDiskFull {
+        tool: ToolSpec,
+    },
+
+    /// Thrown when the host of a tool's download can't be found
+    DownloadToolDnsError {
+        tool: ToolSpec,
+        from_url: String,
+    },
+
+    /// Thrown when the server refuses a tool's download
+    DownloadToolForbidden {
+        tool: ToolSpec,
+        from_url: String,
+    },
+
     DownloadToolNetworkError {
         tool: ToolSpec,
         from_url: String,
     },
 
+    /// Thrown when a tool's download doesn't exist, usually because the version has no build
+    /// for this platform
+    DownloadToolNotFound {
+        tool: ToolSpec,
+        from_url: String,
+    },
+
+    /// Thrown when a secure connection for a tool's download can't be established
+    DownloadToolTlsError {
+        tool: ToolSpec,
+        from_url: String,
+    },
+
+    /// Thrown when `--dry-run` is used with a command, or a part of one, that can't list the
+    /// changes it would make
+    DryRunUnsupported {
+        action: String,
+    },
+
     /// Thrown when building the path to an executable fails
     ExecutablePathError {
         command: String,
@@ -131,12 +216,73 @@ pub enum ErrorDetails {
         command: String,
     },
 
+    /// Thrown when the requested commit, branch, or tag can't be checked out of a git repository
+    GitCheckoutError {
+        url: String,
+        committish: String,
+    },
+
+    /// Thrown when a git repository that a package is being installed from can't be cloned
+    GitCloneError {
+        url: String,
+    },
+
+    /// Thrown when `--headers` is given for a tool other than Node
+    HeadersRequireNode {
+        tool: String,
+    },
+
+    /// Thrown when a hook other than the Yarn index hook includes a `format`.
+    HookFormatNotSupported {
+        hook: String,
+    },
+
     /// Thrown when a hook contains multiple fields (prefix, template, or bin)
     HookMultipleFieldsSpecified,
 
     /// Thrown when a hook doesn't contain any of the known fields (prefix, template, or bin)
     HookNoFieldsSpecified,
 
+    /// Thrown when the download of an archive ends before the whole archive has been received
+    IncompleteDownload {
+        tool: String,
+        version: String,
+    },
+
+    /// Thrown when a fetched tool was installed from an archive that doesn't match the pinned SHA-256
+    InstalledArchiveHashMismatch {
+        tool: String,
+        expected: String,
+        actual: String,
+        image: PathBuf,
+    },
+
+    /// Thrown when the sampling rate of an activity in the hooks isn't from 0 to 1.
+    InvalidActivityRate {
+        activity: String,
+        rate: f64,
+    },
+
+    /// Thrown when the settings file can't be parsed, or sets something it can't
+    InvalidConfig {
+        file: PathBuf,
+        line: usize,
+        key: Option<String>,
+        reason: String,
+    },
+
+    /// Thrown when `volta config set` is given a value that the setting doesn't take
+    InvalidConfigValue {
+        key: String,
+        value: String,
+        reason: String,
+    },
+
+    /// Thrown when the default-packages file lists a tool that isn't a package
+    InvalidDefaultPackage {
+        spec: String,
+    },
+
     InvalidHookCommand {
         command: String,
     },
@@ -146,6 +292,11 @@ pub enum ErrorDetails {
         command: String,
     },
 
+    /// Thrown when VOLTA_INSTALL_PERMISSIONS is set to an unrecognized policy
+    InvalidInstallPermissions {
+        value: String,
+    },
+
     /// Thrown when a user does e.g. `volta install node 12` instead of
     /// `volta install node@12`.
     InvalidInvocation {
@@ -154,20 +305,116 @@ pub enum ErrorDetails {
         version: String,
     },
 
+    /// Thrown when the policy for keeping cached archives is not recognized
+    InvalidKeepArchives {
+        value: String,
+    },
+
+    /// Thrown when an unrecognized architecture is requested for Node
+    InvalidNodeArch {
+        arch: String,
+    },
+
+    /// Thrown when the partial pin policy is not recognized
+    InvalidPartialPinPolicy {
+        value: String,
+    },
+
+    /// Thrown when the passthrough policy is not recognized
+    InvalidPassthroughPolicy {
+        value: String,
+    },
+
+    /// Thrown when the name of the active profile can't be used as a directory name
+    InvalidProfileName {
+        profile: String,
+    },
+
+    /// Thrown when the download rate limit is not recognized
+    InvalidRateLimit {
+        value: String,
+    },
+
+    /// Thrown when the script policy environment variable has an unrecognized value
+    InvalidScriptPolicy {
+        value: String,
+    },
+
+    /// Thrown when the configured temporary directory is not an absolute path
+    InvalidTmpDir {
+        dir: PathBuf,
+    },
+
     /// Thrown when a tool name is invalid per npm's rules.
     InvalidToolName {
         name: String,
         errors: Vec<String>,
     },
 
+    /// Thrown when the Volta directory was migrated by a newer version of Volta.
+    LayoutTooNew {
+        version: u32,
+    },
+
+    /// Thrown when running `volta link` outside of a package
+    LinkOutsidePackage,
+
+    /// Thrown when the image of a linked package couldn't be created
+    LinkPackageError {
+        package: String,
+        dir: PathBuf,
+    },
+
+    /// Thrown when a file could not be moved while migrating the Volta directory.
+    MigrateLayoutError {
+        from: PathBuf,
+        to: PathBuf,
+    },
+
     /// Thrown when BinConfig (read from file) does not contain Platform info.
     NoBinPlatform {
         binary: String,
     },
 
+    /// Thrown when `volta which --all` finds no installed package that provides an executable.
+    NoPackageProvidesBin {
+        bin: String,
+    },
+
+    /// Thrown when an action needs the tools pinned in the project, but Node is not pinned
+    NoPinnedPlatform {
+        action: String,
+    },
+
+    /// Thrown when `volta setup` finds no shell profiles to configure.
+    NoShellProfiles,
+
+    NoUserBun,
+
+    NoUserDeno,
+
+    /// Thrown when setting a default npm without a default Node
+    NoUserNodeForNpm,
+
+    /// Thrown when pinning workspaces in a project that doesn't have any
+    NoWorkspaces,
+
+    /// Thrown when the version locked for a Node range no longer satisfies the range
+    NodeLockOutdated {
+        range: String,
+        locked: String,
+    },
+
+    /// Thrown when Node is pinned to a range without a locked version
+    NodeRangeNotLocked {
+        range: String,
+    },
+
     /// Thrown when there is no Node version matching a requested semver specifier.
     NodeVersionNotFound {
         matching: String,
+        /// The closest versions that are available, if any could be found
+        suggestions: Vec<String>,
     },
 
     NoGlobalInstalls {
@@ -184,6 +431,9 @@ pub enum ErrorDetails {
     /// Thrown when a user tries to install or fetch a package with no executables.
     NoPackageExecutables,
 
+    /// Thrown when updating the pinned Node range in a project that doesn't pin one
+    NoPinnedNodeRange,
+
     /// Thrown when a user tries to pin a Yarn version before pinning a Node version.
     NoPinnedNodeVersion,
 
@@ -205,9 +455,22 @@ pub enum ErrorDetails {
         version: String,
     },
 
+    /// Thrown when preferring an executable that a package doesn't provide
+    PackageBinNotFound {
+        package: String,
+        bin: String,
+    },
+
     /// Thrown when package install command is not successful.
     PackageInstallFailed,
 
+    /// Thrown when a downloaded package tarball doesn't match the checksums from the registry
+    PackageIntegrityError {
+        package: String,
+        version: String,
+        from_url: String,
+    },
+
     /// Thrown when there is an error fetching package metadata
     PackageMetadataFetchError {
         from_url: String,
@@ -218,6 +481,16 @@ pub enum ErrorDetails {
         package: String,
     },
 
+    /// Thrown when upgrading a package that isn't installed
+    PackageNotInstalled {
+        package: String,
+    },
+
+    /// Thrown when unlinking a package that was installed rather than linked
+    PackageNotLinked {
+        package: String,
+    },
+
     /// Thrown when parsing a package manifest fails
     PackageParseError {
         file: PathBuf,
@@ -228,6 +501,16 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when `npm rebuild` fails for an installed package
+    PackageRebuildFailed {
+        package: String,
+    },
+
+    /// Thrown when a package from a git repository or local path has no valid name or version
+    PackageSourceManifestError {
+        source: String,
+    },
+
     /// Thrown when a package has been unpacked but is not formed correctly.
     PackageUnpackError,
 
@@ -242,9 +525,24 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when the security advisories for packages could not be parsed
+    ParseAdvisoriesError {
+        from_url: String,
+    },
+
     /// Thrown when unable to parse a bin config file
     ParseBinConfigError,
 
+    /// Thrown when unable to parse the Bun version index
+    ParseBunIndexError {
+        from_url: String,
+    },
+
+    /// Thrown when unable to parse the Deno version index
+    ParseDenoIndexError {
+        from_url: String,
+    },
+
     /// Thrown when unable to parse a hooks.json file
     ParseHooksError {
         file: PathBuf,
@@ -258,9 +556,6 @@ pub enum ErrorDetails {
         from_url: String,
     },
 
-    /// Thrown when unable to parse the node index cache expiration
-    ParseNodeIndexExpiryError,
-
     /// Thrown when unable to parse the npm manifest file from a node install
     ParseNpmManifestError,
 
@@ -275,19 +570,65 @@ pub enum ErrorDetails {
     /// Thrown when unable to parse the platform.json file
     ParsePlatformError,
 
+    /// Thrown when a project's pnpm-workspace.yaml file can't be parsed
+    ParsePnpmWorkspaceError {
+        file: PathBuf,
+    },
+
+    /// Thrown when the version policy file could not be parsed
+    ParsePolicyError {
+        file: PathBuf,
+    },
+
     /// Thrown when unable to parse a tool spec (`<tool>[@<version>]`)
     ParseToolSpecError {
         tool_spec: String,
     },
 
+    /// Thrown when the Yarn index could not be parsed in the expected format
+    ParseYarnIndexError {
+        from_url: String,
+        format: String,
+    },
+
+    /// Thrown when a binary could not be resolved through a Yarn Plug'n'Play runtime
+    PnpBinaryResolveError {
+        bin: String,
+    },
+
+    /// Thrown when a version of a tool is not allowed by the version policy
+    PolicyViolation {
+        tool: String,
+        version: String,
+        rule: String,
+        file: PathBuf,
+    },
+
+    /// Thrown when a command from the project's `volta` section could not be run.
+    ProjectCommandExecError {
+        name: String,
+    },
+
+    /// Thrown when `volta run` is given a command that the project doesn't define.
+    ProjectCommandNotFound {
+        name: String,
+        /// The commands that the project does define
+        available: Vec<String>,
+    },
+
     /// Thrown when executing a project-local binary fails
     ProjectLocalBinaryExecError {
-        command: String,
+        command: PathBuf,
     },
 
     /// Thrown when a project-local binary could not be found
     ProjectLocalBinaryNotFound {
-        command: String,
+        command: PathBuf,
+    },
+
+    /// Thrown when a platform can't be laid down into the prefix for `volta provision`
+    ProvisionError {
+        dir: PathBuf,
     },
 
     /// Thrown when a publish hook contains both the url and bin fields
@@ -306,50 +647,131 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when the checksums recorded for an image could not be read.
+    ReadChecksumsError {
+        file: PathBuf,
+    },
+
+    /// Thrown when the settings file could not be read
+    ReadConfigError {
+        file: PathBuf,
+    },
+
+    /// Thrown when the list of shims added with `volta shim add` can't be read
+    ReadCustomShimsError {
+        file: PathBuf,
+    },
+
     /// Thrown when unable to read the default npm version file
     ReadDefaultNpmError {
         file: PathBuf,
     },
 
+    /// Thrown when the default-packages file can't be read
+    ReadDefaultPackagesError {
+        file: PathBuf,
+    },
+
+    /// Thrown when there was an error reading the queue of events waiting to be published
+    ReadEventQueueError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error opening a hooks.json file
     ReadHooksError {
         file: PathBuf,
     },
 
+    /// Thrown when the files of an installed image could not be read.
+    ReadImageError {
+        dir: PathBuf,
+    },
+
     /// Thrown when there was an error reading the inventory contents
     ReadInventoryDirError {
         dir: PathBuf,
     },
 
-    /// Thrown when there was an error reading the Node Index Cache
-    ReadNodeIndexCacheError {
+    /// Thrown when the layout version of the Volta directory could not be read.
+    ReadLayoutError {
         file: PathBuf,
     },
 
-    /// Thrown when there was an error reading the Node Index Cache Expiration
-    ReadNodeIndexExpiryError {
+    /// Thrown when there was an error reading the npm manifest file
+    ReadNpmManifestError,
+
+    /// Thrown when there was an error reading an `.npmrc` file
+    ReadNpmrcError {
         file: PathBuf,
     },
 
-    /// Thrown when there was an error reading the npm manifest file
-    ReadNpmManifestError,
+    /// Thrown when a tool needs to be fetched but the Volta directory is read-only.
+    ReadOnlyVoltaHome {
+        dir: PathBuf,
+    },
+
+    /// Thrown when there was an error reading the user package directory
+    ReadPackageConfigDirError {
+        dir: PathBuf,
+    },
 
     /// Thrown when there was an error reading a package configuration file
     ReadPackageConfigError {
         file: PathBuf,
     },
 
+    /// Thrown when a package can't be read from a local directory or tarball
+    ReadPackageSourceError {
+        source: String,
+    },
+
     /// Thrown when there was an error opening the user platform file
     ReadPlatformError {
         file: PathBuf,
     },
 
+    /// Thrown when the version policy file could not be read
+    ReadPolicyError {
+        file: PathBuf,
+    },
+
+    /// Thrown when there was an error reading the project's local bin directory
+    ReadProjectBinDirError {
+        dir: PathBuf,
+    },
+
+    /// Thrown when there was an error reading a shell profile.
+    ReadShellProfileError {
+        file: PathBuf,
+    },
+
+    /// Thrown when the shim directory can't be read
+    ReadShimDirError {
+        dir: PathBuf,
+    },
+
+    /// Thrown when the result of the last check for a new version of Volta could not be read.
+    ReadUpdateCheckError {
+        file: PathBuf,
+    },
+
     /// Thrown when the public registry for Node or Yarn could not be downloaded.
     RegistryFetchError {
         tool: String,
         from_url: String,
     },
 
+    /// Thrown when a project pins npm or Yarn without pinning Node, and partial pins are denied
+    RuntimeNotPinned {
+        tools: String,
+        file: String,
+    },
+
+    /// Thrown when the permissions of an installed file can't be set to match the permission policy
+    SetPermissionsError {
+        path: PathBuf,
+    },
+
     /// Thrown when there was an error copying an unpacked tool to the image directory
     SetupToolImageError {
         tool: String,
@@ -362,6 +784,11 @@ pub enum ErrorDetails {
         name: String,
     },
 
+    /// Thrown when shims keep running each other, which means that a binary leads back to a shim
+    ShimRecursionError {
+        name: String,
+    },
+
     /// Thrown when trying to remove a built-in shim (`node`, `yarn`, etc.)
     ShimRemoveBuiltInError {
         name: String,
@@ -384,11 +811,31 @@ pub enum ErrorDetails {
     /// Thrown when serializing the toolchain to JSON fails
     StringifyToolchainError,
 
+    /// Thrown when files can't be created in the configured temporary directory
+    TmpDirNotWritable {
+        dir: PathBuf,
+    },
+
+    /// Thrown when files in the configured temporary directory can't be made executable
+    TmpDirPermissionsError {
+        dir: PathBuf,
+    },
+
+    /// Thrown when a plugin records events of an activity kind that the hooks don't declare.
+    UndeclaredActivityKind {
+        name: String,
+    },
+
     /// Thrown when a given feature has not yet been implemented
     Unimplemented {
         feature: String,
     },
 
+    /// Thrown when `volta config` is given a setting that doesn't exist
+    UnknownConfigKey {
+        key: String,
+    },
+
     /// Thrown when unpacking an archive (tarball or zip) fails
     UnpackArchiveError {
         tool: String,
@@ -406,27 +853,103 @@ pub enum ErrorDetails {
     /// Thrown when the shell name was not specified in the Volta environment.
     UnspecifiedShell,
 
-    VersionParseError {
-        version: String,
+    /// Thrown when a pre-release channel is requested for a tool that doesn't publish one
+    UnsupportedReleaseChannel {
+        tool: String,
+        channel: String,
     },
 
-    /// Thrown when there was an error writing a bin config file
-    WriteBinConfigError {
-        file: PathBuf,
+    /// Thrown when upgrading every installed package fails for some of them.
+    UpgradePackagesError {
+        packages: Vec<String>,
     },
 
-    /// Thrown when there was an error writing the default npm to file
-    WriteDefaultNpmError {
-        file: PathBuf,
+    VersionParseError {
+        version: String,
+    },
+
+    /// Thrown when a downloaded Volta release doesn't match its published checksum.
+    VoltaChecksumMismatch {
+        version: String,
+        from_url: String,
+    },
+
+    /// Thrown when no checksum is published for the Volta release asset for this platform.
+    VoltaChecksumMissing {
+        file: String,
+        from_url: String,
+    },
+
+    /// Thrown when a new version of Volta could not be downloaded.
+    VoltaDownloadError {
+        from_url: String,
+    },
+
+    /// Thrown when the Windows installer for a new version of Volta fails.
+    VoltaInstallerError {
+        version: String,
+    },
+
+    /// Thrown when the newest version of Volta could not be fetched from its release channel.
+    VoltaLatestFetchError {
+        from_url: String,
+    },
+
+    /// Thrown when the version of OpenSSL that a Linux release of Volta is built for can't be found.
+    VoltaOpensslVersionError,
+
+    /// Thrown when an installed Volta binary could not be replaced during an update.
+    VoltaReplaceError {
+        file: PathBuf,
+    },
+
+    /// Thrown when a workspace glob in a project can't be parsed
+    WorkspaceGlobError {
+        glob: String,
+    },
+
+    /// Thrown when there was an error writing a bin config file
+    WriteBinConfigError {
+        file: PathBuf,
+    },
+
+    /// Thrown when the checksums of a newly installed image could not be written.
+    WriteChecksumsError {
+        file: PathBuf,
+    },
+
+    /// Thrown when the settings file could not be written
+    WriteConfigError {
+        file: PathBuf,
+    },
+
+    /// Thrown when the list of shims added with `volta shim add` can't be written
+    WriteCustomShimsError {
+        file: PathBuf,
+    },
+
+    /// Thrown when there was an error writing the default npm to file
+    WriteDefaultNpmError {
+        file: PathBuf,
+    },
+
+    /// Thrown when there was an error writing the local event log
+    WriteEventLogError {
+        file: PathBuf,
+    },
+
+    /// Thrown when there was an error writing the queue of events waiting to be published
+    WriteEventQueueError {
+        file: PathBuf,
     },
 
-    /// Thrown when there was an error writing the node index cache
-    WriteNodeIndexCacheError {
+    /// Thrown when the layout version of the Volta directory could not be written.
+    WriteLayoutError {
         file: PathBuf,
     },
 
-    /// Thrown when there was an error writing the node index expiration
-    WriteNodeIndexExpiryError {
+    /// Thrown when the report of `volta matrix` couldn't be written
+    WriteMatrixReportError {
         file: PathBuf,
     },
 
@@ -447,6 +970,16 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when there was an error writing a shell profile.
+    WriteShellProfileError {
+        file: PathBuf,
+    },
+
+    /// Thrown when the result of a check for a new version of Volta could not be written.
+    WriteUpdateCheckError {
+        file: PathBuf,
+    },
+
     /// Thrown when there is an error fetching the latest version of Yarn
     YarnLatestFetchError {
         from_url: String,
@@ -461,16 +994,51 @@ pub enum ErrorDetails {
 impl fmt::Display for ErrorDetails {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ErrorDetails::BinaryAlreadyInstalled {
-                bin_name,
-                existing_package,
-                new_package,
+            ErrorDetails::ActivityKindExists { name } => write!(
+                f,
+                "Cannot declare the '{}' activity kind, because Volta already has one with that name.
+
+Please choose a different name in the `events.kinds` section of your hooks.",
+                name
+            ),
+            ErrorDetails::AdvisoriesFetchError { from_url } => write!(
+                f,
+                "Could not fetch security advisories
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
+            ErrorDetails::ArchRequiresNode { tool } => write!(
+                f,
+                "Cannot install {} for another architecture.
+
+Only Node can be installed with --arch. Please install {} separately.",
+                tool, tool
+            ),
+            ErrorDetails::ArchiveHashMismatch {
+                tool,
+                expected,
+                actual,
             } => write!(
                 f,
-                "Executable '{}' is already installed by {}
+                "Could not install {} because its archive does not match the pinned SHA-256.
 
-Please remove {} before installing {}",
-                bin_name, existing_package, existing_package, new_package
+Expected: {}
+Actual:   {}
+
+The archive may have been corrupted or tampered with, so it was removed. If the project's pin is
+out of date, please update it with `volta pin --with-hash`.",
+                tool, expected, actual
+            ),
+            ErrorDetails::ArchiveHashUnknown { tool, image } => write!(
+                f,
+                "Could not determine the SHA-256 of the archive that {} was installed from.
+
+It was installed by an older version of Volta, and its archive has since been removed.
+Please remove {} so that it is downloaded again, and rerun `volta pin --with-hash`.",
+                tool,
+                image.display()
             ),
             ErrorDetails::BinaryExecError => write!(
                 f,
@@ -491,13 +1059,40 @@ Use `volta install` to add a package to your toolchain (see `volta help install`
 
 Please ensure your PATH is valid."
             ),
+            ErrorDetails::BunPlatformNotSupported => write!(
+                f,
+                "Bun is not available for this platform.
+
+Bun only publishes builds for x64 and arm64 Linux and macOS, and x64 Windows."
+            ),
+            ErrorDetails::BunVersionNotFound { matching } => write!(
+                f,
+                r#"Could not find Bun version matching "{}" in the version registry.
+
+Please verify that the version is correct."#,
+                matching
+            ),
+            ErrorDetails::BypassedBinaryNotFound { name } => write!(
+                f,
+                r#"Could not find executable "{}" on your PATH
+
+Volta is bypassed in this directory, so tools are run from your system rather than your toolchain."#,
+                name
+            ),
             ErrorDetails::CannotPinPackage { package } => write!(
                 f,
-                "Only node and yarn can be pinned in a project
+                "Only node, yarn, deno, and bun can be pinned in a project
 
 Use `npm install` or `yarn add` to select a version of {} for this project.",
                 package
             ),
+            ErrorDetails::CannotSetPackageAsDefault { package } => write!(
+                f,
+                "Only node, npm, yarn, deno, and bun have a default version
+
+Use `volta install {}` to install a package in your toolchain.",
+                package
+            ),
             ErrorDetails::CompletionsOutFileError { path } => write!(
                 f,
                 "Completions file `{}` already exists.
@@ -505,6 +1100,14 @@ Use `npm install` or `yarn add` to select a version of {} for this project.",
 Please remove the file or pass `-f` or `--force` to override.",
                 path.display()
             ),
+            ErrorDetails::ConfigTooNew { file, version } => write!(
+                f,
+                "The settings file at {} uses version {} of its schema, which is newer than this version of Volta supports.
+
+Please update Volta with `volta self-update`.",
+                file.display(),
+                version
+            ),
             ErrorDetails::ContainingDirError { path } => write!(
                 f,
                 "Could not determine directory information
@@ -514,6 +1117,12 @@ for {}
                 path.display(),
                 PERMISSIONS_CTA
             ),
+            ErrorDetails::CorepackEnableConflict => write!(
+                f,
+                "Enabling corepack for Yarn would replace the Yarn shims that Volta manages.
+
+Use `volta install yarn` or `volta pin yarn` to select a version of Yarn, or run `corepack enable pnpm` to enable corepack only for pnpm."
+            ),
             ErrorDetails::CouldNotDetermineTool => write!(
                 f,
                 "Could not determine tool name
@@ -541,15 +1150,6 @@ in {}
                 "Could not create temporary directory
 in {}
 
-{}",
-                in_dir.display(),
-                PERMISSIONS_CTA
-            ),
-            ErrorDetails::CreateTempFileError { in_dir } => write!(
-                f,
-                "Could not create temporary file
-in {}
-
 {}",
                 in_dir.display(),
                 PERMISSIONS_CTA
@@ -578,6 +1178,19 @@ at {}
                 file.display(),
                 PERMISSIONS_CTA
             ),
+            ErrorDetails::DenoPlatformNotSupported => write!(
+                f,
+                "Deno is not available for this platform.
+
+Deno only publishes builds for x64 and arm64 Linux and macOS, and x64 Windows."
+            ),
+            ErrorDetails::DenoVersionNotFound { matching } => write!(
+                f,
+                r#"Could not find Deno version matching "{}" in the version registry.
+
+Please verify that the version is correct."#,
+                matching
+            ),
             ErrorDetails::DeprecatedCommandError { command, advice } => {
                 write!(f, "The subcommand `{}` is deprecated.\n{}", command, advice)
             }
@@ -588,6 +1201,29 @@ at {}
 {}",
                 bin, REPORT_BUG_CTA
             ),
+            ErrorDetails::DownloadToolDiskFull { tool } => write!(
+                f,
+                "Could not download {}
+
+There is not enough disk space. Please free some space and try again.",
+                tool
+            ),
+            ErrorDetails::DownloadToolDnsError { tool, from_url } => write!(
+                f,
+                "Could not download {}
+from {} (the host could not be found)
+
+Please verify your internet connection, and your DNS and proxy settings.",
+                tool, from_url
+            ),
+            ErrorDetails::DownloadToolForbidden { tool, from_url } => write!(
+                f,
+                "Could not download {}
+from {} (access denied)
+
+If you download tools from a mirror or a registry that needs authentication, please check its settings in your hooks.json or .npmrc.",
+                tool, from_url
+            ),
             ErrorDetails::DownloadToolNetworkError { tool, from_url } => write!(
                 f,
                 "Could not download {}
@@ -596,6 +1232,35 @@ from {}
 Please verify your internet connection and ensure the correct version is specified.",
                 tool, from_url
             ),
+            ErrorDetails::DownloadToolNotFound { tool, from_url } => {
+                let suggestion = match tool {
+                    ToolSpec::Node(_) => "This version of Node may not provide a build for your platform. Please try another version, another architecture with `--arch`, or the unofficial builds by setting VOLTA_UNOFFICIAL_BUILDS.",
+                    _ => "Please ensure the correct version is specified.",
+                };
+                write!(
+                    f,
+                    "Could not download {}
+from {} (not found)
+
+{}",
+                    tool, from_url, suggestion
+                )
+            }
+            ErrorDetails::DownloadToolTlsError { tool, from_url } => write!(
+                f,
+                "Could not download {}
+from {} (a secure connection could not be established)
+
+If you are behind a proxy that inspects HTTPS traffic, please make sure that its certificate is trusted by your system.",
+                tool, from_url
+            ),
+            ErrorDetails::DryRunUnsupported { action } => write!(
+                f,
+                "{} can't be previewed with --dry-run.
+
+Please run it without --dry-run, or preview `volta install`, `pin`, `uninstall`, `setup` or `migrate` instead.",
+                action
+            ),
             ErrorDetails::ExecutablePathError { command } => write!(
                 f,
                 "Could not determine path to executable '{}'
@@ -617,6 +1282,37 @@ Please verify your internet connection and ensure the correct version is specifi
 Please ensure that the correct command is specified.",
                 command
             ),
+            ErrorDetails::GitCheckoutError { url, committish } => write!(
+                f,
+                r#"Could not check out "{}" from the git repository
+at {}
+
+Please verify that the commit, branch, or tag exists."#,
+                committish, url
+            ),
+            ErrorDetails::GitCloneError { url } => write!(
+                f,
+                "Could not clone the git repository
+at {}
+
+Please ensure that git is installed, and verify your internet connection and that you have
+access to the repository.",
+                url
+            ),
+            ErrorDetails::HeadersRequireNode { tool } => write!(
+                f,
+                "Cannot fetch headers for {}.
+
+Only Node has headers to fetch with --headers. Please fetch {} separately.",
+                tool, tool
+            ),
+            ErrorDetails::HookFormatNotSupported { hook } => write!(
+                f,
+                "Hook configuration for '{}' includes a 'format'.
+
+Only the 'yarn.index' hook supports 'format'. Please remove it from '{}'.",
+                hook, hook
+            ),
             ErrorDetails::HookMultipleFieldsSpecified => write!(
                 f,
                 "Hook configuration includes multiple hook types.
@@ -629,6 +1325,73 @@ Please include only one of 'bin', 'prefix', or 'template'"
 
 Please include one of 'bin', 'prefix', or 'template'"
             ),
+            ErrorDetails::IncompleteDownload { tool, version } => write!(
+                f,
+                "Could not download {} v{}
+The connection closed before the whole archive was received.
+
+Please verify your internet connection and try again.",
+                tool, version
+            ),
+            ErrorDetails::InstalledArchiveHashMismatch {
+                tool,
+                expected,
+                actual,
+                image,
+            } => write!(
+                f,
+                "Could not use {} because it was installed from an archive that does not match
+the pinned SHA-256.
+
+Expected: {}
+Actual:   {}
+
+If the project's pin is out of date, please update it with `volta pin --with-hash`. Otherwise,
+please remove {} so that it is installed again.",
+                tool,
+                expected,
+                actual,
+                image.display()
+            ),
+            ErrorDetails::InvalidActivityRate { activity, rate } => write!(
+                f,
+                "Invalid sampling rate for the '{}' activity: {}
+
+Please set it to true, false, or a number from 0 to 1 in the `events.activities` section of your hooks.",
+                activity, rate
+            ),
+            ErrorDetails::InvalidConfig {
+                file,
+                line,
+                key,
+                reason,
+            } => {
+                match key {
+                    Some(key) => write!(f, "Invalid setting `{}`", key)?,
+                    None => write!(f, "Invalid line")?,
+                }
+                write!(
+                    f,
+                    " on line {} of {}: {}
+
+Please correct the setting, or remove it from the file.",
+                    line,
+                    file.display(),
+                    reason
+                )
+            }
+            ErrorDetails::InvalidConfigValue { key, value, reason } => write!(
+                f,
+                r#"Invalid value "{}" for `{}`: {}"#,
+                value, key, reason
+            ),
+            ErrorDetails::InvalidDefaultPackage { spec } => write!(
+                f,
+                "Only packages can be listed in the default-packages file, found `{}`
+
+Please remove it from the default-packages file.",
+                spec
+            ),
             ErrorDetails::InvalidHookCommand { command } => write!(
                 f,
                 "Invalid hook command: '{}'
@@ -644,6 +1407,13 @@ Please ensure that the command output is valid UTF-8 text.",
                 command
             ),
 
+            ErrorDetails::InvalidInstallPermissions { value } => write!(
+                f,
+                r#"Unrecognized permission policy "{}" in VOLTA_INSTALL_PERMISSIONS
+
+Please set it to "shared" or "private"."#,
+                value
+            ),
             ErrorDetails::InvalidInvocation {
                 action,
                 name,
@@ -673,6 +1443,62 @@ To {action} the packages '{name}' and '{version}', please {action} them in separ
                 write!(f, "{}\n\n{}", error, wrapped_cta)
             }
 
+            ErrorDetails::InvalidKeepArchives { value } => write!(
+                f,
+                r#"Unrecognized archive policy "{}"
+
+Please set VOLTA_KEEP_ARCHIVES or `keepArchives` in your hooks to "always", "never", or "last-N" (e.g. "last-3")."#,
+                value
+            ),
+            ErrorDetails::InvalidNodeArch { arch } => write!(
+                f,
+                "Unrecognized Node architecture: '{}'
+
+Please use one of: x64, x86, arm64, armv7l, ppc64le, s390x",
+                arch
+            ),
+            ErrorDetails::InvalidPartialPinPolicy { value } => write!(
+                f,
+                r#"Unrecognized partial pin policy "{}"
+
+Please set VOLTA_PARTIAL_PINS or `partialPins` in your hooks to "allow", "warn", or "deny"."#,
+                value
+            ),
+            ErrorDetails::InvalidPassthroughPolicy { value } => write!(
+                f,
+                r#"Unrecognized passthrough policy "{}"
+
+Please set VOLTA_PASSTHROUGH or `passthrough` in your hooks to "allow", "warn", or "deny"."#,
+                value
+            ),
+            ErrorDetails::InvalidProfileName { profile } => write!(
+                f,
+                "Invalid profile name \"{}\".
+
+Profile names may only contain letters, numbers, dashes, and underscores.",
+                profile
+            ),
+            ErrorDetails::InvalidRateLimit { value } => write!(
+                f,
+                r#"Unrecognized download rate limit "{}"
+
+Please set VOLTA_LIMIT_RATE, `--limit-rate`, or `limitRate` in your hooks to a number of bytes per second, optionally with a suffix of "k", "m", or "g" (e.g. "500k")."#,
+                value
+            ),
+            ErrorDetails::InvalidScriptPolicy { value } => write!(
+                f,
+                r#"Unrecognized script policy "{}" in VOLTA_SCRIPT_POLICY
+
+Please set it to "allow", "deny", or "prompt"."#,
+                value
+            ),
+            ErrorDetails::InvalidTmpDir { dir } => write!(
+                f,
+                "Could not use {} as the temporary directory, since it is not an absolute path.
+
+Please set VOLTA_TMPDIR or `tmpDir` in your hooks to an absolute path.",
+                dir.display()
+            ),
             ErrorDetails::InvalidToolName { name, errors } => {
                 let indentation = "    ";
                 let wrapped = match text_width() {
@@ -694,6 +1520,39 @@ To {action} the packages '{name}' and '{version}', please {action} them in separ
                 )
             }
 
+            ErrorDetails::LayoutTooNew { version } => write!(
+                f,
+                "The Volta directory uses layout v{}, which is newer than this version of Volta supports.
+
+Please update Volta with `volta self-update`.",
+                version
+            ),
+            ErrorDetails::LinkOutsidePackage => write!(
+                f,
+                "Not in a node package.
+
+Run `volta link` from the directory of the package you want to link."
+            ),
+            ErrorDetails::LinkPackageError { package, dir } => write!(
+                f,
+                "Could not link {} from {}
+
+{}",
+                package,
+                dir.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::MigrateLayoutError { from, to } => write!(
+                f,
+                "Could not migrate the Volta directory: unable to move
+{}
+to {}
+
+No changes were made. {}",
+                from.display(),
+                to.display(),
+                PERMISSIONS_CTA
+            ),
             ErrorDetails::NoBinPlatform { binary } => write!(
                 f,
                 "Platform info for executable `{}` is missing
@@ -701,13 +1560,84 @@ To {action} the packages '{name}' and '{version}', please {action} them in separ
 Please uninstall and re-install the package that provides that executable.",
                 binary
             ),
-            ErrorDetails::NodeVersionNotFound { matching } => write!(
+            ErrorDetails::NoPackageProvidesBin { bin } => write!(
                 f,
-                r#"Could not find Node version matching "{}" in the version registry.
+                "No installed package provides the executable '{}'
 
-Please verify that the version is correct."#,
-                matching
+Use `volta install` to add a package to your toolchain (see `volta help install` for more info).",
+                bin
+            ),
+            ErrorDetails::NoPinnedPlatform { action } => write!(
+                f,
+                "Cannot {} because the Node version is not pinned in this project.
+
+Use `volta pin node` to pin Node first.",
+                action
             ),
+            ErrorDetails::NoShellProfiles => write!(
+                f,
+                "Could not find a profile for any supported shell.
+
+Use `volta setup --shell <shell>` to choose the shell to set up."
+            ),
+            ErrorDetails::NoUserBun => write!(
+                f,
+                "Could not determine Bun version.
+
+Use `volta install bun` to select a default version (see `volta help install` for more info)."
+            ),
+            ErrorDetails::NoUserDeno => write!(
+                f,
+                "Could not determine Deno version.
+
+Use `volta install deno` to select a default version (see `volta help install` for more info)."
+            ),
+            ErrorDetails::NoUserNodeForNpm => write!(
+                f,
+                "Cannot set a default npm because there is no default Node version.
+
+Use `volta install node` to select a default version of Node first, then set a default npm."
+            ),
+            ErrorDetails::NoWorkspaces => write!(
+                f,
+                "Cannot pin workspaces because this project has no workspace packages.
+
+Workspaces are listed by the `workspaces` key in package.json or by `pnpm-workspace.yaml`."
+            ),
+            ErrorDetails::NodeLockOutdated { range, locked } => write!(
+                f,
+                "Node is pinned to {}, but the locked version {} doesn't satisfy it.
+
+Use `volta pin --update` to lock a version that satisfies the range.",
+                range, locked
+            ),
+            ErrorDetails::NodeRangeNotLocked { range } => write!(
+                f,
+                "Node is pinned to {}, but no version is locked for it.
+
+Use `volta pin --update` to lock the latest version that satisfies the range.",
+                range
+            ),
+            ErrorDetails::NodeVersionNotFound {
+                matching,
+                suggestions,
+            } => {
+                let call_to_action = if suggestions.is_empty() {
+                    "Please verify that the version is correct.".to_string()
+                } else {
+                    format!(
+                        "The closest available versions are: {}",
+                        suggestions.join(", ")
+                    )
+                };
+                write!(
+                    f,
+                    r#"Could not find Node version matching "{}" in the version registry.
+
+{}"#,
+                    matching, call_to_action
+                )
+            }
             ErrorDetails::NoGlobalInstalls { package } => write!(
                 f,
                 "Global package installs are not supported.
@@ -741,6 +1671,12 @@ Please ensure the directory is available."
                 "Package has no executables to install.
 
 Please verify the requested package name."
+            ),
+            ErrorDetails::NoPinnedNodeRange => write!(
+                f,
+                "Cannot update the pinned Node version because it is not pinned to a range.
+
+Use `volta pin node@<range>`, like `volta pin node@^18`, to pin Node to a range."
             ),
             ErrorDetails::NoPinnedNodeVersion => write!(
                 f,
@@ -782,6 +1718,11 @@ This project is configured to use version {} of npm.",
                 version
             ),
             // Confirming permissions is a Weak CTA in this case, but it seems the most likely error vector
+            ErrorDetails::PackageBinNotFound { package, bin } => write!(
+                f,
+                "Package `{}` does not provide an executable named `{}`.",
+                package, bin
+            ),
             ErrorDetails::PackageInstallFailed => write!(
                 f,
                 "Could not install package dependencies.
@@ -789,6 +1730,19 @@ This project is configured to use version {} of npm.",
 {}",
                 PERMISSIONS_CTA
             ),
+            ErrorDetails::PackageIntegrityError {
+                package,
+                version,
+                from_url,
+            } => write!(
+                f,
+                "Could not verify the integrity of {}@{}
+downloaded from {}
+
+The downloaded tarball does not match the checksum reported by the package registry.
+Please try again. If the problem persists, your network connection may not be secure.",
+                package, version, from_url
+            ),
             ErrorDetails::PackageMetadataFetchError { from_url } => write!(
                 f,
                 "Could not download package metadata
@@ -804,6 +1758,20 @@ Please verify your internet connection.",
 Please verify the requested package name.",
                 package
             ),
+            ErrorDetails::PackageNotInstalled { package } => write!(
+                f,
+                "Package `{}` is not installed
+
+Use `volta install {}` to install it.",
+                package, package
+            ),
+            ErrorDetails::PackageNotLinked { package } => write!(
+                f,
+                "Package `{}` is not linked.
+
+Use `volta uninstall {}` to remove an installed package.",
+                package, package
+            ),
             ErrorDetails::PackageParseError { file } => write!(
                 f,
                 "Could not parse project manifest
@@ -820,6 +1788,21 @@ from {}
 Please ensure that the file exists.",
                 file.display()
             ),
+            ErrorDetails::PackageRebuildFailed { package } => write!(
+                f,
+                "Could not rebuild the native modules of {}.
+
+Please run `volta rebuild {} --verbose` for more details.",
+                package, package
+            ),
+            ErrorDetails::PackageSourceManifestError { source } => write!(
+                f,
+                "Could not determine the name and version of the package
+from {}
+
+Please ensure that its package.json has valid `name` and `version` fields.",
+                source
+            ),
             ErrorDetails::PackageUnpackError => write!(
                 f,
                 "Could not determine package directory layout.
@@ -841,6 +1824,14 @@ to {}
 Please ensure you have correct permissions.",
                 file.display()
             ),
+            ErrorDetails::ParseAdvisoriesError { from_url } => write!(
+                f,
+                "Could not parse security advisories
+from {}
+
+Please verify that the registry supports `npm audit`.",
+                from_url
+            ),
             ErrorDetails::ParseBinConfigError => write!(
                 f,
                 "Could not parse executable configuration file.
@@ -848,6 +1839,22 @@ Please ensure you have correct permissions.",
 {}",
                 REPORT_BUG_CTA
             ),
+            ErrorDetails::ParseBunIndexError { from_url } => write!(
+                f,
+                "Could not parse Bun version index
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
+            ErrorDetails::ParseDenoIndexError { from_url } => write!(
+                f,
+                "Could not parse Deno version index
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
             ErrorDetails::ParseHooksError { file } => write!(
                 f,
                 "Could not parse hooks configuration file.
@@ -871,13 +1878,6 @@ from {}
 Please verify your internet connection.",
                 from_url
             ),
-            ErrorDetails::ParseNodeIndexExpiryError => write!(
-                f,
-                "Could not parse Node index cache expiration file.
-
-{}",
-                REPORT_BUG_CTA
-            ),
             ErrorDetails::ParseNpmManifestError => write!(
                 f,
                 "Could not parse package.json file for bundled npm.
@@ -888,44 +1888,117 @@ Please ensure the version of Node is correct."
                 f,
                 "Could not parse package configuration file.
 
-{}",
-                REPORT_BUG_CTA
+{}",
+                REPORT_BUG_CTA
+            ),
+            ErrorDetails::ParsePackageMetadataError { from_url } => write!(
+                f,
+                "Could not parse package metadata
+from {}
+
+Please verify the requested package and version.",
+                from_url
+            ),
+            ErrorDetails::ParsePlatformError => write!(
+                f,
+                "Could not parse platform settings file.
+
+{}",
+                REPORT_BUG_CTA
+            ),
+            ErrorDetails::ParsePnpmWorkspaceError { file } => write!(
+                f,
+                "Could not parse workspace file {}
+
+Please ensure that the file is valid YAML with a list of `packages`.",
+                file.display()
+            ),
+            ErrorDetails::ParsePolicyError { file } => write!(
+                f,
+                "Could not parse the version policy
+from {}
+
+Please ensure the file is correctly formatted.",
+                file.display()
+            ),
+            ErrorDetails::ParseToolSpecError { tool_spec } => write!(
+                f,
+                "Could not parse tool spec `{}`
+
+Please supply a spec in the format `<tool name>[@<version>]`.",
+                tool_spec
+            ),
+            ErrorDetails::ParseYarnIndexError { from_url, format } => write!(
+                f,
+                "Could not parse Yarn version index from {} as {}
+
+Please verify your internet connection and the `format` of your Yarn index hook.",
+                from_url, format
             ),
-            ErrorDetails::ParsePackageMetadataError { from_url } => write!(
+            ErrorDetails::PnpBinaryResolveError { bin } => write!(
                 f,
-                "Could not parse package metadata
-from {}
+                "Could not resolve `{}` through Yarn Plug'n'Play.
 
-Please verify the requested package and version.",
-                from_url
+Please ensure that all project dependencies are installed with `yarn install`",
+                bin
             ),
-            ErrorDetails::ParsePlatformError => write!(
+            ErrorDetails::PolicyViolation {
+                tool,
+                version,
+                rule,
+                file,
+            } => write!(
                 f,
-                "Could not parse platform settings file.
+                "{} is not allowed by the version policy in {}, which only allows {}.
 
-{}",
-                REPORT_BUG_CTA
+Please choose a version that the policy allows, or ask the owners of the policy about {}.",
+                tool_version(tool, version),
+                file.display(),
+                rule,
+                tool_version(tool, version)
             ),
-            ErrorDetails::ParseToolSpecError { tool_spec } => write!(
+            ErrorDetails::ProjectCommandExecError { name } => write!(
                 f,
-                "Could not parse tool spec `{}`
+                r#"Could not run the project's "{}" command.
 
-Please supply a spec in the format `<tool name>[@<version>]`.",
-                tool_spec
+Please ensure that a shell is available to run it with."#,
+                name
             ),
+            ErrorDetails::ProjectCommandNotFound { name, available } => {
+                let call_to_action = if available.is_empty() {
+                    "Please add it to `commands` in the `volta` section of package.json.".to_string()
+                } else {
+                    format!("The project's commands are: {}", available.join(", "))
+                };
+                write!(
+                    f,
+                    r#"Could not find the command "{}" in this project.
+
+{}"#,
+                    name, call_to_action
+                )
+            }
             ErrorDetails::ProjectLocalBinaryExecError { command } => write!(
                 f,
                 "Could not execute `{}`
 
 Please ensure you have correct permissions to access the file.",
-                command
+                command.display()
             ),
             ErrorDetails::ProjectLocalBinaryNotFound { command } => write!(
                 f,
                 "Could not execute `{}`, the file does not exist.
 
 Please ensure that all project dependencies are installed with `npm install` or `yarn install`",
-                command
+                command.display()
+            ),
+            ErrorDetails::ProvisionError { dir } => write!(
+                f,
+                "Could not provision the platform into {}
+
+{}",
+                dir.display(),
+                PERMISSIONS_CTA
             ),
             ErrorDetails::PublishHookBothUrlAndBin => write!(
                 f,
@@ -953,6 +2026,33 @@ at {}
                 "Could not read executable configuration
 from {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadChecksumsError { file } => write!(
+                f,
+                "Could not read the checksums of an installed tool
+from {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadConfigError { file } => write!(
+                f,
+                "Could not read settings
+from {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadCustomShimsError { file } => write!(
+                f,
+                "Could not read the list of added shims
+from {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -962,6 +2062,23 @@ from {}
                 "Could not read default npm version
 from {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadDefaultPackagesError { file } => write!(
+                f,
+                "Could not read default packages from {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadEventQueueError { file } => write!(
+                f,
+                "Could not read event queue
+from {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -975,6 +2092,15 @@ from {}
                 file.display(),
                 PERMISSIONS_CTA
             ),
+            ErrorDetails::ReadImageError { dir } => write!(
+                f,
+                "Could not read the files of an installed tool
+in {}
+
+{}",
+                dir.display(),
+                PERMISSIONS_CTA
+            ),
             ErrorDetails::ReadInventoryDirError { dir } => write!(
                 f,
                 "Could not read tool inventory contents
@@ -984,29 +2110,47 @@ from {}
                 dir.display(),
                 PERMISSIONS_CTA
             ),
-            ErrorDetails::ReadNodeIndexCacheError { file } => write!(
+            ErrorDetails::ReadLayoutError { file } => write!(
                 f,
-                "Could not read Node index cache
+                "Could not read layout version
 from {}
 
 {}",
                 file.display(),
                 PERMISSIONS_CTA
             ),
-            ErrorDetails::ReadNodeIndexExpiryError { file } => write!(
+            ErrorDetails::ReadNpmManifestError => write!(
+                f,
+                "Could not read package.json file for bundled npm.
+
+Please ensure the version of Node is correct."
+            ),
+            ErrorDetails::ReadNpmrcError { file } => write!(
                 f,
-                "Could not read Node index cache expiration
+                "Could not read npm configuration file
 from {}
 
 {}",
                 file.display(),
                 PERMISSIONS_CTA
             ),
-            ErrorDetails::ReadNpmManifestError => write!(
+            ErrorDetails::ReadOnlyVoltaHome { dir } => write!(
                 f,
-                "Could not read package.json file for bundled npm.
+                "Could not install tools, because the Volta directory is read-only:
+{}
 
-Please ensure the version of Node is correct."
+Tools that are already installed can still be used. Please install new tools
+with a writable Volta directory, or set VOLTA_HOME to one.",
+                dir.display()
+            ),
+            ErrorDetails::ReadPackageConfigDirError { dir } => write!(
+                f,
+                "Could not read package metadata directory
+at {}
+
+{}",
+                dir.display(),
+                PERMISSIONS_CTA
             ),
             ErrorDetails::ReadPackageConfigError { file } => write!(
                 f,
@@ -1017,11 +2161,63 @@ from {}
                 file.display(),
                 PERMISSIONS_CTA
             ),
+            ErrorDetails::ReadPackageSourceError { source } => write!(
+                f,
+                "Could not read package
+from {}
+
+Please ensure that the path is correct and that the package is correctly formatted.",
+                source
+            ),
             ErrorDetails::ReadPlatformError { file } => write!(
                 f,
                 "Could not read default platform file
 from {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadPolicyError { file } => write!(
+                f,
+                "Could not read the version policy
+from {}
+
+Please ensure that VOLTA_POLICY or `policy` in your hooks points to the policy file.",
+                file.display()
+            ),
+            ErrorDetails::ReadProjectBinDirError { dir } => write!(
+                f,
+                "Could not read project executables directory
+at {}
+
+{}",
+                dir.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadShellProfileError { file } => write!(
+                f,
+                "Could not read shell profile
+from {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadShimDirError { dir } => write!(
+                f,
+                "Could not read shim directory
+at {}
+
+{}",
+                dir.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadUpdateCheckError { file } => write!(
+                f,
+                "Could not read update check
+from {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1034,6 +2230,21 @@ from {}
 Please verify your internet connection.",
                 tool, from_url
             ),
+            ErrorDetails::RuntimeNotPinned { tools, file } => write!(
+                f,
+                "This project's {} pins {} without pinning Node.
+
+Use `volta pin node` to pin Node as well, or set VOLTA_PARTIAL_PINS=allow to run with your default Node.",
+                file, tools
+            ),
+            ErrorDetails::SetPermissionsError { path } => write!(
+                f,
+                "Could not set permissions for {}
+
+{}",
+                path.display(),
+                PERMISSIONS_CTA
+            ),
             ErrorDetails::SetupToolImageError { tool, version, dir } => write!(
                 f,
                 "Could not create environment for {} v{}
@@ -1052,6 +2263,13 @@ at {}
 {}"#,
                 name, PERMISSIONS_CTA
             ),
+            ErrorDetails::ShimRecursionError { name } => write!(
+                f,
+                "Volta's shim for '{}' was run too many times in a row.
+
+This usually means that '{}' on your PATH, or a package binary, leads back to a Volta shim. Please check that it points to the real executable.",
+                name, name
+            ),
             // This case does not have a CTA as there is no avenue to allow users to remove built-in shims
             ErrorDetails::ShimRemoveBuiltInError { name } => {
                 write!(f, r#"Cannot remove built-in shim for "{}""#, name)
@@ -1091,9 +2309,38 @@ at {}
 {}",
                 REPORT_BUG_CTA
             ),
+            ErrorDetails::TmpDirNotWritable { dir } => write!(
+                f,
+                "Could not create files in the temporary directory {}
+
+{}",
+                dir.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::TmpDirPermissionsError { dir } => write!(
+                f,
+                "Could not use {} as the temporary directory, since files there can't be made executable.
+
+Please choose a directory on a filesystem that supports Unix permissions.",
+                dir.display()
+            ),
+            ErrorDetails::UndeclaredActivityKind { name } => write!(
+                f,
+                "Unknown activity kind: '{}'
+
+Please declare it in the `events.kinds` section of your hooks.",
+                name
+            ),
             ErrorDetails::Unimplemented { feature } => {
                 write!(f, "{} is not supported yet.", feature)
             }
+            ErrorDetails::UnknownConfigKey { key } => write!(
+                f,
+                "Unknown setting `{}`
+
+Use `volta config list` to see the settings that can be set.",
+                key
+            ),
             ErrorDetails::UnpackArchiveError { tool, version } => write!(
                 f,
                 "Could not unpack {} v{}
@@ -1115,6 +2362,20 @@ Please ensure you are using a supported shell.",
 Please ensure Volta was installed correctly."
             ),
             ErrorDetails::UnspecifiedShell => write!(f, "Volta shell not specified"),
+            ErrorDetails::UnsupportedReleaseChannel { tool, channel } => write!(
+                f,
+                "{} does not publish '{}' builds.
+
+Pre-release channels are only supported for Node.",
+                tool, channel
+            ),
+            ErrorDetails::UpgradePackagesError { packages } => write!(
+                f,
+                "Could not upgrade: {}
+
+Please see the warnings above for the reason each upgrade failed.",
+                packages.join(", ")
+            ),
             ErrorDetails::VersionParseError { version } => write!(
                 f,
                 r#"Could not parse version "{}"
@@ -1122,11 +2383,101 @@ Please ensure Volta was installed correctly."
 Please verify the intended version."#,
                 version
             ),
+            ErrorDetails::VoltaChecksumMismatch { version, from_url } => write!(
+                f,
+                "Downloaded Volta v{} does not match its published checksum
+from {}
+
+The download may be corrupted or tampered with, so it was not installed.",
+                version, from_url
+            ),
+            ErrorDetails::VoltaChecksumMissing { file, from_url } => write!(
+                f,
+                "Could not find the checksum of {}
+in {}
+
+Volta is only updated from releases with a published checksum. If you use your own release
+server, please set `checksums` in the `volta` hooks to the server publishing their checksums.",
+                file, from_url
+            ),
+            ErrorDetails::VoltaDownloadError { from_url } => write!(
+                f,
+                "Could not download Volta
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
+            ErrorDetails::VoltaInstallerError { version } => write!(
+                f,
+                "Could not run the installer for Volta v{}
+
+Please run the installer again from https://volta.sh",
+                version
+            ),
+            ErrorDetails::VoltaLatestFetchError { from_url } => write!(
+                f,
+                "Could not fetch latest version of Volta
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
+            ErrorDetails::VoltaOpensslVersionError => write!(
+                f,
+                "Could not determine the version of OpenSSL on this system.
+
+Volta's Linux releases are built for a specific version of OpenSSL. Please ensure that
+`openssl` is available on your PATH."
+            ),
+            ErrorDetails::VoltaReplaceError { file } => write!(
+                f,
+                "Could not replace Volta binary
+at {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WorkspaceGlobError { glob } => write!(
+                f,
+                "Could not parse workspace glob \"{}\".
+
+Please ensure that the workspaces of this project are correctly specified.",
+                glob
+            ),
             ErrorDetails::WriteBinConfigError { file } => write!(
                 f,
                 "Could not write executable configuration
 to {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteChecksumsError { file } => write!(
+                f,
+                "Could not write the checksums of an installed tool
+to {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteConfigError { file } => write!(
+                f,
+                "Could not save settings
+to {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteCustomShimsError { file } => write!(
+                f,
+                "Could not write the list of added shims
+to {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1140,24 +2491,41 @@ to {}
                 file.display(),
                 PERMISSIONS_CTA
             ),
-            ErrorDetails::WriteNodeIndexCacheError { file } => write!(
+            ErrorDetails::WriteEventLogError { file } => write!(
+                f,
+                "Could not write event log
+to {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteEventQueueError { file } => write!(
                 f,
-                "Could not write Node index cache
+                "Could not write event queue
 to {}
 
 {}",
                 file.display(),
                 PERMISSIONS_CTA
             ),
-            ErrorDetails::WriteNodeIndexExpiryError { file } => write!(
+            ErrorDetails::WriteLayoutError { file } => write!(
                 f,
-                "Could not write Node index cache expiration
+                "Could not write layout version
 to {}
 
 {}",
                 file.display(),
                 PERMISSIONS_CTA
             ),
+            ErrorDetails::WriteMatrixReportError { file } => write!(
+                f,
+                "Could not write the matrix report
+to {}
+
+Please ensure that you have permission to write to that location.",
+                file.display()
+            ),
             ErrorDetails::WritePackageConfigError { file } => write!(
                 f,
                 "Could not write package configuration
@@ -1187,6 +2555,24 @@ to {}
                 "Could not save platform settings
 to {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteShellProfileError { file } => write!(
+                f,
+                "Could not write shell profile
+to {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteUpdateCheckError { file } => write!(
+                f,
+                "Could not write update check
+to {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1210,43 +2596,305 @@ Please verify that the version is correct."#,
     }
 }
 
+impl ErrorDetails {
+    /// Returns a stable, machine-readable code identifying this kind of error.
+    ///
+    /// These codes are part of Volta's public interface for tools that wrap it, so an existing
+    /// code must never change, even if the variant it belongs to is renamed.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorDetails::ActivityKindExists { .. } => "activity_kind_exists",
+            ErrorDetails::AdvisoriesFetchError { .. } => "advisories_fetch_error",
+            ErrorDetails::ArchRequiresNode { .. } => "arch_requires_node",
+            ErrorDetails::ArchiveHashMismatch { .. } => "archive_hash_mismatch",
+            ErrorDetails::ArchiveHashUnknown { .. } => "archive_hash_unknown",
+            ErrorDetails::BinaryExecError => "binary_exec_error",
+            ErrorDetails::BinaryNotFound { .. } => "binary_not_found",
+            ErrorDetails::BuildPathError => "build_path_error",
+            ErrorDetails::BunPlatformNotSupported => "bun_platform_not_supported",
+            ErrorDetails::BunVersionNotFound { .. } => "bun_version_not_found",
+            ErrorDetails::BypassedBinaryNotFound { .. } => "bypassed_binary_not_found",
+            ErrorDetails::CannotPinPackage { .. } => "cannot_pin_package",
+            ErrorDetails::CannotSetPackageAsDefault { .. } => "cannot_set_package_as_default",
+            ErrorDetails::CompletionsOutFileError { .. } => "completions_out_file_error",
+            ErrorDetails::ConfigTooNew { .. } => "config_too_new",
+            ErrorDetails::ContainingDirError { .. } => "containing_dir_error",
+            ErrorDetails::CorepackEnableConflict => "corepack_enable_conflict",
+            ErrorDetails::CouldNotDetermineTool => "could_not_determine_tool",
+            ErrorDetails::CreateDirError { .. } => "create_dir_error",
+            ErrorDetails::CreatePostscriptError { .. } => "create_postscript_error",
+            ErrorDetails::CreateTempDirError { .. } => "create_temp_dir_error",
+            ErrorDetails::CurrentDirError => "current_dir_error",
+            ErrorDetails::DeleteDirectoryError { .. } => "delete_directory_error",
+            ErrorDetails::DeleteFileError { .. } => "delete_file_error",
+            ErrorDetails::DenoPlatformNotSupported => "deno_platform_not_supported",
+            ErrorDetails::DenoVersionNotFound { .. } => "deno_version_not_found",
+            ErrorDetails::DeprecatedCommandError { .. } => "deprecated_command_error",
+            ErrorDetails::DetermineBinaryLoaderError { .. } => "determine_binary_loader_error",
+            ErrorDetails::DownloadToolDiskFull { .. } => "download_tool_disk_full",
+            ErrorDetails::DownloadToolDnsError { .. } => "download_tool_dns_error",
+            ErrorDetails::DownloadToolForbidden { .. } => "download_tool_forbidden",
+            ErrorDetails::DownloadToolNetworkError { .. } => "download_tool_network_error",
+            ErrorDetails::DownloadToolNotFound { .. } => "download_tool_not_found",
+            ErrorDetails::DownloadToolTlsError { .. } => "download_tool_tls_error",
+            ErrorDetails::DryRunUnsupported { .. } => "dry_run_unsupported",
+            ErrorDetails::ExecutablePathError { .. } => "executable_path_error",
+            ErrorDetails::ExecutablePermissionsError { .. } => "executable_permissions_error",
+            ErrorDetails::ExecuteHookError { .. } => "execute_hook_error",
+            ErrorDetails::GitCheckoutError { .. } => "git_checkout_error",
+            ErrorDetails::GitCloneError { .. } => "git_clone_error",
+            ErrorDetails::HeadersRequireNode { .. } => "headers_require_node",
+            ErrorDetails::HookFormatNotSupported { .. } => "hook_format_not_supported",
+            ErrorDetails::HookMultipleFieldsSpecified => "hook_multiple_fields_specified",
+            ErrorDetails::HookNoFieldsSpecified => "hook_no_fields_specified",
+            ErrorDetails::IncompleteDownload { .. } => "incomplete_download",
+            ErrorDetails::InstalledArchiveHashMismatch { .. } => "installed_archive_hash_mismatch",
+            ErrorDetails::InvalidActivityRate { .. } => "invalid_activity_rate",
+            ErrorDetails::InvalidConfig { .. } => "invalid_config",
+            ErrorDetails::InvalidConfigValue { .. } => "invalid_config_value",
+            ErrorDetails::InvalidDefaultPackage { .. } => "invalid_default_package",
+            ErrorDetails::InvalidHookCommand { .. } => "invalid_hook_command",
+            ErrorDetails::InvalidHookOutput { .. } => "invalid_hook_output",
+            ErrorDetails::InvalidInstallPermissions { .. } => "invalid_install_permissions",
+            ErrorDetails::InvalidInvocation { .. } => "invalid_invocation",
+            ErrorDetails::InvalidKeepArchives { .. } => "invalid_keep_archives",
+            ErrorDetails::InvalidNodeArch { .. } => "invalid_node_arch",
+            ErrorDetails::InvalidPartialPinPolicy { .. } => "invalid_partial_pin_policy",
+            ErrorDetails::InvalidPassthroughPolicy { .. } => "invalid_passthrough_policy",
+            ErrorDetails::InvalidProfileName { .. } => "invalid_profile_name",
+            ErrorDetails::InvalidRateLimit { .. } => "invalid_rate_limit",
+            ErrorDetails::InvalidScriptPolicy { .. } => "invalid_script_policy",
+            ErrorDetails::InvalidTmpDir { .. } => "invalid_tmp_dir",
+            ErrorDetails::InvalidToolName { .. } => "invalid_tool_name",
+            ErrorDetails::LayoutTooNew { .. } => "layout_too_new",
+            ErrorDetails::LinkOutsidePackage => "link_outside_package",
+            ErrorDetails::LinkPackageError { .. } => "link_package_error",
+            ErrorDetails::MigrateLayoutError { .. } => "migrate_layout_error",
+            ErrorDetails::NoBinPlatform { .. } => "no_bin_platform",
+            ErrorDetails::NoPackageProvidesBin { .. } => "no_package_provides_bin",
+            ErrorDetails::NoPinnedPlatform { .. } => "no_pinned_platform",
+            ErrorDetails::NoShellProfiles => "no_shell_profiles",
+            ErrorDetails::NoUserBun => "no_user_bun",
+            ErrorDetails::NoUserDeno => "no_user_deno",
+            ErrorDetails::NoUserNodeForNpm => "no_user_node_for_npm",
+            ErrorDetails::NoWorkspaces => "no_workspaces",
+            ErrorDetails::NodeLockOutdated { .. } => "node_lock_outdated",
+            ErrorDetails::NodeRangeNotLocked { .. } => "node_range_not_locked",
+            ErrorDetails::NodeVersionNotFound { .. } => "node_version_not_found",
+            ErrorDetails::NoGlobalInstalls { .. } => "no_global_installs",
+            ErrorDetails::NoHomeEnvironmentVar => "no_home_environment_var",
+            ErrorDetails::NoInstallDir => "no_install_dir",
+            ErrorDetails::NoLocalDataDir => "no_local_data_dir",
+            ErrorDetails::NoPackageExecutables { .. } => "no_package_executables",
+            ErrorDetails::NoPinnedNodeRange => "no_pinned_node_range",
+            ErrorDetails::NoPinnedNodeVersion => "no_pinned_node_version",
+            ErrorDetails::NoPlatform => "no_platform",
+            ErrorDetails::NoProjectYarn => "no_project_yarn",
+            ErrorDetails::NotInPackage => "not_in_package",
+            ErrorDetails::NoUserYarn => "no_user_yarn",
+            ErrorDetails::NoVersionsFound => "no_versions_found",
+            ErrorDetails::NpxNotAvailable { .. } => "npx_not_available",
+            ErrorDetails::PackageBinNotFound { .. } => "package_bin_not_found",
+            ErrorDetails::PackageInstallFailed => "package_install_failed",
+            ErrorDetails::PackageIntegrityError { .. } => "package_integrity_error",
+            ErrorDetails::PackageMetadataFetchError { .. } => "package_metadata_fetch_error",
+            ErrorDetails::PackageNotFound { .. } => "package_not_found",
+            ErrorDetails::PackageNotInstalled { .. } => "package_not_installed",
+            ErrorDetails::PackageNotLinked { .. } => "package_not_linked",
+            ErrorDetails::PackageParseError { .. } => "package_parse_error",
+            ErrorDetails::PackageReadError { .. } => "package_read_error",
+            ErrorDetails::PackageRebuildFailed { .. } => "package_rebuild_failed",
+            ErrorDetails::PackageSourceManifestError { .. } => "package_source_manifest_error",
+            ErrorDetails::PackageUnpackError => "package_unpack_error",
+            ErrorDetails::PackageVersionNotFound { .. } => "package_version_not_found",
+            ErrorDetails::PackageWriteError { .. } => "package_write_error",
+            ErrorDetails::ParseAdvisoriesError { .. } => "parse_advisories_error",
+            ErrorDetails::ParseBinConfigError => "parse_bin_config_error",
+            ErrorDetails::ParseBunIndexError { .. } => "parse_bun_index_error",
+            ErrorDetails::ParseDenoIndexError { .. } => "parse_deno_index_error",
+            ErrorDetails::ParseHooksError { .. } => "parse_hooks_error",
+            ErrorDetails::ParsePnpmWorkspaceError { .. } => "parse_pnpm_workspace_error",
+            ErrorDetails::ParsePolicyError { .. } => "parse_policy_error",
+            ErrorDetails::ParseToolSpecError { .. } => "parse_tool_spec_error",
+            ErrorDetails::ParseNodeIndexCacheError => "parse_node_index_cache_error",
+            ErrorDetails::ParseNodeIndexError { .. } => "parse_node_index_error",
+            ErrorDetails::ParseNpmManifestError => "parse_npm_manifest_error",
+            ErrorDetails::ParsePackageConfigError => "parse_package_config_error",
+            ErrorDetails::ParsePackageMetadataError { .. } => "parse_package_metadata_error",
+            ErrorDetails::ParsePlatformError => "parse_platform_error",
+            ErrorDetails::ParseYarnIndexError { .. } => "parse_yarn_index_error",
+            ErrorDetails::PnpBinaryResolveError { .. } => "pnp_binary_resolve_error",
+            ErrorDetails::PolicyViolation { .. } => "policy_violation",
+            ErrorDetails::ProjectCommandExecError { .. } => "project_command_exec_error",
+            ErrorDetails::ProjectCommandNotFound { .. } => "project_command_not_found",
+            ErrorDetails::ProjectLocalBinaryExecError { .. } => "project_local_binary_exec_error",
+            ErrorDetails::ProjectLocalBinaryNotFound { .. } => "project_local_binary_not_found",
+            ErrorDetails::ProvisionError { .. } => "provision_error",
+            ErrorDetails::PublishHookBothUrlAndBin => "publish_hook_both_url_and_bin",
+            ErrorDetails::PublishHookNeitherUrlNorBin => "publish_hook_neither_url_nor_bin",
+            ErrorDetails::ReadBinConfigDirError { .. } => "read_bin_config_dir_error",
+            ErrorDetails::ReadBinConfigError { .. } => "read_bin_config_error",
+            ErrorDetails::ReadChecksumsError { .. } => "read_checksums_error",
+            ErrorDetails::ReadConfigError { .. } => "read_config_error",
+            ErrorDetails::ReadCustomShimsError { .. } => "read_custom_shims_error",
+            ErrorDetails::ReadDefaultNpmError { .. } => "read_default_npm_error",
+            ErrorDetails::ReadDefaultPackagesError { .. } => "read_default_packages_error",
+            ErrorDetails::ReadEventQueueError { .. } => "read_event_queue_error",
+            ErrorDetails::ReadHooksError { .. } => "read_hooks_error",
+            ErrorDetails::ReadImageError { .. } => "read_image_error",
+            ErrorDetails::ReadInventoryDirError { .. } => "read_inventory_dir_error",
+            ErrorDetails::ReadLayoutError { .. } => "read_layout_error",
+            ErrorDetails::ReadNpmManifestError => "read_npm_manifest_error",
+            ErrorDetails::ReadNpmrcError { .. } => "read_npmrc_error",
+            ErrorDetails::ReadOnlyVoltaHome { .. } => "read_only_volta_home",
+            ErrorDetails::ReadPackageConfigDirError { .. } => "read_package_config_dir_error",
+            ErrorDetails::ReadPackageConfigError { .. } => "read_package_config_error",
+            ErrorDetails::ReadPackageSourceError { .. } => "read_package_source_error",
+            ErrorDetails::ReadPlatformError { .. } => "read_platform_error",
+            ErrorDetails::ReadPolicyError { .. } => "read_policy_error",
+            ErrorDetails::ReadProjectBinDirError { .. } => "read_project_bin_dir_error",
+            ErrorDetails::ReadShellProfileError { .. } => "read_shell_profile_error",
+            ErrorDetails::ReadShimDirError { .. } => "read_shim_dir_error",
+            ErrorDetails::ReadUpdateCheckError { .. } => "read_update_check_error",
+            ErrorDetails::RegistryFetchError { .. } => "registry_fetch_error",
+            ErrorDetails::RuntimeNotPinned { .. } => "runtime_not_pinned",
+            ErrorDetails::SetPermissionsError { .. } => "set_permissions_error",
+            ErrorDetails::SetupToolImageError { .. } => "setup_tool_image_error",
+            ErrorDetails::ShimCreateError { .. } => "shim_create_error",
+            ErrorDetails::ShimRecursionError { .. } => "shim_recursion_error",
+            ErrorDetails::ShimRemoveBuiltInError { .. } => "shim_remove_built_in_error",
+            ErrorDetails::ShimRemoveError { .. } => "shim_remove_error",
+            ErrorDetails::StringifyBinConfigError => "stringify_bin_config_error",
+            ErrorDetails::StringifyPackageConfigError => "stringify_package_config_error",
+            ErrorDetails::StringifyPlatformError => "stringify_platform_error",
+            ErrorDetails::StringifyToolchainError => "stringify_toolchain_error",
+            ErrorDetails::TmpDirNotWritable { .. } => "tmp_dir_not_writable",
+            ErrorDetails::TmpDirPermissionsError { .. } => "tmp_dir_permissions_error",
+            ErrorDetails::UndeclaredActivityKind { .. } => "undeclared_activity_kind",
+            ErrorDetails::Unimplemented { .. } => "unimplemented",
+            ErrorDetails::UnknownConfigKey { .. } => "unknown_config_key",
+            ErrorDetails::UnpackArchiveError { .. } => "unpack_archive_error",
+            ErrorDetails::UnrecognizedShell { .. } => "unrecognized_shell",
+            ErrorDetails::UnspecifiedPostscript => "unspecified_postscript",
+            ErrorDetails::UnspecifiedShell => "unspecified_shell",
+            ErrorDetails::UnsupportedReleaseChannel { .. } => "unsupported_release_channel",
+            ErrorDetails::UpgradePackagesError { .. } => "upgrade_packages_error",
+            ErrorDetails::VersionParseError { .. } => "version_parse_error",
+            ErrorDetails::VoltaChecksumMismatch { .. } => "volta_checksum_mismatch",
+            ErrorDetails::VoltaChecksumMissing { .. } => "volta_checksum_missing",
+            ErrorDetails::VoltaDownloadError { .. } => "volta_download_error",
+            ErrorDetails::VoltaInstallerError { .. } => "volta_installer_error",
+            ErrorDetails::VoltaLatestFetchError { .. } => "volta_latest_fetch_error",
+            ErrorDetails::VoltaOpensslVersionError => "volta_openssl_version_error",
+            ErrorDetails::VoltaReplaceError { .. } => "volta_replace_error",
+            ErrorDetails::WorkspaceGlobError { .. } => "workspace_glob_error",
+            ErrorDetails::WriteBinConfigError { .. } => "write_bin_config_error",
+            ErrorDetails::WriteChecksumsError { .. } => "write_checksums_error",
+            ErrorDetails::WriteConfigError { .. } => "write_config_error",
+            ErrorDetails::WriteCustomShimsError { .. } => "write_custom_shims_error",
+            ErrorDetails::WriteDefaultNpmError { .. } => "write_default_npm_error",
+            ErrorDetails::WriteEventLogError { .. } => "write_event_log_error",
+            ErrorDetails::WriteEventQueueError { .. } => "write_event_queue_error",
+            ErrorDetails::WriteLayoutError { .. } => "write_layout_error",
+            ErrorDetails::WriteMatrixReportError { .. } => "write_matrix_report_error",
+            ErrorDetails::WritePackageConfigError { .. } => "write_package_config_error",
+            ErrorDetails::WritePackageShasumError { .. } => "write_package_shasum_error",
+            ErrorDetails::WritePlatformError { .. } => "write_platform_error",
+            ErrorDetails::WriteShellProfileError { .. } => "write_shell_profile_error",
+            ErrorDetails::WriteUpdateCheckError { .. } => "write_update_check_error",
+            ErrorDetails::YarnLatestFetchError { .. } => "yarn_latest_fetch_error",
+            ErrorDetails::YarnVersionNotFound { .. } => "yarn_version_not_found",
+        }
+    }
+}
+
 impl VoltaFail for ErrorDetails {
     fn exit_code(&self) -> ExitCode {
         match self {
-            ErrorDetails::BinaryAlreadyInstalled { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ActivityKindExists { .. } => ConfigurationError,
+            ErrorDetails::AdvisoriesFetchError { .. } => ExitCode::NetworkError,
+            ErrorDetails::ArchRequiresNode { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::ArchiveHashMismatch { .. } => ExitCode::NetworkError,
+            ErrorDetails::ArchiveHashUnknown { .. } => ExitCode::FileSystemError,
             ErrorDetails::BinaryExecError => ExitCode::ExecutionFailure,
             ErrorDetails::BinaryNotFound { .. } => ExitCode::ExecutableNotFound,
             ErrorDetails::BuildPathError => ExitCode::EnvironmentError,
+            ErrorDetails::BunPlatformNotSupported => ExitCode::EnvironmentError,
+            ErrorDetails::BunVersionNotFound { .. } => ExitCode::NoVersionMatch,
+            ErrorDetails::BypassedBinaryNotFound { .. } => ExitCode::ExecutableNotFound,
             ErrorDetails::CannotPinPackage { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::CannotSetPackageAsDefault { .. } => ExitCode::InvalidArguments,
             ErrorDetails::CompletionsOutFileError { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::ConfigTooNew { .. } => ExitCode::ConfigurationError,
             ErrorDetails::ContainingDirError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::CorepackEnableConflict => ExitCode::InvalidArguments,
             ErrorDetails::CouldNotDetermineTool => ExitCode::UnknownError,
             ErrorDetails::CreateDirError { .. } => ExitCode::FileSystemError,
             ErrorDetails::CreatePostscriptError { .. } => ExitCode::FileSystemError,
             ErrorDetails::CreateTempDirError { .. } => ExitCode::FileSystemError,
-            ErrorDetails::CreateTempFileError { .. } => ExitCode::FileSystemError,
             ErrorDetails::CurrentDirError => ExitCode::EnvironmentError,
             ErrorDetails::DeleteDirectoryError { .. } => ExitCode::FileSystemError,
             ErrorDetails::DeleteFileError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::DenoPlatformNotSupported => ExitCode::EnvironmentError,
+            ErrorDetails::DenoVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorDetails::DeprecatedCommandError { .. } => ExitCode::InvalidArguments,
             ErrorDetails::DetermineBinaryLoaderError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::DownloadToolDiskFull { .. } => ExitCode::FileSystemError,
+            ErrorDetails::DownloadToolDnsError { .. } => ExitCode::NetworkError,
+            ErrorDetails::DownloadToolForbidden { .. } => ExitCode::NetworkError,
             ErrorDetails::DownloadToolNetworkError { .. } => ExitCode::NetworkError,
+            ErrorDetails::DownloadToolNotFound { .. } => ExitCode::NoVersionMatch,
+            ErrorDetails::DownloadToolTlsError { .. } => ExitCode::NetworkError,
+            ErrorDetails::DryRunUnsupported { .. } => ExitCode::InvalidArguments,
             ErrorDetails::ExecutablePathError { .. } => ExitCode::UnknownError,
             ErrorDetails::ExecutablePermissionsError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ExecuteHookError { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::GitCheckoutError { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::GitCloneError { .. } => ExitCode::NetworkError,
+            ErrorDetails::HeadersRequireNode { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::HookFormatNotSupported { .. } => ExitCode::ConfigurationError,
             ErrorDetails::HookMultipleFieldsSpecified => ExitCode::ConfigurationError,
             ErrorDetails::HookNoFieldsSpecified => ExitCode::ConfigurationError,
+            ErrorDetails::IncompleteDownload { .. } => NetworkError,
+            ErrorDetails::InstalledArchiveHashMismatch { .. } => ExitCode::NetworkError,
+            ErrorDetails::InvalidActivityRate { .. } => ConfigurationError,
+            ErrorDetails::InvalidConfig { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::InvalidConfigValue { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::InvalidDefaultPackage { .. } => ExitCode::ConfigurationError,
             ErrorDetails::InvalidHookCommand { .. } => ExitCode::ExecutableNotFound,
             ErrorDetails::InvalidHookOutput { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::InvalidInstallPermissions { .. } => ExitCode::ConfigurationError,
             ErrorDetails::InvalidInvocation { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::InvalidKeepArchives { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::InvalidNodeArch { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::InvalidPartialPinPolicy { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::InvalidPassthroughPolicy { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::InvalidProfileName { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::InvalidRateLimit { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::InvalidScriptPolicy { .. } => ExitCode::EnvironmentError,
+            ErrorDetails::InvalidTmpDir { .. } => ExitCode::ConfigurationError,
             ErrorDetails::InvalidToolName { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::LayoutTooNew { .. } => ExitCode::EnvironmentError,
+            ErrorDetails::LinkOutsidePackage => ExitCode::ConfigurationError,
+            ErrorDetails::LinkPackageError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::MigrateLayoutError { .. } => ExitCode::FileSystemError,
             ErrorDetails::NoBinPlatform { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::NoPackageProvidesBin { .. } => ExitCode::ExecutableNotFound,
+            ErrorDetails::NoPinnedPlatform { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::NoShellProfiles => ExitCode::EnvironmentError,
+            ErrorDetails::NoUserBun => ExitCode::ConfigurationError,
+            ErrorDetails::NoUserDeno => ExitCode::ConfigurationError,
+            ErrorDetails::NoUserNodeForNpm => ExitCode::ConfigurationError,
+            ErrorDetails::NoWorkspaces => ExitCode::ConfigurationError,
+            ErrorDetails::NodeLockOutdated { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::NodeRangeNotLocked { .. } => ExitCode::ConfigurationError,
             ErrorDetails::NodeVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorDetails::NoGlobalInstalls { .. } => ExitCode::InvalidArguments,
             ErrorDetails::NoHomeEnvironmentVar => ExitCode::EnvironmentError,
             ErrorDetails::NoInstallDir => ExitCode::EnvironmentError,
             ErrorDetails::NoLocalDataDir => ExitCode::EnvironmentError,
             ErrorDetails::NoPackageExecutables { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::NoPinnedNodeRange => ExitCode::ConfigurationError,
             ErrorDetails::NoPinnedNodeVersion => ExitCode::ConfigurationError,
             ErrorDetails::NoPlatform => ExitCode::ConfigurationError,
             ErrorDetails::NoProjectYarn => ExitCode::ConfigurationError,
@@ -1254,60 +2902,114 @@ impl VoltaFail for ErrorDetails {
             ErrorDetails::NoUserYarn => ExitCode::ConfigurationError,
             ErrorDetails::NoVersionsFound => ExitCode::NoVersionMatch,
             ErrorDetails::NpxNotAvailable { .. } => ExitCode::ExecutableNotFound,
+            ErrorDetails::PackageBinNotFound { .. } => ExitCode::InvalidArguments,
             ErrorDetails::PackageInstallFailed => ExitCode::FileSystemError,
+            ErrorDetails::PackageIntegrityError { .. } => ExitCode::NetworkError,
             ErrorDetails::PackageMetadataFetchError { .. } => ExitCode::NetworkError,
             ErrorDetails::PackageNotFound { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::PackageNotInstalled { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::PackageNotLinked { .. } => ExitCode::InvalidArguments,
             ErrorDetails::PackageParseError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::PackageReadError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::PackageRebuildFailed { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::PackageSourceManifestError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::PackageUnpackError => ExitCode::ConfigurationError,
             ErrorDetails::PackageVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorDetails::PackageWriteError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ParseAdvisoriesError { .. } => ExitCode::UnknownError,
             ErrorDetails::ParseBinConfigError => ExitCode::UnknownError,
+            ErrorDetails::ParseBunIndexError { .. } => ExitCode::NetworkError,
+            ErrorDetails::ParseDenoIndexError { .. } => ExitCode::NetworkError,
             ErrorDetails::ParseHooksError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::ParsePnpmWorkspaceError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::ParsePolicyError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::ParseToolSpecError { .. } => ExitCode::InvalidArguments,
             ErrorDetails::ParseNodeIndexCacheError => ExitCode::UnknownError,
             ErrorDetails::ParseNodeIndexError { .. } => ExitCode::NetworkError,
-            ErrorDetails::ParseNodeIndexExpiryError => ExitCode::UnknownError,
             ErrorDetails::ParseNpmManifestError => ExitCode::UnknownError,
             ErrorDetails::ParsePackageConfigError => ExitCode::UnknownError,
             ErrorDetails::ParsePackageMetadataError { .. } => ExitCode::UnknownError,
             ErrorDetails::ParsePlatformError => ExitCode::ConfigurationError,
+            ErrorDetails::ParseYarnIndexError { .. } => ExitCode::NetworkError,
+            ErrorDetails::PnpBinaryResolveError { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::PolicyViolation { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::ProjectCommandExecError { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::ProjectCommandNotFound { .. } => ExitCode::ExecutableNotFound,
             ErrorDetails::ProjectLocalBinaryExecError { .. } => ExitCode::ExecutionFailure,
             ErrorDetails::ProjectLocalBinaryNotFound { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ProvisionError { .. } => ExitCode::FileSystemError,
             ErrorDetails::PublishHookBothUrlAndBin => ExitCode::ConfigurationError,
             ErrorDetails::PublishHookNeitherUrlNorBin => ExitCode::ConfigurationError,
             ErrorDetails::ReadBinConfigDirError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadBinConfigError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadChecksumsError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadConfigError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadCustomShimsError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadDefaultNpmError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadDefaultPackagesError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadEventQueueError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadHooksError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadImageError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadInventoryDirError { .. } => ExitCode::FileSystemError,
-            ErrorDetails::ReadNodeIndexCacheError { .. } => ExitCode::FileSystemError,
-            ErrorDetails::ReadNodeIndexExpiryError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadLayoutError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadNpmManifestError => ExitCode::UnknownError,
+            ErrorDetails::ReadNpmrcError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadOnlyVoltaHome { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadPackageConfigDirError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadPackageConfigError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadPackageSourceError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadPlatformError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadPolicyError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadProjectBinDirError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadShellProfileError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadShimDirError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadUpdateCheckError { .. } => ExitCode::FileSystemError,
             ErrorDetails::RegistryFetchError { .. } => ExitCode::NetworkError,
+            ErrorDetails::RuntimeNotPinned { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::SetPermissionsError { .. } => ExitCode::FileSystemError,
             ErrorDetails::SetupToolImageError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ShimCreateError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ShimRecursionError { .. } => ExitCode::ExecutionFailure,
             ErrorDetails::ShimRemoveBuiltInError { .. } => ExitCode::InvalidArguments,
             ErrorDetails::ShimRemoveError { .. } => ExitCode::FileSystemError,
             ErrorDetails::StringifyBinConfigError => ExitCode::UnknownError,
             ErrorDetails::StringifyPackageConfigError => ExitCode::UnknownError,
             ErrorDetails::StringifyPlatformError => ExitCode::UnknownError,
             ErrorDetails::StringifyToolchainError => ExitCode::UnknownError,
+            ErrorDetails::TmpDirNotWritable { .. } => ExitCode::FileSystemError,
+            ErrorDetails::TmpDirPermissionsError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::UndeclaredActivityKind { .. } => ConfigurationError,
             ErrorDetails::Unimplemented { .. } => ExitCode::UnknownError,
+            ErrorDetails::UnknownConfigKey { .. } => ExitCode::InvalidArguments,
             ErrorDetails::UnpackArchiveError { .. } => ExitCode::UnknownError,
             ErrorDetails::UnrecognizedShell { .. } => ExitCode::EnvironmentError,
             ErrorDetails::UnspecifiedPostscript => ExitCode::EnvironmentError,
             ErrorDetails::UnspecifiedShell => ExitCode::EnvironmentError,
+            ErrorDetails::UnsupportedReleaseChannel { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::UpgradePackagesError { .. } => ExitCode::UnknownError,
             ErrorDetails::VersionParseError { .. } => ExitCode::NoVersionMatch,
+            ErrorDetails::VoltaChecksumMismatch { .. } => ExitCode::NetworkError,
+            ErrorDetails::VoltaChecksumMissing { .. } => ExitCode::NetworkError,
+            ErrorDetails::VoltaDownloadError { .. } => ExitCode::NetworkError,
+            ErrorDetails::VoltaInstallerError { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::VoltaLatestFetchError { .. } => ExitCode::NetworkError,
+            ErrorDetails::VoltaOpensslVersionError => ExitCode::EnvironmentError,
+            ErrorDetails::VoltaReplaceError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WorkspaceGlobError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::WriteBinConfigError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteChecksumsError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteConfigError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteCustomShimsError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WriteDefaultNpmError { .. } => ExitCode::FileSystemError,
-            ErrorDetails::WriteNodeIndexCacheError { .. } => ExitCode::FileSystemError,
-            ErrorDetails::WriteNodeIndexExpiryError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteEventLogError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteEventQueueError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteLayoutError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteMatrixReportError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WritePackageConfigError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WritePackageShasumError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WritePlatformError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteShellProfileError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteUpdateCheckError { .. } => ExitCode::FileSystemError,
             ErrorDetails::YarnLatestFetchError { .. } => ExitCode::NetworkError,
             ErrorDetails::YarnVersionNotFound { .. } => ExitCode::NoVersionMatch,
         }
@@ -2,4 +2,4 @@ mod details;
 mod reporter;
 
 pub use details::{CreatePostscriptErrorPath, ErrorDetails};
-pub use reporter::report_error;
+pub use reporter::{report_error, ErrorFormat};
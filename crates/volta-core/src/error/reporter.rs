@@ -4,16 +4,54 @@ use std::fs::File;
 use std::io::Write as IoWrite;
 use std::path::PathBuf;
 
+use super::ErrorDetails;
+use crate::env::OUTPUT;
 use crate::fs::ensure_containing_dir_exists;
 use crate::path::log_dir;
 use crate::style::format_error_cause;
 use chrono::Local;
-use failure::Error;
+use failure::{Context, Error, Fail};
 use log::{debug, error};
+use serde::Serialize;
 use volta_fail::VoltaError;
 
+/// The code reported for errors that don't come from `ErrorDetails`.
+const UNKNOWN_ERROR_CODE: &str = "unknown_error";
+
+/// The format in which errors, and warnings, are reported on the console.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorFormat {
+    /// Human-readable messages, styled for the terminal.
+    Human,
+    /// A single JSON object, for tools that wrap Volta.
+    Json,
+}
+
+impl ErrorFormat {
+    /// Reads the format from the `VOLTA_OUTPUT` environment variable, defaulting to `Human`.
+    pub fn from_env() -> Self {
+        match env::var(OUTPUT) {
+            Ok(ref output) if output == "json" => ErrorFormat::Json,
+            _ => ErrorFormat::Human,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonError<'a> {
+    code: &'a str,
+    message: String,
+    exit_code: i32,
+    context: Vec<String>,
+}
+
 /// Report an error, both to the console and to error logs
-pub fn report_error(volta_version: &str, err: &VoltaError) {
+pub fn report_error(volta_version: &str, err: &VoltaError, format: ErrorFormat) {
+    if format == ErrorFormat::Json {
+        report_json_error(volta_version, err);
+        return;
+    }
+
     let message = err.to_string();
     error!("{}", message);
 
@@ -34,6 +72,53 @@ pub fn report_error(volta_version: &str, err: &VoltaError) {
     }
 }
 
+/// Report an error as a JSON object on stderr, writing the details to the error log without
+/// mentioning it, so that stderr contains nothing but the JSON
+fn report_json_error(volta_version: &str, err: &VoltaError) {
+    let message = err.to_string();
+
+    if let Some(details) = compose_error_details(err) {
+        let _ = write_error_log(volta_version, message.clone(), details);
+    }
+
+    let json = JsonError {
+        code: error_code(err),
+        message,
+        exit_code: err.exit_code() as i32,
+        context: error_context(err),
+    };
+
+    match serde_json::to_string(&json) {
+        Ok(json) => eprintln!("{}", json),
+        Err(_) => eprintln!("Unable to serialize error!"),
+    }
+}
+
+/// Returns the stable code of an error, or `unknown_error` if it isn't one of ours
+fn error_code(err: &VoltaError) -> &'static str {
+    // Errors created with `with_context` wrap their details in a `Context`
+    err.downcast_ref::<ErrorDetails>()
+        .or_else(|| {
+            err.downcast_ref::<Context<ErrorDetails>>()
+                .map(Context::get_context)
+        })
+        .map(ErrorDetails::code)
+        .unwrap_or(UNKNOWN_ERROR_CODE)
+}
+
+/// Collects the messages of the underlying causes of an error, outermost first
+fn error_context(err: &VoltaError) -> Vec<String> {
+    let mut context = Vec::new();
+    let mut current = err.as_fail().cause();
+
+    while let Some(cause) = current {
+        context.push(cause.to_string());
+        current = cause.cause();
+    }
+
+    context
+}
+
 /// Write an error log with all details about the error
 fn write_error_log(
     volta_version: &str,
@@ -101,3 +186,27 @@ fn collect_arguments() -> String {
         .collect::<Vec<String>>()
         .join(" ")
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::{error_code, error_context};
+    use crate::error::ErrorDetails;
+    use std::io;
+    use volta_fail::{FailExt, ResultExt, VoltaError};
+
+    #[test]
+    fn reports_codes_of_wrapped_details() {
+        let err: VoltaError = ErrorDetails::NoPinnedNodeVersion.into();
+        assert_eq!(error_code(&err), "no_pinned_node_version");
+        assert!(error_context(&err).is_empty());
+
+        let err = Err::<(), _>(io::Error::new(io::ErrorKind::Other, "disk on fire"))
+            .with_context(|_| ErrorDetails::NoHomeEnvironmentVar)
+            .unwrap_err();
+        assert_eq!(error_code(&err), "no_home_environment_var");
+        assert_eq!(error_context(&err), vec!["disk on fire".to_string()]);
+
+        let err = io::Error::new(io::ErrorKind::Other, "disk on fire").unknown();
+        assert_eq!(error_code(&err), "unknown_error");
+    }
+}
@@ -2,15 +2,28 @@
 
 use os_info;
 
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
 use std::env;
+use std::fs::{metadata, rename, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use log::debug;
 use serde::Serialize;
 
-use crate::hook::Publish;
+use crate::error::ErrorDetails;
+use crate::event_sink::EventSink;
+use crate::fs::ensure_containing_dir_exists;
+use crate::hook::{ActivityRates, Publish};
 use crate::monitor::Monitor;
 use crate::session::ActivityKind;
-use volta_fail::{ExitCode, VoltaError};
+use volta_fail::{ExitCode, Fallible, ResultExt, VoltaError};
+
+/// The size at which the local event log is rotated, keeping a single previous file.
+const MAX_EVENT_LOG_SIZE: u64 = 1024 * 1024;
 
 // the Event data that is serialized to JSON and sent the plugin
 #[derive(Serialize)]
@@ -126,10 +139,25 @@ impl EventLog {
         self.events.push(event);
     }
 
+    /// Drops the events of the activities that aren't sampled this time, given how often each
+    /// kind of activity is recorded. An activity's events are all kept or all dropped together,
+    /// so that each recorded start has its end.
+    pub fn sample(&mut self, rates: &ActivityRates) {
+        let mut sampled = HashMap::new();
+        self.events.retain(|event| {
+            *sampled
+                .entry(event.name.clone())
+                .or_insert_with(|| is_sampled(rates.rate(&event.name)))
+        });
+    }
+
     pub fn publish(&mut self, plugin: Option<&Publish>) {
         match plugin {
-            // Note: This call to unimplemented is left in, as it's not a Fallible operation that can use ErrorDetails::Unimplemented
-            Some(&Publish::Url(_)) => unimplemented!(),
+            Some(&Publish::Url(ref url)) => {
+                if let Err(error) = EventSink::new(url).send_events(&self.events) {
+                    debug!("Unable to publish events to {}.\n{}", url, error);
+                }
+            }
             Some(&Publish::Bin(ref command)) => {
                 let mut monitor = Monitor::new(command);
                 monitor.send_events(&self.events);
@@ -137,6 +165,59 @@ impl EventLog {
             None => {}
         }
     }
+
+    /// Appends the events to a local log file as JSON lines, first moving the file aside to
+    /// `<file>.1` (replacing any older one) if it has grown too large.
+    pub fn write_to_file(&self, file: &Path) -> Fallible<()> {
+        self.append_to_file(file, MAX_EVENT_LOG_SIZE)
+    }
+
+    fn append_to_file(&self, file: &Path, max_size: u64) -> Fallible<()> {
+        if self.events.is_empty() {
+            return Ok(());
+        }
+
+        ensure_containing_dir_exists(&file)?;
+        if let Ok(existing) = metadata(file) {
+            if existing.len() >= max_size {
+                let mut rotated = file.as_os_str().to_owned();
+                rotated.push(".1");
+                rename(file, rotated).with_context(write_error(file))?;
+            }
+        }
+
+        // Write all of the lines at once, so that events from concurrent invocations don't
+        // interleave within a line
+        let mut lines = String::new();
+        for event in &self.events {
+            lines.push_str(&serde_json::to_string(event).with_context(write_error(file))?);
+            lines.push('\n');
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+            .and_then(|mut log| log.write_all(lines.as_bytes()))
+            .with_context(write_error(file))
+    }
+}
+
+fn is_sampled(rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+
+    // The standard library seeds each `RandomState` randomly, which is plenty for sampling
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(unix_timestamp());
+    let fraction = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+    fraction < rate
+}
+
+fn write_error<E>(file: &Path) -> impl FnOnce(&E) -> ErrorDetails {
+    let file = file.to_path_buf();
+    |_| ErrorDetails::WriteEventLogError { file }
 }
 
 #[cfg(test)]
@@ -144,7 +225,11 @@ pub mod tests {
 
     use super::EventLog;
     use crate::error::ErrorDetails;
+    use crate::hook::ActivityRates;
     use crate::session::ActivityKind;
+    use std::collections::HashMap;
+    use std::fs::read_to_string;
+    use tempfile::tempdir;
     use volta_fail::ExitCode;
 
     #[test]
@@ -169,4 +254,62 @@ pub mod tests {
         assert_eq!(event_log.events.len(), 4);
         assert_eq!(event_log.events[3].name, "install");
     }
+
+    #[test]
+    fn test_appending_to_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("log").join("events.jsonl");
+        let rotated = dir.path().join("log").join("events.jsonl.1");
+
+        let mut event_log = EventLog::new();
+        event_log.add_event_start(ActivityKind::Install);
+        event_log.add_event_end(ActivityKind::Install, ExitCode::Success);
+
+        event_log.append_to_file(&file, 1024).unwrap();
+        event_log.append_to_file(&file, 1024).unwrap();
+        let contents = read_to_string(&file).unwrap();
+        assert_eq!(contents.lines().count(), 4);
+        assert!(contents
+            .lines()
+            .all(|line| line.contains(r#""name":"install""#)));
+        assert!(!rotated.exists());
+
+        // once the file reaches the maximum size, it is moved aside before appending
+        event_log.append_to_file(&file, 1).unwrap();
+        assert_eq!(read_to_string(&rotated).unwrap(), contents);
+        assert_eq!(read_to_string(&file).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn test_sampling_activities() {
+        let mut rates = HashMap::new();
+        rates.insert("binary".to_string(), 0.0);
+        rates.insert("install".to_string(), 1.0);
+        let rates = ActivityRates::new(rates).unwrap();
+
+        let mut event_log = EventLog::new();
+        event_log.add_event_start(ActivityKind::Binary);
+        event_log.add_event_start(ActivityKind::Install);
+        event_log.add_event_start(ActivityKind::Pin);
+        event_log.add_event_end(ActivityKind::Pin, ExitCode::Success);
+        event_log.add_event_end(ActivityKind::Install, ExitCode::Success);
+        event_log.add_event_tool_end(ActivityKind::Binary, 0);
+
+        event_log.sample(&rates);
+        let names: Vec<&str> = event_log
+            .events
+            .iter()
+            .map(|event| event.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["install", "pin", "pin", "install"]);
+    }
+
+    #[test]
+    fn test_built_in_activity_kinds() {
+        let install = ActivityKind::built_in("install").unwrap();
+        assert!(install == ActivityKind::Install);
+        assert_eq!(install.to_string(), "install");
+        assert_eq!(ActivityKind::SelfUpdate.to_string(), "self-update");
+        assert!(ActivityKind::built_in("lint").is_none());
+    }
 }
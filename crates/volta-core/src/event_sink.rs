@@ -0,0 +1,597 @@
+//! Provides the `EventSink` type, which sends events to the URL configured in the `publish`
+//! events hook.
+//!
+//! Events are redacted and then added to a queue on disk. Once the queue holds a full batch, a
+//! detached `volta send-events` process claims it and sends it, so that shims are never held up
+//! by the endpoint. If sending fails (for instance, because the machine is offline), the events
+//! are put back in the queue and are retried with the next batch, after a delay.
+
+use std::env;
+use std::fs::{metadata, remove_file, rename, write, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use dirs;
+use log::debug;
+use reqwest;
+use serde_json::{self, Value};
+
+use crate::command::{create_command, detach};
+use crate::error::ErrorDetails;
+use crate::event::Event;
+use crate::fs::{ensure_containing_dir_exists, read_dir_eager, read_file_opt};
+use crate::path;
+use volta_fail::{Fallible, ResultExt};
+
+/// The number of events to collect before sending them.
+const BATCH_SIZE: usize = 50;
+
+/// The most events to keep in the queue while they can't be sent; older events are dropped.
+const MAX_QUEUED_EVENTS: usize = 5000;
+
+/// How long to wait after failing to send events before trying again.
+const RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// The suffix of the files that events are set aside in when they can't be put back in the queue.
+const UNSENT_SUFFIX: &str = ".unsent";
+
+/// How long to wait for the endpoint to respond.
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to keep trying to take the lock on the queue before giving up on queueing the events.
+const LOCK_WAIT: Duration = Duration::from_secs(1);
+
+/// How long a lock on the queue is held at most. A process that was killed before it could remove
+/// its lock only holds up the others until then.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct EventSink {
+    url: String,
+}
+
+impl EventSink {
+    pub fn new(url: &str) -> Self {
+        EventSink {
+            url: url.to_string(),
+        }
+    }
+
+    /// Queues the events, starting a background process to send the queue if it holds a full
+    /// batch.
+    pub fn send_events(&self, events: &[Event]) -> Fallible<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        if path::is_read_only_home() {
+            debug!("Not queueing events, since the Volta directory is read-only.");
+            return Ok(());
+        }
+
+        let redactions = Redactions::current();
+        let events = events
+            .iter()
+            .map(|event| serde_json::to_value(event).map(|value| redactions.apply(value)))
+            .collect::<Result<Vec<Value>, _>>()
+            .unknown()?;
+
+        let queue_file = path::event_queue_file()?;
+        let queued = match QueueLock::acquire(&queue_file)? {
+            Some(_lock) => queue_events(&queue_file, events)?,
+            None => {
+                debug!("Not queueing events, since the queue is locked.");
+                return Ok(());
+            }
+        };
+        if queued < BATCH_SIZE || recently_failed(&queue_file) {
+            return Ok(());
+        }
+
+        let mut command = create_command(path::volta_file()?);
+        command
+            .args(&["send-events", "--url", &self.url])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        detach(&mut command);
+
+        debug!(
+            "Sending {} events to {} in the background",
+            queued, self.url
+        );
+        command.spawn().unknown()?;
+        Ok(())
+    }
+
+    /// Claims the queue and sends it, putting the events back in the queue if that fails. This is
+    /// run by `volta send-events`, in the process that `send_events` starts.
+    pub fn flush(&self) -> Fallible<()> {
+        let queue_file = path::event_queue_file()?;
+        let sending_file = sending_file(&queue_file);
+
+        // Moving the queue aside claims it, so that events which are queued in the meantime (or
+        // another process that was started to send it) don't interfere
+        match QueueLock::acquire(&queue_file)? {
+            Some(_lock) => {
+                restore_unsent_events(&queue_file)?;
+                match rename(&queue_file, &sending_file) {
+                    Ok(()) => {}
+                    Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+                    Err(error) => return Err(error).with_context(queue_error(&queue_file)),
+                }
+            }
+            None => return Ok(()),
+        }
+
+        let events = read_queue(&sending_file)?;
+        debug!("Sending {} events to {}", events.len(), self.url);
+        match post_events(&self.url, &events) {
+            Ok(()) => {
+                let _ = remove_file(retry_file(&queue_file));
+            }
+            Err(error) => {
+                debug!("Could not send events, will retry later: {}", error);
+                let retry_file = retry_file(&queue_file);
+                File::create(&retry_file).with_context(queue_error(&retry_file))?;
+
+                match QueueLock::acquire(&queue_file)? {
+                    Some(_lock) => {
+                        requeue_events(&queue_file, events)?;
+                    }
+                    None => {
+                        // The events are set aside rather than lost, and the next process to
+                        // claim the queue puts them back
+                        debug!("Setting the events aside, since the queue is locked.");
+                        let unsent_file = unsent_file(&queue_file);
+                        return rename(&sending_file, &unsent_file)
+                            .with_context(queue_error(&unsent_file));
+                    }
+                }
+            }
+        }
+
+        remove_file(&sending_file).with_context(queue_error(&sending_file))
+    }
+}
+
+/// Adds events to the end of the queue, dropping the oldest events if it grows too large, and
+/// returns how many events it holds.
+fn queue_events(queue_file: &Path, events: Vec<Value>) -> Fallible<usize> {
+    let mut queue = read_queue(queue_file)?;
+    queue.extend(events);
+    write_queue(queue_file, queue)
+}
+
+/// Puts events that couldn't be sent back at the start of the queue, ahead of the events that were
+/// queued while they were being sent.
+fn requeue_events(queue_file: &Path, mut events: Vec<Value>) -> Fallible<usize> {
+    events.extend(read_queue(queue_file)?);
+    write_queue(queue_file, events)
+}
+
+/// Puts the events that were set aside, because the queue was locked when they couldn't be sent,
+/// back at the start of the queue. This must only be called while holding the lock.
+fn restore_unsent_events(queue_file: &Path) -> Fallible<()> {
+    let dir = match queue_file.parent() {
+        Some(dir) => dir,
+        None => return Ok(()),
+    };
+    let prefix = queue_file.file_name().unwrap_or_default().to_string_lossy();
+    let entries = match read_dir_eager(dir) {
+        Ok(entries) => entries,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error).with_context(queue_error(queue_file)),
+    };
+
+    let mut unsent_files = entries
+        .map(|(entry, _)| entry.path())
+        .filter(|file| {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with(&*prefix) && name.ends_with(UNSENT_SUFFIX)
+        })
+        .collect::<Vec<_>>();
+    unsent_files.sort();
+
+    for unsent_file in unsent_files.iter().rev() {
+        requeue_events(queue_file, read_queue(unsent_file)?)?;
+        remove_file(unsent_file).with_context(queue_error(unsent_file))?;
+    }
+    Ok(())
+}
+
+fn read_queue(queue_file: &Path) -> Fallible<Vec<Value>> {
+    let src = read_file_opt(&queue_file.to_path_buf()).with_context(|_| {
+        ErrorDetails::ReadEventQueueError {
+            file: queue_file.to_path_buf(),
+        }
+    })?;
+
+    // A line that can't be parsed was most likely cut off when a process was killed, so it is
+    // dropped
+    Ok(src
+        .iter()
+        .flat_map(|src| src.lines())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn write_queue(queue_file: &Path, mut queue: Vec<Value>) -> Fallible<usize> {
+    if queue.len() > MAX_QUEUED_EVENTS {
+        queue.drain(..queue.len() - MAX_QUEUED_EVENTS);
+    }
+
+    let mut lines = String::new();
+    for event in &queue {
+        lines.push_str(&event.to_string());
+        lines.push('\n');
+    }
+
+    ensure_containing_dir_exists(&queue_file)?;
+    write(queue_file, lines).with_context(queue_error(queue_file))?;
+
+    Ok(queue.len())
+}
+
+fn post_events(url: &str, events: &[Value]) -> Result<(), reqwest::Error> {
+    reqwest::Client::builder()
+        .timeout(SEND_TIMEOUT)
+        .build()?
+        .post(url)
+        .json(events)
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Determines whether sending the queue failed within the retry delay.
+fn recently_failed(queue_file: &Path) -> bool {
+    modified_within(&retry_file(queue_file), RETRY_DELAY)
+}
+
+fn modified_within(file: &Path, duration: Duration) -> bool {
+    metadata(file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map(|elapsed| elapsed < duration)
+        .unwrap_or(false)
+}
+
+fn retry_file(queue_file: &Path) -> PathBuf {
+    sibling_file(queue_file, ".failed")
+}
+
+fn lock_file(queue_file: &Path) -> PathBuf {
+    sibling_file(queue_file, ".lock")
+}
+
+/// Names the file that a process moves the queue to while sending it, after the process.
+fn sending_file(queue_file: &Path) -> PathBuf {
+    sibling_file(queue_file, &format!(".{}.sending", process::id()))
+}
+
+/// Names the file that a process sets events aside in when it can't send them or put them back
+/// in the queue, after the process.
+fn unsent_file(queue_file: &Path) -> PathBuf {
+    sibling_file(queue_file, &format!(".{}{}", process::id(), UNSENT_SUFFIX))
+}
+
+fn sibling_file(queue_file: &Path, suffix: &str) -> PathBuf {
+    let mut file = queue_file.as_os_str().to_owned();
+    file.push(suffix);
+    PathBuf::from(file)
+}
+
+fn queue_error<E>(file: &Path) -> impl FnOnce(&E) -> ErrorDetails {
+    let file = file.to_path_buf();
+    |_| ErrorDetails::WriteEventQueueError { file }
+}
+
+/// An exclusive lock on the queue, which is released when it is dropped.
+struct QueueLock {
+    file: PathBuf,
+}
+
+impl QueueLock {
+    /// Takes the lock, waiting up to `LOCK_WAIT` for another process to release it. Returns `None`
+    /// if it is still held by then.
+    fn acquire(queue_file: &Path) -> Fallible<Option<Self>> {
+        let file = lock_file(queue_file);
+        ensure_containing_dir_exists(&file)?;
+
+        let started = SystemTime::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&file) {
+                Ok(_) => return Ok(Some(QueueLock { file })),
+                Err(ref error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error).with_context(queue_error(&file)),
+            }
+
+            if !modified_within(&file, LOCK_TIMEOUT) {
+                debug!("Taking over the stale lock {}", file.display());
+                let _ = remove_file(&file);
+                continue;
+            }
+            if started
+                .elapsed()
+                .map_or(true, |elapsed| elapsed >= LOCK_WAIT)
+            {
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for QueueLock {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.file);
+    }
+}
+
+/// Personal details that are removed from the fields of events which hold paths and command
+/// lines, before the events leave the machine.
+struct Redactions {
+    home: Option<String>,
+    user: Option<String>,
+}
+
+impl Redactions {
+    /// Redacts the current user's home directory and username.
+    fn current() -> Self {
+        let home = dirs::home_dir().map(|home| home.to_string_lossy().into_owned());
+        let user = env::var("USER").or_else(|_| env::var("USERNAME")).ok();
+        Redactions::new(home, user)
+    }
+
+    fn new(home: Option<String>, user: Option<String>) -> Self {
+        Redactions {
+            home: home.filter(|home| !home.is_empty()),
+            user: user.filter(|user| !user.is_empty()),
+        }
+    }
+
+    fn apply(&self, mut event: Value) -> Value {
+        if let Some(Value::String(path)) = event.pointer_mut("/event/error/env/path") {
+            *path = self.redact_search_path(path);
+        }
+        if let Some(Value::String(exec_path)) = event.pointer_mut("/event/error/env/exec_path") {
+            *exec_path = self.redact_path(exec_path);
+        }
+        for field in &["/event/error/env/argv", "/event/error/error"] {
+            if let Some(Value::String(text)) = event.pointer_mut(field) {
+                *text = self.redact_words(text);
+            }
+        }
+        event
+    }
+
+    /// Redacts each of the directories in a search path, like `PATH`.
+    fn redact_search_path(&self, search_path: &str) -> String {
+        let redacted = env::split_paths(search_path)
+            .map(|dir| self.redact_path(&dir.to_string_lossy()))
+            .collect::<Vec<String>>();
+        env::join_paths(redacted)
+            .map(|joined| joined.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| search_path.to_string())
+    }
+
+    /// Redacts the paths in text, such as a command line or an error message, taking each word
+    /// that starts with a path (after any quotes or brackets) to be one.
+    fn redact_words(&self, text: &str) -> String {
+        text.split(' ')
+            .map(|word| {
+                let start = word
+                    .find(|c: char| c.is_alphanumeric() || is_separator(c) || c == '~')
+                    .unwrap_or(word.len());
+                format!("{}{}", &word[..start], self.redact_path(&word[start..]))
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Replaces the home directory at the start of a path with `~`, and any component of the path
+    /// that is the username with `<user>`. Text that isn't a path is left as it is.
+    fn redact_path(&self, path: &str) -> String {
+        let mut redacted = path.to_string();
+        if let Some(ref home) = self.home {
+            if path.starts_with(home.as_str())
+                && path[home.len()..].chars().next().map_or(true, is_separator)
+            {
+                redacted = format!("~{}", &path[home.len()..]);
+            }
+        }
+
+        let user = match self.user {
+            Some(ref user) if redacted.contains(is_separator) => user,
+            _ => return redacted,
+        };
+        let mut result = String::new();
+        let mut component = String::new();
+        for c in redacted.chars().chain(Some('/')) {
+            if is_separator(c) {
+                result.push_str(if component == *user {
+                    "<user>"
+                } else {
+                    &component
+                });
+                component.clear();
+                result.push(c);
+            } else {
+                component.push(c);
+            }
+        }
+        result.pop();
+        result
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{
+        queue_events, requeue_events, restore_unsent_events, unsent_file, QueueLock, Redactions,
+        MAX_QUEUED_EVENTS,
+    };
+    use serde_json::{json, Value};
+    use std::env;
+    use std::fs::{read_to_string, write};
+    use tempfile::tempdir;
+
+    fn search_path(dirs: &[&str]) -> String {
+        env::join_paths(dirs)
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn redacts_paths_in_error_details() {
+        let redactions =
+            Redactions::new(Some("/home/chris".to_string()), Some("chris".to_string()));
+        let event = json!({
+            "name": "chris",
+            "event": {
+                "error": {
+                    "exit_code": 126,
+                    "error": "Could not read `/home/chris/.volta/tools` as chris",
+                    "env": {
+                        "argv": "/home/chris/.volta/bin/node --user chris /srv/chris/app.js",
+                        "exec_path": "/home/chris/.volta/bin/volta-shim",
+                        "path": search_path(&["/home/chris/bin", "/home/christine/bin", "/usr/bin"]),
+                        "platform": "Linux",
+                        "platform_version": "6.1"
+                    }
+                }
+            }
+        });
+
+        assert_eq!(
+            redactions.apply(event),
+            json!({
+                "name": "chris",
+                "event": {
+                    "error": {
+                        "exit_code": 126,
+                        "error": "Could not read `~/.volta/tools` as chris",
+                        "env": {
+                            "argv": "~/.volta/bin/node --user chris /srv/<user>/app.js",
+                            "exec_path": "~/.volta/bin/volta-shim",
+                            "path": search_path(&["~/bin", "/home/christine/bin", "/usr/bin"]),
+                            "platform": "Linux",
+                            "platform_version": "6.1"
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn queues_events() {
+        let dir = tempdir().unwrap();
+        let queue_file = dir.path().join("events-queue.jsonl");
+
+        assert_eq!(
+            queue_events(&queue_file, vec![json!({ "name": "first" })]).unwrap(),
+            1
+        );
+
+        // partially written lines are dropped
+        let mut src = read_to_string(&queue_file).unwrap();
+        src.push_str("{\"name\":\"trunc\n");
+        write(&queue_file, src).unwrap();
+
+        assert_eq!(
+            queue_events(&queue_file, vec![json!({ "name": "second" })]).unwrap(),
+            2
+        );
+        assert_eq!(
+            read_to_string(&queue_file).unwrap(),
+            "{\"name\":\"first\"}\n{\"name\":\"second\"}\n"
+        );
+    }
+
+    #[test]
+    fn requeues_unsent_events_first() {
+        let dir = tempdir().unwrap();
+        let queue_file = dir.path().join("events-queue.jsonl");
+
+        queue_events(&queue_file, vec![json!({ "name": "later" })]).unwrap();
+        requeue_events(&queue_file, vec![json!({ "name": "unsent" })]).unwrap();
+
+        assert_eq!(
+            read_to_string(&queue_file).unwrap(),
+            "{\"name\":\"unsent\"}\n{\"name\":\"later\"}\n"
+        );
+    }
+
+    #[test]
+    fn restores_events_set_aside() {
+        let dir = tempdir().unwrap();
+        let queue_file = dir.path().join("events-queue.jsonl");
+        let unsent_file = unsent_file(&queue_file);
+
+        queue_events(&queue_file, vec![json!({ "name": "later" })]).unwrap();
+        write(&unsent_file, "{\"name\":\"unsent\"}\n").unwrap();
+        write(dir.path().join("other.unsent"), "{\"name\":\"other\"}\n").unwrap();
+
+        restore_unsent_events(&queue_file).unwrap();
+
+        assert_eq!(
+            read_to_string(&queue_file).unwrap(),
+            "{\"name\":\"unsent\"}\n{\"name\":\"later\"}\n"
+        );
+        assert!(!unsent_file.exists());
+        assert!(dir.path().join("other.unsent").exists());
+    }
+
+    #[test]
+    fn restores_events_set_aside_without_a_queue() {
+        let dir = tempdir().unwrap();
+        let queue_file = dir.path().join("events-queue.jsonl");
+        write(unsent_file(&queue_file), "{\"name\":\"unsent\"}\n").unwrap();
+
+        restore_unsent_events(&queue_file).unwrap();
+
+        assert_eq!(
+            read_to_string(&queue_file).unwrap(),
+            "{\"name\":\"unsent\"}\n"
+        );
+    }
+
+    #[test]
+    fn drops_oldest_events() {
+        let dir = tempdir().unwrap();
+        let queue_file = dir.path().join("events-queue.jsonl");
+
+        let events: Vec<Value> = (0..MAX_QUEUED_EVENTS + 2)
+            .map(|index| json!({ "index": index }))
+            .collect();
+        assert_eq!(
+            queue_events(&queue_file, events).unwrap(),
+            MAX_QUEUED_EVENTS
+        );
+
+        let src = read_to_string(&queue_file).unwrap();
+        assert_eq!(src.lines().next(), Some("{\"index\":2}"));
+    }
+
+    #[test]
+    fn only_one_process_holds_the_lock() {
+        let dir = tempdir().unwrap();
+        let queue_file = dir.path().join("events-queue.jsonl");
+
+        let lock = QueueLock::acquire(&queue_file).unwrap();
+        assert!(lock.is_some());
+        assert!(QueueLock::acquire(&queue_file).unwrap().is_none());
+
+        drop(lock);
+        assert!(QueueLock::acquire(&queue_file).unwrap().is_some());
+    }
+}
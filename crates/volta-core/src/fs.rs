@@ -1,10 +1,16 @@
 //! Provides utilities for operating on the filesystem.
 
 use std::fs::{self, create_dir_all, read_dir, DirEntry, File, Metadata};
-use std::io::{self, ErrorKind};
+use std::io::{self, ErrorKind, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use crate::error::ErrorDetails;
+use crate::path::{
+    self, create_dir_symlink, create_file_symlink, extended_length_path, remove_dir_symlink,
+};
+use log::debug;
+use tempfile::{tempdir_in, Builder, TempDir};
 use volta_fail::{Fallible, ResultExt};
 
 /// Opens a file, creating it if it doesn't exist
@@ -29,21 +35,194 @@ pub fn ensure_containing_dir_exists<P: AsRef<Path>>(path: &P) -> Fallible<()> {
             .into(),
         )
         .and_then(|dir| {
-            fs::create_dir_all(dir).with_context(|_| ErrorDetails::CreateDirError {
-                dir: dir.to_path_buf(),
+            fs::create_dir_all(extended_length_path(dir)).with_context(|_| {
+                ErrorDetails::CreateDirError {
+                    dir: dir.to_path_buf(),
+                }
             })
         })
 }
 
-/// This deletes the input directory, if it exists
+/// This deletes the input directory, if it exists. If it is a symlink, only the link is deleted,
+/// leaving the directory it points to (even if that no longer exists) alone.
 pub fn ensure_dir_does_not_exist<P: AsRef<Path>>(path: &P) -> Fallible<()> {
-    if path.as_ref().exists() {
-        // remove the directory and all of its contents
-        fs::remove_dir_all(path).with_context(delete_dir_error(path))?;
+    let extended = extended_length_path(path.as_ref());
+    match fs::symlink_metadata(&extended) {
+        Ok(ref metadata) if metadata.file_type().is_symlink() => {
+            remove_dir_symlink(&extended).with_context(delete_dir_error(path))?;
+        }
+        Ok(_) => {
+            // remove the directory and all of its contents
+            fs::remove_dir_all(&extended).with_context(delete_dir_error(path))?;
+        }
+        Err(_) => (),
+    }
+    Ok(())
+}
+
+/// Creates a temporary directory to unpack a tool in before it is moved into place. On Windows,
+/// its path is an extended-length path, so that deeply nested packages can be unpacked into it.
+pub fn create_staging_dir() -> Fallible<TempDir> {
+    let tmp_root = path::tmp_dir()?;
+    tempdir_in(extended_length_path(&tmp_root))
+        .with_context(|_| ErrorDetails::CreateTempDirError { in_dir: tmp_root })
+}
+
+/// The suffix of the files that `write_atomic` stages its writes in.
+const STAGED_SUFFIX: &str = ".staged";
+
+/// How long a staged file is left alone before it is taken to be from a write that never finished.
+/// Staged files are renamed into place as soon as they are written, so anything older was left
+/// behind by a Volta that was killed partway through.
+const STAGED_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Writes a file all at once: the contents are staged in a file next to it, flushed to disk, and
+/// then renamed over it, so that an interrupted write leaves the previous contents in place rather
+/// than a partial file.
+pub fn write_atomic(file: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = file
+        .parent()
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "the file has no directory"))?;
+    create_dir_all(dir)?;
+
+    let mut prefix = file.file_name().unwrap_or_default().to_os_string();
+    prefix.push(".");
+    let mut staged = Builder::new()
+        .prefix(&prefix)
+        .suffix(STAGED_SUFFIX)
+        .tempfile_in(dir)?;
+    staged.write_all(contents)?;
+    staged.as_file().sync_all()?;
+    staged.persist(file).map_err(|error| error.error)?;
+
+    Ok(())
+}
+
+/// Removes the files in a directory that `write_atomic` staged but never renamed into place,
+/// which rolls back the writes that they were for. Returns the files that were removed.
+pub fn remove_stale_staged_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    remove_staged_files_older_than(dir, STAGED_TIMEOUT)
+}
+
+fn remove_staged_files_older_than(dir: &Path, timeout: Duration) -> io::Result<Vec<PathBuf>> {
+    let entries = match read_dir_eager(dir) {
+        Ok(entries) => entries,
+        Err(ref error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    let mut removed = Vec::new();
+    for (entry, metadata) in entries {
+        let is_staged = entry.file_name().to_string_lossy().ends_with(STAGED_SUFFIX);
+        let is_stale = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .map_or(false, |age| age >= timeout);
+
+        if metadata.is_file() && is_staged && is_stale {
+            fs::remove_file(entry.path())?;
+            removed.push(entry.path());
+        }
+    }
+    Ok(removed)
+}
+
+/// Renames a file or directory, using extended-length paths on Windows so that a directory can be
+/// moved even if the paths of the files in it are longer than `MAX_PATH`.
+///
+/// Nothing can be renamed from one filesystem to another, as happens when the temporary directory
+/// is on a tmpfs or the Volta home directory is mounted, so it is copied and then removed instead.
+pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+    let from = extended_length_path(from.as_ref());
+    let to = extended_length_path(to.as_ref());
+
+    match fs::rename(&from, &to) {
+        Err(ref error) if is_cross_device(error) => {
+            debug!(
+                "{} is on a different device than {}, copying it instead",
+                from.display(),
+                to.display()
+            );
+            move_across_devices(&from, &to)
+        }
+        result => result,
     }
+}
+
+fn is_cross_device(error: &io::Error) -> bool {
+    // EXDEV on Unix, and ERROR_NOT_SAME_DEVICE on Windows
+    #[cfg(unix)]
+    const CROSS_DEVICE: i32 = 18;
+    #[cfg(windows)]
+    const CROSS_DEVICE: i32 = 17;
+
+    error.raw_os_error() == Some(CROSS_DEVICE)
+}
+
+/// Moves a file or directory by copying it. The copy is made next to the destination and only
+/// renamed into place once it has been written to disk, so that an interrupted copy is never
+/// mistaken for a complete one. The original is removed last.
+fn move_across_devices(from: &Path, to: &Path) -> io::Result<()> {
+    let mut partial_name = to.file_name().unwrap_or_default().to_os_string();
+    partial_name.push(".partial");
+    let partial = to.with_file_name(partial_name);
+
+    if partial.exists() {
+        remove_path(&partial)?;
+    }
+    if let Err(error) = copy_synced(from, &partial) {
+        let _ = remove_path(&partial);
+        return Err(error);
+    }
+
+    fs::rename(&partial, to)?;
+    remove_path(from)
+}
+
+/// Copies a file or a directory tree, including its permissions and links, and flushes each file
+/// to disk.
+fn copy_synced(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
+    let file_type = metadata.file_type();
+
+    if file_type.is_symlink() {
+        let target = fs::read_link(from)?;
+        let links_to_dir = fs::metadata(from)
+            .map(|linked| linked.is_dir())
+            .unwrap_or(false);
+        if links_to_dir {
+            create_dir_symlink(target, to.to_path_buf())?;
+        } else {
+            create_file_symlink(target, to.to_path_buf())?;
+        }
+    } else if file_type.is_dir() {
+        fs::create_dir(to)?;
+        for entry in read_dir(from)? {
+            let entry = entry?;
+            copy_synced(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        // Set last, so that a read-only directory can still be filled
+        fs::set_permissions(to, metadata.permissions())?;
+    } else {
+        let mut source = File::open(from)?;
+        let mut dest = File::create(to)?;
+        io::copy(&mut source, &mut dest)?;
+        dest.sync_all()?;
+        fs::set_permissions(to, metadata.permissions())?;
+    }
+
     Ok(())
 }
 
+fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
 pub fn delete_dir_error<P: AsRef<Path>>(directory: &P) -> impl FnOnce(&io::Error) -> ErrorDetails {
     let directory = directory.as_ref().to_path_buf();
     |_| ErrorDetails::DeleteDirectoryError { directory }
@@ -97,3 +276,244 @@ where
         .filter_map(|(entry, _)| f(&entry))
         .collect::<Vec<T>>())
 }
+
+/// Builds `to` as a symlink farm of `from`: the same tree of directories, in which every file is
+/// a link to the file in `from`. Entries are created in order of name, so the same tree always
+/// produces the same farm. Symbolic links in `from` are recreated as they are, so that relative
+/// links keep pointing within the farm. Files that can't be linked, e.g. on a filesystem without
+/// support for symbolic links, are copied instead.
+pub fn link_farm(from: &Path, to: &Path) -> io::Result<()> {
+    create_dir_all(to)?;
+
+    let mut entries = read_dir(from)?.collect::<io::Result<Vec<DirEntry>>>()?;
+    entries.sort_by_key(DirEntry::file_name);
+
+    for entry in entries {
+        let source = entry.path();
+        let dest = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            link_farm(&source, &dest)?;
+            continue;
+        }
+
+        let target = if file_type.is_symlink() {
+            fs::read_link(&source)?
+        } else {
+            source.clone()
+        };
+
+        if let Err(error) = create_file_symlink(target, dest.clone()) {
+            debug!(
+                "Could not link {}, copying it instead: {}",
+                dest.display(),
+                error
+            );
+            fs::copy(&source, &dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::fs::{read_to_string, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn links_every_file() {
+        let root = tempdir().unwrap();
+        let from = root.path().join("from");
+        let to = root.path().join("to");
+        create_dir_all(from.join("bin")).unwrap();
+        write(from.join("README.md"), "readme").unwrap();
+        write(from.join("bin").join("tool"), "tool").unwrap();
+
+        link_farm(&from, &to).unwrap();
+
+        assert!(to.join("bin").is_dir());
+        assert_eq!(read_to_string(to.join("README.md")).unwrap(), "readme");
+        assert_eq!(read_to_string(to.join("bin").join("tool")).unwrap(), "tool");
+        #[cfg(unix)]
+        assert_eq!(
+            fs::read_link(to.join("bin").join("tool")).unwrap(),
+            from.join("bin").join("tool")
+        );
+    }
+
+    #[test]
+    fn removes_only_the_link_to_a_directory() {
+        let root = tempdir().unwrap();
+        let source = root.path().join("source");
+        let link = root.path().join("link");
+        create_dir_all(&source).unwrap();
+        write(source.join("package.json"), "{}").unwrap();
+        create_dir_symlink(source.clone(), link.clone()).unwrap();
+
+        ensure_dir_does_not_exist(&link).unwrap();
+
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert_eq!(read_to_string(source.join("package.json")).unwrap(), "{}");
+    }
+
+    #[test]
+    fn removes_a_dangling_link() {
+        let root = tempdir().unwrap();
+        let source = root.path().join("source");
+        let link = root.path().join("link");
+        create_dir_all(&source).unwrap();
+        create_dir_symlink(source.clone(), link.clone()).unwrap();
+        fs::remove_dir(&source).unwrap();
+
+        ensure_dir_does_not_exist(&link).unwrap();
+
+        assert!(fs::symlink_metadata(&link).is_err());
+    }
+
+    #[test]
+    fn writes_atomically() {
+        let root = tempdir().unwrap();
+        let file = root.path().join("user").join("platform.json");
+
+        write_atomic(&file, b"{}").unwrap();
+        write_atomic(&file, b"{\"node\":null}").unwrap();
+
+        assert_eq!(read_to_string(&file).unwrap(), "{\"node\":null}");
+        assert_eq!(read_dir(file.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn removes_stale_staged_files() {
+        let root = tempdir().unwrap();
+        let file = root.path().join("platform.json");
+        let staged = root.path().join("platform.json.a1b2c3.staged");
+        write(&file, "{}").unwrap();
+        write(&staged, "{\"node\":").unwrap();
+
+        assert_eq!(
+            remove_stale_staged_files(root.path()).unwrap(),
+            Vec::<PathBuf>::new()
+        );
+        assert!(staged.exists());
+
+        assert_eq!(
+            remove_staged_files_older_than(root.path(), Duration::from_secs(0)).unwrap(),
+            vec![staged.clone()]
+        );
+        assert!(!staged.exists());
+        assert_eq!(read_to_string(&file).unwrap(), "{}");
+
+        let missing = root.path().join("missing");
+        assert!(remove_stale_staged_files(&missing).unwrap().is_empty());
+    }
+
+    #[test]
+    fn moves_and_removes_trees_longer_than_max_path() {
+        let root = tempdir().unwrap();
+        let from = root.path().join("from");
+        let mut nested = from.clone();
+        while nested.as_os_str().len() <= 300 {
+            nested.push("node_modules");
+        }
+        let manifest = nested.join("package.json");
+
+        ensure_containing_dir_exists(&manifest).unwrap();
+        write(extended_length_path(&manifest), "{}").unwrap();
+
+        let to = root.path().join("to");
+        rename(&from, &to).unwrap();
+        let moved = to.join(manifest.strip_prefix(&from).unwrap());
+        assert!(extended_length_path(&moved).is_file());
+
+        ensure_dir_does_not_exist(&to).unwrap();
+        assert!(!to.exists());
+    }
+
+    /// Unpacks a package whose files are nested deeper than `MAX_PATH` allows, the way tools are
+    /// installed: into a staging directory (see `create_staging_dir`), and then moved into place.
+    #[test]
+    #[cfg(windows)]
+    fn unpacks_archives_with_paths_longer_than_max_path() {
+        use archive::{Archive, Tarball};
+        use tempfile::tempdir_in;
+
+        let root = tempdir().unwrap();
+        let staging = tempdir_in(extended_length_path(root.path())).unwrap();
+        let tarball = File::open(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("fixtures")
+                .join("long_paths")
+                .join("nested.tar.gz"),
+        )
+        .unwrap();
+        let archive = Tarball::load(tarball).unwrap();
+        archive.unpack(staging.path(), &mut |_, _| ()).unwrap();
+
+        let image = root.path().join("image");
+        rename(staging.path().join("package"), &image).unwrap();
+
+        let mut manifest = image;
+        for _ in 0..8 {
+            manifest.push("node_modules");
+            manifest.push("deeply-nested-dependency");
+        }
+        manifest.push("package.json");
+        assert!(manifest.as_os_str().len() > 260);
+        assert!(extended_length_path(&manifest).is_file());
+    }
+
+    #[test]
+    fn recognizes_cross_device_errors() {
+        #[cfg(unix)]
+        let code = 18;
+        #[cfg(windows)]
+        let code = 17;
+
+        assert!(is_cross_device(&io::Error::from_raw_os_error(code)));
+        assert!(!is_cross_device(&io::Error::from(ErrorKind::NotFound)));
+    }
+
+    #[test]
+    fn moves_trees_by_copying() {
+        let root = tempdir().unwrap();
+        let from = root.path().join("from");
+        let to = root.path().join("to");
+        create_dir_all(from.join("bin")).unwrap();
+        write(from.join("package.json"), "{}").unwrap();
+        write(from.join("bin").join("tool"), "tool").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("bin/tool", from.join("tool")).unwrap();
+
+        move_across_devices(&from, &to).unwrap();
+
+        assert!(!from.exists());
+        assert!(!root.path().join("to.partial").exists());
+        assert_eq!(read_to_string(to.join("package.json")).unwrap(), "{}");
+        assert_eq!(read_to_string(to.join("bin").join("tool")).unwrap(), "tool");
+        #[cfg(unix)]
+        assert_eq!(
+            fs::read_link(to.join("tool")).unwrap(),
+            PathBuf::from("bin/tool")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_permissions_when_copying() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempdir().unwrap();
+        let from = root.path().join("tool");
+        let to = root.path().join("moved");
+        write(&from, "#!/bin/sh").unwrap();
+        fs::set_permissions(&from, fs::Permissions::from_mode(0o755)).unwrap();
+
+        move_across_devices(&from, &to).unwrap();
+
+        let mode = fs::metadata(&to).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+}
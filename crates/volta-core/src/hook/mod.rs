@@ -1,20 +1,29 @@
 //! Provides types for working with Volta hooks.
 
+use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::marker::PhantomData;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::str::FromStr;
 
 use lazycell::LazyCell;
+use serde::{Deserialize, Serialize};
 
+use crate::command::create_command;
+use crate::distro::bun::BunDistro;
+use crate::distro::deno::DenoDistro;
 use crate::distro::node::NodeDistro;
 use crate::distro::package::PackageDistro;
 use crate::distro::yarn::YarnDistro;
 use crate::distro::Distro;
+use crate::env::{PLATFORM, PREVIOUS_PLATFORM};
 use crate::error::ErrorDetails;
 use crate::path::{find_project_dir, user_hooks_file};
+use cmdline_words_parser::StrExt;
 use log::debug;
-use volta_fail::{Fallible, ResultExt};
+use volta_fail::{throw, Fallible, ResultExt};
 
 pub(crate) mod serial;
 pub mod tool;
@@ -22,7 +31,8 @@ pub mod tool;
 /// A hook for publishing Volta events.
 #[derive(PartialEq, Debug)]
 pub enum Publish {
-    /// Reports an event by sending a POST request to a URL.
+    /// Reports events by sending them to a URL in batched POST requests, queueing them on disk
+    /// until a batch is ready or while the URL can't be reached.
     Url(String),
 
     /// Reports an event by forking a process and sending the event by IPC.
@@ -52,8 +62,11 @@ impl LazyHookConfig {
 pub struct HookConfig {
     pub node: Option<ToolHooks<NodeDistro>>,
     pub yarn: Option<ToolHooks<YarnDistro>>,
+    pub deno: Option<ToolHooks<DenoDistro>>,
+    pub bun: Option<ToolHooks<BunDistro>>,
     pub package: Option<ToolHooks<PackageDistro>>,
     pub events: Option<EventHooks>,
+    pub volta: Option<VoltaHooks>,
 }
 
 /// Volta hooks for an individual tool
@@ -64,20 +77,62 @@ pub struct ToolHooks<D: Distro> {
     pub latest: Option<tool::MetadataHook>,
     /// The hook for resolving the Tool Index URL
     pub index: Option<tool::MetadataHook>,
+    /// The format of the listing returned by the Tool Index URL, if not the default
+    pub index_format: Option<tool::IndexFormat>,
+    /// The `Authorization` header to send to the registry that the `index` and `latest` hooks
+    /// point to, instead of any credentials in `.npmrc`. Only packages support this.
+    pub authorization: Option<String>,
+    /// The hooks for packages in each scope (like `@myorg`), which are used instead of these for
+    /// packages in that scope. Only packages support this.
+    pub scopes: HashMap<String, ToolHooks<D>>,
 
-    pub phantom: PhantomData<D>,
+    /// Marks which tool the hooks are for, without tying whether the hooks can be shared between
+    /// threads to whether the tool's distro can be.
+    pub phantom: PhantomData<fn() -> D>,
 }
 
 impl<D: Distro> ToolHooks<D> {
     /// Creates a merged struct, with "right" having precedence over "left".
     fn merge(left: Self, right: Self) -> Self {
+        // Credentials are only sent to a registry from the same config, so that a project can't
+        // redirect the user's credentials to a registry of its choosing
+        let authorization = if right.index.is_some() || right.latest.is_some() {
+            right.authorization
+        } else {
+            left.authorization
+        };
+        // The index format describes the index URL, so it has to come from the same config
+        let (index, index_format) = match right.index {
+            Some(index) => (Some(index), right.index_format),
+            None => (left.index, left.index_format),
+        };
+        // For the same reason, the hooks for a scope are replaced as a whole, rather than merged
+        let mut scopes = left.scopes;
+        scopes.extend(right.scopes);
+
         Self {
             distro: right.distro.or(left.distro),
             latest: right.latest.or(left.latest),
-            index: right.index.or(left.index),
+            index,
+            index_format,
+            authorization,
+            scopes,
             phantom: PhantomData,
         }
     }
+
+    /// Returns the hooks for the named package, which are the hooks for its scope if there are
+    /// any, and otherwise these.
+    pub fn for_package(&self, name: &str) -> &Self {
+        if !name.starts_with('@') {
+            return self;
+        }
+
+        name.split('/')
+            .next()
+            .and_then(|scope| self.scopes.get(scope))
+            .unwrap_or(self)
+    }
 }
 
 macro_rules! merge_hook_config_field {
@@ -110,8 +165,11 @@ impl HookConfig {
                 Self {
                     node: None,
                     yarn: None,
+                    deno: None,
+                    bun: None,
                     package: None,
                     events: None,
+                    volta: None,
                 }
             }
         })
@@ -159,7 +217,7 @@ impl HookConfig {
     }
 
     /// Returns the per-user hooks, loaded from the filesystem.
-    fn for_user() -> Fallible<Option<Self>> {
+    pub(crate) fn for_user() -> Fallible<Option<Self>> {
         let path = user_hooks_file()?;
         let hooks_config = Self::from_file(&path)?;
 
@@ -170,21 +228,122 @@ impl HookConfig {
         Ok(hooks_config)
     }
 
+    /// Returns the temporary directory set in the user hooks, if any.
+    pub(crate) fn user_tmp_dir() -> Fallible<Option<PathBuf>> {
+        Ok(Self::for_user()?
+            .and_then(|hooks| hooks.volta)
+            .and_then(|volta| volta.tmp_dir))
+    }
+
+    /// Returns the permission policy set in the user hooks, if any.
+    pub(crate) fn user_install_permissions() -> Fallible<Option<InstallPermissions>> {
+        Ok(Self::for_user()?
+            .and_then(|hooks| hooks.volta)
+            .and_then(|volta| volta.install_permissions))
+    }
+
+    /// Returns the policy for keeping cached archives set in the user hooks, if any.
+    pub(crate) fn user_keep_archives() -> Fallible<Option<KeepArchives>> {
+        Ok(Self::for_user()?
+            .and_then(|hooks| hooks.volta)
+            .and_then(|volta| volta.keep_archives))
+    }
+
+    /// Returns the download rate limit set in the user hooks, if any.
+    pub(crate) fn user_limit_rate() -> Fallible<Option<RateLimit>> {
+        Ok(Self::for_user()?
+            .and_then(|hooks| hooks.volta)
+            .and_then(|volta| volta.limit_rate))
+    }
+
+    /// Returns whether the user hooks turn fetching pinned platforms in the background on or off,
+    /// if they set it.
+    pub(crate) fn user_background_fetch() -> Fallible<Option<bool>> {
+        Ok(Self::for_user()?
+            .and_then(|hooks| hooks.volta)
+            .and_then(|volta| volta.background_fetch))
+    }
+
+    /// Returns the version policy file set in the user hooks, if any.
+    pub(crate) fn user_policy() -> Fallible<Option<PathBuf>> {
+        Ok(Self::for_user()?
+            .and_then(|hooks| hooks.volta)
+            .and_then(|volta| volta.policy))
+    }
+
+    /// Returns the passthrough policy set in the user hooks, if any.
+    pub(crate) fn user_passthrough() -> Fallible<Option<PassthroughPolicy>> {
+        Ok(Self::for_user()?
+            .and_then(|hooks| hooks.volta)
+            .and_then(|volta| volta.passthrough))
+    }
+
     /// Creates a merged struct, with "right" having precedence over "left".
     fn merge(left: Self, right: Self) -> Self {
         Self {
             node: merge_hook_config_field!(left, right, node, ToolHooks),
             yarn: merge_hook_config_field!(left, right, yarn, ToolHooks),
+            deno: merge_hook_config_field!(left, right, deno, ToolHooks),
+            bun: merge_hook_config_field!(left, right, bun, ToolHooks),
             package: merge_hook_config_field!(left, right, package, ToolHooks),
             events: merge_hook_config_field!(left, right, events, EventHooks),
+            volta: merge_hook_config_field!(left, right, volta, VoltaHooks),
         }
     }
 }
 
+/// A hook that runs a command in a project when the platform that its shims run with changes,
+/// e.g. to run `npm rebuild` after someone changes the pinned version of Node.
+#[derive(PartialEq, Debug)]
+pub struct PlatformChangeHook {
+    pub bin: String,
+}
+
+impl PlatformChangeHook {
+    /// Runs the command in the project root, telling it the previous and new platforms in
+    /// `VOLTA_PREVIOUS_PLATFORM` and `VOLTA_PLATFORM`.
+    pub(crate) fn run(
+        &self,
+        project_root: &Path,
+        previous: &str,
+        current: &str,
+    ) -> Fallible<ExitStatus> {
+        let mut trimmed = self.bin.trim().to_string();
+        let mut words = trimmed.parse_cmdline_words();
+        let exe = match words.next() {
+            Some(exe) => exe,
+            None => throw!(ErrorDetails::InvalidHookCommand {
+                command: self.bin.trim().to_string(),
+            }),
+        };
+
+        let mut command = create_command(exe);
+        command
+            .args(words)
+            .current_dir(project_root)
+            .env(PREVIOUS_PLATFORM, previous)
+            .env(PLATFORM, current);
+
+        debug!("Running platformChange hook command: {:?}", command);
+        command
+            .status()
+            .with_context(|_| ErrorDetails::ExecuteHookError {
+                command: exe.to_string(),
+            })
+    }
+}
+
 /// Volta hooks related to events.
 pub struct EventHooks {
     /// The hook for publishing events, if any.
     pub publish: Option<Publish>,
+    /// The hook to run when the platform of a project changes, if any.
+    pub platform_change: Option<PlatformChangeHook>,
+    /// The names of the kinds of activity that plugins record events of, besides the built-in
+    /// ones.
+    pub kinds: Vec<String>,
+    /// How often the events of each kind of activity are recorded, if not always.
+    pub activities: Option<ActivityRates>,
 }
 
 impl EventHooks {
@@ -192,6 +351,245 @@ impl EventHooks {
     fn merge(left: Self, right: Self) -> Self {
         Self {
             publish: right.publish.or(left.publish),
+            platform_change: right.platform_change.or(left.platform_change),
+            kinds: {
+                let mut kinds = right.kinds;
+                for kind in left.kinds {
+                    if !kinds.contains(&kind) {
+                        kinds.push(kind);
+                    }
+                }
+                kinds
+            },
+            activities: right.activities.or(left.activities),
+        }
+    }
+}
+
+/// How often the events of each kind of activity (by name, like `install` or `binary`) are
+/// recorded, from 0 (never) to 1 (always), so that fleets of machines can keep the number of
+/// events down. Activities that aren't listed are always recorded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActivityRates(HashMap<String, f64>);
+
+impl ActivityRates {
+    pub fn new(rates: HashMap<String, f64>) -> Fallible<Self> {
+        for (activity, &rate) in &rates {
+            if !(0.0..=1.0).contains(&rate) {
+                throw!(ErrorDetails::InvalidActivityRate {
+                    activity: activity.clone(),
+                    rate,
+                });
+            }
+        }
+        Ok(ActivityRates(rates))
+    }
+
+    /// Returns how often the events of an activity are recorded.
+    pub fn rate(&self, activity: &str) -> f64 {
+        self.0.get(activity).cloned().unwrap_or(1.0)
+    }
+}
+
+/// The release channels of Volta itself.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoltaChannel {
+    Stable,
+    Preview,
+}
+
+/// How the npm and Yarn shims handle commands that install packages globally, like
+/// `npm install -g` or `yarn global add`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlobalInstallPolicy {
+    /// Fails, suggesting `volta install` instead. This is the default.
+    Error,
+    /// Installs the packages with `volta install` instead.
+    Install,
+    /// Lets the package manager install the packages, after warning that Volta won't manage them.
+    Warn,
+    /// Lets the package manager install the packages.
+    Passthrough,
+}
+
+impl Default for GlobalInstallPolicy {
+    fn default() -> Self {
+        GlobalInstallPolicy::Error
+    }
+}
+
+/// Who can use the images, shims, and config files that Volta installs. Without a policy, they
+/// get whatever permissions the umask of the installing user allows.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallPermissions {
+    /// Readable and executable by every user, and writable only by the owner.
+    Shared,
+    /// Usable only by the owner.
+    Private,
+}
+
+/// What shims do when there is no platform to run a tool with, so that the tool could only come
+/// from the system `PATH`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PassthroughPolicy {
+    /// Runs the tool from the system `PATH`. This is the default.
+    Allow,
+    /// Runs the tool from the system `PATH`, after warning that Volta isn't managing it.
+    Warn,
+    /// Fails as if the tool weren't available at all.
+    Deny,
+}
+
+impl Default for PassthroughPolicy {
+    fn default() -> Self {
+        PassthroughPolicy::Allow
+    }
+}
+
+/// What shims do in a project that pins npm or Yarn without pinning Node, which they run with the
+/// Node of the default platform.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PartialPinPolicy {
+    /// Runs the tool with the default Node.
+    Allow,
+    /// Runs the tool with the default Node, after warning that the project doesn't pin Node. This
+    /// is the default.
+    Warn,
+    /// Fails until the project pins Node as well.
+    Deny,
+}
+
+impl Default for PartialPinPolicy {
+    fn default() -> Self {
+        PartialPinPolicy::Warn
+    }
+}
+
+/// Which of the archives that tools were installed from are kept in the inventory, so that the
+/// tools can be installed again without downloading them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeepArchives {
+    /// Keeps every archive. This is the default.
+    Always,
+    /// Removes each archive once the tool has been installed from it.
+    Never,
+    /// Keeps the most recently downloaded archives of each tool.
+    Last(usize),
+}
+
+impl Default for KeepArchives {
+    fn default() -> Self {
+        KeepArchives::Always
+    }
+}
+
+impl FromStr for KeepArchives {
+    type Err = ErrorDetails;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let count = if value.starts_with("last-") {
+            value["last-".len()..].parse().ok()
+        } else {
+            None
+        };
+
+        match (value, count) {
+            ("always", _) => Ok(KeepArchives::Always),
+            ("never", _) => Ok(KeepArchives::Never),
+            (_, Some(count)) => Ok(KeepArchives::Last(count)),
+            _ => Err(ErrorDetails::InvalidKeepArchives {
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+/// How fast tools can be downloaded, in bytes per second, so that downloads don't saturate a slow
+/// or metered connection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit(pub u64);
+
+impl FromStr for RateLimit {
+    type Err = ErrorDetails;
+
+    /// Parses a number of bytes per second, optionally with a suffix of `k`, `m`, or `g` for
+    /// kilobytes, megabytes, or gigabytes (in multiples of 1024) per second, like `500k`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (number, multiplier) = match value.chars().last().map(|c| c.to_ascii_lowercase()) {
+            Some('k') => (&value[..value.len() - 1], 1 << 10),
+            Some('m') => (&value[..value.len() - 1], 1 << 20),
+            Some('g') => (&value[..value.len() - 1], 1 << 30),
+            _ => (value, 1),
+        };
+
+        match number.parse::<u64>() {
+            Ok(number) if number > 0 => Ok(RateLimit(number.saturating_mul(multiplier))),
+            _ => Err(ErrorDetails::InvalidRateLimit {
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+/// Volta hooks related to Volta itself.
+pub struct VoltaHooks {
+    /// The release channel to update from, if not the stable channel.
+    pub channel: Option<VoltaChannel>,
+    /// The URL of the server that publishes releases, if not the public releases.
+    pub releases: Option<String>,
+    /// The URL of the server that publishes the SHA-256 checksums of releases, if not the public
+    /// checksums. Releases are only installed if they match a checksum from this server.
+    pub checksums: Option<String>,
+    /// Whether to show a notice when a new version is available, which is the default.
+    pub notify: Option<bool>,
+    /// How global installs by npm and Yarn are handled, if not with an error.
+    pub global_installs: Option<GlobalInstallPolicy>,
+    /// The directory to unpack tools in before they're moved into place, if not the one in the
+    /// Volta home directory.
+    pub tmp_dir: Option<PathBuf>,
+    /// The permissions to install files with, if not the ones allowed by the umask.
+    pub install_permissions: Option<InstallPermissions>,
+    /// Which cached archives are kept after tools are installed from them, if not all of them.
+    pub keep_archives: Option<KeepArchives>,
+    /// How fast tools are downloaded, if not as fast as the connection allows.
+    pub limit_rate: Option<RateLimit>,
+    /// Whether shims fetch a project's newly pinned platform in the background, running with the
+    /// default platform in the meantime, rather than waiting for it. They wait by default.
+    pub background_fetch: Option<bool>,
+    /// The file with the policy that restricts which versions of Node and Yarn can be installed
+    /// or pinned, if there is one.
+    pub policy: Option<PathBuf>,
+    /// What shims do when there is no platform, if not run the tool from the system `PATH`.
+    pub passthrough: Option<PassthroughPolicy>,
+    /// What shims do in a project that pins npm or Yarn without pinning Node, if not warn.
+    pub partial_pins: Option<PartialPinPolicy>,
+}
+
+impl VoltaHooks {
+    /// Creates a merged struct, with "right" having precedence over "left".
+    fn merge(left: Self, right: Self) -> Self {
+        Self {
+            channel: right.channel.or(left.channel),
+            releases: right.releases.or(left.releases),
+            checksums: right.checksums.or(left.checksums),
+            notify: right.notify.or(left.notify),
+            global_installs: right.global_installs.or(left.global_installs),
+            partial_pins: right.partial_pins.or(left.partial_pins),
+            // The temporary directory, permissions, archives, rate limit, background fetching,
+            // version policy, and passthrough policy apply to every project, so they're only read
+            // from the user hooks
+            tmp_dir: left.tmp_dir,
+            install_permissions: left.install_permissions,
+            keep_archives: left.keep_archives,
+            limit_rate: left.limit_rate,
+            background_fetch: left.background_fetch,
+            policy: left.policy,
+            passthrough: left.passthrough,
         }
     }
 }
@@ -199,7 +597,12 @@ impl EventHooks {
 #[cfg(test)]
 pub mod tests {
 
-    use super::{tool, HookConfig, Publish};
+    use super::{
+        tool, ActivityRates, GlobalInstallPolicy, HookConfig, InstallPermissions, KeepArchives,
+        PartialPinPolicy, PassthroughPolicy, PlatformChangeHook, Publish, RateLimit, VoltaChannel,
+    };
+    use std::collections::HashMap;
+    use std::env;
     use std::path::PathBuf;
 
     fn fixture_path(fixture_dir: &str) -> PathBuf {
@@ -221,6 +624,121 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_from_str_platform_change() {
+        let fixture_dir = fixture_path("hooks");
+        let platform_change_file = fixture_dir.join("platform_change.json");
+        let hooks = HookConfig::from_file(&platform_change_file)
+            .unwrap()
+            .unwrap();
+        let events = hooks.events.unwrap();
+
+        assert_eq!(
+            events.platform_change,
+            Some(PlatformChangeHook {
+                bin: "npm rebuild".to_string()
+            })
+        );
+        assert_eq!(events.publish, None);
+    }
+
+    #[test]
+    fn test_from_str_event_activities() {
+        let fixture_dir = fixture_path("hooks");
+        let activities_file = fixture_dir.join("event_activities.json");
+        let hooks = HookConfig::from_file(&activities_file).unwrap().unwrap();
+        let activities = hooks.events.unwrap().activities.unwrap();
+
+        assert_eq!(activities.rate("binary"), 0.0);
+        assert_eq!(activities.rate("fetch"), 0.25);
+        assert_eq!(activities.rate("install"), 1.0);
+        assert_eq!(activities.rate("lint"), 0.0);
+        assert_eq!(activities.rate("pin"), 1.0);
+    }
+
+    #[test]
+    fn test_from_str_event_kinds() {
+        let fixture_dir = fixture_path("hooks");
+        let activities_file = fixture_dir.join("event_activities.json");
+        let hooks = HookConfig::from_file(&activities_file).unwrap().unwrap();
+        assert_eq!(hooks.events.unwrap().kinds, vec!["lint".to_string()]);
+
+        let built_in_file = fixture_dir.join("event_kinds_built_in.json");
+        assert!(HookConfig::from_file(&built_in_file).is_err());
+    }
+
+    #[test]
+    fn test_activity_rates_must_be_fractions() {
+        let mut rates = HashMap::new();
+        rates.insert("fetch".to_string(), 1.5);
+        assert!(ActivityRates::new(rates).is_err());
+    }
+
+    #[test]
+    fn test_from_str_volta_channel() {
+        let fixture_dir = fixture_path("hooks");
+        let channel_file = fixture_dir.join("volta_channel.json");
+        let hooks = HookConfig::from_file(&channel_file).unwrap().unwrap();
+        let volta = hooks.volta.unwrap();
+
+        assert_eq!(volta.channel, Some(VoltaChannel::Preview));
+        assert_eq!(
+            volta.releases,
+            Some("https://releases.example.com/volta".to_string())
+        );
+        assert_eq!(
+            volta.checksums,
+            Some("https://checksums.example.com/volta".to_string())
+        );
+        assert_eq!(volta.notify, Some(false));
+        assert_eq!(volta.global_installs, Some(GlobalInstallPolicy::Warn));
+        assert_eq!(volta.tmp_dir, Some(PathBuf::from("/mnt/scratch/volta")));
+        assert_eq!(volta.install_permissions, Some(InstallPermissions::Shared));
+        assert_eq!(volta.keep_archives, Some(KeepArchives::Last(2)));
+        assert_eq!(volta.limit_rate, Some(RateLimit(500 * 1024)));
+        assert_eq!(volta.background_fetch, Some(true));
+        assert_eq!(volta.policy, Some(PathBuf::from("/etc/volta/policy.json")));
+        assert_eq!(volta.passthrough, Some(PassthroughPolicy::Warn));
+        assert_eq!(volta.partial_pins, Some(PartialPinPolicy::Deny));
+    }
+
+    #[test]
+    fn test_parse_keep_archives() {
+        assert_eq!(
+            "always".parse::<KeepArchives>().unwrap(),
+            KeepArchives::Always
+        );
+        assert_eq!(
+            "never".parse::<KeepArchives>().unwrap(),
+            KeepArchives::Never
+        );
+        assert_eq!(
+            "last-3".parse::<KeepArchives>().unwrap(),
+            KeepArchives::Last(3)
+        );
+        assert!("last-".parse::<KeepArchives>().is_err());
+        assert!("3".parse::<KeepArchives>().is_err());
+        assert!("sometimes".parse::<KeepArchives>().is_err());
+    }
+
+    #[test]
+    fn test_parse_rate_limit() {
+        assert_eq!("2048".parse::<RateLimit>().unwrap(), RateLimit(2048));
+        assert_eq!("500k".parse::<RateLimit>().unwrap(), RateLimit(500 * 1024));
+        assert_eq!(
+            "2M".parse::<RateLimit>().unwrap(),
+            RateLimit(2 * 1024 * 1024)
+        );
+        assert_eq!(
+            "1g".parse::<RateLimit>().unwrap(),
+            RateLimit(1024 * 1024 * 1024)
+        );
+        assert!("0".parse::<RateLimit>().is_err());
+        assert!("k".parse::<RateLimit>().is_err());
+        assert!("1.5m".parse::<RateLimit>().is_err());
+        assert!("fast".parse::<RateLimit>().is_err());
+    }
+
     #[test]
     fn test_from_str_bins() {
         let fixture_dir = fixture_path("hooks");
@@ -356,6 +874,137 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_from_str_index_format() {
+        let fixture_dir = fixture_path("hooks");
+        let format_file = fixture_dir.join("format.json");
+        let hooks = HookConfig::from_file(&format_file).unwrap().unwrap();
+        let yarn = hooks.yarn.unwrap();
+
+        assert_eq!(
+            yarn.index,
+            Some(tool::MetadataHook::Template(
+                "http://localhost/yarn/index/".to_string()
+            ))
+        );
+        assert_eq!(yarn.index_format, Some(tool::IndexFormat::SimpleJson));
+
+        let template_file = fixture_dir.join("templates.json");
+        let hooks = HookConfig::from_file(&template_file).unwrap().unwrap();
+        assert_eq!(hooks.yarn.unwrap().index_format, None);
+    }
+
+    #[test]
+    fn test_index_format_only_for_yarn_index() {
+        let fixture_dir = fixture_path("hooks");
+        let format_file = fixture_dir.join("format_unsupported.json");
+        assert!(HookConfig::from_file(&format_file).is_err());
+    }
+
+    #[test]
+    fn test_from_str_bun_mirror() {
+        let fixture_dir = fixture_path("hooks");
+        let mirror_file = fixture_dir.join("mirrors.json");
+        let hooks = HookConfig::from_file(&mirror_file).unwrap().unwrap();
+        let bun = hooks.bun.unwrap();
+
+        assert_eq!(
+            bun.distro,
+            Some(tool::DistroHook::Prefix(
+                "http://localhost/bun/distro/".to_string()
+            ))
+        );
+        assert_eq!(bun.latest, None);
+        assert_eq!(
+            bun.index,
+            Some(tool::MetadataHook::Prefix(
+                "http://localhost/bun/index/".to_string()
+            ))
+        );
+        assert!(hooks.node.is_none());
+    }
+
+    #[test]
+    fn test_from_str_package_registry() {
+        env::set_var("VOLTA_TEST_HOOK_TOKEN", "secret");
+        let fixture_dir = fixture_path("hooks");
+        let packages_file = fixture_dir.join("packages.json");
+        let hooks = HookConfig::from_file(&packages_file).unwrap().unwrap();
+        let packages = hooks.package.unwrap();
+
+        assert_eq!(
+            packages.index,
+            Some(tool::MetadataHook::Template(
+                "https://npm.example.com/{{name}}".to_string()
+            ))
+        );
+        assert_eq!(packages.authorization, Some("Bearer secret".to_string()));
+    }
+
+    #[test]
+    fn test_merge_package_authorization() {
+        let fixture_dir = fixture_path("hooks");
+        let user_hooks = HookConfig::from_file(&fixture_dir.join("packages.json"))
+            .unwrap()
+            .unwrap();
+        let project_hooks = HookConfig::from_file(&fixture_dir.join("prefixes.json"))
+            .unwrap()
+            .unwrap();
+        let merged_hooks = HookConfig::merge(user_hooks, project_hooks);
+
+        // The project doesn't change the package registry, so the user's credentials are kept
+        assert!(merged_hooks.package.unwrap().authorization.is_some());
+
+        let user_hooks = HookConfig::from_file(&fixture_dir.join("packages.json"))
+            .unwrap()
+            .unwrap();
+        let mut project_hooks = HookConfig::from_file(&fixture_dir.join("packages.json"))
+            .unwrap()
+            .unwrap();
+        if let Some(ref mut packages) = project_hooks.package {
+            packages.authorization = None;
+        }
+        let merged_hooks = HookConfig::merge(user_hooks, project_hooks);
+
+        // The project changes the package registry, so the user's credentials aren't sent to it
+        assert_eq!(merged_hooks.package.unwrap().authorization, None);
+    }
+
+    #[test]
+    fn test_package_scopes() {
+        let fixture_dir = fixture_path("hooks");
+        let scopes_file = fixture_dir.join("scopes.json");
+        let hooks = HookConfig::from_file(&scopes_file).unwrap().unwrap();
+        let packages = hooks.package.unwrap();
+
+        let myorg = packages.for_package("@myorg/cli");
+        assert_eq!(
+            myorg.index,
+            Some(tool::MetadataHook::Template(
+                "https://npm.myorg.com/{{name}}".to_string()
+            ))
+        );
+        assert_eq!(myorg.authorization, Some("Bearer myorg-token".to_string()));
+
+        // Scopes can be written without the `@`
+        assert_eq!(
+            packages.for_package("@other/cli").index,
+            Some(tool::MetadataHook::Prefix(
+                "https://npm.other.com/".to_string()
+            ))
+        );
+
+        let unscoped = packages.for_package("cowsay");
+        assert_eq!(
+            unscoped.index,
+            Some(tool::MetadataHook::Prefix(
+                "https://registry.example.com/".to_string()
+            ))
+        );
+        assert_eq!(unscoped.authorization, None);
+        assert_eq!(packages.for_package("@unknown/cli").index, unscoped.index);
+    }
+
     #[test]
     fn test_for_dir() {
         let project_dir = fixture_path("hooks/project");
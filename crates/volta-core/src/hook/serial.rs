@@ -1,19 +1,26 @@
 use super::tool;
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::path::PathBuf;
 
+use crate::distro::bun::BunDistro;
+use crate::distro::deno::DenoDistro;
 use crate::distro::node::NodeDistro;
 use crate::distro::package::PackageDistro;
 use crate::distro::yarn::YarnDistro;
 use crate::distro::Distro;
 use crate::error::ErrorDetails;
+use crate::npmrc::expand_env_vars;
+use crate::session::ActivityKind;
 use serde::{Deserialize, Serialize};
-use volta_fail::Fallible;
+use volta_fail::{throw, Fallible};
 
 #[derive(Serialize, Deserialize)]
 pub struct ResolveHook {
     prefix: Option<String>,
     template: Option<String>,
     bin: Option<String>,
+    format: Option<tool::IndexFormat>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -34,21 +41,25 @@ impl ResolveHook {
                 prefix: Some(prefix),
                 template: None,
                 bin: None,
+                ..
             } => Ok(to_prefix(prefix)),
             ResolveHook {
                 prefix: None,
                 template: Some(template),
                 bin: None,
+                ..
             } => Ok(to_template(template)),
             ResolveHook {
                 prefix: None,
                 template: None,
                 bin: Some(bin),
+                ..
             } => Ok(to_bin(bin)),
             ResolveHook {
                 prefix: None,
                 template: None,
                 bin: None,
+                ..
             } => Err(ErrorDetails::HookNoFieldsSpecified.into()),
             _ => Err(ErrorDetails::HookMultipleFieldsSpecified.into()),
         }
@@ -95,14 +106,34 @@ impl PublishHook {
 pub struct HookConfig {
     pub node: Option<ToolHooks<NodeDistro>>,
     pub yarn: Option<ToolHooks<YarnDistro>>,
+    pub deno: Option<ToolHooks<DenoDistro>>,
+    pub bun: Option<ToolHooks<BunDistro>>,
     pub packages: Option<ToolHooks<PackageDistro>>,
     pub events: Option<EventHooks>,
+    pub volta: Option<VoltaHooks>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlatformChangeHook {
+    bin: String,
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename = "events")]
 pub struct EventHooks {
     pub publish: Option<PublishHook>,
+    #[serde(rename = "platformChange")]
+    pub platform_change: Option<PlatformChangeHook>,
+    pub kinds: Option<Vec<String>>,
+    pub activities: Option<HashMap<String, ActivityRate>>,
+}
+
+/// Whether an activity's events are recorded, or how often.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ActivityRate {
+    Enabled(bool),
+    Sampled(f64),
 }
 
 impl EventHooks {
@@ -113,16 +144,101 @@ impl EventHooks {
             } else {
                 None
             },
+            platform_change: self
+                .platform_change
+                .map(|hook| super::PlatformChangeHook { bin: hook.bin }),
+            kinds: self
+                .kinds
+                .map(|kinds| {
+                    kinds
+                        .into_iter()
+                        .map(|name| match ActivityKind::built_in(&name) {
+                            Some(_) => Err(ErrorDetails::ActivityKindExists { name }.into()),
+                            None => Ok(name),
+                        })
+                        .collect::<Fallible<Vec<_>>>()
+                })
+                .transpose()?
+                .unwrap_or_default(),
+            activities: self
+                .activities
+                .map(|activities| {
+                    let rates = activities
+                        .into_iter()
+                        .map(|(activity, rate)| match rate {
+                            ActivityRate::Enabled(true) => (activity, 1.0),
+                            ActivityRate::Enabled(false) => (activity, 0.0),
+                            ActivityRate::Sampled(rate) => (activity, rate),
+                        })
+                        .collect();
+                    super::ActivityRates::new(rates)
+                })
+                .transpose()?,
         })
     }
 }
 
 #[derive(Serialize, Deserialize)]
-#[serde(rename = "tool")]
+#[serde(rename = "volta")]
+pub struct VoltaHooks {
+    pub channel: Option<super::VoltaChannel>,
+    pub releases: Option<String>,
+    pub checksums: Option<String>,
+    pub notify: Option<bool>,
+    #[serde(rename = "globalInstalls")]
+    pub global_installs: Option<super::GlobalInstallPolicy>,
+    #[serde(rename = "tmpDir")]
+    pub tmp_dir: Option<PathBuf>,
+    #[serde(rename = "installPermissions")]
+    pub install_permissions: Option<super::InstallPermissions>,
+    #[serde(rename = "keepArchives")]
+    pub keep_archives: Option<String>,
+    #[serde(rename = "limitRate")]
+    pub limit_rate: Option<String>,
+    #[serde(rename = "backgroundFetch")]
+    pub background_fetch: Option<bool>,
+    pub policy: Option<PathBuf>,
+    pub passthrough: Option<super::PassthroughPolicy>,
+    #[serde(rename = "partialPins")]
+    pub partial_pins: Option<super::PartialPinPolicy>,
+}
+
+impl VoltaHooks {
+    pub fn into_volta_hooks(self) -> Fallible<super::VoltaHooks> {
+        let keep_archives = self
+            .keep_archives
+            .map(|keep| keep.parse::<super::KeepArchives>())
+            .transpose()?;
+        let limit_rate = self
+            .limit_rate
+            .map(|limit| limit.parse::<super::RateLimit>())
+            .transpose()?;
+        Ok(super::VoltaHooks {
+            channel: self.channel,
+            releases: self.releases,
+            checksums: self.checksums,
+            notify: self.notify,
+            global_installs: self.global_installs,
+            tmp_dir: self.tmp_dir,
+            install_permissions: self.install_permissions,
+            keep_archives,
+            limit_rate,
+            background_fetch: self.background_fetch,
+            policy: self.policy,
+            passthrough: self.passthrough,
+            partial_pins: self.partial_pins,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "tool", bound = "")]
 pub struct ToolHooks<I> {
     pub distro: Option<ResolveHook>,
     pub latest: Option<ResolveHook>,
     pub index: Option<ResolveHook>,
+    pub authorization: Option<String>,
+    pub scopes: Option<HashMap<String, ToolHooks<I>>>,
 
     #[serde(skip)]
     phantom: PhantomData<I>,
@@ -130,29 +246,78 @@ pub struct ToolHooks<I> {
 
 impl HookConfig {
     pub fn into_hook_config(self) -> Fallible<super::HookConfig> {
-        let node = self.node.map(|n| n.into_tool_hooks()).transpose()?;
-        let yarn = self.yarn.map(|y| y.into_tool_hooks()).transpose()?;
-        let package = self.packages.map(|p| p.into_tool_hooks()).transpose()?;
+        let node = self.node.map(|n| n.into_tool_hooks("node")).transpose()?;
+        let yarn = self.yarn.map(|y| y.into_tool_hooks("yarn")).transpose()?;
+        let deno = self.deno.map(|d| d.into_tool_hooks("deno")).transpose()?;
+        let bun = self.bun.map(|b| b.into_tool_hooks("bun")).transpose()?;
+        let package = self
+            .packages
+            .map(|p| p.into_tool_hooks("packages"))
+            .transpose()?;
         let events = self.events.map(|e| e.into_event_hooks()).transpose()?;
+        let volta = self.volta.map(|v| v.into_volta_hooks()).transpose()?;
         Ok(super::HookConfig {
             node,
             yarn,
+            deno,
+            bun,
             package,
             events,
+            volta,
         })
     }
 }
 
 impl<D: Distro> ToolHooks<D> {
-    pub fn into_tool_hooks(self) -> Fallible<super::ToolHooks<D>> {
+    /// Converts the hooks for a tool, named as in the hooks file. Only the Yarn index can be read
+    /// in formats other than the default, so `format` is rejected on every other hook rather than
+    /// being ignored.
+    pub fn into_tool_hooks(self, tool: &str) -> Fallible<super::ToolHooks<D>> {
+        for (name, hook) in &[
+            ("distro", &self.distro),
+            ("latest", &self.latest),
+            ("index", &self.index),
+        ] {
+            let supports_format = tool == "yarn" && *name == "index";
+            if let Some(ResolveHook {
+                format: Some(_), ..
+            }) = hook
+            {
+                if !supports_format {
+                    throw!(ErrorDetails::HookFormatNotSupported {
+                        hook: format!("{}.{}", tool, name),
+                    });
+                }
+            }
+        }
+
+        let index_format = self.index.as_ref().and_then(|index| index.format);
         let distro = self.distro.map(|d| d.into_distro_hook()).transpose()?;
         let latest = self.latest.map(|d| d.into_metadata_hook()).transpose()?;
         let index = self.index.map(|d| d.into_metadata_hook()).transpose()?;
+        let authorization = self.authorization.as_ref().map(|a| expand_env_vars(a));
+        let scopes = self
+            .scopes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(scope, hooks)| {
+                // Accept scopes with or without the leading `@`, as in `.npmrc`
+                let scope = if scope.starts_with('@') {
+                    scope
+                } else {
+                    format!("@{}", scope)
+                };
+                Ok((scope, hooks.into_tool_hooks(tool)?))
+            })
+            .collect::<Fallible<_>>()?;
 
         Ok(super::ToolHooks {
             distro,
             latest,
             index,
+            index_format,
+            authorization,
+            scopes,
             phantom: PhantomData,
         })
     }
@@ -1,6 +1,7 @@
 //! Types representing Volta Tool Hooks.
 
 use std::ffi::OsString;
+use std::fmt;
 use std::process::Stdio;
 
 use crate::command::create_command;
@@ -9,11 +10,13 @@ use crate::path::{ARCH, OS};
 use cmdline_words_parser::StrExt;
 use log::debug;
 use semver::Version;
+use serde::{Deserialize, Serialize};
 use volta_fail::{throw, Fallible, ResultExt};
 
 const ARCH_TEMPLATE: &'static str = "{{arch}}";
 const OS_TEMPLATE: &'static str = "{{os}}";
 const VERSION_TEMPLATE: &'static str = "{{version}}";
+const NAME_TEMPLATE: &'static str = "{{name}}";
 
 /// A hook for resolving the distro URL for a given tool version
 #[derive(PartialEq, Debug)]
@@ -57,6 +60,44 @@ impl MetadataHook {
             &MetadataHook::Bin(ref bin) => execute_binary(bin, None),
         }
     }
+
+    /// Performs resolution of the metadata URL for the named package. Unlike the other tools,
+    /// the name is appended to a prefix, substituted into a template, and passed to a binary.
+    pub fn resolve_package(&self, name: &str) -> Fallible<String> {
+        match self {
+            &MetadataHook::Prefix(ref prefix) => Ok(format!("{}{}", prefix, name)),
+            &MetadataHook::Template(ref template) => Ok(template
+                .replace(ARCH_TEMPLATE, ARCH)
+                .replace(OS_TEMPLATE, OS)
+                .replace(NAME_TEMPLATE, name)),
+            &MetadataHook::Bin(ref bin) => execute_binary(bin, Some(name.to_string())),
+        }
+    }
+}
+
+/// The format of the listing returned by an index hook URL
+///
+/// Currently only Yarn supports formats other than the default, see `distro::yarn::parse_index`
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndexFormat {
+    /// The GitHub releases API format, used by the public Yarn index
+    Github,
+    /// The npm registry package metadata format
+    Npm,
+    /// A plain JSON array of version strings
+    SimpleJson,
+}
+
+impl fmt::Display for IndexFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            IndexFormat::Github => "github",
+            IndexFormat::Npm => "npm",
+            IndexFormat::SimpleJson => "simple-json",
+        };
+        f.write_str(s)
+    }
 }
 
 /// Execute a shell command and return the trimmed stdout from that command
@@ -161,4 +202,23 @@ pub mod tests {
             expected
         );
     }
+
+    #[test]
+    fn test_metadata_package_resolve() {
+        let prefix = MetadataHook::Prefix("http://localhost/packages/".to_string());
+        assert_eq!(
+            prefix
+                .resolve_package("@myorg/cli")
+                .expect("Could not resolve URL"),
+            "http://localhost/packages/@myorg/cli"
+        );
+
+        let template = MetadataHook::Template("http://localhost/{{os}}/{{name}}".to_string());
+        assert_eq!(
+            template
+                .resolve_package("@myorg/cli")
+                .expect("Could not resolve URL"),
+            format!("http://localhost/{}/@myorg/cli", OS)
+        );
+    }
 }
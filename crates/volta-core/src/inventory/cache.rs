@@ -0,0 +1,244 @@
+//! Caches the remote metadata that versions are resolved from: the Node index, the Yarn, Deno,
+//! and Bun releases, and package metadata from the registry. Metadata that expired only a little
+//! while ago is used right away while a new copy is fetched in the background, so that resolving
+//! a version seldom has to wait for the network. A background fetch doesn't outlive the command,
+//! so metadata that is still stale after the soft TTL is fetched before it's used instead.
+
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use headers_011::Headers011;
+use log::debug;
+use reqwest::hyper_011::header::{CacheControl, CacheDirective, Expires, HttpDate};
+use sha2::{Digest, Sha256};
+
+use crate::fs::{read_file_opt, write_atomic};
+use crate::path;
+use crate::style::progress_spinner;
+use volta_fail::Fallible;
+
+/// How long after it expires that metadata is still used while a new copy is fetched. Past this,
+/// the refresh is waited for, so that metadata can't stay stale when commands are too short for
+/// the background fetch to finish.
+const SOFT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long metadata is kept for when the server doesn't say, in seconds.
+const DEFAULT_MAX_AGE: u32 = 4 * 60 * 60;
+
+#[derive(Debug, PartialEq)]
+enum Freshness {
+    /// The metadata hasn't expired yet.
+    Fresh,
+    /// The metadata has expired, but within the soft TTL, so it's used while it's refreshed.
+    Stale,
+    /// The metadata is past the soft TTL, so it's refreshed before it's used.
+    Expired,
+}
+
+/// The metadata from a URL, which is cached in one file with its expiry date in another.
+pub(crate) struct CachedMetadata {
+    url: String,
+    file: PathBuf,
+    expiry_file: PathBuf,
+}
+
+impl CachedMetadata {
+    /// The cached Node index, which is kept in the Node cache directory.
+    pub(crate) fn node_index(url: &str) -> Fallible<Self> {
+        Ok(CachedMetadata {
+            url: url.to_string(),
+            file: path::node_index_file()?,
+            expiry_file: path::node_index_expiry_file()?,
+        })
+    }
+
+    /// The cached metadata from any other URL, which is named after a hash of the URL.
+    pub(crate) fn for_url(url: &str) -> Fallible<Self> {
+        let name = hex::encode(&Sha256::digest(url.as_bytes()));
+        let dir = path::metadata_cache_dir()?;
+        Ok(CachedMetadata {
+            url: url.to_string(),
+            file: dir.join(format!("{}.json", name)),
+            expiry_file: dir.join(format!("{}.json.expires", name)),
+        })
+    }
+
+    /// Reads the cached metadata if it hasn't expired, without fetching it.
+    pub(crate) fn read_fresh(&self) -> Option<String> {
+        match self.read(SystemTime::now()) {
+            Some((body, Freshness::Fresh)) => Some(body),
+            _ => None,
+        }
+    }
+
+    /// Gets the metadata from the cache, or with `request` if it isn't cached or has expired, in
+    /// which case a spinner shows the `fetching` message. Metadata that expired within the soft
+    /// TTL is still used, and a new copy is fetched on a background thread for next time. If the
+    /// command finishes first, the next one tries again, until the soft TTL has passed.
+    pub(crate) fn get<F>(self, fetching: &str, request: F) -> reqwest::Result<String>
+    where
+        F: Fn() -> reqwest::Result<reqwest::Response> + Send + 'static,
+    {
+        match self.read(SystemTime::now()) {
+            Some((body, Freshness::Fresh)) => Ok(body),
+            Some((body, Freshness::Stale)) => {
+                debug!(
+                    "Using the cached metadata for {} while it's refreshed",
+                    self.url
+                );
+                thread::spawn(move || {
+                    if let Err(error) = self.fetch(&request) {
+                        debug!("Could not refresh the metadata for {}: {}", self.url, error);
+                    }
+                });
+                Ok(body)
+            }
+            _ => {
+                let spinner = progress_spinner(fetching);
+                let fetched = self.fetch(&request);
+                spinner.finish_and_clear();
+                fetched
+            }
+        }
+    }
+
+    /// Reads the cached metadata and how fresh it is. Metadata that's missing or can't be read is
+    /// treated as not cached, since it can always be fetched again.
+    fn read(&self, now: SystemTime) -> Option<(String, Freshness)> {
+        let expiry = read_file_opt(&self.expiry_file).ok()??;
+        let expiry = HttpDate::from_str(expiry.trim()).ok()?;
+        let body = read_file_opt(&self.file).ok()??;
+
+        Some((body, freshness(&expiry, now)))
+    }
+
+    /// Fetches the metadata and caches it, unless the Volta directory is read-only. Failing to
+    /// cache it is only logged, since the metadata was still fetched.
+    fn fetch<F>(&self, request: &F) -> reqwest::Result<String>
+    where
+        F: Fn() -> reqwest::Result<reqwest::Response>,
+    {
+        let mut response = request()?.error_for_status()?;
+        let body = response.text()?;
+        let expiry = expiry(&response, SystemTime::now());
+
+        if path::is_read_only_home() {
+            debug!(
+                "Not caching the metadata for {}, since the Volta directory is read-only.",
+                self.url
+            );
+        } else if let Err(error) = self.write(&body, &expiry) {
+            debug!("Could not cache the metadata for {}: {}", self.url, error);
+        }
+
+        Ok(body)
+    }
+
+    /// Writes the metadata before its expiry date, so that old metadata is never given a new
+    /// expiry date if writing stops partway.
+    fn write(&self, body: &str, expiry: &HttpDate) -> io::Result<()> {
+        write_atomic(&self.file, body.as_bytes())?;
+        write_atomic(&self.expiry_file, expiry.to_string().as_bytes())
+    }
+}
+
+fn freshness(expiry: &HttpDate, now: SystemTime) -> Freshness {
+    if HttpDate::from(now) < *expiry {
+        Freshness::Fresh
+    } else if HttpDate::from(now - SOFT_TTL) < *expiry {
+        Freshness::Stale
+    } else {
+        Freshness::Expired
+    }
+}
+
+/// Determines when a response expires, from its `Expires` header or else from the max-age in its
+/// `Cache-Control` header.
+fn expiry(response: &reqwest::Response, now: SystemTime) -> HttpDate {
+    match response.headers().get_011::<Expires>() {
+        Some(expires) => expires.0,
+        None => HttpDate::from(now + Duration::from_secs(max_age(response).into())),
+    }
+}
+
+/// Get the cache max-age of an HTTP reponse.
+fn max_age(response: &reqwest::Response) -> u32 {
+    if let Some(cache_control_header) = response.headers().get_011::<CacheControl>() {
+        for cache_directive in cache_control_header.iter() {
+            if let CacheDirective::MaxAge(max_age) = cache_directive {
+                return *max_age;
+            }
+        }
+    }
+
+    DEFAULT_MAX_AGE
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn cached_in(dir: &Path) -> CachedMetadata {
+        CachedMetadata {
+            url: "https://nodejs.org/dist/index.json".to_string(),
+            file: dir.join("index.json"),
+            expiry_file: dir.join("index.json.expires"),
+        }
+    }
+
+    #[test]
+    fn judges_freshness_from_expiry() {
+        let now = SystemTime::now();
+
+        assert_eq!(
+            freshness(&HttpDate::from(now + MINUTE), now),
+            Freshness::Fresh
+        );
+        assert_eq!(
+            freshness(&HttpDate::from(now - MINUTE), now),
+            Freshness::Stale
+        );
+        assert_eq!(
+            freshness(&HttpDate::from(now - SOFT_TTL - MINUTE), now),
+            Freshness::Expired
+        );
+    }
+
+    #[test]
+    fn reads_what_was_written() {
+        let dir = tempdir().unwrap();
+        let cached = cached_in(dir.path());
+        let now = SystemTime::now();
+
+        assert_eq!(cached.read(now), None);
+
+        cached.write("[]", &HttpDate::from(now + MINUTE)).unwrap();
+        assert_eq!(cached.read(now), Some(("[]".to_string(), Freshness::Fresh)));
+        assert_eq!(cached.read_fresh(), Some("[]".to_string()));
+
+        cached.write("[{}]", &HttpDate::from(now - MINUTE)).unwrap();
+        assert_eq!(
+            cached.read(now),
+            Some(("[{}]".to_string(), Freshness::Stale))
+        );
+        assert_eq!(cached.read_fresh(), None);
+    }
+
+    #[test]
+    fn ignores_unreadable_expiry() {
+        let dir = tempdir().unwrap();
+        let cached = cached_in(dir.path());
+        fs::write(&cached.file, "[]").unwrap();
+        fs::write(&cached.expiry_file, "tomorrow").unwrap();
+
+        assert_eq!(cached.read(SystemTime::now()), None);
+    }
+}
@@ -2,53 +2,59 @@
 //! of available tool versions.
 
 use std::collections::{BTreeSet, HashSet};
-use std::fs::File;
-use std::io::Write;
 use std::marker::PhantomData;
-use std::str::FromStr;
 use std::string::ToString;
-use std::time::{Duration, SystemTime};
 
-use headers_011::Headers011;
 use lazycell::LazyCell;
-use log::debug;
+use log::{debug, trace};
 use reqwest;
-use reqwest::hyper_011::header::{CacheControl, CacheDirective, Expires, HttpDate};
 use semver::{Version, VersionReq};
 use serde_json;
-use tempfile::NamedTempFile;
 use volta_fail::{throw, Fallible, ResultExt};
 
-use crate::distro::node::{NodeDistro, NodeVersion};
-use crate::distro::package::{PackageDistro, PackageEntry, PackageIndex, PackageVersion};
-use crate::distro::yarn::YarnDistro;
+use crate::checksum::ArchiveHashes;
+use crate::distro::bun::BunDistro;
+use crate::distro::deno::DenoDistro;
+use crate::distro::github::{self, GitHubReleaseDistro, GitHubReleaseTool};
+use crate::distro::node::{self, NodeDistro, NodeVersion};
+use crate::distro::package::{
+    registry_get, PackageDistro, PackageEntry, PackageIndex, PackageVersion,
+};
+use crate::distro::yarn::{self, YarnDistro, YARN_BERRY_PACKAGE};
 use crate::distro::{Distro, Fetched};
 use crate::error::ErrorDetails;
-use crate::fs::{ensure_containing_dir_exists, read_file_opt};
+use crate::hook::tool::IndexFormat;
 use crate::hook::ToolHooks;
-use crate::path;
+use crate::npmrc::Npmrc;
+use crate::policy::Policy;
 use crate::style::progress_spinner;
-use crate::version::VersionSpec;
+use crate::version::{nearest_versions, newest_per_major, ReleaseChannel, VersionSpec};
 
+mod cache;
 pub(crate) mod serial;
 
+use self::cache::CachedMetadata;
+
 #[cfg(feature = "mock-network")]
-use mockito;
+use crate::env::mock_server_url;
 
 // ISSUE (#86): Move public repository URLs to config file
 cfg_if::cfg_if! {
     if #[cfg(feature = "mock-network")] {
         fn public_node_version_index() -> String {
-            format!("{}/node-dist/index.json", mockito::SERVER_URL)
+            format!("{}/node-dist/index.json", mock_server_url())
         }
         fn public_yarn_version_index() -> String {
-            format!("{}/yarn-releases/index.json", mockito::SERVER_URL)
+            format!("{}/yarn-releases/index.json", mock_server_url())
         }
         fn public_yarn_latest_version() -> String {
-            format!("{}/yarn-latest", mockito::SERVER_URL)
+            format!("{}/yarn-latest", mock_server_url())
+        }
+        fn public_yarn_berry_index() -> String {
+            format!("{}/registry/{}", mock_server_url(), YARN_BERRY_PACKAGE)
         }
-        fn public_package_registry_root() -> String {
-            format!("{}/registry", mockito::SERVER_URL)
+        pub(crate) fn public_package_registry_root() -> String {
+            format!("{}/registry", mock_server_url())
         }
     } else {
         /// Returns the URL of the index of available Node versions on the public Node server.
@@ -63,8 +69,12 @@ cfg_if::cfg_if! {
         fn public_yarn_latest_version() -> String {
             "https://yarnpkg.com/latest-version".to_string()
         }
+        /// URL of the npm registry metadata for Yarn 2+ ("Berry") releases.
+        fn public_yarn_berry_index() -> String {
+            format!("https://registry.npmjs.org/{}", YARN_BERRY_PACKAGE)
+        }
         /// URL of the Npm registry containing an index of availble public packages.
-        fn public_package_registry_root() -> String {
+        pub(crate) fn public_package_registry_root() -> String {
             "https://registry.npmjs.org".to_string()
         }
     }
@@ -103,12 +113,16 @@ pub struct Collection<D: Distro> {
 
 pub type NodeCollection = Collection<NodeDistro>;
 pub type YarnCollection = Collection<YarnDistro>;
+pub type DenoCollection = Collection<DenoDistro>;
+pub type BunCollection = Collection<BunDistro>;
 pub type PackageCollection = Collection<PackageDistro>;
 
 /// The inventory of locally available tool versions.
 pub struct Inventory {
     pub node: NodeCollection,
     pub yarn: YarnCollection,
+    pub deno: DenoCollection,
+    pub bun: BunCollection,
     pub packages: PackageCollection,
 }
 
@@ -118,6 +132,8 @@ impl Inventory {
         Ok(Inventory {
             node: NodeCollection::load()?,
             yarn: YarnCollection::load()?,
+            deno: DenoCollection::load()?,
+            bun: BunCollection::load()?,
             packages: PackageCollection::load()?,
         })
     }
@@ -148,14 +164,37 @@ pub trait FetchResolve<D: Distro> {
         matching: &VersionSpec,
         hooks: Option<&ToolHooks<D>>,
     ) -> Fallible<D> {
+        let version = self.resolve_version(name, matching, hooks)?;
+        D::new(name, version, hooks)
+    }
+
+    /// Resolves the specified semantic versioning requirements into a version of this tool
+    fn resolve_version(
+        &self,
+        name: &str,
+        matching: &VersionSpec,
+        hooks: Option<&ToolHooks<D>>,
+    ) -> Fallible<D::ResolvedVersion> {
+        trace!("Resolving {}@{}", name, matching);
         let version = match matching {
             VersionSpec::Latest => self.resolve_latest(&name, hooks)?,
             VersionSpec::Lts => self.resolve_lts(&name, hooks)?,
             VersionSpec::Semver(requirement) => self.resolve_semver(&name, requirement, hooks)?,
             VersionSpec::Exact(version) => self.resolve_exact(&name, version.to_owned(), hooks)?,
+            VersionSpec::Channel(channel) => self.resolve_channel(&name, *channel, hooks)?,
         };
+        self.check_policy(&version, hooks)?;
 
-        D::new(name, version, hooks)
+        Ok(version)
+    }
+
+    /// Checks that the version policy, if there is one, allows a resolved version of this tool
+    fn check_policy(
+        &self,
+        _version: &D::ResolvedVersion,
+        _hooks: Option<&ToolHooks<D>>,
+    ) -> Fallible<()> {
+        Ok(())
     }
 
     /// Resolves the latest version for this tool, using either the `latest` hook or the public registry
@@ -189,6 +228,29 @@ pub trait FetchResolve<D: Distro> {
         version: Version,
         hooks: Option<&ToolHooks<D>>,
     ) -> Fallible<D::ResolvedVersion>;
+
+    /// Resolves the newest build on a pre-release channel of this tool
+    fn resolve_channel(
+        &self,
+        name: &str,
+        channel: ReleaseChannel,
+        _hooks: Option<&ToolHooks<D>>,
+    ) -> Fallible<D::ResolvedVersion> {
+        throw!(ErrorDetails::UnsupportedReleaseChannel {
+            tool: name.to_string(),
+            channel: channel.to_string(),
+        })
+    }
+}
+
+/// Returns the URL of the index of available Node versions, which comes from the unofficial
+/// builds server on platforms that nodejs.org doesn't publish builds for.
+fn node_version_index() -> String {
+    if node::use_unofficial_builds() {
+        node::unofficial_node_version_index()
+    } else {
+        public_node_version_index()
+    }
 }
 
 fn registry_fetch_error(
@@ -211,24 +273,139 @@ fn match_node_version(
         .map(|NodeEntry { version, .. }| version))
 }
 
-impl FetchResolve<NodeDistro> for NodeCollection {
-    type FetchedVersion = NodeVersion;
+/// Finds the ABI version that native modules must be built against to be loaded by the specified
+/// version of Node, from the Node index, if the index records it.
+pub fn node_abi(version: &Version, hooks: Option<&ToolHooks<NodeDistro>>) -> Fallible<Option<u32>> {
+    let url = node_index_url(hooks)?;
+    let index = resolve_node_versions(&url)?.into_index()?;
+    Ok(index
+        .entries
+        .into_iter()
+        .find(|entry| &entry.version == version)
+        .and_then(|entry| entry.modules))
+}
 
-    fn fetch(
+fn node_index_url(hooks: Option<&ToolHooks<NodeDistro>>) -> Fallible<String> {
+    match hooks {
+        Some(&ToolHooks {
+            index: Some(ref hook),
+            ..
+        }) => {
+            debug!("Using node.index hook to determine node index URL");
+            hook.resolve("index.json")
+        }
+        _ => Ok(node_version_index()),
+    }
+}
+
+/// The number of alternatives suggested when a requested version can't be found
+const SUGGESTION_COUNT: usize = 3;
+
+fn nearest_node_versions(matching: &str, index: &NodeIndex) -> Vec<String> {
+    let available = index.entries.iter().map(|entry| &entry.version);
+    nearest_versions(matching, available, SUGGESTION_COUNT)
+        .iter()
+        .map(Version::to_string)
+        .collect()
+}
+
+/// The number of release lines offered when a version is chosen interactively
+const CHOICE_COUNT: usize = 6;
+
+impl NodeCollection {
+    /// Finds the newest version of each of the most recent release lines of Node, newest first,
+    /// along with whether the line is an LTS line.
+    pub fn recent_versions(
+        &self,
+        hooks: Option<&ToolHooks<NodeDistro>>,
+    ) -> Fallible<Vec<(Version, bool)>> {
+        let url = node_index_url(hooks)?;
+        let index = resolve_node_versions(&url)?.into_index()?;
+        let newest = newest_per_major(
+            index.entries.iter().map(|entry| &entry.version),
+            CHOICE_COUNT,
+        );
+
+        Ok(newest
+            .into_iter()
+            .map(|version| {
+                let lts = index
+                    .entries
+                    .iter()
+                    .any(|entry| entry.version == version && entry.lts);
+                (version, lts)
+            })
+            .collect())
+    }
+
+    /// Fetches a Node version matching the specified semantic versioning requirements, for the
+    /// specified architecture or for the native one if no architecture is specified.
+    pub fn fetch_for_arch(
         &mut self,
-        name: &str, // not used here, we already know this is "node"
         matching: &VersionSpec,
+        arch: Option<&str>,
         hooks: Option<&ToolHooks<NodeDistro>>,
     ) -> Fallible<Fetched<NodeVersion>> {
-        let distro = self.resolve(name, matching, hooks)?;
+        let version = self.resolve_version("node", matching, hooks)?;
+        self.fetch_version(version, arch, &ArchiveHashes::new(), hooks)
+    }
+
+    /// Fetches an exact version of Node for the specified architecture, or for the native one if
+    /// no architecture is specified. If SHA-256s are pinned for its archives, it is only installed
+    /// from an archive that matches, and a version that was already fetched has to have been
+    /// installed from one.
+    pub fn fetch_version(
+        &mut self,
+        version: Version,
+        arch: Option<&str>,
+        sha256: &ArchiveHashes,
+        hooks: Option<&ToolHooks<NodeDistro>>,
+    ) -> Fallible<Fetched<NodeVersion>> {
+        // The archive of a fetched version may have been pruned (see `VOLTA_KEEP_ARCHIVES`), so
+        // the distro, which opens or downloads the archive, is only provisioned when needed
+        let already_fetched = match arch {
+            Some(arch) => node::is_fetched_for_arch(&version, arch),
+            None => self.contains(&version),
+        };
+        if already_fetched {
+            node::check_fetched_archive(&version, arch, sha256)?;
+            let npm = node::load_default_npm_version(&version)?;
+            return Ok(Fetched::Already(NodeVersion {
+                runtime: version,
+                npm,
+                arch: arch.map(str::to_string),
+            }));
+        }
+
+        let distro = NodeDistro::for_arch(version, arch, hooks)?.with_sha256(sha256);
+        trace!("Fetching node@{}", distro.version());
         let fetched = distro.fetch(&self)?;
 
-        if let &Fetched::Now(NodeVersion { ref runtime, .. }) = &fetched {
+        // The collection only tracks builds for the native architecture
+        if let &Fetched::Now(NodeVersion {
+            ref runtime,
+            arch: None,
+            ..
+        }) = &fetched
+        {
             self.versions.insert(runtime.clone());
         }
 
         Ok(fetched)
     }
+}
+
+impl FetchResolve<NodeDistro> for NodeCollection {
+    type FetchedVersion = NodeVersion;
+
+    fn fetch(
+        &mut self,
+        _name: &str, // not used here, we already know this is "node"
+        matching: &VersionSpec,
+        hooks: Option<&ToolHooks<NodeDistro>>,
+    ) -> Fallible<Fetched<NodeVersion>> {
+        self.fetch_for_arch(matching, None, hooks)
+    }
 
     fn resolve_latest(
         &self,
@@ -246,7 +423,7 @@ impl FetchResolve<NodeDistro> for NodeCollection {
                 debug!("Using node.latest hook to determine node index URL");
                 hook.resolve("index.json")?
             }
-            _ => public_node_version_index(),
+            _ => node_version_index(),
         };
         let version_opt = match_node_version(&url, |_| true)?;
 
@@ -255,7 +432,8 @@ impl FetchResolve<NodeDistro> for NodeCollection {
             Ok(version)
         } else {
             throw!(ErrorDetails::NodeVersionNotFound {
-                matching: "latest".to_string()
+                matching: "latest".to_string(),
+                suggestions: Vec::new(),
             })
         }
     }
@@ -267,18 +445,13 @@ impl FetchResolve<NodeDistro> for NodeCollection {
         hooks: Option<&ToolHooks<NodeDistro>>,
     ) -> Fallible<Version> {
         // ISSUE #34: also make sure this OS is available for this version
-        let url = match hooks {
-            Some(&ToolHooks {
-                index: Some(ref hook),
-                ..
-            }) => {
-                debug!("Using node.index hook to determine node index URL");
-                hook.resolve("index.json")?
-            }
-            _ => public_node_version_index(),
-        };
-        let version_opt =
-            match_node_version(&url, |NodeEntry { version, .. }| matching.matches(version))?;
+        let url = node_index_url(hooks)?;
+        let index = resolve_node_versions(&url)?.into_index()?;
+        let version_opt = index
+            .entries
+            .iter()
+            .find(|NodeEntry { version, .. }| matching.matches(version))
+            .map(|NodeEntry { version, .. }| version.clone());
 
         if let Some(version) = version_opt {
             debug!(
@@ -287,23 +460,17 @@ impl FetchResolve<NodeDistro> for NodeCollection {
             );
             Ok(version)
         } else {
+            let matching = matching.to_string();
+            let suggestions = nearest_node_versions(&matching, &index);
             throw!(ErrorDetails::NodeVersionNotFound {
-                matching: matching.to_string()
+                matching,
+                suggestions,
             })
         }
     }
 
     fn resolve_lts(&self, _name: &str, hooks: Option<&ToolHooks<NodeDistro>>) -> Fallible<Version> {
-        let url = match hooks {
-            Some(&ToolHooks {
-                index: Some(ref hook),
-                ..
-            }) => {
-                debug!("Using node.index hook to determine node index URL");
-                hook.resolve("index.json")?
-            }
-            _ => public_node_version_index(),
-        };
+        let url = node_index_url(hooks)?;
         let version_opt = match_node_version(&url, |&NodeEntry { lts, .. }| lts)?;
 
         if let Some(version) = version_opt {
@@ -311,7 +478,8 @@ impl FetchResolve<NodeDistro> for NodeCollection {
             Ok(version)
         } else {
             throw!(ErrorDetails::NodeVersionNotFound {
-                matching: "lts".to_string()
+                matching: "lts".to_string(),
+                suggestions: Vec::new(),
             })
         }
     }
@@ -320,10 +488,85 @@ impl FetchResolve<NodeDistro> for NodeCollection {
         &self,
         _name: &str,
         version: Version,
-        _hooks: Option<&ToolHooks<NodeDistro>>,
+        hooks: Option<&ToolHooks<NodeDistro>>,
     ) -> Fallible<Version> {
+        // Exact versions are normally trusted without consulting the index, but when the cached
+        // copy of the public index shows that one doesn't exist, we can say so (and offer the
+        // nearest alternatives) before attempting a download that's bound to fail.
+        let custom_source = hooks.map_or(false, |hooks| {
+            hooks.distro.is_some() || hooks.index.is_some()
+        });
+        if custom_source
+            || node::use_unofficial_builds()
+            || version.is_prerelease()
+            || self.versions.contains(&version)
+        {
+            return Ok(version);
+        }
+
+        if let Ok(Some(cached)) = read_cached_opt() {
+            let index = cached.into_index()?;
+            let newest = index.entries.iter().map(|entry| &entry.version).max();
+            let listed = index.entries.iter().any(|entry| entry.version == version);
+
+            // A version newer than any in the cache may simply have been released since
+            if !listed && newest.map_or(false, |newest| &version < newest) {
+                let matching = version.to_string();
+                let suggestions = nearest_node_versions(&matching, &index);
+                throw!(ErrorDetails::NodeVersionNotFound {
+                    matching,
+                    suggestions,
+                });
+            }
+        }
+
         Ok(version)
     }
+
+    fn resolve_channel(
+        &self,
+        _name: &str,
+        channel: ReleaseChannel,
+        _hooks: Option<&ToolHooks<NodeDistro>>,
+    ) -> Fallible<Version> {
+        // Pre-release builds are only ever resolved from their channel's own index, never
+        // from the stable index, so they can't satisfy a regular semver requirement
+        let url = node::public_node_channel_index(channel);
+        let index = fetch_node_channel_versions(&url)?.into_index()?;
+        let version_opt = index
+            .entries
+            .into_iter()
+            .next()
+            .map(|NodeEntry { version, .. }| version);
+
+        if let Some(version) = version_opt {
+            debug!(
+                "Found newest {} node version ({}) from {}",
+                channel, version, url
+            );
+            Ok(version)
+        } else {
+            throw!(ErrorDetails::NodeVersionNotFound {
+                matching: channel.to_string(),
+                suggestions: Vec::new(),
+            })
+        }
+    }
+
+    fn check_policy(
+        &self,
+        version: &Version,
+        hooks: Option<&ToolHooks<NodeDistro>>,
+    ) -> Fallible<()> {
+        match Policy::current()? {
+            Some(policy) => policy.check_node(version, || {
+                let url = node_index_url(hooks)?;
+                let lts = match_node_version(&url, |entry| &entry.version == version && entry.lts)?;
+                Ok(lts.is_some())
+            }),
+            None => Ok(()),
+        }
+    }
 }
 
 impl FetchResolve<YarnDistro> for YarnCollection {
@@ -336,14 +579,8 @@ impl FetchResolve<YarnDistro> for YarnCollection {
         matching: &VersionSpec,
         hooks: Option<&ToolHooks<YarnDistro>>,
     ) -> Fallible<Fetched<Self::FetchedVersion>> {
-        let distro = self.resolve(name, &matching, hooks)?;
-        let fetched = distro.fetch(&self)?;
-
-        if let &Fetched::Now(ref version) = &fetched {
-            self.versions.insert(version.clone());
-        }
-
-        Ok(fetched)
+        let version = self.resolve_version(name, &matching, hooks)?;
+        self.fetch_version(version, &ArchiveHashes::new(), hooks)
     }
 
     fn resolve_latest(
@@ -361,8 +598,11 @@ impl FetchResolve<YarnDistro> for YarnCollection {
             }
             _ => public_yarn_latest_version(),
         };
-        let response_text = reqwest::get(&url)
-            .and_then(|mut resp| resp.text())
+        let response_text = CachedMetadata::for_url(&url)?
+            .get(
+                &format!("Fetching public registry: {}", url),
+                public_get(&url),
+            )
             .with_context(|_| ErrorDetails::YarnLatestFetchError {
                 from_url: url.clone(),
             })?;
@@ -377,23 +617,7 @@ impl FetchResolve<YarnDistro> for YarnCollection {
         matching: &VersionReq,
         hooks: Option<&ToolHooks<YarnDistro>>,
     ) -> Fallible<Version> {
-        let url = match hooks {
-            Some(&ToolHooks {
-                index: Some(ref hook),
-                ..
-            }) => {
-                debug!("Using yarn.index hook to determine yarn index URL");
-                hook.resolve("releases")?
-            }
-            _ => public_yarn_version_index(),
-        };
-
-        let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
-        let releases: serial::YarnIndex = reqwest::get(&url)
-            .and_then(|mut resp| resp.json())
-            .with_context(registry_fetch_error("Yarn", &url))?;
-        let releases = releases.into_index()?.entries;
-        spinner.finish_and_clear();
+        let (url, releases) = resolve_yarn_versions(hooks)?;
         let version_opt = releases.into_iter().rev().find(|v| matching.matches(v));
 
         if let Some(version) = version_opt {
@@ -417,6 +641,254 @@ impl FetchResolve<YarnDistro> for YarnCollection {
     ) -> Fallible<Version> {
         Ok(version)
     }
+
+    fn check_policy(
+        &self,
+        version: &Version,
+        _hooks: Option<&ToolHooks<YarnDistro>>,
+    ) -> Fallible<()> {
+        match Policy::current()? {
+            Some(policy) => policy.check_yarn(version),
+            None => Ok(()),
+        }
+    }
+}
+
+impl YarnCollection {
+    /// Fetches an exact version of Yarn. If SHA-256s are pinned for its archives, it is only
+    /// installed from an archive that matches, and a version that was already fetched has to have
+    /// been installed from one.
+    pub fn fetch_version(
+        &mut self,
+        version: Version,
+        sha256: &ArchiveHashes,
+        hooks: Option<&ToolHooks<YarnDistro>>,
+    ) -> Fallible<Fetched<Version>> {
+        // The archive of a fetched version may have been pruned (see `VOLTA_KEEP_ARCHIVES`), so
+        // the distro, which opens or downloads the archive, is only provisioned when needed
+        if self.contains(&version) {
+            yarn::check_fetched_archive(&version, sha256)?;
+            return Ok(Fetched::Already(version));
+        }
+
+        let distro = YarnDistro::new("yarn", version, hooks)?.with_sha256(sha256);
+        trace!("Fetching yarn@{}", distro.version());
+        let fetched = distro.fetch(&self)?;
+
+        if let &Fetched::Now(ref version) = &fetched {
+            self.versions.insert(version.clone());
+        }
+
+        Ok(fetched)
+    }
+
+    /// Finds the newest version of each of the most recent major versions of Yarn, newest first.
+    pub fn recent_versions(&self, hooks: Option<&ToolHooks<YarnDistro>>) -> Fallible<Vec<Version>> {
+        let (_, releases) = resolve_yarn_versions(hooks)?;
+        Ok(newest_per_major(&releases, CHOICE_COUNT))
+    }
+}
+
+/// Resolves the available Yarn versions, along with the URL of the index they were listed in.
+fn resolve_yarn_versions(
+    hooks: Option<&ToolHooks<YarnDistro>>,
+) -> Fallible<(String, BTreeSet<Version>)> {
+    // Yarn 2+ releases are only included when using the public index, since a custom
+    // index is expected to list every version it supports
+    let (url, include_berry) = match hooks {
+        Some(&ToolHooks {
+            index: Some(ref hook),
+            ..
+        }) => {
+            debug!("Using yarn.index hook to determine yarn index URL");
+            (hook.resolve("releases")?, false)
+        }
+        _ => (public_yarn_version_index(), true),
+    };
+
+    let format = match hooks {
+        Some(&ToolHooks {
+            index_format: Some(format),
+            ..
+        }) => format,
+        _ => IndexFormat::Github,
+    };
+
+    let response_text = CachedMetadata::for_url(&url)?
+        .get(
+            &format!("Fetching public registry: {}", url),
+            public_get(&url),
+        )
+        .with_context(registry_fetch_error("Yarn", &url))?;
+    let mut releases = yarn::parse_index(format, &response_text, &url)?;
+
+    if include_berry {
+        releases.extend(resolve_yarn_berry_versions()?);
+    }
+
+    Ok((url, releases))
+}
+
+/// Resolves the available Yarn 2+ ("Berry") versions, which are published to the npm registry
+/// rather than as GitHub releases.
+fn resolve_yarn_berry_versions() -> Fallible<Vec<Version>> {
+    let url = public_yarn_berry_index();
+    let index = resolve_package_metadata(YARN_BERRY_PACKAGE, &url, None)?.into_index();
+    Ok(index
+        .entries
+        .into_iter()
+        .map(|entry| entry.version)
+        .collect())
+}
+
+/// The most releases that the GitHub API lists on one page.
+const GITHUB_RELEASES_PER_PAGE: usize = 100;
+
+/// Fetches the versions of a tool that is published on GitHub. The public listing is split into
+/// pages, which are fetched until one isn't full; a listing from a hook is read as it is.
+fn resolve_github_versions<T>(url: &str, paginate: bool) -> Fallible<BTreeSet<Version>>
+where
+    T: GitHubReleaseTool,
+{
+    let mut versions = BTreeSet::new();
+    let mut page = 1;
+
+    loop {
+        let page_url = if page == 1 {
+            url.to_string()
+        } else {
+            format!("{}&page={}", url, page)
+        };
+        let response_text = CachedMetadata::for_url(&page_url)?
+            .get(
+                &format!("Fetching public registry: {}", page_url),
+                public_get(&page_url),
+            )
+            .with_context(registry_fetch_error(T::DISPLAY_NAME, &page_url))?;
+        let (count, page_versions) = github::parse_releases::<T>(&response_text, &page_url)?;
+        versions.extend(page_versions);
+
+        if !paginate || count < GITHUB_RELEASES_PER_PAGE {
+            return Ok(versions);
+        }
+        page += 1;
+    }
+}
+
+impl<T: GitHubReleaseTool> FetchResolve<GitHubReleaseDistro<T>>
+    for Collection<GitHubReleaseDistro<T>>
+{
+    type FetchedVersion = Version;
+
+    /// Fetches a version matching the specified semantic versioning requirements.
+    fn fetch(
+        &mut self,
+        name: &str, // not used here, the tool is already known
+        matching: &VersionSpec,
+        hooks: Option<&ToolHooks<GitHubReleaseDistro<T>>>,
+    ) -> Fallible<Fetched<Self::FetchedVersion>> {
+        let version = self.resolve_version(name, &matching, hooks)?;
+
+        // The archive of a fetched version may have been pruned (see `VOLTA_KEEP_ARCHIVES`), so
+        // the distro, which opens or downloads the archive, is only provisioned when needed
+        if self.contains(&version) {
+            return Ok(Fetched::Already(version));
+        }
+
+        let distro = GitHubReleaseDistro::<T>::new(name, version, hooks)?;
+        trace!("Fetching {}@{}", name, distro.version());
+        let fetched = distro.fetch(&self)?;
+
+        if let &Fetched::Now(ref version) = &fetched {
+            self.versions.insert(version.clone());
+        }
+
+        Ok(fetched)
+    }
+
+    fn resolve_latest(
+        &self,
+        _name: &str,
+        hooks: Option<&ToolHooks<GitHubReleaseDistro<T>>>,
+    ) -> Fallible<Version> {
+        let (url, paginate) = match hooks {
+            Some(&ToolHooks {
+                latest: Some(ref hook),
+                ..
+            }) => {
+                debug!(
+                    "Using {}.latest hook to determine {} index URL",
+                    T::NAME,
+                    T::NAME
+                );
+                (hook.resolve("releases")?, false)
+            }
+            _ => (T::public_index_url(), true),
+        };
+        let version_opt = resolve_github_versions::<T>(&url, paginate)?
+            .into_iter()
+            .next_back();
+
+        if let Some(version) = version_opt {
+            debug!(
+                "Found latest {} version ({}) from {}",
+                T::NAME,
+                version,
+                url
+            );
+            Ok(version)
+        } else {
+            throw!(T::version_not_found("latest".to_string()))
+        }
+    }
+
+    fn resolve_semver(
+        &self,
+        _name: &str,
+        matching: &VersionReq,
+        hooks: Option<&ToolHooks<GitHubReleaseDistro<T>>>,
+    ) -> Fallible<Version> {
+        let (url, paginate) = match hooks {
+            Some(&ToolHooks {
+                index: Some(ref hook),
+                ..
+            }) => {
+                debug!(
+                    "Using {}.index hook to determine {} index URL",
+                    T::NAME,
+                    T::NAME
+                );
+                (hook.resolve("releases")?, false)
+            }
+            _ => (T::public_index_url(), true),
+        };
+        let version_opt = resolve_github_versions::<T>(&url, paginate)?
+            .into_iter()
+            .rev()
+            .find(|v| matching.matches(v));
+
+        if let Some(version) = version_opt {
+            debug!(
+                "Found {}@{} matching requirement '{}' from {}",
+                T::NAME,
+                version,
+                matching,
+                url
+            );
+            Ok(version)
+        } else {
+            throw!(T::version_not_found(matching.to_string()))
+        }
+    }
+
+    fn resolve_exact(
+        &self,
+        _name: &str,
+        version: Version,
+        _hooks: Option<&ToolHooks<GitHubReleaseDistro<T>>>,
+    ) -> Fallible<Version> {
+        Ok(version)
+    }
 }
 
 // use the input predicate to match a package in the index
@@ -428,15 +900,19 @@ fn match_package_entry(
     entries.find(predicate)
 }
 
-// fetch metadata for the input url
+// fetch metadata for the input url, sending the `Authorization` header if there is one
 fn resolve_package_metadata(
     package_name: &str,
     package_info_url: &str,
+    authorization: Option<&str>,
 ) -> Fallible<serial::PackageMetadata> {
-    let spinner = progress_spinner(&format!("Fetching package metadata: {}", package_info_url));
-    let response_text = reqwest::get(package_info_url)
-        .and_then(|resp| resp.error_for_status())
-        .and_then(|mut resp| resp.text())
+    let url = package_info_url.to_string();
+    let authorization = authorization.map(str::to_string);
+    let response_text = CachedMetadata::for_url(package_info_url)?
+        .get(
+            &format!("Fetching package metadata: {}", package_info_url),
+            move || registry_get(&url, authorization.as_ref().map(String::as_str)),
+        )
         .with_context(|err| match err.status() {
             Some(reqwest::StatusCode::NOT_FOUND) => ErrorDetails::PackageNotFound {
                 package: package_name.into(),
@@ -453,10 +929,66 @@ fn resolve_package_metadata(
             }
         })?;
 
-    spinner.finish_and_clear();
     Ok(metadata)
 }
 
+// fetch the index of versions for a package, using any credentials from `.npmrc` for the
+// package's registry for both the metadata and the tarballs served from that registry. Credentials
+// from a hook are used instead for the hook's registry. Neither is sent to any other host.
+fn resolve_package_index(
+    package_name: &str,
+    package_info_url: &str,
+    hook_authorization: Option<&str>,
+    npmrc: &Npmrc,
+) -> Fallible<PackageIndex> {
+    let registry = package_registry(package_name, npmrc);
+    let authorization_for = |url: &str| match hook_authorization {
+        Some(authorization) if same_origin(url, package_info_url) => {
+            Some(authorization.to_string())
+        }
+        _ if same_origin(url, &registry) => npmrc.authorization_for(&registry),
+        _ => None,
+    };
+
+    let authorization = authorization_for(package_info_url);
+    let mut index = resolve_package_metadata(
+        package_name,
+        package_info_url,
+        authorization.as_ref().map(String::as_str),
+    )?
+    .into_index();
+
+    for entry in index.entries.iter_mut() {
+        entry.authorization = authorization_for(&entry.tarball);
+    }
+
+    Ok(index)
+}
+
+fn same_origin(left: &str, right: &str) -> bool {
+    match (reqwest::Url::parse(left), reqwest::Url::parse(right)) {
+        (Ok(left), Ok(right)) => left.origin() == right.origin(),
+        _ => false,
+    }
+}
+
+// the registry configured for a package in `.npmrc`, if any, otherwise the public registry
+fn package_registry(package_name: &str, npmrc: &Npmrc) -> String {
+    npmrc
+        .registry_for(package_name)
+        .unwrap_or_else(public_package_registry_root)
+}
+
+// the metadata URL for a package in its registry
+fn package_metadata_url(package_name: &str, npmrc: &Npmrc) -> String {
+    // registries expect the slash in scoped package names to be encoded
+    format!(
+        "{}/{}",
+        package_registry(package_name, npmrc),
+        package_name.replace('/', "%2f")
+    )
+}
+
 impl FetchResolve<PackageDistro> for PackageCollection {
     type FetchedVersion = PackageVersion;
 
@@ -468,6 +1000,7 @@ impl FetchResolve<PackageDistro> for PackageCollection {
         hooks: Option<&ToolHooks<PackageDistro>>,
     ) -> Fallible<Fetched<Self::FetchedVersion>> {
         let distro = self.resolve(name, &matching, hooks)?;
+        trace!("Fetching {}@{}", name, distro.version());
         let fetched = distro.fetch(&self)?;
 
         if let &Fetched::Now(PackageVersion { ref version, .. }) = &fetched {
@@ -482,18 +1015,22 @@ impl FetchResolve<PackageDistro> for PackageCollection {
         name: &str,
         hooks: Option<&ToolHooks<PackageDistro>>,
     ) -> Fallible<PackageEntry> {
-        let url = match hooks {
+        let npmrc = Npmrc::current()?;
+        let hooks = hooks.map(|hooks| hooks.for_package(name));
+        let (url, authorization) = match hooks {
             Some(&ToolHooks {
                 latest: Some(ref hook),
+                ref authorization,
                 ..
             }) => {
                 debug!("Using packages.latest hook to determine package metadata URL");
-                hook.resolve(&name)?
+                (hook.resolve_package(&name)?, authorization.as_ref())
             }
-            _ => format!("{}/{}", public_package_registry_root(), name),
+            _ => (package_metadata_url(name, &npmrc), None),
         };
 
-        let package_index = resolve_package_metadata(name, &url)?.into_index();
+        let package_index =
+            resolve_package_index(name, &url, authorization.map(String::as_str), &npmrc)?;
         let latest = package_index.latest.clone();
 
         let entry_opt = match_package_entry(package_index, |PackageEntry { version, .. }| {
@@ -520,18 +1057,22 @@ impl FetchResolve<PackageDistro> for PackageCollection {
         matching: &VersionReq,
         hooks: Option<&ToolHooks<PackageDistro>>,
     ) -> Fallible<PackageEntry> {
-        let url = match hooks {
+        let npmrc = Npmrc::current()?;
+        let hooks = hooks.map(|hooks| hooks.for_package(name));
+        let (url, authorization) = match hooks {
             Some(&ToolHooks {
                 index: Some(ref hook),
+                ref authorization,
                 ..
             }) => {
                 debug!("Using packages.index hook to determine package metadata URL");
-                hook.resolve(&name)?
+                (hook.resolve_package(&name)?, authorization.as_ref())
             }
-            _ => format!("{}/{}", public_package_registry_root(), name),
+            _ => (package_metadata_url(name, &npmrc), None),
         };
 
-        let package_index = resolve_package_metadata(name, &url)?.into_index();
+        let package_index =
+            resolve_package_index(name, &url, authorization.map(String::as_str), &npmrc)?;
 
         let entry_opt = match_package_entry(package_index, |PackageEntry { version, .. }| {
             matching.matches(&version)
@@ -557,18 +1098,22 @@ impl FetchResolve<PackageDistro> for PackageCollection {
         exact_version: Version,
         hooks: Option<&ToolHooks<PackageDistro>>,
     ) -> Fallible<PackageEntry> {
-        let url = match hooks {
+        let npmrc = Npmrc::current()?;
+        let hooks = hooks.map(|hooks| hooks.for_package(name));
+        let (url, authorization) = match hooks {
             Some(&ToolHooks {
                 index: Some(ref hook),
+                ref authorization,
                 ..
             }) => {
                 debug!("Using packages.index hook to determine package metadata URL");
-                hook.resolve(&name)?
+                (hook.resolve_package(&name)?, authorization.as_ref())
             }
-            _ => format!("{}/{}", public_package_registry_root(), name),
+            _ => (package_metadata_url(name, &npmrc), None),
         };
 
-        let package_index = resolve_package_metadata(name, &url)?.into_index();
+        let package_index =
+            resolve_package_index(name, &url, authorization.map(String::as_str), &npmrc)?;
 
         let entry_opt = match_package_entry(package_index, |PackageEntry { version, .. }| {
             &exact_version == version
@@ -597,11 +1142,13 @@ pub struct NodeEntry {
     pub npm: Version,
     pub files: NodeDistroFiles,
     pub lts: bool,
+    /// The ABI version (`process.versions.modules`) that native modules must be built against.
+    pub modules: Option<u32>,
 }
 
 /// The public Yarn index.
 pub struct YarnIndex {
-    entries: BTreeSet<Version>,
+    pub(crate) entries: BTreeSet<Version>,
 }
 
 /// The set of available files on the public Node server for a given Node version.
@@ -612,124 +1159,56 @@ pub struct NodeDistroFiles {
 
 /// Reads a public index from the Node cache, if it exists and hasn't expired.
 fn read_cached_opt() -> Fallible<Option<serial::NodeIndex>> {
-    let expiry_file = path::node_index_expiry_file()?;
-    let expiry = read_file_opt(&expiry_file)
-        .with_context(|_| ErrorDetails::ReadNodeIndexExpiryError { file: expiry_file })?;
-
-    if let Some(string) = expiry {
-        let expiry_date = HttpDate::from_str(&string)
-            .with_context(|_| ErrorDetails::ParseNodeIndexExpiryError)?;
-        let current_date = HttpDate::from(SystemTime::now());
-
-        if current_date < expiry_date {
-            let index_file = path::node_index_file()?;
-            let cached = read_file_opt(&index_file)
-                .with_context(|_| ErrorDetails::ReadNodeIndexCacheError { file: index_file })?;
-
-            if let Some(string) = cached {
-                return serde_json::de::from_str(&string)
-                    .with_context(|_| ErrorDetails::ParseNodeIndexCacheError);
-            }
-        }
+    match CachedMetadata::node_index(&node_version_index())?.read_fresh() {
+        Some(string) => serde_json::de::from_str(&string)
+            .map(Some)
+            .with_context(|_| ErrorDetails::ParseNodeIndexCacheError),
+        None => Ok(None),
     }
-
-    Ok(None)
 }
 
-/// Get the cache max-age of an HTTP reponse.
-fn max_age(response: &reqwest::Response) -> u32 {
-    if let Some(cache_control_header) = response.headers().get_011::<CacheControl>() {
-        for cache_directive in cache_control_header.iter() {
-            if let CacheDirective::MaxAge(max_age) = cache_directive {
-                return *max_age;
-            }
+/// Fetches the index of a Node release channel. Unlike the public index, these are never
+/// cached, since the nightly and canary channels are updated every day.
+fn fetch_node_channel_versions(url: &str) -> Fallible<serial::NodeIndex> {
+    let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
+
+    let mut response: reqwest::Response =
+        archive::get(url).with_context(registry_fetch_error("Node", url))?;
+    let response_text = response
+        .text()
+        .with_context(registry_fetch_error("Node", url))?;
+    let index = serde_json::de::from_str(&response_text).with_context(|_| {
+        ErrorDetails::ParseNodeIndexError {
+            from_url: url.to_string(),
         }
-    }
+    })?;
 
-    // Default to four hours.
-    4 * 60 * 60
+    spinner.finish_and_clear();
+    Ok(index)
 }
 
 fn resolve_node_versions(url: &str) -> Fallible<serial::NodeIndex> {
-    match read_cached_opt()? {
-        Some(serial) => Ok(serial),
-        None => {
-            let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
-
-            let mut response: reqwest::Response =
-                reqwest::get(url).with_context(registry_fetch_error("Node", url))?;
-            let response_text = response
-                .text()
-                .with_context(registry_fetch_error("Node", url))?;
-            let index: serial::NodeIndex =
-                serde_json::de::from_str(&response_text).with_context(|_| {
-                    ErrorDetails::ParseNodeIndexError {
-                        from_url: url.to_string(),
-                    }
-                })?;
-
-            let tmp_root = path::tmp_dir()?;
-            // Helper to lazily determine temp dir string, without moving the file into the closures below
-            let get_tmp_root = || tmp_root.to_owned();
-
-            let cached = NamedTempFile::new_in(&tmp_root).with_context(|_| {
-                ErrorDetails::CreateTempFileError {
-                    in_dir: get_tmp_root(),
-                }
-            })?;
-
-            // Block to borrow cached for cached_file.
-            {
-                let mut cached_file: &File = cached.as_file();
-                cached_file
-                    .write(response_text.as_bytes())
-                    .with_context(|_| ErrorDetails::WriteNodeIndexCacheError {
-                        file: cached.path().to_path_buf(),
-                    })?;
-            }
-
-            let index_cache_file = path::node_index_file()?;
-            ensure_containing_dir_exists(&index_cache_file)?;
-            cached.persist(&index_cache_file).with_context(|_| {
-                ErrorDetails::WriteNodeIndexCacheError {
-                    file: index_cache_file,
-                }
-            })?;
-
-            let expiry = NamedTempFile::new_in(&tmp_root).with_context(|_| {
-                ErrorDetails::CreateTempFileError {
-                    in_dir: get_tmp_root(),
-                }
-            })?;
-
-            // Block to borrow expiry for expiry_file.
-            {
-                let mut expiry_file: &File = expiry.as_file();
-
-                let result = if let Some(expires_header) = response.headers().get_011::<Expires>() {
-                    write!(expiry_file, "{}", expires_header)
-                } else {
-                    let expiry_date =
-                        SystemTime::now() + Duration::from_secs(max_age(&response).into());
-
-                    write!(expiry_file, "{}", HttpDate::from(expiry_date))
-                };
-
-                result.with_context(|_| ErrorDetails::WriteNodeIndexExpiryError {
-                    file: expiry.path().to_path_buf(),
-                })?;
-            }
-
-            let index_expiry_file = path::node_index_expiry_file()?;
-            ensure_containing_dir_exists(&index_expiry_file)?;
-            expiry.persist(&index_expiry_file).with_context(|_| {
-                ErrorDetails::WriteNodeIndexExpiryError {
-                    file: index_expiry_file,
-                }
-            })?;
+    // Only the index that Node is normally resolved from is kept in the Node cache, where
+    // `resolve_exact` looks for it
+    let cached = if url == node_version_index() {
+        CachedMetadata::node_index(url)?
+    } else {
+        CachedMetadata::for_url(url)?
+    };
+    let response_text = cached
+        .get(
+            &format!("Fetching public registry: {}", url),
+            public_get(url),
+        )
+        .with_context(registry_fetch_error("Node", url))?;
+
+    serde_json::de::from_str(&response_text).with_context(|_| ErrorDetails::ParseNodeIndexError {
+        from_url: url.to_string(),
+    })
+}
 
-            spinner.finish_and_clear();
-            Ok(index)
-        }
-    }
+/// Requests a URL without credentials, for the cache to fetch public metadata with.
+fn public_get(url: &str) -> impl Fn() -> reqwest::Result<reqwest::Response> + Send + 'static {
+    let url = url.to_string();
+    move || archive::get(&url)
 }
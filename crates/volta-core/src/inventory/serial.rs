@@ -1,17 +1,20 @@
 use std::collections::HashMap;
 use std::collections::{BTreeSet, HashSet};
+use std::ffi::OsString;
 use std::fs::{read_to_string, write};
 use std::iter::FromIterator;
 use std::marker::PhantomData;
 use std::path::Path;
 use std::path::PathBuf;
 
-use super::{NodeCollection, PackageCollection, YarnCollection};
+use super::{BunCollection, DenoCollection, NodeCollection, PackageCollection, YarnCollection};
 use crate::distro::package;
 use crate::error::ErrorDetails;
 use crate::fs::ensure_containing_dir_exists;
 use crate::fs::read_dir_eager;
+use crate::fs::write_atomic;
 use crate::path;
+use crate::permissions;
 use crate::toolchain;
 use crate::version::{option_version_serde, version_serde, VersionSpec};
 use volta_fail::{Fallible, ResultExt};
@@ -20,6 +23,39 @@ use regex::Regex;
 use semver::Version;
 use serde::{Deserialize, Deserializer, Serialize};
 
+/// Finds the versions of a tool in its inventory directory in each of the tool roots (see
+/// `path::tools_roots`). A directory won't exist until the first version is fetched into it.
+fn inventory_versions(tool: &str, re: &Regex) -> Fallible<BTreeSet<Version>> {
+    let mut versions = BTreeSet::new();
+    for dir in path::inventory_dirs(tool)? {
+        if dir.is_dir() {
+            versions.extend(versions_matching(&dir, re)?);
+        }
+    }
+    Ok(versions)
+}
+
+/// Finds the versions of a tool that have images in each of the tool roots. An image outlives the
+/// archive it was unpacked from when archives are pruned (see `VOLTA_KEEP_ARCHIVES`), so these
+/// versions are fetched even if they aren't in the inventory directory.
+fn image_versions(tool: &str) -> Fallible<BTreeSet<Version>> {
+    let mut versions = BTreeSet::new();
+    for dir in path::image_dirs(tool)? {
+        if dir.is_dir() {
+            let contents = read_dir_eager(&dir)
+                .with_context(|_| ErrorDetails::ReadInventoryDirError { dir: dir.clone() })?;
+            versions.extend(
+                contents
+                    .filter(|(_, metadata)| metadata.is_dir())
+                    .filter_map(|(entry, _)| {
+                        Version::parse(&entry.file_name().to_string_lossy()).ok()
+                    }),
+            );
+        }
+    }
+    Ok(versions)
+}
+
 /// Reads the contents of a directory and returns the set of all versions found
 /// in the directory's listing by matching filenames against the specified regex
 /// and parsing the `version` named capture as a semantic version.
@@ -55,7 +91,9 @@ where
 
 impl NodeCollection {
     pub(crate) fn load() -> Fallible<Self> {
-        let re = Regex::new(
+        // Only builds for the native architecture are tracked, since builds for other
+        // architectures (see `volta install --arch`) live in their own image directory
+        let re = Regex::new(&format!(
             r"(?x)
             node
             -
@@ -63,13 +101,35 @@ impl NodeCollection {
             -
             (?P<os>[a-z]+)              # operating system
             -
-            (?P<arch>[a-z0-9]+)         # architecture
-            \.(zip|tar\.gz)
+            (?P<arch>{})                # architecture
+            (-(?P<libc>[a-z]+))?        # libc, for unofficial builds
+            \.(zip|tar\.gz|tar\.xz|tar\.zst)$
             ",
-        )
+            regex::escape(path::ARCH)
+        ))
+        .unwrap();
+
+        let mut versions = inventory_versions("node", &re)?;
+
+        // Pre-release builds are kept in their own directory
+        let prerelease_re = Regex::new(&format!(
+            r"(?x)
+            node
+            -
+            v(?P<version>\d+\.\d+\.\d+-[0-9A-Za-z.-]+?) # Node pre-release version
+            -
+            (?P<os>[a-z]+)                              # operating system
+            -
+            (?P<arch>{})                                # architecture
+            (-(?P<libc>[a-z]+))?                        # libc, for unofficial builds
+            \.(zip|tar\.gz|tar\.xz|tar\.zst)$
+            ",
+            regex::escape(path::ARCH)
+        ))
         .unwrap();
 
-        let versions = versions_matching(&path::node_inventory_dir()?, &re)?;
+        versions.extend(inventory_versions("node-prerelease", &prerelease_re)?);
+        versions.extend(image_versions("node")?);
 
         Ok(NodeCollection {
             versions: versions,
@@ -85,12 +145,13 @@ impl YarnCollection {
             yarn
             -
             v(?P<version>\d+\.\d+\.\d+) # Yarn version
-            \.tar\.gz
+            \.tar\.gz$
             ",
         )
         .unwrap();
 
-        let versions = versions_matching(&path::yarn_inventory_dir()?, &re)?;
+        let mut versions = inventory_versions("yarn", &re)?;
+        versions.extend(image_versions("yarn")?);
 
         Ok(YarnCollection {
             versions: versions,
@@ -99,6 +160,50 @@ impl YarnCollection {
     }
 }
 
+impl DenoCollection {
+    pub(crate) fn load() -> Fallible<Self> {
+        let re = Regex::new(
+            r"(?x)
+            deno
+            -
+            v(?P<version>\d+\.\d+\.\d+) # Deno version
+            \.zip
+            ",
+        )
+        .unwrap();
+
+        let mut versions = inventory_versions("deno", &re)?;
+        versions.extend(image_versions("deno")?);
+
+        Ok(DenoCollection {
+            versions: versions,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl BunCollection {
+    pub(crate) fn load() -> Fallible<Self> {
+        let re = Regex::new(
+            r"(?x)
+            bun
+            -
+            v(?P<version>\d+\.\d+\.\d+) # Bun version
+            \.zip
+            ",
+        )
+        .unwrap();
+
+        let mut versions = inventory_versions("bun", &re)?;
+        versions.extend(image_versions("bun")?);
+
+        Ok(BunCollection {
+            versions: versions,
+            phantom: PhantomData,
+        })
+    }
+}
+
 impl PackageCollection {
     // loads an empty PackageCollection
     // ISSUE(#288) Collection only supports versions - for packages we also need names
@@ -123,6 +228,8 @@ pub struct NodeEntry {
     pub files: Vec<String>,
     #[serde(deserialize_with = "lts_version_serde")]
     pub lts: bool,
+    #[serde(default)]
+    pub modules: Option<String>,
 }
 
 impl NodeIndex {
@@ -138,6 +245,7 @@ impl NodeIndex {
                     npm,
                     files: data,
                     lts: entry.lts,
+                    modules: entry.modules.and_then(|modules| modules.parse().ok()),
                 });
             }
         }
@@ -221,6 +329,8 @@ pub struct PackageDistTags {
 pub struct DistInfo {
     pub shasum: String,
     pub tarball: String,
+    #[serde(default)]
+    pub integrity: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -230,6 +340,12 @@ pub struct PackageConfig {
     pub version: Version,
     pub platform: toolchain::serial::Platform,
     pub bins: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_abi: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -242,12 +358,16 @@ pub struct BinConfig {
     pub platform: toolchain::serial::Platform,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub loader: Option<BinLoader>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_abi: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BinLoader {
     pub command: String,
     pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
 }
 
 impl PackageMetadata {
@@ -258,6 +378,8 @@ impl PackageMetadata {
                 version: version_info.version,
                 tarball: version_info.dist.tarball,
                 shasum: version_info.dist.shasum,
+                integrity: version_info.dist.integrity,
+                authorization: None,
             };
             entries.push(entry);
         }
@@ -287,6 +409,9 @@ impl package::PackageConfig {
             version: self.version.clone(),
             platform: self.platform.to_serial(),
             bins: self.bins.clone(),
+            spec: self.spec.clone(),
+            node_abi: self.node_abi,
+            installed: self.installed.clone(),
         }
     }
 }
@@ -306,6 +431,7 @@ impl package::BinConfig {
             path: self.path.to_string(),
             platform: self.platform.to_serial(),
             loader: self.loader.as_ref().map(|l| l.to_serial()),
+            node_abi: self.node_abi,
         }
     }
 }
@@ -314,7 +440,12 @@ impl package::BinLoader {
     pub fn to_serial(&self) -> BinLoader {
         BinLoader {
             command: self.command.clone(),
-            args: self.args.clone(),
+            args: self
+                .args
+                .iter()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            env: self.env.clone(),
         }
     }
 }
@@ -335,8 +466,9 @@ impl PackageConfig {
         let config_file_path = path::user_package_config_file(&self.name)?;
         ensure_containing_dir_exists(&config_file_path)?;
         write(&config_file_path, src).with_context(|_| ErrorDetails::WritePackageConfigError {
-            file: config_file_path,
-        })
+            file: config_file_path.clone(),
+        })?;
+        permissions::apply(&config_file_path)
     }
 
     pub fn into_config(self) -> Fallible<package::PackageConfig> {
@@ -348,6 +480,9 @@ impl PackageConfig {
                 .into_platform()?
                 .ok_or(ErrorDetails::NoBinPlatform { binary: self.name })?,
             bins: self.bins,
+            spec: self.spec,
+            node_abi: self.node_abi,
+            installed: self.installed,
         })
     }
 }
@@ -365,10 +500,15 @@ impl BinConfig {
     pub fn write(&self) -> Fallible<()> {
         let src = self.to_json()?;
         let bin_config_path = path::user_tool_bin_config(&self.name)?;
-        ensure_containing_dir_exists(&bin_config_path)?;
-        write(&bin_config_path, src).with_context(|_| ErrorDetails::WriteBinConfigError {
-            file: bin_config_path,
-        })
+        // The config is renamed into place, rather than overwritten, so that the directory of
+        // installed executables is modified when a package takes an executable over. Shims use its
+        // modification time to tell when the executables provided by a project have changed.
+        write_atomic(&bin_config_path, src.as_bytes()).with_context(|_| {
+            ErrorDetails::WriteBinConfigError {
+                file: bin_config_path.clone(),
+            }
+        })?;
+        permissions::apply(&bin_config_path)
     }
 
     pub fn into_config(self) -> Fallible<package::BinConfig> {
@@ -382,6 +522,7 @@ impl BinConfig {
                 .into_platform()?
                 .ok_or(ErrorDetails::NoBinPlatform { binary: self.name })?,
             loader: self.loader.map(|l| l.into_loader()),
+            node_abi: self.node_abi,
         })
     }
 }
@@ -390,7 +531,36 @@ impl BinLoader {
     pub fn into_loader(self) -> package::BinLoader {
         package::BinLoader {
             command: self.command,
-            args: self.args,
+            args: self.args.into_iter().map(OsString::from).collect(),
+            env: self.env,
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::NodeIndex;
+    use semver::Version;
+
+    #[test]
+    fn reads_node_abi_from_index() {
+        let index: NodeIndex = serde_json::de::from_str(
+            r#"[
+                {"version":"v20.11.0","npm":"10.2.4","lts":"Iron","modules":"115","files":["linux-x64"]},
+                {"version":"v0.8.28","npm":"1.2.30","lts":false,"files":["linux-x64"]}
+            ]"#,
+        )
+        .expect("parses");
+        let index = index.into_index().expect("converts");
+
+        let modules_of = |version: Version| {
+            index
+                .entries
+                .iter()
+                .find(|entry| entry.version == version)
+                .and_then(|entry| entry.modules)
+        };
+        assert_eq!(modules_of(Version::new(20, 11, 0)), Some(115));
+        assert_eq!(modules_of(Version::new(0, 8, 28)), None);
+    }
+}
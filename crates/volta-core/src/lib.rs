@@ -4,24 +4,40 @@
 // https://doc.rust-lang.org/nightly/unstable-book/language-features/doc-cfg.html
 #![cfg_attr(feature = "cross-platform-docs", feature(doc_cfg))]
 
+pub mod api;
+pub mod audit;
+pub mod checksum;
 mod command;
+pub mod config;
+pub mod corepack;
 mod distro;
 pub mod env;
 pub mod error;
 mod event;
+pub mod event_sink;
 pub(crate) mod fs;
 mod hook;
 pub mod inventory;
 pub mod log;
 pub mod manifest;
+pub mod migrate;
 pub mod monitor;
+mod npmrc;
 pub mod path;
+mod permissions;
+pub mod plan;
 pub mod platform;
+mod policy;
 pub mod project;
+mod project_bins;
+pub mod provision;
+pub mod self_update;
 pub mod session;
 pub mod shell;
 pub mod shim;
 pub mod style;
 pub mod tool;
 pub mod toolchain;
+pub mod trace;
+pub mod update_check;
 pub mod version;
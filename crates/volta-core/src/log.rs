@@ -1,11 +1,19 @@
 //! This module provides a custom Logger implementation for use with the `log` crate
 use atty::Stream;
+use chrono::Local;
 use console::style;
-use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use log::{warn, Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde::Serialize;
+use std::cmp::max;
 use std::env;
 use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::process;
+use std::sync::Mutex;
 use textwrap::{NoHyphenation, Wrapper};
 
+use crate::error::ErrorFormat;
 use crate::style::text_width;
 
 const ERROR_PREFIX: &'static str = "error:";
@@ -13,6 +21,7 @@ const WARNING_PREFIX: &'static str = "warning:";
 const SHIM_ERROR_PREFIX: &'static str = "Volta error:";
 const SHIM_WARNING_PREFIX: &'static str = "Volta warning:";
 const VOLTA_LOGLEVEL: &'static str = "VOLTA_LOGLEVEL";
+const VOLTA_LOG_FILE: &'static str = "VOLTA_LOG_FILE";
 const ALLOWED_CRATE: &'static str = "volta_core";
 const WRAP_INDENT: &'static str = "    ";
 
@@ -25,56 +34,150 @@ pub enum LogContext {
     Shim,
 }
 
+impl LogContext {
+    fn name(&self) -> &'static str {
+        match self {
+            LogContext::Volta => "volta",
+            LogContext::Shim => "shim",
+        }
+    }
+}
+
+/// A warning or error, as it is written on stderr with `ErrorFormat::Json`
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    level: &'a str,
+    message: String,
+}
+
 /// Represents the level of verbosity that was requested by the user
 pub enum LogVerbosity {
     Quiet,
     Default,
     Verbose,
+    VeryVerbose,
 }
 
 pub struct Logger {
     context: LogContext,
     level: LevelFilter,
+    /// With `ErrorFormat::Json`, errors and warnings are written as JSON records on stderr, so
+    /// that tools that wrap Volta can read everything Volta reports there
+    format: ErrorFormat,
+    /// The file named by `VOLTA_LOG_FILE`, which receives debug logs regardless of `level`
+    log_file: Option<Mutex<File>>,
 }
 
 impl Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.level
+        metadata.level() <= self.max_level()
     }
 
     fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) && record.target().starts_with(ALLOWED_CRATE) {
+        if !self.enabled(record.metadata()) || !record.target().starts_with(ALLOWED_CRATE) {
+            return;
+        }
+
+        if record.level() <= self.level {
             match record.level() {
+                Level::Error | Level::Warn if self.format == ErrorFormat::Json => {
+                    eprintln!("{}", json_record(record.level(), record.args()))
+                }
                 Level::Error => self.log_error(record.args()),
                 Level::Warn => self.log_warning(record.args()),
                 Level::Debug => println!("[verbose] {}", record.args()),
+                Level::Trace => println!("[trace] {}", record.args()),
                 _ => println!("{}", record.args()),
             }
         }
+
+        if let Some(ref log_file) = self.log_file {
+            if record.level() <= LevelFilter::Debug {
+                if let Ok(mut log_file) = log_file.lock() {
+                    let _ = writeln!(
+                        log_file,
+                        "{} [{} {}] {:<5} {}",
+                        Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+                        self.context.name(),
+                        process::id(),
+                        record.level(),
+                        record.args()
+                    );
+                }
+            }
+        }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Some(ref log_file) = self.log_file {
+            if let Ok(mut log_file) = log_file.lock() {
+                let _ = log_file.flush();
+            }
+        }
+    }
 }
 
 impl Logger {
     /// Initialize the global logger with a Logger instance
     /// Will use the requested level of Verbosity
     /// If set to Default, will use the environment to determine the level of verbosity
-    pub fn init(context: LogContext, verbosity: LogVerbosity) -> Result<(), SetLoggerError> {
-        let logger = Logger::new(context, verbosity);
-        log::set_max_level(logger.level);
+    ///
+    /// If `VOLTA_LOG_FILE` is set, debug logs are also appended to that file, whatever the
+    /// level of verbosity on the console
+    ///
+    /// With `ErrorFormat::Json`, errors and warnings are written as JSON records instead
+    pub fn init(
+        context: LogContext,
+        verbosity: LogVerbosity,
+        format: ErrorFormat,
+    ) -> Result<(), SetLoggerError> {
+        let (log_file, log_file_error) = match env::var_os(VOLTA_LOG_FILE) {
+            Some(ref file) if !file.is_empty() => {
+                match OpenOptions::new().create(true).append(true).open(file) {
+                    Ok(log_file) => (Some(log_file), None),
+                    Err(error) => (None, Some((file.to_string_lossy().into_owned(), error))),
+                }
+            }
+            _ => (None, None),
+        };
+
+        let logger = Logger::new(context, verbosity, format, log_file);
+        log::set_max_level(logger.max_level());
         log::set_boxed_logger(Box::new(logger))?;
+
+        if let Some((file, error)) = log_file_error {
+            warn_log_file_error(&file, &error);
+        }
         Ok(())
     }
 
-    fn new(context: LogContext, verbosity: LogVerbosity) -> Self {
+    fn new(
+        context: LogContext,
+        verbosity: LogVerbosity,
+        format: ErrorFormat,
+        log_file: Option<File>,
+    ) -> Self {
         let level = match verbosity {
             LogVerbosity::Quiet => LevelFilter::Error,
             LogVerbosity::Default => level_from_env(),
             LogVerbosity::Verbose => LevelFilter::Debug,
+            LogVerbosity::VeryVerbose => LevelFilter::Trace,
         };
 
-        Logger { context, level }
+        Logger {
+            context,
+            level,
+            format,
+            log_file: log_file.map(Mutex::new),
+        }
+    }
+
+    /// The most verbose level that is logged anywhere, to the console or to the log file
+    fn max_level(&self) -> LevelFilter {
+        match self.log_file {
+            Some(_) => max(self.level, LevelFilter::Debug),
+            None => self.level,
+        }
     }
 
     fn log_error<D>(&self, message: &D)
@@ -125,6 +228,21 @@ where
     }
 }
 
+/// Formats a warning or error as a single-line JSON record
+fn json_record<D>(level: Level, message: &D) -> String
+where
+    D: Display,
+{
+    let record = JsonRecord {
+        level: match level {
+            Level::Error => "error",
+            _ => "warning",
+        },
+        message: message.to_string(),
+    };
+    serde_json::to_string(&record).unwrap_or_else(|_| format!("{}", message))
+}
+
 /// Determines the correct logging level based on the environment
 /// If VOLTA_LOGLEVEL is set to a valid level, we use that
 /// If not, we check the current stdout to determine whether it is a TTY or not
@@ -148,5 +266,60 @@ fn level_from_env() -> LevelFilter {
     }
 }
 
+fn warn_log_file_error(file: &str, error: &io::Error) {
+    warn!(
+        "Could not open {} file {}, logs will only be written to the console: {}",
+        VOLTA_LOG_FILE, file, error
+    );
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::{json_record, LogContext, LogVerbosity, Logger};
+    use crate::error::ErrorFormat;
+    use log::{Level, LevelFilter};
+    use tempfile::tempfile;
+
+    #[test]
+    fn maps_verbosity_to_levels() {
+        let level = |verbosity| {
+            Logger::new(LogContext::Volta, verbosity, ErrorFormat::Human, None).max_level()
+        };
+
+        assert_eq!(level(LogVerbosity::Quiet), LevelFilter::Error);
+        assert_eq!(level(LogVerbosity::Verbose), LevelFilter::Debug);
+        assert_eq!(level(LogVerbosity::VeryVerbose), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn log_file_receives_debug_logs() {
+        let quiet = Logger::new(
+            LogContext::Shim,
+            LogVerbosity::Quiet,
+            ErrorFormat::Human,
+            tempfile().ok(),
+        );
+        assert_eq!(quiet.level, LevelFilter::Error);
+        assert_eq!(quiet.max_level(), LevelFilter::Debug);
+
+        let very_verbose = Logger::new(
+            LogContext::Shim,
+            LogVerbosity::VeryVerbose,
+            ErrorFormat::Json,
+            tempfile().ok(),
+        );
+        assert_eq!(very_verbose.max_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn formats_json_records() {
+        assert_eq!(
+            json_record(Level::Warn, &"Node 8.9.4 is \"deprecated\"\nUpgrade soon"),
+            r#"{"level":"warning","message":"Node 8.9.4 is \"deprecated\"\nUpgrade soon"}"#
+        );
+        assert_eq!(
+            json_record(Level::Error, &"Could not fetch"),
+            r#"{"level":"error","message":"Could not fetch"}"#
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Provides targeted edits to the text of a `package.json` file, so that writing a key leaves
+//! the formatting, key order, and line endings of everything else in the file as they were.
+
+use std::ops::Range;
+
+/// Sets the value of a key in the top-level object of a JSON document, replacing the existing
+/// value if the key is present and adding it after the last key otherwise.
+///
+/// The value must already be serialized; its lines after the first are indented to sit inside
+/// the top-level object. Returns `None` if the document isn't a JSON object.
+pub(crate) fn set_key(src: &str, key: &str, value: &str, indent: &str) -> Option<String> {
+    let object = TopLevelObject::parse(src)?;
+    let newline = if src.contains("\r\n") { "\r\n" } else { "\n" };
+    let value = value
+        .lines()
+        .collect::<Vec<&str>>()
+        .join(&format!("{}{}", newline, indent));
+
+    let mut updated = String::with_capacity(src.len() + value.len());
+    match object.members.iter().find(|member| member.key == key) {
+        Some(member) => {
+            updated.push_str(&src[..member.value.start]);
+            updated.push_str(&value);
+            updated.push_str(&src[member.value.end..]);
+        }
+        None => {
+            let entry = format!("{}{}\"{}\": {}", newline, indent, key, value);
+            match object.members.last() {
+                Some(last) => {
+                    updated.push_str(&src[..last.value.end]);
+                    updated.push(',');
+                    updated.push_str(&entry);
+                    updated.push_str(&src[last.value.end..]);
+                }
+                None => {
+                    updated.push_str(&src[..object.open + 1]);
+                    updated.push_str(&entry);
+                    updated.push_str(newline);
+                    updated.push_str(src[object.open + 1..].trim_start());
+                }
+            }
+        }
+    }
+
+    Some(updated)
+}
+
+/// The locations of the members of the top-level object in a JSON document.
+struct TopLevelObject {
+    /// The index of the opening brace.
+    open: usize,
+    members: Vec<Member>,
+}
+
+struct Member {
+    key: String,
+    value: Range<usize>,
+}
+
+impl TopLevelObject {
+    fn parse(src: &str) -> Option<Self> {
+        let mut scanner = Scanner { src, pos: 0 };
+        scanner.skip_whitespace();
+        let open = scanner.pos;
+        scanner.expect(b'{')?;
+
+        let mut members = Vec::new();
+        scanner.skip_whitespace();
+        if scanner.peek()? == b'}' {
+            return Some(TopLevelObject { open, members });
+        }
+
+        loop {
+            scanner.skip_whitespace();
+            let key = scanner.string()?;
+            let key = serde_json::from_str(&src[key]).ok()?;
+            scanner.skip_whitespace();
+            scanner.expect(b':')?;
+            scanner.skip_whitespace();
+            let value = scanner.value()?;
+            members.push(Member { key, value });
+
+            scanner.skip_whitespace();
+            match scanner.next()? {
+                b',' => continue,
+                b'}' => return Some(TopLevelObject { open, members }),
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Walks over the bytes of a JSON document, finding where values start and end without
+/// decoding them.
+struct Scanner<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn expect(&mut self, expected: u8) -> Option<()> {
+        if self.next()? == expected {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    /// Finds the extent of a string, including its quotes.
+    fn string(&mut self) -> Option<Range<usize>> {
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match self.next()? {
+                b'\\' => {
+                    self.next()?;
+                }
+                b'"' => return Some(start..self.pos),
+                _ => {}
+            }
+        }
+    }
+
+    /// Finds the extent of any value, including nested objects and arrays.
+    fn value(&mut self) -> Option<Range<usize>> {
+        let start = self.pos;
+        match self.peek()? {
+            b'"' => {
+                self.string()?;
+            }
+            b'{' | b'[' => {
+                let mut depth = 0;
+                loop {
+                    match self.peek()? {
+                        b'"' => {
+                            self.string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => depth -= 1,
+                        _ => {}
+                    }
+                    self.pos += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            // numbers, booleans, and null run until the next delimiter
+            _ => {
+                while let Some(byte) = self.peek() {
+                    match byte {
+                        b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r' => break,
+                        _ => self.pos += 1,
+                    }
+                }
+            }
+        }
+
+        if self.pos > start {
+            Some(start..self.pos)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::set_key;
+
+    const VOLTA: &str = "{\n  \"node\": \"10.16.0\"\n}";
+
+    #[test]
+    fn replaces_existing_key() {
+        let src = "{\n  \"name\": \"pkg\",\n  \"volta\": {\"node\": \"8.0.0\"},\n  \"files\": [\"a\", \"b\"]\n}\n";
+        assert_eq!(
+            set_key(src, "volta", VOLTA, "  ").unwrap(),
+            "{\n  \"name\": \"pkg\",\n  \"volta\": {\n    \"node\": \"10.16.0\"\n  },\n  \"files\": [\"a\", \"b\"]\n}\n"
+        );
+    }
+
+    #[test]
+    fn adds_missing_key() {
+        let src = "{\r\n\t\"name\": \"pkg\",\r\n\t\"private\": true\r\n}\r\n";
+        assert_eq!(
+            set_key(src, "volta", "{\n\t\"node\": \"10.16.0\"\n}", "\t").unwrap(),
+            "{\r\n\t\"name\": \"pkg\",\r\n\t\"private\": true,\r\n\t\"volta\": {\r\n\t\t\"node\": \"10.16.0\"\r\n\t}\r\n}\r\n"
+        );
+
+        assert_eq!(
+            set_key("{}", "volta", VOLTA, "  ").unwrap(),
+            "{\n  \"volta\": {\n    \"node\": \"10.16.0\"\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn ignores_nested_keys() {
+        let src = "{\"config\": {\"volta\": \"x\", \"s\": \"}\\\"\"}, \"n\": 1.5e3}";
+        assert_eq!(
+            set_key(src, "volta", "{}", "  ").unwrap(),
+            "{\"config\": {\"volta\": \"x\", \"s\": \"}\\\"\"}, \"n\": 1.5e3,\n  \"volta\": {}}"
+        );
+    }
+
+    #[test]
+    fn rejects_non_objects() {
+        assert_eq!(set_key("[]", "volta", VOLTA, "  "), None);
+    }
+}
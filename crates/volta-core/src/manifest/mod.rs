@@ -1,24 +1,46 @@
 //! Provides the `Manifest` type, which represents a Node manifest file (`package.json`).
 
-use std::collections::{HashMap, HashSet};
-use std::fs::{read_to_string, File};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{read_to_string, write, File};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+use crate::checksum::ArchiveHashes;
 use crate::error::ErrorDetails;
-use crate::platform::PlatformSpec;
+use crate::platform::{PartialPlatformSpec, PlatformSpec};
 use detect_indent;
 use semver::Version;
 use serde::Serialize;
 use serde_json;
 use volta_fail::{Fallible, ResultExt};
 
+mod edit;
 pub(crate) mod serial;
 
 /// A Node manifest file.
 pub struct Manifest {
     /// The platform image specified by the `volta` section.
     pub platform: Option<Rc<PlatformSpec>>,
+    /// The versions of npm and Yarn that the `volta` section pins without pinning Node, if any.
+    pub partial_platform: Option<PartialPlatformSpec>,
+    /// The range that Node is pinned to by the `volta` section, if it isn't an exact version.
+    pub node_range: Option<String>,
+    /// The SHA-256s that the `volta` section pins for the archives of Node, by file name.
+    pub node_sha256: ArchiveHashes,
+    /// The SHA-256s that the `volta` section pins for the archives of Yarn, by file name.
+    pub yarn_sha256: ArchiveHashes,
+    /// The version of Deno specified by the `volta` section.
+    pub deno: Option<Version>,
+    /// The version of Bun specified by the `volta` section.
+    pub bun: Option<Version>,
+    /// Whether the `volta` section opts the project out of Volta entirely.
+    pub bypass: bool,
+    /// The environment variables that the `volta` section sets for the project's tools.
+    pub env: BTreeMap<String, String>,
+    /// The flags that the `volta` section passes to the pinned Node whenever it is run.
+    pub node_flags: Vec<String>,
+    /// The commands that the `volta` section defines, which are run with `volta run`.
+    pub commands: BTreeMap<String, String>,
     /// The `dependencies` section.
     pub dependencies: HashMap<String, String>,
     /// The `devDependencies` section.
@@ -27,12 +49,20 @@ pub struct Manifest {
     pub bin: HashMap<String, String>,
     /// The `engines` section, containing a spec of the Node versions that the package works on.
     pub engines: Option<String>,
+    /// The `workspaces` section, containing globs that match the project's workspace packages.
+    pub workspaces: Vec<String>,
 }
 
 impl Manifest {
-    /// Loads and parses a Node manifest for the project rooted at the specified path.
+    /// Loads and parses a Node manifest for the project rooted at the specified path, falling
+    /// back to a standalone `volta.json` if the project has no package.json.
     pub fn for_dir(project_root: &Path) -> Fallible<Manifest> {
         let package_file = project_root.join("package.json");
+        let volta_file = project_root.join("volta.json");
+        if !package_file.is_file() && volta_file.is_file() {
+            return Manifest::for_volta_file(&volta_file);
+        }
+
         let file = File::open(&package_file).with_context(|_| ErrorDetails::PackageReadError {
             file: package_file.to_path_buf(),
         })?;
@@ -45,11 +75,32 @@ impl Manifest {
         serial.into_manifest(&package_file)
     }
 
+    /// Loads and parses a standalone `volta.json` file, which holds the same keys as the `volta`
+    /// section of package.json.
+    fn for_volta_file(volta_file: &Path) -> Fallible<Manifest> {
+        let file = File::open(volta_file).with_context(|_| ErrorDetails::PackageReadError {
+            file: volta_file.to_path_buf(),
+        })?;
+
+        let serial: serial::ToolchainSpec =
+            serde_json::de::from_reader(file).with_context(|_| {
+                ErrorDetails::PackageParseError {
+                    file: volta_file.to_path_buf(),
+                }
+            })?;
+        serial.into_manifest()
+    }
+
     /// Returns a reference to the platform image specified by manifest, if any.
     pub fn platform(&self) -> Option<Rc<PlatformSpec>> {
         self.platform.as_ref().map(|p| p.clone())
     }
 
+    /// Returns the versions of npm and Yarn that are pinned without pinning Node, if any.
+    pub fn partial_platform(&self) -> Option<&PartialPlatformSpec> {
+        self.partial_platform.as_ref()
+    }
+
     /// Returns a copy of the "engines" specification from the manifest, if any.
     pub fn engines(&self) -> Option<String> {
         self.engines.as_ref().map(|e| e.clone())
@@ -74,6 +125,20 @@ impl Manifest {
         self.platform().map(|t| t.node_runtime.to_string())
     }
 
+    /// Returns the range that Node is pinned to, if it isn't pinned to an exact version.
+    pub fn node_range(&self) -> Option<String> {
+        self.node_range.clone()
+    }
+
+    /// Returns the SHA-256s that the archives of a version of Node have to match, by file name,
+    /// if that is the version of Node that is pinned.
+    pub fn pinned_node_sha256(&self, version: &Version) -> ArchiveHashes {
+        match self.node() {
+            Some(ref node) if node == version => self.node_sha256.clone(),
+            _ => ArchiveHashes::new(),
+        }
+    }
+
     /// Returns the pinned verison of Yarn as a Version, if any.
     pub fn yarn(&self) -> Option<Version> {
         self.platform().map(|t| t.yarn.clone()).unwrap_or(None)
@@ -85,8 +150,43 @@ impl Manifest {
             .and_then(|t| t.yarn.as_ref().map(|yarn| yarn.to_string()))
     }
 
+    /// Returns the SHA-256s that the archives of a version of Yarn have to match, by file name,
+    /// if that is the version of Yarn that is pinned.
+    pub fn pinned_yarn_sha256(&self, version: &Version) -> ArchiveHashes {
+        match self.yarn() {
+            Some(ref yarn) if yarn == version => self.yarn_sha256.clone(),
+            _ => ArchiveHashes::new(),
+        }
+    }
+
+    /// Returns the architecture of Node specified by the manifest, if any.
+    pub fn arch(&self) -> Option<String> {
+        self.platform().and_then(|t| t.arch.clone())
+    }
+
+    /// Returns the pinned version of Deno as a Version, if any.
+    pub fn deno(&self) -> Option<Version> {
+        self.deno.clone()
+    }
+
+    /// Returns the pinned version of Deno as a String, if any.
+    pub fn deno_str(&self) -> Option<String> {
+        self.deno.as_ref().map(|deno| deno.to_string())
+    }
+
+    /// Returns the pinned version of Bun as a Version, if any.
+    pub fn bun(&self) -> Option<Version> {
+        self.bun.clone()
+    }
+
+    /// Returns the pinned version of Bun as a String, if any.
+    pub fn bun_str(&self) -> Option<String> {
+        self.bun.as_ref().map(|bun| bun.to_string())
+    }
+
     /// Writes the input ToolchainManifest to package.json, adding the "volta" key if
-    /// necessary.
+    /// necessary. Only the "volta" key is rewritten, so the rest of the file keeps its
+    /// formatting and key order.
     pub fn update_toolchain(
         toolchain: serial::ToolchainSpec,
         package_file: PathBuf,
@@ -98,29 +198,92 @@ impl Manifest {
         // parse the entire package.json file into a Value
         let contents = read_to_string(&package_file)
             .with_context(|_| ErrorDetails::PackageReadError { file: get_file() })?;
-        let mut v: serde_json::Value = serde_json::from_str(&contents)
+        let v: serde_json::Value = serde_json::from_str(&contents)
             .with_context(|_| ErrorDetails::PackageParseError { file: get_file() })?;
 
-        if let Some(map) = v.as_object_mut() {
+        if let Some(map) = v.as_object() {
             // detect indentation in package.json
             let indent = detect_indent::detect_indent(&contents);
 
-            // update the "volta" key
-            let toolchain_value = serde_json::to_value(toolchain)
-                .with_context(|_| ErrorDetails::StringifyToolchainError)?;
-            map.insert("volta".to_string(), toolchain_value);
-
-            // serialize the updated contents back to package.json
-            let file = File::create(&package_file)
-                .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })?;
+            // update the "volta" key, keeping the settings that aren't pinned tools
+            let volta = map.get("volta");
+            let bypass = volta
+                .and_then(|volta| volta.get("bypass"))
+                .and_then(|bypass| bypass.as_bool());
+            let env = volta
+                .and_then(|volta| volta.get("env"))
+                .and_then(|env| serde_json::from_value(env.clone()).ok());
+            let node_flags = volta
+                .and_then(|volta| volta.get("nodeFlags"))
+                .and_then(|flags| serde_json::from_value(flags.clone()).ok());
+            // Commands that can't be parsed would be lost, so they are an error rather than
+            // left out
+            let commands = volta
+                .and_then(|volta| volta.get("commands"))
+                .map(|commands| serde_json::from_value(commands.clone()))
+                .transpose()
+                .with_context(|_| ErrorDetails::PackageParseError { file: get_file() })?;
+            let toolchain = serial::ToolchainSpec {
+                bypass: toolchain.bypass.or(bypass),
+                env: toolchain.env.or(env),
+                node_flags: toolchain.node_flags.or(node_flags),
+                commands: toolchain.commands.or(commands),
+                ..toolchain
+            };
+            let mut toolchain_json = Vec::new();
             let formatter =
                 serde_json::ser::PrettyFormatter::with_indent(indent.indent().as_bytes());
-            let mut ser = serde_json::Serializer::with_formatter(file, formatter);
-            map.serialize(&mut ser)
+            let mut ser = serde_json::Serializer::with_formatter(&mut toolchain_json, formatter);
+            toolchain
+                .serialize(&mut ser)
+                .with_context(|_| ErrorDetails::StringifyToolchainError)?;
+            let toolchain_json = String::from_utf8(toolchain_json)
+                .with_context(|_| ErrorDetails::StringifyToolchainError)?;
+
+            let updated = edit::set_key(&contents, "volta", &toolchain_json, indent.indent())
+                .ok_or_else(|| ErrorDetails::PackageParseError { file: get_file() })?;
+
+            // write the updated contents back to package.json
+            write(&package_file, updated)
                 .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })?;
         }
         Ok(())
     }
+
+    /// Writes the input ToolchainManifest to a standalone `volta.json` file, replacing its
+    /// contents.
+    pub fn update_volta_file(
+        toolchain: serial::ToolchainSpec,
+        volta_file: PathBuf,
+    ) -> Fallible<()> {
+        let get_file = || volta_file.to_owned();
+
+        let contents = read_to_string(&volta_file)
+            .with_context(|_| ErrorDetails::PackageReadError { file: get_file() })?;
+        let existing: serial::ToolchainSpec = serde_json::from_str(&contents)
+            .with_context(|_| ErrorDetails::PackageParseError { file: get_file() })?;
+
+        // keep the settings that aren't pinned tools
+        let toolchain = serial::ToolchainSpec {
+            bypass: toolchain.bypass.or(existing.bypass),
+            env: toolchain.env.or(existing.env),
+            node_flags: toolchain.node_flags.or(existing.node_flags),
+            commands: toolchain.commands.or(existing.commands),
+            ..toolchain
+        };
+
+        let indent = detect_indent::detect_indent(&contents);
+        let mut updated = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.indent().as_bytes());
+        let mut ser = serde_json::Serializer::with_formatter(&mut updated, formatter);
+        toolchain
+            .serialize(&mut ser)
+            .with_context(|_| ErrorDetails::StringifyToolchainError)?;
+        updated.push(b'\n');
+
+        write(&volta_file, updated)
+            .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })
+    }
 }
 
 // unit tests
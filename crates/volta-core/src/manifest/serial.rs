@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::hash::Hash;
 use std::marker::PhantomData;
@@ -7,14 +7,18 @@ use std::path::Path;
 use std::rc::Rc;
 
 use log::warn;
+use semver::Version;
 use serde;
 use serde::de::{Deserialize, Deserializer, Error, MapAccess, Visitor};
 use serde_json::value::Value;
 
-use volta_fail::Fallible;
+use volta_fail::{throw, Fallible};
 
 use super::super::{manifest, platform};
-use crate::version::VersionSpec;
+use crate::checksum::ArchiveHashes;
+use crate::distro::node;
+use crate::error::ErrorDetails;
+use crate::version::{is_range, VersionSpec};
 
 // wrapper for HashMap to use with deserialization
 #[derive(Debug, PartialEq)]
@@ -69,15 +73,105 @@ pub struct Manifest {
     // See https://github.com/volta-cli/volta/issues/388 for example.
     #[serde(default, deserialize_with = "Engines::deserialize")]
     pub engines: Option<Engines>,
+
+    // npm and Yarn accept either a list of globs or an object with a `packages` list of globs
+    // (see https://classic.yarnpkg.com/en/docs/workspaces#toc-workspaces-in-yarn)
+    #[serde(default, deserialize_with = "deserialize_workspaces")]
+    pub workspaces: Vec<String>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct ToolchainSpec {
-    pub node: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<ToolPin>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub npm: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub yarn: Option<String>,
+    pub yarn: Option<ToolPin>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deno: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bun: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock: Option<LockSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bypass: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<BTreeMap<String, String>>,
+    #[serde(rename = "nodeFlags", default, skip_serializing_if = "Option::is_none")]
+    pub node_flags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commands: Option<BTreeMap<String, String>>,
+}
+
+/// The version that a tool is pinned to, which can also pin the SHA-256s of the archives that the
+/// tool has to be installed from, by their file names, since each platform installs from a
+/// different archive, like
+/// `"node": { "version": "18.16.0", "sha256": { "node-v18.16.0-linux-x64.tar.xz": "..." } }`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolPin {
+    pub version: String,
+    pub sha256: ArchiveHashes,
+}
+
+impl From<String> for ToolPin {
+    fn from(version: String) -> Self {
+        ToolPin {
+            version,
+            sha256: ArchiveHashes::new(),
+        }
+    }
+}
+
+/// The ways that a tool can be pinned in package.json: just its version, or its version along
+/// with the SHA-256s of its archives.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum ToolPinSpec {
+    Version(String),
+    Hashed {
+        version: String,
+        sha256: ArchiveHashes,
+    },
+}
+
+impl serde::Serialize for ToolPin {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let spec = if self.sha256.is_empty() {
+            ToolPinSpec::Version(self.version.clone())
+        } else {
+            ToolPinSpec::Hashed {
+                version: self.version.clone(),
+                sha256: self.sha256.clone(),
+            }
+        };
+        serde::Serialize::serialize(&spec, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolPin {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match ToolPinSpec::deserialize(deserializer)? {
+            ToolPinSpec::Version(version) => ToolPin::from(version),
+            ToolPinSpec::Hashed { version, sha256 } => ToolPin { version, sha256 },
+        })
+    }
+}
+
+/// The exact versions that the ranges in a toolchain resolved to when they were pinned, so that
+/// everyone working on the project uses the same versions.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct LockSpec {
+    pub node: String,
 }
 
 #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -110,6 +204,31 @@ impl Engines {
     }
 }
 
+/// Handle deserialization of the `workspaces` key, permissively treating anything that isn't one
+/// of the supported forms as having no workspaces, like `Engines::deserialize`.
+fn deserialize_workspaces<'de, D>(d: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Deserialize::deserialize(d).map(|value: Option<Value>| {
+        let globs = match value {
+            Some(Value::Array(globs)) => globs,
+            Some(Value::Object(mut object)) => match object.remove("packages") {
+                Some(Value::Array(globs)) => globs,
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        globs
+            .into_iter()
+            .filter_map(|glob| match glob {
+                Value::String(glob) => Some(glob),
+                _ => None,
+            })
+            .collect()
+    })
+}
+
 impl Manifest {
     pub fn into_manifest(self, package_path: &Path) -> Fallible<manifest::Manifest> {
         let mut map = HashMap::new();
@@ -124,22 +243,76 @@ impl Manifest {
                 }
             }
         }
+        let toolchain = self.volta_toolchain(package_path);
+        let (
+            platform,
+            partial_platform,
+            node_range,
+            node_sha256,
+            yarn_sha256,
+            deno,
+            bun,
+            bypass,
+            env,
+            node_flags,
+            commands,
+        ) = match toolchain {
+            Some(toolchain) => (
+                toolchain.to_platform()?,
+                toolchain.to_partial_platform()?,
+                toolchain.node_range(),
+                toolchain.node_sha256(),
+                toolchain.yarn_sha256(),
+                toolchain.deno_version()?,
+                toolchain.bun_version()?,
+                toolchain.bypass.unwrap_or(false),
+                toolchain.env.clone().unwrap_or_default(),
+                toolchain.node_flags.clone().unwrap_or_default(),
+                toolchain.commands.clone().unwrap_or_default(),
+            ),
+            None => (
+                None,
+                None,
+                None,
+                ArchiveHashes::new(),
+                ArchiveHashes::new(),
+                None,
+                None,
+                false,
+                BTreeMap::new(),
+                Vec::new(),
+                BTreeMap::new(),
+            ),
+        };
+
         Ok(manifest::Manifest {
-            platform: self.to_platform(package_path)?.map(Rc::new),
+            platform: platform.map(Rc::new),
+            partial_platform,
+            node_range,
+            node_sha256,
+            yarn_sha256,
+            deno,
+            bun,
+            bypass,
+            env,
+            node_flags,
+            commands,
             dependencies: self.dependencies,
             dev_dependencies: self.dev_dependencies,
             bin: map,
             engines: self.engines.map(|e| e.node),
+            workspaces: self.workspaces,
         })
     }
 
-    pub fn to_platform(&self, package_path: &Path) -> Fallible<Option<platform::PlatformSpec>> {
+    /// Returns the toolchain specified by the manifest, if any.
+    fn volta_toolchain(&self, package_path: &Path) -> Option<&ToolchainSpec> {
         // Backwards compatibility to allow users to upgrade to using the
         // `volta` key simultaneously with the `toolchain` key, but with
         // deprecation warnings about the use of `toolchain`. Prefer the `volta`
         // key if it is set, and provide a deprecation warning if the user is
         // using the `toolchain` key.
-        let toolchain = match (&self.volta, &self.toolchain) {
+        match (&self.volta, &self.toolchain) {
             (Some(volta), None) => Some(volta),
             (Some(volta), Some(_toolchain)) => {
                 warn!(
@@ -156,38 +329,228 @@ impl Manifest {
                 Some(toolchain)
             }
             (None, None) => None,
-        };
-
-        if let Some(toolchain) = &toolchain {
-            return Ok(Some(platform::PlatformSpec {
-                node_runtime: VersionSpec::parse_version(&toolchain.node)?,
-                npm: if let Some(npm) = &toolchain.npm {
-                    Some(VersionSpec::parse_version(&npm)?)
-                } else {
-                    None
-                },
-                yarn: if let Some(yarn) = &toolchain.yarn {
-                    Some(VersionSpec::parse_version(&yarn)?)
-                } else {
-                    None
-                },
-            }));
         }
-        Ok(None)
     }
 }
 
 impl ToolchainSpec {
     pub fn new(
-        node_version: String,
+        node_version: Option<String>,
         npm_version: Option<String>,
         yarn_version: Option<String>,
+        arch: Option<String>,
+        deno_version: Option<String>,
+        bun_version: Option<String>,
     ) -> Self {
         ToolchainSpec {
-            node: node_version,
+            node: node_version.map(ToolPin::from),
             npm: npm_version,
-            yarn: yarn_version,
+            yarn: yarn_version.map(ToolPin::from),
+            arch,
+            deno: deno_version,
+            bun: bun_version,
+            lock: None,
+            bypass: None,
+            env: None,
+            node_flags: None,
+            commands: None,
+        }
+    }
+
+    /// Pins Node to the specified range rather than to an exact version, moving the exact
+    /// version into the `lock` key.
+    pub fn with_node_range(self, range: Option<String>) -> Self {
+        match range {
+            Some(range) => {
+                // The SHA-256 of Node's archive stays with the pin, though it's the locked version
+                // that it is the SHA-256 of
+                let (lock, sha256) = match self.node {
+                    Some(node) => (Some(LockSpec { node: node.version }), node.sha256),
+                    None => (None, ArchiveHashes::new()),
+                };
+                ToolchainSpec {
+                    lock,
+                    node: Some(ToolPin {
+                        version: range,
+                        sha256,
+                    }),
+                    ..self
+                }
+            }
+            None => self,
+        }
+    }
+
+    /// Pins the SHA-256s of archives that the pinned versions of Node and Yarn are installed
+    /// from, by their file names, alongside the SHA-256s that are pinned already.
+    pub fn with_sha256(mut self, node: ArchiveHashes, yarn: ArchiveHashes) -> Self {
+        if let Some(pin) = self.node.as_mut() {
+            pin.sha256.extend(node);
+        }
+        if let Some(pin) = self.yarn.as_mut() {
+            pin.sha256.extend(yarn);
+        }
+        self
+    }
+
+    /// Keeps the SHA-256s that a manifest already pins, as long as this toolchain pins the same
+    /// versions of Node and Yarn. The SHA-256s that this toolchain pins for the same archives take
+    /// their place.
+    pub fn keep_sha256(mut self, pinned: &manifest::Manifest) -> Self {
+        let node = self
+            .lock
+            .as_ref()
+            .map(|lock| &lock.node)
+            .or_else(|| self.node.as_ref().map(|node| &node.version))
+            .and_then(|node| VersionSpec::parse_version(node).ok());
+        if let (Some(pin), Some(node)) = (self.node.as_mut(), node) {
+            let mut sha256 = pinned.pinned_node_sha256(&node);
+            sha256.append(&mut pin.sha256);
+            pin.sha256 = sha256;
+        }
+
+        let yarn = self
+            .yarn
+            .as_ref()
+            .and_then(|yarn| VersionSpec::parse_version(&yarn.version).ok());
+        if let (Some(pin), Some(yarn)) = (self.yarn.as_mut(), yarn) {
+            let mut sha256 = pinned.pinned_yarn_sha256(&yarn);
+            sha256.append(&mut pin.sha256);
+            pin.sha256 = sha256;
+        }
+
+        self
+    }
+
+    /// Produces the manifest for a project that pins its tools with a standalone `volta.json`,
+    /// which has none of the other sections of package.json.
+    pub fn into_manifest(self) -> Fallible<manifest::Manifest> {
+        Ok(manifest::Manifest {
+            platform: self.to_platform()?.map(Rc::new),
+            partial_platform: self.to_partial_platform()?,
+            node_range: self.node_range(),
+            node_sha256: self.node_sha256(),
+            yarn_sha256: self.yarn_sha256(),
+            deno: self.deno_version()?,
+            bun: self.bun_version()?,
+            bypass: self.bypass.unwrap_or(false),
+            env: self.env.unwrap_or_default(),
+            node_flags: self.node_flags.unwrap_or_default(),
+            commands: self.commands.unwrap_or_default(),
+            dependencies: HashMap::new(),
+            dev_dependencies: HashMap::new(),
+            bin: HashMap::new(),
+            engines: None,
+            workspaces: Vec::new(),
+        })
+    }
+
+    /// Produces the Node platform specified by this toolchain. Deno and Bun are managed
+    /// separately from the Node platform, so a toolchain may pin them without pinning Node.
+    pub fn to_platform(&self) -> Fallible<Option<platform::PlatformSpec>> {
+        let node = match &self.node {
+            Some(node) => &node.version,
+            None => return Ok(None),
+        };
+
+        // When Node is pinned to a range, the version to use is the one locked for that range
+        let node_runtime = match &self.lock {
+            Some(lock) => {
+                let range = VersionSpec::parse_requirements(&node)?;
+                let locked = VersionSpec::parse_version(&lock.node)?;
+                if !range.matches(&locked) {
+                    throw!(ErrorDetails::NodeLockOutdated {
+                        range: node.clone(),
+                        locked: lock.node.clone(),
+                    });
+                }
+                locked
+            }
+            None if is_range(&node) => throw!(ErrorDetails::NodeRangeNotLocked {
+                range: node.clone()
+            }),
+            None => VersionSpec::parse_version(&node)?,
+        };
+
+        Ok(Some(platform::PlatformSpec {
+            node_runtime,
+            npm: if let Some(npm) = &self.npm {
+                Some(VersionSpec::parse_version(&npm)?)
+            } else {
+                None
+            },
+            yarn: if let Some(yarn) = &self.yarn {
+                Some(VersionSpec::parse_version(&yarn.version)?)
+            } else {
+                None
+            },
+            arch: if let Some(arch) = &self.arch {
+                Some(node::parse_arch(&arch)?)
+            } else {
+                None
+            },
+        }))
+    }
+
+    /// Produces the range that Node is pinned to by this toolchain, if it isn't pinned to an
+    /// exact version.
+    pub fn node_range(&self) -> Option<String> {
+        self.lock
+            .as_ref()
+            .and(self.node.as_ref().map(|node| node.version.clone()))
+    }
+
+    /// Produces the SHA-256s that this toolchain pins for the archives of Node, by file name.
+    pub fn node_sha256(&self) -> ArchiveHashes {
+        self.node
+            .as_ref()
+            .map(|node| node.sha256.clone())
+            .unwrap_or_default()
+    }
+
+    /// Produces the SHA-256s that this toolchain pins for the archives of Yarn, by file name.
+    pub fn yarn_sha256(&self) -> ArchiveHashes {
+        self.yarn
+            .as_ref()
+            .map(|yarn| yarn.sha256.clone())
+            .unwrap_or_default()
+    }
+
+    /// Produces the versions of npm and Yarn that this toolchain pins if it doesn't pin Node,
+    /// which would otherwise be left out of its platform.
+    pub fn to_partial_platform(&self) -> Fallible<Option<platform::PartialPlatformSpec>> {
+        if self.node.is_some() || (self.npm.is_none() && self.yarn.is_none()) {
+            return Ok(None);
         }
+
+        Ok(Some(platform::PartialPlatformSpec {
+            npm: self
+                .npm
+                .as_ref()
+                .map(|npm| VersionSpec::parse_version(&npm))
+                .transpose()?,
+            yarn: self
+                .yarn
+                .as_ref()
+                .map(|yarn| VersionSpec::parse_version(&yarn.version))
+                .transpose()?,
+        }))
+    }
+
+    /// Produces the version of Deno specified by this toolchain, if any.
+    pub fn deno_version(&self) -> Fallible<Option<Version>> {
+        self.deno
+            .as_ref()
+            .map(|deno| VersionSpec::parse_version(&deno))
+            .transpose()
+    }
+
+    /// Produces the version of Bun specified by this toolchain, if any.
+    pub fn bun_version(&self) -> Fallible<Option<Version>> {
+        self.bun
+            .as_ref()
+            .map(|bun| VersionSpec::parse_version(&bun))
+            .transpose()
     }
 }
 
@@ -262,7 +625,8 @@ where
 #[cfg(test)]
 pub mod tests {
 
-    use super::{BinMap, Engines, Manifest};
+    use super::{BinMap, Engines, Manifest, ToolchainSpec};
+    use semver::Version;
     use serde_json;
     use std::collections::HashMap;
 
@@ -566,4 +930,23 @@ pub mod tests {
             "We treat booleans as `None`"
         );
     }
+
+    #[test]
+    fn test_node_range_requires_matching_lock() {
+        let toolchain =
+            ToolchainSpec::new(Some("10.16.0".to_string()), None, None, None, None, None)
+                .with_node_range(Some("^10.15".to_string()));
+        let platform = toolchain.to_platform().unwrap().unwrap();
+        assert_eq!(platform.node_runtime, Version::parse("10.16.0").unwrap());
+        assert_eq!(toolchain.node_range(), Some("^10.15".to_string()));
+
+        let toolchain =
+            ToolchainSpec::new(Some("12.0.0".to_string()), None, None, None, None, None)
+                .with_node_range(Some("^10.15".to_string()));
+        assert!(toolchain.to_platform().is_err());
+
+        let toolchain =
+            ToolchainSpec::new(Some("^10.15".to_string()), None, None, None, None, None);
+        assert!(toolchain.to_platform().is_err());
+    }
 }
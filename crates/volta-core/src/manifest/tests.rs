@@ -1,7 +1,11 @@
+use crate::checksum::ArchiveHashes;
+use crate::manifest::serial::ToolchainSpec;
 use crate::manifest::Manifest;
 use semver::Version;
 use std::collections::{HashMap, HashSet};
+use std::fs::{read_to_string, write};
 use std::path::PathBuf;
+use tempfile::tempdir;
 
 fn fixture_path(fixture_dir: &str) -> PathBuf {
     let mut cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -84,6 +88,187 @@ fn yarn_for_no_toolchain() {
     assert_eq!(manifest.yarn(), None);
 }
 
+#[test]
+fn gets_locked_node_version() {
+    let project_path = fixture_path("node_range");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(manifest.node(), Some(Version::parse("6.11.1").unwrap()));
+    assert_eq!(manifest.node_range(), Some("^6.11".to_string()));
+
+    let project_path = fixture_path("basic");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(manifest.node_range(), None);
+}
+
+#[test]
+fn gets_pinned_sha256() {
+    let project_path = fixture_path("pinned_hash");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    let node = Version::parse("6.11.1").unwrap();
+    assert_eq!(manifest.node(), Some(node.clone()));
+    let pinned = manifest.pinned_node_sha256(&node);
+    assert_eq!(pinned.len(), 1);
+    assert_eq!(
+        pinned
+            .get("node-v6.11.1-linux-x64.tar.gz")
+            .map(String::as_str),
+        Some("8a6c2e1b5d7f3c4e9a0b1d2c3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f")
+    );
+    assert!(manifest
+        .pinned_node_sha256(&Version::parse("6.11.2").unwrap())
+        .is_empty());
+    assert!(manifest
+        .pinned_yarn_sha256(&Version::parse("1.2.0").unwrap())
+        .is_empty());
+}
+
+#[test]
+fn keeps_pinned_sha256_of_other_archives() {
+    let project_path = fixture_path("pinned_hash");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    let mut sha256 = ArchiveHashes::new();
+    sha256.insert(
+        "node-v6.11.1-darwin-x64.tar.gz".to_string(),
+        "abc".to_string(),
+    );
+    let toolchain = ToolchainSpec::new(Some("6.11.1".to_string()), None, None, None, None, None)
+        .with_sha256(sha256, ArchiveHashes::new())
+        .keep_sha256(&manifest);
+    let pinned = toolchain.node_sha256();
+    assert_eq!(pinned.len(), 2);
+    assert_eq!(
+        pinned
+            .get("node-v6.11.1-darwin-x64.tar.gz")
+            .map(String::as_str),
+        Some("abc")
+    );
+    assert!(pinned.contains_key("node-v6.11.1-linux-x64.tar.gz"));
+
+    let toolchain = ToolchainSpec::new(Some("6.11.2".to_string()), None, None, None, None, None)
+        .keep_sha256(&manifest);
+    assert!(toolchain.node_sha256().is_empty());
+}
+
+#[test]
+fn gets_partial_platform() {
+    let project_path = fixture_path("partial_pins");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(manifest.platform(), None);
+    let partial = manifest.partial_platform().unwrap();
+    assert_eq!(partial.npm, None);
+    assert_eq!(partial.yarn, Some(Version::parse("1.19.0").unwrap()));
+}
+
+#[test]
+fn partial_platform_with_node() {
+    let project_path = fixture_path("basic");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(manifest.partial_platform(), None);
+}
+
+#[test]
+fn gets_deno_version() {
+    let project_path = fixture_path("deno");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(manifest.deno(), Some(Version::parse("1.40.0").unwrap()));
+    assert_eq!(manifest.node(), None);
+}
+
+#[test]
+fn deno_for_no_toolchain() {
+    let project_path = fixture_path("no_toolchain");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(manifest.deno(), None);
+}
+
+#[test]
+fn gets_bun_version() {
+    let project_path = fixture_path("bun");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(manifest.bun(), Some(Version::parse("1.0.25").unwrap()));
+    assert_eq!(manifest.node(), None);
+}
+
+#[test]
+fn bun_for_no_toolchain() {
+    let project_path = fixture_path("no_toolchain");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(manifest.bun(), None);
+}
+
+#[test]
+fn gets_bypass() {
+    let project_path = fixture_path("bypass");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert!(manifest.bypass);
+
+    let project_path = fixture_path("basic");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert!(!manifest.bypass);
+}
+
+#[test]
+fn gets_env() {
+    let project_path = fixture_path("env");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(
+        manifest.env.get("NODE_OPTIONS").map(String::as_str),
+        Some("--max-old-space-size=4096")
+    );
+    assert_eq!(manifest.env.len(), 2);
+
+    let project_path = fixture_path("basic");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert!(manifest.env.is_empty());
+}
+
+#[test]
+fn gets_node_flags() {
+    let project_path = fixture_path("node_flags");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(
+        manifest.node_flags,
+        vec!["--max-old-space-size=4096", "--experimental-vm-modules"]
+    );
+
+    let project_path = fixture_path("basic");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert!(manifest.node_flags.is_empty());
+}
+
+#[test]
+fn gets_commands() {
+    let project_path = fixture_path("commands");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert_eq!(
+        manifest.commands.get("test").map(String::as_str),
+        Some("jest --ci")
+    );
+    assert_eq!(manifest.commands.len(), 2);
+
+    let project_path = fixture_path("basic");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    assert!(manifest.commands.is_empty());
+}
+
+#[test]
+fn update_rejects_invalid_commands() {
+    let dir = tempdir().expect("Could not create temporary directory");
+    let package_file = dir.path().join("package.json");
+    let contents = r#"{
+  "volta": {
+    "node": "10.16.0",
+    "commands": ["jest --ci"]
+  }
+}
+"#;
+    write(&package_file, contents).expect("Could not write package.json");
+
+    let toolchain = ToolchainSpec::new(Some("12.0.0".to_string()), None, None, None, None, None);
+    assert!(Manifest::update_toolchain(toolchain, package_file.clone()).is_err());
+    assert_eq!(read_to_string(&package_file).unwrap(), contents);
+}
+
 #[test]
 fn gets_bin_map_format() {
     let project_path = fixture_path("basic/node_modules/eslint");
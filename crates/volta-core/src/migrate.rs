@@ -0,0 +1,251 @@
+//! Provides the migrations that bring an older layout of the Volta home directory up to date.
+//!
+//! The layout's version is recorded in a marker file in the Volta home directory, which is
+//! missing from layouts that predate it. Each migration moves files from the layout before it,
+//! and is applied as a whole or not at all: if any of its changes fail, the ones already made
+//! are undone and the marker is left as it was.
+
+use std::fmt;
+use std::fs::{rename, write};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+
+use crate::error::ErrorDetails;
+use crate::fs::{ensure_containing_dir_exists, read_file_opt};
+use crate::path;
+use crate::style::success_prefix;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// The version of the layout that this version of Volta uses.
+pub const CURRENT_LAYOUT: u32 = 1;
+
+/// A step from one layout version to the next.
+struct Migration {
+    /// The version of the layout after migrating.
+    to: u32,
+    description: &'static str,
+    /// Lists the changes to make to the Volta home directory.
+    plan: fn(&Path) -> Fallible<Vec<Change>>,
+}
+
+/// The migrations, in order.
+const MIGRATIONS: [Migration; 1] = [Migration {
+    to: 1,
+    description: "Record the version of the layout",
+    plan: plan_v1,
+}];
+
+/// A single change to the Volta home directory.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Move { from: PathBuf, to: PathBuf },
+}
+
+impl Change {
+    fn apply(&self) -> Fallible<()> {
+        match self {
+            Change::Move { from, to } => {
+                ensure_containing_dir_exists(to)?;
+                rename(from, to).with_context(|_| ErrorDetails::MigrateLayoutError {
+                    from: from.clone(),
+                    to: to.clone(),
+                })
+            }
+        }
+    }
+
+    fn undo(&self) {
+        match self {
+            Change::Move { from, to } => {
+                if let Err(error) = rename(to, from) {
+                    debug!("Could not undo move of {}: {}", from.display(), error);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Move { from, to } => {
+                write!(f, "move {} to {}", from.display(), to.display())
+            }
+        }
+    }
+}
+
+/// A migration that hasn't been applied yet, along with the changes it will make.
+pub struct PendingMigration {
+    pub to: u32,
+    pub description: &'static str,
+    pub changes: Vec<Change>,
+}
+
+/// Lists the migrations that the Volta home directory needs, without changing anything.
+pub fn pending_migrations() -> Fallible<Vec<PendingMigration>> {
+    let home = path::volta_home()?;
+    pending_migrations_in(&home, read_layout(&home)?)
+}
+
+/// Brings the layout of the Volta home directory up to date, reporting each migration when
+/// `report` is set.
+pub fn migrate(report: bool) -> Fallible<()> {
+    let home = path::volta_home()?;
+    if read_layout(&home)? == CURRENT_LAYOUT {
+        return Ok(());
+    }
+
+    if path::is_read_only_home() {
+        debug!("Not migrating the Volta directory, since it is read-only.");
+        return Ok(());
+    }
+
+    for migration in migrate_in(&home)? {
+        if report {
+            info!("{} {}", success_prefix(), migration.description);
+        } else {
+            debug!("Migrated Volta directory to layout v{}", migration.to);
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies the pending migrations, recording the layout after each one, and returns them.
+fn migrate_in(home: &Path) -> Fallible<Vec<PendingMigration>> {
+    let pending = pending_migrations_in(home, read_layout(home)?)?;
+    for migration in &pending {
+        apply_all(&migration.changes)?;
+        write_layout(home, migration.to)?;
+    }
+    Ok(pending)
+}
+
+fn pending_migrations_in(home: &Path, layout: u32) -> Fallible<Vec<PendingMigration>> {
+    if layout > CURRENT_LAYOUT {
+        throw!(ErrorDetails::LayoutTooNew { version: layout });
+    }
+
+    MIGRATIONS
+        .iter()
+        .filter(|migration| migration.to > layout)
+        .map(|migration| {
+            Ok(PendingMigration {
+                to: migration.to,
+                description: migration.description,
+                changes: (migration.plan)(home)?,
+            })
+        })
+        .collect()
+}
+
+/// Applies the changes in order, undoing the ones already made if any of them fail.
+fn apply_all(changes: &[Change]) -> Fallible<()> {
+    for (index, change) in changes.iter().enumerate() {
+        if let Err(error) = change.apply() {
+            for applied in changes[..index].iter().rev() {
+                applied.undo();
+            }
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+fn layout_file(home: &Path) -> PathBuf {
+    home.join("layout")
+}
+
+/// Reads the layout version, which is 0 for a directory without a marker.
+fn read_layout(home: &Path) -> Fallible<u32> {
+    let file = layout_file(home);
+    let src = read_file_opt(&file)
+        .with_context(|_| ErrorDetails::ReadLayoutError { file: file.clone() })?;
+
+    match src {
+        Some(src) => src
+            .trim()
+            .parse()
+            .with_context(|_| ErrorDetails::ReadLayoutError { file }),
+        None => Ok(0),
+    }
+}
+
+fn write_layout(home: &Path, version: u32) -> Fallible<()> {
+    let file = layout_file(home);
+    write(&file, format!("{}\n", version)).with_context(|_| ErrorDetails::WriteLayoutError { file })
+}
+
+/// Layout v1 is the first to record its version, and is otherwise the same as the layout before
+/// it, so there is nothing to move.
+fn plan_v1(_home: &Path) -> Fallible<Vec<Change>> {
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{apply_all, migrate_in, pending_migrations_in, read_layout, Change};
+    use std::fs::{create_dir_all, write};
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn touch(file: &Path) {
+        create_dir_all(file.parent().unwrap()).unwrap();
+        write(file, "").unwrap();
+    }
+
+    #[test]
+    fn plans_migrations() {
+        let home = tempdir().unwrap();
+        touch(
+            &home
+                .path()
+                .join("tools/inventory/node/node-v10.16.0-linux-x64.tar.gz"),
+        );
+
+        let pending = pending_migrations_in(home.path(), 0).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].to, 1);
+        assert!(pending[0].changes.is_empty());
+
+        assert!(pending_migrations_in(home.path(), 1).unwrap().is_empty());
+        assert!(pending_migrations_in(home.path(), 2).is_err());
+    }
+
+    #[test]
+    fn migrates_layout() {
+        let home = tempdir().unwrap();
+        let archive = Path::new("tools/inventory/node/node-v10.16.0-linux-x64.tar.gz");
+        touch(&home.path().join(archive));
+
+        migrate_in(home.path()).unwrap();
+
+        assert!(home.path().join(archive).exists());
+        assert_eq!(read_layout(home.path()).unwrap(), 1);
+        assert!(migrate_in(home.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn undoes_failed_migrations() {
+        let home = tempdir().unwrap();
+        let moved = home.path().join("a");
+        touch(&moved);
+
+        let changes = vec![
+            Change::Move {
+                from: moved.clone(),
+                to: home.path().join("b"),
+            },
+            Change::Move {
+                from: home.path().join("missing"),
+                to: home.path().join("c"),
+            },
+        ];
+
+        assert!(apply_all(&changes).is_err());
+        assert!(moved.exists());
+        assert!(!home.path().join("b").exists());
+    }
+}
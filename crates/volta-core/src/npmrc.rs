@@ -0,0 +1,279 @@
+//! Provides the `Npmrc` type, which holds the registry configuration and credentials from the
+//! user's and the current project's `.npmrc` files.
+
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use base64;
+use dirs;
+use log::debug;
+
+use crate::error::ErrorDetails;
+use crate::fs::read_file_opt;
+use crate::path::find_project_dir;
+use volta_fail::{Fallible, ResultExt};
+
+/// The environment variable npm uses to override the location of the user's `.npmrc`.
+const USER_CONFIG_VAR: &str = "NPM_CONFIG_USERCONFIG";
+
+/// The registry settings from `.npmrc` files.
+#[derive(Default)]
+pub struct Npmrc {
+    settings: HashMap<String, String>,
+}
+
+impl Npmrc {
+    /// Loads the settings from the user's `.npmrc`, followed by the `.npmrc` of the project
+    /// containing the current directory, if any. Project settings take precedence.
+    pub fn current() -> Fallible<Self> {
+        let mut npmrc = Npmrc::default();
+
+        if let Some(user_file) = user_npmrc_file() {
+            npmrc.load_file(user_file)?;
+        }
+
+        if let Ok(current_dir) = env::current_dir() {
+            if let Some(project_dir) = find_project_dir(&current_dir) {
+                npmrc.load_file(project_dir.join(".npmrc"))?;
+            }
+        }
+
+        Ok(npmrc)
+    }
+
+    fn load_file(&mut self, file: PathBuf) -> Fallible<()> {
+        let src = read_file_opt(&file)
+            .with_context(|_| ErrorDetails::ReadNpmrcError { file: file.clone() })?;
+
+        if let Some(src) = src {
+            debug!("Using registry settings from {}", file.display());
+            self.merge(&src);
+        }
+
+        Ok(())
+    }
+
+    /// Adds the settings from the contents of an `.npmrc` file, replacing any existing settings
+    /// with the same keys.
+    fn merge(&mut self, src: &str) {
+        for line in src.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                self.settings
+                    .insert(key.trim().to_string(), parse_value(value.trim()));
+            }
+        }
+    }
+
+    /// Returns the registry that the specified package should be fetched from, if one is
+    /// configured: the registry for the package's scope, otherwise the default registry.
+    pub fn registry_for(&self, package: &str) -> Option<String> {
+        let scoped = if package.starts_with('@') {
+            package
+                .split('/')
+                .next()
+                .and_then(|scope| self.settings.get(&format!("{}:registry", scope)))
+        } else {
+            None
+        };
+
+        scoped
+            .or_else(|| self.settings.get("registry"))
+            .map(|registry| registry.trim_end_matches('/').to_string())
+    }
+
+    /// Returns the value of the `Authorization` header to send to the specified registry, if
+    /// there are credentials configured for it. As with npm, credentials are keyed by exactly the
+    /// registry's URL without its scheme (e.g. `//registry.example.com/path/:_authToken`), so
+    /// credentials for one registry are never sent to another on the same host.
+    pub fn authorization_for(&self, registry: &str) -> Option<String> {
+        let registry = strip_scheme(registry).trim_end_matches('/');
+        let key = vec![format!("{}/", registry), registry.to_string()]
+            .into_iter()
+            .find(|key| {
+                self.settings.keys().any(|setting| {
+                    setting.starts_with(key) && setting[key.len()..].starts_with(':')
+                })
+            })?;
+
+        let setting = |name: &str| self.settings.get(&format!("{}:{}", key, name));
+
+        if let Some(token) = setting("_authToken") {
+            Some(format!("Bearer {}", token))
+        } else if let Some(auth) = setting("_auth") {
+            Some(format!("Basic {}", auth))
+        } else if let (Some(username), Some(password)) = (setting("username"), setting("_password"))
+        {
+            // The password is stored base64-encoded
+            let password = base64::decode(password).ok()?;
+            let password = String::from_utf8(password).ok()?;
+            let credentials = base64::encode(&format!("{}:{}", username, password));
+            Some(format!("Basic {}", credentials))
+        } else {
+            None
+        }
+    }
+}
+
+fn user_npmrc_file() -> Option<PathBuf> {
+    match env::var_os(USER_CONFIG_VAR) {
+        Some(file) => Some(PathBuf::from(file)),
+        None => dirs::home_dir().map(|home| home.join(".npmrc")),
+    }
+}
+
+fn strip_scheme(url: &str) -> &str {
+    match url.find("//") {
+        Some(index) => &url[index..],
+        None => url,
+    }
+}
+
+/// Removes surrounding quotes from a setting value and replaces `${VAR}` references with the
+/// values of the corresponding environment variables, as npm does.
+fn parse_value(value: &str) -> String {
+    let value = if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    };
+
+    expand_env_vars(value)
+}
+
+/// Replaces `${VAR}` references with the values of the corresponding environment variables,
+/// leaving an empty string for any that aren't set.
+pub(crate) fn expand_env_vars(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        match rest[start..].find('}') {
+            Some(len) => {
+                let name = &rest[start + 2..start + len];
+                result.push_str(&rest[..start]);
+                result.push_str(&env::var(name).unwrap_or_default());
+                rest = &rest[start + len + 1..];
+            }
+            None => break,
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::Npmrc;
+    use std::env;
+
+    fn npmrc(src: &str) -> Npmrc {
+        let mut npmrc = Npmrc::default();
+        npmrc.merge(src);
+        npmrc
+    }
+
+    #[test]
+    fn uses_scoped_registry() {
+        let npmrc = npmrc(
+            "registry=https://registry.example.com/
+@myorg:registry = https://npm.myorg.com/packages/
+; a comment
+# another comment",
+        );
+
+        assert_eq!(
+            npmrc.registry_for("@myorg/cli"),
+            Some("https://npm.myorg.com/packages".to_string())
+        );
+        assert_eq!(
+            npmrc.registry_for("@other/cli"),
+            Some("https://registry.example.com".to_string())
+        );
+        assert_eq!(
+            npmrc.registry_for("cowsay"),
+            Some("https://registry.example.com".to_string())
+        );
+        assert_eq!(Npmrc::default().registry_for("cowsay"), None);
+    }
+
+    #[test]
+    fn later_settings_take_precedence() {
+        let mut npmrc = npmrc("registry=https://user.example.com/");
+        npmrc.merge("registry=https://project.example.com/");
+
+        assert_eq!(
+            npmrc.registry_for("cowsay"),
+            Some("https://project.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn matches_auth_token_to_exact_registry() {
+        let npmrc = npmrc(
+            r#"//npm.myorg.com/:_authToken=host-token
+//npm.myorg.com/packages/:_authToken="path-token"
+//npm.other.com/private:_authToken=other-token"#,
+        );
+
+        assert_eq!(
+            npmrc.authorization_for("https://npm.myorg.com/packages/"),
+            Some("Bearer path-token".to_string())
+        );
+        assert_eq!(
+            npmrc.authorization_for("https://npm.myorg.com"),
+            Some("Bearer host-token".to_string())
+        );
+        assert_eq!(
+            npmrc.authorization_for("https://npm.other.com/private"),
+            Some("Bearer other-token".to_string())
+        );
+        assert_eq!(npmrc.authorization_for("https://npm.myorg.com/other"), None);
+        assert_eq!(
+            npmrc.authorization_for("https://npm.other.com/private-2"),
+            None
+        );
+        assert_eq!(
+            npmrc.authorization_for("https://npm.myorg.com.evil.com"),
+            None
+        );
+        assert_eq!(npmrc.authorization_for("https://registry.npmjs.org"), None);
+    }
+
+    #[test]
+    fn uses_basic_auth() {
+        let npmrc = npmrc(
+            "//one.example.com/:_auth=dXNlcjpwYXNz
+//two.example.com/:username=user
+//two.example.com/:_password=cGFzcw==",
+        );
+
+        assert_eq!(
+            npmrc.authorization_for("https://one.example.com/"),
+            Some("Basic dXNlcjpwYXNz".to_string())
+        );
+        assert_eq!(
+            npmrc.authorization_for("https://two.example.com"),
+            Some("Basic dXNlcjpwYXNz".to_string())
+        );
+    }
+
+    #[test]
+    fn expands_environment_variables() {
+        env::set_var("VOLTA_TEST_NPM_TOKEN", "secret");
+        let npmrc = npmrc("//npm.myorg.com/:_authToken=${VOLTA_TEST_NPM_TOKEN}");
+
+        assert_eq!(
+            npmrc.authorization_for("https://npm.myorg.com"),
+            Some("Bearer secret".to_string())
+        );
+    }
+}
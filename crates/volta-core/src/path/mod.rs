@@ -5,9 +5,15 @@ use std::env;
 use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
 
+use lazy_static::lazy_static;
+use log::warn;
+
+use crate::env::{PROFILE, SHARED_HOME, TMP_DIR};
 use crate::error::ErrorDetails;
-use volta_fail::{Fallible, ResultExt};
+use crate::hook::HookConfig;
+use volta_fail::{throw, Fallible, ResultExt};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "cross-platform-docs")] {
@@ -61,26 +67,206 @@ pub fn volta_home() -> Fallible<PathBuf> {
     }
 }
 
+lazy_static! {
+    static ref READ_ONLY_HOME: bool = volta_home()
+        .map(|home| is_read_only_dir(&home))
+        .unwrap_or(false);
+}
+
+/// Determines whether an existing directory can't be written to, by trying to create a file in it.
+fn is_read_only_dir(dir: &Path) -> bool {
+    dir.is_dir() && tempfile::tempfile_in(dir).is_err()
+}
+
+/// Determines whether the Volta home directory is on a read-only filesystem, as it may be on
+/// locked-down CI machines and in Nix-like environments. Volta can still run the tools that are
+/// already there, but skips recording events and can't fetch anything new.
+pub fn is_read_only_home() -> bool {
+    *READ_ONLY_HOME
+}
+
+/// Ensures that the Volta home directory can be written to, before fetching a tool into it.
+pub fn ensure_writable_home() -> Fallible<()> {
+    if is_read_only_home() {
+        throw!(ErrorDetails::ReadOnlyVoltaHome { dir: volta_home()? });
+    }
+    Ok(())
+}
+
 pub fn cache_dir() -> Fallible<PathBuf> {
     Ok(volta_home()?.join("cache"))
 }
 
+/// The queue of events waiting to be sent to the URL in the `publish` events hook.
+pub fn event_queue_file() -> Fallible<PathBuf> {
+    Ok(cache_dir()?.join("events-queue.jsonl"))
+}
+
+/// The platform that shims last ran with in each project, to notice when it changes.
+pub fn project_platforms_file() -> Fallible<PathBuf> {
+    Ok(cache_dir()?.join("project-platforms.json"))
+}
+
+/// The memos of the executables provided by each project's direct dependencies.
+pub fn project_bins_dir() -> Fallible<PathBuf> {
+    Ok(cache_dir()?.join("project-bins"))
+}
+
+/// The lock that a background fetch of a project's pinned platform holds while it runs (see
+/// `platform::prefetch`), named after the platform.
+pub fn prefetch_lock_file(platform: &str) -> Fallible<PathBuf> {
+    Ok(cache_dir()?.join("prefetch").join(format!("{}.lock", platform)))
+}
+
+/// The result of the last check for a new version of Volta.
+pub fn update_check_file() -> Fallible<PathBuf> {
+    Ok(cache_dir()?.join("update-check.json"))
+}
+
+/// The directory for temporary files, where tools are unpacked before they're moved into place.
+/// It can be set with `VOLTA_TMPDIR` or with `tmpDir` in the `volta` section of the user hooks,
+/// e.g. when the disk with the Volta home directory is small. Otherwise, it's in the Volta home
+/// directory, or is the system's when the Volta home directory is read-only.
 pub fn tmp_dir() -> Fallible<PathBuf> {
-    Ok(volta_home()?.join("tmp"))
+    if let Some(dir) = configured_tmp_dir()? {
+        Ok(dir)
+    } else if is_read_only_home() {
+        Ok(env::temp_dir())
+    } else {
+        Ok(volta_home()?.join("tmp"))
+    }
+}
+
+lazy_static! {
+    static ref VALIDATED_TMP_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+fn configured_tmp_dir() -> Fallible<Option<PathBuf>> {
+    let dir = match env::var_os(TMP_DIR) {
+        Some(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => match HookConfig::user_tmp_dir()? {
+            Some(dir) => dir,
+            None => return Ok(None),
+        },
+    };
+
+    // The directory is only checked the first time it's used
+    let mut validated = VALIDATED_TMP_DIR
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if validated.as_ref() != Some(&dir) {
+        validate_tmp_dir(&dir)?;
+        *validated = Some(dir.clone());
+    }
+
+    Ok(Some(dir))
+}
+
+/// Checks that a directory can be used to unpack tools in: Volta must be able to create files
+/// there and make them executable. It should also be on the same filesystem as the Volta home
+/// directory, so that tools can be moved into place instead of copied.
+fn validate_tmp_dir(dir: &Path) -> Fallible<()> {
+    if !dir.is_absolute() {
+        throw!(ErrorDetails::InvalidTmpDir {
+            dir: dir.to_path_buf()
+        });
+    }
+
+    ensure_dir_exists(dir.to_path_buf())?;
+    let probe =
+        tempfile::NamedTempFile::new_in(dir).with_context(|_| ErrorDetails::TmpDirNotWritable {
+            dir: dir.to_path_buf(),
+        })?;
+
+    if !can_make_executable(probe.path()) {
+        throw!(ErrorDetails::TmpDirPermissionsError {
+            dir: dir.to_path_buf()
+        });
+    }
+
+    let home = volta_home()?;
+    if !is_same_device(dir, &home) {
+        warn!(
+            "{} is on a different filesystem than {}, so tools will be copied into place instead of moved",
+            dir.display(),
+            home.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn can_make_executable(file: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(file, fs::Permissions::from_mode(0o755))
+        .and_then(|_| fs::metadata(file))
+        .map(|metadata| metadata.permissions().mode() & 0o111 == 0o111)
+        .unwrap_or(false)
+}
+
+/// Windows doesn't have permissions for executing files, so any file can be executed.
+#[cfg(windows)]
+fn can_make_executable(_file: &Path) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn is_same_device(left: &Path, right: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::metadata(left), fs::metadata(right)) {
+        (Ok(left), Ok(right)) => left.dev() == right.dev(),
+        // If either can't be read, there's nothing to warn about yet
+        _ => true,
+    }
+}
+
+#[cfg(windows)]
+fn is_same_device(left: &Path, right: &Path) -> bool {
+    left.components().next() == right.components().next()
 }
 
 pub fn log_dir() -> Fallible<PathBuf> {
     Ok(volta_home()?.join("log"))
 }
 
+/// The file that events are appended to when `VOLTA_EVENT_LOG` is set.
+pub fn event_log_file() -> Fallible<PathBuf> {
+    Ok(log_dir()?.join("events.jsonl"))
+}
+
+/// The file that a shim writes its trace to when `VOLTA_TRACE` is set, named for when the shim
+/// started, in milliseconds since the Unix epoch, and for its process.
+pub fn trace_file(started: u64, pid: u32) -> Fallible<PathBuf> {
+    Ok(log_dir()?
+        .join("traces")
+        .join(format!("{}-{}.json", started, pid)))
+}
+
 pub fn node_inventory_dir() -> Fallible<PathBuf> {
     Ok(inventory_dir()?.join("node"))
 }
 
+/// Pre-release (RC, nightly, and canary) Node builds are kept separate from the stable
+/// releases, so that they are never mistaken for one.
+pub fn node_prerelease_inventory_dir() -> Fallible<PathBuf> {
+    Ok(inventory_dir()?.join("node-prerelease"))
+}
+
 pub fn yarn_inventory_dir() -> Fallible<PathBuf> {
     Ok(inventory_dir()?.join("yarn"))
 }
 
+pub fn deno_inventory_dir() -> Fallible<PathBuf> {
+    Ok(inventory_dir()?.join("deno"))
+}
+
+pub fn bun_inventory_dir() -> Fallible<PathBuf> {
+    Ok(inventory_dir()?.join("bun"))
+}
+
 pub fn package_inventory_dir() -> Fallible<PathBuf> {
     Ok(inventory_dir()?.join("packages"))
 }
@@ -105,6 +291,95 @@ pub fn node_index_expiry_file() -> Fallible<PathBuf> {
     Ok(node_cache_dir()?.join("index.json.expires"))
 }
 
+/// The cached copies of release indexes and package metadata, other than the Node index.
+pub fn metadata_cache_dir() -> Fallible<PathBuf> {
+    Ok(cache_dir()?.join("metadata"))
+}
+
+/// The `tools` directories that downloaded tools are kept in, in the order they are consulted:
+/// those of the Volta homes listed in `VOLTA_SHARED_HOME`, followed by the one in `VOLTA_HOME`.
+///
+/// Shared homes let a machine image come with tools already fetched (e.g. in `/opt/volta`), and
+/// may be read-only; user defaults and global packages always stay in `VOLTA_HOME`.
+pub fn tools_roots() -> Fallible<Vec<PathBuf>> {
+    let mut roots: Vec<PathBuf> = env::var_os(SHARED_HOME)
+        .iter()
+        .flat_map(env::split_paths)
+        .filter(|home| !home.as_os_str().is_empty())
+        .map(|home| home.join("tools"))
+        .collect();
+    roots.push(tools_dir()?);
+    Ok(roots)
+}
+
+lazy_static! {
+    static ref WRITABLE_TOOLS_DIR: Mutex<Option<(Vec<PathBuf>, PathBuf)>> = Mutex::new(None);
+}
+
+/// The `tools` directory that newly fetched tools are kept in: the first of the tool roots that
+/// is writable, falling back to the one in `VOLTA_HOME`.
+fn writable_tools_dir() -> Fallible<PathBuf> {
+    let mut roots = tools_roots()?;
+    let home_root = roots
+        .pop()
+        .expect("The tool roots always end with VOLTA_HOME");
+    if roots.is_empty() {
+        return Ok(home_root);
+    }
+
+    // Each root is only probed for writing the first time that the same roots are used
+    let mut probed = WRITABLE_TOOLS_DIR
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    match *probed {
+        Some((ref probed_roots, ref dir)) if *probed_roots == roots => Ok(dir.clone()),
+        _ => {
+            let dir = roots
+                .iter()
+                .find(|root| tempfile::tempfile_in(root).is_ok())
+                .cloned()
+                .unwrap_or(home_root);
+            *probed = Some((roots, dir.clone()));
+            Ok(dir)
+        }
+    }
+}
+
+/// Finds a file or directory in the first of the tool roots that has it, or else in the writable
+/// root, where it would be created.
+fn shared_tool_path(relative: &Path) -> Fallible<PathBuf> {
+    let roots = tools_roots()?;
+    if roots.len() > 1 {
+        if let Some(existing) = find_in_roots(&roots, relative) {
+            return Ok(existing);
+        }
+    }
+    Ok(writable_tools_dir()?.join(relative))
+}
+
+fn find_in_roots(roots: &[PathBuf], relative: &Path) -> Option<PathBuf> {
+    roots
+        .iter()
+        .map(|root| root.join(relative))
+        .find(|path| path.exists())
+}
+
+/// The inventory directories of a tool, like `node` or `yarn`, in each of the tool roots.
+pub fn inventory_dirs(tool: &str) -> Fallible<Vec<PathBuf>> {
+    Ok(tools_roots()?
+        .into_iter()
+        .map(|root| root.join("inventory").join(tool))
+        .collect())
+}
+
+/// The image directories of a tool, like `node` or `yarn`, in each of the tool roots.
+pub fn image_dirs(tool: &str) -> Fallible<Vec<PathBuf>> {
+    Ok(tools_roots()?
+        .into_iter()
+        .map(|root| root.join("image").join(tool))
+        .collect())
+}
+
 pub fn image_dir() -> Fallible<PathBuf> {
     Ok(tools_dir()?.join("image"))
 }
@@ -113,8 +388,51 @@ pub fn node_image_root_dir() -> Fallible<PathBuf> {
     Ok(image_dir()?.join("node"))
 }
 
-pub fn node_image_dir(node: &str, npm: &str) -> Fallible<PathBuf> {
-    Ok(node_image_root_dir()?.join(node).join(npm))
+/// The directory of a Node image. Images for an architecture other than the native one (see
+/// `volta install --arch`) are kept in a separate root, so that both can be installed side by side.
+pub fn node_image_dir(node: &str, npm: &str, arch: Option<&str>) -> Fallible<PathBuf> {
+    let root = match arch.filter(|&arch| arch != ARCH) {
+        Some(arch) => format!("node-{}", arch),
+        None => "node".to_string(),
+    };
+    shared_tool_path(&Path::new("image").join(root).join(node).join(npm))
+}
+
+/// The directory of the images of a version of Node, one for each version of npm that it has been
+/// installed with.
+pub fn node_image_version_dir(node: &str, arch: Option<&str>) -> Fallible<PathBuf> {
+    let root = match arch.filter(|&arch| arch != ARCH) {
+        Some(arch) => format!("node-{}", arch),
+        None => "node".to_string(),
+    };
+    shared_tool_path(&Path::new("image").join(root).join(node))
+}
+
+/// The directory that the headers of each version of Node are kept in, for node-gyp (see
+/// `volta fetch --headers`).
+pub fn node_headers_root_dir() -> Fallible<PathBuf> {
+    Ok(image_dir()?.join("node-headers"))
+}
+
+pub fn node_headers_dir(node: &str) -> Fallible<PathBuf> {
+    Ok(node_headers_root_dir()?.join(node))
+}
+
+/// The directory that an unpacked Node distro is kept in, unchanged, when its images are built as
+/// symlink farms (see `VOLTA_LINK_IMAGES`).
+pub fn node_store_dir(node: &str, arch: Option<&str>) -> Fallible<PathBuf> {
+    let root = match arch.filter(|&arch| arch != ARCH) {
+        Some(arch) => format!("node-{}", arch),
+        None => "node".to_string(),
+    };
+    Ok(writable_tools_dir()?.join("store").join(root).join(node))
+}
+
+pub fn yarn_store_dir(version: &str) -> Fallible<PathBuf> {
+    Ok(writable_tools_dir()?
+        .join("store")
+        .join("yarn")
+        .join(version))
 }
 
 pub fn yarn_image_root_dir() -> Fallible<PathBuf> {
@@ -122,21 +440,50 @@ pub fn yarn_image_root_dir() -> Fallible<PathBuf> {
 }
 
 pub fn yarn_image_dir(version: &str) -> Fallible<PathBuf> {
-    Ok(yarn_image_root_dir()?.join(version))
+    shared_tool_path(&Path::new("image").join("yarn").join(version))
 }
 
 pub fn yarn_image_bin_dir(version: &str) -> Fallible<PathBuf> {
     Ok(yarn_image_dir(version)?.join("bin"))
 }
 
+pub fn deno_image_root_dir() -> Fallible<PathBuf> {
+    Ok(image_dir()?.join("deno"))
+}
+
+pub fn deno_image_dir(version: &str) -> Fallible<PathBuf> {
+    shared_tool_path(&Path::new("image").join("deno").join(version))
+}
+
+pub fn deno_image_bin_dir(version: &str) -> Fallible<PathBuf> {
+    Ok(deno_image_dir(version)?.join("bin"))
+}
+
+pub fn bun_image_root_dir() -> Fallible<PathBuf> {
+    Ok(image_dir()?.join("bun"))
+}
+
+pub fn bun_image_dir(version: &str) -> Fallible<PathBuf> {
+    shared_tool_path(&Path::new("image").join("bun").join(version))
+}
+
+pub fn bun_image_bin_dir(version: &str) -> Fallible<PathBuf> {
+    Ok(bun_image_dir(version)?.join("bin"))
+}
+
 pub fn package_image_root_dir() -> Fallible<PathBuf> {
-    Ok(image_dir()?.join("packages"))
+    Ok(user_tools_dir()?.join("image").join("packages"))
 }
 
 pub fn package_image_dir(name: &str, version: &str) -> Fallible<PathBuf> {
     Ok(package_image_root_dir()?.join(name).join(version))
 }
 
+/// The executables of a version of npm that is pinned in place of the one bundled with Node.
+pub fn npm_image_bin_dir(version: &str) -> Fallible<PathBuf> {
+    Ok(package_image_dir("npm", version)?.join("bin"))
+}
+
 pub fn shim_dir() -> Fallible<PathBuf> {
     Ok(volta_home()?.join("bin"))
 }
@@ -145,16 +492,75 @@ pub fn user_hooks_file() -> Fallible<PathBuf> {
     Ok(volta_home()?.join("hooks.json"))
 }
 
+/// The file with the user's settings (see `config`).
+pub fn config_file() -> Fallible<PathBuf> {
+    Ok(volta_home()?.join("config.toml"))
+}
+
+/// The file listing the packages to install whenever a new default version of Node is installed.
+pub fn default_packages_file() -> Fallible<PathBuf> {
+    Ok(volta_home()?.join("default-packages"))
+}
+
 pub fn tools_dir() -> Fallible<PathBuf> {
     Ok(volta_home()?.join("tools"))
 }
 
 pub fn inventory_dir() -> Fallible<PathBuf> {
-    Ok(tools_dir()?.join("inventory"))
+    Ok(writable_tools_dir()?.join("inventory"))
+}
+
+/// The name of the active profile, set by `VOLTA_PROFILE` (or `volta --profile`), if any.
+pub fn profile() -> Fallible<Option<String>> {
+    match env::var(PROFILE) {
+        Ok(ref profile) if profile.is_empty() => Ok(None),
+        Ok(profile) => {
+            if !is_valid_profile(&profile) {
+                throw!(ErrorDetails::InvalidProfileName { profile });
+            }
+            Ok(Some(profile))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+// profile names become directory names, so they are kept to characters that are safe in paths
+fn is_valid_profile(profile: &str) -> bool {
+    profile
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The directory holding the user toolchain and the images of global packages. Each profile has
+/// its own, so that their defaults and packages stay separate, while the inventory of downloaded
+/// tools is shared between them.
+fn user_tools_dir() -> Fallible<PathBuf> {
+    match profile()? {
+        Some(profile) => Ok(tools_dir()?.join("profiles").join(profile)),
+        None => tools_dir(),
+    }
 }
 
 pub fn user_toolchain_dir() -> Fallible<PathBuf> {
-    Ok(tools_dir()?.join("user"))
+    Ok(user_tools_dir()?.join("user"))
+}
+
+/// The directories holding the configs of the executables installed by every profile, which all
+/// share the shim directory.
+pub fn all_user_bin_dirs() -> Fallible<Vec<PathBuf>> {
+    let tools_dir = tools_dir()?;
+    let mut dirs = vec![tools_dir.join("user").join("bins")];
+    if let Ok(profiles) = fs::read_dir(tools_dir.join("profiles")) {
+        for profile in profiles.filter_map(Result::ok) {
+            dirs.push(profile.path().join("user").join("bins"));
+        }
+    }
+    Ok(dirs)
+}
+
+/// The list of shims added with `volta shim add`, which don't belong to any installed package.
+pub fn custom_shims_file() -> Fallible<PathBuf> {
+    Ok(tools_dir()?.join("shims.json"))
 }
 
 pub fn user_platform_file() -> Fallible<PathBuf> {
@@ -177,21 +583,35 @@ pub fn user_tool_bin_config(bin_name: &str) -> Fallible<PathBuf> {
     Ok(user_bin_dir()?.join(format!("{}.json", bin_name)))
 }
 
-pub fn node_distro_file_name(version: &str) -> String {
+pub fn node_distro_file_name(version: &str, arch: Option<&str>) -> String {
     format!(
         "{}.{}",
-        node_archive_root_dir_name(version),
+        node_archive_root_dir_name(version, arch),
         archive_extension()
     )
 }
 
+pub fn node_headers_file_name(version: &str) -> String {
+    format!("node-v{}-headers.tar.gz", version)
+}
+
 pub fn node_npm_version_file(version: &str) -> Fallible<PathBuf> {
     let filename = format!("node-v{}-npm", version);
-    Ok(node_inventory_dir()?.join(&filename))
+    shared_tool_path(&Path::new("inventory").join("node").join(&filename))
 }
 
-pub fn node_archive_root_dir_name(version: &str) -> String {
-    format!("node-v{}-{}-{}", version, OS, ARCH)
+pub fn node_archive_root_dir_name(version: &str, arch: Option<&str>) -> String {
+    format!("node-v{}-{}", version, node_platform(arch))
+}
+
+/// The platform component of a Node distribution's name, e.g. `linux-x64` or `linux-x64-musl`.
+/// If no architecture is specified, the native one is used.
+pub fn node_platform(arch: Option<&str>) -> String {
+    let arch = arch.unwrap_or(ARCH);
+    match LIBC {
+        Some(libc) => format!("{}-{}-{}", OS, arch, libc),
+        None => format!("{}-{}", OS, arch),
+    }
 }
 
 pub fn yarn_distro_file_name(version: &str) -> String {
@@ -202,6 +622,17 @@ pub fn yarn_archive_root_dir_name(version: &str) -> String {
     format!("yarn-v{}", version)
 }
 
+/// Deno is published as a zip archive on every platform, containing only the `deno` executable.
+pub fn deno_distro_file_name(version: &str) -> String {
+    format!("deno-v{}.zip", version)
+}
+
+/// Bun is published as a zip archive on every platform, containing a single directory named for
+/// the platform, e.g. `bun-linux-x64`.
+pub fn bun_distro_file_name(version: &str) -> String {
+    format!("bun-v{}.zip", version)
+}
+
 pub fn package_distro_file_name(name: &str, version: &str) -> String {
     format!("{}.tgz", package_archive_root_dir_name(name, version))
 }
@@ -214,8 +645,9 @@ pub fn package_archive_root_dir_name(name: &str, version: &str) -> String {
     format!("{}-{}", name, version)
 }
 
+// a project without a package.json can pin its tools in a standalone volta.json
 fn is_node_root(dir: &Path) -> bool {
-    dir.join("package.json").is_file()
+    dir.join("package.json").is_file() || dir.join("volta.json").is_file()
 }
 
 fn is_node_modules(dir: &Path) -> bool {
@@ -230,6 +662,14 @@ fn is_project_root(dir: &Path) -> bool {
     is_node_root(dir) && !is_dependency(dir)
 }
 
+/// Determines whether Volta is bypassed in a directory, because it or one of its ancestors
+/// contains a `.volta-bypass` file.
+pub fn is_bypassed_dir(base_dir: &Path) -> bool {
+    base_dir
+        .ancestors()
+        .any(|dir| dir.join(".volta-bypass").is_file())
+}
+
 pub fn find_project_dir(base_dir: &Path) -> Option<&Path> {
     let mut dir = base_dir.clone();
     while !is_project_root(dir) {
@@ -259,20 +699,49 @@ pub mod tests {
         cargo_manifest_dir
     }
 
+    #[test]
+    fn test_is_bypassed_dir() {
+        assert!(is_bypassed_dir(&fixture_path(&["bypass"])));
+        assert!(is_bypassed_dir(&fixture_path(&["bypass", "src", "lib"])));
+        assert!(!is_bypassed_dir(&fixture_path(&["basic"])));
+    }
+
     #[test]
     fn test_node_distro_file_name() {
         assert_eq!(
-            node_distro_file_name("1.2.3"),
-            format!("node-v1.2.3-{}-{}.{}", OS, ARCH, archive_extension())
+            node_distro_file_name("1.2.3", None),
+            format!("node-v1.2.3-{}.{}", node_platform(None), archive_extension())
+        );
+    }
+
+    #[test]
+    fn test_node_headers_file_name() {
+        assert_eq!(
+            node_headers_file_name("1.2.3"),
+            "node-v1.2.3-headers.tar.gz".to_string()
         );
     }
 
     #[test]
     fn test_node_archive_root_dir() {
         assert_eq!(
-            node_archive_root_dir_name("1.2.3"),
-            format!("node-v1.2.3-{}-{}", OS, ARCH)
+            node_archive_root_dir_name("1.2.3", None),
+            format!("node-v1.2.3-{}", node_platform(None))
         );
+        assert_eq!(
+            node_archive_root_dir_name("1.2.3", Some("x64")),
+            format!("node-v1.2.3-{}", node_platform(Some("x64")))
+        );
+    }
+
+    #[test]
+    fn test_node_platform() {
+        let platform = node_platform(None);
+        assert!(platform.starts_with(&format!("{}-{}", OS, ARCH)));
+        assert_eq!(platform.ends_with("-musl"), LIBC == Some("musl"));
+
+        let platform = node_platform(Some("x64"));
+        assert!(platform.starts_with(&format!("{}-x64", OS)));
     }
 
     #[test]
@@ -288,6 +757,57 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_deno_distro_file_name() {
+        assert_eq!(deno_distro_file_name("1.40.0"), "deno-v1.40.0.zip");
+    }
+
+    #[test]
+    fn test_bun_distro_file_name() {
+        assert_eq!(bun_distro_file_name("1.0.25"), "bun-v1.0.25.zip");
+    }
+
+    #[test]
+    fn test_is_read_only_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!is_read_only_dir(dir.path()));
+        assert!(!is_read_only_dir(&dir.path().join("missing")));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o555)).unwrap();
+            // privileged users can write to the directory anyway
+            if tempfile::tempfile_in(dir.path()).is_err() {
+                assert!(is_read_only_dir(dir.path()));
+            }
+            fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_is_valid_profile() {
+        assert!(is_valid_profile("work"));
+        assert!(is_valid_profile("client_2-staging"));
+        assert!(!is_valid_profile("../escape"));
+        assert!(!is_valid_profile("two words"));
+    }
+
+    #[test]
+    fn test_find_in_roots() {
+        let roots = vec![fixture_path(&["basic"]), fixture_path(&["workspaces"])];
+        assert_eq!(
+            find_in_roots(&roots, Path::new("packages")),
+            Some(fixture_path(&["workspaces", "packages"]))
+        );
+        assert_eq!(
+            find_in_roots(&roots, Path::new("package.json")),
+            Some(fixture_path(&["basic", "package.json"]))
+        );
+        assert_eq!(find_in_roots(&roots, Path::new("missing")), None);
+    }
+
     #[test]
     fn test_find_project_dir_direct() {
         let base_dir = fixture_path(&["basic"]);
@@ -304,6 +824,14 @@ pub mod tests {
         assert_eq!(project_dir, fixture_path(&["basic"]));
     }
 
+    #[test]
+    fn test_find_project_dir_volta_json() {
+        let base_dir = fixture_path(&["volta_json", "tools"]);
+        let project_dir = find_project_dir(&base_dir).expect("Failed to find project directory");
+
+        assert_eq!(project_dir, fixture_path(&["volta_json"]));
+    }
+
     #[test]
     fn test_find_project_dir_dependency() {
         let base_dir = fixture_path(&["basic", "node_modules", "eslint"]);
@@ -311,4 +839,18 @@ pub mod tests {
 
         assert_eq!(project_dir, fixture_path(&["basic"]));
     }
+
+    #[test]
+    fn rejects_relative_tmp_dirs() {
+        assert!(validate_tmp_dir(Path::new("scratch")).is_err());
+    }
+
+    #[test]
+    fn creates_configured_tmp_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("scratch").join("volta");
+
+        validate_tmp_dir(&dir).unwrap();
+        assert!(dir.is_dir());
+    }
 }
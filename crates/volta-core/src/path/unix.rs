@@ -1,6 +1,7 @@
 //! Provides functions for determining the paths of files and directories
 //! in a standard Volta layout in Unix-based operating systems.
 
+use std::fs;
 use std::io;
 use std::os::unix;
 use std::path::{Path, PathBuf};
@@ -35,8 +36,26 @@ cfg_if::cfg_if! {
     } else if #[cfg(target_arch = "x86_64")] {
         /// The system architecture component of a Node distribution tarball's name.
         pub const ARCH: &'static str = "x64";
+    } else if #[cfg(target_arch = "arm")] {
+        /// The system architecture component of a Node distribution tarball's name.
+        pub const ARCH: &'static str = "armv7l";
+    } else if #[cfg(target_arch = "aarch64")] {
+        /// The system architecture component of a Node distribution tarball's name.
+        pub const ARCH: &'static str = "arm64";
+    } else {
+        compile_error!("Unsupported target_arch variant of unix (expected 'x86', 'x64', 'armv7l', or 'arm64').");
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(target_env = "musl")] {
+        /// The libc component of a Node distribution tarball's name, for platforms whose builds
+        /// are only published to the unofficial builds server (https://unofficial-builds.nodejs.org).
+        pub const LIBC: Option<&'static str> = Some("musl");
     } else {
-        compile_error!("Unsupported target_arch variant of unix (expected 'x86' or 'x64').");
+        /// The libc component of a Node distribution tarball's name, for platforms whose builds
+        /// are only published to the unofficial builds server (https://unofficial-builds.nodejs.org).
+        pub const LIBC: Option<&'static str> = None;
     }
 }
 
@@ -46,29 +65,47 @@ cfg_if::cfg_if! {
 //             node/                                       node_cache_dir
 //                 index.json                              node_index_file
 //                 index.json.expires                      node_index_expiry_file
+//             metadata/                                   metadata_cache_dir
 //         bin/                                            shim_dir
 //             node                                        shim_file("node")
 //             yarn
 //             npm
 //             npx
+//             corepack
 //             ...
 //             ember
 //         log/                                            log_dir
 //         tools/                                          tools_dir
 //             inventory/                                  inventory_dir
+//                 bun/                                    bun_inventory_dir
+//                     bun-v1.0.25.zip                     bun_distro_file_name("1.0.25")
+//                 deno/                                   deno_inventory_dir
+//                     deno-v1.40.0.zip                    deno_distro_file_name("1.40.0")
 //                 node/                                   node_inventory_dir
-//                     node-v4.8.4-linux-x64.tar.gz        node_distro_file_name("4.8.4")
+//                     node-v4.8.4-linux-x64.tar.gz        node_distro_file_name("4.8.4", None)
 //                     node-v4.8.4-npm                     node_npm_version_file("4.8.4")
 //                     ...
+//                 node-prerelease/                        node_prerelease_inventory_dir
+//                     node-v20.0.0-rc.2-linux-x64.tar.gz  node_distro_file_name("20.0.0-rc.2", None)
+//                     ...
 //                 packages/                               package_inventory_dir
 //                     ember-cli-3.7.1.tgz                 package_distro_file("ember-cli", "3.7.1")
 //                     ember-cli-3.7.1.shasum              package_distro_shasum("ember-cli", "3.7.1")
 //                 yarn/                                   yarn_inventory_dir
 //             image/                                      image_dir
+//                 bun/                                    bun_image_root_dir
+//                     1.0.25/                             bun_image_dir("1.0.25")
+//                         bin/                            bun_image_bin_dir("1.0.25")
+//                 deno/                                   deno_image_root_dir
+//                     1.40.0/                             deno_image_dir("1.40.0")
+//                         bin/                            deno_image_bin_dir("1.40.0")
 //                 node/                                   node_image_root_dir
 //                     10.13.0/
-//                         6.4.0/                          node_image_dir("10.13.0", "6.4.0")
-//                             bin/                        node_image_bin_dir("10.13.0", "6.4.0")
+//                         6.4.0/                          node_image_dir("10.13.0", "6.4.0", None)
+//                             bin/                        node_image_bin_dir("10.13.0", "6.4.0", None)
+//                 node-x64/
+//                     10.13.0/
+//                         6.4.0/                          node_image_dir("10.13.0", "6.4.0", Some("x64"))
 //                 yarn/                                   yarn_image_root_dir
 //                     1.7.0/                              yarn_image_dir("1.7.0")
 //                 packages/                               package_image_root_dir
@@ -80,9 +117,17 @@ cfg_if::cfg_if! {
 //                 packages/                               user_package_dir
 //                     ember-cli.json                      user_package_config_file("ember-cli")
 //                 platform.json                           user_platform_file
+//             profiles/
+//                 work/                                   (when VOLTA_PROFILE=work)
+//                     image/
+//                         packages/                       package_image_root_dir
+//                     user/                               user_toolchain_dir
+//             shims.json                                  custom_shims_file
 //         volta                                           volta_file
 //         shim                                            shim_executable
 //         hooks.json                                      user_hooks_file
+//         default-packages                                default_packages_file
+//         layout                                          (layout version, see `migrate`)
 
 pub fn default_volta_home() -> Fallible<PathBuf> {
     let home = dirs::home_dir().ok_or(ErrorDetails::NoHomeEnvironmentVar)?;
@@ -93,12 +138,12 @@ pub fn archive_extension() -> String {
     String::from("tar.gz")
 }
 
-pub fn node_image_bin_dir(node: &str, npm: &str) -> Fallible<PathBuf> {
-    Ok(node_image_dir(node, npm)?.join("bin"))
+pub fn node_image_bin_dir(node: &str, npm: &str, arch: Option<&str>) -> Fallible<PathBuf> {
+    Ok(node_image_dir(node, npm, arch)?.join("bin"))
 }
 
-pub fn node_archive_npm_package_json_path(version: &str) -> PathBuf {
-    Path::new(&node_archive_root_dir_name(version))
+pub fn node_archive_npm_package_json_path(version: &str, arch: Option<&str>) -> PathBuf {
+    Path::new(&node_archive_root_dir_name(version, arch))
         .join("lib")
         .join("node_modules")
         .join("npm")
@@ -125,3 +170,20 @@ pub fn env_paths() -> Fallible<Vec<PathBuf>> {
 pub fn create_file_symlink(src: PathBuf, dst: PathBuf) -> Result<(), io::Error> {
     unix::fs::symlink(src, dst)
 }
+
+/// Create a symlink to a directory. The `dst` path will be a symbolic link pointing to the `src`
+/// directory.
+pub fn create_dir_symlink(src: PathBuf, dst: PathBuf) -> Result<(), io::Error> {
+    unix::fs::symlink(src, dst)
+}
+
+/// Remove a symlink to a directory, leaving the directory it points to alone.
+pub fn remove_dir_symlink(link: &Path) -> Result<(), io::Error> {
+    fs::remove_file(link)
+}
+
+/// Converts a path to the form that allows it to be longer than the platform's usual limit. Unix
+/// has no such form, so the path is used as it is.
+pub fn extended_length_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
@@ -1,10 +1,12 @@
 //! Provides functions for determining the paths of files and directories
 //! in a standard Volta layout in Windows operating systems.
 
+use std::ffi::OsString;
+use std::fs;
 use std::io;
 #[cfg(windows)]
 use std::os::windows;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf, Prefix};
 
 use crate::error::ErrorDetails;
 use cfg_if::cfg_if;
@@ -29,33 +31,54 @@ cfg_if! {
     }
 }
 
+/// Official Node builds are published for all supported Windows platforms.
+pub const LIBC: Option<&'static str> = None;
+
 // C:\Users\johndoe\AppData\Local\
 //     Volta\
 //         cache\                                          cache_dir
 //             node\                                       node_cache_dir
 //                 index.json                              node_index_file
 //                 index.json.expires                      node_index_expiry_file
+//             metadata\                                   metadata_cache_dir
 //         bin\                                            shim_dir
 //             node.exe                                    shim_file("node")
 //             npm.exe
 //             npx.exe
+//             corepack.exe
 //             ...
 //         log\                                            log_dir
 //         tools\                                          tools_dir
 //             inventory\                                  inventory_dir
+//                 bun\                                    bun_inventory_dir
+//                     bun-v1.0.25.zip                     bun_distro_file_name("1.0.25")
+//                 deno\                                   deno_inventory_dir
+//                     deno-v1.40.0.zip                    deno_distro_file_name("1.40.0")
 //                 node\                                   node_inventory_dir
-//                     node-v4.8.4-win-x64.zip             node_archive_file("4.8.4")
+//                     node-v4.8.4-win-x64.zip             node_distro_file_name("4.8.4", None)
 //                     node-v4.8.4-npm                     node_npm_version_file("4.8.4")
 //                     ...
+//                 node-prerelease\                        node_prerelease_inventory_dir
+//                     node-v20.0.0-rc.2-win-x64.zip       node_distro_file_name("20.0.0-rc.2", None)
+//                     ...
 //                 packages\                               package_inventory_dir
 //                     ember-cli-3.7.1.tgz                 package_distro_file("ember-cli", "3.7.1")
 //                     ember-cli-3.7.1.shasum              package_distro_shasum("ember-cli", "3.7.1")
 //                 yarn\                                   yarn_inventory_dir
 //             image\                                      image_dir
+//                 bun\                                    bun_image_root_dir
+//                     1.0.25\                             bun_image_dir("1.0.25")
+//                         bin\                            bun_image_bin_dir("1.0.25")
+//                 deno\                                   deno_image_root_dir
+//                     1.40.0\                             deno_image_dir("1.40.0")
+//                         bin\                            deno_image_bin_dir("1.40.0")
 //                 node\                                   node_image_root_dir
 //                     10.13.0\
-//                         6.4.0\                          node_image_dir("10.13.0", "6.4.0")
-//                                                         node_image_bin_dir("10.13.0", "6.4.0")
+//                         6.4.0\                          node_image_dir("10.13.0", "6.4.0", None)
+//                                                         node_image_bin_dir("10.13.0", "6.4.0", None)
+//                 node-x64\
+//                     10.13.0\
+//                         6.4.0\                          node_image_dir("10.13.0", "6.4.0", Some("x64"))
 //                 yarn\                                   yarn_image_root_dir
 //                     1.7.0\                              yarn_image_dir("1.7.0")
 //                 packages\                               package_image_root_dir
@@ -67,7 +90,15 @@ cfg_if! {
 //                 packages\                               user_package_dir
 //                     ember-cli.json                      user_package_config_file("ember-cli")
 //                 platform.json                           user_platform_file
+//             profiles\
+//                 work\                                   (when VOLTA_PROFILE=work)
+//                     image\
+//                         packages\                       package_image_root_dir
+//                     user\                               user_toolchain_dir
+//             shims.json                                  custom_shims_file
 //         hooks.json                                      user_hooks_file
+//         default-packages                                default_packages_file
+//         layout                                          (layout version, see `migrate`)
 //
 // C:\Program Files\
 //     Volta\                                             (Path stored in Windows Registry by installer)
@@ -77,6 +108,7 @@ cfg_if! {
 //             npm.exe                                     copy of shim_executable
 //             npx.exe                                     copy of shim_executable
 //             yarn.exe                                    copy of shim_executable
+//             corepack.exe                                copy of shim_executable
 //         shim.exe                                        shim_executable
 
 pub fn default_volta_home() -> Fallible<PathBuf> {
@@ -88,12 +120,12 @@ pub fn archive_extension() -> String {
     String::from("zip")
 }
 
-pub fn node_image_bin_dir(node: &str, npm: &str) -> Fallible<PathBuf> {
-    node_image_dir(node, npm)
+pub fn node_image_bin_dir(node: &str, npm: &str, arch: Option<&str>) -> Fallible<PathBuf> {
+    node_image_dir(node, npm, arch)
 }
 
-pub fn node_archive_npm_package_json_path(version: &str) -> PathBuf {
-    Path::new(&node_archive_root_dir_name(version))
+pub fn node_archive_npm_package_json_path(version: &str, arch: Option<&str>) -> PathBuf {
+    Path::new(&node_archive_root_dir_name(version, arch))
         .join("node_modules")
         .join("npm")
         .join("package.json")
@@ -119,7 +151,7 @@ cfg_if::cfg_if! {
         fn install_dir() -> Fallible<PathBuf> {
             let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
             let volta_key = hklm.open_subkey(VOLTA_REGISTRY_PATH).with_context(install_dir_error)?;
-            let install_path: String = volta_key.get_value(VOLTA_INSTALL_DIR).with_context(install_dir_error)?;
+            let install_path: OsString = volta_key.get_value(VOLTA_INSTALL_DIR).with_context(install_dir_error)?;
             Ok(PathBuf::from(install_path))
         }
         fn install_dir_error(_err: &io::Error) -> ErrorDetails {
@@ -161,3 +193,97 @@ pub fn create_file_symlink(src: PathBuf, dst: PathBuf) -> Result<(), io::Error>
     #[cfg(feature = "cross-platform-docs")]
     unimplemented!()
 }
+
+/// Create a symlink to a directory. The `dst` path will be a symbolic link pointing to the `src`
+/// directory.
+pub fn create_dir_symlink(src: PathBuf, dst: PathBuf) -> Result<(), io::Error> {
+    #[cfg(windows)]
+    return windows::fs::symlink_dir(src, dst);
+
+    // Cross-platform docs are built on a Unix machine so we can't use Windows APIs.
+    #[cfg(feature = "cross-platform-docs")]
+    unimplemented!()
+}
+
+/// Remove a symlink to a directory, leaving the directory it points to alone. Windows removes
+/// directory symlinks as directories rather than as files.
+pub fn remove_dir_symlink(link: &Path) -> Result<(), io::Error> {
+    fs::remove_dir(link)
+}
+
+/// Converts an absolute path to an extended-length path (e.g. `\\?\C:\Users\...`), which may be
+/// longer than `MAX_PATH`. Windows doesn't normalize extended-length paths, so `.` and `..` are
+/// resolved here. Relative paths can't be extended, so they are used as they are.
+pub fn extended_length_path(path: &Path) -> PathBuf {
+    let mut components = path.components();
+    let mut extended = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut extended = OsString::from(r"\\?\");
+                extended.push(prefix.as_os_str());
+                PathBuf::from(extended)
+            }
+            Prefix::UNC(server, share) => {
+                let mut extended = OsString::from(r"\\?\UNC\");
+                extended.push(server);
+                extended.push(r"\");
+                extended.push(share);
+                PathBuf::from(extended)
+            }
+            // Verbatim and device paths are already passed to Windows as they are
+            _ => return path.to_path_buf(),
+        },
+        _ => return path.to_path_buf(),
+    };
+
+    for component in components {
+        match component {
+            Component::RootDir => extended.push(r"\"),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                extended.pop();
+            }
+            Component::Normal(name) => extended.push(name),
+            Component::Prefix(_) => unreachable!("a prefix can only be the first component"),
+        }
+    }
+
+    extended
+}
+
+#[cfg(all(test, windows))]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn extends_disk_paths() {
+        assert_eq!(
+            extended_length_path(Path::new(r"C:\Users\volta\..\Volta\.\tmp")),
+            PathBuf::from(r"\\?\C:\Users\Volta\tmp")
+        );
+        assert_eq!(
+            extended_length_path(Path::new("C:/Users/volta")),
+            PathBuf::from(r"\\?\C:\Users\volta")
+        );
+    }
+
+    #[test]
+    fn extends_unc_paths() {
+        assert_eq!(
+            extended_length_path(Path::new(r"\\server\share\Volta")),
+            PathBuf::from(r"\\?\UNC\server\share\Volta")
+        );
+    }
+
+    #[test]
+    fn keeps_verbatim_and_relative_paths() {
+        assert_eq!(
+            extended_length_path(Path::new(r"\\?\C:\Volta")),
+            PathBuf::from(r"\\?\C:\Volta")
+        );
+        assert_eq!(
+            extended_length_path(Path::new(r"Volta\tmp")),
+            PathBuf::from(r"Volta\tmp")
+        );
+    }
+}
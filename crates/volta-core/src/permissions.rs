@@ -0,0 +1,169 @@
+//! Applies the permission policy to the images, shims, and config files that Volta installs, so
+//! that they can be used by the right users whatever the umask of the user who installed them.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::env::INSTALL_PERMISSIONS;
+use crate::error::ErrorDetails;
+use crate::hook::{HookConfig, InstallPermissions};
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// Reads the policy from `VOLTA_INSTALL_PERMISSIONS`, or from `installPermissions` in the `volta`
+/// section of the user hooks. Without either, files are left with the permissions they were
+/// created with.
+pub(crate) fn policy() -> Fallible<Option<InstallPermissions>> {
+    match env::var(INSTALL_PERMISSIONS) {
+        Ok(ref value) if value == "shared" => Ok(Some(InstallPermissions::Shared)),
+        Ok(ref value) if value == "private" => Ok(Some(InstallPermissions::Private)),
+        Ok(ref value) if value.is_empty() => HookConfig::user_install_permissions(),
+        Ok(value) => throw!(ErrorDetails::InvalidInstallPermissions { value }),
+        Err(_) => HookConfig::user_install_permissions(),
+    }
+}
+
+/// Sets the permissions of a newly written file or directory, but not of anything in it.
+pub(crate) fn apply(path: &Path) -> Fallible<()> {
+    apply_with(path, false)
+}
+
+/// Sets the permissions of a newly unpacked image and of everything in it.
+pub(crate) fn apply_to_tree(dir: &Path) -> Fallible<()> {
+    apply_with(dir, true)
+}
+
+fn apply_with(path: &Path, recursive: bool) -> Fallible<()> {
+    if let Some(policy) = policy()? {
+        set_permissions(path, policy, recursive).with_context(|_| {
+            ErrorDetails::SetPermissionsError {
+                path: path.to_path_buf(),
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Sets the mode of a file or directory from the policy, keeping files executable if they were.
+/// Links are left alone, since their permissions are those of what they link to.
+#[cfg(unix)]
+fn set_permissions(path: &Path, policy: InstallPermissions, recursive: bool) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = fs::symlink_metadata(path)?;
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        return Ok(());
+    }
+
+    let executable = file_type.is_dir() || metadata.permissions().mode() & 0o111 != 0;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode(policy, executable)))?;
+
+    // The directory is set first, so that it can be read even if it wasn't readable before
+    if file_type.is_dir() && recursive {
+        for entry in fs::read_dir(path)? {
+            set_permissions(&entry?.path(), policy, true)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Files on Windows get their access from the directory they are created in, so there's nothing
+/// to set.
+#[cfg(windows)]
+fn set_permissions(_path: &Path, _policy: InstallPermissions, _recursive: bool) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn mode(policy: InstallPermissions, executable: bool) -> u32 {
+    match (policy, executable) {
+        (InstallPermissions::Shared, true) => 0o755,
+        (InstallPermissions::Shared, false) => 0o644,
+        (InstallPermissions::Private, true) => 0o700,
+        (InstallPermissions::Private, false) => 0o600,
+    }
+}
+
+#[cfg(all(test, unix))]
+pub mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use tempfile::tempdir;
+
+    fn mode_of(path: &Path) -> u32 {
+        fs::symlink_metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    fn set_mode(path: &Path, mode: u32) {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn shares_trees() {
+        let image = tempdir().unwrap();
+        let bin_dir = image.path().join("bin");
+        create_dir_all(&bin_dir).unwrap();
+        write(bin_dir.join("node"), "node").unwrap();
+        write(image.path().join("README.md"), "readme").unwrap();
+        set_mode(&bin_dir, 0o700);
+        set_mode(&bin_dir.join("node"), 0o770);
+        set_mode(&image.path().join("README.md"), 0o666);
+
+        set_permissions(image.path(), InstallPermissions::Shared, true).unwrap();
+
+        assert_eq!(mode_of(image.path()), 0o755);
+        assert_eq!(mode_of(&bin_dir), 0o755);
+        assert_eq!(mode_of(&bin_dir.join("node")), 0o755);
+        assert_eq!(mode_of(&image.path().join("README.md")), 0o644);
+    }
+
+    #[test]
+    fn keeps_private_trees_to_the_owner() {
+        let image = tempdir().unwrap();
+        let bin_dir = image.path().join("bin");
+        create_dir_all(&bin_dir).unwrap();
+        write(bin_dir.join("node"), "node").unwrap();
+        write(image.path().join("README.md"), "readme").unwrap();
+        set_mode(&bin_dir.join("node"), 0o755);
+        set_mode(&image.path().join("README.md"), 0o644);
+
+        set_permissions(image.path(), InstallPermissions::Private, true).unwrap();
+
+        assert_eq!(mode_of(image.path()), 0o700);
+        assert_eq!(mode_of(&bin_dir), 0o700);
+        assert_eq!(mode_of(&bin_dir.join("node")), 0o700);
+        assert_eq!(mode_of(&image.path().join("README.md")), 0o600);
+    }
+
+    #[test]
+    fn leaves_contents_alone_unless_recursive() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("platform.json");
+        write(&file, "{}").unwrap();
+        set_mode(&file, 0o666);
+
+        set_permissions(dir.path(), InstallPermissions::Shared, false).unwrap();
+
+        assert_eq!(mode_of(dir.path()), 0o755);
+        assert_eq!(mode_of(&file), 0o666);
+    }
+
+    #[test]
+    fn skips_links() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("volta-shim");
+        let link = dir.path().join("node");
+        write(&target, "shim").unwrap();
+        set_mode(&target, 0o775);
+        symlink(&target, &link).unwrap();
+
+        set_permissions(&link, InstallPermissions::Private, false).unwrap();
+
+        assert_eq!(mode_of(&target), 0o775);
+    }
+}
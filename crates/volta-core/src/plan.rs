@@ -0,0 +1,192 @@
+//! Provides the changes that a command would make, so that `volta --dry-run` can list them
+//! instead of making them.
+//!
+//! Installing Node, Yarn, Deno, or Bun lists its changes whether or not it is a dry run, and
+//! then either prints them or makes them (see `Session::apply`), so that what is listed is what
+//! is done.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::style::tool_version;
+use semver::Version;
+
+/// A single change that a command would make.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    /// Fetching a version of a tool into its inventory directory and unpacking its image, for
+    /// the specified architecture of Node or for the native one.
+    Fetch {
+        tool: String,
+        version: Version,
+        arch: Option<String>,
+        into: PathBuf,
+        image: PathBuf,
+    },
+    /// Creating the shim for an executable.
+    CreateShim { name: String, file: PathBuf },
+    /// Saving a version of a tool as the default in the user's platform file, for the specified
+    /// architecture of Node or for the native one.
+    SetDefault {
+        tool: String,
+        version: Version,
+        arch: Option<String>,
+        file: PathBuf,
+    },
+    /// Downloading a tool into the inventory, from a URL if it is known before downloading.
+    Download {
+        tool: String,
+        url: Option<String>,
+        into: PathBuf,
+    },
+    /// Writing a file, or unpacking into a directory.
+    Write(PathBuf),
+    /// Removing a file or directory.
+    Remove(PathBuf),
+    /// Setting a key in a project's manifest, or removing it if there is no value.
+    SetKey {
+        file: PathBuf,
+        key: String,
+        value: Option<String>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Fetch {
+                tool,
+                version,
+                arch,
+                into,
+                image,
+            } => write!(
+                f,
+                "download {} into {} and unpack it into {}",
+                describe_tool(tool, version, arch),
+                into.display(),
+                image.display()
+            ),
+            Change::CreateShim { file, .. } => write!(f, "write {}", file.display()),
+            Change::SetDefault {
+                tool,
+                version,
+                arch,
+                file,
+            } => write!(
+                f,
+                "set {} as the default in {}",
+                describe_tool(tool, version, arch),
+                file.display()
+            ),
+            Change::Download {
+                tool,
+                url: Some(url),
+                into,
+            } => write!(f, "download {} from {} into {}", tool, url, into.display()),
+            Change::Download {
+                tool,
+                url: None,
+                into,
+            } => write!(f, "download {} into {}", tool, into.display()),
+            Change::Write(file) => write!(f, "write {}", file.display()),
+            Change::Remove(file) => write!(f, "remove {}", file.display()),
+            Change::SetKey {
+                file,
+                key,
+                value: Some(value),
+            } => write!(f, "set `{}` to \"{}\" in {}", key, value, file.display()),
+            Change::SetKey {
+                file,
+                key,
+                value: None,
+            } => write!(f, "remove `{}` from {}", key, file.display()),
+        }
+    }
+}
+
+/// Describes a version of a tool, along with its architecture if it isn't the native one.
+pub(crate) fn describe_tool(tool: &str, version: &Version, arch: &Option<String>) -> String {
+    match arch {
+        Some(arch) => format!("{} ({})", tool_version(tool, version), arch),
+        None => tool_version(tool, version),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::Change;
+    use semver::Version;
+    use std::path::PathBuf;
+
+    #[test]
+    fn describes_changes() {
+        let download = Change::Download {
+            tool: "ember-cli@3.10.0".to_string(),
+            url: Some("https://registry.npmjs.org/ember-cli-3.10.0.tgz".to_string()),
+            into: PathBuf::from("/volta/ember-cli-3.10.0.tgz"),
+        };
+        assert_eq!(
+            download.to_string(),
+            "download ember-cli@3.10.0 from https://registry.npmjs.org/ember-cli-3.10.0.tgz into /volta/ember-cli-3.10.0.tgz"
+        );
+
+        let download = Change::Download {
+            tool: "node@10.16.0".to_string(),
+            url: None,
+            into: PathBuf::from("/volta/inventory/node"),
+        };
+        assert_eq!(
+            download.to_string(),
+            "download node@10.16.0 into /volta/inventory/node"
+        );
+
+        let pin = Change::SetKey {
+            file: PathBuf::from("/project/package.json"),
+            key: "volta.node".to_string(),
+            value: Some("10.16.0".to_string()),
+        };
+        assert_eq!(
+            pin.to_string(),
+            "set `volta.node` to \"10.16.0\" in /project/package.json"
+        );
+
+        let unpin = Change::SetKey {
+            file: PathBuf::from("/project/package.json"),
+            key: "volta.npm".to_string(),
+            value: None,
+        };
+        assert_eq!(
+            unpin.to_string(),
+            "remove `volta.npm` from /project/package.json"
+        );
+
+        assert_eq!(
+            Change::Remove(PathBuf::from("/volta/bin/ember")).to_string(),
+            "remove /volta/bin/ember"
+        );
+
+        let fetch = Change::Fetch {
+            tool: "node".to_string(),
+            version: Version::parse("10.16.0").unwrap(),
+            arch: Some("x64".to_string()),
+            into: PathBuf::from("/volta/inventory/node"),
+            image: PathBuf::from("/volta/image/node/10.16.0-x64"),
+        };
+        assert_eq!(
+            fetch.to_string(),
+            "download node@10.16.0 (x64) into /volta/inventory/node and unpack it into /volta/image/node/10.16.0-x64"
+        );
+
+        let default = Change::SetDefault {
+            tool: "yarn".to_string(),
+            version: Version::parse("1.17.3").unwrap(),
+            arch: None,
+            file: PathBuf::from("/volta/tools/user/platform.json"),
+        };
+        assert_eq!(
+            default.to_string(),
+            "set yarn@1.17.3 as the default in /volta/tools/user/platform.json"
+        );
+    }
+}
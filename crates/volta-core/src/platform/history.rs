@@ -0,0 +1,128 @@
+//! Remembers the platform that shims last ran with in each project, so that Volta can notice when
+//! it changes.
+
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, remove_file, write, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use log::debug;
+
+use crate::fs::ensure_containing_dir_exists;
+use crate::path;
+use volta_fail::Fallible;
+
+/// The platforms that shims last ran with, by project root.
+type ProjectPlatforms = BTreeMap<String, String>;
+
+/// How long the record can be locked before the lock is assumed to have been abandoned.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Records the platform that a shim is running with in a project, returning the platform that the
+/// last shim to run in the project used, if it was different. Projects that haven't been seen
+/// before have nothing to compare with, so their platform is only recorded.
+pub(crate) fn record_project_platform(
+    project_root: &Path,
+    platform: &str,
+) -> Fallible<Option<String>> {
+    if path::is_read_only_home() {
+        return Ok(None);
+    }
+
+    let file = path::project_platforms_file()?;
+    // Another shim that is updating the record at the same time is left to it, rather than waited
+    // for, since the record is only a cache
+    let _lock = match RecordLock::acquire(&file) {
+        Some(lock) => lock,
+        None => {
+            debug!("Not recording the platform of the project, since the record is locked");
+            return Ok(None);
+        }
+    };
+
+    // The record is only a cache, so a missing or corrupt file is started over
+    let mut platforms: ProjectPlatforms = read_to_string(&file)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    let previous = platforms.insert(
+        project_root.to_string_lossy().into_owned(),
+        platform.to_string(),
+    );
+    if previous.as_ref().map(String::as_str) == Some(platform) {
+        return Ok(None);
+    }
+
+    // Failing to write the record shouldn't stop the tool from running
+    let written = ensure_containing_dir_exists(&file)
+        .ok()
+        .and_then(|_| serde_json::to_string_pretty(&platforms).ok())
+        .and_then(|json| write(&file, json).ok());
+    if written.is_none() {
+        debug!(
+            "Could not record the platform of the project in {}",
+            file.display()
+        );
+    }
+
+    Ok(previous)
+}
+
+/// An exclusive lock on the record, which is released when it is dropped.
+struct RecordLock {
+    file: PathBuf,
+}
+
+impl RecordLock {
+    /// Takes the lock, unless another process holds it.
+    fn acquire(record: &Path) -> Option<Self> {
+        let mut file = record.as_os_str().to_owned();
+        file.push(".lock");
+        let file = PathBuf::from(file);
+        ensure_containing_dir_exists(&file).ok()?;
+
+        if is_stale(&file) {
+            debug!("Taking over the stale lock {}", file.display());
+            let _ = remove_file(&file);
+        }
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&file)
+            .ok()
+            .map(|_| RecordLock { file })
+    }
+}
+
+impl Drop for RecordLock {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.file);
+    }
+}
+
+fn is_stale(lock: &Path) -> bool {
+    lock.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map_or(false, |age| age >= LOCK_TIMEOUT)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::RecordLock;
+    use tempfile::tempdir;
+
+    #[test]
+    fn only_one_shim_holds_the_lock() {
+        let dir = tempdir().unwrap();
+        let record = dir.path().join("project-platforms.json");
+
+        let lock = RecordLock::acquire(&record).expect("lock is free");
+        assert!(RecordLock::acquire(&record).is_none());
+
+        drop(lock);
+        assert!(RecordLock::acquire(&record).is_some());
+    }
+}
@@ -1,6 +1,8 @@
 use std::env::JoinPathsError;
 use std::ffi::OsString;
+use std::fmt;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 use envoy;
 use semver::Version;
@@ -9,8 +11,12 @@ use crate::distro::node::{load_default_npm_version, NodeVersion};
 use crate::error::ErrorDetails;
 use crate::path;
 use crate::session::Session;
+use crate::trace;
 use volta_fail::{Fallible, ResultExt};
 
+pub(crate) mod history;
+pub(crate) mod prefetch;
+
 /// A specification of tool versions needed for a platform
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct PlatformSpec {
@@ -20,11 +26,21 @@ pub struct PlatformSpec {
     pub npm: Option<Version>,
     /// The pinned version of Yarn, if any.
     pub yarn: Option<Version>,
+    /// The architecture of Node to use, if not the native one.
+    pub arch: Option<String>,
 }
 
 impl PlatformSpec {
     pub fn checkout(&self, session: &mut Session) -> Fallible<Image> {
-        session.ensure_node(&self.node_runtime)?;
+        let _span = trace::span("checkout platform");
+        session.ensure_node(&self.node_runtime, self.arch.as_ref().map(String::as_str))?;
+
+        let bundled_npm = load_default_npm_version(&self.node_runtime)?;
+        if let Some(ref npm_version) = self.npm {
+            if *npm_version != bundled_npm {
+                session.ensure_npm(npm_version)?;
+            }
+        }
 
         if let Some(ref yarn_version) = self.yarn {
             session.ensure_yarn(yarn_version)?;
@@ -33,31 +49,200 @@ impl PlatformSpec {
         Ok(Image {
             node: NodeVersion {
                 runtime: self.node_runtime.clone(),
-                npm: match self.npm {
-                    Some(ref version) => version.clone(),
-                    None => load_default_npm_version(&self.node_runtime)?,
-                },
+                npm: self.npm.clone().unwrap_or_else(|| bundled_npm.clone()),
+                arch: self.arch.clone(),
+            },
+            bundled_npm,
+            yarn: self.yarn.clone(),
+        })
+    }
+
+    /// Resolves the image for this platform without fetching anything. Returns `None` if the
+    /// version of npm bundled with its Node hasn't been fetched.
+    pub fn resolve_image(&self) -> Option<Image> {
+        let bundled_npm = load_default_npm_version(&self.node_runtime).ok()?;
+
+        Some(Image {
+            node: NodeVersion {
+                runtime: self.node_runtime.clone(),
+                npm: self.npm.clone().unwrap_or_else(|| bundled_npm.clone()),
+                arch: self.arch.clone(),
             },
+            bundled_npm,
             yarn: self.yarn.clone(),
         })
     }
 }
 
+impl fmt::Display for PlatformSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "node@{}", self.node_runtime)?;
+        if let Some(ref arch) = self.arch {
+            write!(f, " ({})", arch)?;
+        }
+        if let Some(ref npm) = self.npm {
+            write!(f, " npm@{}", npm)?;
+        }
+        if let Some(ref yarn) = self.yarn {
+            write!(f, " yarn@{}", yarn)?;
+        }
+        Ok(())
+    }
+}
+
+/// The versions of npm and Yarn that a project pins without pinning Node. They can't make up a
+/// platform on their own, so they are merged with the user's default platform instead (see
+/// `MergedPlatform`).
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct PartialPlatformSpec {
+    /// The pinned version of npm, if any.
+    pub npm: Option<Version>,
+    /// The pinned version of Yarn, if any.
+    pub yarn: Option<Version>,
+}
+
+impl fmt::Display for PartialPlatformSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tools: Vec<String> = self
+            .npm
+            .iter()
+            .map(|npm| format!("npm@{}", npm))
+            .chain(self.yarn.iter().map(|yarn| format!("yarn@{}", yarn)))
+            .collect();
+        f.write_str(&tools.join(" "))
+    }
+}
+
+/// Where the version of a tool in the current platform comes from.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Source {
+    /// The project pins the tool.
+    Project,
+    /// The user's default platform provides the tool.
+    Default,
+}
+
+/// The platform that tools run with, where each tool that the project pins overlays the user's
+/// default platform, along with where each of its tools comes from.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct MergedPlatform {
+    pub spec: Rc<PlatformSpec>,
+    pub node: Source,
+    pub npm: Source,
+    pub yarn: Source,
+}
+
+impl MergedPlatform {
+    /// Merges the tools that a project pins with the user's default platform, per tool:
+    ///
+    /// - Node (along with its architecture) comes from the project if it pins Node, and otherwise
+    ///   from the default platform.
+    /// - npm comes from the project if it pins npm. Otherwise, it is the version bundled with the
+    ///   project's Node, since versions of npm only support some versions of Node, or the
+    ///   default npm if Node also comes from the default platform.
+    /// - Yarn comes from the project if it pins Yarn, and otherwise from the default platform.
+    ///
+    /// A tool that neither sets is attributed to wherever Node comes from.
+    pub fn merge(
+        project: Option<Rc<PlatformSpec>>,
+        partial: Option<&PartialPlatformSpec>,
+        default: Option<Rc<PlatformSpec>>,
+    ) -> Option<MergedPlatform> {
+        match (project, default) {
+            (Some(project), default) => {
+                let default_yarn = default.and_then(|default| default.yarn.clone());
+                match (&project.yarn, default_yarn) {
+                    (None, Some(yarn)) => Some(MergedPlatform {
+                        spec: Rc::new(PlatformSpec {
+                            yarn: Some(yarn),
+                            ..(*project).clone()
+                        }),
+                        node: Source::Project,
+                        npm: Source::Project,
+                        yarn: Source::Default,
+                    }),
+                    _ => Some(MergedPlatform {
+                        spec: project,
+                        node: Source::Project,
+                        npm: Source::Project,
+                        yarn: Source::Project,
+                    }),
+                }
+            }
+            (None, Some(default)) => {
+                let partial = match partial {
+                    Some(partial) => partial,
+                    None => {
+                        return Some(MergedPlatform {
+                            spec: default,
+                            node: Source::Default,
+                            npm: Source::Default,
+                            yarn: Source::Default,
+                        })
+                    }
+                };
+                let source = |pinned: &Option<Version>| match pinned {
+                    Some(_) => Source::Project,
+                    None => Source::Default,
+                };
+                Some(MergedPlatform {
+                    spec: Rc::new(PlatformSpec {
+                        npm: partial.npm.clone().or_else(|| default.npm.clone()),
+                        yarn: partial.yarn.clone().or_else(|| default.yarn.clone()),
+                        ..(*default).clone()
+                    }),
+                    node: Source::Default,
+                    npm: source(&partial.npm),
+                    yarn: source(&partial.yarn),
+                })
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Determines whether every tool comes from the same place.
+    pub fn is_uniform(&self) -> bool {
+        self.npm == self.node && self.yarn == self.node
+    }
+}
+
 /// A platform image.
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct Image {
-    /// The pinned version of Node.
+    /// The pinned version of Node, along with the version of npm to use with it.
     pub node: NodeVersion,
+    /// The version of npm bundled with Node, which its image is kept under.
+    pub bundled_npm: Version,
     /// The pinned version of Yarn, if any.
     pub yarn: Option<Version>,
 }
 
 impl Image {
+    /// The directory that Node is unpacked into.
+    pub fn node_dir(&self) -> Fallible<PathBuf> {
+        path::node_image_dir(
+            &self.node.runtime.to_string(),
+            &self.bundled_npm.to_string(),
+            self.node.arch.as_ref().map(String::as_str),
+        )
+    }
+
+    /// The directories of the image's executables, in the order they go on the `PATH`. A pinned
+    /// npm other than the one bundled with Node comes first, so that `npm`, `npx`, and the man
+    /// pages next to them are all found in it rather than in Node's directory.
     pub fn bins(&self) -> Fallible<Vec<PathBuf>> {
         let node_str = self.node.runtime.to_string();
-        let npm_str = self.node.npm.to_string();
-        // ISSUE(#292): Install npm, and handle using that
-        let mut bins = vec![path::node_image_bin_dir(&node_str, &npm_str)?];
+        let arch_str = self.node.arch.as_ref().map(String::as_str);
+
+        let mut bins = Vec::new();
+        if self.node.npm != self.bundled_npm {
+            bins.push(path::npm_image_bin_dir(&self.node.npm.to_string())?);
+        }
+        bins.push(path::node_image_bin_dir(
+            &node_str,
+            &self.bundled_npm.to_string(),
+            arch_str,
+        )?);
         if let Some(ref yarn) = self.yarn {
             let yarn_str = yarn.to_string();
             bins.push(path::yarn_image_bin_dir(&yarn_str)?);
@@ -69,6 +254,7 @@ impl Image {
     /// will find toolchain executables (Node, Yarn) in the installation directories
     /// for the given versions instead of in the Volta shim directory.
     pub fn path(&self) -> Fallible<OsString> {
+        let _span = trace::span("build image path");
         let old_path = envoy::path().unwrap_or(envoy::Var::from(""));
         let mut new_path = old_path.split();
 
@@ -135,6 +321,74 @@ mod test {
 
     // Since unit tests are run in parallel, tests that modify the PATH environment variable are subject to race conditions
     // To prevent that, ensure that all tests that rely on PATH are run in serial by adding them to this meta-test
+    #[test]
+    fn test_display() {
+        let platform = PlatformSpec {
+            node_runtime: Version::parse("12.4.0").unwrap(),
+            npm: None,
+            yarn: Some(Version::parse("1.16.0").unwrap()),
+            arch: None,
+        };
+        assert_eq!(platform.to_string(), "node@12.4.0 yarn@1.16.0");
+
+        let platform = PlatformSpec {
+            node_runtime: Version::parse("12.4.0").unwrap(),
+            npm: Some(Version::parse("6.9.0").unwrap()),
+            yarn: None,
+            arch: Some("x64".to_string()),
+        };
+        assert_eq!(platform.to_string(), "node@12.4.0 (x64) npm@6.9.0");
+    }
+
+    fn spec(node: &str, npm: Option<&str>, yarn: Option<&str>) -> Rc<PlatformSpec> {
+        Rc::new(PlatformSpec {
+            node_runtime: Version::parse(node).unwrap(),
+            npm: npm.map(|npm| Version::parse(npm).unwrap()),
+            yarn: yarn.map(|yarn| Version::parse(yarn).unwrap()),
+            arch: None,
+        })
+    }
+
+    #[test]
+    fn test_merge() {
+        let default = spec("18.16.0", Some("9.6.7"), Some("1.22.19"));
+
+        // The project's Node keeps its bundled npm, but Yarn comes from the default
+        let merged = MergedPlatform::merge(
+            Some(spec("16.20.0", None, None)),
+            None,
+            Some(default.clone()),
+        )
+        .unwrap();
+        assert_eq!(*merged.spec, *spec("16.20.0", None, Some("1.22.19")));
+        assert_eq!(merged.node, Source::Project);
+        assert_eq!(merged.npm, Source::Project);
+        assert_eq!(merged.yarn, Source::Default);
+        assert!(!merged.is_uniform());
+
+        // Yarn pinned without Node overlays the default platform
+        let partial = PartialPlatformSpec {
+            npm: None,
+            yarn: Some(Version::parse("1.19.0").unwrap()),
+        };
+        assert_eq!(partial.to_string(), "yarn@1.19.0");
+        let merged = MergedPlatform::merge(None, Some(&partial), Some(default.clone())).unwrap();
+        assert_eq!(
+            *merged.spec,
+            *spec("18.16.0", Some("9.6.7"), Some("1.19.0"))
+        );
+        assert_eq!(merged.node, Source::Default);
+        assert_eq!(merged.npm, Source::Default);
+        assert_eq!(merged.yarn, Source::Project);
+
+        // Without a default platform, there is no Node to run the pinned Yarn with
+        assert_eq!(MergedPlatform::merge(None, Some(&partial), None), None);
+
+        let merged = MergedPlatform::merge(None, None, Some(default.clone())).unwrap();
+        assert_eq!(merged.spec, default);
+        assert!(merged.is_uniform());
+    }
+
     #[test]
     fn test_paths() {
         test_image_path();
@@ -179,7 +433,9 @@ mod test {
             node: NodeVersion {
                 runtime: v123.clone(),
                 npm: v643.clone(),
+                arch: None,
             },
+            bundled_npm: v643.clone(),
             yarn: None,
         };
 
@@ -192,7 +448,9 @@ mod test {
             node: NodeVersion {
                 runtime: v123.clone(),
                 npm: v643.clone(),
+                arch: None,
             },
+            bundled_npm: v643.clone(),
             yarn: Some(v457.clone()),
         };
 
@@ -203,6 +461,35 @@ mod test {
                 expected_node_bin, expected_yarn_bin
             ),
         );
+
+        // A pinned npm comes before Node, so that `npm` and `npx` aren't the bundled ones
+        let npm_bin = volta_home()
+            .unwrap()
+            .join("tools")
+            .join("image")
+            .join("packages")
+            .join("npm")
+            .join("8.1.0")
+            .join("bin");
+        let expected_npm_bin = npm_bin.as_path().to_str().unwrap();
+
+        let custom_npm_image = Image {
+            node: NodeVersion {
+                runtime: v123.clone(),
+                npm: Version::parse("8.1.0").unwrap(),
+                arch: None,
+            },
+            bundled_npm: v643.clone(),
+            yarn: Some(v457.clone()),
+        };
+
+        assert_eq!(
+            custom_npm_image.path().unwrap().into_string().unwrap(),
+            format!(
+                "{}:{}:{}:/usr/bin:/blah:/doesnt/matter/bin",
+                expected_npm_bin, expected_node_bin, expected_yarn_bin
+            ),
+        );
     }
 
     #[cfg(windows)]
@@ -246,7 +533,9 @@ mod test {
             node: NodeVersion {
                 runtime: v123.clone(),
                 npm: v643.clone(),
+                arch: None,
             },
+            bundled_npm: v643.clone(),
             yarn: None,
         };
 
@@ -259,7 +548,9 @@ mod test {
             node: NodeVersion {
                 runtime: v123.clone(),
                 npm: v643.clone(),
+                arch: None,
             },
+            bundled_npm: v643.clone(),
             yarn: Some(v457.clone()),
         };
 
@@ -270,6 +561,35 @@ mod test {
                 expected_node_bin, expected_yarn_bin
             ),
         );
+
+        // A pinned npm comes before Node, so that `npm` and `npx` aren't the bundled ones
+        let npm_bin = volta_home()
+            .unwrap()
+            .join("tools")
+            .join("image")
+            .join("packages")
+            .join("npm")
+            .join("8.1.0")
+            .join("bin");
+        let expected_npm_bin = npm_bin.as_path().to_str().unwrap();
+
+        let custom_npm_image = Image {
+            node: NodeVersion {
+                runtime: v123.clone(),
+                npm: Version::parse("8.1.0").unwrap(),
+                arch: None,
+            },
+            bundled_npm: v643.clone(),
+            yarn: Some(v457.clone()),
+        };
+
+        assert_eq!(
+            custom_npm_image.path().unwrap().into_string().unwrap(),
+            format!(
+                "{};{};{};C:\\\\somebin;D:\\\\ProbramFlies",
+                expected_npm_bin, expected_node_bin, expected_yarn_bin
+            ),
+        );
     }
 
     #[cfg(unix)]
@@ -0,0 +1,137 @@
+//! Fetches a project's newly pinned platform in a background process, so that the first command
+//! after someone changes the pins isn't held up while the tools download. Until the platform has
+//! been fetched, shims run with the user's default platform (or from the system `PATH`) instead.
+
+use std::fs::{create_dir_all, remove_file, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+
+use log::debug;
+
+use super::PlatformSpec;
+use crate::command::{create_command, detach};
+use crate::env::{flag_enabled, BACKGROUND_FETCH};
+use crate::hook::HookConfig;
+use crate::path;
+use volta_fail::{Fallible, ResultExt};
+
+/// How long a lock keeps other shims from starting another fetch of the same platform. A fetch
+/// that was killed before it could remove its lock only holds up the next one until then.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Determines whether shims fetch pinned platforms in the background, which is turned on with
+/// `VOLTA_BACKGROUND_FETCH` or with `backgroundFetch` in the `volta` section of the user hooks.
+pub(crate) fn is_enabled() -> Fallible<bool> {
+    if flag_enabled(BACKGROUND_FETCH) {
+        return Ok(true);
+    }
+    Ok(HookConfig::user_background_fetch()?.unwrap_or(false))
+}
+
+/// Starts `volta fetch --pinned` in the project as a detached process, unless another one is
+/// already fetching the platform.
+pub(crate) fn start(project_root: &Path, platform: &PlatformSpec) -> Fallible<()> {
+    let lock = lock_file(platform)?;
+    if !acquire(&lock).unknown()? {
+        debug!("{} is already being fetched in the background", platform);
+        return Ok(());
+    }
+
+    let mut command = create_command(path::volta_file()?);
+    command
+        .args(&["fetch", "--pinned", "--quiet"])
+        .current_dir(project_root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    detach(&mut command);
+
+    debug!("Fetching {} in the background", platform);
+    if let Err(error) = command.spawn() {
+        let _ = remove_file(&lock);
+        return Err(error).unknown();
+    }
+    Ok(())
+}
+
+/// Releases the lock on a platform once it has been fetched, or has failed to be, so that a shim
+/// can try again.
+pub(crate) fn finish(platform: &PlatformSpec) {
+    if let Ok(lock) = lock_file(platform) {
+        let _ = remove_file(lock);
+    }
+}
+
+fn lock_file(platform: &PlatformSpec) -> Fallible<PathBuf> {
+    path::prefetch_lock_file(&lock_name(platform))
+}
+
+/// Names the lock after the platform, e.g. `node@18.16.0-yarn@1.22.19`.
+fn lock_name(platform: &PlatformSpec) -> String {
+    platform
+        .to_string()
+        .chars()
+        .filter(|&c| c != '(' && c != ')')
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// Takes the lock, unless a fetch that started less than `LOCK_TIMEOUT` ago holds it.
+fn acquire(lock: &Path) -> io::Result<bool> {
+    if let Some(dir) = lock.parent() {
+        create_dir_all(dir)?;
+    }
+    if is_stale(lock) {
+        debug!("Taking over the stale lock {}", lock.display());
+        let _ = remove_file(lock);
+    }
+
+    match OpenOptions::new().write(true).create_new(true).open(lock) {
+        Ok(_) => Ok(true),
+        Err(ref error) if error.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+fn is_stale(lock: &Path) -> bool {
+    lock.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map_or(false, |age| age >= LOCK_TIMEOUT)
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::{acquire, lock_name};
+    use crate::platform::PlatformSpec;
+    use semver::Version;
+    use std::fs::remove_file;
+    use tempfile::tempdir;
+
+    #[test]
+    fn names_locks_after_platforms() {
+        let platform = PlatformSpec {
+            node_runtime: Version::parse("18.16.0").unwrap(),
+            npm: None,
+            yarn: Some(Version::parse("1.22.19").unwrap()),
+            arch: Some("x64".to_string()),
+        };
+        assert_eq!(lock_name(&platform), "node@18.16.0-x64-yarn@1.22.19");
+    }
+
+    #[test]
+    fn only_one_fetch_holds_the_lock() {
+        let dir = tempdir().unwrap();
+        let lock = dir.path().join("prefetch").join("node@18.16.0.lock");
+
+        assert!(acquire(&lock).unwrap());
+        assert!(!acquire(&lock).unwrap());
+
+        remove_file(&lock).unwrap();
+        assert!(acquire(&lock).unwrap());
+    }
+}
@@ -0,0 +1,216 @@
+//! Provides the version policy, which an organization can use to restrict the versions of Node and
+//! Yarn that can be installed or pinned, e.g. to LTS versions of Node 16 and above. The policy is
+//! a JSON file that `VOLTA_POLICY`, or `policy` in the `volta` section of the user hooks, points
+//! to:
+//!
+//! ```json
+//! {
+//!     "node": { "versions": ">=16", "lts": true },
+//!     "yarn": { "versions": "^1.22" }
+//! }
+//! ```
+
+use std::env;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+
+use crate::env::POLICY;
+use crate::error::ErrorDetails;
+use crate::hook::HookConfig;
+use crate::version::VersionSpec;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// The versions of each tool that the policy allows.
+pub struct Policy {
+    file: PathBuf,
+    node: ToolPolicy,
+    yarn: ToolPolicy,
+}
+
+#[derive(Default)]
+struct ToolPolicy {
+    /// The range that versions have to be in, if they are restricted to one.
+    versions: Option<Range>,
+    /// Whether only LTS versions are allowed. Only Node has LTS versions.
+    lts: bool,
+}
+
+/// A range of versions, along with how it was written in the policy.
+struct Range {
+    src: String,
+    requirement: VersionReq,
+}
+
+impl Policy {
+    /// Loads the version policy, if one is set.
+    pub fn current() -> Fallible<Option<Self>> {
+        let file = match env::var_os(POLICY) {
+            Some(ref file) if !file.is_empty() => PathBuf::from(file),
+            _ => match HookConfig::user_policy()? {
+                Some(file) => file,
+                None => return Ok(None),
+            },
+        };
+
+        // A policy that can't be read isn't ignored, since it would let anything through
+        let src = read_to_string(&file)
+            .with_context(|_| ErrorDetails::ReadPolicyError { file: file.clone() })?;
+        let serial: serial::Policy = serde_json::from_str(&src)
+            .with_context(|_| ErrorDetails::ParsePolicyError { file: file.clone() })?;
+
+        Ok(Some(Policy {
+            file,
+            node: ToolPolicy::from_serial(serial.node)?,
+            yarn: ToolPolicy::from_serial(serial.yarn)?,
+        }))
+    }
+
+    /// Checks that a version of Node is allowed. Whether the version is an LTS version is only
+    /// looked up if the policy needs to know.
+    pub fn check_node<F>(&self, version: &Version, is_lts: F) -> Fallible<()>
+    where
+        F: FnOnce() -> Fallible<bool>,
+    {
+        self.check("node", &self.node, version)?;
+        if self.node.lts && !is_lts()? {
+            throw!(self.violation("node", version, "LTS versions".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Checks that a version of Yarn is allowed.
+    pub fn check_yarn(&self, version: &Version) -> Fallible<()> {
+        self.check("yarn", &self.yarn, version)
+    }
+
+    /// Determines whether a version of Node is allowed, for offering only allowed versions to
+    /// choose from.
+    pub fn allows_node(&self, version: &Version, lts: bool) -> bool {
+        self.node.allows(version) && (lts || !self.node.lts)
+    }
+
+    /// Determines whether a version of Yarn is allowed, for offering only allowed versions to
+    /// choose from.
+    pub fn allows_yarn(&self, version: &Version) -> bool {
+        self.yarn.allows(version)
+    }
+
+    fn check(&self, tool: &str, policy: &ToolPolicy, version: &Version) -> Fallible<()> {
+        match policy.versions {
+            Some(ref range) if !range.requirement.matches(version) => {
+                throw!(self.violation(tool, version, format!("versions {}", range.src)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn violation(&self, tool: &str, version: &Version, rule: String) -> ErrorDetails {
+        ErrorDetails::PolicyViolation {
+            tool: tool.to_string(),
+            version: version.to_string(),
+            rule,
+            file: self.file.clone(),
+        }
+    }
+}
+
+impl ToolPolicy {
+    /// Converts a tool's section of the policy file, where a missing section allows any version.
+    fn from_serial(serial: Option<serial::ToolPolicy>) -> Fallible<Self> {
+        match serial {
+            Some(serial) => Ok(ToolPolicy {
+                versions: serial.versions.map(Range::parse).transpose()?,
+                lts: serial.lts.unwrap_or(false),
+            }),
+            None => Ok(ToolPolicy::default()),
+        }
+    }
+
+    fn allows(&self, version: &Version) -> bool {
+        self.versions
+            .as_ref()
+            .map_or(true, |range| range.requirement.matches(version))
+    }
+}
+
+impl Range {
+    fn parse(src: String) -> Fallible<Self> {
+        let requirement = VersionSpec::parse_requirements(&src)?;
+        Ok(Range { src, requirement })
+    }
+}
+
+mod serial {
+    use super::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct Policy {
+        pub node: Option<ToolPolicy>,
+        pub yarn: Option<ToolPolicy>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ToolPolicy {
+        pub versions: Option<String>,
+        pub lts: Option<bool>,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::{Policy, Range, ToolPolicy};
+    use semver::Version;
+    use std::path::PathBuf;
+    use volta_fail::Fallible;
+
+    fn policy(node: &str, lts: bool, yarn: &str) -> Policy {
+        Policy {
+            file: PathBuf::from("/etc/volta/policy.json"),
+            node: ToolPolicy {
+                versions: Some(Range::parse(node.to_string()).unwrap()),
+                lts,
+            },
+            yarn: ToolPolicy {
+                versions: Some(Range::parse(yarn.to_string()).unwrap()),
+                lts: false,
+            },
+        }
+    }
+
+    #[test]
+    fn checks_versions_against_the_policy() {
+        let policy = policy(">=16", true, "^1.22");
+
+        assert!(policy
+            .check_node(&Version::parse("18.16.0").unwrap(), || Ok(true))
+            .is_ok());
+        assert!(policy
+            .check_node(&Version::parse("14.21.3").unwrap(), || Ok(true))
+            .is_err());
+        assert!(policy
+            .check_node(&Version::parse("19.9.0").unwrap(), || Ok(false))
+            .is_err());
+
+        assert!(policy
+            .check_yarn(&Version::parse("1.22.19").unwrap())
+            .is_ok());
+        assert!(policy
+            .check_yarn(&Version::parse("3.6.0").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn only_looks_up_lts_when_needed() {
+        let policy = policy(">=16", false, "*");
+
+        let is_lts = || -> Fallible<bool> { panic!("looked up whether 19.9.0 is LTS") };
+        assert!(policy
+            .check_node(&Version::parse("19.9.0").unwrap(), is_lts)
+            .is_ok());
+        assert!(policy.allows_node(&Version::parse("19.9.0").unwrap(), false));
+    }
+}
@@ -1,39 +1,62 @@
 //! Provides the `Project` type, which represents a Node project tree in
 //! the filesystem.
 
+use std::collections::HashSet;
 use std::env;
 use std::ffi::OsStr;
+use std::fs::{read_dir, read_to_string};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+use glob::{glob, Pattern};
 use lazycell::LazyCell;
 use semver::Version;
+use serde::Deserialize;
 
+use crate::checksum::{ArchiveHash, ArchiveHashes};
 use crate::distro::node::{load_default_npm_version, NodeVersion};
-use crate::distro::package::BinConfig;
 use crate::error::ErrorDetails;
 use crate::manifest::{serial, Manifest};
 use crate::path;
 use crate::platform::PlatformSpec;
+use crate::project_bins::{self, DirectBins};
+use crate::tool::ToolSpec;
+use crate::trace;
 use log::debug;
-use volta_fail::{Fallible, ResultExt};
+use volta_fail::{throw, Fallible, ResultExt};
 
 /// A lazily loaded Project
 pub struct LazyProject {
+    /// The directory to find the project from, if not the current directory
+    dir: Option<PathBuf>,
     project: LazyCell<Option<Rc<Project>>>,
 }
 
 impl LazyProject {
     pub fn new() -> Self {
         LazyProject {
+            dir: None,
+            project: LazyCell::new(),
+        }
+    }
+
+    /// Constructs a `LazyProject` for the project containing the specified directory, rather
+    /// than the current directory.
+    pub fn for_dir(dir: &Path) -> Self {
+        LazyProject {
+            dir: Some(dir.to_path_buf()),
             project: LazyCell::new(),
         }
     }
 
     pub fn get(&self) -> Fallible<Option<Rc<Project>>> {
-        let project = self
-            .project
-            .try_borrow_with(|| Project::for_current_dir())?;
+        let project = self.project.try_borrow_with(|| {
+            let _span = trace::span("detect project");
+            match self.dir {
+                Some(ref dir) => Project::for_dir(dir),
+                None => Project::for_current_dir(),
+            }
+        })?;
         Ok(project.clone())
     }
 }
@@ -42,6 +65,7 @@ impl LazyProject {
 pub struct Project {
     manifest: Manifest,
     project_root: PathBuf,
+    direct_bins: LazyCell<DirectBins>,
 }
 
 impl Project {
@@ -54,13 +78,14 @@ impl Project {
     }
 
     /// Returns the Node project for the input directory, if any.
-    fn for_dir(base_dir: &Path) -> Fallible<Option<Rc<Project>>> {
+    pub(crate) fn for_dir(base_dir: &Path) -> Fallible<Option<Rc<Project>>> {
         match path::find_project_dir(base_dir) {
             Some(dir) => {
                 debug!("Found project manifest at {}", dir.display());
                 Ok(Some(Rc::new(Project {
                     manifest: Manifest::for_dir(&dir)?,
                     project_root: PathBuf::from(dir),
+                    direct_bins: LazyCell::new(),
                 })))
             }
             None => Ok(None),
@@ -87,31 +112,162 @@ impl Project {
         self.project_root.join("package.json")
     }
 
+    /// Returns the name of the file that pins this project's tools: package.json, or a
+    /// standalone `volta.json` for a project without a package.json.
+    pub fn manifest_file_name(&self) -> &'static str {
+        if self.package_file().is_file() {
+            "package.json"
+        } else {
+            "volta.json"
+        }
+    }
+
+    /// Returns the root directory of this project.
+    pub fn project_root(&self) -> &Path {
+        &self.project_root
+    }
+
+    /// Returns the `yarnPath` setting from the project's `.yarnrc.yml`, if any.
+    ///
+    /// Yarn 2+ checks in a copy of itself and sets `yarnPath` to point to it; whichever version of
+    /// Yarn is launched (including the version pinned by Volta) will then defer to that copy.
+    pub fn yarn_path(&self) -> Option<String> {
+        let yarnrc = read_to_string(self.project_root.join(".yarnrc.yml")).ok()?;
+        yarnrc.lines().find_map(|line| {
+            if line.starts_with("yarnPath:") {
+                let value = line["yarnPath:".len()..].trim();
+                Some(value.trim_matches(|c| c == '"' || c == '\'').to_string())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the root directories of this project's workspace packages, as matched by the
+    /// `workspaces` key in package.json or the `packages` list in `pnpm-workspace.yaml`.
+    pub fn workspace_roots(&self) -> Fallible<Vec<PathBuf>> {
+        let mut globs = self.manifest.workspaces.clone();
+        let pnpm_file = self.project_root.join("pnpm-workspace.yaml");
+        if let Ok(pnpm) = read_to_string(&pnpm_file) {
+            globs.extend(pnpm_workspace_packages(&pnpm, &pnpm_file)?);
+        }
+
+        let (excluded, included): (Vec<&str>, Vec<&str>) = globs
+            .iter()
+            .map(|glob| glob.trim_start_matches("./"))
+            .partition(|glob| glob.starts_with('!'));
+        let excluded = excluded
+            .into_iter()
+            .map(|glob| {
+                let glob = glob[1..].trim_start_matches("./");
+                Pattern::new(glob).with_context(workspace_glob_error(glob))
+            })
+            .collect::<Fallible<Vec<Pattern>>>()?;
+
+        let mut roots = Vec::new();
+        for included in included {
+            let pattern = self.project_root.join(included);
+            let matches =
+                glob(&pattern.to_string_lossy()).with_context(workspace_glob_error(included))?;
+
+            for dir in matches.filter_map(|entry| entry.ok()) {
+                let relative = dir.strip_prefix(&self.project_root).unwrap_or(&dir);
+                let is_excluded = excluded.iter().any(|glob| glob.matches_path(relative));
+                if !is_excluded
+                    && dir != self.project_root
+                    && dir.join("package.json").is_file()
+                    && !roots.contains(&dir)
+                {
+                    roots.push(dir);
+                }
+            }
+        }
+        roots.sort();
+
+        Ok(roots)
+    }
+
     /// Returns the path to the local binary directory for this project.
     pub fn local_bin_dir(&self) -> PathBuf {
         let sub_dir: PathBuf = ["node_modules", ".bin"].iter().collect();
         self.project_root.join(sub_dir)
     }
 
+    /// Returns the names of the executables in the local binary directory for this project that
+    /// are provided by its direct dependencies, leaving out those of transitive dependencies.
+    pub fn local_bins(&self) -> Fallible<Vec<String>> {
+        let bin_dir = self.local_bin_dir();
+        if !bin_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let node_modules = self.project_root.join("node_modules");
+        let direct_bins: HashSet<String> = self
+            .manifest
+            .merged_dependencies()
+            .iter()
+            .filter_map(|dependency| Manifest::for_dir(&node_modules.join(dependency)).ok())
+            .flat_map(|manifest| manifest.bin.into_iter().map(|(name, _)| name))
+            .collect();
+
+        let entries = read_dir(&bin_dir)
+            .with_context(|_| ErrorDetails::ReadProjectBinDirError { dir: bin_dir })?;
+        let mut bins: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| direct_bins.contains(name))
+            .collect();
+        bins.sort();
+
+        Ok(bins)
+    }
+
+    /// Returns the path to the Yarn Plug'n'Play runtime for this project, if it uses one.
+    pub fn pnp_file(&self) -> Option<PathBuf> {
+        [".pnp.cjs", ".pnp.js"]
+            .iter()
+            .map(|file_name| self.project_root.join(file_name))
+            .find(|pnp_file| pnp_file.is_file())
+    }
+
     /// Returns true if the input binary name is a direct dependency of the input project
     pub fn has_direct_bin(&self, bin_name: &OsStr) -> Fallible<bool> {
-        if let Some(name) = bin_name.to_str() {
-            let config_path = path::user_tool_bin_config(name)?;
-            if config_path.exists() {
-                let config = BinConfig::from_file(config_path)?;
-                return Ok(self.has_direct_dependency(&config.package));
-            }
-        }
-        Ok(false)
+        Ok(self.direct_bin_package(bin_name)?.is_some())
     }
 
-    fn has_direct_dependency(&self, dependency: &str) -> bool {
+    /// Returns the name of the direct dependency that provides the input binary, if any
+    pub fn direct_bin_package(&self, bin_name: &OsStr) -> Fallible<Option<String>> {
+        let direct_bins = self
+            .direct_bins
+            .try_borrow_with(|| project_bins::direct_bins(self))?;
+
+        Ok(bin_name
+            .to_str()
+            .and_then(|name| direct_bins.get(name))
+            .cloned())
+    }
+
+    pub(crate) fn has_direct_dependency(&self, dependency: &str) -> bool {
         self.manifest.dependencies.contains_key(dependency)
             || self.manifest.dev_dependencies.contains_key(dependency)
     }
 
-    /// Writes the specified version of Node to the `volta.node` key in package.json.
-    pub fn pin_node(&self, node_version: &NodeVersion) -> Fallible<()> {
+    /// Writes the toolchain to the `volta` key in package.json, or over the contents of
+    /// `volta.json` for a project that uses one instead.
+    pub(crate) fn write_toolchain(&self, toolchain: serial::ToolchainSpec) -> Fallible<()> {
+        // A pinned SHA-256 is kept for as long as the version that it's the SHA-256 of is pinned
+        let toolchain = toolchain.keep_sha256(self.manifest());
+        let manifest_file = self.project_root.join(self.manifest_file_name());
+        if self.package_file().is_file() {
+            Manifest::update_toolchain(toolchain, manifest_file)
+        } else {
+            Manifest::update_volta_file(toolchain, manifest_file)
+        }
+    }
+
+    /// Writes the specified version of Node to the `volta.node` key in package.json. If a range
+    /// is given, the range is written there instead and the version goes in `volta.lock.node`.
+    pub fn pin_node(&self, node_version: &NodeVersion, range: Option<String>) -> Fallible<()> {
         // prevent writing the npm version if it is equal to the default version
 
         let npm_str = load_default_npm_version(&node_version.runtime)
@@ -126,11 +282,15 @@ impl Project {
             });
 
         let toolchain = serial::ToolchainSpec::new(
-            node_version.runtime.to_string(),
+            Some(node_version.runtime.to_string()),
             npm_str,
             self.manifest().yarn_str().clone(),
-        );
-        Manifest::update_toolchain(toolchain, self.package_file())?;
+            self.manifest().arch(),
+            self.manifest().deno_str(),
+            self.manifest().bun_str(),
+        )
+        .with_node_range(range);
+        self.write_toolchain(toolchain)?;
         Ok(())
     }
 
@@ -138,11 +298,15 @@ impl Project {
     pub fn pin_yarn(&self, yarn_version: &Version) -> Fallible<()> {
         if let Some(platform) = self.manifest().platform() {
             let toolchain = serial::ToolchainSpec::new(
-                platform.node_runtime.to_string(),
+                Some(platform.node_runtime.to_string()),
                 platform.npm.as_ref().map(|npm| npm.to_string()),
                 Some(yarn_version.to_string()),
-            );
-            Manifest::update_toolchain(toolchain, self.package_file())
+                platform.arch.clone(),
+                self.manifest().deno_str(),
+                self.manifest().bun_str(),
+            )
+            .with_node_range(self.manifest().node_range());
+            self.write_toolchain(toolchain)
         } else {
             Err(ErrorDetails::NoPinnedNodeVersion.into())
         }
@@ -152,24 +316,215 @@ impl Project {
     pub fn pin_npm(&self, npm_version: &Version) -> Fallible<()> {
         if let Some(platform) = self.manifest().platform() {
             let toolchain = serial::ToolchainSpec::new(
-                platform.node_runtime.to_string(),
+                Some(platform.node_runtime.to_string()),
                 Some(npm_version.to_string()),
                 self.manifest().yarn_str().clone(),
-            );
-            Manifest::update_toolchain(toolchain, self.package_file())
+                platform.arch.clone(),
+                self.manifest().deno_str(),
+                self.manifest().bun_str(),
+            )
+            .with_node_range(self.manifest().node_range());
+            self.write_toolchain(toolchain)
         } else {
             Err(ErrorDetails::NoPinnedNodeVersion.into())
         }
     }
+
+    /// Writes the SHA-256s of the archives that the pinned Node and Yarn are installed from to the
+    /// `volta.node` and `volta.yarn` keys in package.json, by the archives' file names. The
+    /// SHA-256s pinned for the archives of other platforms are kept.
+    pub fn pin_sha256(
+        &self,
+        node_sha256: ArchiveHash,
+        yarn_sha256: Option<ArchiveHash>,
+    ) -> Fallible<()> {
+        if let Some(platform) = self.manifest().platform() {
+            let toolchain = serial::ToolchainSpec::new(
+                Some(platform.node_runtime.to_string()),
+                platform.npm.as_ref().map(|npm| npm.to_string()),
+                self.manifest().yarn_str(),
+                platform.arch.clone(),
+                self.manifest().deno_str(),
+                self.manifest().bun_str(),
+            )
+            .with_node_range(self.manifest().node_range())
+            .with_sha256(
+                archive_hashes(Some(node_sha256)),
+                archive_hashes(yarn_sha256),
+            );
+            self.write_toolchain(toolchain)
+        } else {
+            Err(ErrorDetails::NoPinnedPlatform {
+                action: "pin the SHA-256 of Node".into(),
+            }
+            .into())
+        }
+    }
+
+    /// Writes the specified version of Deno to the `volta.deno` key in package.json.
+    ///
+    /// Unlike Yarn and npm, Deno doesn't run on Node, so it can be pinned without Node.
+    pub fn pin_deno(&self, deno_version: &Version) -> Fallible<()> {
+        let platform = self.manifest().platform();
+        let toolchain = serial::ToolchainSpec::new(
+            platform.as_ref().map(|p| p.node_runtime.to_string()),
+            platform
+                .as_ref()
+                .and_then(|p| p.npm.as_ref().map(|npm| npm.to_string())),
+            self.manifest().yarn_str(),
+            self.manifest().arch(),
+            Some(deno_version.to_string()),
+            self.manifest().bun_str(),
+        )
+        .with_node_range(self.manifest().node_range());
+        self.write_toolchain(toolchain)
+    }
+
+    /// Writes the specified version of Bun to the `volta.bun` key in package.json.
+    ///
+    /// Like Deno, Bun doesn't run on Node, so it can be pinned without Node.
+    pub fn pin_bun(&self, bun_version: &Version) -> Fallible<()> {
+        let platform = self.manifest().platform();
+        let toolchain = serial::ToolchainSpec::new(
+            platform.as_ref().map(|p| p.node_runtime.to_string()),
+            platform
+                .as_ref()
+                .and_then(|p| p.npm.as_ref().map(|npm| npm.to_string())),
+            self.manifest().yarn_str(),
+            self.manifest().arch(),
+            self.manifest().deno_str(),
+            Some(bun_version.to_string()),
+        )
+        .with_node_range(self.manifest().node_range());
+        self.write_toolchain(toolchain)
+    }
+
+    /// Builds the toolchain that pins the same versions of `tools` as the `pinned` manifest does,
+    /// while keeping this project's own pins of any other tools. Nothing is written, so that every
+    /// workspace of a project can be checked before any of them is changed.
+    pub(crate) fn toolchain_matching(
+        &self,
+        pinned: &Manifest,
+        tools: &[ToolSpec],
+    ) -> Fallible<serial::ToolchainSpec> {
+        let pins = |name: &str| {
+            tools.iter().any(|tool| match tool {
+                ToolSpec::Node(_) => name == "node",
+                ToolSpec::Npm(_) => name == "npm",
+                ToolSpec::Yarn(_) => name == "yarn",
+                ToolSpec::Deno(_) => name == "deno",
+                ToolSpec::Bun(_) => name == "bun",
+                ToolSpec::Package(..) | ToolSpec::PackageSource(_) => false,
+            })
+        };
+        let own = self.manifest();
+        let own_platform = own.platform();
+
+        let (node, range, mut npm) = match pinned.platform() {
+            Some(ref platform) if pins("node") => {
+                // As with `pin_node`, npm isn't written if it's the version bundled with Node
+                let default_npm = load_default_npm_version(&platform.node_runtime).ok();
+                let npm = platform
+                    .npm
+                    .as_ref()
+                    .filter(|npm| default_npm.as_ref() != Some(npm))
+                    .map(|npm| npm.to_string());
+                (
+                    Some(platform.node_runtime.to_string()),
+                    pinned.node_range(),
+                    npm,
+                )
+            }
+            _ => (
+                own_platform
+                    .as_ref()
+                    .map(|platform| platform.node_runtime.to_string()),
+                own.node_range(),
+                own_platform
+                    .as_ref()
+                    .and_then(|platform| platform.npm.as_ref().map(|npm| npm.to_string())),
+            ),
+        };
+
+        let pinned_npm = pinned
+            .platform()
+            .and_then(|platform| platform.npm.as_ref().map(|npm| npm.to_string()));
+        let npm_changed = pins("npm") && pinned_npm.is_some();
+        if npm_changed {
+            npm = pinned_npm;
+        }
+        let yarn_changed = pins("yarn") && pinned.yarn_str().is_some();
+        let yarn = if yarn_changed {
+            pinned.yarn_str()
+        } else {
+            own.yarn_str()
+        };
+
+        // Like `pin_npm` and `pin_yarn`, npm and Yarn can't be pinned without Node
+        if node.is_none() && (npm_changed || yarn_changed) {
+            throw!(ErrorDetails::NoPinnedNodeVersion);
+        }
+
+        let deno = match pinned.deno_str() {
+            Some(deno) if pins("deno") => Some(deno),
+            _ => own.deno_str(),
+        };
+        let bun = match pinned.bun_str() {
+            Some(bun) if pins("bun") => Some(bun),
+            _ => own.bun_str(),
+        };
+
+        Ok(
+            serial::ToolchainSpec::new(node, npm, yarn, own.arch(), deno, bun)
+                .with_node_range(range),
+        )
+    }
+}
+
+/// The SHA-256 of an archive, if any, as the SHA-256s that a pin records by file name.
+fn archive_hashes(hash: Option<ArchiveHash>) -> ArchiveHashes {
+    hash.into_iter()
+        .map(|hash| (hash.file_name, hash.sha256))
+        .collect()
+}
+
+/// The part of a `pnpm-workspace.yaml` file that lists the workspace packages.
+#[derive(Deserialize)]
+struct PnpmWorkspace {
+    #[serde(default)]
+    packages: Vec<String>,
+}
+
+/// Reads the globs from the `packages` list of a `pnpm-workspace.yaml` file.
+fn pnpm_workspace_packages(src: &str, file: &Path) -> Fallible<Vec<String>> {
+    // An empty file is a valid YAML document, though it has no mapping to deserialize
+    if src.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let workspace: PnpmWorkspace =
+        serde_yaml::from_str(src).with_context(|_| ErrorDetails::ParsePnpmWorkspaceError {
+            file: file.to_path_buf(),
+        })?;
+    Ok(workspace.packages)
+}
+
+fn workspace_glob_error<E>(glob: &str) -> impl FnOnce(&E) -> ErrorDetails {
+    let glob = glob.to_string();
+    |_| ErrorDetails::WorkspaceGlobError { glob }
 }
 
 // unit tests
 
 #[cfg(test)]
 pub mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
+    use super::pnpm_workspace_packages;
+    use crate::manifest::Manifest;
     use crate::project::Project;
+    use crate::tool::ToolSpec;
+    use crate::version::VersionSpec;
+    use semver::Version;
 
     fn fixture_path(fixture_dir: &str) -> PathBuf {
         let mut cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -189,6 +544,103 @@ pub mod tests {
         assert!(test_project.has_direct_dependency("@namespaced/something-else"));
     }
 
+    #[test]
+    fn yarn_path() {
+        let berry_path = fixture_path("yarn-berry");
+        let berry_project = Project::for_dir(&berry_path).unwrap().unwrap();
+        assert_eq!(
+            berry_project.yarn_path(),
+            Some(".yarn/releases/yarn-berry.js".to_string())
+        );
+
+        let basic_path = fixture_path("basic");
+        let basic_project = Project::for_dir(&basic_path).unwrap().unwrap();
+        assert_eq!(basic_project.yarn_path(), None);
+    }
+
+    #[test]
+    fn local_bins() {
+        // `tsc` is also in `node_modules/.bin`, but from `typescript`, which is only a transitive
+        // dependency
+        let basic_path = fixture_path("basic");
+        let basic_project = Project::for_dir(&basic_path).unwrap().unwrap();
+        assert_eq!(
+            basic_project.local_bins().unwrap(),
+            vec!["rsvp".to_string()]
+        );
+
+        let berry_path = fixture_path("yarn-berry");
+        let berry_project = Project::for_dir(&berry_path).unwrap().unwrap();
+        assert!(berry_project.local_bins().unwrap().is_empty());
+    }
+
+    #[test]
+    fn workspace_roots() {
+        let workspaces_path = fixture_path("workspaces");
+        let workspaces_project = Project::for_dir(&workspaces_path).unwrap().unwrap();
+        assert_eq!(
+            workspaces_project.workspace_roots().unwrap(),
+            vec![
+                workspaces_path.join("packages").join("app"),
+                workspaces_path.join("packages").join("lib"),
+                workspaces_path.join("tools"),
+            ]
+        );
+
+        let basic_path = fixture_path("basic");
+        let basic_project = Project::for_dir(&basic_path).unwrap().unwrap();
+        assert!(basic_project.workspace_roots().unwrap().is_empty());
+    }
+
+    #[test]
+    fn toolchain_matching() {
+        let pinned = Manifest::for_dir(&fixture_path("basic")).unwrap();
+        let app_path = fixture_path("workspaces").join("packages").join("app");
+        let app = Project::for_dir(&app_path).unwrap().unwrap();
+        let node = || ToolSpec::Node(VersionSpec::default());
+        let yarn = || ToolSpec::Yarn(VersionSpec::default());
+
+        // The workspace pins no Node of its own, so Yarn can only be pinned along with Node
+        assert!(app.toolchain_matching(&pinned, &[yarn()]).is_err());
+        assert!(app.toolchain_matching(&pinned, &[node(), yarn()]).is_ok());
+    }
+
+    #[test]
+    fn pnpm_workspaces() {
+        let file = Path::new("pnpm-workspace.yaml");
+        let src = "packages:\n  # the apps\n  - 'apps/*'\n  - \"!apps/legacy\"\n\ncatalog:\n  react: ^18.2.0\n";
+        assert_eq!(
+            pnpm_workspace_packages(src, file).unwrap(),
+            vec!["apps/*".to_string(), "!apps/legacy".to_string()]
+        );
+
+        let flow = "packages: ['apps/*', \"tools\"]\n";
+        assert_eq!(
+            pnpm_workspace_packages(flow, file).unwrap(),
+            vec!["apps/*".to_string(), "tools".to_string()]
+        );
+
+        assert!(pnpm_workspace_packages("", file).unwrap().is_empty());
+        assert!(
+            pnpm_workspace_packages("catalog:\n  react: ^18.2.0\n", file)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(pnpm_workspace_packages("packages: [apps/*\n", file).is_err());
+    }
+
+    #[test]
+    fn volta_json() {
+        let project_path = fixture_path("volta_json");
+        let project = Project::for_dir(&project_path).unwrap().unwrap();
+        assert_eq!(project.manifest_file_name(), "volta.json");
+        assert_eq!(
+            project.manifest().node(),
+            Some(Version::parse("10.16.0").unwrap())
+        );
+        assert!(project.manifest().dependencies.is_empty());
+    }
+
     #[test]
     fn direct_dependency_false() {
         let project_path = fixture_path("basic");
@@ -0,0 +1,303 @@
+//! Remembers which of the installed executables are provided by each project's direct
+//! dependencies. Tools like lint-staged run dozens of shims at once in a project, and this lets
+//! them share a single lookup instead of each reading the installed executables again.
+
+use std::collections::BTreeMap;
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::distro::package::BinConfig;
+use crate::fs::{read_dir_eager, write_atomic};
+use crate::path;
+use crate::project::Project;
+use volta_fail::Fallible;
+
+/// The executables provided by a project's direct dependencies, mapped to the packages that
+/// provide them.
+pub(crate) type DirectBins = BTreeMap<String, String>;
+
+/// How long a memo is kept after it was last saved. Older memos are removed whenever another
+/// is saved, so that those of projects that no longer exist don't pile up.
+const MEMO_LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Deserialize, Serialize)]
+struct Memo {
+    /// When the executables were found from the project's manifest and the installed executables,
+    /// which has to match for the memo to still be used.
+    key: String,
+    bins: DirectBins,
+}
+
+/// Finds the installed executables provided by the direct dependencies of a project, from the
+/// memo for the project if it's still current. Otherwise, they're found from the configs of the
+/// installed executables and a new memo is saved.
+pub(crate) fn direct_bins(project: &Project) -> Fallible<DirectBins> {
+    let bin_dir = path::user_bin_dir()?;
+    let memo_file = memo_file(project.project_root())?;
+    let key = memo_key(project, &bin_dir);
+
+    if let Some(bins) = read_memo(&memo_file, &key) {
+        return Ok(bins);
+    }
+
+    let bins = find_direct_bins(project, &bin_dir);
+
+    // The memo is only a cache, so failing to save it shouldn't stop the tool from running
+    if !path::is_read_only_home() {
+        if let Err(error) = write_memo(&memo_file, key, &bins) {
+            debug!(
+                "Could not save the project executables to {}: {}",
+                memo_file.display(),
+                error
+            );
+        }
+        prune_memos(&memo_file, MEMO_LIFETIME);
+    }
+
+    Ok(bins)
+}
+
+fn memo_file(project_root: &Path) -> Fallible<PathBuf> {
+    let root_hash = Sha256::digest(project_root.to_string_lossy().as_bytes());
+    Ok(path::project_bins_dir()?.join(format!("{}.json", hex::encode(&root_hash))))
+}
+
+/// Combines the modification times of the project's package.json and of the directory of
+/// installed executables, whose entries change whenever an executable is installed, removed, or
+/// taken over by another package (configs are renamed into place, see `BinConfig::write`).
+/// Only their metadata is read, so that checking the memo stays cheap.
+fn memo_key(project: &Project, bin_dir: &Path) -> String {
+    format!(
+        "{}:{}",
+        modified_nanos(&project.package_file()),
+        modified_nanos(bin_dir)
+    )
+}
+
+fn modified_nanos(path: &Path) -> u128 {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since_epoch| since_epoch.as_nanos())
+}
+
+/// Reads the memo, if there is one and it's for the same key. A missing or corrupt memo is
+/// treated as out of date.
+fn read_memo(memo_file: &Path, key: &str) -> Option<DirectBins> {
+    read_to_string(memo_file)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Memo>(&json).ok())
+        .filter(|memo| memo.key == key)
+        .map(|memo| memo.bins)
+}
+
+/// Writes the memo all at once, so that shims running at the same time never read a partly
+/// written memo.
+fn write_memo(memo_file: &Path, key: String, bins: &DirectBins) -> io::Result<()> {
+    let memo = Memo {
+        key,
+        bins: bins.clone(),
+    };
+    let json = serde_json::to_string(&memo)?;
+    write_atomic(memo_file, json.as_bytes())
+}
+
+/// Removes the memos, other than the one just saved, that were last saved longer ago than the
+/// lifetime. Memos that can't be removed are left for next time.
+fn prune_memos(memo_file: &Path, lifetime: Duration) {
+    let memo_dir = match memo_file.parent() {
+        Some(memo_dir) => memo_dir,
+        None => return,
+    };
+    let entries = match read_dir_eager(memo_dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for (entry, metadata) in entries {
+        let file = entry.path();
+        let expired = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .map_or(false, |age| age >= lifetime);
+        if expired && file != memo_file {
+            debug!("Removing expired memo {}", file.display());
+            let _ = fs::remove_file(&file);
+        }
+    }
+}
+
+/// Reads the config of each installed executable, keeping those provided by direct dependencies.
+/// Configs that can't be read are skipped, so that one broken executable doesn't stop the others.
+fn find_direct_bins(project: &Project, bin_dir: &Path) -> DirectBins {
+    let mut bins = DirectBins::new();
+
+    let entries = match read_dir_eager(bin_dir) {
+        Ok(entries) => entries,
+        Err(_) => return bins,
+    };
+
+    for (entry, _) in entries {
+        let config_file = entry.path();
+        if config_file
+            .extension()
+            .map_or(true, |extension| extension != "json")
+        {
+            continue;
+        }
+
+        match BinConfig::from_file(config_file.clone()) {
+            Ok(config) => {
+                if project.has_direct_dependency(&config.package) {
+                    bins.insert(config.name, config.package);
+                }
+            }
+            Err(_) => debug!("Skipping unreadable config {}", config_file.display()),
+        }
+    }
+
+    bins
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::fs::write;
+    use tempfile::tempdir;
+
+    /// Writes the config of an installed executable the way `BinConfig::write` does.
+    fn write_bin_config(bin_dir: &Path, name: &str, package: &str) {
+        write_atomic(
+            &bin_dir.join(format!("{}.json", name)),
+            format!(
+                r#"{{
+                    "name": "{}",
+                    "package": "{}",
+                    "version": "1.0.0",
+                    "path": "bin/{}",
+                    "platform": {{ "node": {{ "runtime": "12.0.0", "npm": "6.9.0" }}, "yarn": null }}
+                }}"#,
+                name, package, name
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    }
+
+    fn write_manifest(project_root: &Path, dependencies: &str) {
+        write(
+            project_root.join("package.json"),
+            format!(
+                r#"{{ "name": "app", "dependencies": {{ {} }} }}"#,
+                dependencies
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn finds_bins_of_direct_dependencies() {
+        let project_root = tempdir().unwrap();
+        let bin_dir = tempdir().unwrap();
+        write_manifest(
+            project_root.path(),
+            r#""eslint": "^6.0.0", "rsvp": "^4.0.0""#,
+        );
+        write_bin_config(bin_dir.path(), "eslint", "eslint");
+        write_bin_config(bin_dir.path(), "tsc", "typescript");
+        write_bin_config(bin_dir.path(), "tsserver", "typescript");
+        write(bin_dir.path().join("broken.json"), "{").unwrap();
+
+        let project = Project::for_dir(project_root.path()).unwrap().unwrap();
+        let mut expected = DirectBins::new();
+        expected.insert("eslint".to_string(), "eslint".to_string());
+        assert_eq!(find_direct_bins(&project, bin_dir.path()), expected);
+    }
+
+    #[test]
+    fn reuses_memo_until_inputs_change() {
+        let project_root = tempdir().unwrap();
+        let bin_dir = tempdir().unwrap();
+        let memo_dir = tempdir().unwrap();
+        let memo_file = memo_dir.path().join("project-bins").join("app.json");
+        write_manifest(project_root.path(), r#""eslint": "^6.0.0""#);
+        write_bin_config(bin_dir.path(), "eslint", "eslint");
+
+        let project = Project::for_dir(project_root.path()).unwrap().unwrap();
+        let key = memo_key(&project, bin_dir.path());
+        let bins = find_direct_bins(&project, bin_dir.path());
+        write_memo(&memo_file, key.clone(), &bins).unwrap();
+        assert_eq!(read_memo(&memo_file, &key), Some(bins));
+
+        // Lockfiles don't change which executables the direct dependencies provide
+        write(project_root.path().join("yarn.lock"), "eslint@^6.0.0").unwrap();
+        assert_eq!(memo_key(&project, bin_dir.path()), key);
+
+        write_bin_config(bin_dir.path(), "tsc", "typescript");
+        let installed_key = memo_key(&project, bin_dir.path());
+        assert_ne!(installed_key, key);
+        assert_eq!(read_memo(&memo_file, &installed_key), None);
+
+        write_manifest(
+            project_root.path(),
+            r#""eslint": "^6.0.0", "typescript": "^3.0.0""#,
+        );
+        assert_ne!(memo_key(&project, bin_dir.path()), installed_key);
+    }
+
+    #[test]
+    fn refreshes_memo_when_executable_changes_owner() {
+        let project_root = tempdir().unwrap();
+        let bin_dir = tempdir().unwrap();
+        let memo_dir = tempdir().unwrap();
+        let memo_file = memo_dir.path().join("project-bins").join("app.json");
+        write_manifest(
+            project_root.path(),
+            r#""eslint": "^6.0.0", "eslint-fork": "^6.0.0""#,
+        );
+        write_bin_config(bin_dir.path(), "eslint", "eslint");
+
+        let project = Project::for_dir(project_root.path()).unwrap().unwrap();
+        let key = memo_key(&project, bin_dir.path());
+        write_memo(
+            &memo_file,
+            key.clone(),
+            &find_direct_bins(&project, bin_dir.path()),
+        )
+        .unwrap();
+
+        // Another package takes the executable over, as `volta prefer` does
+        write_bin_config(bin_dir.path(), "eslint", "eslint-fork");
+        let preferred_key = memo_key(&project, bin_dir.path());
+        assert_ne!(preferred_key, key);
+        assert_eq!(read_memo(&memo_file, &preferred_key), None);
+
+        let mut expected = DirectBins::new();
+        expected.insert("eslint".to_string(), "eslint-fork".to_string());
+        assert_eq!(find_direct_bins(&project, bin_dir.path()), expected);
+    }
+
+    #[test]
+    fn prunes_expired_memos() {
+        let memo_dir = tempdir().unwrap();
+        let kept = memo_dir.path().join("kept.json");
+        let expired = memo_dir.path().join("expired.json");
+        write_memo(&kept, "key".to_string(), &DirectBins::new()).unwrap();
+        write_memo(&expired, "key".to_string(), &DirectBins::new()).unwrap();
+
+        prune_memos(&kept, MEMO_LIFETIME);
+        assert!(expired.exists());
+
+        prune_memos(&kept, Duration::from_secs(0));
+        assert!(kept.exists());
+        assert!(!expired.exists());
+    }
+}
@@ -0,0 +1,241 @@
+//! Lays down a project's pinned platform into a prefix like `/usr/local`, the way that a
+//! system-wide install of Node would be, so that the tools run without Volta or its shims. This
+//! lets the same pins drive both developers' machines and container images (see
+//! `volta provision`).
+//!
+//! Node is copied into the prefix as it was distributed, with npm replaced if the project pins a
+//! version other than the one bundled with Node. Yarn is added next to npm in Node's global
+//! `node_modules`, with its executables in the prefix's `bin` directory, as `npm install --global
+//! yarn` would have done.
+
+use std::fs::{self, create_dir_all, read_dir};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use semver::Version;
+
+use crate::error::ErrorDetails;
+use crate::path::{self, create_dir_symlink, create_file_symlink};
+use crate::platform::{Image, PlatformSpec};
+use crate::session::Session;
+use crate::style::{success_prefix, tool_version};
+use crate::version::VersionSpec;
+use volta_fail::{Fallible, ResultExt};
+
+/// Fetches the tools of a platform and copies them into a prefix, returning the image that was
+/// provisioned.
+pub fn provision(session: &mut Session, platform: &PlatformSpec, dest: &Path) -> Fallible<Image> {
+    let image = platform.checkout(session)?;
+
+    let node_image = image.node_dir()?;
+    debug!("Copying {} into {}", node_image.display(), dest.display());
+    copy_into(&node_image, dest).with_context(provision_error(dest))?;
+
+    if image.node.npm != image.bundled_npm {
+        let npm = session
+            .fetch_npm(&VersionSpec::Exact(image.node.npm.clone()))?
+            .into_version()
+            .version;
+        replace_package(dest, "npm", &npm)?;
+    }
+
+    if let Some(ref yarn) = image.yarn {
+        let yarn_image = path::yarn_image_dir(&yarn.to_string())?;
+        let yarn_dir = global_modules_dir(dest).join("yarn");
+        replace_dir(&yarn_image, &yarn_dir).with_context(provision_error(dest))?;
+        link_yarn_bins(dest).with_context(provision_error(dest))?;
+    }
+
+    let mut tools = vec![
+        tool_version("node", &image.node.runtime),
+        tool_version("npm", &image.node.npm),
+    ];
+    if let Some(ref yarn) = image.yarn {
+        tools.push(tool_version("yarn", yarn));
+    }
+    info!(
+        "{} provisioned {} into {}",
+        success_prefix(),
+        tools.join(", "),
+        dest.display()
+    );
+
+    Ok(image)
+}
+
+/// The directory that Node keeps its global packages in, including npm.
+fn global_modules_dir(prefix: &Path) -> PathBuf {
+    if cfg!(windows) {
+        prefix.join("node_modules")
+    } else {
+        prefix.join("lib").join("node_modules")
+    }
+}
+
+/// Replaces a package that was bundled with Node with a version from the package inventory.
+fn replace_package(dest: &Path, name: &str, version: &Version) -> Fallible<()> {
+    let image = path::package_image_dir(name, &version.to_string())?;
+    let dir = global_modules_dir(dest).join(name);
+    replace_dir(&image, &dir).with_context(provision_error(dest))
+}
+
+fn replace_dir(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(to).is_ok() {
+        remove(to)?;
+    }
+    copy_into(from, to)
+}
+
+/// Adds the executables of Yarn to the prefix: relative links on Unix, the same as npm's, and
+/// batch files that run it with the Node next to them on Windows.
+#[cfg(unix)]
+fn link_yarn_bins(dest: &Path) -> io::Result<()> {
+    let bin_dir = dest.join("bin");
+    create_dir_all(&bin_dir)?;
+    for &name in ["yarn", "yarnpkg"].iter() {
+        let link = bin_dir.join(name);
+        if fs::symlink_metadata(&link).is_ok() {
+            remove(&link)?;
+        }
+        create_file_symlink(PathBuf::from("../lib/node_modules/yarn/bin/yarn.js"), link)?;
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn link_yarn_bins(dest: &Path) -> io::Result<()> {
+    use std::fs::write;
+
+    for &name in ["yarn.cmd", "yarnpkg.cmd"].iter() {
+        write(
+            dest.join(name),
+            "@\"%~dp0\\node.exe\" \"%~dp0\\node_modules\\yarn\\bin\\yarn.js\" %*\r\n",
+        )?;
+    }
+    Ok(())
+}
+
+/// Copies a tree into a directory that may already have files in it, like `/usr/local`,
+/// replacing any files of the same name. Links with relative targets, like Node's `bin/npm`, are
+/// recreated as they are. Links with absolute targets, which point into the Volta directory when
+/// images are built as symlink farms, are replaced by copies of what they point to. Volta's own
+/// files in the image, like the checksums of its files, are left out.
+fn copy_into(from: &Path, to: &Path) -> io::Result<()> {
+    create_dir_all(to)?;
+
+    for entry in read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(".volta-") {
+            continue;
+        }
+
+        let source = entry.path();
+        let dest = to.join(entry.file_name());
+        let existing = fs::symlink_metadata(&dest).ok();
+
+        if entry.file_type()?.is_dir() {
+            if existing.map_or(false, |metadata| !metadata.is_dir()) {
+                remove(&dest)?;
+            }
+            copy_into(&source, &dest)?;
+            continue;
+        }
+
+        if existing.is_some() {
+            remove(&dest)?;
+        }
+        match fs::read_link(&source) {
+            Ok(ref target) if target.is_relative() => {
+                if source.is_dir() {
+                    create_dir_symlink(target.clone(), dest)?;
+                } else {
+                    create_file_symlink(target.clone(), dest)?;
+                }
+            }
+            _ if source.is_dir() => copy_into(&source, &dest)?,
+            _ => {
+                fs::copy(&source, &dest)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn provision_error(dest: &Path) -> impl FnOnce(&io::Error) -> ErrorDetails {
+    let dir = dest.to_path_buf();
+    |_| ErrorDetails::ProvisionError { dir }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::copy_into;
+    use std::fs::{create_dir_all, read_to_string, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn copies_into_an_existing_prefix() {
+        let root = tempdir().unwrap();
+        let from = root.path().join("image");
+        let to = root.path().join("prefix");
+        create_dir_all(from.join("bin")).unwrap();
+        create_dir_all(to.join("bin")).unwrap();
+        write(from.join("bin").join("node"), "node v18").unwrap();
+        write(from.join(".volta-checksums.json"), "{}").unwrap();
+        write(to.join("bin").join("node"), "node v16").unwrap();
+        write(to.join("bin").join("other"), "other").unwrap();
+
+        copy_into(&from, &to).unwrap();
+
+        assert_eq!(
+            read_to_string(to.join("bin").join("node")).unwrap(),
+            "node v18"
+        );
+        assert_eq!(
+            read_to_string(to.join("bin").join("other")).unwrap(),
+            "other"
+        );
+        assert!(!to.join(".volta-checksums.json").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_relative_links_and_copies_absolute_ones() {
+        use std::fs::read_link;
+        use std::os::unix::fs::symlink;
+        use std::path::PathBuf;
+
+        let root = tempdir().unwrap();
+        let store = root.path().join("store");
+        let from = root.path().join("image");
+        let to = root.path().join("prefix");
+        create_dir_all(&store).unwrap();
+        create_dir_all(from.join("bin")).unwrap();
+        create_dir_all(from.join("lib")).unwrap();
+        write(store.join("node"), "node v18").unwrap();
+        write(from.join("lib").join("npm-cli.js"), "npm").unwrap();
+        symlink(store.join("node"), from.join("bin").join("node")).unwrap();
+        symlink("../lib/npm-cli.js", from.join("bin").join("npm")).unwrap();
+
+        copy_into(&from, &to).unwrap();
+
+        let node = to.join("bin").join("node");
+        assert!(!node.symlink_metadata().unwrap().file_type().is_symlink());
+        assert_eq!(read_to_string(node).unwrap(), "node v18");
+        assert_eq!(
+            read_link(to.join("bin").join("npm")).unwrap(),
+            PathBuf::from("../lib/npm-cli.js")
+        );
+        assert_eq!(read_to_string(to.join("bin").join("npm")).unwrap(), "npm");
+    }
+}
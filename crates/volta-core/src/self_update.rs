@@ -0,0 +1,532 @@
+//! Provides `volta self-update`, which fetches a newer build of Volta from a release channel and
+//! swaps it in place of the installed binaries.
+
+use std::fs::{read, File};
+#[cfg(not(windows))]
+use std::fs::{remove_file, rename};
+use std::path::Path;
+#[cfg(not(windows))]
+use std::path::PathBuf;
+#[cfg(target_os = "linux")]
+use std::process::Command;
+use std::time::Duration;
+
+use log::{debug, info};
+use semver::Version;
+use sha2::{Digest, Sha256};
+
+use crate::error::ErrorDetails;
+use crate::hook::{HookConfig, VoltaChannel};
+use crate::shim;
+use crate::style::{progress_spinner, success_prefix};
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// The URL of the files that report the newest public release of Volta on each channel.
+const PUBLIC_LATEST_URL: &str = "https://volta.sh";
+
+/// The URL under which each public release of Volta is published, in a directory per version.
+const PUBLIC_RELEASES_URL: &str = "https://github.com/volta-cli/volta/releases/download";
+
+/// The URL under which the SHA-256 checksums of each public release of Volta are published, in a
+/// directory per version. They are served separately from the releases, so that a release can't
+/// be replaced without its checksum also being replaced on a different server.
+const PUBLIC_CHECKSUMS_URL: &str = "https://volta.sh/checksums";
+
+/// The name of the file listing the checksums of a release's assets, as `sha256sum` writes it.
+const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+/// How long to wait for the release channel to report its newest version.
+const LATEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "macos")] {
+        /// Returns the name of the release asset that Volta is installed from on this platform.
+        fn release_file_name(version: &Version) -> Fallible<String> {
+            Ok(format!("volta-{}-macos.tar.gz", version))
+        }
+    } else if #[cfg(target_os = "linux")] {
+        /// Returns the name of the release asset that Volta is installed from on this platform.
+        /// Linux releases are built for each supported version of OpenSSL, so the one for the
+        /// system's version is used.
+        fn release_file_name(version: &Version) -> Fallible<String> {
+            Ok(format!(
+                "volta-{}-linux-openssl-{}.tar.gz",
+                version,
+                openssl_version()?
+            ))
+        }
+    } else if #[cfg(windows)] {
+        /// Returns the name of the release asset that Volta is installed from on this platform.
+        fn release_file_name(version: &Version) -> Fallible<String> {
+            Ok(format!("volta-{}-windows-x86_64.msi", version))
+        }
+    } else {
+        compile_error!("Unsupported target_os variant (expected 'macos', 'linux', or 'windows').");
+    }
+}
+
+/// A source of Volta releases: a URL reporting the newest version, a URL under which each
+/// version's archives are published, and a URL under which their checksums are published.
+pub(crate) struct Channel {
+    latest_url: String,
+    releases_url: String,
+    checksums_url: String,
+}
+
+impl Channel {
+    /// The channel configured by the `volta` hooks, which is the public stable channel unless
+    /// another channel or release server is chosen.
+    pub(crate) fn current() -> Fallible<Self> {
+        // Only the user's hooks are consulted, so that a project can't redirect updates
+        let hooks = HookConfig::for_user()?.and_then(|hooks| hooks.volta);
+        let (channel, releases, checksums) = match hooks {
+            Some(hooks) => (hooks.channel, hooks.releases, hooks.checksums),
+            None => (None, None, None),
+        };
+        let checksums_url = checksums
+            .as_ref()
+            .map(|url| url.trim_end_matches('/'))
+            .unwrap_or(PUBLIC_CHECKSUMS_URL)
+            .to_string();
+
+        let latest_file = match channel.unwrap_or(VoltaChannel::Stable) {
+            VoltaChannel::Stable => "latest-version",
+            VoltaChannel::Preview => "latest-preview-version",
+        };
+
+        Ok(match releases {
+            Some(url) => {
+                let url = url.trim_end_matches('/');
+                Channel {
+                    latest_url: format!("{}/{}", url, latest_file),
+                    releases_url: url.to_string(),
+                    checksums_url,
+                }
+            }
+            None => Channel {
+                latest_url: format!("{}/{}", PUBLIC_LATEST_URL, latest_file),
+                releases_url: PUBLIC_RELEASES_URL.to_string(),
+                checksums_url,
+            },
+        })
+    }
+
+    /// Fetches the newest version on the channel.
+    pub(crate) fn latest_version(&self) -> Fallible<Version> {
+        let response_text = reqwest::Client::builder()
+            .timeout(LATEST_TIMEOUT)
+            .build()
+            .and_then(|client| client.get(&self.latest_url).send())
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.text())
+            .with_context(|_| ErrorDetails::VoltaLatestFetchError {
+                from_url: self.latest_url.clone(),
+            })?;
+
+        let latest = parse_version(&response_text)?;
+        debug!(
+            "Found Volta latest version ({}) from {}",
+            latest, self.latest_url
+        );
+        Ok(latest)
+    }
+
+    /// Fetches the newest version on the channel, if it is newer than the current version.
+    fn newer_version(&self, current: &str) -> Fallible<Option<Version>> {
+        let current = parse_version(current)?;
+
+        let spinner = progress_spinner(&format!("Checking for updates: {}", self.latest_url));
+        let latest = self.latest_version()?;
+        spinner.finish_and_clear();
+
+        Ok(if latest > current { Some(latest) } else { None })
+    }
+
+    fn release_url(&self, version: &Version, file_name: &str) -> String {
+        format!("{}/v{}/{}", self.releases_url, version, file_name)
+    }
+
+    /// Fetches the published SHA-256 checksum of a release asset.
+    fn checksum(&self, version: &Version, file_name: &str) -> Fallible<String> {
+        let url = format!("{}/v{}/{}", self.checksums_url, version, CHECKSUMS_FILE);
+        let checksums = reqwest::Client::builder()
+            .timeout(LATEST_TIMEOUT)
+            .build()
+            .and_then(|client| client.get(&url).send())
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.text())
+            .with_context(|_| ErrorDetails::VoltaDownloadError {
+                from_url: url.clone(),
+            })?;
+
+        match find_checksum(&checksums, file_name) {
+            Some(checksum) => Ok(checksum.to_string()),
+            None => throw!(ErrorDetails::VoltaChecksumMissing {
+                file: file_name.to_string(),
+                from_url: url,
+            }),
+        }
+    }
+}
+
+/// Reports whether a newer version of Volta than the current one is available.
+pub fn check_for_update(current: &str) -> Fallible<()> {
+    match Channel::current()?.newer_version(current)? {
+        Some(latest) => info!(
+            "Volta v{} is available (currently v{}). Run `volta self-update` to install it.",
+            latest, current
+        ),
+        None => info!("Volta v{} is up to date", current),
+    }
+    Ok(())
+}
+
+/// Installs the newest version of Volta, if it is newer than the current one.
+pub fn self_update(current: &str) -> Fallible<()> {
+    let channel = Channel::current()?;
+    let version = match channel.newer_version(current)? {
+        Some(version) => version,
+        None => {
+            info!("Volta v{} is up to date", current);
+            return Ok(());
+        }
+    };
+
+    let file_name = release_file_name(&version)?;
+    let checksum = channel.checksum(&version, &file_name)?;
+    let url = channel.release_url(&version, &file_name);
+    install_release(&url, &file_name, &checksum, &version)?;
+
+    shim::create_built_in()?;
+
+    info!("{} updated Volta to v{}", success_prefix(), version);
+    Ok(())
+}
+
+/// Installs a release from its archive, by unpacking each new binary next to the one it replaces
+/// and then renaming it into place.
+#[cfg(not(windows))]
+fn install_release(url: &str, file_name: &str, checksum: &str, version: &Version) -> Fallible<()> {
+    use crate::path;
+    use archive::{self, Archive};
+    use tempfile::tempdir_in;
+
+    let binaries = vec![path::volta_file()?, path::shim_executable()?];
+    let install_dir = binaries[0].parent().unwrap_or(Path::new("."));
+    let temp = tempdir_in(install_dir).with_context(|_| ErrorDetails::CreateTempDirError {
+        in_dir: install_dir.to_path_buf(),
+    })?;
+
+    let archive_file = temp.path().join(file_name);
+    download(url, &archive_file, version)?;
+    verify_checksum(&archive_file, checksum, url, version)?;
+
+    let unpack_dir = temp.path().join("unpacked");
+    let archive: Box<Archive> = File::open(&archive_file)
+        .map_err(failure::Error::from)
+        .and_then(archive::load_native)
+        .with_context(unpack_error(version))?;
+    archive
+        .unpack(&unpack_dir, &mut |_, _| ())
+        .with_context(unpack_error(version))?;
+
+    let replacements = binaries
+        .into_iter()
+        .map(|dest| {
+            let file_name = dest.file_name().unwrap_or_default();
+            (unpack_dir.join(file_name), dest)
+        })
+        .collect::<Vec<_>>();
+    replace_binaries(&replacements)
+}
+
+/// Installs a release by running its Windows installer, which replaces the binaries itself.
+#[cfg(windows)]
+fn install_release(url: &str, file_name: &str, checksum: &str, version: &Version) -> Fallible<()> {
+    use crate::command::create_command;
+    use crate::fs::create_staging_dir;
+
+    let temp = create_staging_dir()?;
+    let installer = temp.path().join(file_name);
+    download(url, &installer, version)?;
+    verify_checksum(&installer, checksum, url, version)?;
+
+    let installer_error = || ErrorDetails::VoltaInstallerError {
+        version: version.to_string(),
+    };
+    let status = create_command("msiexec")
+        .arg("/i")
+        .arg(&installer)
+        .arg("/passive")
+        .status()
+        .with_context(|_| installer_error())?;
+    if !status.success() {
+        throw!(installer_error());
+    }
+    Ok(())
+}
+
+/// Finds the version of OpenSSL on the system, as the major and minor version that Volta's Linux
+/// releases are named for.
+#[cfg(target_os = "linux")]
+fn openssl_version() -> Fallible<String> {
+    let output = Command::new("openssl")
+        .arg("version")
+        .output()
+        .with_context(|_| ErrorDetails::VoltaOpensslVersionError)?;
+    match parse_openssl_version(&String::from_utf8_lossy(&output.stdout)) {
+        Some(version) => Ok(version),
+        None => throw!(ErrorDetails::VoltaOpensslVersionError),
+    }
+}
+
+/// Parses the output of `openssl version`, such as "OpenSSL 1.1.1k  25 Mar 2021", into its major
+/// and minor version, such as "1.1".
+#[cfg(any(target_os = "linux", test))]
+fn parse_openssl_version(output: &str) -> Option<String> {
+    let mut words = output.split_whitespace();
+    if words.next() != Some("OpenSSL") {
+        return None;
+    }
+
+    let mut parts = words.next()?.split('.');
+    let major = parts.next()?;
+    let minor = parts.next()?;
+    let is_number = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    if is_number(major) && is_number(minor) {
+        Some(format!("{}.{}", major, minor))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn parse_version(src: &str) -> Fallible<Version> {
+    let src = src.trim().trim_start_matches('v');
+    Version::parse(src).with_context(|_| ErrorDetails::VersionParseError {
+        version: src.to_string(),
+    })
+}
+
+/// Downloads a release asset into a file.
+fn download(url: &str, file: &Path, version: &Version) -> Fallible<()> {
+    let spinner = progress_spinner(&format!("Fetching Volta v{}: {}", version, url));
+
+    let mut asset = File::create(file).with_context(|_| ErrorDetails::VoltaDownloadError {
+        from_url: url.to_string(),
+    })?;
+    reqwest::get(url)
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.copy_to(&mut asset))
+        .with_context(|_| ErrorDetails::VoltaDownloadError {
+            from_url: url.to_string(),
+        })?;
+    spinner.finish_and_clear();
+
+    Ok(())
+}
+
+/// Ensures that a downloaded release asset matches its published checksum, before anything is
+/// installed from it.
+fn verify_checksum(file: &Path, checksum: &str, url: &str, version: &Version) -> Fallible<()> {
+    let contents = read(file).with_context(|_| ErrorDetails::VoltaDownloadError {
+        from_url: url.to_string(),
+    })?;
+    if !checksum.eq_ignore_ascii_case(&hex::encode(Sha256::digest(&contents))) {
+        throw!(ErrorDetails::VoltaChecksumMismatch {
+            version: version.to_string(),
+            from_url: url.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Finds the checksum of a file in a checksums file, which lists the hex-encoded digest of each
+/// file followed by its name, as `sha256sum` writes it.
+fn find_checksum<'a>(checksums: &'a str, file_name: &str) -> Option<&'a str> {
+    checksums.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        let checksum = words.next()?;
+        // `sha256sum` marks files that were read in binary mode with a '*'
+        let name = words.next()?.trim_start_matches('*');
+        if name == file_name {
+            Some(checksum)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(not(windows))]
+/// Moves each new binary into place, putting the old binaries back if any can't be replaced,
+/// so that Volta is never left with a mix of versions.
+fn replace_binaries(replacements: &[(PathBuf, PathBuf)]) -> Fallible<()> {
+    let mut replaced: Vec<(&Path, PathBuf)> = Vec::new();
+
+    for (new, dest) in replacements {
+        let backup = backup_file(dest);
+        if let Err(error) = replace_binary(new, dest, &backup) {
+            for (dest, backup) in replaced.iter().rev() {
+                let _ = rename(backup, dest);
+            }
+            return Err(error);
+        }
+        replaced.push((dest.as_path(), backup));
+    }
+
+    for (_, backup) in replaced {
+        let _ = remove_file(backup);
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn replace_binary(new: &Path, dest: &Path, backup: &Path) -> Fallible<()> {
+    let _ = remove_file(backup);
+    let had_binary = dest.exists();
+    if had_binary {
+        rename(dest, backup).with_context(replace_error(dest))?;
+    }
+
+    if let Err(error) = rename(new, dest) {
+        if had_binary {
+            let _ = rename(backup, dest);
+        }
+        return Err(error).with_context(replace_error(dest));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn backup_file(binary: &Path) -> PathBuf {
+    let mut backup = binary.as_os_str().to_owned();
+    backup.push(".old");
+    PathBuf::from(backup)
+}
+
+#[cfg(not(windows))]
+fn unpack_error(version: &Version) -> impl FnOnce(&failure::Error) -> ErrorDetails {
+    let version = version.to_string();
+    |_| ErrorDetails::UnpackArchiveError {
+        tool: String::from("Volta"),
+        version,
+    }
+}
+
+#[cfg(not(windows))]
+fn replace_error<E>(file: &Path) -> impl FnOnce(&E) -> ErrorDetails {
+    let file = file.to_path_buf();
+    |_| ErrorDetails::VoltaReplaceError { file }
+}
+
+#[cfg(test)]
+pub mod tests {
+    #[cfg(not(windows))]
+    use super::{backup_file, replace_binaries};
+    use super::{find_checksum, parse_openssl_version, verify_checksum};
+    use semver::Version;
+    #[cfg(not(windows))]
+    use std::fs::read_to_string;
+    use std::fs::write;
+    use tempfile::tempdir;
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn parses_openssl_versions() {
+        assert_eq!(
+            parse_openssl_version("OpenSSL 1.1.1k  25 Mar 2021\n"),
+            Some("1.1".to_string())
+        );
+        assert_eq!(
+            parse_openssl_version("OpenSSL 1.0.2k-fips  26 Jan 2017"),
+            Some("1.0".to_string())
+        );
+        assert_eq!(
+            parse_openssl_version("OpenSSL 3.0.2 15 Mar 2022 (Library: OpenSSL 3.0.2 15 Mar 2022)"),
+            Some("3.0".to_string())
+        );
+        assert_eq!(parse_openssl_version("LibreSSL 2.8.3"), None);
+        assert_eq!(parse_openssl_version(""), None);
+    }
+
+    #[test]
+    fn finds_checksums() {
+        let checksums = format!(
+            "{}  volta-1.2.0-macos.tar.gz\n{} *volta-1.2.0-windows-x86_64.msi\n",
+            "0".repeat(64),
+            HELLO_SHA256
+        );
+        assert_eq!(
+            find_checksum(&checksums, "volta-1.2.0-windows-x86_64.msi"),
+            Some(HELLO_SHA256)
+        );
+        assert_eq!(
+            find_checksum(&checksums, "volta-1.2.0-macos.tar.gz").map(str::len),
+            Some(64)
+        );
+        assert_eq!(find_checksum(&checksums, "volta-1.2.0-linux.tar.gz"), None);
+    }
+
+    #[test]
+    fn accepts_download_matching_checksum() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("volta.tar.gz");
+        write(&file, "hello").unwrap();
+
+        let version = Version::parse("1.2.0").unwrap();
+        assert!(verify_checksum(&file, HELLO_SHA256, "https://example.com", &version).is_ok());
+        assert!(verify_checksum(
+            &file,
+            &HELLO_SHA256.to_uppercase(),
+            "https://example.com",
+            &version
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_download_not_matching_checksum() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("volta.tar.gz");
+        write(&file, "tampered").unwrap();
+
+        let version = Version::parse("1.2.0").unwrap();
+        assert!(verify_checksum(&file, HELLO_SHA256, "https://example.com", &version).is_err());
+        assert!(verify_checksum(&file, "", "https://example.com", &version).is_err());
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn replaces_binaries() {
+        let dir = tempdir().unwrap();
+        let new = dir.path().join("new-volta");
+        let dest = dir.path().join("volta");
+        write(&new, "new").unwrap();
+        write(&dest, "old").unwrap();
+
+        replace_binaries(&[(new.clone(), dest.clone())]).unwrap();
+
+        assert_eq!(read_to_string(&dest).unwrap(), "new");
+        assert!(!new.exists());
+        assert!(!backup_file(&dest).exists());
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn restores_binaries_on_failure() {
+        let dir = tempdir().unwrap();
+        let new_volta = dir.path().join("new-volta");
+        let volta = dir.path().join("volta");
+        let shim = dir.path().join("shim");
+        write(&new_volta, "new").unwrap();
+        write(&volta, "old").unwrap();
+        write(&shim, "old").unwrap();
+
+        let missing = dir.path().join("new-shim");
+        assert!(replace_binaries(&[(new_volta, volta.clone()), (missing, shim.clone())]).is_err());
+
+        assert_eq!(read_to_string(&volta).unwrap(), "old");
+        assert_eq!(read_to_string(&shim).unwrap(), "old");
+    }
+}
@@ -2,77 +2,176 @@
 //! execution of a Volta tool, including their current directory, Volta
 //! hook configuration, and the state of the local inventory.
 
+use std::env;
 use std::ffi::OsStr;
 use std::fmt::{self, Display, Formatter};
+use std::fs::remove_file;
+use std::io;
+use std::panic::resume_unwind;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::rc::Rc;
+use std::sync::{PoisonError, RwLock};
+use std::thread;
 
-use crate::distro::node::NodeVersion;
-use crate::distro::package::{PackageVersion, UserTool};
+use crate::checksum::ArchiveHashes;
+use crate::distro;
+use crate::distro::bun::Bun;
+use crate::distro::deno::Deno;
+use crate::distro::github;
+use crate::distro::node::{
+    self, fetch_headers, is_fetched_for_arch, load_default_npm_version, parse_arch, NodeVersion,
+};
+use crate::distro::package::{
+    installed_package_config, installed_package_configs, installed_packages, linked_dir,
+    package_bin_paths, PackageConfig, PackageVersion, ScriptPolicy, UserTool,
+};
+use crate::distro::package_source::PackageSource;
+use crate::distro::yarn;
 use crate::distro::Fetched;
+use crate::env::{flag_enabled, EVENT_LOG};
 use crate::error::ErrorDetails;
 use crate::event::EventLog;
-use crate::hook::{HookConfig, LazyHookConfig, Publish};
-use crate::inventory::{FetchResolve, Inventory, LazyInventory};
-use crate::platform::PlatformSpec;
+use crate::fs::{create_staging_dir, ensure_dir_does_not_exist, read_file_opt, rename};
+use crate::hook::{ActivityRates, HookConfig, KeepArchives, LazyHookConfig, Publish, ToolHooks};
+use crate::inventory::{self, FetchResolve, Inventory, LazyInventory};
+use crate::manifest::Manifest;
+use crate::path;
+use crate::plan::{describe_tool, Change};
+use crate::platform::{prefetch, MergedPlatform, PlatformSpec};
+use crate::policy::Policy;
 use crate::project::{LazyProject, Project};
-use crate::style::{success_prefix, tool_version};
+use crate::shim;
+use crate::style::{success_prefix, tool_version, with_progress_bar, ProgressGroup};
+use crate::tool::ToolSpec;
 use crate::toolchain::LazyToolchain;
 use crate::version::VersionSpec;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 use semver::Version;
-use volta_fail::{throw, ExitCode, Fallible, VoltaError};
-
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
-pub enum ActivityKind {
-    Fetch,
-    Install,
-    Uninstall,
-    Current,
-    Deactivate,
-    Activate,
-    Default,
-    Pin,
-    Node,
-    Npm,
-    Npx,
-    Yarn,
-    Volta,
-    Tool,
-    Help,
-    Version,
-    Binary,
-    Shim,
-    Completions,
-    Which,
+use volta_fail::{throw, ExitCode, Fallible, ResultExt, VoltaError};
+
+/// Declares the built-in kinds of activity along with their names, so that the names that
+/// plugins can't declare again are always the names that events are recorded with.
+macro_rules! activity_kinds {
+    ($($kind:ident => $name:expr,)*) => {
+        #[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+        pub enum ActivityKind {
+            $($kind,)*
+            /// A kind of activity that a plugin declared in the `events.kinds` section of the
+            /// hooks, see `Session::activity_kind`.
+            Custom(usize),
+        }
+
+        /// The names of the built-in kinds of activity.
+        const BUILT_IN_ACTIVITY_KINDS: &[(ActivityKind, &str)] =
+            &[$((ActivityKind::$kind, $name),)*];
+    };
+}
+
+activity_kinds! {
+    Fetch => "fetch",
+    Install => "install",
+    Uninstall => "uninstall",
+    Upgrade => "upgrade",
+    Rebuild => "rebuild",
+    Prefer => "prefer",
+    Link => "link",
+    Unlink => "unlink",
+    Current => "current",
+    Deactivate => "deactivate",
+    Activate => "activate",
+    Default => "default",
+    Pin => "pin",
+    Node => "node",
+    Npm => "npm",
+    Npx => "npx",
+    Yarn => "yarn",
+    Deno => "deno",
+    Bun => "bun",
+    Bunx => "bunx",
+    Volta => "volta",
+    Tool => "tool",
+    Help => "help",
+    Version => "version",
+    Binary => "binary",
+    Shim => "shim",
+    Completions => "completions",
+    Inspect => "inspect",
+    Which => "which",
+    Setup => "setup",
+    SelfUpdate => "self-update",
+    Migrate => "migrate",
+    Corepack => "corepack",
+    Doctor => "doctor",
+    Matrix => "matrix",
+    Run => "run",
+    List => "list",
+    Verify => "verify",
+    Audit => "audit",
+    PlatformChange => "platform-change",
+    Init => "init",
+    Provision => "provision",
+    Config => "config",
+    Gc => "gc",
+}
+
+lazy_static! {
+    /// The names of the kinds of activity that plugins have declared, in the order that they were
+    /// first used.
+    static ref CUSTOM_ACTIVITY_KINDS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+}
+
+impl ActivityKind {
+    /// Returns the built-in kind of activity with a name, if any.
+    pub fn built_in(name: &str) -> Option<ActivityKind> {
+        BUILT_IN_ACTIVITY_KINDS
+            .iter()
+            .find(|&&(_, built_in)| built_in == name)
+            .map(|&(kind, _)| kind)
+    }
+
+    /// Registers a kind of activity that a plugin declared, which its events are named after.
+    /// Registering the same name again returns the same kind.
+    fn register(name: &str) -> Fallible<ActivityKind> {
+        if ActivityKind::built_in(name).is_some() {
+            throw!(ErrorDetails::ActivityKindExists {
+                name: name.to_string()
+            });
+        }
+
+        let mut kinds = CUSTOM_ACTIVITY_KINDS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let id = match kinds.iter().position(|kind| kind == name) {
+            Some(id) => id,
+            None => {
+                kinds.push(name.to_string());
+                kinds.len() - 1
+            }
+        };
+        Ok(ActivityKind::Custom(id))
+    }
 }
 
 impl Display for ActivityKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-        let s = match self {
-            &ActivityKind::Fetch => "fetch",
-            &ActivityKind::Install => "install",
-            &ActivityKind::Uninstall => "uninstall",
-            &ActivityKind::Current => "current",
-            &ActivityKind::Deactivate => "deactivate",
-            &ActivityKind::Activate => "activate",
-            &ActivityKind::Default => "default",
-            &ActivityKind::Pin => "pin",
-            &ActivityKind::Node => "node",
-            &ActivityKind::Npm => "npm",
-            &ActivityKind::Npx => "npx",
-            &ActivityKind::Yarn => "yarn",
-            &ActivityKind::Volta => "volta",
-            &ActivityKind::Tool => "tool",
-            &ActivityKind::Help => "help",
-            &ActivityKind::Version => "version",
-            &ActivityKind::Binary => "binary",
-            &ActivityKind::Shim => "shim",
-            &ActivityKind::Completions => "completions",
-            &ActivityKind::Which => "which",
-        };
-        f.write_str(s)
+        match *self {
+            ActivityKind::Custom(id) => {
+                let kinds = CUSTOM_ACTIVITY_KINDS
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner);
+                f.write_str(&kinds[id])
+            }
+            kind => {
+                let name = BUILT_IN_ACTIVITY_KINDS
+                    .iter()
+                    .find(|&&(built_in, _)| built_in == kind)
+                    .map(|&(_, name)| name)
+                    .unwrap_or_default();
+                f.write_str(name)
+            }
+        }
     }
 }
 
@@ -89,17 +188,91 @@ pub struct Session {
     toolchain: LazyToolchain,
     project: LazyProject,
     event_log: EventLog,
+    script_policy: Option<ScriptPolicy>,
+    /// Whether commands list the changes they would make instead of making them.
+    dry_run: bool,
+    /// Whether the project's platform is being fetched in the background, so that this session
+    /// runs with the user's platform instead.
+    project_platform_deferred: bool,
+}
+
+/// A tool among those that `Session::fetch_concurrently` fetches: either one that is returned as
+/// it is, or the fetch that resolves it.
+enum ConcurrentFetch<'scope> {
+    Ready(ToolSpec),
+    Started(thread::ScopedJoinHandle<'scope, Fallible<ToolSpec>>),
+}
+
+/// Adds the changes that creating the shims for a tool would make, for those that don't exist.
+fn plan_shims(names: &[&str], changes: &mut Vec<Change>) -> Fallible<()> {
+    for name in names {
+        let file = path::shim_file(name)?;
+        if !file.exists() {
+            changes.push(Change::CreateShim {
+                name: name.to_string(),
+                file,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The change to a key of a project's manifest that pinning a tool would make.
+fn pin_change<V: Display>(file: &Path, key: &str, value: Option<V>) -> Change {
+    Change::SetKey {
+        file: file.to_path_buf(),
+        key: key.to_string(),
+        value: value.map(|value| value.to_string()),
+    }
 }
 
 impl Session {
     /// Constructs a new `Session`.
     pub fn new() -> Session {
+        distro::apply_network_settings();
         Session {
             hooks: LazyHookConfig::new(),
             inventory: LazyInventory::new(),
             toolchain: LazyToolchain::new(),
             project: LazyProject::new(),
             event_log: EventLog::new(),
+            script_policy: None,
+            dry_run: false,
+            project_platform_deferred: false,
+        }
+    }
+
+    /// Constructs a `Session` for the project containing the specified directory, rather than
+    /// the current directory.
+    pub fn for_dir(dir: &Path) -> Session {
+        Session {
+            project: LazyProject::for_dir(dir),
+            ..Session::new()
+        }
+    }
+
+    /// Prevents lifecycle scripts from running while installing packages in this session,
+    /// regardless of the configured policy.
+    pub fn ignore_scripts(&mut self) {
+        self.script_policy = Some(ScriptPolicy::Deny);
+    }
+
+    /// Makes the commands of this session list the changes they would make instead of making
+    /// them (see `volta --dry-run`).
+    pub fn enable_dry_run(&mut self) {
+        self.dry_run = true;
+    }
+
+    /// Returns whether the commands of this session only list the changes they would make.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Returns the policy for running lifecycle scripts while installing packages.
+    pub(crate) fn script_policy(&self) -> Fallible<ScriptPolicy> {
+        match self.script_policy {
+            Some(policy) => Ok(policy),
+            None => ScriptPolicy::from_env(),
         }
     }
 
@@ -108,11 +281,43 @@ impl Session {
         self.project.get()
     }
 
-    pub fn current_platform(&self) -> Fallible<Option<Rc<PlatformSpec>>> {
-        match self.project_platform()? {
-            Some(platform) => Ok(Some(platform)),
-            None => self.user_platform(),
+    /// Determines whether Volta should stay out of the way in the current directory, either
+    /// because of a `.volta-bypass` file in the directory tree or because the current project
+    /// sets `volta.bypass`.
+    pub fn is_bypassed(&self) -> Fallible<bool> {
+        let current_dir = env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?;
+        if path::is_bypassed_dir(&current_dir) {
+            return Ok(true);
         }
+
+        Ok(self
+            .project()?
+            .map(|project| project.manifest().bypass)
+            .unwrap_or(false))
+    }
+
+    pub fn current_platform(&self) -> Fallible<Option<Rc<PlatformSpec>>> {
+        Ok(self.merged_platform()?.map(|merged| merged.spec))
+    }
+
+    /// Returns the platform that tools run with, where the tools that the current project pins
+    /// overlay the user's default platform, along with where each of its tools comes from (see
+    /// `MergedPlatform::merge`).
+    pub fn merged_platform(&self) -> Fallible<Option<MergedPlatform>> {
+        let project = if self.project_platform_deferred {
+            None
+        } else {
+            self.project()?
+        };
+        let partial = project
+            .as_ref()
+            .and_then(|project| project.manifest().partial_platform());
+
+        Ok(MergedPlatform::merge(
+            self.project_platform()?,
+            partial,
+            self.user_platform()?,
+        ))
     }
 
     pub fn user_platform(&self) -> Fallible<Option<Rc<PlatformSpec>>> {
@@ -124,12 +329,100 @@ impl Session {
 
     /// Returns the current project's pinned platform image, if any.
     pub fn project_platform(&self) -> Fallible<Option<Rc<PlatformSpec>>> {
+        if self.project_platform_deferred {
+            return Ok(None);
+        }
         if let Some(ref project) = self.project()? {
             return Ok(project.platform());
         }
         Ok(None)
     }
 
+    /// Determines whether the tools of a platform have all been fetched.
+    pub fn is_fetched(&self, platform: &PlatformSpec) -> Fallible<bool> {
+        let inventory = self.inventory()?;
+        let node = match platform.arch {
+            Some(ref arch) => is_fetched_for_arch(&platform.node_runtime, arch),
+            None => inventory.node.contains(&platform.node_runtime),
+        };
+        let yarn = match platform.yarn {
+            Some(ref yarn) => inventory.yarn.contains(yarn),
+            None => true,
+        };
+        Ok(node && yarn)
+    }
+
+    /// Starts fetching the current project's pinned platform in a background process (see
+    /// `platform::prefetch`), and runs this session with the user's platform in the meantime, as
+    /// if the project didn't pin one. Returns whether the fetch was started, or was already
+    /// running; if neither, the platform should be fetched as usual.
+    pub fn defer_project_platform(&mut self) -> Fallible<bool> {
+        let (project_root, platform) = match self.project()? {
+            Some(ref project) => match project.platform() {
+                Some(platform) => (project.project_root().to_path_buf(), platform),
+                None => return Ok(false),
+            },
+            None => return Ok(false),
+        };
+
+        if let Err(error) = prefetch::start(&project_root, &platform) {
+            debug!("Could not fetch {} in the background: {}", platform, error);
+            return Ok(false);
+        }
+        self.project_platform_deferred = true;
+        Ok(true)
+    }
+
+    /// Fetches the tools pinned in the current project, as its shims would.
+    pub fn fetch_pinned(&mut self) -> Fallible<()> {
+        let platform = match self.project()? {
+            Some(ref project) => match project.platform() {
+                Some(platform) => platform,
+                None => throw!(ErrorDetails::NoPinnedPlatform {
+                    action: "fetch the pinned tools".into()
+                }),
+            },
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+
+        // The lock of a background fetch is released even if the fetch fails, so it can be retried
+        let fetched = platform.checkout(self);
+        prefetch::finish(&platform);
+        fetched.map(|_| ())
+    }
+
+    /// Returns the version of Deno for the current directory: the project's pinned version if
+    /// there is one, otherwise the user's default version, if any.
+    pub fn current_deno(&self) -> Fallible<Option<Version>> {
+        if let Some(ref project) = self.project()? {
+            if let Some(deno) = project.manifest().deno() {
+                return Ok(Some(deno));
+            }
+        }
+        self.user_deno()
+    }
+
+    /// Returns the version of Bun for the current directory: the project's pinned version if
+    /// there is one, otherwise the user's default version, if any.
+    pub fn current_bun(&self) -> Fallible<Option<Version>> {
+        if let Some(ref project) = self.project()? {
+            if let Some(bun) = project.manifest().bun() {
+                return Ok(Some(bun));
+            }
+        }
+        self.user_bun()
+    }
+
+    /// Returns the user's default version of Deno, if any.
+    pub fn user_deno(&self) -> Fallible<Option<Version>> {
+        Ok(self.toolchain.get()?.deno().cloned())
+    }
+
+    /// Returns the user's default version of Bun, if any.
+    pub fn user_bun(&self) -> Fallible<Option<Version>> {
+        Ok(self.toolchain.get()?.bun().cloned())
+    }
+
     /// Produces a reference to the current inventory.
     pub fn inventory(&self) -> Fallible<&Inventory> {
         self.inventory.get()
@@ -145,15 +438,40 @@ impl Session {
         self.hooks.get()
     }
 
-    /// Ensures that a specific Node version has been fetched and unpacked
-    pub(crate) fn ensure_node(&mut self, version: &Version) -> Fallible<()> {
+    /// Finds the ABI version that native modules must be built against to be loaded by the
+    /// specified version of Node, if the Node index records it.
+    pub(crate) fn node_abi(&self, version: &Version) -> Fallible<Option<u32>> {
+        let hooks = self.hooks.get()?;
+        inventory::node_abi(version, hooks.node.as_ref())
+    }
+
+    /// Ensures that a specific Node version has been fetched and unpacked, for the specified
+    /// architecture or for the native one if no architecture is specified
+    pub(crate) fn ensure_node(&mut self, version: &Version, arch: Option<&str>) -> Fallible<()> {
+        let sha256 = self.pinned_node_sha256(version)?;
         let inventory = self.inventory.get_mut()?;
 
-        if !inventory.node.contains(version) {
+        let fetched = match arch {
+            Some(arch) => is_fetched_for_arch(version, arch),
+            None => inventory.node.contains(version),
+        };
+
+        // A version that was already fetched is still checked against the pinned SHA-256s
+        if !fetched || !sha256.is_empty() {
             let hooks = self.hooks.get()?;
             inventory
                 .node
-                .fetch("node", &VersionSpec::exact(version), hooks.node.as_ref())?;
+                .fetch_version(version.clone(), arch, &sha256, hooks.node.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Ensures that a specific npm version, pinned in place of the one bundled with Node, has been
+    /// fetched and unpacked
+    pub(crate) fn ensure_npm(&mut self, version: &Version) -> Fallible<()> {
+        if !path::npm_image_bin_dir(&version.to_string())?.is_dir() {
+            self.fetch_npm(&VersionSpec::Exact(version.clone()))?;
         }
 
         Ok(())
@@ -161,38 +479,292 @@ impl Session {
 
     /// Ensures that a specific Yarn version has been fetched and unpacked
     pub(crate) fn ensure_yarn(&mut self, version: &Version) -> Fallible<()> {
+        let sha256 = self.pinned_yarn_sha256(version)?;
         let inventory = self.inventory.get_mut()?;
 
-        if !inventory.yarn.contains(version) {
+        // A version that was already fetched is still checked against the pinned SHA-256s
+        if !inventory.yarn.contains(version) || !sha256.is_empty() {
             let hooks = self.hooks.get()?;
             inventory
                 .yarn
-                .fetch("yarn", &VersionSpec::exact(version), hooks.yarn.as_ref())?;
+                .fetch_version(version.clone(), &sha256, hooks.yarn.as_ref())?;
         }
 
         Ok(())
     }
 
-    /// Fetch and unpack a version of Node matching the input requirements.
-    pub fn install_node(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
-        let node_distro = self.fetch_node(version_spec)?.into_version();
-        let success_message = format!(
-            "installed and set {} as default",
-            tool_version("node", &node_distro.runtime)
-        );
+    /// Returns the SHA-256s that the current project pins for the archives of a version of Node,
+    /// if that version is the one it pins.
+    fn pinned_node_sha256(&self, version: &Version) -> Fallible<ArchiveHashes> {
+        Ok(self
+            .project()?
+            .map(|project| project.manifest().pinned_node_sha256(version))
+            .unwrap_or_default())
+    }
+
+    /// Returns the SHA-256s that the current project pins for the archives of a version of Yarn,
+    /// if that version is the one it pins.
+    fn pinned_yarn_sha256(&self, version: &Version) -> Fallible<ArchiveHashes> {
+        Ok(self
+            .project()?
+            .map(|project| project.manifest().pinned_yarn_sha256(version))
+            .unwrap_or_default())
+    }
+
+    /// Ensures that a specific Deno version has been fetched and unpacked
+    pub(crate) fn ensure_deno(&mut self, version: &Version) -> Fallible<()> {
+        let inventory = self.inventory.get_mut()?;
+
+        if !inventory.deno.contains(version) {
+            let hooks = self.hooks.get()?;
+            inventory
+                .deno
+                .fetch("deno", &VersionSpec::exact(version), hooks.deno.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Ensures that a specific Bun version has been fetched and unpacked
+    pub(crate) fn ensure_bun(&mut self, version: &Version) -> Fallible<()> {
+        let inventory = self.inventory.get_mut()?;
+
+        if !inventory.bun.contains(version) {
+            let hooks = self.hooks.get()?;
+            inventory
+                .bun
+                .fetch("bun", &VersionSpec::exact(version), hooks.bun.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the Node, Yarn, Deno, and Bun versions among the given tools at the same time,
+    /// showing their progress together. Each is fetched just as `fetch_node` and the others
+    /// would, on its own thread. Returns the tools with those versions resolved exactly, so that
+    /// installing or pinning them afterwards finds them already fetched; other tools, and any
+    /// further versions of a tool that is already being fetched, are returned as they are.
+    pub fn fetch_concurrently(&mut self, tools: Vec<ToolSpec>) -> Fallible<Vec<ToolSpec>> {
+        let runtimes = tools
+            .iter()
+            .filter(|tool| match tool {
+                ToolSpec::Node(_) | ToolSpec::Yarn(_) | ToolSpec::Deno(_) | ToolSpec::Bun(_) => {
+                    true
+                }
+                _ => false,
+            })
+            .count();
+
+        // A single tool is fetched as usual, with its own progress bar
+        if runtimes < 2 {
+            return Ok(tools);
+        }
+
+        let Inventory {
+            ref mut node,
+            ref mut yarn,
+            ref mut deno,
+            ref mut bun,
+            ..
+        } = *self.inventory.get_mut()?;
+        let hooks = self.hooks.get()?;
+
+        // Each collection is lent to a single fetch, since fetching updates it
+        let mut node = Some(node);
+        let mut yarn = Some(yarn);
+        let mut deno = Some(deno);
+        let mut bun = Some(bun);
+        let progress = ProgressGroup::new();
+
+        thread::scope(|scope| {
+            let fetches = tools
+                .into_iter()
+                .map(|tool| match tool {
+                    ToolSpec::Node(spec) => match node.take() {
+                        Some(node) => {
+                            let bar = progress.add(&tool_version("node", &spec));
+                            let hooks = hooks.node.as_ref();
+                            ConcurrentFetch::Started(scope.spawn(move || {
+                                with_progress_bar(bar, || node.fetch("node", &spec, hooks)).map(
+                                    |fetched| {
+                                        ToolSpec::Node(VersionSpec::exact(
+                                            &fetched.into_version().runtime,
+                                        ))
+                                    },
+                                )
+                            }))
+                        }
+                        None => ConcurrentFetch::Ready(ToolSpec::Node(spec)),
+                    },
+                    ToolSpec::Yarn(spec) => match yarn.take() {
+                        Some(yarn) => {
+                            let bar = progress.add(&tool_version("yarn", &spec));
+                            let hooks = hooks.yarn.as_ref();
+                            ConcurrentFetch::Started(scope.spawn(move || {
+                                with_progress_bar(bar, || yarn.fetch("yarn", &spec, hooks)).map(
+                                    |fetched| {
+                                        ToolSpec::Yarn(VersionSpec::exact(&fetched.into_version()))
+                                    },
+                                )
+                            }))
+                        }
+                        None => ConcurrentFetch::Ready(ToolSpec::Yarn(spec)),
+                    },
+                    ToolSpec::Deno(spec) => match deno.take() {
+                        Some(deno) => {
+                            let bar = progress.add(&tool_version("deno", &spec));
+                            let hooks = hooks.deno.as_ref();
+                            ConcurrentFetch::Started(scope.spawn(move || {
+                                with_progress_bar(bar, || deno.fetch("deno", &spec, hooks)).map(
+                                    |fetched| {
+                                        ToolSpec::Deno(VersionSpec::exact(&fetched.into_version()))
+                                    },
+                                )
+                            }))
+                        }
+                        None => ConcurrentFetch::Ready(ToolSpec::Deno(spec)),
+                    },
+                    ToolSpec::Bun(spec) => match bun.take() {
+                        Some(bun) => {
+                            let bar = progress.add(&tool_version("bun", &spec));
+                            let hooks = hooks.bun.as_ref();
+                            ConcurrentFetch::Started(scope.spawn(move || {
+                                with_progress_bar(bar, || bun.fetch("bun", &spec, hooks)).map(
+                                    |fetched| {
+                                        ToolSpec::Bun(VersionSpec::exact(&fetched.into_version()))
+                                    },
+                                )
+                            }))
+                        }
+                        None => ConcurrentFetch::Ready(ToolSpec::Bun(spec)),
+                    },
+                    other => ConcurrentFetch::Ready(other),
+                })
+                .collect::<Vec<_>>();
+
+            progress.join();
+
+            // Every fetch is waited for, even after one fails, and the first failure is reported
+            let mut resolved = Vec::with_capacity(fetches.len());
+            let mut result = Ok(());
+            for fetch in fetches {
+                match fetch {
+                    ConcurrentFetch::Ready(tool) => resolved.push(tool),
+                    ConcurrentFetch::Started(handle) => match handle.join() {
+                        Ok(Ok(tool)) => resolved.push(tool),
+                        Ok(Err(error)) => {
+                            if result.is_ok() {
+                                result = Err(error);
+                            }
+                        }
+                        Err(panic) => resume_unwind(panic),
+                    },
+                }
+            }
+            result.map(|_| resolved)
+        })
+    }
+
+    /// Sets a version of Node matching the input requirements as the default, for the specified
+    /// architecture or for the native one if no architecture is specified. The version is
+    /// fetched first if it hasn't been already.
+    pub fn default_node(&mut self, version_spec: &VersionSpec, arch: Option<&str>) -> Fallible<()> {
+        let node_distro = self.fetch_node_for_arch(version_spec, arch)?.into_version();
+        // The shims for the tools that come with Node are created along with a default, since an
+        // older install of Volta may be missing some of them, like `corepack`
+        shim::create_built_in()?;
+        self.set_default_node(node_distro, "set")
+    }
+
+    fn set_default_node(&mut self, node_distro: NodeVersion, action: &str) -> Fallible<()> {
+        let success_message = match node_distro.arch {
+            Some(ref arch) => format!(
+                "{} {} ({}) as default",
+                action,
+                tool_version("node", &node_distro.runtime),
+                arch
+            ),
+            None => format!(
+                "{} {} as default",
+                action,
+                tool_version("node", &node_distro.runtime)
+            ),
+        };
         let toolchain = self.toolchain.get_mut()?;
+        let previous_default = toolchain
+            .platform_ref()
+            .map(|platform| (platform.node_runtime.clone(), platform.arch.clone()));
 
-        toolchain.set_active_node(node_distro)?;
+        toolchain.set_active_node(node_distro.clone())?;
         info!("{} {}", success_prefix(), success_message);
 
+        if previous_default != Some((node_distro.runtime.clone(), node_distro.arch.clone())) {
+            self.install_default_packages(&node_distro)?;
+        }
+
+        Ok(())
+    }
+
+    /// Installs the packages listed in the `default-packages` file against a newly set default
+    /// version of Node. Packages that fail to install are reported without stopping the others.
+    fn install_default_packages(&mut self, node_version: &NodeVersion) -> Fallible<()> {
+        let default_packages_file = path::default_packages_file()?;
+        let contents = read_file_opt(&default_packages_file).with_context(|_| {
+            ErrorDetails::ReadDefaultPackagesError {
+                file: default_packages_file.clone(),
+            }
+        })?;
+        let contents = match contents {
+            Some(contents) => contents,
+            None => return Ok(()),
+        };
+
+        for spec in default_package_specs(&contents) {
+            debug!("Installing default package {}", spec);
+            if let Err(error) = self.install_default_package(spec, node_version) {
+                warn!("Could not install default package `{}`: {}", spec, error);
+            }
+        }
+
         Ok(())
     }
 
-    /// Fetch and unpack a version of Yarn matching the input requirements.
-    pub fn install_yarn(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
+    fn install_default_package(&mut self, spec: &str, node_version: &NodeVersion) -> Fallible<()> {
+        let (package_version, spec) = match ToolSpec::try_from_str(spec)? {
+            ToolSpec::Package(name, version) => {
+                let spec = format!("{}@{}", name, version);
+                match self.fetch_package(&name, &version)? {
+                    // any existing dependencies were built for the previous default Node
+                    Fetched::Installed(package_version) => {
+                        package_version.remove_dependencies()?;
+                        (package_version, spec)
+                    }
+                    fetched => (fetched.into_version(), spec),
+                }
+            }
+            ToolSpec::PackageSource(source) => {
+                (self.fetch_package_source(&source)?, source.to_spec())
+            }
+            _ => throw!(ErrorDetails::InvalidDefaultPackage {
+                spec: spec.to_string(),
+            }),
+        };
+
+        self.install_package_version(&package_version, spec, Some(node_version))?;
+        Ok(())
+    }
+
+    /// Sets a version of Yarn matching the input requirements as the default, fetching it first
+    /// if it hasn't been already.
+    pub fn default_yarn(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
         let yarn_distro = self.fetch_yarn(version_spec)?.into_version();
+        self.set_default_yarn(yarn_distro, "set")
+    }
+
+    fn set_default_yarn(&mut self, yarn_distro: Version, action: &str) -> Fallible<()> {
         let success_message = format!(
-            "installed and set {} as default",
+            "{} {} as default",
+            action,
             tool_version("yarn", &yarn_distro)
         );
         let toolchain = self.toolchain.get_mut()?;
@@ -203,6 +775,89 @@ impl Session {
         Ok(())
     }
 
+    /// Sets a version of Deno matching the input requirements as the default, fetching it first
+    /// if it hasn't been already.
+    pub fn default_deno(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
+        let deno_distro = self.fetch_deno(version_spec)?.into_version();
+        // The `deno` shim isn't created by the Volta installer, since Deno is optional. It is
+        // created before the default is saved, so that the switch only takes effect once it's done
+        shim::create("deno")?;
+        self.set_default_deno(deno_distro, "set")
+    }
+
+    fn set_default_deno(&mut self, deno_distro: Version, action: &str) -> Fallible<()> {
+        let success_message = format!(
+            "{} {} as default",
+            action,
+            tool_version("deno", &deno_distro)
+        );
+        let toolchain = self.toolchain.get_mut()?;
+
+        toolchain.set_active_deno(deno_distro)?;
+        info!("{} {}", success_prefix(), success_message);
+
+        Ok(())
+    }
+
+    /// Uninstalls Deno, removing it as the default along with every version that has been
+    /// fetched. Projects that pin a version of Deno fetch it again the next time it's run.
+    pub fn uninstall_deno(&mut self) -> Fallible<()> {
+        self.toolchain.get_mut()?.clear_deno()?;
+        github::remove_versions::<Deno>()?;
+        self.inventory.get_mut()?.deno.versions.clear();
+
+        info!("{} deno uninstalled", success_prefix());
+        Ok(())
+    }
+
+    /// Lists the changes that uninstalling Deno would make (see `uninstall_deno`).
+    pub fn plan_uninstall_deno(&mut self) -> Fallible<Vec<Change>> {
+        let mut changes = github::plan_remove_versions::<Deno>()?;
+        if self.toolchain.get()?.deno().is_some() {
+            changes.push(Change::Write(path::user_platform_file()?));
+        }
+        Ok(changes)
+    }
+
+    /// Sets a version of Bun matching the input requirements as the default, fetching it first
+    /// if it hasn't been already.
+    pub fn default_bun(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
+        let bun_distro = self.fetch_bun(version_spec)?.into_version();
+        shim::create("bun")?;
+        shim::create("bunx")?;
+        self.set_default_bun(bun_distro, "set")
+    }
+
+    fn set_default_bun(&mut self, bun_distro: Version, action: &str) -> Fallible<()> {
+        let success_message = format!("{} {} as default", action, tool_version("bun", &bun_distro));
+        let toolchain = self.toolchain.get_mut()?;
+
+        toolchain.set_active_bun(bun_distro)?;
+        info!("{} {}", success_prefix(), success_message);
+
+        Ok(())
+    }
+
+    /// Uninstalls Bun, removing it as the default along with every version that has been
+    /// fetched. Projects that pin a version of Bun fetch it again the next time it's run.
+    pub fn uninstall_bun(&mut self) -> Fallible<()> {
+        self.toolchain.get_mut()?.clear_bun()?;
+        github::remove_versions::<Bun>()?;
+        self.inventory.get_mut()?.bun.versions.clear();
+
+        info!("{} bun uninstalled", success_prefix());
+        Ok(())
+    }
+
+    /// Lists the changes that uninstalling Bun would make (see `uninstall_bun`).
+    pub fn plan_uninstall_bun(&mut self) -> Fallible<Vec<Change>> {
+        let mut changes = github::plan_remove_versions::<Bun>()?;
+        if self.toolchain.get()?.bun().is_some() {
+            changes.push(Change::Write(path::user_platform_file()?));
+        }
+        Ok(changes)
+    }
+
     /// Fetch, unpack, and install a version of Npm matching the input requirements.
     // ISSUE(#292): Install npm as part of the platform
     pub fn install_npm(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
@@ -212,9 +867,25 @@ impl Session {
         Ok(())
     }
 
+    /// Sets a version of npm matching the input requirements as the default, in place of the one
+    /// bundled with the default Node, fetching it first if it hasn't been already.
+    pub fn default_npm(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
+        if self.toolchain.get()?.platform_ref().is_none() {
+            throw!(ErrorDetails::NoUserNodeForNpm);
+        }
+
+        let npm_version = self.fetch_npm(version_spec)?.into_version().version;
+        let success_message = format!("set {} as default", tool_version("npm", &npm_version));
+        self.toolchain.get_mut()?.set_active_npm(npm_version)?;
+        info!("{} {}", success_prefix(), success_message);
+
+        Ok(())
+    }
+
     /// Fetch, unpack, and install a package matching the input requirements.
     pub fn install_package(&mut self, name: String, version: &VersionSpec) -> Fallible<Version> {
         // fetches and unpacks package
+        let spec = format!("{}@{}", name, version);
         let fetched_package = self.fetch_package(&name, version)?;
         let package_version = fetched_package.version();
 
@@ -228,6 +899,234 @@ impl Session {
             return Ok(version);
         }
 
+        self.install_package_version(package_version, spec, None)
+    }
+
+    /// Fetch a package from a git repository or local path and install it.
+    pub fn install_package_source(&mut self, source: &PackageSource) -> Fallible<Version> {
+        let package_version = self.fetch_package_source(source)?;
+        self.install_package_version(&package_version, source.to_spec(), None)
+    }
+
+    /// Re-resolves an installed package against the spec it was installed from (or the latest
+    /// version, if that isn't known) and reinstalls it. The image of the installed version is
+    /// kept until the new version has been installed successfully.
+    pub fn upgrade_package(&mut self, name: &str) -> Fallible<()> {
+        let config = installed_package_config(name)?;
+        if let Some(dir) = linked_dir(&config)? {
+            info!(
+                "Package `{}` is linked to {}, which is always up-to-date",
+                name,
+                dir.display()
+            );
+            return Ok(());
+        }
+
+        let spec = config
+            .spec
+            .clone()
+            .unwrap_or_else(|| format!("{}@latest", name));
+
+        let fetched = match ToolSpec::try_from_str(&spec)? {
+            ToolSpec::Package(name, version) => self.fetch_package(&name, &version)?,
+            ToolSpec::Npm(version) => self.fetch_package("npm", &version)?,
+            ToolSpec::PackageSource(source) => {
+                return self.upgrade_package_source(&source, &config, spec)
+            }
+            _ => throw!(ErrorDetails::ParseToolSpecError { tool_spec: spec }),
+        };
+
+        let package_version = match fetched {
+            Fetched::Installed(package_version) => {
+                info!(
+                    "Package `{}` is up-to-date, version {} already installed",
+                    name, package_version.version
+                );
+                return Ok(());
+            }
+            Fetched::Now(package_version) => package_version,
+        };
+
+        if let Err(error) = self.install_package_version(&package_version, spec, None) {
+            // the installed version is still configured, so only the new image is removed
+            ensure_dir_does_not_exist(&path::package_image_dir(
+                name,
+                &package_version.version.to_string(),
+            )?)?;
+            return Err(error);
+        }
+
+        remove_previous_version(&config, &package_version)
+    }
+
+    /// Rebuilds the native modules of an installed package against the default platform, for
+    /// use after that platform has changed.
+    pub fn rebuild_package(&mut self, name: &str) -> Fallible<()> {
+        let platform = match self.user_platform()? {
+            Some(platform) => platform,
+            None => throw!(ErrorDetails::NoPlatform),
+        };
+
+        PackageVersion::rebuild(name, &platform, self)?;
+        info!(
+            "{} rebuilt {} for {}",
+            success_prefix(),
+            name,
+            tool_version("node", &platform.node_runtime)
+        );
+        Ok(())
+    }
+
+    /// Links the package containing the current directory into the user toolchain, so that its
+    /// executables run from its source directory with the current platform.
+    pub fn link_package(&mut self) -> Fallible<()> {
+        let project = match self.project()? {
+            Some(project) => project,
+            None => throw!(ErrorDetails::LinkOutsidePackage),
+        };
+        let platform = match self.current_platform()? {
+            Some(platform) => platform,
+            None => throw!(ErrorDetails::NoPlatform),
+        };
+
+        let node_abi = self.node_abi(&platform.node_runtime)?;
+        let package_version = PackageVersion::link(project.project_root(), &platform, node_abi)?;
+        reconcile_shims();
+        let bin_list = package_version
+            .bins
+            .keys()
+            .map(|k| k.as_ref())
+            .collect::<Vec<&str>>()
+            .join(", ");
+        info!(
+            "{} linked {} with executables: {}",
+            success_prefix(),
+            tool_version(&package_version.name, &package_version.version),
+            bin_list
+        );
+        Ok(())
+    }
+
+    /// Removes a package linked with `volta link` from the user toolchain: either the specified
+    /// package or the package containing the current directory.
+    pub fn unlink_package(&mut self, name: Option<&str>) -> Fallible<()> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => match self.project()? {
+                Some(project) => {
+                    let root = project.project_root();
+                    PackageSource::Directory(root.to_path_buf())
+                        .read_name_and_version(root)?
+                        .0
+                }
+                None => throw!(ErrorDetails::LinkOutsidePackage),
+            },
+        };
+
+        PackageVersion::unlink(&name)?;
+        reconcile_shims();
+
+        info!("{} package '{}' unlinked", success_prefix(), name);
+        Ok(())
+    }
+
+    /// Makes an installed package provide the specified executables (or all of its executables),
+    /// in place of any other packages that also provide them.
+    pub fn prefer_package(&mut self, name: &str, bin_names: &[String]) -> Fallible<()> {
+        let bin_names = PackageVersion::prefer(name, bin_names)?;
+        info!(
+            "{} {} now provides executables: {}",
+            success_prefix(),
+            name,
+            bin_names.join(", ")
+        );
+        Ok(())
+    }
+
+    /// Loads the configs of every installed package, in order of name.
+    pub fn installed_packages(&self) -> Fallible<Vec<PackageConfig>> {
+        installed_package_configs()
+    }
+
+    /// Returns the paths to an executable in every installed package that provides it, starting
+    /// with the package that currently provides it.
+    pub fn package_bin_paths(&self, bin_name: &str) -> Fallible<Vec<PathBuf>> {
+        package_bin_paths(bin_name)
+    }
+
+    /// Upgrades every installed package. A failure to upgrade one package doesn't stop the others
+    /// from being upgraded; the failures are reported together once every package has been tried.
+    pub fn upgrade_all_packages(&mut self) -> Fallible<()> {
+        let packages = installed_packages()?;
+        if packages.is_empty() {
+            info!("No packages are installed");
+        }
+
+        let mut failures = Vec::new();
+        for name in packages {
+            if let Err(error) = self.upgrade_package(&name) {
+                warn!("Could not upgrade `{}`: {}", name, error);
+                failures.push(name);
+            }
+        }
+
+        if !failures.is_empty() {
+            throw!(ErrorDetails::UpgradePackagesError { packages: failures });
+        }
+
+        Ok(())
+    }
+
+    /// Fetches and reinstalls a package from its source. Fetching a source can produce the same
+    /// version again, which replaces the image in place, so the installed image is set aside and
+    /// only restored if the upgrade fails.
+    fn upgrade_package_source(
+        &mut self,
+        source: &PackageSource,
+        config: &PackageConfig,
+        spec: String,
+    ) -> Fallible<()> {
+        let image_dir = path::package_image_dir(&config.name, &config.version.to_string())?;
+        let backup = create_staging_dir()?;
+        let backup_dir = backup.path().join("image");
+        let setup_error = |_: &io::Error| ErrorDetails::SetupToolImageError {
+            tool: config.name.clone(),
+            version: config.version.to_string(),
+            dir: image_dir.clone(),
+        };
+
+        if image_dir.exists() {
+            rename(&image_dir, &backup_dir).with_context(setup_error)?;
+        }
+
+        let upgraded = self
+            .fetch_package_source(source)
+            .and_then(|package_version| {
+                self.install_package_version(&package_version, spec, None)?;
+                Ok(package_version)
+            });
+
+        match upgraded {
+            Ok(package_version) => remove_previous_version(config, &package_version),
+            Err(error) => {
+                if backup_dir.exists() {
+                    ensure_dir_does_not_exist(&image_dir)?;
+                    rename(&backup_dir, &image_dir).with_context(setup_error)?;
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Install a package that has been fetched and unpacked, along with its dependencies. The
+    /// package is installed with the specified version of Node if that version satisfies the
+    /// package's requirements, and otherwise with the latest matching version.
+    fn install_package_version(
+        &mut self,
+        package_version: &PackageVersion,
+        spec: String,
+        preferred_node: Option<&NodeVersion>,
+    ) -> Fallible<Version> {
         // This uses the "engines" field from package.json to determine the node version to use
         // From https://docs.npmjs.com/files/package.json#engines:
         //
@@ -239,16 +1138,20 @@ impl Session {
         //
         // If you specify an "engines" field, then npm will require that "node" be somewhere on that list. If "engines" is omitted, then npm will just assume that it works on node.
         let req_node_version = package_version.engines_spec()?;
-        let node_version = self.fetch_node(&req_node_version)?.into_version();
+        let node_version = match preferred_node {
+            Some(node) if satisfies(&req_node_version, &node.runtime) => node.clone(),
+            _ => self.fetch_node(&req_node_version)?.into_version(),
+        };
 
         let use_platform = Rc::new(PlatformSpec {
             node_runtime: node_version.runtime,
             npm: Some(node_version.npm),
             yarn: None,
+            arch: node_version.arch,
         });
 
         // finally, install the package
-        package_version.install(&use_platform, self)?;
+        package_version.install(&use_platform, &spec, self)?;
 
         let bin_list = package_version
             .bins
@@ -262,17 +1165,354 @@ impl Session {
             tool_version(&package_version.name, &package_version.version),
             bin_list
         );
+        reconcile_shims();
         Ok(package_version.version.clone())
     }
 
     /// Uninstall the specified package.
     pub fn uninstall_package(&self, name: String) -> Fallible<()> {
         PackageVersion::uninstall(&name)?;
+        reconcile_shims();
 
         info!("{} package '{}' uninstalled", success_prefix(), name);
         Ok(())
     }
 
+    /// Lists the changes that installing a version of Node, Yarn, Deno, or Bun makes, which
+    /// `apply` then makes unless this is a dry run (see `volta --dry-run`): fetching it unless it
+    /// has been fetched already and, if `set_default` is set, creating its missing shims and
+    /// saving it as the default unless it is already. The version is resolved, which may read
+    /// the index, but nothing is downloaded. `arch` only applies to Node.
+    pub fn plan_install(
+        &mut self,
+        tool: &ToolSpec,
+        arch: Option<&str>,
+        set_default: bool,
+    ) -> Fallible<Vec<Change>> {
+        let arch = match arch {
+            Some(arch) => Some(parse_arch(arch)?),
+            None => None,
+        };
+        let (name, version, fetch) = self.plan_fetch(tool, arch.as_ref().map(String::as_str))?;
+        let mut changes: Vec<Change> = fetch.into_iter().collect();
+        if !set_default {
+            return Ok(changes);
+        }
+
+        let toolchain = self.toolchain.get()?;
+        let unchanged = match name {
+            "node" => {
+                // The shims for the tools that come with Node are created along with a default,
+                // since an older install of Volta may be missing some of them, like `corepack`
+                plan_shims(&shim::BUILT_IN, &mut changes)?;
+                let npm = load_default_npm_version(&version).ok();
+                toolchain.platform_ref().map_or(false, |platform| {
+                    platform.node_runtime == version && platform.npm == npm && platform.arch == arch
+                })
+            }
+            // Yarn is only saved as the default once there is a default Node, which may be set
+            // by an earlier change in the same plan
+            "yarn" => toolchain
+                .platform_ref()
+                .map_or(false, |platform| platform.yarn.as_ref() == Some(&version)),
+            // The Deno and Bun shims aren't created by the Volta installer, since they are
+            // optional. They are created before the default is saved, so that the switch only
+            // takes effect once it's done
+            "deno" => {
+                plan_shims(&["deno"], &mut changes)?;
+                toolchain.deno() == Some(&version)
+            }
+            _ => {
+                plan_shims(&["bun", "bunx"], &mut changes)?;
+                toolchain.bun() == Some(&version)
+            }
+        };
+        if !unchanged {
+            changes.push(Change::SetDefault {
+                tool: name.to_string(),
+                version,
+                arch,
+                file: path::user_platform_file()?,
+            });
+        }
+        Ok(changes)
+    }
+
+    /// Makes the changes listed by `plan_install` or `plan_gc`, in order. The tools are fetched
+    /// concurrently first if there are several, so that they are found already fetched below.
+    pub fn apply(&mut self, changes: Vec<Change>) -> Fallible<()> {
+        let native = changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::Fetch {
+                    tool,
+                    version,
+                    arch: None,
+                    ..
+                } => Some(ToolSpec::from_str_and_version(
+                    tool,
+                    VersionSpec::exact(version),
+                )),
+                _ => None,
+            })
+            .collect();
+        self.fetch_concurrently(native)?;
+
+        let defaults: Vec<String> = changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::SetDefault { tool, .. } => Some(tool.clone()),
+                _ => None,
+            })
+            .collect();
+
+        for change in changes {
+            match change {
+                Change::Fetch {
+                    tool,
+                    version,
+                    arch,
+                    ..
+                } => {
+                    self.fetch_planned(&tool, &version, &arch)?;
+                    if !defaults.contains(&tool) {
+                        info!(
+                            "{} installed {} without changing the default",
+                            success_prefix(),
+                            describe_tool(&tool, &version, &arch)
+                        );
+                    }
+                }
+                Change::CreateShim { name, .. } => {
+                    shim::create(&name)?;
+                }
+                Change::Remove(file) => {
+                    debug!("Removing {}", file.display());
+                    remove_file(&file)
+                        .with_context(|_| ErrorDetails::DeleteFileError { file: file.clone() })?;
+                }
+                Change::SetDefault {
+                    tool,
+                    version,
+                    arch,
+                    ..
+                } => {
+                    let action = "installed and set";
+                    match tool.as_str() {
+                        "node" => {
+                            let node = self
+                                .fetch_node_for_arch(
+                                    &VersionSpec::exact(&version),
+                                    arch.as_ref().map(String::as_str),
+                                )?
+                                .into_version();
+                            self.set_default_node(node, action)?;
+                        }
+                        "yarn" => self.set_default_yarn(version, action)?,
+                        "deno" => self.set_default_deno(version, action)?,
+                        _ => self.set_default_bun(version, action)?,
+                    }
+                }
+                other => throw!(ErrorDetails::Unimplemented {
+                    feature: format!("Making the change `{}`", other)
+                }),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the exact version of a tool listed in a `Change::Fetch`.
+    fn fetch_planned(
+        &mut self,
+        tool: &str,
+        version: &Version,
+        arch: &Option<String>,
+    ) -> Fallible<()> {
+        match ToolSpec::from_str_and_version(tool, VersionSpec::exact(version)) {
+            ToolSpec::Node(version_spec) => {
+                self.fetch_node_for_arch(&version_spec, arch.as_ref().map(String::as_str))?;
+            }
+            ToolSpec::Yarn(version_spec) => {
+                self.fetch_yarn(&version_spec)?;
+            }
+            ToolSpec::Deno(version_spec) => {
+                self.fetch_deno(&version_spec)?;
+            }
+            ToolSpec::Bun(version_spec) => {
+                self.fetch_bun(&version_spec)?;
+            }
+            other => throw!(ErrorDetails::Unimplemented {
+                feature: format!("Fetching {}", other)
+            }),
+        }
+        Ok(())
+    }
+
+    /// Lists the changes that installing a package would make, without making them.
+    pub fn plan_install_package(
+        &self,
+        name: &str,
+        version_spec: &VersionSpec,
+    ) -> Fallible<Vec<Change>> {
+        let inventory = self.inventory.get()?;
+        let hooks = self.hooks.get()?;
+        inventory
+            .packages
+            .resolve(name, version_spec, hooks.package.as_ref())?
+            .plan_install()
+    }
+
+    /// Lists the cached archives that `volta gc` removes: those that the specified archive policy
+    /// doesn't keep, or that the configured policy doesn't keep if none is specified.
+    pub fn plan_gc(&self, keep: Option<&str>) -> Fallible<Vec<Change>> {
+        let keep = match keep {
+            Some(keep) => Some(keep.parse::<KeepArchives>()?),
+            None => None,
+        };
+        distro::plan_prune_archives(keep)
+    }
+
+    /// Lists the changes that uninstalling a package would make, without making them.
+    pub fn plan_uninstall_package(&self, name: &str) -> Fallible<Vec<Change>> {
+        PackageVersion::plan_uninstall(name)
+    }
+
+    /// Resolves the version of Node, Yarn, Deno, or Bun that fetching it would fetch, returning
+    /// the tool's name and version along with the change that fetching it makes, unless it has
+    /// been fetched already.
+    fn plan_fetch(
+        &self,
+        tool: &ToolSpec,
+        arch: Option<&str>,
+    ) -> Fallible<(&'static str, Version, Option<Change>)> {
+        let inventory = self.inventory.get()?;
+        let hooks = self.hooks.get()?;
+        let (name, version, fetched) = match tool {
+            ToolSpec::Node(version_spec) => {
+                let version =
+                    inventory
+                        .node
+                        .resolve_version("node", version_spec, hooks.node.as_ref())?;
+                // Builds for other architectures aren't tracked in the inventory
+                let fetched = match arch {
+                    Some(arch) => is_fetched_for_arch(&version, arch),
+                    None => inventory.node.contains(&version),
+                };
+                ("node", version, fetched)
+            }
+            ToolSpec::Yarn(version_spec) => {
+                let version =
+                    inventory
+                        .yarn
+                        .resolve_version("yarn", version_spec, hooks.yarn.as_ref())?;
+                let fetched = inventory.yarn.contains(&version);
+                ("yarn", version, fetched)
+            }
+            ToolSpec::Deno(version_spec) => {
+                let version =
+                    inventory
+                        .deno
+                        .resolve_version("deno", version_spec, hooks.deno.as_ref())?;
+                let fetched = inventory.deno.contains(&version);
+                ("deno", version, fetched)
+            }
+            ToolSpec::Bun(version_spec) => {
+                let version =
+                    inventory
+                        .bun
+                        .resolve_version("bun", version_spec, hooks.bun.as_ref())?;
+                let fetched = inventory.bun.contains(&version);
+                ("bun", version, fetched)
+            }
+            other => throw!(ErrorDetails::Unimplemented {
+                feature: format!("Fetching {}", other)
+            }),
+        };
+        if fetched {
+            return Ok((name, version, None));
+        }
+
+        let version_str = version.to_string();
+        let (into, image) = match name {
+            "node" => (
+                node::inventory_dir(&version)?,
+                path::node_image_version_dir(&version_str, arch)?,
+            ),
+            "yarn" => (
+                path::yarn_inventory_dir()?,
+                path::yarn_image_dir(&version_str)?,
+            ),
+            "deno" => (
+                path::deno_inventory_dir()?,
+                path::deno_image_dir(&version_str)?,
+            ),
+            _ => (
+                path::bun_inventory_dir()?,
+                path::bun_image_dir(&version_str)?,
+            ),
+        };
+        let fetch = Change::Fetch {
+            tool: name.to_string(),
+            version: version.clone(),
+            arch: arch.map(str::to_string),
+            into,
+            image,
+        };
+        Ok((name, version, Some(fetch)))
+    }
+
+    /// Fetches a Node version matching the specified semantic versioning requirements, for the
+    /// specified architecture or for the native one if no architecture is specified.
+    pub fn fetch_node_for_arch(
+        &mut self,
+        version_spec: &VersionSpec,
+        arch: Option<&str>,
+    ) -> Fallible<Fetched<NodeVersion>> {
+        let arch = match arch {
+            Some(arch) => Some(parse_arch(arch)?),
+            None => None,
+        };
+        let inventory = self.inventory.get_mut()?;
+        let hooks = self.hooks.get()?;
+        inventory.node.fetch_for_arch(
+            version_spec,
+            arch.as_ref().map(String::as_str),
+            hooks.node.as_ref(),
+        )
+    }
+
+    /// Finds the newest version of each recent release line of Node, and whether it is an LTS
+    /// line, for choosing a version from. Versions that the version policy doesn't allow are left
+    /// out.
+    pub fn node_choices(&self) -> Fallible<Vec<(Version, bool)>> {
+        let inventory = self.inventory.get()?;
+        let hooks = self.hooks.get()?;
+        let choices = inventory.node.recent_versions(hooks.node.as_ref())?;
+        match Policy::current()? {
+            Some(policy) => Ok(choices
+                .into_iter()
+                .filter(|(version, lts)| policy.allows_node(version, *lts))
+                .collect()),
+            None => Ok(choices),
+        }
+    }
+
+    /// Finds the newest version of each recent major version of Yarn, for choosing a version
+    /// from. Versions that the version policy doesn't allow are left out.
+    pub fn yarn_choices(&self) -> Fallible<Vec<Version>> {
+        let inventory = self.inventory.get()?;
+        let hooks = self.hooks.get()?;
+        let choices = inventory.yarn.recent_versions(hooks.yarn.as_ref())?;
+        match Policy::current()? {
+            Some(policy) => Ok(choices
+                .into_iter()
+                .filter(|version| policy.allows_yarn(version))
+                .collect()),
+            None => Ok(choices),
+        }
+    }
+
     /// Fetches a Node version matching the specified semantic versioning requirements.
     pub fn fetch_node(&mut self, version_spec: &VersionSpec) -> Fallible<Fetched<NodeVersion>> {
         let inventory = self.inventory.get_mut()?;
@@ -282,6 +1522,13 @@ impl Session {
             .fetch("node", &version_spec, hooks.node.as_ref())
     }
 
+    /// Fetches the headers of a fetched version of Node, so that node-gyp can build native modules
+    /// with it offline.
+    pub fn fetch_node_headers(&self, version: &Version) -> Fallible<()> {
+        let hooks = self.hooks.get()?;
+        fetch_headers(version, hooks.node.as_ref())
+    }
+
     /// Fetches a Yarn version matching the specified semantic versioning requirements.
     pub fn fetch_yarn(&mut self, version_spec: &VersionSpec) -> Fallible<Fetched<Version>> {
         let inventory = self.inventory.get_mut()?;
@@ -291,6 +1538,24 @@ impl Session {
             .fetch("yarn", &version_spec, hooks.yarn.as_ref())
     }
 
+    /// Fetches a Deno version matching the specified semantic versioning requirements.
+    pub fn fetch_deno(&mut self, version_spec: &VersionSpec) -> Fallible<Fetched<Version>> {
+        let inventory = self.inventory.get_mut()?;
+        let hooks = self.hooks.get()?;
+        inventory
+            .deno
+            .fetch("deno", &version_spec, hooks.deno.as_ref())
+    }
+
+    /// Fetches a Bun version matching the specified semantic versioning requirements.
+    pub fn fetch_bun(&mut self, version_spec: &VersionSpec) -> Fallible<Fetched<Version>> {
+        let inventory = self.inventory.get_mut()?;
+        let hooks = self.hooks.get()?;
+        inventory
+            .bun
+            .fetch("bun", &version_spec, hooks.bun.as_ref())
+    }
+
     /// Fetches a Npm version matching the specified semantic versioning requirements.
     pub fn fetch_npm(&mut self, version_spec: &VersionSpec) -> Fallible<Fetched<PackageVersion>> {
         let inventory = self.inventory.get_mut()?;
@@ -313,17 +1578,29 @@ impl Session {
             .fetch(name, version_spec, hooks.package.as_ref())
     }
 
+    /// Fetches a package from a git repository or local path.
+    pub fn fetch_package_source(&mut self, source: &PackageSource) -> Fallible<PackageVersion> {
+        source.fetch()
+    }
+
     /// Updates 'volta' in package.json with the Node version matching the specified semantic
-    /// versioning requirements.
-    pub fn pin_node(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
+    /// versioning requirements. If a range is given, it is pinned instead of the exact version,
+    /// which is locked alongside it.
+    pub fn pin_node(&mut self, version_spec: &VersionSpec, range: Option<String>) -> Fallible<()> {
         if let Some(ref project) = self.project()? {
             let node_version = self.fetch_node(version_spec)?.into_version();
-            project.pin_node(&node_version)?;
+            let pinned = match range {
+                Some(ref range) => format!(" for {}", range),
+                None => String::new(),
+            };
+            project.pin_node(&node_version, range)?;
             info!(
-                "{} pinned {} (with {}) in package.json",
+                "{} pinned {} (with {}){} in {}",
                 success_prefix(),
                 tool_version("node", node_version.runtime),
                 tool_version("npm", node_version.npm),
+                pinned,
+                project.manifest_file_name(),
             );
         } else {
             throw!(ErrorDetails::NotInPackage);
@@ -331,6 +1608,108 @@ impl Session {
         Ok(())
     }
 
+    /// Re-resolves the range that Node is pinned to in package.json, locking the latest version
+    /// that satisfies it.
+    pub fn update_node_pin(&mut self) -> Fallible<()> {
+        let (version_spec, range) = self.pinned_node_range()?;
+        self.pin_node(&version_spec, Some(range))
+    }
+
+    /// Lists the changes that updating the locked version of Node would make, without making
+    /// them.
+    pub fn plan_update_node_pin(&mut self) -> Fallible<Vec<Change>> {
+        let (version_spec, range) = self.pinned_node_range()?;
+        self.plan_pin(&[ToolSpec::Node(version_spec)], Some(range))
+    }
+
+    fn pinned_node_range(&self) -> Fallible<(VersionSpec, String)> {
+        let range = match self.project()? {
+            Some(ref project) => project.manifest().node_range(),
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+
+        match range {
+            Some(range) => {
+                let version_spec = VersionSpec::Semver(VersionSpec::parse_requirements(&range)?);
+                Ok((version_spec, range))
+            }
+            None => throw!(ErrorDetails::NoPinnedNodeRange),
+        }
+    }
+
+    /// Lists the changes that pinning tools in the project would make, without making them (see
+    /// `volta --dry-run`). Pinning a tool fetches it, so fetching it is listed as well. If a range
+    /// is given, Node is pinned to the range and the version it resolves to is locked.
+    pub fn plan_pin(
+        &mut self,
+        tools: &[ToolSpec],
+        node_range: Option<String>,
+    ) -> Fallible<Vec<Change>> {
+        let project = match self.project()? {
+            Some(project) => project,
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+        let file = project.project_root().join(project.manifest_file_name());
+        // A standalone `volta.json` holds the tools at the top level
+        let prefix = if project.package_file().is_file() {
+            "volta."
+        } else {
+            ""
+        };
+        let key = |name: &str| format!("{}{}", prefix, name);
+        let platform = project.manifest().platform();
+        let mut node_pinned = platform.is_some();
+        let mut changes = Vec::new();
+
+        for tool in tools {
+            match tool {
+                ToolSpec::Node(_) => {
+                    let (_, version, fetch) = self.plan_fetch(tool, None)?;
+                    changes.extend(fetch);
+                    match node_range {
+                        Some(ref range) => {
+                            changes.push(pin_change(&file, &key("node"), Some(range)));
+                            changes.push(pin_change(&file, &key("lock.node"), Some(&version)));
+                        }
+                        None => changes.push(pin_change(&file, &key("node"), Some(&version))),
+                    }
+                    // Pinning Node pins the npm bundled with it, which is pinned by leaving npm out
+                    if platform
+                        .as_ref()
+                        .map_or(false, |platform| platform.npm.is_some())
+                    {
+                        changes.push(pin_change(&file, &key("npm"), None::<String>));
+                    }
+                    node_pinned = true;
+                }
+                ToolSpec::Yarn(_) => {
+                    if !node_pinned {
+                        throw!(ErrorDetails::NoPinnedNodeVersion);
+                    }
+                    let (_, version, fetch) = self.plan_fetch(tool, None)?;
+                    changes.extend(fetch);
+                    changes.push(pin_change(&file, &key("yarn"), Some(&version)));
+                }
+                ToolSpec::Deno(_) => {
+                    let (_, version, fetch) = self.plan_fetch(tool, None)?;
+                    changes.extend(fetch);
+                    changes.push(pin_change(&file, &key("deno"), Some(&version)));
+                    plan_shims(&["deno"], &mut changes)?;
+                }
+                ToolSpec::Bun(_) => {
+                    let (_, version, fetch) = self.plan_fetch(tool, None)?;
+                    changes.extend(fetch);
+                    changes.push(pin_change(&file, &key("bun"), Some(&version)));
+                    plan_shims(&["bun", "bunx"], &mut changes)?;
+                }
+                // `volta pin` reports that the other tools can't be pinned before planning
+                _ => {}
+            }
+        }
+
+        Ok(changes)
+    }
+
     /// Updates 'volta' in package.json with the Yarn version matching the specified semantic
     /// versioning requirements.
     pub fn pin_yarn(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
@@ -338,9 +1717,56 @@ impl Session {
             let yarn_version = self.fetch_yarn(version_spec)?.into_version();
             project.pin_yarn(&yarn_version)?;
             info!(
-                "{} pinned {} in package.json",
+                "{} pinned {} in {}",
                 success_prefix(),
-                tool_version("yarn", yarn_version)
+                tool_version("yarn", &yarn_version),
+                project.manifest_file_name(),
+            );
+            if let Some(yarn_path) = project.yarn_path() {
+                info!(
+                    "This project's .yarnrc.yml sets `yarnPath` to {}, so {} will defer to that release of Yarn.",
+                    yarn_path,
+                    tool_version("yarn", yarn_version)
+                );
+            }
+        } else {
+            throw!(ErrorDetails::NotInPackage);
+        }
+        Ok(())
+    }
+
+    /// Updates 'volta' in package.json with the Deno version matching the specified semantic
+    /// versioning requirements.
+    pub fn pin_deno(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
+        if let Some(ref project) = self.project()? {
+            let deno_version = self.fetch_deno(version_spec)?.into_version();
+            project.pin_deno(&deno_version)?;
+            shim::create("deno")?;
+            info!(
+                "{} pinned {} in {}",
+                success_prefix(),
+                tool_version("deno", &deno_version),
+                project.manifest_file_name(),
+            );
+        } else {
+            throw!(ErrorDetails::NotInPackage);
+        }
+        Ok(())
+    }
+
+    /// Updates 'volta' in package.json with the Bun version matching the specified semantic
+    /// versioning requirements.
+    pub fn pin_bun(&mut self, version_spec: &VersionSpec) -> Fallible<()> {
+        if let Some(ref project) = self.project()? {
+            let bun_version = self.fetch_bun(version_spec)?.into_version();
+            project.pin_bun(&bun_version)?;
+            shim::create("bun")?;
+            shim::create("bunx")?;
+            info!(
+                "{} pinned {} in {}",
+                success_prefix(),
+                tool_version("bun", &bun_version),
+                project.manifest_file_name(),
             );
         } else {
             throw!(ErrorDetails::NotInPackage);
@@ -360,9 +1786,10 @@ impl Session {
                 .version;
             project.pin_npm(&npm_version)?;
             info!(
-                "{} pinned {} in package.json",
+                "{} pinned {} in {}",
                 success_prefix(),
-                tool_version("npm", npm_version)
+                tool_version("npm", npm_version),
+                project.manifest_file_name(),
             );
         } else {
             throw!(ErrorDetails::NotInPackage);
@@ -370,6 +1797,119 @@ impl Session {
         Ok(())
     }
 
+    /// Pins the SHA-256s of the archives that the Node and Yarn pinned in the current project are
+    /// installed from, fetching them first if they haven't been.
+    pub fn pin_sha256(&mut self) -> Fallible<()> {
+        let project_root = match self.project()? {
+            Some(ref project) => project.project_root().to_path_buf(),
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+        // The project may have been loaded before its pins were written, so it is read again
+        let project = match Project::for_dir(&project_root)? {
+            Some(project) => project,
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+        let platform = match project.platform() {
+            Some(platform) => platform,
+            None => throw!(ErrorDetails::NoPinnedPlatform {
+                action: "pin the SHA-256 of Node".into()
+            }),
+        };
+
+        // The tools aren't checked against the SHA-256s that are pinned already, since pinning
+        // again is how a pin that is out of date gets replaced
+        let arch = platform.arch.as_ref().map(String::as_str);
+        {
+            let inventory = self.inventory.get_mut()?;
+            let hooks = self.hooks.get()?;
+            inventory.node.fetch_version(
+                platform.node_runtime.clone(),
+                arch,
+                &ArchiveHashes::new(),
+                hooks.node.as_ref(),
+            )?;
+            if let Some(ref yarn_version) = platform.yarn {
+                inventory.yarn.fetch_version(
+                    yarn_version.clone(),
+                    &ArchiveHashes::new(),
+                    hooks.yarn.as_ref(),
+                )?;
+            }
+        }
+        let node_sha256 = node::archive_sha256(&platform.node_runtime, arch)?;
+        let yarn_sha256 = match platform.yarn {
+            Some(ref yarn_version) => Some(yarn::archive_sha256(yarn_version)?),
+            None => None,
+        };
+
+        project.pin_sha256(node_sha256, yarn_sha256)?;
+        info!(
+            "{} pinned the SHA-256 of {} in {}",
+            success_prefix(),
+            match platform.yarn {
+                Some(ref yarn_version) => format!(
+                    "{} and {}",
+                    tool_version("node", &platform.node_runtime),
+                    tool_version("yarn", yarn_version)
+                ),
+                None => tool_version("node", &platform.node_runtime),
+            },
+            project.manifest_file_name(),
+        );
+        Ok(())
+    }
+
+    /// Gets the root directories of the workspace packages in the current project.
+    pub fn workspace_roots(&self) -> Fallible<Vec<PathBuf>> {
+        let roots = match self.project()? {
+            Some(ref project) => project.workspace_roots()?,
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+
+        if roots.is_empty() {
+            throw!(ErrorDetails::NoWorkspaces);
+        }
+        Ok(roots)
+    }
+
+    /// Copies the versions of the specified tools that are pinned in the current project into
+    /// 'volta' in the package.json of each of its workspace packages.
+    pub fn pin_workspaces(&self, workspace_roots: &[PathBuf], tools: &[ToolSpec]) -> Fallible<()> {
+        let project_root = match self.project()? {
+            Some(ref project) => project.project_root().to_path_buf(),
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+        // The project was loaded before its pins were written, so its manifest is read again
+        let pinned = Manifest::for_dir(&project_root)?;
+
+        // Every workspace's pins are worked out before any are written, so that a workspace that
+        // can't be pinned leaves all of them unchanged
+        let mut planned = Vec::new();
+        for workspace_root in workspace_roots {
+            if let Some(workspace) = Project::for_dir(workspace_root)? {
+                let toolchain = workspace.toolchain_matching(&pinned, tools)?;
+                planned.push((workspace, toolchain));
+            }
+        }
+
+        for (workspace, toolchain) in planned {
+            workspace.write_toolchain(toolchain)?;
+
+            let package_file = workspace
+                .project_root()
+                .join(workspace.manifest_file_name());
+            info!(
+                "{} pinned the same versions in {}",
+                success_prefix(),
+                package_file
+                    .strip_prefix(&project_root)
+                    .unwrap_or(&package_file)
+                    .display()
+            );
+        }
+        Ok(())
+    }
+
     /// Gets the installed UserTool with the input name, if any.
     pub fn get_user_tool(&mut self, tool_name: &OsStr) -> Fallible<Option<UserTool>> {
         match tool_name.to_str() {
@@ -378,6 +1918,29 @@ impl Session {
         }
     }
 
+    /// Returns the kind of activity with a name, for a plugin to record its events with. Other
+    /// than the built-in kinds, only the kinds that are declared in the `events.kinds` section
+    /// of the hooks can be used, and their events are sampled by name like any others.
+    pub fn activity_kind(&self, name: &str) -> Fallible<ActivityKind> {
+        if let Some(kind) = ActivityKind::built_in(name) {
+            return Ok(kind);
+        }
+
+        let hooks = self.hooks.get()?;
+        let declared = hooks
+            .events
+            .as_ref()
+            .map(|events| events.kinds.iter().any(|kind| kind == name))
+            .unwrap_or(false);
+        if !declared {
+            throw!(ErrorDetails::UndeclaredActivityKind {
+                name: name.to_string()
+            });
+        }
+
+        ActivityKind::register(name)
+    }
+
     pub fn add_event_start(&mut self, activity_kind: ActivityKind) {
         self.event_log.add_event_start(activity_kind)
     }
@@ -392,6 +1955,22 @@ impl Session {
     }
 
     fn publish_to_event_log(mut self) {
+        match activity_rates(&self.hooks) {
+            Ok(Some(rates)) => self.event_log.sample(rates),
+            Ok(None) => {}
+            Err(e) => debug!("Unable to read which activities are recorded.\n{}", e),
+        }
+
+        if path::is_read_only_home() {
+            debug!("Not writing local event log, since the Volta directory is read-only.");
+        } else if flag_enabled(EVENT_LOG) {
+            if let Err(e) =
+                path::event_log_file().and_then(|file| self.event_log.write_to_file(&file))
+            {
+                debug!("Unable to write local event log.\n{}", e);
+            }
+        }
+
         match publish_plugin(&self.hooks) {
             Ok(plugin) => {
                 self.event_log.publish(plugin);
@@ -413,6 +1992,14 @@ impl Session {
     }
 }
 
+fn activity_rates(hooks: &LazyHookConfig) -> Fallible<Option<&ActivityRates>> {
+    let hooks = hooks.get()?;
+    Ok(hooks
+        .events
+        .as_ref()
+        .and_then(|events| events.activities.as_ref()))
+}
+
 fn publish_plugin(hooks: &LazyHookConfig) -> Fallible<Option<&Publish>> {
     let hooks = hooks.get()?;
     Ok(hooks
@@ -421,10 +2008,59 @@ fn publish_plugin(hooks: &LazyHookConfig) -> Fallible<Option<&Publish>> {
         .and_then(|events| events.publish.as_ref()))
 }
 
+/// Removes what is left of the previous version of an upgraded package: its image, if the
+/// version changed, and any executables that the new version no longer provides.
+fn remove_previous_version(previous: &PackageConfig, current: &PackageVersion) -> Fallible<()> {
+    if previous.version != current.version {
+        ensure_dir_does_not_exist(&path::package_image_dir(
+            &previous.name,
+            &previous.version.to_string(),
+        )?)?;
+    }
+
+    for bin_name in previous
+        .bins
+        .iter()
+        .filter(|bin_name| !current.bins.contains_key(*bin_name))
+    {
+        PackageVersion::remove_config_and_shim(bin_name, &previous.name)?;
+    }
+
+    Ok(())
+}
+
+/// Brings the shims in line with the installed packages after a package operation. The operation
+/// has already succeeded by then, so a failure is only reported.
+fn reconcile_shims() {
+    if let Err(error) = shim::reconcile() {
+        warn!("Could not reconcile shims: {}", error);
+    }
+}
+
+/// Lists the package specs in the contents of a `default-packages` file, skipping blank lines
+/// and comments.
+fn default_package_specs(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Determines whether a version of Node meets a package's `engines` requirements.
+fn satisfies(engines: &VersionSpec, node_version: &Version) -> bool {
+    match engines {
+        VersionSpec::Semver(requirement) => requirement.matches(node_version),
+        VersionSpec::Exact(version) => version == node_version,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
 
-    use crate::session::Session;
+    use crate::session::{default_package_specs, satisfies, Session};
+    use crate::version::VersionSpec;
+    use semver::Version;
     use std::env;
     use std::path::PathBuf;
 
@@ -453,4 +2089,30 @@ pub mod tests {
             .expect("Couldn't create Project");
         assert_eq!(unpinned_platform.is_none(), true);
     }
+
+    #[test]
+    fn lists_default_package_specs() {
+        let contents = "# tools for every Node\n\ntypescript@3\n  ember-cli  \n# yarn\n";
+        assert_eq!(
+            default_package_specs(contents).collect::<Vec<_>>(),
+            vec!["typescript@3", "ember-cli"]
+        );
+    }
+
+    #[test]
+    fn checks_engines_against_node_versions() {
+        let node = Version::parse("10.16.0").unwrap();
+
+        let range = VersionSpec::Semver(VersionSpec::parse_requirements(">=8").unwrap());
+        assert!(satisfies(&range, &node));
+
+        let range = VersionSpec::Semver(VersionSpec::parse_requirements("^12").unwrap());
+        assert!(!satisfies(&range, &node));
+
+        assert!(satisfies(
+            &VersionSpec::Exact(Version::parse("10.16.0").unwrap()),
+            &node
+        ));
+        assert!(!satisfies(&VersionSpec::Lts, &node));
+    }
 }
@@ -9,12 +9,21 @@ use crate::fs::ensure_containing_dir_exists;
 use volta_fail::{Fallible, ResultExt, VoltaError};
 
 use crate::env;
+use crate::path::volta_home;
 
 mod bash;
 mod fish;
+mod nushell;
+mod powershell;
+mod profile;
 
 pub(crate) use self::bash::Bash;
 pub(crate) use self::fish::Fish;
+pub(crate) use self::nushell::Nushell;
+pub(crate) use self::powershell::PowerShell;
+pub use self::profile::{
+    plan_remove_profiles, plan_setup_profiles, remove_profiles, setup_profiles, ProfileShell,
+};
 
 pub enum Postscript {
     Activate(String),
@@ -72,6 +81,14 @@ impl FromStr for CurrentShell {
         match src {
             "bash" => Ok(CurrentShell(Box::new(Bash { postscript_path }))),
             "fish" => Ok(CurrentShell(Box::new(Fish { postscript_path }))),
+            "powershell" | "pwsh" => Ok(CurrentShell(Box::new(PowerShell {
+                postscript_path,
+                volta_home: volta_home()?,
+            }))),
+            "nushell" | "nu" => Ok(CurrentShell(Box::new(Nushell {
+                postscript_path,
+                volta_home: volta_home()?,
+            }))),
             _ => Err(ErrorDetails::UnrecognizedShell {
                 name: src.to_string(),
             }
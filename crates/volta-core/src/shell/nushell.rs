@@ -0,0 +1,124 @@
+use std::env::split_paths;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use super::{Postscript, Shell};
+
+/// Nushell, which can't source a script that is only written while it runs. Instead, the
+/// postscript is a JSON record of the variables to set and the variables to hide, which the
+/// `volta` command in `load.nu` applies with `load-env` and `hide-env`.
+pub(crate) struct Nushell {
+    pub(crate) postscript_path: PathBuf,
+    pub(crate) volta_home: PathBuf,
+}
+
+/// Nushell keeps the Windows capitalization of the `PATH` variable.
+#[cfg(windows)]
+static PATH_VAR: &'static str = "Path";
+#[cfg(not(windows))]
+static PATH_VAR: &'static str = "PATH";
+
+impl Shell for Nushell {
+    fn postscript_path(&self) -> &Path {
+        &self.postscript_path
+    }
+
+    fn compile_postscript(&self, postscript: &Postscript) -> String {
+        let changes = match postscript {
+            &Postscript::Activate(ref s) => json!({
+                "set": {
+                    PATH_VAR: path_list(s),
+                    "VOLTA_HOME": self.volta_home.to_string_lossy(),
+                },
+                "unset": [],
+            }),
+            &Postscript::Deactivate(ref s) => json!({
+                "set": { PATH_VAR: path_list(s) },
+                "unset": ["VOLTA_HOME"],
+            }),
+            &Postscript::ToolVersion {
+                ref tool,
+                ref version,
+            } => json!({
+                "set": {
+                    format!("VOLTA_{}_VERSION", tool.to_ascii_uppercase()): version.to_string(),
+                },
+                "unset": [],
+            }),
+        };
+        format!("{}\n", changes)
+    }
+}
+
+/// Splits the `PATH` into the list that Nushell keeps it as, at the separator of the platform.
+fn path_list(path: &str) -> Value {
+    split_paths(path)
+        .map(|dir| Value::String(dir.to_string_lossy().into_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+    use std::env::join_paths;
+    use std::path::PathBuf;
+
+    use super::{Nushell, PATH_VAR};
+    use crate::shell::{Postscript, Shell};
+
+    fn nushell() -> Nushell {
+        Nushell {
+            postscript_path: PathBuf::from("volta_tmp_1234.json"),
+            volta_home: PathBuf::from("/home/\"quoted\"/.volta"),
+        }
+    }
+
+    fn path(dirs: &[&str]) -> String {
+        join_paths(dirs).unwrap().into_string().unwrap()
+    }
+
+    #[test]
+    fn test_compile_postscript() {
+        let nushell = nushell();
+
+        assert_eq!(
+            nushell.compile_postscript(&Postscript::Deactivate(path(&["/first", "/second"]))),
+            format!(
+                "{{\"set\":{{\"{}\":[\"/first\",\"/second\"]}},\"unset\":[\"VOLTA_HOME\"]}}\n",
+                PATH_VAR
+            )
+        );
+
+        assert_eq!(
+            nushell.compile_postscript(&Postscript::ToolVersion {
+                tool: "test".to_string(),
+                version: Version::parse("2.4.5").unwrap()
+            }),
+            "{\"set\":{\"VOLTA_TEST_VERSION\":\"2.4.5\"},\"unset\":[]}\n"
+        );
+
+        assert_eq!(
+            nushell.compile_postscript(&Postscript::Activate(path(&["/single'quotes'"]))),
+            format!(
+                "{{\"set\":{{\"{}\":[\"/single'quotes'\"],\"VOLTA_HOME\":\"/home/\\\"quoted\\\"/.volta\"}},\"unset\":[]}}\n",
+                PATH_VAR
+            )
+        );
+    }
+
+    #[test]
+    fn test_path_uses_platform_separator() {
+        let nushell = nushell();
+        let colons = nushell.compile_postscript(&Postscript::Deactivate("/a:/b".to_string()));
+        let semicolons = nushell.compile_postscript(&Postscript::Deactivate("/a;/b".to_string()));
+
+        if cfg!(windows) {
+            assert!(semicolons.contains("[\"/a\",\"/b\"]"));
+            assert!(colons.contains("[\"/a:/b\"]"));
+        } else {
+            assert!(colons.contains("[\"/a\",\"/b\"]"));
+            assert!(semicolons.contains("[\"/a;/b\"]"));
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+
+use super::{Postscript, Shell};
+
+/// PowerShell, both Windows PowerShell and the cross-platform PowerShell Core. The `PATH` is
+/// written as it is, since it already uses the separator of the platform that PowerShell is
+/// running on: `;` on Windows and `:` everywhere else.
+pub(crate) struct PowerShell {
+    pub(crate) postscript_path: PathBuf,
+    pub(crate) volta_home: PathBuf,
+}
+
+static UNSET_VOLTA_HOME: &'static str =
+    "Remove-Item Env:VOLTA_HOME -ErrorAction SilentlyContinue\n";
+
+impl Shell for PowerShell {
+    fn postscript_path(&self) -> &Path {
+        &self.postscript_path
+    }
+
+    fn compile_postscript(&self, postscript: &Postscript) -> String {
+        match postscript {
+            &Postscript::Activate(ref s) => format!(
+                "$env:PATH = '{}'\n$env:VOLTA_HOME = '{}'\n",
+                quote(s),
+                quote(&self.volta_home.to_string_lossy())
+            ),
+            &Postscript::Deactivate(ref s) => {
+                format!("$env:PATH = '{}'\n", quote(s)) + UNSET_VOLTA_HOME
+            }
+            &Postscript::ToolVersion {
+                ref tool,
+                ref version,
+            } => format!(
+                "$env:VOLTA_{}_VERSION = '{}'\n",
+                tool.to_ascii_uppercase(),
+                version
+            ),
+        }
+    }
+}
+
+/// Escapes a value for a single-quoted string, in which only quotes are special.
+fn quote(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+    use std::env::join_paths;
+    use std::path::PathBuf;
+
+    use super::PowerShell;
+    use crate::shell::{Postscript, Shell};
+
+    fn powershell() -> PowerShell {
+        PowerShell {
+            postscript_path: PathBuf::from("volta_tmp_1234.ps1"),
+            volta_home: PathBuf::from("/home/o'neil/.volta"),
+        }
+    }
+
+    #[test]
+    fn test_compile_postscript() {
+        let powershell = powershell();
+
+        assert_eq!(
+            powershell.compile_postscript(&Postscript::Deactivate("some:path".to_string())),
+            String::from("$env:PATH = 'some:path'\n") + super::UNSET_VOLTA_HOME
+        );
+
+        assert_eq!(
+            powershell.compile_postscript(&Postscript::Deactivate(
+                "/path:/with:/single'quotes'".to_string()
+            )),
+            String::from("$env:PATH = '/path:/with:/single''quotes'''\n") + super::UNSET_VOLTA_HOME
+        );
+
+        assert_eq!(
+            powershell.compile_postscript(&Postscript::ToolVersion {
+                tool: "test".to_string(),
+                version: Version::parse("2.4.5").unwrap()
+            }),
+            "$env:VOLTA_TEST_VERSION = '2.4.5'\n"
+        );
+
+        assert_eq!(
+            powershell.compile_postscript(&Postscript::Activate("some:path".to_string())),
+            "$env:PATH = 'some:path'\n$env:VOLTA_HOME = '/home/o''neil/.volta'\n"
+        );
+    }
+
+    #[test]
+    fn test_path_uses_platform_separator() {
+        let path = join_paths(&["/first", "/second"])
+            .unwrap()
+            .into_string()
+            .unwrap();
+        let expected = if cfg!(windows) {
+            "$env:PATH = '/first;/second'\n"
+        } else {
+            "$env:PATH = '/first:/second'\n"
+        };
+
+        assert!(powershell()
+            .compile_postscript(&Postscript::Deactivate(path))
+            .starts_with(expected));
+    }
+}
@@ -0,0 +1,457 @@
+//! Provides the startup files of each shell that `volta setup` configures, and the marked
+//! blocks it writes to them so that they can later be updated or removed.
+
+use std::env;
+use std::fs::write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use dirs;
+use log::info;
+
+use crate::error::ErrorDetails;
+use crate::fs::{ensure_containing_dir_exists, read_file_opt};
+use crate::path;
+use crate::plan::Change;
+use crate::style::success_prefix;
+use volta_fail::{throw, Fallible, ResultExt, VoltaError};
+
+/// The first line of the block that Volta manages in a profile.
+const BEGIN_MARKER: &str = "# BEGIN volta (managed by `volta setup`)";
+
+/// The last line of the block that Volta manages in a profile.
+const END_MARKER: &str = "# END volta";
+
+/// A shell whose startup files `volta setup` can configure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProfileShell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+    Nushell,
+}
+
+impl ProfileShell {
+    pub const ALL: [ProfileShell; 5] = [
+        ProfileShell::Bash,
+        ProfileShell::Zsh,
+        ProfileShell::Fish,
+        ProfileShell::PowerShell,
+        ProfileShell::Nushell,
+    ];
+
+    pub const NAMES: [&'static str; 5] = ["bash", "zsh", "fish", "powershell", "nushell"];
+
+    /// The shells that have a profile file on this machine.
+    pub fn detect() -> Fallible<Vec<ProfileShell>> {
+        let mut shells = Vec::new();
+        for &shell in ProfileShell::ALL.iter() {
+            if shell.profile_files()?.iter().any(|file| file.is_file()) {
+                shells.push(shell);
+            }
+        }
+        Ok(shells)
+    }
+
+    /// The files this shell reads at startup, in the order they are preferred for configuring.
+    fn profile_files(self) -> Fallible<Vec<PathBuf>> {
+        let home = dirs::home_dir().ok_or(ErrorDetails::NoHomeEnvironmentVar)?;
+        let files = match self {
+            // macOS terminals start login shells, which read `.bash_profile` instead of `.bashrc`
+            ProfileShell::Bash if cfg!(target_os = "macos") => {
+                vec![home.join(".bash_profile"), home.join(".bashrc")]
+            }
+            ProfileShell::Bash => vec![home.join(".bashrc"), home.join(".bash_profile")],
+            ProfileShell::Zsh => {
+                let dir = env::var_os("ZDOTDIR").map_or(home, PathBuf::from);
+                vec![dir.join(".zshrc")]
+            }
+            ProfileShell::Fish => vec![xdg_config_dir(&home).join("fish").join("config.fish")],
+            ProfileShell::PowerShell if cfg!(windows) => {
+                let documents = dirs::document_dir().unwrap_or_else(|| home.join("Documents"));
+                vec![
+                    documents
+                        .join("PowerShell")
+                        .join("Microsoft.PowerShell_profile.ps1"),
+                    documents
+                        .join("WindowsPowerShell")
+                        .join("Microsoft.PowerShell_profile.ps1"),
+                ]
+            }
+            ProfileShell::PowerShell => vec![xdg_config_dir(&home)
+                .join("powershell")
+                .join("Microsoft.PowerShell_profile.ps1")],
+            ProfileShell::Nushell => {
+                let config = dirs::config_dir().unwrap_or_else(|| xdg_config_dir(&home));
+                vec![config.join("nushell").join("env.nu")]
+            }
+        };
+        Ok(files)
+    }
+
+    /// The file to configure: the first profile file that exists, or else the preferred one.
+    fn profile_file(self) -> Fallible<PathBuf> {
+        let files = self.profile_files()?;
+        Ok(files
+            .iter()
+            .find(|file| file.is_file())
+            .unwrap_or(&files[0])
+            .to_path_buf())
+    }
+
+    /// The file that loads the `volta` command for this shell, if it has to be configured apart
+    /// from the profile file. Nushell runs `env.nu` before `config.nu`, and only the latter can
+    /// define commands.
+    fn loader_file(self) -> Fallible<Option<PathBuf>> {
+        match self {
+            ProfileShell::Nushell => {
+                let home = dirs::home_dir().ok_or(ErrorDetails::NoHomeEnvironmentVar)?;
+                let config = dirs::config_dir().unwrap_or_else(|| xdg_config_dir(&home));
+                Ok(Some(config.join("nushell").join("config.nu")))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The managed blocks that configuring this shell writes, along with the files that they are
+    /// written to.
+    fn blocks(self, volta_home: &Path) -> Fallible<Vec<(PathBuf, String)>> {
+        let mut blocks = vec![(self.profile_file()?, self.config(volta_home))];
+        if let Some(file) = self.loader_file()? {
+            blocks.push((file, self.loader(volta_home)));
+        }
+        Ok(blocks)
+    }
+
+    /// Every file that configuring this shell may have written a managed block to.
+    fn all_files(self) -> Fallible<Vec<PathBuf>> {
+        let mut files = self.profile_files()?;
+        files.extend(self.loader_file()?);
+        Ok(files)
+    }
+
+    /// The line that loads the `volta` command, for the shells with a `loader_file`. Nushell only
+    /// sources files by a path that is known when the file is parsed, so the path is written out.
+    fn loader(self, volta_home: &Path) -> String {
+        let load = volta_home.join("load.nu");
+        format!(
+            "source \"{}\"",
+            load.to_string_lossy()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        )
+    }
+
+    /// The lines that set `VOLTA_HOME` and add the shim directory to `PATH`.
+    fn config(self, volta_home: &Path) -> String {
+        let home = volta_home.to_string_lossy();
+        match self {
+            ProfileShell::Bash | ProfileShell::Zsh => format!(
+                "export VOLTA_HOME='{}'\nexport PATH=\"$VOLTA_HOME/bin:$PATH\"",
+                home.replace('\'', "'\\''")
+            ),
+            ProfileShell::Fish => format!(
+                "set -gx VOLTA_HOME '{}'\nset -gx PATH \"$VOLTA_HOME/bin\" $PATH",
+                home.replace('\\', "\\\\").replace('\'', "\\'")
+            ),
+            ProfileShell::PowerShell => format!(
+                "$env:VOLTA_HOME = '{}'\n$env:PATH = (Join-Path $env:VOLTA_HOME 'bin') + [IO.Path]::PathSeparator + $env:PATH",
+                home.replace('\'', "''")
+            ),
+            ProfileShell::Nushell => format!(
+                "$env.VOLTA_HOME = \"{}\"\n$env.PATH = ($env.PATH | split row (char esep) | prepend ($env.VOLTA_HOME | path join 'bin'))",
+                home.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+        }
+    }
+}
+
+impl FromStr for ProfileShell {
+    type Err = VoltaError;
+
+    fn from_str(src: &str) -> Result<Self, VoltaError> {
+        match src.to_ascii_lowercase().as_str() {
+            "bash" => Ok(ProfileShell::Bash),
+            "zsh" => Ok(ProfileShell::Zsh),
+            "fish" => Ok(ProfileShell::Fish),
+            "powershell" | "pwsh" => Ok(ProfileShell::PowerShell),
+            "nushell" | "nu" => Ok(ProfileShell::Nushell),
+            _ => Err(ErrorDetails::UnrecognizedShell {
+                name: src.to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+/// Adds Volta's configuration to the profile of each shell, or updates it if it is already
+/// there, and reports each file.
+pub fn setup_profiles(shells: &[ProfileShell]) -> Fallible<()> {
+    if shells.is_empty() {
+        throw!(ErrorDetails::NoShellProfiles);
+    }
+
+    let volta_home = path::volta_home()?;
+    for &shell in shells {
+        for (file, config) in shell.blocks(&volta_home)? {
+            let src = read_profile(&file)?.unwrap_or_default();
+            let updated = with_block(&src, &config);
+
+            if updated == src {
+                info!("Volta is already set up in {}", file.display());
+            } else {
+                write_profile(&file, &updated)?;
+                info!("{} set up Volta in {}", success_prefix(), file.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes Volta's configuration from every profile file of each shell, and reports each file
+/// that was changed.
+pub fn remove_profiles(shells: &[ProfileShell]) -> Fallible<()> {
+    let mut removed = false;
+    for &shell in shells {
+        for file in shell.all_files()? {
+            let src = match read_profile(&file)? {
+                Some(src) => src,
+                None => continue,
+            };
+
+            if let Some(updated) = without_block(&src) {
+                write_profile(&file, &updated)?;
+                info!("{} removed Volta from {}", success_prefix(), file.display());
+                removed = true;
+            }
+        }
+    }
+
+    if !removed {
+        info!("Volta is not set up in any shell profiles");
+    }
+    Ok(())
+}
+
+/// Lists the profile files that `setup_profiles` would write, without writing them (see
+/// `volta --dry-run`).
+pub fn plan_setup_profiles(shells: &[ProfileShell]) -> Fallible<Vec<Change>> {
+    if shells.is_empty() {
+        throw!(ErrorDetails::NoShellProfiles);
+    }
+
+    let volta_home = path::volta_home()?;
+    let mut changes = Vec::new();
+    for &shell in shells {
+        for (file, config) in shell.blocks(&volta_home)? {
+            let src = read_profile(&file)?.unwrap_or_default();
+            if with_block(&src, &config) != src {
+                changes.push(Change::Write(file));
+            }
+        }
+    }
+    Ok(changes)
+}
+
+/// Lists the profile files that `remove_profiles` would write, without writing them.
+pub fn plan_remove_profiles(shells: &[ProfileShell]) -> Fallible<Vec<Change>> {
+    let mut changes = Vec::new();
+    for &shell in shells {
+        for file in shell.all_files()? {
+            if let Some(src) = read_profile(&file)? {
+                if without_block(&src).is_some() {
+                    changes.push(Change::Write(file));
+                }
+            }
+        }
+    }
+    Ok(changes)
+}
+
+fn xdg_config_dir(home: &Path) -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME").map_or_else(|| home.join(".config"), PathBuf::from)
+}
+
+fn read_profile(file: &Path) -> Fallible<Option<String>> {
+    read_file_opt(&file.to_path_buf()).with_context(|_| ErrorDetails::ReadShellProfileError {
+        file: file.to_path_buf(),
+    })
+}
+
+fn write_profile(file: &Path, src: &str) -> Fallible<()> {
+    ensure_containing_dir_exists(&file)?;
+    write(file, src).with_context(|_| ErrorDetails::WriteShellProfileError {
+        file: file.to_path_buf(),
+    })
+}
+
+/// The span of the managed block in a profile, including the line ending after it.
+fn find_block(src: &str) -> Option<(usize, usize)> {
+    let start = src.find(BEGIN_MARKER)?;
+    let end = start + src[start..].find(END_MARKER)? + END_MARKER.len();
+    let end = match src[end..].find('\n') {
+        Some(newline) if src[end..end + newline].trim().is_empty() => end + newline + 1,
+        _ => end,
+    };
+    Some((start, end))
+}
+
+/// Puts the configuration in the managed block of a profile, replacing the block if it is
+/// already there and appending it otherwise.
+fn with_block(src: &str, config: &str) -> String {
+    let newline = if src.contains("\r\n") { "\r\n" } else { "\n" };
+    let block = format!("{}\n{}\n{}\n", BEGIN_MARKER, config, END_MARKER).replace('\n', newline);
+
+    match find_block(src) {
+        Some((start, end)) => format!("{}{}{}", &src[..start], block, &src[end..]),
+        None if src.trim().is_empty() => block,
+        None => {
+            let separator = if src.ends_with(newline) {
+                newline.to_string()
+            } else {
+                format!("{}{}", newline, newline)
+            };
+            format!("{}{}{}", src, separator, block)
+        }
+    }
+}
+
+/// Removes the managed block from a profile, along with the blank line that separated it from
+/// the rest of the file. Returns `None` if there is no block.
+fn without_block(src: &str) -> Option<String> {
+    let (start, end) = find_block(src)?;
+    let before = &src[..start];
+    let after = &src[end..];
+
+    let before = if after.trim().is_empty() {
+        before.trim_end_matches(&['\r', '\n'][..])
+    } else {
+        before
+    };
+    let newline = if src.contains("\r\n") { "\r\n" } else { "\n" };
+    let before = if before.is_empty() || before.ends_with('\n') {
+        before.to_string()
+    } else {
+        format!("{}{}", before, newline)
+    };
+
+    Some(format!(
+        "{}{}",
+        before,
+        after.trim_start_matches(&['\r', '\n'][..])
+    ))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{with_block, without_block, ProfileShell, BEGIN_MARKER, END_MARKER};
+    use std::path::Path;
+
+    #[test]
+    fn adds_block() {
+        let block = format!("{}\nexport A=1\n{}\n", BEGIN_MARKER, END_MARKER);
+
+        assert_eq!(with_block("", "export A=1"), block);
+        assert_eq!(
+            with_block("alias ll='ls -l'\n", "export A=1"),
+            format!("alias ll='ls -l'\n\n{}", block)
+        );
+        assert_eq!(
+            with_block("alias ll='ls -l'", "export A=1"),
+            format!("alias ll='ls -l'\n\n{}", block)
+        );
+    }
+
+    #[test]
+    fn updates_block() {
+        let src = format!(
+            "alias ll='ls -l'\n\n{}\nexport A=1\n{}\nexport B=2\n",
+            BEGIN_MARKER, END_MARKER
+        );
+        let updated = with_block(&src, "export A=3");
+
+        assert_eq!(
+            updated,
+            format!(
+                "alias ll='ls -l'\n\n{}\nexport A=3\n{}\nexport B=2\n",
+                BEGIN_MARKER, END_MARKER
+            )
+        );
+        assert_eq!(with_block(&updated, "export A=3"), updated);
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let updated = with_block("$a = 1\r\n", "$b = 2");
+        assert_eq!(
+            updated,
+            format!(
+                "$a = 1\r\n\r\n{}\r\n$b = 2\r\n{}\r\n",
+                BEGIN_MARKER, END_MARKER
+            )
+        );
+        assert_eq!(without_block(&updated).unwrap(), "$a = 1\r\n");
+    }
+
+    #[test]
+    fn removes_block() {
+        let original = "alias ll='ls -l'\n";
+        assert_eq!(
+            without_block(&with_block(original, "export A=1")).unwrap(),
+            original
+        );
+
+        let src = format!(
+            "export Z=0\n{}\nexport A=1\n{}\nexport B=2\n",
+            BEGIN_MARKER, END_MARKER
+        );
+        assert_eq!(without_block(&src).unwrap(), "export Z=0\nexport B=2\n");
+
+        assert_eq!(without_block(original), None);
+    }
+
+    #[test]
+    fn quotes_volta_home() {
+        let home = Path::new("/home/o'neil/.volta");
+
+        assert_eq!(
+            ProfileShell::Bash.config(home),
+            "export VOLTA_HOME='/home/o'\\''neil/.volta'\nexport PATH=\"$VOLTA_HOME/bin:$PATH\""
+        );
+        assert_eq!(
+            ProfileShell::Fish.config(home),
+            "set -gx VOLTA_HOME '/home/o\\'neil/.volta'\nset -gx PATH \"$VOLTA_HOME/bin\" $PATH"
+        );
+        assert!(ProfileShell::PowerShell
+            .config(home)
+            .starts_with("$env:VOLTA_HOME = '/home/o''neil/.volta'\n"));
+        assert!(ProfileShell::Nushell
+            .config(home)
+            .starts_with("$env.VOLTA_HOME = \"/home/o'neil/.volta\"\n"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn sources_nushell_loader() {
+        assert_eq!(
+            ProfileShell::Nushell.loader(Path::new("/home/user/.volta")),
+            "source \"/home/user/.volta/load.nu\""
+        );
+        assert_eq!(
+            ProfileShell::Nushell.loader(Path::new("/home/\"quoted\"/.volta")),
+            "source \"/home/\\\"quoted\\\"/.volta/load.nu\""
+        );
+    }
+
+    #[test]
+    fn parses_shell_names() {
+        assert_eq!(
+            "pwsh".parse::<ProfileShell>().unwrap(),
+            ProfileShell::PowerShell
+        );
+        assert_eq!("Zsh".parse::<ProfileShell>().unwrap(), ProfileShell::Zsh);
+        assert!("tcsh".parse::<ProfileShell>().is_err());
+    }
+}
@@ -1,10 +1,16 @@
 //! Provides utilities for modifying shims for 3rd-party executables
 
+use std::collections::BTreeSet;
+use std::path::Path;
 use std::{fs, io};
 
+use crate::distro::package::bin_owner;
 use crate::error::ErrorDetails;
+use crate::fs::{read_dir_eager, read_file_opt};
 use crate::path;
-use volta_fail::{throw, FailExt, Fallible};
+use crate::permissions;
+use log::debug;
+use volta_fail::{throw, FailExt, Fallible, ResultExt};
 
 #[derive(PartialEq)]
 pub enum ShimResult {
@@ -16,11 +22,206 @@ pub enum ShimResult {
 
 fn is_3p_shim(name: &str) -> bool {
     match name {
-        "node" | "yarn" | "npm" | "npx" => false,
+        "node" | "yarn" | "npm" | "npx" | "corepack" | "deno" | "bun" | "bunx" => false,
         _ => true,
     }
 }
 
+/// A shim in the shim directory.
+pub struct ShimInfo {
+    pub name: String,
+    /// Whether the shim is for one of the tools that Volta provides, rather than a package.
+    pub built_in: bool,
+    /// The installed package that provides the shim's executable, if any.
+    pub package: Option<String>,
+}
+
+/// Lists the shims in the shim directory, sorted by name.
+pub fn list() -> Fallible<Vec<ShimInfo>> {
+    let shim_dir = path::shim_dir()?;
+    let entries = read_dir_eager(&shim_dir).with_context(|_| ErrorDetails::ReadShimDirError {
+        dir: shim_dir.clone(),
+    })?;
+
+    let mut shims = Vec::new();
+    for (entry, metadata) in entries {
+        if metadata.is_dir() {
+            continue;
+        }
+        let file_name = entry.file_name();
+        if let Some(name) = file_name.to_str().and_then(tool_name) {
+            shims.push(ShimInfo {
+                name: name.to_string(),
+                built_in: !is_3p_shim(name),
+                package: bin_owner(name)?,
+            });
+        }
+    }
+
+    shims.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(shims)
+}
+
+/// Gets the name of the tool that a file in the shim directory is the shim for.
+#[cfg(unix)]
+fn tool_name(file_name: &str) -> Option<&str> {
+    Some(file_name)
+}
+
+/// Gets the name of the tool that a file in the shim directory is the shim for. The Git Bash
+/// scripts that are next to the shims have no extension, so they are skipped.
+#[cfg(windows)]
+fn tool_name(file_name: &str) -> Option<&str> {
+    if file_name.ends_with(".exe") {
+        Some(file_name.trim_end_matches(".exe"))
+    } else {
+        None
+    }
+}
+
+/// Creates a shim for an executable that no installed package provides, such as a project's own
+/// binary, and records it so that reconciling the shims keeps it.
+pub fn add(shim_name: &str) -> Fallible<ShimResult> {
+    let result = create(shim_name)?;
+    if is_3p_shim(shim_name) {
+        let mut custom = read_custom_shims()?;
+        if custom.insert(shim_name.to_string()) {
+            write_custom_shims(&custom)?;
+        }
+    }
+    Ok(result)
+}
+
+/// Removes a shim, along with the record of it if it was added with `add`.
+pub fn remove(shim_name: &str) -> Fallible<ShimResult> {
+    let result = delete(shim_name)?;
+    let mut custom = read_custom_shims()?;
+    if custom.remove(shim_name) {
+        write_custom_shims(&custom)?;
+    }
+    Ok(result)
+}
+
+/// The changes made by reconciling the shims.
+#[derive(Default)]
+pub struct Reconciled {
+    pub created: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Brings the shims for 3rd-party executables in line with the binaries of the packages
+/// installed by every profile and the shims added with `add`. Missing shims are created, and
+/// shims for executables that nothing provides are removed. The built-in shims are left alone.
+pub fn reconcile() -> Fallible<Reconciled> {
+    let reconciled = plan_reconcile()?;
+    for name in &reconciled.removed {
+        delete(name)?;
+    }
+    for name in &reconciled.created {
+        create(name)?;
+    }
+
+    debug!(
+        "Reconciled shims, created: {:?}, removed: {:?}",
+        reconciled.created, reconciled.removed
+    );
+    Ok(reconciled)
+}
+
+/// Lists the shims that reconciling would create and remove, without changing them (see
+/// `volta --dry-run`).
+pub fn plan_reconcile() -> Fallible<Reconciled> {
+    let mut expected = read_custom_shims()?;
+    for bin_dir in path::all_user_bin_dirs()? {
+        expected.extend(bin_names_in(&bin_dir)?);
+    }
+
+    let mut reconciled = Reconciled::default();
+    let existing = list()?;
+    for shim in &existing {
+        if !shim.built_in && !expected.contains(&shim.name) {
+            reconciled.removed.push(shim.name.clone());
+        }
+    }
+    for name in expected {
+        if !existing.iter().any(|shim| shim.name == name) {
+            reconciled.created.push(name);
+        }
+    }
+    Ok(reconciled)
+}
+
+/// Gets the names of the executables whose configs are in a directory of bin configs.
+fn bin_names_in(bin_dir: &Path) -> Fallible<Vec<String>> {
+    let entries = match read_dir_eager(bin_dir) {
+        Ok(entries) => entries,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => {
+            throw!(error.with_context(|_| ErrorDetails::ReadBinConfigDirError {
+                dir: bin_dir.to_path_buf(),
+            }))
+        }
+    };
+
+    Ok(entries
+        .filter(|(_, metadata)| metadata.is_file())
+        .filter_map(|(entry, _)| {
+            let path = entry.path();
+            match path.extension() {
+                Some(extension) if extension == "json" => path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .map(String::from),
+                _ => None,
+            }
+        })
+        .collect())
+}
+
+fn read_custom_shims() -> Fallible<BTreeSet<String>> {
+    let file = path::custom_shims_file()?;
+    let src = read_file_opt(&file)
+        .with_context(|_| ErrorDetails::ReadCustomShimsError { file: file.clone() })?;
+
+    match src {
+        Some(src) => {
+            serde_json::from_str(&src).with_context(|_| ErrorDetails::ReadCustomShimsError { file })
+        }
+        None => Ok(BTreeSet::new()),
+    }
+}
+
+fn write_custom_shims(custom: &BTreeSet<String>) -> Fallible<()> {
+    let file = path::custom_shims_file()?;
+    let src = serde_json::to_string_pretty(custom).unknown()?;
+    fs::write(&file, src)
+        .with_context(|_| ErrorDetails::WriteCustomShimsError { file: file.clone() })?;
+    permissions::apply(&file)
+}
+
+/// The shims for Node and the tools that come with it, which every install of Volta has.
+pub const BUILT_IN: [&str; 5] = ["node", "npm", "npx", "yarn", "corepack"];
+
+/// Creates whichever of the built-in shims are missing, such as the `corepack` shim in an install
+/// of Volta that predates it.
+pub fn create_built_in() -> Fallible<()> {
+    for name in BUILT_IN.iter() {
+        create(name)?;
+    }
+    Ok(())
+}
+
+/// Lists the built-in shims that are missing (see `create_built_in`).
+pub fn missing_built_in() -> Fallible<Vec<&'static str>> {
+    let mut missing = Vec::new();
+    for name in BUILT_IN.iter() {
+        if fs::symlink_metadata(path::shim_file(name)?).is_err() {
+            missing.push(*name);
+        }
+    }
+    Ok(missing)
+}
+
 pub fn create(shim_name: &str) -> Fallible<ShimResult> {
     let executable = path::shim_executable()?;
     let shim = path::shim_file(shim_name)?;
@@ -29,7 +230,10 @@ pub fn create(shim_name: &str) -> Fallible<ShimResult> {
     windows::create_git_bash_script(shim_name)?;
 
     match path::create_file_symlink(executable, shim) {
-        Ok(_) => Ok(ShimResult::Created),
+        Ok(_) => {
+            permissions::apply(&path::shim_dir()?)?;
+            Ok(ShimResult::Created)
+        }
         Err(err) => {
             if err.kind() == io::ErrorKind::AlreadyExists {
                 Ok(ShimResult::AlreadyExists)
@@ -105,3 +309,27 @@ mod windows {
         })
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::bin_names_in;
+    use std::fs::{create_dir, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn finds_bin_names() {
+        let dir = tempdir().unwrap();
+        write(dir.path().join("tsc.json"), "{}").unwrap();
+        write(dir.path().join("ember.json"), "{}").unwrap();
+        write(dir.path().join("notes.txt"), "").unwrap();
+        create_dir(dir.path().join("nested.json")).unwrap();
+
+        let mut names = bin_names_in(dir.path()).unwrap();
+        names.sort();
+        assert_eq!(names, vec!["ember", "tsc"]);
+
+        assert!(bin_names_in(&dir.path().join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+}
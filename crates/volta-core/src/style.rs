@@ -1,13 +1,31 @@
 //! The view layer of Volta, with utilities for styling command-line output.
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
 use archive::Origin;
+use atty::Stream;
 use console::{style, StyledObject};
 use failure::Fail;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use term_size;
 
+use crate::env::{flag_enabled, NO_PROGRESS};
+
 const MAX_WIDTH: usize = 100;
 const MAX_PROGRESS_WIDTH: usize = 40;
 
+/// How often progress is reported when it is written as lines of text instead of drawn as a bar.
+const PROGRESS_LINE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Set by `--no-progress`, in addition to `VOLTA_NO_PROGRESS`.
+static PROGRESS_DISABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// A bar from a `ProgressGroup` to use for the next progress bar created on this thread.
+    static ASSIGNED_BAR: RefCell<Option<ProgressBar>> = RefCell::new(None);
+}
+
 /// Generate the styled prefix for a success message
 pub(crate) fn success_prefix() -> StyledObject<&'static str> {
     style("success:").green().bold()
@@ -44,12 +62,139 @@ pub fn text_width() -> Option<usize> {
     term_size::dimensions().map(|(w, _)| w.min(MAX_WIDTH))
 }
 
+/// Replaces animated progress bars and spinners with plain lines of text for the rest of this
+/// process, as if `VOLTA_NO_PROGRESS` were set.
+pub fn disable_progress() {
+    PROGRESS_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Determines whether progress can be animated: it can't be when stderr isn't a terminal (e.g.
+/// in CI logs), or when it has been turned off with `--no-progress` or `VOLTA_NO_PROGRESS`.
+fn progress_animated() -> bool {
+    !PROGRESS_DISABLED.load(Ordering::Relaxed)
+        && !flag_enabled(NO_PROGRESS)
+        && atty::is(Stream::Stderr)
+}
+
+/// The progress of a long-running operation, drawn as a bar in a terminal or otherwise reported
+/// as a line of text every few seconds.
+pub struct Progress {
+    display: ProgressDisplay,
+}
+
+enum ProgressDisplay {
+    Bar(ProgressBar),
+    Lines(ProgressLines),
+}
+
+struct ProgressLines {
+    message: String,
+    len: u64,
+    position: Cell<u64>,
+    last_report: Cell<Instant>,
+}
+
+impl Progress {
+    /// Advances the progress by the specified number of steps.
+    pub fn inc(&self, delta: u64) {
+        match self.display {
+            ProgressDisplay::Bar(ref bar) => bar.inc(delta),
+            ProgressDisplay::Lines(ref lines) => {
+                lines.position.set(lines.position.get() + delta);
+                if lines.last_report.get().elapsed() >= PROGRESS_LINE_INTERVAL {
+                    lines.report();
+                }
+            }
+        }
+    }
+
+    /// Marks the progress as complete, clearing the bar or reporting the final total.
+    pub fn finish_and_clear(&self) {
+        match self.display {
+            ProgressDisplay::Bar(ref bar) => bar.finish_and_clear(),
+            ProgressDisplay::Lines(ref lines) => {
+                lines.position.set(lines.len);
+                lines.report();
+            }
+        }
+    }
+}
+
+impl ProgressLines {
+    fn report(&self) {
+        let position = self.position.get().min(self.len);
+        let percent = if self.len == 0 {
+            100
+        } else {
+            position * 100 / self.len
+        };
+        eprintln!(
+            "{}: {} / {} ({}%)",
+            self.message,
+            format_bytes(position),
+            format_bytes(self.len),
+            percent
+        );
+        self.last_report.set(Instant::now());
+    }
+}
+
+/// Formats a number of bytes for progress messages, e.g. `12.3 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
 /// Constructs a command-line progress bar based on the specified Origin enum
 /// (e.g., `Origin::Remote`), details string (e.g., `"v1.23.4"`), and logical
 /// length (i.e., the number of logical progress steps in the process being
 /// visualized by the progress bar).
-pub fn progress_bar(origin: Origin, details: &str, len: u64) -> ProgressBar {
+///
+/// If progress can't be animated, the progress is instead reported as a line of text when it
+/// starts, every few seconds while it continues, and when it finishes.
+pub fn progress_bar(origin: Origin, details: &str, len: u64) -> Progress {
     let action = action_str(origin);
+
+    if !progress_animated() {
+        let lines = ProgressLines {
+            message: format!("{} {}", action, details),
+            len,
+            position: Cell::new(0),
+            last_report: Cell::new(Instant::now()),
+        };
+        lines.report();
+        return Progress {
+            display: ProgressDisplay::Lines(lines),
+        };
+    }
+
+    let bar = match ASSIGNED_BAR.with(|assigned| assigned.borrow_mut().take()) {
+        Some(bar) => {
+            bar.set_length(len);
+            bar
+        }
+        None => ProgressBar::new(len),
+    };
+    style_bar(&bar, action, details);
+
+    Progress {
+        display: ProgressDisplay::Bar(bar),
+    }
+}
+
+fn style_bar(bar: &ProgressBar, action: &str, details: &str) {
     let action_width = action.len() + 2; // plus 2 spaces to look nice
     let msg_width = action_width + 1 + details.len();
 
@@ -61,8 +206,6 @@ pub fn progress_bar(origin: Origin, details: &str, len: u64) -> ProgressBar {
         None => MAX_PROGRESS_WIDTH,
     };
 
-    let bar = ProgressBar::new(len);
-
     bar.set_message(&format!(
         "{: >width$} {}",
         style(action).green().bold(),
@@ -77,13 +220,76 @@ pub fn progress_bar(origin: Origin, details: &str, len: u64) -> ProgressBar {
             ))
             .progress_chars("=> "),
     );
+}
+
+/// Progress bars for several operations running at once on different threads, which are drawn
+/// together. If progress can't be animated, each operation reports its own progress as lines.
+pub(crate) struct ProgressGroup {
+    multi: Option<MultiProgress>,
+}
+
+impl ProgressGroup {
+    pub(crate) fn new() -> Self {
+        ProgressGroup {
+            multi: if progress_animated() {
+                Some(MultiProgress::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Adds a bar to the group for an operation that is about to start, which is shown as
+    /// fetching until the operation creates its own progress bar.
+    pub(crate) fn add(&self, details: &str) -> Option<ProgressBar> {
+        self.multi.as_ref().map(|multi| {
+            let bar = multi.add(ProgressBar::new(0));
+            style_bar(&bar, action_str(Origin::Remote), details);
+            bar
+        })
+    }
 
-    bar
+    /// Draws the bars until every operation has finished.
+    pub(crate) fn join(self) {
+        if let Some(multi) = self.multi {
+            let _ = multi.join_and_clear();
+        }
+    }
+}
+
+/// Runs an operation with a bar from a `ProgressGroup`, which is used by the first progress bar
+/// that the operation creates. The bar is finished when the operation ends, even if it fails or
+/// never creates a progress bar, so that the group doesn't wait for it forever.
+pub(crate) fn with_progress_bar<T, F>(bar: Option<ProgressBar>, operation: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    struct FinishOnDrop(Option<ProgressBar>);
+
+    impl Drop for FinishOnDrop {
+        fn drop(&mut self) {
+            ASSIGNED_BAR.with(|assigned| assigned.borrow_mut().take());
+            if let Some(ref bar) = self.0 {
+                bar.finish_and_clear();
+            }
+        }
+    }
+
+    let _finish = FinishOnDrop(bar.clone());
+    ASSIGNED_BAR.with(|assigned| *assigned.borrow_mut() = bar);
+    operation()
 }
 
 /// Constructs a command-line progress spinner with the specified "message"
 /// string. The spinner is ticked by default every 50ms.
+///
+/// If progress can't be animated, the message is written once as a line of text instead.
 pub fn progress_spinner(message: &str) -> ProgressBar {
+    if !progress_animated() {
+        eprintln!("{}", message);
+        return ProgressBar::hidden();
+    }
+
     // ⠋ Fetching public registry: https://nodejs.org/dist/index.json
     let spinner = ProgressBar::new_spinner();
 
@@ -93,3 +299,17 @@ pub fn progress_spinner(message: &str) -> ProgressBar {
 
     spinner
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::format_bytes;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(30 * 1024 * 1024 + 104_858), "30.1 MiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
@@ -1,80 +1,447 @@
-use std::ffi::OsString;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Display, Formatter};
 use std::iter::once;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use super::ToolCommand;
+use super::{explain_platform, pnp, ToolCommand};
+use crate::distro::package::{BinLoader, UserTool};
+use crate::env::{flag_enabled, TRANSITIVE_BINS};
 use crate::error::ErrorDetails;
+use crate::platform::System;
+use crate::project::Project;
 use crate::session::{ActivityKind, Session};
 
+use cfg_if::cfg_if;
+use log::debug;
 use volta_fail::{throw, Fallible};
 
+/// Where the shim for a binary finds it, as decided by `resolve`.
+enum Resolution {
+    /// The binary is run from the project, for the given reason.
+    Project {
+        project: Rc<Project>,
+        reason: ProjectBinReason,
+        local_bin: LocalBin,
+    },
+    /// The binary is run from a package in the user toolchain.
+    User(UserTool),
+    /// The binary isn't provided by the project or the user toolchain, so it is run from the
+    /// system `PATH`.
+    System,
+}
+
+/// Where a binary that is run from the project is found in it.
+enum LocalBin {
+    /// The binary is in the project's `node_modules/.bin`.
+    Found(PathBuf, Option<BinLoader>),
+    /// The project uses Plug'n'Play, so there is no `node_modules/.bin`, and the binary has to be
+    /// resolved through the PnP runtime from the package that provides it.
+    PnP { pnp_file: PathBuf, package: String },
+    /// The binary should be in the project, but isn't.
+    Missing,
+}
+
+/// Decides where a binary is run from: the project, if the binary should come from it; the user
+/// toolchain, if a package there provides it; or the system `PATH` otherwise. Both running the
+/// binary (`command`) and explaining how it's run (`explain`) go through this.
+fn resolve(exe: &OsStr, session: &mut Session) -> Fallible<Resolution> {
+    if let Some(project) = session.project()? {
+        if let Some(reason) = project_bin_reason(&project, exe)? {
+            debug!(
+                "Using {} from the project, since {}",
+                exe.to_string_lossy(),
+                reason
+            );
+            let local_bin = match find_local_bin(&project.local_bin_dir(), exe) {
+                Some((path_to_bin, loader)) => LocalBin::Found(path_to_bin, loader),
+                None => match (project.pnp_file(), project.direct_bin_package(exe)?) {
+                    (Some(pnp_file), Some(package)) => LocalBin::PnP { pnp_file, package },
+                    _ => LocalBin::Missing,
+                },
+            };
+            return Ok(Resolution::Project {
+                project,
+                reason,
+                local_bin,
+            });
+        }
+
+        if resolve_transitive_bins() {
+            debug!(
+                "{} is not in the project's node_modules/.bin, falling back to the user toolchain",
+                exe.to_string_lossy()
+            );
+        }
+    }
+
+    match session.get_user_tool(exe)? {
+        Some(user_tool) => Ok(Resolution::User(user_tool)),
+        None => Ok(Resolution::System),
+    }
+}
+
 pub(super) fn command<A>(exe: OsString, args: A, session: &mut Session) -> Fallible<ToolCommand>
 where
     A: IntoIterator<Item = OsString>,
 {
     session.add_event_start(ActivityKind::Binary);
 
-    // first try to use the project toolchain
-    if let Some(project) = session.project()? {
-        // check if the executable is a direct dependency
-        if project.has_direct_bin(&exe)? {
-            // use the full path to the file
-            let mut path_to_bin = project.local_bin_dir();
-            path_to_bin.push(&exe);
-
-            if !path_to_bin.is_file() {
-                throw!(ErrorDetails::ProjectLocalBinaryNotFound {
-                    command: path_to_bin.to_string_lossy().to_string(),
-                });
-            }
+    match resolve(&exe, session)? {
+        Resolution::Project {
+            project, local_bin, ..
+        } => {
+            // use the project's pins over the user platform
+            let platform = session.current_platform()?;
+            let image = match platform {
+                Some(ref platform) => Some(platform.checkout(session)?),
+                None => None,
+            };
+            let path = match image {
+                Some(ref image) => Some(image.path()?),
+                None => None,
+            };
+
+            let (path_to_bin, loader) = match local_bin {
+                LocalBin::Found(path_to_bin, loader) => (path_to_bin, loader),
+                LocalBin::PnP { pnp_file, package } => {
+                    let path_var = match path {
+                        Some(ref path) => path.clone(),
+                        None => System::path()?,
+                    };
+                    pnp::find_bin(&project, &pnp_file, &package, &exe, &path_var)?
+                }
+                LocalBin::Missing => throw!(ErrorDetails::ProjectLocalBinaryNotFound {
+                    command: project.local_bin_dir().join(&exe),
+                }),
+            };
+
+            let env = loader_env(&loader);
+            let (bin, args) = with_loader(path_to_bin.into_os_string(), loader, args);
+
+            let mut command = match path {
+                Some(path) => {
+                    let command = ToolCommand::project_local(&bin, args, &path);
+                    match image {
+                        Some(ref image) => command.with_node(&image.node.runtime),
+                        None => command,
+                    }
+                }
+                // if there's no user platform selected, pass through to existing PATH.
+                None => ToolCommand::passthrough(&bin, args, ErrorDetails::NoPlatform)?,
+            };
+            command.envs(&env);
+            Ok(command)
+        }
+        Resolution::User(user_tool) => {
+            let path = user_tool.image.path()?;
+            let env = loader_env(&user_tool.loader);
+            let (bin, args) =
+                with_loader(user_tool.bin_path.into_os_string(), user_tool.loader, args);
+            let mut command =
+                ToolCommand::direct(&bin, args, &path).with_node(&user_tool.image.node.runtime);
+            command.envs(&env);
+            Ok(command)
+        }
+        // there is no project or user toolchain that provides the binary, so it passes through
+        // to the existing PATH
+        Resolution::System => ToolCommand::passthrough(
+            &exe,
+            args,
+            ErrorDetails::BinaryNotFound {
+                name: exe.to_string_lossy().to_string(),
+            },
+        ),
+    }
+}
 
-            let path_to_bin = path_to_bin.as_os_str();
+/// Explains how `command` resolves a binary, adding each step it takes to `steps`.
+pub(super) fn explain(exe: &OsStr, session: &mut Session, steps: &mut Vec<String>) -> Fallible<()> {
+    let name = exe.to_string_lossy();
 
-            // if we're in a pinned project, use the project's platform.
-            if let Some(ref platform) = project.platform() {
-                let image = platform.checkout(session)?;
-                let path = image.path()?;
-                return Ok(ToolCommand::project_local(&path_to_bin, args, &path));
+    let resolution = resolve(exe, session)?;
+    match session.project()? {
+        Some(project) => steps.push(format!(
+            "Found a project at {}",
+            project.project_root().display()
+        )),
+        None => steps.push("Not in a project".to_string()),
+    }
+
+    match resolution {
+        Resolution::Project {
+            project,
+            reason,
+            local_bin,
+        } => {
+            steps.push(format!("Using {} from the project, since {}", name, reason));
+            explain_platform(session, steps)?;
+
+            match local_bin {
+                LocalBin::Found(path_to_bin, _) => {
+                    steps.push(format!("Found {}", path_to_bin.display()))
+                }
+                LocalBin::PnP { package, .. } => steps.push(format!(
+                    "Resolving {} from the {} package through Plug'n'Play",
+                    name, package
+                )),
+                LocalBin::Missing => steps.push(format!(
+                    "{} is not in {}",
+                    name,
+                    project.local_bin_dir().display()
+                )),
             }
+        }
+        Resolution::User(user_tool) => {
+            if session.project()?.is_some() {
+                steps.push(not_from_project(&name));
+            }
+            steps.push(format!(
+                "Using {} from your toolchain, at {}",
+                name,
+                user_tool.bin_path.display()
+            ));
+        }
+        Resolution::System => {
+            if session.project()?.is_some() {
+                steps.push(not_from_project(&name));
+            }
+            steps.push(format!(
+                "{} is not installed in your toolchain, so the system PATH is used",
+                name
+            ));
+        }
+    }
+
+    Ok(())
+}
 
-            // otherwise use the user platform.
-            if let Some(ref platform) = session.user_platform()? {
-                let image = platform.checkout(session)?;
-                let path = image.path()?;
-                return Ok(ToolCommand::project_local(&path_to_bin, args, &path));
+fn not_from_project(name: &str) -> String {
+    format!(
+        "{} is not provided by a direct dependency of the project",
+        name
+    )
+}
+
+/// The reason that a binary is run from the project's `node_modules/.bin`.
+enum ProjectBinReason {
+    /// The binary is provided by a direct dependency.
+    DirectDependency,
+    /// The binary is in `node_modules/.bin` and `VOLTA_TRANSITIVE_BINS` is set.
+    TransitiveBins,
+}
+
+impl Display for ProjectBinReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectBinReason::DirectDependency => {
+                f.write_str("it is provided by a direct dependency")
             }
+            ProjectBinReason::TransitiveBins => write!(
+                f,
+                "it is in node_modules/.bin and {} is set",
+                TRANSITIVE_BINS
+            ),
+        }
+    }
+}
+
+/// Determines whether a binary should be run from the project's `node_modules/.bin`, and why.
+///
+/// Binaries provided by direct dependencies always take precedence over the user toolchain.
+/// If `VOLTA_TRANSITIVE_BINS` is set, any other executable in `node_modules/.bin` (e.g. one
+/// provided by a transitive dependency) also takes precedence over the user toolchain.
+fn project_bin_reason(project: &Project, exe: &OsStr) -> Fallible<Option<ProjectBinReason>> {
+    if project.has_direct_bin(exe)? {
+        return Ok(Some(ProjectBinReason::DirectDependency));
+    }
 
-            // if there's no user platform selected, pass through to existing PATH.
-            return ToolCommand::passthrough(&path_to_bin, args, ErrorDetails::NoPlatform);
+    if resolve_transitive_bins() && find_local_bin(&project.local_bin_dir(), exe).is_some() {
+        return Ok(Some(ProjectBinReason::TransitiveBins));
+    }
+
+    Ok(None)
+}
+
+fn resolve_transitive_bins() -> bool {
+    // We should only use bins from transitive dependencies if the VOLTA_TRANSITIVE_BINS variable is set
+    flag_enabled(TRANSITIVE_BINS)
+}
+
+/// Returns the variables that a binary's shebang sets, which need to be set on the command since
+/// the loader is run directly rather than through `env`.
+fn loader_env(loader: &Option<BinLoader>) -> HashMap<String, String> {
+    match loader {
+        Some(loader) => loader.env.clone(),
+        None => HashMap::new(),
+    }
+}
+
+/// Determines the executable and full argument list for running a binary, taking into account
+/// the loader (if any) that is needed to run it.
+pub(super) fn with_loader<A>(
+    bin_path: OsString,
+    loader: Option<BinLoader>,
+    args: A,
+) -> (OsString, Vec<OsString>)
+where
+    A: IntoIterator<Item = OsString>,
+{
+    match loader {
+        Some(loader) => (
+            OsString::from(loader.command),
+            loader
+                .args
+                .into_iter()
+                .chain(once(bin_path))
+                .chain(args)
+                .collect(),
+        ),
+        None => (bin_path, args.into_iter().collect()),
+    }
+}
+
+cfg_if! {
+    if #[cfg(windows)] {
+        /// The extensions that npm and Yarn use for the Windows variants of the binaries they
+        /// link into `node_modules/.bin`, in order of preference.
+        const LOCAL_BIN_EXTENSIONS: &[&str] = &["exe", "cmd", "bat", "ps1"];
+
+        /// Finds the Windows variant of a project-local binary, along with the loader needed
+        /// to run it.
+        ///
+        /// The extension-less file that npm writes next to these is a shell script, which
+        /// can't be run directly on Windows, so it is intentionally not considered.
+        pub(super) fn find_local_bin(bin_dir: &Path, exe: &OsStr) -> Option<(PathBuf, Option<BinLoader>)> {
+            LOCAL_BIN_EXTENSIONS.iter().find_map(|extension| {
+                let mut file_name = exe.to_os_string();
+                file_name.push(".");
+                file_name.push(extension);
+
+                let path_to_bin = bin_dir.join(file_name);
+                if path_to_bin.is_file() {
+                    let loader = BinLoader::for_extension(&path_to_bin).unwrap_or(None);
+                    Some((path_to_bin, loader))
+                } else {
+                    None
+                }
+            })
+        }
+    } else {
+        /// Finds a project-local binary. On Unix, the binary in `node_modules/.bin` can always
+        /// be executed directly, so there is never a loader.
+        pub(super) fn find_local_bin(bin_dir: &Path, exe: &OsStr) -> Option<(PathBuf, Option<BinLoader>)> {
+            let path_to_bin = bin_dir.join(exe);
+            if path_to_bin.is_file() {
+                Some((path_to_bin, None))
+            } else {
+                None
+            }
         }
     }
+}
 
-    // try to use the user toolchain
-    if let Some(user_tool) = session.get_user_tool(&exe)? {
-        let path = user_tool.image.path()?;
-        let tool_path = user_tool.bin_path.into_os_string();
-        let cmd = match user_tool.loader {
-            Some(loader) => ToolCommand::direct(
-                loader.command.as_ref(),
-                loader
-                    .args
-                    .iter()
-                    .map(|arg| OsString::from(arg))
-                    .chain(once(tool_path))
-                    .chain(args),
-                &path,
-            ),
-            None => ToolCommand::direct(&tool_path, args, &path),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_bin_and_args_through_without_a_loader() {
+        let (bin, args) = with_loader(
+            OsString::from("/bin/tsc"),
+            None,
+            vec![OsString::from("--version")],
+        );
+        assert_eq!(bin, "/bin/tsc");
+        assert_eq!(args, vec![OsString::from("--version")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_paths_and_args_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let bin_path = OsStr::from_bytes(b"/home/\xe9milie/bin/tsc").to_os_string();
+        let arg = OsStr::from_bytes(b"caf\xe9.ts").to_os_string();
+        let loader = BinLoader {
+            command: "node".to_string(),
+            args: vec![
+                OsString::from("--require"),
+                OsStr::from_bytes(b"/home/\xe9milie/.pnp.js").to_os_string(),
+            ],
+            env: HashMap::new(),
         };
-        return Ok(cmd);
+
+        let (bin, args) = with_loader(bin_path.clone(), Some(loader), vec![arg.clone()]);
+        assert_eq!(bin, "node");
+        assert_eq!(
+            args,
+            vec![
+                OsString::from("--require"),
+                OsStr::from_bytes(b"/home/\xe9milie/.pnp.js").to_os_string(),
+                bin_path,
+                arg,
+            ]
+        );
     }
 
-    // at this point, there is no project or user toolchain
-    // Pass through to the existing PATH
-    ToolCommand::passthrough(
-        &exe,
-        args,
-        ErrorDetails::BinaryNotFound {
-            name: exe.to_string_lossy().to_string(),
-        },
-    )
+    // macOS file systems only allow names that are valid UTF-8
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn finds_local_bins_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        use tempfile::tempdir;
+
+        let bin_dir = tempdir().unwrap();
+        let exe = OsStr::from_bytes(b"t\xffsc");
+        std::fs::write(bin_dir.path().join(exe), "").unwrap();
+
+        let (path_to_bin, loader) = find_local_bin(bin_dir.path(), exe).unwrap();
+        assert_eq!(path_to_bin, bin_dir.path().join(exe));
+        assert_eq!(loader, None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn prefers_cmd_variants_of_local_bins() {
+        use tempfile::tempdir;
+
+        let bin_dir = tempdir().unwrap();
+        for file_name in &["tsc", "tsc.cmd", "tsc.ps1"] {
+            std::fs::write(bin_dir.path().join(file_name), "").unwrap();
+        }
+
+        let (path_to_bin, loader) = find_local_bin(bin_dir.path(), OsStr::new("tsc")).unwrap();
+        assert_eq!(path_to_bin, bin_dir.path().join("tsc.cmd"));
+        assert_eq!(loader, None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn runs_powershell_local_bins_with_powershell() {
+        use tempfile::tempdir;
+
+        let bin_dir = tempdir().unwrap();
+        std::fs::write(bin_dir.path().join("tsc"), "").unwrap();
+        std::fs::write(bin_dir.path().join("tsc.ps1"), "").unwrap();
+
+        let (path_to_bin, loader) = find_local_bin(bin_dir.path(), OsStr::new("tsc")).unwrap();
+        assert_eq!(path_to_bin, bin_dir.path().join("tsc.ps1"));
+
+        let (bin, args) = with_loader(path_to_bin.clone().into_os_string(), loader, vec![]);
+        assert_eq!(bin, "powershell");
+        assert_eq!(args.last(), Some(&path_to_bin.into_os_string()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn ignores_shell_scripts_for_local_bins() {
+        use tempfile::tempdir;
+
+        let bin_dir = tempdir().unwrap();
+        std::fs::write(bin_dir.path().join("tsc"), "#!/bin/sh").unwrap();
+
+        assert_eq!(find_local_bin(bin_dir.path(), OsStr::new("tsc")), None);
+    }
 }
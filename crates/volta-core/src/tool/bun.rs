@@ -0,0 +1,41 @@
+use std::ffi::{OsStr, OsString};
+
+use super::{standalone_runtime_path, ToolCommand};
+use crate::error::ErrorDetails;
+use crate::path;
+use crate::session::{ActivityKind, Session};
+
+use volta_fail::Fallible;
+
+pub(super) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    session.add_event_start(ActivityKind::Bun);
+    bun_command(OsStr::new("bun"), args, session)
+}
+
+pub(super) fn bunx_command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    session.add_event_start(ActivityKind::Bunx);
+    bun_command(OsStr::new("bunx"), args, session)
+}
+
+/// Runs `exe` (either `bun` or `bunx`) from the current Bun version, which both come from the
+/// same image.
+fn bun_command<A>(exe: &OsStr, args: A, session: &mut Session) -> Fallible<ToolCommand>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    match session.current_bun()? {
+        Some(ref version) => {
+            session.ensure_bun(version)?;
+            let bin_dir = path::bun_image_bin_dir(&version.to_string())?;
+            let path = standalone_runtime_path(bin_dir)?;
+            Ok(ToolCommand::direct(exe, args, &path))
+        }
+        None => ToolCommand::passthrough(exe, args, ErrorDetails::NoUserBun),
+    }
+}
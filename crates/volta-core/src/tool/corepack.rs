@@ -0,0 +1,81 @@
+use std::ffi::{OsStr, OsString};
+
+use super::{yarn, ToolCommand};
+use crate::error::ErrorDetails;
+use crate::session::{ActivityKind, Session};
+
+use log::debug;
+use volta_fail::{throw, Fallible};
+
+pub(super) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    session.add_event_start(ActivityKind::Corepack);
+    let mut args: Vec<OsString> = args.into_iter().collect();
+
+    match args.first().and_then(|arg| arg.to_str()) {
+        // Volta selects the version of Yarn, so `corepack yarn` runs the same Yarn as `yarn`
+        Some("yarn") | Some("yarnpkg") => {
+            debug!("Running Yarn through Volta instead of corepack");
+            args.remove(0);
+            return yarn::command(args, session);
+        }
+        Some("enable") if enables_yarn(&args[1..]) => {
+            throw!(ErrorDetails::CorepackEnableConflict);
+        }
+        _ => {}
+    }
+
+    match session.current_platform()? {
+        Some(ref platform) => {
+            let image = platform.checkout(session)?;
+            let path = image.path()?;
+            Ok(ToolCommand::direct(OsStr::new("corepack"), args, &path)
+                .with_node(&image.node.runtime))
+        }
+        None => ToolCommand::passthrough(OsStr::new("corepack"), args, ErrorDetails::NoPlatform),
+    }
+}
+
+/// Determines whether `corepack enable` would create Yarn shims, which it does for every package
+/// manager unless it is given their names.
+fn enables_yarn(args: &[OsString]) -> bool {
+    let mut names = Vec::new();
+    let mut args = args.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = args.next() {
+        if arg == "--install-directory" {
+            args.next();
+        } else if !arg.starts_with('-') {
+            names.push(arg);
+        }
+    }
+
+    names.is_empty() || names.iter().any(|name| name == "yarn" || name == "yarnpkg")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::enables_yarn;
+    use std::ffi::OsString;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn detects_enabling_yarn() {
+        assert!(enables_yarn(&args(&[])));
+        assert!(enables_yarn(&args(&["yarn"])));
+        assert!(enables_yarn(&args(&[
+            "--install-directory",
+            "/usr/local/bin"
+        ])));
+        assert!(!enables_yarn(&args(&["pnpm"])));
+        assert!(!enables_yarn(&args(&[
+            "--install-directory",
+            "yarn",
+            "pnpm"
+        ])));
+    }
+}
@@ -0,0 +1,25 @@
+use std::ffi::{OsStr, OsString};
+
+use super::{standalone_runtime_path, ToolCommand};
+use crate::error::ErrorDetails;
+use crate::path;
+use crate::session::{ActivityKind, Session};
+
+use volta_fail::Fallible;
+
+pub(super) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    session.add_event_start(ActivityKind::Deno);
+
+    match session.current_deno()? {
+        Some(ref version) => {
+            session.ensure_deno(version)?;
+            let bin_dir = path::deno_image_bin_dir(&version.to_string())?;
+            let path = standalone_runtime_path(bin_dir)?;
+            Ok(ToolCommand::direct(OsStr::new("deno"), args, &path))
+        }
+        None => ToolCommand::passthrough(OsStr::new("deno"), args, ErrorDetails::NoUserDeno),
+    }
+}
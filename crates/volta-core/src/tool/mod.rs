@@ -3,28 +3,48 @@
 use std::env::{self, args_os, ArgsOs};
 use std::ffi::{OsStr, OsString};
 use std::fmt::{self, Debug, Display, Formatter};
-use std::path::Path;
+use std::iter;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus};
 use std::str::FromStr;
 
 use lazy_static::lazy_static;
+use log::{debug, trace, warn};
 use regex::Regex;
+use semver::Version;
 
 use validate_npm_package_name::{validate, Validity};
-use volta_fail::{throw, Fallible, ResultExt};
+use volta_fail::{throw, ExitCode, Fallible, ResultExt};
 
 use crate::command::create_command;
-use crate::env::UNSAFE_GLOBAL;
+use crate::config;
+use crate::distro::package_source::PackageSource;
+use crate::env::{
+    flag_enabled, NODE_VERSION, NPM_CONFIG_NODEDIR, PARTIAL_PINS, PASSTHROUGH, PROJECT_ROOT,
+    SHIM_DEPTH, TOOL_SOURCE, UNSAFE_GLOBAL,
+};
 use crate::error::ErrorDetails;
+use crate::hook::{GlobalInstallPolicy, HookConfig, PartialPinPolicy, PassthroughPolicy};
+use crate::migrate;
 use crate::path;
-use crate::platform::System;
-use crate::session::Session;
+use crate::plan::Change;
+use crate::platform::history::record_project_platform;
+use crate::platform::{prefetch, Image, MergedPlatform, PlatformSpec, Source, System};
+use crate::project::Project;
+use crate::session::{ActivityKind, Session};
+use crate::style::tool_version;
+use crate::trace;
 use crate::version::VersionSpec;
 
 mod binary;
+mod bun;
+mod corepack;
+mod deno;
 mod node;
 mod npm;
 mod npx;
+mod pnp;
+mod project_command;
 mod yarn;
 
 lazy_static! {
@@ -34,10 +54,14 @@ lazy_static! {
     static ref HAS_VERSION: Regex = Regex::new(r"^[^\s]+@").expect("regex is valid");
 }
 
+/// How many shims can run inside each other before Volta assumes that they are in a loop. Real
+/// chains of tools (e.g. `yarn` running a script that runs `node`) are much shorter than this.
+const MAX_SHIM_DEPTH: u32 = 32;
+
 /// Distinguish global `add` commands in npm or yarn from all others.
 enum CommandArg {
-    /// The command is a *global* add command.
-    GlobalAdd(Option<OsString>),
+    /// The command is a *global* add command, of the given packages.
+    GlobalAdd(Vec<OsString>),
     /// The command is a local, i.e. non-global, add command.
     NotGlobalAdd,
 }
@@ -56,7 +80,10 @@ pub enum ToolSpec {
     Node(VersionSpec),
     Npm(VersionSpec),
     Yarn(VersionSpec),
+    Deno(VersionSpec),
+    Bun(VersionSpec),
     Package(String, VersionSpec),
+    PackageSource(PackageSource),
 }
 
 impl ToolSpec {
@@ -65,21 +92,68 @@ impl ToolSpec {
             "node" => ToolSpec::Node(version),
             "npm" => ToolSpec::Npm(version),
             "yarn" => ToolSpec::Yarn(version),
+            "deno" => ToolSpec::Deno(version),
+            "bun" => ToolSpec::Bun(version),
             package => ToolSpec::Package(package.to_string(), version),
         }
     }
 
     pub fn install(&self, session: &mut Session) -> Fallible<()> {
         match self {
-            ToolSpec::Node(version) => session.install_node(&version)?,
+            ToolSpec::Package(name, version) => {
+                session.install_package(name.to_string(), &version)?;
+            }
+            ToolSpec::PackageSource(source) => {
+                session.install_package_source(&source)?;
+            }
+            tool => {
+                let changes = tool.plan_install(session, true, None)?;
+                session.apply(changes)?;
+            }
+        };
+        Ok(())
+    }
+
+    /// Lists the changes that installing the tool makes. Unless this is a dry run (see
+    /// `volta --dry-run`), installing Node, Yarn, Deno, or Bun then makes these changes with
+    /// `Session::apply`, while packages list their changes only for a dry run. The default
+    /// version is only changed if `set_default` is set, and `arch` only applies to Node.
+    pub fn plan_install(
+        &self,
+        session: &mut Session,
+        set_default: bool,
+        arch: Option<&str>,
+    ) -> Fallible<Vec<Change>> {
+        match self {
             // ISSUE(#292): Implement install for npm
             ToolSpec::Npm(_version) => throw!(ErrorDetails::Unimplemented {
                 feature: "Installing npm".into()
             }),
-            ToolSpec::Yarn(version) => session.install_yarn(&version)?,
-            ToolSpec::Package(name, version) => {
-                session.install_package(name.to_string(), &version)?;
+            ToolSpec::Package(name, version) => session.plan_install_package(name, &version),
+            // The package's name isn't known until it has been cloned or packed
+            ToolSpec::PackageSource(_source) => throw!(ErrorDetails::DryRunUnsupported {
+                action: "Installing a package from git or a local path".into()
+            }),
+            tool => session.plan_install(tool, arch, set_default),
+        }
+    }
+
+    /// Sets the tool as the default version, fetching it first if necessary.
+    pub fn set_default(&self, session: &mut Session) -> Fallible<()> {
+        match self {
+            ToolSpec::Node(version) => session.default_node(&version, None)?,
+            ToolSpec::Npm(version) => session.default_npm(&version)?,
+            ToolSpec::Yarn(version) => session.default_yarn(&version)?,
+            ToolSpec::Deno(version) => session.default_deno(&version)?,
+            ToolSpec::Bun(version) => session.default_bun(&version)?,
+            ToolSpec::Package(name, _version) => {
+                throw!(ErrorDetails::CannotSetPackageAsDefault {
+                    package: name.to_string()
+                })
             }
+            ToolSpec::PackageSource(source) => throw!(ErrorDetails::CannotSetPackageAsDefault {
+                package: source.to_spec()
+            }),
         };
         Ok(())
     }
@@ -96,15 +170,38 @@ impl ToolSpec {
             ToolSpec::Yarn(_version) => throw!(ErrorDetails::Unimplemented {
                 feature: "Uninstalling yarn".into()
             }),
+            ToolSpec::Deno(_version) => session.uninstall_deno()?,
+            ToolSpec::Bun(_version) => session.uninstall_bun()?,
             ToolSpec::Package(name, _version) => {
                 session.uninstall_package(name.to_string())?;
             }
+            ToolSpec::PackageSource(_source) => throw!(ErrorDetails::Unimplemented {
+                feature: "Uninstalling a package by its source (uninstall it by name instead)"
+                    .into()
+            }),
         }
         Ok(())
     }
 
-    /// Try to parse a tool and version from a string like `<tool>[@<version>].
+    /// Lists the changes that uninstalling the tool would make, without making them.
+    pub fn plan_uninstall(&self, session: &mut Session) -> Fallible<Vec<Change>> {
+        match self {
+            ToolSpec::Package(name, _version) => session.plan_uninstall_package(name),
+            ToolSpec::Deno(_version) => session.plan_uninstall_deno(),
+            ToolSpec::Bun(_version) => session.plan_uninstall_bun(),
+            // The other tools can't be uninstalled, which `uninstall` reports without changing
+            // anything
+            _ => self.uninstall(session).map(|_| Vec::new()),
+        }
+    }
+
+    /// Try to parse a tool and version from a string like `<tool>[@<version>]`, or a package
+    /// source like a git URL or local path.
     pub fn try_from_str(tool_spec: &str) -> Fallible<Self> {
+        if let Some(source) = PackageSource::parse(tool_spec) {
+            return Ok(ToolSpec::PackageSource(source));
+        }
+
         let captures =
             TOOL_SPEC_PATTERN
                 .captures(tool_spec)
@@ -132,6 +229,8 @@ impl ToolSpec {
             "node" => ToolSpec::Node(version),
             "npm" => ToolSpec::Npm(version),
             "yarn" => ToolSpec::Yarn(version),
+            "deno" => ToolSpec::Deno(version),
+            "bun" => ToolSpec::Bun(version),
             package => ToolSpec::Package(package.into(), version),
         })
     }
@@ -199,7 +298,10 @@ impl Debug for ToolSpec {
             &ToolSpec::Node(ref version) => format!("node version {}", version),
             &ToolSpec::Yarn(ref version) => format!("yarn version {}", version),
             &ToolSpec::Npm(ref version) => format!("npm version {}", version),
+            &ToolSpec::Deno(ref version) => format!("deno version {}", version),
+            &ToolSpec::Bun(ref version) => format!("bun version {}", version),
             &ToolSpec::Package(ref name, ref version) => format!("{} version {}", name, version),
+            &ToolSpec::PackageSource(ref source) => format!("package from {}", source),
         };
         f.write_str(&s)
     }
@@ -211,7 +313,10 @@ impl Display for ToolSpec {
             &ToolSpec::Node(ref version) => format!("node version {}", version),
             &ToolSpec::Yarn(ref version) => format!("yarn version {}", version),
             &ToolSpec::Npm(ref version) => format!("npm version {}", version),
+            &ToolSpec::Deno(ref version) => format!("deno version {}", version),
+            &ToolSpec::Bun(ref version) => format!("bun version {}", version),
             &ToolSpec::Package(ref name, ref version) => format!("{} version {}", name, version),
+            &ToolSpec::PackageSource(ref source) => format!("package from {}", source),
         };
         f.write_str(&s)
     }
@@ -219,25 +324,365 @@ impl Display for ToolSpec {
 
 pub fn execute_tool(session: &mut Session) -> Fallible<ExitStatus> {
     path::ensure_volta_dirs_exist()?;
+    // Checking the layout only reads its marker, once the layout is up to date
+    migrate::migrate(false)?;
+    // A broken settings file shouldn't stop every tool from running, so the shim carries on
+    // without it, while `volta` itself still reports the error
+    if let Err(error) = config::apply() {
+        warn!("Ignoring the Volta settings: {}", error);
+    }
 
     let mut args = args_os();
     let exe = get_tool_name(&mut args)?;
+    let _span = trace::span(exe.to_string_lossy());
+
+    if shim_depth() >= MAX_SHIM_DEPTH {
+        throw!(ErrorDetails::ShimRecursionError {
+            name: exe.to_string_lossy().to_string(),
+        });
+    }
+
+    if session.is_bypassed()? {
+        debug!(
+            "Volta is bypassed in this directory, running {} from the system",
+            exe.to_string_lossy()
+        );
+        let name = exe.to_string_lossy().to_string();
+        return ToolCommand::system(&exe, args, ErrorDetails::BypassedBinaryNotFound { name })?
+            .exec();
+    }
+
+    // Only the outermost shim checks, so that nested shims don't repeat the warning
+    if shim_depth() == 0 && uses_platform(&exe) {
+        if let Some(project) = session.project()? {
+            check_partial_pins(session, &project)?;
+        }
+    }
 
-    let command = match &exe.to_str() {
+    let deferred = defer_project_fetch(session)?;
+
+    let mut command = match &exe.to_str() {
         Some("node") => node::command(args, session)?,
         Some("npm") => npm::command(args, session)?,
         Some("npx") => npx::command(args, session)?,
         Some("yarn") => yarn::command(args, session)?,
+        Some("corepack") => corepack::command(args, session)?,
+        Some("deno") => deno::command(args, session)?,
+        Some("bun") => bun::command(args, session)?,
+        Some("bunx") => bun::bunx_command(args, session)?,
         _ => binary::command(exe, args, session)?,
     };
 
+    if let Some(project) = session.project()? {
+        command.envs(&project.manifest().env);
+        command.command.env(PROJECT_ROOT, project.project_root());
+        if command.source == ToolSource::Default && pinned_by_project(&exe, &project) && !deferred {
+            command.source = ToolSource::Project;
+        }
+        // Only the outermost shim checks, so that e.g. `npm test` doesn't check again for `node`,
+        // and only once the tool runs with the project's own platform
+        if shim_depth() == 0 && !deferred {
+            check_platform_change(session, &project)?;
+        }
+    } else {
+        command.command.env_remove(PROJECT_ROOT);
+    }
+
     command.exec()
 }
 
+/// Starts fetching the project's pinned platform in the background, if it hasn't been fetched and
+/// background fetching is turned on (see `platform::prefetch`), so that the tool runs right away
+/// with the user's default platform, or from the system `PATH`, rather than waiting for it.
+/// Returns whether the tool runs without the project's platform.
+fn defer_project_fetch(session: &mut Session) -> Fallible<bool> {
+    if !prefetch::is_enabled()? {
+        return Ok(false);
+    }
+    let platform = match session.project_platform()? {
+        Some(ref platform) if !session.is_fetched(platform)? => platform.clone(),
+        _ => return Ok(false),
+    };
+
+    let fallback = match session.user_platform()? {
+        Some(user) => format!("your default {}", tool_version("node", &user.node_runtime)),
+        None if passthrough_policy()? != PassthroughPolicy::Deny => "your PATH".to_string(),
+        // There is nothing else to run the tool with, so it waits for the platform after all
+        None => return Ok(false),
+    };
+
+    if !session.defer_project_platform()? {
+        return Ok(false);
+    }
+    // Nested shims would only repeat the warning
+    if shim_depth() == 0 {
+        warn!(
+            "{} is being fetched in the background, so this project's tools will run with {} until it's ready.",
+            platform, fallback
+        );
+    }
+    Ok(true)
+}
+
+/// Runs one of the commands that the project defines in the `commands` of its `volta` section,
+/// with the project's platform and its own executables on `PATH`.
+pub fn run_project_command<A>(name: &str, args: A, session: &mut Session) -> Fallible<ExitStatus>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    let mut command = project_command::command(name, args, session)?;
+
+    if let Some(project) = session.project()? {
+        command.envs(&project.manifest().env);
+        command.command.env(PROJECT_ROOT, project.project_root());
+    }
+
+    command.exec()
+}
+
+/// Runs the `platformChange` events hook if the platform that shims run with in the project has
+/// changed since a shim last ran in it, e.g. because someone changed the project's pins. The hook
+/// failing is only a warning, since the tool can still run.
+fn check_platform_change(session: &mut Session, project: &Project) -> Fallible<()> {
+    // Without a hook to run, there is no need to keep track of the platform at all
+    let has_hook = session
+        .hooks()?
+        .events
+        .as_ref()
+        .map_or(false, |events| events.platform_change.is_some());
+    if !has_hook {
+        return Ok(());
+    }
+
+    let platform = match session.current_platform()? {
+        Some(platform) => platform.to_string(),
+        None => return Ok(()),
+    };
+    let previous = match record_project_platform(project.project_root(), &platform)? {
+        Some(previous) => previous,
+        None => return Ok(()),
+    };
+
+    debug!(
+        "The platform of the project changed from {} to {}",
+        previous, platform
+    );
+    session.add_event_start(ActivityKind::PlatformChange);
+
+    let hook = session
+        .hooks()?
+        .events
+        .as_ref()
+        .and_then(|events| events.platform_change.as_ref());
+    if let Some(hook) = hook {
+        match hook.run(project.project_root(), &previous, &platform) {
+            Ok(status) if status.success() => {}
+            Ok(status) => warn!("The platformChange hook failed with {}", status),
+            Err(error) => warn!("Could not run the platformChange hook: {}", error),
+        }
+    }
+
+    session.add_event_end(ActivityKind::PlatformChange, ExitCode::Success);
+    Ok(())
+}
+
+/// Counts the shims that are running the current one, which each record their depth in the
+/// environment of the tool they run.
+fn shim_depth() -> u32 {
+    env::var(SHIM_DEPTH)
+        .ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Determines whether a tool runs with the Node platform, rather than being a standalone runtime
+/// or a package binary.
+fn uses_platform(exe: &OsStr) -> bool {
+    match exe.to_str() {
+        Some("node") | Some("npm") | Some("npx") | Some("yarn") | Some("corepack") => true,
+        _ => false,
+    }
+}
+
+/// Warns about a project that pins npm or Yarn without pinning Node, or refuses to run its tools,
+/// depending on the policy for partial pins (see `partial_pin_policy`).
+fn check_partial_pins(session: &Session, project: &Project) -> Fallible<()> {
+    let partial = match project.manifest().partial_platform() {
+        Some(partial) => partial,
+        None => return Ok(()),
+    };
+
+    match partial_pin_policy(session)? {
+        PartialPinPolicy::Allow => {}
+        PartialPinPolicy::Warn => warn!(
+            "This project's {} pins {} without pinning Node, so your default Node is used with it. Use `volta pin node` to pin Node as well.",
+            project.manifest_file_name(),
+            partial
+        ),
+        PartialPinPolicy::Deny => throw!(ErrorDetails::RuntimeNotPinned {
+            tools: partial.to_string(),
+            file: project.manifest_file_name().to_string(),
+        }),
+    }
+    Ok(())
+}
+
+/// Determines whether the project pins the version of a built-in tool, rather than leaving it to
+/// the user's default.
+fn pinned_by_project(exe: &OsStr, project: &Project) -> bool {
+    let partial = project.manifest().partial_platform();
+    match exe.to_str() {
+        Some("npm") | Some("npx") if partial.map_or(false, |partial| partial.npm.is_some()) => true,
+        Some("yarn") if partial.map_or(false, |partial| partial.yarn.is_some()) => true,
+        Some("node") | Some("npm") | Some("npx") | Some("yarn") | Some("corepack") => {
+            project.is_pinned()
+        }
+        Some("deno") => project.manifest().deno().is_some(),
+        Some("bun") | Some("bunx") => project.manifest().bun().is_some(),
+        _ => false,
+    }
+}
+
+/// Explains how the shim for a tool decides what to run, as a list of the steps it takes, in
+/// order. This is the same logic as `execute_tool`, except that nothing is fetched or run.
+pub fn explain_tool(exe: &OsStr, session: &mut Session) -> Fallible<Vec<String>> {
+    let mut steps = Vec::new();
+
+    if session.is_bypassed()? {
+        steps.push("Volta is bypassed in this directory, so the system PATH is used".to_string());
+        return Ok(steps);
+    }
+
+    match exe.to_str() {
+        Some("node") | Some("npm") | Some("npx") | Some("yarn") | Some("corepack") => {
+            explain_platform(session, &mut steps)?;
+        }
+        Some("deno") => explain_runtime("deno", session.current_deno()?, &mut steps),
+        Some("bun") | Some("bunx") => explain_runtime("bun", session.current_bun()?, &mut steps),
+        _ => binary::explain(exe, session, &mut steps)?,
+    }
+
+    Ok(steps)
+}
+
+/// Explains which platform is used: the tools that the project pins, over the user's default
+/// platform for the rest.
+fn explain_platform(session: &Session, steps: &mut Vec<String>) -> Fallible<()> {
+    if let Some(project) = session.project()? {
+        if let Some(partial) = project.manifest().partial_platform() {
+            let policy = match partial_pin_policy(session)? {
+                PartialPinPolicy::Allow => "allowed",
+                PartialPinPolicy::Warn => "allowed with a warning",
+                PartialPinPolicy::Deny => "not allowed, so the tools will fail to run",
+            };
+            steps.push(format!(
+                "The project pins {} without pinning Node, which is {} (see {})",
+                partial, policy, PARTIAL_PINS
+            ));
+        }
+    }
+
+    match session.merged_platform()? {
+        Some(ref merged) if merged.is_uniform() && merged.node == Source::Project => steps.push(
+            format!("The project pins {}", describe_platform(&merged.spec)?),
+        ),
+        Some(ref merged) if merged.is_uniform() => steps.push(format!(
+            "Using your default platform, {}",
+            describe_platform(&merged.spec)?
+        )),
+        Some(ref merged) => steps.push(format!(
+            "Merging the project's pins with your default platform: {}",
+            describe_merged_platform(merged)?
+        )),
+        None => steps
+            .push("There is no pinned or default platform, so the system PATH is used".to_string()),
+    }
+    Ok(())
+}
+
+/// Explains which version of a standalone runtime (Deno or Bun) is used.
+fn explain_runtime(name: &str, version: Option<Version>, steps: &mut Vec<String>) {
+    match version {
+        Some(version) => steps.push(format!("Using {} v{}", name, version)),
+        None => steps.push(format!(
+            "No version of {} is selected, so the system PATH is used",
+            name
+        )),
+    }
+}
+
+/// Describes the tools in a platform, along with where its Node image is if it has been fetched.
+fn describe_platform(platform: &PlatformSpec) -> Fallible<String> {
+    describe_tools(platform, None)
+}
+
+/// Describes the tools in a merged platform like `describe_platform`, along with where each of
+/// them comes from.
+fn describe_merged_platform(merged: &MergedPlatform) -> Fallible<String> {
+    describe_tools(&merged.spec, Some(merged))
+}
+
+fn describe_tools(platform: &PlatformSpec, sources: Option<&MergedPlatform>) -> Fallible<String> {
+    let from = |source: fn(&MergedPlatform) -> Source| match sources.map(source) {
+        Some(Source::Project) => " from the project",
+        Some(Source::Default) => " from your default",
+        None => "",
+    };
+
+    let mut description = format!(
+        "node v{}{}",
+        platform.node_runtime,
+        from(|merged| merged.node)
+    );
+    if let Some(ref npm) = platform.npm {
+        description.push_str(&format!(", npm v{}{}", npm, from(|merged| merged.npm)));
+    }
+    if let Some(ref yarn) = platform.yarn {
+        description.push_str(&format!(", yarn v{}{}", yarn, from(|merged| merged.yarn)));
+    }
+
+    let image_dir = match platform.resolve_image() {
+        Some(image) => Some(image.node_dir()?),
+        None => None,
+    };
+    match image_dir {
+        Some(ref dir) if dir.is_dir() => {
+            description.push_str(&format!(" (image at {})", dir.display()))
+        }
+        _ => description.push_str(" (not fetched yet)"),
+    }
+
+    Ok(description)
+}
+
+/// Where the version of a tool comes from, which the tool can read from `VOLTA_TOOL_SOURCE`.
+#[derive(Clone, Copy, PartialEq)]
+enum ToolSource {
+    /// The project pins the tool, or the tool is one of the project's own binaries.
+    Project,
+    /// The user's default toolchain provides the tool.
+    Default,
+    /// Volta isn't managing the tool, so it is found on the system `PATH`.
+    Passthrough,
+}
+
+impl ToolSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            ToolSource::Project => "project",
+            ToolSource::Default => "default",
+            ToolSource::Passthrough => "passthrough",
+        }
+    }
+}
+
 /// Represents the command to execute a tool
 struct ToolCommand {
     command: Command,
     error: ErrorDetails,
+    source: ToolSource,
+    /// The version of Node that the tool runs with, if Volta selected it.
+    node: Option<Version>,
 }
 
 impl ToolCommand {
@@ -248,6 +693,8 @@ impl ToolCommand {
         ToolCommand {
             command: command_for(exe, args, path_var),
             error: ErrorDetails::BinaryExecError,
+            source: ToolSource::Default,
+            node: None,
         }
     }
 
@@ -258,12 +705,33 @@ impl ToolCommand {
         ToolCommand {
             command: command_for(exe, args, path_var),
             error: ErrorDetails::ProjectLocalBinaryExecError {
-                command: exe.to_string_lossy().to_string(),
+                command: PathBuf::from(exe),
             },
+            source: ToolSource::Project,
+            node: None,
         }
     }
 
+    /// Runs a tool that there is no platform for from the system `PATH`, if the passthrough
+    /// policy allows it. Otherwise, the tool fails with `default_error`.
     fn passthrough<A>(exe: &OsStr, args: A, default_error: ErrorDetails) -> Fallible<Self>
+    where
+        A: IntoIterator<Item = OsString>,
+    {
+        match passthrough_policy()? {
+            PassthroughPolicy::Allow => {}
+            PassthroughPolicy::Warn => warn!(
+                "No platform is set for {}, so it will run from your PATH without Volta.",
+                exe.to_string_lossy()
+            ),
+            PassthroughPolicy::Deny => throw!(default_error),
+        }
+
+        Self::system(exe, args, default_error)
+    }
+
+    /// Runs a tool from the system `PATH`, e.g. because Volta is bypassed.
+    fn system<A>(exe: &OsStr, args: A, default_error: ErrorDetails) -> Fallible<Self>
     where
         A: IntoIterator<Item = OsString>,
     {
@@ -271,11 +739,105 @@ impl ToolCommand {
         Ok(ToolCommand {
             command: command_for(exe, args, &path),
             error: default_error,
+            source: ToolSource::Passthrough,
+            node: None,
+        })
+    }
+
+    /// Runs one of the commands that the project defines, which has been built into a command for
+    /// the shell.
+    fn project_command(command: Command, name: &str, source: ToolSource) -> Self {
+        ToolCommand {
+            command,
+            error: ErrorDetails::ProjectCommandExecError {
+                name: name.to_string(),
+            },
+            source,
+            node: None,
+        }
+    }
+
+    /// Installs packages with `volta install`, in place of a global install by npm or Yarn.
+    fn volta_install(packages: Vec<OsString>) -> Fallible<Self> {
+        let volta = path::volta_file()?;
+        let path = env::var_os("PATH").unwrap_or_default();
+        let args = iter::once(OsString::from("install")).chain(packages);
+
+        Ok(ToolCommand {
+            command: command_for(volta.as_os_str(), args, &path),
+            error: ErrorDetails::BinaryExecError,
+            source: ToolSource::Default,
+            node: None,
         })
     }
 
+    /// Sets environment variables for the tool, such as those the project sets in its `volta`
+    /// section.
+    fn envs<'a, I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (&'a String, &'a String)>,
+    {
+        self.command.envs(vars);
+    }
+
+    /// Records the version of Node that the tool runs with.
+    fn with_node(self, node: &Version) -> Self {
+        ToolCommand {
+            node: Some(node.clone()),
+            ..self
+        }
+    }
+
+    /// Points node-gyp at the headers of the Node that the tool runs with, if they have been
+    /// fetched (see `volta fetch --headers`), so that native modules build without downloading
+    /// them. Headers that an outer shim chose for another version of Node are cleared, but a
+    /// `nodedir` that the user set is left alone.
+    fn set_node_headers(&mut self) -> Fallible<()> {
+        let headers_root = path::node_headers_root_dir()?;
+        let headers_dir = self
+            .node
+            .as_ref()
+            .map(|node| headers_root.join(node.to_string()))
+            .filter(|dir| dir.is_dir());
+        let current = env::var_os(NPM_CONFIG_NODEDIR);
+
+        match node_dir_change(current.as_ref().map(Path::new), &headers_root, headers_dir) {
+            NodeDirChange::Keep => {}
+            NodeDirChange::Set(dir) => {
+                self.command.env(NPM_CONFIG_NODEDIR, dir);
+            }
+            NodeDirChange::Remove => {
+                self.command.env_remove(NPM_CONFIG_NODEDIR);
+            }
+        }
+
+        Ok(())
+    }
+
     fn exec(mut self) -> Fallible<ExitStatus> {
-        self.command.status().with_context(|_| self.error)
+        self.command.env(TOOL_SOURCE, self.source.as_str());
+        self.command.env(SHIM_DEPTH, (shim_depth() + 1).to_string());
+        match self.node {
+            Some(ref node) => self.command.env(NODE_VERSION, node.to_string()),
+            None => self.command.env_remove(NODE_VERSION),
+        };
+        self.set_node_headers()?;
+        trace!(
+            "Running {:?} (source: {})",
+            self.command,
+            self.source.as_str()
+        );
+
+        let spawned = {
+            let _span = trace::span("spawn");
+            self.command.spawn()
+        };
+        let status = spawned.and_then(|mut child| {
+            let _span = trace::span("wait");
+            child.wait()
+        });
+
+        status.with_context(|_| self.error)
     }
 }
 
@@ -293,13 +855,32 @@ fn tool_name_from_file_name(file_name: &OsStr) -> OsString {
 #[cfg(windows)]
 fn tool_name_from_file_name(file_name: &OsStr) -> OsString {
     // On Windows PowerShell, the file name includes the .exe suffix
-    // We need to remove that to get the raw tool name
-    match file_name.to_str() {
-        Some(file) => OsString::from(file.trim_end_matches(".exe")),
-        None => OsString::from(file_name),
+    // We need to remove that to get the raw tool name, without assuming the rest is valid Unicode
+    let path = Path::new(file_name);
+    let is_exe = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("exe"));
+
+    match path.file_stem() {
+        Some(stem) if is_exe => stem.to_os_string(),
+        _ => file_name.to_os_string(),
     }
 }
 
+/// Builds a command that runs with the tools of the given platform image, rather than the ones
+/// Volta would otherwise select, e.g. to run a project's tests with several versions of Node.
+pub fn image_command<A>(exe: &OsStr, args: A, image: &Image) -> Fallible<Command>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    let path = image.path()?;
+    let mut command = command_for(exe, args, &path);
+    command.env(NODE_VERSION, image.node.runtime.to_string());
+    command.env_remove(TOOL_SOURCE);
+    Ok(command)
+}
+
 fn command_for<A>(exe: &OsStr, args: A, path_var: &OsStr) -> Command
 where
     A: IntoIterator<Item = OsString>,
@@ -310,9 +891,123 @@ where
     command
 }
 
-fn intercept_global_installs() -> bool {
-    // We should only intercept global installs if the VOLTA_UNSAFE_GLOBAL variable is not set
-    env::var_os(UNSAFE_GLOBAL).is_none()
+/// Produces a modified version of the current `PATH` environment variable that will find a
+/// standalone runtime (Deno or Bun) in its installation directory. Unlike the Node platform, the
+/// Volta shims are left in place, so that any Node tools launched by the runtime are still
+/// managed by Volta.
+fn standalone_runtime_path(bin_dir: PathBuf) -> Fallible<OsString> {
+    let old_path = envoy::path().unwrap_or(envoy::Var::from(""));
+
+    old_path
+        .split()
+        .prefix_entry(bin_dir)
+        .join()
+        .with_context(|_| ErrorDetails::BuildPathError)
+}
+
+/// Applies the global install policy to a command of npm or Yarn. Returns the packages to install
+/// with `volta install` instead, if the policy says to, or `None` to run the command as it is.
+fn intercept_global_installs(
+    tool: &str,
+    command: CommandArg,
+    session: &Session,
+) -> Fallible<Option<Vec<OsString>>> {
+    let packages = match command {
+        CommandArg::GlobalAdd(packages) => packages,
+        CommandArg::NotGlobalAdd => return Ok(None),
+    };
+
+    match global_install_policy(session)? {
+        GlobalInstallPolicy::Passthrough => Ok(None),
+        GlobalInstallPolicy::Warn => {
+            warn!(
+                "{} is installing packages globally, so Volta won't manage them. Use `volta install` to add them to your toolchain instead.",
+                tool
+            );
+            Ok(None)
+        }
+        GlobalInstallPolicy::Install if !packages.is_empty() => Ok(Some(packages)),
+        _ => throw!(ErrorDetails::NoGlobalInstalls {
+            package: packages.into_iter().next(),
+        }),
+    }
+}
+
+/// Reads the policy for partial pins from `VOLTA_PARTIAL_PINS`, or from `partialPins` in the
+/// `volta` section of the hooks.
+fn partial_pin_policy(session: &Session) -> Fallible<PartialPinPolicy> {
+    let policy = match env::var(PARTIAL_PINS) {
+        Ok(ref value) if value == "allow" => Some(PartialPinPolicy::Allow),
+        Ok(ref value) if value == "warn" => Some(PartialPinPolicy::Warn),
+        Ok(ref value) if value == "deny" => Some(PartialPinPolicy::Deny),
+        Ok(ref value) if value.is_empty() => None,
+        Ok(value) => throw!(ErrorDetails::InvalidPartialPinPolicy { value }),
+        Err(_) => None,
+    };
+
+    match policy {
+        Some(policy) => Ok(policy),
+        None => Ok(session
+            .hooks()?
+            .volta
+            .as_ref()
+            .and_then(|volta| volta.partial_pins)
+            .unwrap_or_default()),
+    }
+}
+
+/// Reads the passthrough policy from `VOLTA_PASSTHROUGH`, or from `passthrough` in the `volta`
+/// section of the user hooks.
+fn passthrough_policy() -> Fallible<PassthroughPolicy> {
+    let policy = match env::var(PASSTHROUGH) {
+        Ok(ref value) if value == "allow" => Some(PassthroughPolicy::Allow),
+        Ok(ref value) if value == "warn" => Some(PassthroughPolicy::Warn),
+        Ok(ref value) if value == "deny" => Some(PassthroughPolicy::Deny),
+        Ok(ref value) if value.is_empty() => HookConfig::user_passthrough()?,
+        Ok(value) => throw!(ErrorDetails::InvalidPassthroughPolicy { value }),
+        Err(_) => HookConfig::user_passthrough()?,
+    };
+
+    Ok(policy.unwrap_or_default())
+}
+
+/// How a tool's `npm_config_nodedir` is changed to point at the headers Volta fetched.
+#[derive(Debug, PartialEq)]
+enum NodeDirChange {
+    Keep,
+    Set(PathBuf),
+    Remove,
+}
+
+/// Decides how to change `npm_config_nodedir`, given its current value, the directory that Volta
+/// keeps headers in, and the fetched headers of the tool's Node, if any. Only values that Volta
+/// set itself (or none at all) are replaced.
+fn node_dir_change(
+    current: Option<&Path>,
+    headers_root: &Path,
+    headers_dir: Option<PathBuf>,
+) -> NodeDirChange {
+    let from_volta = current.map_or(true, |dir| dir.starts_with(headers_root));
+    match headers_dir {
+        _ if !from_volta => NodeDirChange::Keep,
+        Some(dir) => NodeDirChange::Set(dir),
+        None if current.is_some() => NodeDirChange::Remove,
+        None => NodeDirChange::Keep,
+    }
+}
+
+fn global_install_policy(session: &Session) -> Fallible<GlobalInstallPolicy> {
+    // Setting VOLTA_UNSAFE_GLOBAL always lets global installs through
+    if flag_enabled(UNSAFE_GLOBAL) {
+        return Ok(GlobalInstallPolicy::Passthrough);
+    }
+
+    Ok(session
+        .hooks()?
+        .volta
+        .as_ref()
+        .and_then(|volta| volta.global_installs)
+        .unwrap_or_default())
 }
 
 #[cfg(test)]
@@ -321,7 +1016,8 @@ mod tests {
         use std::str::FromStr as _;
 
         use super::super::ToolSpec;
-        use crate::version::VersionSpec;
+        use crate::distro::package_source::PackageSource;
+        use crate::version::{ReleaseChannel, VersionSpec};
 
         const LTS: &str = "lts";
         const LATEST: &str = "latest";
@@ -380,6 +1076,28 @@ mod tests {
             );
         }
 
+        #[test]
+        fn parses_node_with_release_channels() {
+            let tool = "node";
+
+            assert_eq!(
+                ToolSpec::try_from_str(&versioned_tool!(tool, "nightly")).expect("succeeds"),
+                ToolSpec::Node(VersionSpec::Channel(ReleaseChannel::Nightly))
+            );
+
+            assert_eq!(
+                ToolSpec::try_from_str(&versioned_tool!(tool, "rc")).expect("succeeds"),
+                ToolSpec::Node(VersionSpec::Channel(ReleaseChannel::Rc))
+            );
+
+            assert_eq!(
+                ToolSpec::try_from_str(&versioned_tool!(tool, "20.0.0-rc.2")).expect("succeeds"),
+                ToolSpec::Node(VersionSpec::Exact(
+                    VersionSpec::parse_version("20.0.0-rc.2").expect("is a valid version")
+                ))
+            );
+        }
+
         #[test]
         fn parses_bare_yarn() {
             assert_eq!(
@@ -424,6 +1142,45 @@ mod tests {
             );
         }
 
+        #[test]
+        fn parses_deno_with_valid_versions() {
+            let tool = "deno";
+
+            assert_eq!(
+                ToolSpec::try_from_str(tool).expect("succeeds"),
+                ToolSpec::Deno(VersionSpec::default())
+            );
+
+            assert_eq!(
+                ToolSpec::try_from_str(&versioned_tool!(tool, MINOR)).expect("succeeds"),
+                ToolSpec::Deno(
+                    VersionSpec::from_str(MINOR).expect("`VersionSpec` has its own tests")
+                )
+            );
+
+            assert_eq!(
+                ToolSpec::try_from_str(&versioned_tool!(tool, LATEST)).expect("succeeds"),
+                ToolSpec::Deno(VersionSpec::Latest)
+            );
+        }
+
+        #[test]
+        fn parses_bun_with_valid_versions() {
+            let tool = "bun";
+
+            assert_eq!(
+                ToolSpec::try_from_str(tool).expect("succeeds"),
+                ToolSpec::Bun(VersionSpec::default())
+            );
+
+            assert_eq!(
+                ToolSpec::try_from_str(&versioned_tool!(tool, PATCH)).expect("succeeds"),
+                ToolSpec::Bun(
+                    VersionSpec::from_str(PATCH).expect("`VersionSpec` has its own tests")
+                )
+            );
+        }
+
         #[test]
         fn parses_bare_packages() {
             let package = "ember-cli";
@@ -442,6 +1199,17 @@ mod tests {
             );
         }
 
+        #[test]
+        fn parses_package_sources() {
+            let source = "github:org/cli#v1.0.0";
+            assert_eq!(
+                ToolSpec::try_from_str(source).expect("succeeds"),
+                ToolSpec::PackageSource(
+                    PackageSource::parse(source).expect("`PackageSource` has its own tests")
+                )
+            );
+        }
+
         #[test]
         fn parses_bare_packages_with_valid_versions() {
             let package = "something-awesome";
@@ -596,4 +1364,102 @@ mod tests {
             );
         }
     }
+
+    mod tool_name {
+        use super::super::tool_name_from_file_name;
+        use std::ffi::OsStr;
+
+        #[test]
+        fn keeps_plain_names() {
+            assert_eq!(tool_name_from_file_name(OsStr::new("node")), "node");
+        }
+
+        #[cfg(windows)]
+        #[test]
+        fn strips_exe_extension() {
+            assert_eq!(tool_name_from_file_name(OsStr::new("node.exe")), "node");
+            assert_eq!(tool_name_from_file_name(OsStr::new("tsc.EXE")), "tsc");
+            assert_eq!(tool_name_from_file_name(OsStr::new("tsc.cmd")), "tsc.cmd");
+        }
+
+        #[cfg(windows)]
+        #[test]
+        fn keeps_names_that_are_not_unicode() {
+            use std::ffi::OsString;
+            use std::os::windows::ffi::OsStringExt;
+
+            // An unpaired surrogate, which can't be converted to a `str`
+            let mut name = OsString::from_wide(&[0x74, 0xD800]);
+            let stem = name.clone();
+            name.push(".exe");
+            assert_eq!(tool_name_from_file_name(&name), stem);
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn keeps_names_that_are_not_utf8() {
+            use std::os::unix::ffi::OsStrExt;
+
+            let name = OsStr::from_bytes(b"t\xffsc");
+            assert_eq!(tool_name_from_file_name(name), name);
+        }
+    }
+
+    mod node_dir_change {
+        use std::path::{Path, PathBuf};
+
+        use super::super::{node_dir_change, NodeDirChange};
+
+        const ROOT: &str = "/volta/tools/image/node-headers";
+
+        fn headers(version: &str) -> PathBuf {
+            Path::new(ROOT).join(version)
+        }
+
+        #[test]
+        fn sets_fetched_headers_when_unset() {
+            assert_eq!(
+                node_dir_change(None, Path::new(ROOT), Some(headers("10.16.0"))),
+                NodeDirChange::Set(headers("10.16.0"))
+            );
+        }
+
+        #[test]
+        fn replaces_headers_from_an_outer_shim() {
+            let outer = headers("8.9.4");
+            assert_eq!(
+                node_dir_change(
+                    Some(outer.as_path()),
+                    Path::new(ROOT),
+                    Some(headers("10.16.0"))
+                ),
+                NodeDirChange::Set(headers("10.16.0"))
+            );
+            assert_eq!(
+                node_dir_change(Some(outer.as_path()), Path::new(ROOT), None),
+                NodeDirChange::Remove
+            );
+        }
+
+        #[test]
+        fn keeps_a_user_nodedir() {
+            let user = Path::new("/home/user/node");
+            assert_eq!(
+                node_dir_change(Some(user), Path::new(ROOT), Some(headers("10.16.0"))),
+                NodeDirChange::Keep
+            );
+            assert_eq!(
+                node_dir_change(Some(user), Path::new(ROOT), None),
+                NodeDirChange::Keep
+            );
+        }
+
+        #[test]
+        fn leaves_nodedir_unset_without_headers() {
+            assert_eq!(
+                node_dir_change(None, Path::new(ROOT), None),
+                NodeDirChange::Keep
+            );
+        }
+    }
 }
@@ -12,11 +12,18 @@ where
 {
     session.add_event_start(ActivityKind::Node);
 
+    // The flags belong to the project's pinned Node, so they aren't passed to any other Node
+    let flags = match session.project()? {
+        Some(ref project) if project.is_pinned() => project.manifest().node_flags.clone(),
+        _ => Vec::new(),
+    };
+    let args = flags.into_iter().map(OsString::from).chain(args);
+
     match session.current_platform()? {
         Some(ref platform) => {
             let image = platform.checkout(session)?;
             let path = image.path()?;
-            Ok(ToolCommand::direct(OsStr::new("node"), args, &path))
+            Ok(ToolCommand::direct(OsStr::new("node"), args, &path).with_node(&image.node.runtime))
         }
         None => ToolCommand::passthrough(OsStr::new("node"), args, ErrorDetails::NoPlatform),
     }
@@ -5,7 +5,7 @@ use super::{intercept_global_installs, CommandArg, ToolCommand};
 use crate::error::ErrorDetails;
 use crate::session::{ActivityKind, Session};
 
-use volta_fail::{throw, Fallible};
+use volta_fail::Fallible;
 
 pub(super) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
 where
@@ -15,14 +15,13 @@ where
 
     match session.current_platform()? {
         Some(ref platform) => {
-            if intercept_global_installs() {
-                if let CommandArg::GlobalAdd(package) = check_npm_install() {
-                    throw!(ErrorDetails::NoGlobalInstalls { package });
-                }
+            let command_arg = check_npm_install();
+            if let Some(packages) = intercept_global_installs("npm", command_arg, session)? {
+                return ToolCommand::volta_install(packages);
             }
             let image = platform.checkout(session)?;
             let path = image.path()?;
-            Ok(ToolCommand::direct(OsStr::new("npm"), args, &path))
+            Ok(ToolCommand::direct(OsStr::new("npm"), args, &path).with_node(&image.node.runtime))
         }
         None => ToolCommand::passthrough(OsStr::new("npm"), args, ErrorDetails::NoPlatform),
     }
@@ -52,8 +51,8 @@ fn check_npm_install() -> CommandArg {
         || command == Some(OsString::from("add"))
     {
         // `args` here picks up from where the command lookup left off, so
-        // will be the name of the package passed to the command.
-        CommandArg::GlobalAdd(args.next())
+        // will be the names of the packages passed to the command.
+        CommandArg::GlobalAdd(args.collect())
     } else {
         CommandArg::NotGlobalAdd
     }
@@ -1,28 +1,61 @@
 use std::ffi::{OsStr, OsString};
 
+use super::binary::{find_local_bin, with_loader};
 use super::ToolCommand;
 use crate::error::ErrorDetails;
 use crate::session::{ActivityKind, Session};
 use crate::version::VersionSpec;
 
+use log::debug;
 use volta_fail::Fallible;
 
+/// The npx flags that can come before the command without changing which package provides it.
+const PASSIVE_FLAGS: [&str; 8] = [
+    "-y",
+    "--yes",
+    "--no",
+    "--no-install",
+    "-q",
+    "--quiet",
+    "--prefer-offline",
+    "--offline",
+];
+
 pub(super) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
 where
     A: IntoIterator<Item = OsString>,
 {
     session.add_event_start(ActivityKind::Npx);
+    let args: Vec<OsString> = args.into_iter().collect();
 
     match session.current_platform()? {
         Some(ref platform) => {
             let image = platform.checkout(session)?;
+            let path = image.path()?;
+
+            // A command the project already provides is run directly, so that npx never looks
+            // for it in the registry and it always runs with the project's platform
+            if let (Some(project), Some(index)) = (session.project()?, local_command(&args)) {
+                if let Some((path_to_bin, loader)) =
+                    find_local_bin(&project.local_bin_dir(), &args[index])
+                {
+                    debug!(
+                        "Running {} from the project instead of through npx",
+                        path_to_bin.display()
+                    );
+                    let rest = args[index + 1..].iter().cloned();
+                    let (bin, args) = with_loader(path_to_bin.into_os_string(), loader, rest);
+                    return Ok(ToolCommand::project_local(&bin, args, &path)
+                        .with_node(&image.node.runtime));
+                }
+            }
 
             // npx was only included with npm 5.2.0 and higher. If the npm version is less than that, we
             // should include a helpful error message
             let required_npm = VersionSpec::parse_version("5.2.0")?;
             if image.node.npm >= required_npm {
-                let path = image.path()?;
-                Ok(ToolCommand::direct(OsStr::new("npx"), args, &path))
+                Ok(ToolCommand::direct(OsStr::new("npx"), args, &path)
+                    .with_node(&image.node.runtime))
             } else {
                 Err(ErrorDetails::NpxNotAvailable {
                     version: image.node.npm.to_string(),
@@ -33,3 +66,57 @@ where
         None => ToolCommand::passthrough(OsStr::new("npx"), args, ErrorDetails::NoPlatform),
     }
 }
+
+/// Finds the position of the command in the npx arguments, if it could be a binary from the
+/// project. Commands with a version, a path, or an explicit `--package` or `--call` always go
+/// through npx, as do any after flags that might take a value.
+fn local_command(args: &[OsString]) -> Option<usize> {
+    let mut index = 0;
+    while let Some(arg) = args.get(index) {
+        let arg = arg.to_str()?;
+        if arg == "--" {
+            index += 1;
+            break;
+        } else if PASSIVE_FLAGS.contains(&arg) {
+            index += 1;
+        } else if arg.starts_with('-') {
+            return None;
+        } else {
+            break;
+        }
+    }
+
+    let command = args.get(index)?.to_str()?;
+    if command.is_empty() || command.contains(&['@', '/', '\\'][..]) {
+        None
+    } else {
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::local_command;
+    use std::ffi::OsString;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn finds_local_commands() {
+        assert_eq!(local_command(&args(&["eslint", "--fix"])), Some(0));
+        assert_eq!(local_command(&args(&["--yes", "eslint"])), Some(1));
+        assert_eq!(local_command(&args(&["--", "eslint", "-p"])), Some(1));
+    }
+
+    #[test]
+    fn leaves_other_commands_to_npx() {
+        assert_eq!(local_command(&args(&[])), None);
+        assert_eq!(local_command(&args(&["eslint@8"])), None);
+        assert_eq!(local_command(&args(&["@scope/tool"])), None);
+        assert_eq!(local_command(&args(&["./script.js"])), None);
+        assert_eq!(local_command(&args(&["-p", "typescript", "tsc"])), None);
+        assert_eq!(local_command(&args(&["--package=typescript", "tsc"])), None);
+    }
+}
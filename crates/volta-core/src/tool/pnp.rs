@@ -0,0 +1,99 @@
+//! Provides support for running binaries in Yarn Plug'n'Play projects, which install
+//! dependencies into zip archives instead of `node_modules` and so have no `node_modules/.bin`.
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use crate::command::create_command;
+use crate::distro::package::BinLoader;
+use crate::error::ErrorDetails;
+use crate::project::Project;
+
+use log::debug;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// Script run with the project's PnP runtime loaded, which prints the full path to a binary
+/// provided by a dependency. Since the PnP runtime patches `require` and `fs`, this works even
+/// though the dependency's files live inside a zip archive.
+///
+/// Note: This is kept to a single line without double quotes, since on Windows it is passed
+/// through `cmd.exe`, which would end the command at the first line break.
+const RESOLVE_BIN_SCRIPT: &'static str = concat!(
+    "const path = require('path');",
+    "const [name, bin] = process.argv.slice(1);",
+    "const manifestPath = require.resolve(name + '/package.json', { paths: [process.cwd()] });",
+    "const manifest = require(manifestPath);",
+    "let bins = manifest.bin || {};",
+    "if (typeof bins === 'string') {",
+    "  bins = { [manifest.name.replace(/^@[^/]+[/]/, '')]: bins };",
+    "}",
+    "if (bins[bin]) {",
+    "  process.stdout.write(path.join(path.dirname(manifestPath), bins[bin]));",
+    "}",
+);
+
+/// Resolves a binary provided by the input dependency of a Plug'n'Play project, returning the
+/// path to the binary along with the loader needed to run it with the PnP runtime.
+pub(super) fn find_bin(
+    project: &Project,
+    pnp_file: &Path,
+    package: &str,
+    exe: &OsStr,
+    path_var: &OsStr,
+) -> Fallible<(PathBuf, Option<BinLoader>)> {
+    let bin = exe.to_string_lossy().to_string();
+
+    let mut command = create_command("node");
+    command
+        .arg("--require")
+        .arg(pnp_file)
+        .arg("-e")
+        .arg(RESOLVE_BIN_SCRIPT)
+        .arg(package)
+        .arg(exe)
+        .current_dir(project.project_root())
+        .env("PATH", path_var)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+
+    debug!(
+        "Resolving {} from {} with the Plug'n'Play runtime at {}",
+        bin,
+        package,
+        pnp_file.display()
+    );
+    let output = command
+        .output()
+        .with_context(|_| ErrorDetails::PnpBinaryResolveError { bin: bin.clone() })?;
+
+    let bin_path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || bin_path.is_empty() {
+        throw!(ErrorDetails::PnpBinaryResolveError { bin });
+    }
+
+    debug!("Found {} at {}", bin, bin_path);
+    Ok((
+        PathBuf::from(bin_path),
+        Some(BinLoader {
+            command: "node".to_string(),
+            args: vec![
+                OsString::from("--require"),
+                pnp_file.as_os_str().to_os_string(),
+            ],
+            env: HashMap::new(),
+        }),
+    ))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::RESOLVE_BIN_SCRIPT;
+
+    #[test]
+    fn resolve_script_is_safe_for_cmd() {
+        assert!(!RESOLVE_BIN_SCRIPT.contains('\n'));
+        assert!(!RESOLVE_BIN_SCRIPT.contains('"'));
+    }
+}
@@ -0,0 +1,164 @@
+//! Provides the command for running one of the commands that a project defines in the `commands`
+//! of its `volta` section, like `"test": "jest --ci"`.
+
+use std::env;
+use std::ffi::OsString;
+use std::iter;
+use std::process::Command;
+
+use log::debug;
+use volta_fail::{throw, Fallible, ResultExt};
+
+use super::{ToolCommand, ToolSource};
+use crate::error::ErrorDetails;
+use crate::platform::{MergedPlatform, Source};
+use crate::session::Session;
+
+pub(super) fn command<A>(name: &str, args: A, session: &mut Session) -> Fallible<ToolCommand>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    let project = match session.project()? {
+        Some(project) => project,
+        None => throw!(ErrorDetails::NotInPackage),
+    };
+    let commands = &project.manifest().commands;
+    let script = match commands.get(name) {
+        Some(script) => script.clone(),
+        None => throw!(ErrorDetails::ProjectCommandNotFound {
+            name: name.to_string(),
+            available: commands.keys().cloned().collect(),
+        }),
+    };
+
+    // Like the project's tools, the command runs with the default platform if the project doesn't
+    // pin one
+    let (platform, source) = match session.merged_platform()? {
+        Some(MergedPlatform {
+            spec,
+            node: Source::Project,
+            ..
+        }) => (spec, ToolSource::Project),
+        Some(merged) => (merged.spec, ToolSource::Default),
+        None => throw!(ErrorDetails::NoPlatform),
+    };
+    let image = platform.checkout(session)?;
+
+    // The project's own executables come first, as they do for npm scripts
+    let image_path = image.path()?;
+    let path =
+        env::join_paths(iter::once(project.local_bin_dir()).chain(env::split_paths(&image_path)))
+            .with_context(|_| ErrorDetails::BuildPathError)?;
+
+    debug!(
+        "Running the project's {} command `{}` with {}",
+        name, script, platform
+    );
+    let mut command = shell_command(&script, args);
+    command.env("PATH", path);
+
+    Ok(ToolCommand::project_command(command, name, source).with_node(&image.node.runtime))
+}
+
+/// Builds a command that runs a command line with the shell, as npm does for scripts. The extra
+/// arguments are passed to the shell separately, so that they don't need to be quoted.
+#[cfg(unix)]
+fn shell_command<A>(script: &str, args: A) -> Command
+where
+    A: IntoIterator<Item = OsString>,
+{
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$@\"", script))
+        .arg("sh")
+        .args(args);
+    command
+}
+
+/// Builds a command that runs a command line with `cmd.exe`, as npm does for scripts. The extra
+/// arguments are added to the command line escaped for `cmd.exe` (see `quote_cmd_arg`), and the
+/// command line is passed as is, since the usual quoting of arguments doesn't apply to `cmd.exe`.
+#[cfg(windows)]
+fn shell_command<A>(script: &str, args: A) -> Command
+where
+    A: IntoIterator<Item = OsString>,
+{
+    use std::os::windows::process::CommandExt;
+
+    let mut line = script.to_string();
+    for arg in args {
+        line.push(' ');
+        line.push_str(&quote_cmd_arg(&arg.to_string_lossy()));
+    }
+
+    // With `/s`, `cmd.exe` removes the outer quotes and runs the rest of the line unchanged
+    let mut command = Command::new("cmd.exe");
+    command.raw_arg(format!("/d /s /c \"{}\"", line));
+    command
+}
+
+/// Escapes an argument for a `cmd.exe` command line. An argument with spaces or quotes is quoted,
+/// with its own quotes doubled. Then every character that `cmd.exe` treats specially, including
+/// the quotes, is escaped with a caret, so that `cmd.exe` passes the argument through unchanged.
+#[cfg(windows)]
+fn quote_cmd_arg(arg: &str) -> String {
+    let quoted = if arg.is_empty() || arg.contains(|c| c == ' ' || c == '\t' || c == '"') {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    } else {
+        arg.to_string()
+    };
+
+    let mut escaped = String::with_capacity(quoted.len());
+    for c in quoted.chars() {
+        if let '&' | '|' | '<' | '>' | '^' | '"' = c {
+            escaped.push('^');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(all(test, unix))]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_arguments_to_the_shell() {
+        let args = vec![OsString::from("big world"), OsString::from("--ci")];
+        let output = shell_command("echo hello", args).output().unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hello big world --ci\n"
+        );
+    }
+}
+
+#[cfg(all(test, windows))]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_arguments_for_cmd() {
+        assert_eq!(quote_cmd_arg("--ci"), "--ci");
+        assert_eq!(quote_cmd_arg(""), "^\"^\"");
+        assert_eq!(quote_cmd_arg("big world"), "^\"big world^\"");
+        assert_eq!(quote_cmd_arg("a&b|c"), "a^&b^|c");
+        assert_eq!(quote_cmd_arg("<in>^"), "^<in^>^^");
+        assert_eq!(quote_cmd_arg("say \"hi\""), "^\"say ^\"^\"hi^\"^\"^\"");
+    }
+
+    #[test]
+    fn passes_arguments_to_cmd() {
+        let args = vec![OsString::from("big world"), OsString::from("a&b")];
+        let output = shell_command("echo hello", args).output().unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim_end(),
+            "hello \"big world\" a&b"
+        );
+    }
+}
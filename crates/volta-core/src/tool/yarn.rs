@@ -4,10 +4,11 @@ use std::rc::Rc;
 
 use super::{intercept_global_installs, CommandArg, ToolCommand};
 use crate::error::ErrorDetails;
-use crate::platform::PlatformSpec;
+use crate::platform::{PlatformSpec, Source};
 use crate::session::{ActivityKind, Session};
 
-use volta_fail::{throw, Fallible};
+use log::debug;
+use volta_fail::Fallible;
 
 pub(super) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
 where
@@ -17,39 +18,46 @@ where
 
     match get_yarn_platform(session)? {
         Some(ref platform) => {
-            if intercept_global_installs() {
-                if let CommandArg::GlobalAdd(package) = check_yarn_add() {
-                    throw!(ErrorDetails::NoGlobalInstalls { package });
-                }
+            let command_arg = check_yarn_add();
+            if let Some(packages) = intercept_global_installs("Yarn", command_arg, session)? {
+                return ToolCommand::volta_install(packages);
             }
 
             let image = platform.checkout(session)?;
             let path = image.path()?;
-            Ok(ToolCommand::direct(OsStr::new("yarn"), args, &path))
+            Ok(ToolCommand::direct(OsStr::new("yarn"), args, &path).with_node(&image.node.runtime))
         }
         None => ToolCommand::passthrough(OsStr::new("yarn"), args, ErrorDetails::NoPlatform),
     }
 }
 
-/// Determine the correct platform (project or user) and check if yarn is set for that platform
+/// Determine the current platform, where the project's pins overlay the user's default platform,
+/// and check if yarn is set for that platform
 fn get_yarn_platform(session: &mut Session) -> Fallible<Option<Rc<PlatformSpec>>> {
-    // First check if we are in a pinned project
-    if let Some(platform) = session.project_platform()? {
-        return match platform.yarn {
-            Some(_) => Ok(Some(platform)),
-            None => Err(ErrorDetails::NoProjectYarn.into()),
-        };
+    let merged = match session.merged_platform()? {
+        Some(merged) => merged,
+        None => return Ok(None),
+    };
+
+    if merged.yarn == Source::Project {
+        if let Some(yarn_path) = session.project()?.and_then(|project| project.yarn_path()) {
+            debug!(
+                "Project sets yarnPath to {}, the pinned Yarn will defer to that release",
+                yarn_path
+            );
+        }
     }
 
-    // If not, fall back to the user platform
-    if let Some(platform) = session.user_platform()? {
-        return match platform.yarn {
-            Some(_) => Ok(Some(platform)),
-            None => Err(ErrorDetails::NoUserYarn.into()),
-        };
+    if merged.spec.yarn.is_some() {
+        return Ok(Some(merged.spec));
     }
 
-    Ok(None)
+    // Yarn isn't set by the project or by the default platform, so the error points to wherever
+    // the rest of the platform comes from
+    match merged.yarn {
+        Source::Project => Err(ErrorDetails::NoProjectYarn.into()),
+        Source::Default => Err(ErrorDetails::NoUserYarn.into()),
+    }
 }
 
 fn check_yarn_add() -> CommandArg {
@@ -61,7 +69,7 @@ fn check_yarn_add() -> CommandArg {
     });
 
     if (args.next(), args.next()) == (Some(OsString::from("global")), Some(OsString::from("add"))) {
-        CommandArg::GlobalAdd(args.next())
+        CommandArg::GlobalAdd(args.collect())
     } else {
         CommandArg::NotGlobalAdd
     }
@@ -1,13 +1,11 @@
-use std::fs::write;
-
 use lazycell::LazyCell;
-use readext::ReadExt;
 use semver::Version;
 
 use crate::distro::node::NodeVersion;
 use crate::error::ErrorDetails;
-use crate::fs::touch;
-use crate::path::user_platform_file;
+use crate::fs::{read_file_opt, remove_stale_staged_files, write_atomic};
+use crate::path::{self, user_platform_file};
+use crate::permissions;
 use crate::platform::PlatformSpec;
 
 use log::debug;
@@ -41,26 +39,46 @@ impl LazyToolchain {
 
 pub struct Toolchain {
     platform: Option<PlatformSpec>,
+    deno: Option<Version>,
+    bun: Option<Version>,
 }
 
 impl Toolchain {
     fn current() -> Fallible<Toolchain> {
         let path = user_platform_file()?;
-        let src = touch(&path)
-            .and_then(|mut file| file.read_into_string())
-            .with_context(|_| ErrorDetails::ReadPlatformError { file: path.clone() })?;
-
-        let platform = serial::Platform::from_json(src)?.into_platform()?;
+        // The file isn't created here, so that a read-only Volta directory can still be used
+        let src = read_file_opt(&path)
+            .with_context(|_| ErrorDetails::ReadPlatformError { file: path.clone() })?
+            .unwrap_or_default();
+
+        let user_platform = serial::Platform::from_json(src)?;
+        let deno = user_platform.deno.clone();
+        let bun = user_platform.bun.clone();
+        let platform = user_platform.into_platform()?;
         if platform.is_some() {
             debug!("Found user default platform at {}", path.display());
         }
-        Ok(Toolchain { platform })
+        Ok(Toolchain {
+            platform,
+            deno,
+            bun,
+        })
     }
 
     pub fn platform_ref(&self) -> Option<&PlatformSpec> {
         self.platform.as_ref()
     }
 
+    /// Returns the user's default version of Deno, if any.
+    pub fn deno(&self) -> Option<&Version> {
+        self.deno.as_ref()
+    }
+
+    /// Returns the user's default version of Bun, if any.
+    pub fn bun(&self) -> Option<&Version> {
+        self.bun.as_ref()
+    }
+
     /// Set the active Node version in the user platform file.
     pub fn set_active_node(&mut self, node_version: NodeVersion) -> Fallible<()> {
         let mut dirty = false;
@@ -75,11 +93,17 @@ impl Toolchain {
                 platform.npm = Some(node_version.npm);
                 dirty = true;
             }
+
+            if platform.arch != node_version.arch {
+                platform.arch = node_version.arch;
+                dirty = true;
+            }
         } else {
             self.platform = Some(PlatformSpec {
                 node_runtime: node_version.runtime,
                 npm: Some(node_version.npm),
                 yarn: None,
+                arch: node_version.arch,
             });
             dirty = true;
         }
@@ -127,15 +151,93 @@ impl Toolchain {
         Ok(())
     }
 
+    /// Set the active Deno version in the user platform file.
+    pub fn set_active_deno(&mut self, deno_version: Version) -> Fallible<()> {
+        if self.deno.as_ref() != Some(&deno_version) {
+            self.deno = Some(deno_version);
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the default Deno version from the user platform file.
+    pub fn clear_deno(&mut self) -> Fallible<()> {
+        if self.deno.take().is_some() {
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    /// Set the active Bun version in the user platform file.
+    pub fn set_active_bun(&mut self, bun_version: Version) -> Fallible<()> {
+        if self.bun.as_ref() != Some(&bun_version) {
+            self.bun = Some(bun_version);
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the default Bun version from the user platform file.
+    pub fn clear_bun(&mut self) -> Fallible<()> {
+        if self.bun.take().is_some() {
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the user platform file. The new default is staged next to the file and renamed over
+    /// it, so that a switch that is interrupted leaves the previous default in place.
     pub fn save(&self) -> Fallible<()> {
         let path = user_platform_file()?;
-        let result = match &self.platform {
-            Some(platform) => {
-                let src = platform.to_serial().to_json()?;
-                write(&path, src)
+        let src = match (&self.platform, &self.deno, &self.bun) {
+            (None, None, None) => "{}".to_string(),
+            (platform, deno, bun) => {
+                let mut user_platform = match platform {
+                    Some(platform) => platform.to_serial(),
+                    None => serial::Platform::default(),
+                };
+                user_platform.deno = deno.clone();
+                user_platform.bun = bun.clone();
+                user_platform.to_json()?
             }
-            None => write(&path, "{}"),
         };
-        result.with_context(|_| ErrorDetails::WritePlatformError { file: path })
+        write_atomic(&path, src.as_bytes())
+            .with_context(|_| ErrorDetails::WritePlatformError { file: path.clone() })?;
+        permissions::apply(&path)
+    }
+}
+
+/// Rolls back the changes to the default platform that were interrupted before they took effect,
+/// by removing what they staged, so that they don't pile up next to the files they were for. The
+/// configs of installed executables, the metadata cache, and the project memos are staged the same
+/// way (see `fs::write_atomic`), so their leftovers are removed too.
+pub fn roll_back_incomplete_switches() -> Fallible<()> {
+    for dir in &[
+        path::user_toolchain_dir()?,
+        path::node_inventory_dir()?,
+        path::user_bin_dir()?,
+        path::metadata_cache_dir()?,
+        path::project_bins_dir()?,
+    ] {
+        match remove_stale_staged_files(dir) {
+            Ok(removed) => {
+                for file in removed {
+                    debug!(
+                        "Rolled back an incomplete change by removing {}",
+                        file.display()
+                    );
+                }
+            }
+            Err(error) => debug!(
+                "Could not roll back the incomplete changes in {}: {}",
+                dir.display(),
+                error
+            ),
+        }
     }
+    Ok(())
 }
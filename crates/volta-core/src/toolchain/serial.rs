@@ -14,24 +14,38 @@ pub struct NodeVersion {
     pub runtime: Version,
     #[serde(with = "option_version_serde")]
     pub npm: Option<Version>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arch: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
 pub struct Platform {
     #[serde(default)]
     pub node: Option<NodeVersion>,
     #[serde(default)]
     #[serde(with = "option_version_serde")]
     pub yarn: Option<Version>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "option_version_serde")]
+    pub deno: Option<Version>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "option_version_serde")]
+    pub bun: Option<Version>,
 }
 
 impl Platform {
+    /// Produces the user's Node platform. The user's default Deno and Bun aren't part of the
+    /// platform, so they should be read from `deno` and `bun` first.
     pub fn into_platform(self) -> Fallible<Option<PlatformSpec>> {
         let yarn = self.yarn;
         Ok(self.node.map(|node_version| PlatformSpec {
             node_runtime: node_version.runtime,
             npm: node_version.npm,
             yarn,
+            arch: node_version.arch,
         }))
     }
 
@@ -58,8 +72,11 @@ impl PlatformSpec {
             node: Some(NodeVersion {
                 runtime: self.node_runtime.clone(),
                 npm: self.npm.clone(),
+                arch: self.arch.clone(),
             }),
             yarn: self.yarn.clone(),
+            deno: None,
+            bun: None,
         }
     }
 }
@@ -91,7 +108,10 @@ pub mod tests {
             node: Some(NodeVersion {
                 runtime: Version::parse("4.5.6").expect("could not parse version"),
                 npm: Some(Version::parse("7.8.9").expect("could not parse version")),
+                arch: None,
             }),
+            deno: None,
+            bun: None,
         };
         assert_eq!(platform, expected_platform);
     }
@@ -103,6 +123,8 @@ pub mod tests {
         let expected_platform = Platform {
             node: None,
             yarn: None,
+            deno: None,
+            bun: None,
         };
         assert_eq!(platform, expected_platform);
     }
@@ -113,6 +135,7 @@ pub mod tests {
             yarn: Some(Version::parse("1.2.3").expect("could not parse version")),
             node_runtime: Version::parse("4.5.6").expect("could not parse version"),
             npm: Some(Version::parse("7.8.9").expect("could not parse version")),
+            arch: None,
         };
         let json_str = platform
             .to_serial()
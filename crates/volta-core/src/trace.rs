@@ -0,0 +1,142 @@
+//! Records how long each step of running a shim takes when `VOLTA_TRACE` is set, and writes the
+//! steps as a Chrome trace to the `log/traces` directory of the Volta home directory. Traces can
+//! be opened in `chrome://tracing` or Perfetto, and attached to reports of Volta being slow.
+
+use std::fs::write;
+use std::mem;
+use std::process;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+use log::debug;
+use serde::Serialize;
+
+use crate::env::{flag_enabled, TRACE};
+use crate::fs::ensure_containing_dir_exists;
+use crate::path;
+use volta_fail::ResultExt;
+
+lazy_static! {
+    static ref ENABLED: bool = flag_enabled(TRACE);
+    static ref EVENTS: Mutex<Vec<TraceEvent>> = Mutex::new(Vec::new());
+}
+
+/// A complete event in the Chrome trace format, whose times are in microseconds.
+#[derive(Serialize)]
+struct TraceEvent {
+    name: String,
+    cat: &'static str,
+    ph: &'static str,
+    ts: u64,
+    dur: u64,
+    pid: u32,
+    tid: u32,
+}
+
+#[derive(Serialize)]
+struct TraceFile<'a> {
+    #[serde(rename = "traceEvents")]
+    trace_events: &'a [TraceEvent],
+    #[serde(rename = "displayTimeUnit")]
+    display_time_unit: &'static str,
+}
+
+/// A step that is being timed, which is recorded when it is dropped.
+pub struct Span {
+    started: Option<(String, SystemTime, Instant)>,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some((name, start, instant)) = self.started.take() {
+            let event = TraceEvent {
+                name,
+                cat: "volta",
+                ph: "X",
+                ts: micros(start.duration_since(UNIX_EPOCH).unwrap_or_default()),
+                dur: micros(instant.elapsed()),
+                pid: process::id(),
+                tid: 0,
+            };
+            if let Ok(mut events) = EVENTS.lock() {
+                events.push(event);
+            }
+        }
+    }
+}
+
+/// Starts timing a step, which ends when the returned span is dropped. Nothing is recorded unless
+/// tracing is enabled.
+pub fn span<S>(name: S) -> Span
+where
+    S: Into<String>,
+{
+    Span {
+        started: if *ENABLED {
+            Some((name.into(), SystemTime::now(), Instant::now()))
+        } else {
+            None
+        },
+    }
+}
+
+/// Writes the recorded steps to a new trace file, if tracing is enabled. Failing to write the
+/// trace is only logged, since the tool has already run.
+pub fn write_trace() {
+    if !*ENABLED {
+        return;
+    }
+
+    let events = match EVENTS.lock() {
+        Ok(mut events) => mem::replace(&mut *events, Vec::new()),
+        Err(_) => return,
+    };
+    let started = events.iter().map(|event| event.ts).min().unwrap_or(0);
+    let trace = TraceFile {
+        trace_events: &events,
+        display_time_unit: "ms",
+    };
+
+    let written = path::trace_file(started / 1000, process::id()).and_then(|file| {
+        ensure_containing_dir_exists(&file)?;
+        let json = serde_json::to_string(&trace).unknown()?;
+        write(&file, json).unknown()?;
+        Ok(file)
+    });
+    match written {
+        Ok(file) => debug!("Wrote the trace to {}", file.display()),
+        Err(error) => debug!("Could not write the trace: {}", error),
+    }
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_complete_events() {
+        let event = TraceEvent {
+            name: "spawn".to_string(),
+            cat: "volta",
+            ph: "X",
+            ts: micros(Duration::from_millis(1500)),
+            dur: micros(Duration::new(0, 2_500_000)),
+            pid: 42,
+            tid: 0,
+        };
+        let trace = TraceFile {
+            trace_events: &[event],
+            display_time_unit: "ms",
+        };
+
+        assert_eq!(
+            serde_json::to_string(&trace).unwrap(),
+            r#"{"traceEvents":[{"name":"spawn","cat":"volta","ph":"X","ts":1500000,"dur":2500,"pid":42,"tid":0}],"displayTimeUnit":"ms"}"#
+        );
+    }
+}
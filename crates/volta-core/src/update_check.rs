@@ -0,0 +1,192 @@
+//! Provides the notice that a newer version of Volta is available. The newest version is fetched
+//! in the background at most once a day, whether or not the fetch finishes before the command
+//! does, and the notice is shown at the end of interactive `volta` commands.
+
+use std::fs::write;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use atty::Stream;
+use console::style;
+use log::debug;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::env::{flag_enabled, NO_UPDATE_NOTIFIER};
+use crate::error::ErrorDetails;
+use crate::fs::{ensure_containing_dir_exists, read_file_opt};
+use crate::hook::HookConfig;
+use crate::path;
+use crate::self_update::{parse_version, Channel};
+use volta_fail::{Fallible, ResultExt};
+
+/// How long to wait after fetching the newest version before fetching it again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The result of the last check, kept on disk between commands.
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    /// When the newest version was last fetched, in seconds since the Unix epoch.
+    last_check: u64,
+    /// The newest version on the release channel at that time.
+    latest: Option<String>,
+}
+
+pub struct UpdateCheck {
+    current: Option<Version>,
+    latest: Option<Version>,
+    /// The check running in the background, if the last one is out of date.
+    pending: Option<Receiver<Version>>,
+}
+
+impl UpdateCheck {
+    /// Starts fetching the newest version in the background, if notices are enabled and the
+    /// last check is out of date.
+    pub fn start(current: &str, enabled: bool) -> Self {
+        let current = match parse_version(current) {
+            Ok(current) if enabled && notices_enabled() => current,
+            _ => {
+                return UpdateCheck {
+                    current: None,
+                    latest: None,
+                    pending: None,
+                }
+            }
+        };
+
+        let state = read_state().unwrap_or_else(|error| {
+            debug!("Could not read the last update check.\n{}", error);
+            State::default()
+        });
+        let pending = if is_due(state.last_check, now()) {
+            // The check is recorded before it starts, since the command may well exit before the
+            // check finishes, and a check that fails shouldn't be retried by every command either
+            let started = State {
+                last_check: now(),
+                latest: state.latest.clone(),
+            };
+            match write_state(&started) {
+                Ok(()) => start_check(),
+                Err(error) => {
+                    debug!("Could not save the update check.\n{}", error);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        UpdateCheck {
+            current: Some(current),
+            latest: state.latest.and_then(|latest| Version::parse(&latest).ok()),
+            pending,
+        }
+    }
+
+    /// Shows the notice if a newer version than the current one is known. A check that is still
+    /// running isn't waited for, so that the notice never holds up a command.
+    pub fn show(self) {
+        let latest = match self.pending.and_then(|pending| pending.try_recv().ok()) {
+            Some(latest) => Some(latest),
+            None => self.latest,
+        };
+
+        if let (Some(current), Some(latest)) = (self.current, latest) {
+            if latest > current {
+                eprintln!(
+                    "\n{} Volta v{} is available (currently v{}). Run `volta self-update` to install it.",
+                    style("note:").cyan().bold(),
+                    latest,
+                    current
+                );
+            }
+        }
+    }
+}
+
+/// Determines whether notices are wanted, which they are unless they are turned off with
+/// `VOLTA_NO_UPDATE_NOTIFIER` or the `volta.notify` hook, or nobody is at a terminal to see them.
+fn notices_enabled() -> bool {
+    if flag_enabled(NO_UPDATE_NOTIFIER) || !atty::is(Stream::Stderr) || path::is_read_only_home() {
+        return false;
+    }
+
+    match HookConfig::for_user() {
+        Ok(hooks) => hooks
+            .and_then(|hooks| hooks.volta)
+            .and_then(|volta| volta.notify)
+            .unwrap_or(true),
+        Err(_) => false,
+    }
+}
+
+fn start_check() -> Option<Receiver<Version>> {
+    let channel = Channel::current().ok()?;
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || match channel.latest_version() {
+        Ok(latest) => {
+            let state = State {
+                last_check: now(),
+                latest: Some(latest.to_string()),
+            };
+            if let Err(error) = write_state(&state) {
+                debug!("Could not save the update check.\n{}", error);
+            }
+            let _ = sender.send(latest);
+        }
+        Err(error) => debug!("Could not check for a new version of Volta.\n{}", error),
+    });
+
+    Some(receiver)
+}
+
+fn is_due(last_check: u64, now: u64) -> bool {
+    // A last check in the future means the clock has changed, so it can't be trusted
+    now < last_check || now - last_check >= CHECK_INTERVAL.as_secs()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+fn read_state() -> Fallible<State> {
+    let file = path::update_check_file()?;
+    let src = read_file_opt(&file)
+        .with_context(|_| ErrorDetails::ReadUpdateCheckError { file: file.clone() })?;
+
+    match src {
+        Some(src) => {
+            serde_json::from_str(&src).with_context(|_| ErrorDetails::ReadUpdateCheckError { file })
+        }
+        None => Ok(State::default()),
+    }
+}
+
+fn write_state(state: &State) -> Fallible<()> {
+    let file = path::update_check_file()?;
+    let src = serde_json::to_string(state).unknown()?;
+    ensure_containing_dir_exists(&file)?;
+    write(&file, src).with_context(|_| ErrorDetails::WriteUpdateCheckError { file })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{is_due, CHECK_INTERVAL};
+
+    #[test]
+    fn checks_once_a_day() {
+        let interval = CHECK_INTERVAL.as_secs();
+
+        assert!(is_due(0, 1_500_000_000));
+        assert!(!is_due(1_500_000_000, 1_500_000_000 + interval - 1));
+        assert!(is_due(1_500_000_000, 1_500_000_000 + interval));
+
+        // a clock that moved backwards doesn't prevent checking
+        assert!(is_due(1_500_000_000, 1_400_000_000));
+    }
+}
@@ -16,6 +16,36 @@ pub enum VersionSpec {
     Lts,
     Semver(VersionReq),
     Exact(Version),
+    Channel(ReleaseChannel),
+}
+
+/// A channel on which pre-release builds of a tool are published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReleaseChannel {
+    Rc,
+    Nightly,
+    Canary,
+}
+
+impl ReleaseChannel {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rc" => Some(ReleaseChannel::Rc),
+            "nightly" => Some(ReleaseChannel::Nightly),
+            "canary" => Some(ReleaseChannel::Canary),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ReleaseChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match *self {
+            ReleaseChannel::Rc => write!(f, "rc"),
+            ReleaseChannel::Nightly => write!(f, "nightly"),
+            ReleaseChannel::Canary => write!(f, "canary"),
+        }
+    }
 }
 
 impl fmt::Display for VersionSpec {
@@ -25,6 +55,7 @@ impl fmt::Display for VersionSpec {
             VersionSpec::Lts => write!(f, "lts"),
             VersionSpec::Semver(ref req) => req.fmt(f),
             VersionSpec::Exact(ref version) => version.fmt(f),
+            VersionSpec::Channel(ref channel) => channel.fmt(f),
         }
     }
 }
@@ -62,6 +93,8 @@ impl FromStr for VersionSpec {
             return Ok(VersionSpec::Latest);
         } else if s == "lts" {
             return Ok(VersionSpec::Lts);
+        } else if let Some(channel) = ReleaseChannel::from_name(s) {
+            return Ok(VersionSpec::Channel(channel));
         }
 
         if let Ok(ref exact) = VersionSpec::parse_version(s) {
@@ -72,6 +105,15 @@ impl FromStr for VersionSpec {
     }
 }
 
+/// Determines whether a version string is a range, like `^18` or `>=16 <20`, rather than a full or
+/// partial version, like `18.17.1` or `18`.
+pub fn is_range(s: &str) -> bool {
+    let s = trim_version(s);
+    !s.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && Version::parse(s).is_err()
+        && parse_requirements(s).is_ok()
+}
+
 fn version_parse_error<E, S>(version: S) -> impl FnOnce(&E) -> ErrorDetails
 where
     E: std::error::Error,
@@ -91,6 +133,71 @@ fn trim_version(s: &str) -> &str {
     }
 }
 
+/// Finds up to `count` of the available versions that are closest to the version written in
+/// `target`, for suggesting alternatives when `target` can't be resolved. Closeness is measured
+/// component by component, so a version with the same major and minor versions is always nearer
+/// than one with only the same major version. Ties are broken in favor of the newer version.
+pub fn nearest_versions<'a>(
+    target: &str,
+    available: impl IntoIterator<Item = &'a Version>,
+    count: usize,
+) -> Vec<Version> {
+    let wanted = version_components(target);
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+
+    let distance = |version: &Version| {
+        let actual = [version.major, version.minor, version.patch];
+        let mut distance = [0; 3];
+        for (index, &component) in wanted.iter().enumerate() {
+            distance[index] = if actual[index] > component {
+                actual[index] - component
+            } else {
+                component - actual[index]
+            };
+        }
+        distance
+    };
+
+    let mut candidates: Vec<&Version> = available
+        .into_iter()
+        .filter(|version| !version.is_prerelease())
+        .collect();
+    candidates.sort_by(|a, b| distance(a).cmp(&distance(b)).then_with(|| b.cmp(a)));
+    candidates.dedup();
+
+    candidates.into_iter().take(count).cloned().collect()
+}
+
+/// Finds the newest version of each of the `count` most recent major versions, newest first, for
+/// offering a choice between release lines. Pre-releases are left out.
+pub fn newest_per_major<'a>(
+    available: impl IntoIterator<Item = &'a Version>,
+    count: usize,
+) -> Vec<Version> {
+    let mut newest: Vec<&Version> = available
+        .into_iter()
+        .filter(|version| !version.is_prerelease())
+        .collect();
+    newest.sort_by(|a, b| b.cmp(a));
+    newest.dedup_by_key(|version| version.major);
+
+    newest.into_iter().take(count).cloned().collect()
+}
+
+/// Reads the leading numeric components of a full or partial version, ignoring any operator, so
+/// that both `16.99` and `^16.99.0` produce `[16, 99]`.
+fn version_components(s: &str) -> Vec<u64> {
+    trim_version(s.trim_start_matches(|c: char| !c.is_ascii_digit() && c != 'v'))
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .take_while(Result::is_ok)
+        .filter_map(Result::ok)
+        .take(3)
+        .collect()
+}
+
 // custom serialization and de-serialization for Version
 // because Version doesn't work with serde out of the box
 pub mod version_serde {
@@ -164,3 +271,64 @@ pub mod option_version_serde {
     }
 
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn versions(list: &[&str]) -> Vec<Version> {
+        list.iter().map(|v| Version::parse(v).unwrap()).collect()
+    }
+
+    #[test]
+    fn reads_version_components() {
+        assert_eq!(version_components("16.99"), vec![16, 99]);
+        assert_eq!(version_components("^16.99.0"), vec![16, 99, 0]);
+        assert_eq!(version_components(">=v12"), vec![12]);
+        assert_eq!(version_components("16.x"), vec![16]);
+        assert!(version_components("latest").is_empty());
+    }
+
+    #[test]
+    fn suggests_nearest_versions() {
+        let available = versions(&[
+            "18.1.0",
+            "16.20.2",
+            "16.20.1",
+            "16.3.0",
+            "14.21.3",
+            "17.0.0-rc.1",
+        ]);
+
+        assert_eq!(
+            nearest_versions("16.99", &available, 3),
+            versions(&["16.20.2", "16.20.1", "16.3.0"])
+        );
+        assert_eq!(
+            nearest_versions("17", &available, 2),
+            versions(&["18.1.0", "16.20.2"])
+        );
+        assert!(nearest_versions("latest", &available, 3).is_empty());
+    }
+
+    #[test]
+    fn finds_newest_of_each_major() {
+        let available = versions(&[
+            "16.20.1",
+            "18.1.0",
+            "16.20.2",
+            "20.0.0-rc.1",
+            "14.21.3",
+            "18.0.0",
+        ]);
+
+        assert_eq!(
+            newest_per_major(&available, 2),
+            versions(&["18.1.0", "16.20.2"])
+        );
+        assert_eq!(
+            newest_per_major(&available, 5),
+            versions(&["18.1.0", "16.20.2", "14.21.3"])
+        );
+    }
+}
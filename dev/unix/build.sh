@@ -33,7 +33,7 @@ encode_expand_sed_command() {
   # - https://unix.stackexchange.com/questions/141387/sed-replace-string-with-file-contents
   # - https://serverfault.com/questions/391360/remove-line-break-using-awk
   # - https://stackoverflow.com/questions/1421478/how-do-i-use-a-new-line-replacement-in-a-bsd-sed
-  command printf "s|<PLACEHOLDER_$2_PAYLOAD>|$(sed 's/|/\\|/g' $3 | awk '{printf "%s\\\n",$0} END {print ""}' )\\\n|\n" > $1.expand.txt
+  command printf "s|<PLACEHOLDER_$2_PAYLOAD>|$(sed 's/[|&]/\\&/g' $3 | awk '{printf "%s\\\n",$0} END {print ""}' )\\\n|\n" > $1.expand.txt
 }
 
 build_dir="$script_dir/../../target/$target_dir"
@@ -43,11 +43,15 @@ encode_base64_sed_command volta VOLTA "$build_dir/volta"
 encode_base64_sed_command shim SHIM "$build_dir/shim"
 encode_expand_sed_command bash_launcher BASH_LAUNCHER "$shell_dir/unix/load.sh"
 encode_expand_sed_command fish_launcher FISH_LAUNCHER "$shell_dir/unix/load.fish"
+encode_expand_sed_command nushell_launcher NUSHELL_LAUNCHER "$shell_dir/unix/load.nu"
+encode_expand_sed_command powershell_launcher POWERSHELL_LAUNCHER "$shell_dir/unix/load.ps1"
 
 sed -f volta.base64.txt \
     -f shim.base64.txt \
     -f bash_launcher.expand.txt \
     -f fish_launcher.expand.txt \
+    -f nushell_launcher.expand.txt \
+    -f powershell_launcher.expand.txt \
     < "$script_dir/install.sh.in" > "$script_dir/install.sh"
 
 chmod 755 "$script_dir/install.sh"
@@ -55,4 +59,6 @@ chmod 755 "$script_dir/install.sh"
 rm volta.base64.txt \
    shim.base64.txt \
    bash_launcher.expand.txt \
-   fish_launcher.expand.txt
+   fish_launcher.expand.txt \
+   nushell_launcher.expand.txt \
+   powershell_launcher.expand.txt
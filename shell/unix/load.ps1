@@ -0,0 +1,24 @@
+function volta {
+    # Use the user's existing `VOLTA_HOME` environment value if set; otherwise,
+    # use a default of `~/.volta`.
+    $VoltaRoot = if ($env:VOLTA_HOME) { $env:VOLTA_HOME } else { Join-Path $HOME '.volta' }
+
+    # Generate 32 bits of randomness, to avoid clashing with concurrent executions.
+    $env:VOLTA_POSTSCRIPT = Join-Path $VoltaRoot "tmp/volta_tmp_$(Get-Random).ps1"
+
+    # Forward the arguments to the Volta executable.
+    $env:VOLTA_SHELL = 'powershell'
+    & (Join-Path $VoltaRoot 'volta') @args
+    $ExitCode = $LASTEXITCODE
+    Remove-Item Env:VOLTA_SHELL
+
+    # Call the post-invocation script if it is present, then delete it.
+    # This allows the invocation to potentially modify the caller's environment (e.g., PATH).
+    if (Test-Path $env:VOLTA_POSTSCRIPT) {
+        . $env:VOLTA_POSTSCRIPT
+        Remove-Item $env:VOLTA_POSTSCRIPT
+    }
+
+    Remove-Item Env:VOLTA_POSTSCRIPT
+    $global:LASTEXITCODE = $ExitCode
+}
@@ -1 +1,24 @@
-Write-Host "Not yet implemented"
+# Runs Volta from PowerShell, so that the invocation can modify the caller's environment (e.g.,
+# PATH). Environment variables belong to the process, so the changes outlive this script.
+
+# Use the user's existing `VOLTA_HOME` environment value if set; otherwise,
+# use the default of `%LOCALAPPDATA%\Volta`.
+$VoltaRoot = if ($env:VOLTA_HOME) { $env:VOLTA_HOME } else { Join-Path $env:LOCALAPPDATA 'Volta' }
+
+# Generate 32 bits of randomness, to avoid clashing with concurrent executions.
+$env:VOLTA_POSTSCRIPT = Join-Path $VoltaRoot "tmp\volta_tmp_$(Get-Random).ps1"
+
+# Forward the arguments to the Volta executable, which is installed alongside this script.
+$env:VOLTA_SHELL = 'powershell'
+& (Join-Path $PSScriptRoot 'volta.exe') @args
+$ExitCode = $LASTEXITCODE
+Remove-Item Env:VOLTA_SHELL
+
+# Call the post-invocation script if it is present, then delete it.
+if (Test-Path $env:VOLTA_POSTSCRIPT) {
+    . $env:VOLTA_POSTSCRIPT
+    Remove-Item $env:VOLTA_POSTSCRIPT
+}
+
+Remove-Item Env:VOLTA_POSTSCRIPT
+exit $ExitCode
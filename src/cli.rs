@@ -1,9 +1,13 @@
 use structopt::StructOpt;
 
 use crate::command::{self, Command};
+use volta_core::config;
+use volta_core::error::ErrorDetails;
+use volta_core::migrate;
 use volta_core::path;
 use volta_core::session::Session;
-use volta_fail::{ExitCode, Fallible};
+use volta_core::toolchain;
+use volta_fail::{throw, ExitCode, Fallible};
 
 #[derive(StructOpt)]
 #[structopt(
@@ -25,8 +29,13 @@ pub(crate) struct Volta {
     #[structopt(subcommand)]
     pub(crate) command: Option<Subcommand>,
 
-    #[structopt(long = "verbose", help = "Enables verbose diagnostics", global = true)]
-    pub(crate) verbose: bool,
+    #[structopt(
+        long = "verbose",
+        help = "Enables verbose diagnostics (repeat for even more detail)",
+        global = true,
+        parse(from_occurrences)
+    )]
+    pub(crate) verbose: u8,
 
     #[structopt(
         long = "quiet",
@@ -37,6 +46,42 @@ pub(crate) struct Volta {
     )]
     pub(crate) quiet: bool,
 
+    #[structopt(
+        long = "json-errors",
+        help = "Reports errors as JSON on stderr (also enabled by VOLTA_OUTPUT=json)",
+        global = true
+    )]
+    pub(crate) json_errors: bool,
+
+    #[structopt(
+        long = "no-progress",
+        help = "Reports progress as plain lines of text (also enabled by VOLTA_NO_PROGRESS)",
+        global = true
+    )]
+    pub(crate) no_progress: bool,
+
+    #[structopt(
+        long = "profile",
+        help = "Uses the named profile's default tools and global packages (also set by VOLTA_PROFILE)",
+        global = true
+    )]
+    pub(crate) profile: Option<String>,
+
+    #[structopt(
+        long = "limit-rate",
+        value_name = "rate",
+        help = "Limits how fast tools are downloaded, in bytes per second like `500k` (also set by VOLTA_LIMIT_RATE)",
+        global = true
+    )]
+    pub(crate) limit_rate: Option<String>,
+
+    #[structopt(
+        long = "dry-run",
+        help = "Lists the changes that install, pin, uninstall, setup, gc, or migrate would make, without making them",
+        global = true
+    )]
+    pub(crate) dry_run: bool,
+
     #[structopt(
         short = "v",
         long = "version",
@@ -47,7 +92,32 @@ pub(crate) struct Volta {
 
 impl Volta {
     pub(crate) fn run(self, session: &mut Session) -> Fallible<ExitCode> {
-        path::ensure_volta_dirs_exist()?;
+        if self.dry_run {
+            match self.command {
+                Some(ref command) if command.supports_dry_run() => session.enable_dry_run(),
+                // Printing the version changes nothing
+                None if self.version => {}
+                _ => throw!(ErrorDetails::DryRunUnsupported {
+                    action: "This command".into()
+                }),
+            }
+        } else {
+            path::ensure_volta_dirs_exist()?;
+        }
+
+        // `volta migrate` migrates explicitly, so that `--dry-run` can preview the changes, and
+        // nothing is migrated or rolled back while previewing another command
+        match self.command {
+            Some(Subcommand::Migrate(_)) => {}
+            _ if self.dry_run => {}
+            _ => migrate::migrate(false)?,
+        }
+
+        config::apply()?;
+        if !self.dry_run {
+            toolchain::roll_back_incomplete_switches()?;
+        }
+
         if self.version {
             println!("{}", env!("CARGO_PKG_VERSION"));
             Ok(ExitCode::Success)
@@ -61,18 +131,58 @@ impl Volta {
 
 #[derive(StructOpt)]
 pub(crate) enum Subcommand {
+    /// Sets up Volta and your default tools, asking which versions to use
+    #[structopt(name = "init", author = "", version = "")]
+    Init(command::Init),
+
     /// Fetches a tool to the local machine
     #[structopt(name = "fetch", author = "", version = "")]
     Fetch(command::Fetch),
 
+    /// Installs a project's pinned tools into a prefix without shims, e.g. for a Docker image
+    #[structopt(name = "provision", author = "", version = "")]
+    Provision(command::Provision),
+
     /// Installs a tool in your toolchain
     #[structopt(name = "install", author = "", version = "")]
     Install(command::Install),
 
+    /// Sets the default version of a tool in your toolchain
+    #[structopt(name = "default", author = "", version = "")]
+    Default(command::Default),
+
     /// Uninstalls a tool from your toolchain
     #[structopt(name = "uninstall", author = "", version = "")]
     Uninstall(command::Uninstall),
 
+    /// Upgrades installed packages to the newest versions matching how they were installed
+    #[structopt(name = "upgrade", author = "", version = "")]
+    Upgrade(command::Upgrade),
+
+    /// Rebuilds the native modules of installed packages for your default Node
+    #[structopt(name = "rebuild", author = "", version = "")]
+    Rebuild(command::Rebuild),
+
+    /// Chooses which installed package provides an executable that several packages provide
+    #[structopt(name = "prefer", author = "", version = "")]
+    Prefer(command::Prefer),
+
+    /// Links the package in the current directory into your toolchain, like `npm link`
+    #[structopt(name = "link", author = "", version = "")]
+    Link(command::Link),
+
+    /// Removes a package linked with `volta link` from your toolchain
+    #[structopt(name = "unlink", author = "", version = "")]
+    Unlink(command::Unlink),
+
+    /// Lists your tools and where their versions come from, or the packages in your toolchain
+    #[structopt(name = "list", author = "", version = "")]
+    List(command::List),
+
+    /// Checks installed packages and their dependencies for known vulnerabilities
+    #[structopt(name = "audit", author = "", version = "")]
+    Audit(command::Audit),
+
     /// Pins your project's runtime or package manager
     #[structopt(name = "pin", author = "", version = "")]
     Pin(command::Pin),
@@ -117,6 +227,50 @@ otherwise, they will be written to `stdout`.
     )]
     Completions(command::Completions),
 
+    /// Adds Volta to the `PATH` in your shell profiles
+    #[structopt(name = "setup", author = "", version = "")]
+    Setup(command::Setup),
+
+    /// Updates Volta to the newest version on its release channel
+    #[structopt(name = "self-update", author = "", version = "")]
+    SelfUpdate(command::SelfUpdate),
+
+    /// Gets and sets Volta's settings in `config.toml`
+    #[structopt(name = "config", author = "", version = "")]
+    Config(command::Config),
+
+    /// Removes the cached archives of tools that the `keep-archives` policy doesn't keep
+    #[structopt(name = "gc", author = "", version = "")]
+    Gc(command::Gc),
+
+    /// Migrates the Volta directory from the layout of an older version of Volta
+    #[structopt(name = "migrate", author = "", version = "")]
+    Migrate(command::Migrate),
+
+    /// Manages the shims that run executables through Volta
+    #[structopt(name = "shim", author = "", version = "")]
+    Shim(command::Shim),
+
+    /// Checks for problems with how Volta is set up
+    #[structopt(name = "doctor", author = "", version = "")]
+    Doctor(command::Doctor),
+
+    /// Runs a command with each of several versions of Node
+    #[structopt(name = "matrix", author = "", version = "")]
+    Matrix(command::Matrix),
+
+    /// Runs one of the commands defined in the `volta` section of the project's package.json
+    #[structopt(name = "run", author = "", version = "")]
+    Run(command::Run),
+
+    /// Checks installed tools for files that have changed since they were installed
+    #[structopt(name = "verify", author = "", version = "")]
+    Verify(command::Verify),
+
+    /// Reports the project, platforms, and images Volta uses in the current directory
+    #[structopt(name = "inspect", author = "", version = "")]
+    Inspect(command::Inspect),
+
     /// Locates the actual binary that will be called by Volta
     #[structopt(name = "which", author = "", version = "")]
     Which(command::Which),
@@ -132,21 +286,66 @@ otherwise, they will be written to `stdout`.
         )
     )]
     Use(command::Use),
+
+    /// Sends the queued events to the URL configured in the `publish` events hook
+    #[structopt(
+        name = "send-events",
+        author = "",
+        version = "",
+        raw(setting = "structopt::clap::AppSettings::Hidden")
+    )]
+    SendEvents(command::SendEvents),
 }
 
 impl Subcommand {
     pub(crate) fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         match self {
+            Subcommand::Init(init) => init.run(session),
             Subcommand::Fetch(fetch) => fetch.run(session),
+            Subcommand::Provision(provision) => provision.run(session),
             Subcommand::Install(install) => install.run(session),
+            Subcommand::Default(default) => default.run(session),
             Subcommand::Uninstall(uninstall) => uninstall.run(session),
+            Subcommand::Upgrade(upgrade) => upgrade.run(session),
+            Subcommand::Rebuild(rebuild) => rebuild.run(session),
+            Subcommand::Prefer(prefer) => prefer.run(session),
+            Subcommand::Link(link) => link.run(session),
+            Subcommand::Unlink(unlink) => unlink.run(session),
+            Subcommand::List(list) => list.run(session),
+            Subcommand::Audit(audit) => audit.run(session),
             Subcommand::Pin(pin) => pin.run(session),
             Subcommand::Current(current) => current.run(session),
             Subcommand::Deactivate(deactivate) => deactivate.run(session),
             Subcommand::Activate(activate) => activate.run(session),
             Subcommand::Completions(completions) => completions.run(session),
+            Subcommand::Setup(setup) => setup.run(session),
+            Subcommand::SelfUpdate(self_update) => self_update.run(session),
+            Subcommand::Config(config) => config.run(session),
+            Subcommand::Gc(gc) => gc.run(session),
+            Subcommand::Migrate(migrate) => migrate.run(session),
+            Subcommand::Shim(shim) => shim.run(session),
+            Subcommand::Doctor(doctor) => doctor.run(session),
+            Subcommand::Matrix(matrix) => matrix.run(session),
+            Subcommand::Run(run) => run.run(session),
+            Subcommand::Verify(verify) => verify.run(session),
+            Subcommand::Inspect(inspect) => inspect.run(session),
             Subcommand::Which(which) => which.run(session),
             Subcommand::Use(r#use) => r#use.run(session),
+            Subcommand::SendEvents(send_events) => send_events.run(session),
+        }
+    }
+
+    /// Whether the command lists the changes it would make instead of making them when run with
+    /// `--dry-run`. Other commands refuse to run with it, rather than making their changes.
+    fn supports_dry_run(&self) -> bool {
+        match self {
+            Subcommand::Install(_)
+            | Subcommand::Pin(_)
+            | Subcommand::Uninstall(_)
+            | Subcommand::Setup(_)
+            | Subcommand::Gc(_)
+            | Subcommand::Migrate(_) => true,
+            _ => false,
         }
     }
 }
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use structopt::StructOpt;
+
+use volta_core::audit::{dependency_tree, find_vulnerabilities, PackageVersions, Vulnerability};
+use volta_core::path::package_image_dir;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible, ResultExt};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Audit {
+    /// Prints the vulnerabilities as JSON, for CI and other tools
+    #[structopt(long = "json")]
+    json: bool,
+}
+
+/// An installed package with vulnerabilities in it or its dependencies.
+#[derive(Serialize)]
+struct AuditedPackage {
+    name: String,
+    version: String,
+    vulnerabilities: Vec<Vulnerability>,
+}
+
+impl Command for Audit {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Audit);
+
+        let mut trees = Vec::new();
+        let mut all_packages = PackageVersions::new();
+        for config in session.installed_packages()? {
+            let version = config.version.to_string();
+            let tree = dependency_tree(&package_image_dir(&config.name, &version)?)?;
+            for (name, versions) in &tree {
+                all_packages
+                    .entry(name.clone())
+                    .or_default()
+                    .extend(versions.iter().cloned());
+            }
+            trees.push((config.name, version, tree));
+        }
+
+        let vulnerabilities = find_vulnerabilities(&all_packages)?;
+
+        let audited: Vec<AuditedPackage> = trees
+            .into_iter()
+            .map(|(name, version, tree)| AuditedPackage {
+                name,
+                version,
+                vulnerabilities: vulnerabilities
+                    .iter()
+                    .filter(|vulnerability| {
+                        tree.get(&vulnerability.name)
+                            .map_or(false, |versions| versions.contains(&vulnerability.version))
+                    })
+                    .cloned()
+                    .collect(),
+            })
+            .filter(|package| !package.vulnerabilities.is_empty())
+            .collect();
+
+        if self.json {
+            let json = serde_json::to_string_pretty(&audited).unknown()?;
+            println!("{}", json);
+        } else if audited.is_empty() {
+            println!("No known vulnerabilities were found in the installed packages.");
+        } else {
+            for package in &audited {
+                print_package(package);
+            }
+        }
+
+        let exit_code = if audited.is_empty() {
+            ExitCode::Success
+        } else {
+            ExitCode::UnknownError
+        };
+
+        session.add_event_end(ActivityKind::Audit, exit_code);
+        Ok(exit_code)
+    }
+}
+
+fn print_package(package: &AuditedPackage) {
+    println!("{}@{}", package.name, package.version);
+
+    for vulnerability in &package.vulnerabilities {
+        let advisory = &vulnerability.advisory;
+        println!(
+            "    {}: {} ({}@{})",
+            advisory.severity, advisory.title, vulnerability.name, vulnerability.version
+        );
+        match advisory.patched_versions {
+            Some(ref patched) => println!("        fixed in: {}", patched),
+            None => println!("        affects: {}", advisory.vulnerable_versions),
+        }
+        println!("        {}", advisory.url);
+    }
+}
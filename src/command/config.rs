@@ -0,0 +1,83 @@
+use structopt::StructOpt;
+
+use volta_core::config::{Config as Settings, Setting};
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Config {
+    #[structopt(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(StructOpt)]
+enum ConfigCommand {
+    /// Prints the value of a setting, if it is set
+    #[structopt(name = "get", author = "", version = "")]
+    Get {
+        /// The setting to print, like `limit-rate`
+        key: String,
+    },
+
+    /// Sets a setting, like `volta config set progress false`
+    #[structopt(name = "set", author = "", version = "")]
+    Set {
+        /// The setting to set
+        key: String,
+
+        /// The value to set it to
+        value: String,
+    },
+
+    /// Lists every setting, along with its value and the environment variable it stands in for
+    #[structopt(name = "list", author = "", version = "")]
+    List,
+}
+
+impl Command for Config {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Config);
+
+        match self.command {
+            ConfigCommand::Get { key } => {
+                let setting = Setting::find(&key)?;
+                if let Some(value) = Settings::current()?.get(setting) {
+                    println!("{}", value);
+                }
+            }
+            ConfigCommand::Set { key, value } => {
+                let setting = Setting::find(&key)?;
+                let mut settings = Settings::current()?;
+                settings.set(setting, &value)?;
+                settings.save()?;
+                println!("set {} to {}", key, value);
+            }
+            ConfigCommand::List => {
+                let settings = Settings::current()?;
+                let width = Setting::all()
+                    .iter()
+                    .map(|setting| setting.name.len())
+                    .max()
+                    .unwrap_or(0);
+                for setting in Setting::all() {
+                    let value = match settings.get(setting) {
+                        Some(value) => value.to_string(),
+                        None => "(unset)".to_string(),
+                    };
+                    println!(
+                        "{:width$}  {}  [{}]",
+                        setting.name,
+                        value,
+                        setting.var,
+                        width = width
+                    );
+                }
+            }
+        }
+
+        session.add_event_end(ActivityKind::Config, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
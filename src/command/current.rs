@@ -3,6 +3,7 @@ use std::string::ToString;
 use structopt::StructOpt;
 
 use volta_core::error::ErrorDetails;
+use volta_core::platform::Source;
 use volta_core::session::{ActivityKind, Session};
 use volta_fail::{throw, ExitCode, Fallible};
 
@@ -17,10 +18,23 @@ pub(crate) struct Current {
     /// Display the user's Node version
     #[structopt(short = "u", long = "user")]
     user: bool,
+
+    /// Display the active platform in a fixed format for shell prompts, like
+    /// `node:18.16.0 yarn:1.22.19 source:project`
+    #[structopt(
+        long = "porcelain",
+        raw(conflicts_with_all = r#"&["project", "user"]"#)
+    )]
+    pub(crate) porcelain: bool,
 }
 
 impl Command for Current {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        // Shell prompts run this on every render, so it reads no more than the platforms
+        if self.porcelain {
+            return print_porcelain(&session);
+        }
+
         session.add_event_start(ActivityKind::Current);
 
         let result = match (self.project, self.user) {
@@ -87,3 +101,28 @@ fn user_node_version(session: &Session) -> Fallible<Option<String>> {
         .user_platform()?
         .map(|platform| platform.node_runtime.to_string()))
 }
+
+/// Prints the active platform as `node:<version> yarn:<version or none> source:<project or
+/// default>`, or nothing if there is no platform. The tools that a project pins are merged over
+/// the default platform, and the source is `project` if any of them come from the project.
+fn print_porcelain(session: &Session) -> Fallible<ExitCode> {
+    let merged = match session.merged_platform()? {
+        Some(merged) => merged,
+        None => return Ok(ExitCode::NoVersionMatch),
+    };
+    let from_project = [merged.node, merged.npm, merged.yarn]
+        .iter()
+        .any(|&source| source == Source::Project);
+    let source = if from_project { "project" } else { "default" };
+    let yarn = merged
+        .spec
+        .yarn
+        .as_ref()
+        .map_or_else(|| "none".to_string(), ToString::to_string);
+
+    println!(
+        "node:{} yarn:{} source:{}",
+        merged.spec.node_runtime, yarn, source
+    );
+    Ok(ExitCode::Success)
+}
@@ -0,0 +1,38 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool::ToolSpec;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Default {
+    /// Tools to set as your default, like `node@12`, `npm@8`, `yarn@1.17` or `bun@1.0`. Tools that
+    /// haven't been fetched yet are fetched first.
+    #[structopt(name = "tool[@version]", required = true, min_values = 1)]
+    tools: Vec<String>,
+
+    /// The architecture of Node to set as your default, if not the native one (e.g. `x64` on
+    /// Apple Silicon).
+    #[structopt(long = "arch")]
+    arch: Option<String>,
+}
+
+impl Command for Default {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Default);
+
+        for tool in ToolSpec::from_strings(&self.tools, "default")? {
+            match (tool, &self.arch) {
+                (ToolSpec::Node(version), Some(arch)) => {
+                    session.default_node(&version, Some(arch))?
+                }
+                (tool, _) => tool.set_default(session)?,
+            }
+        }
+
+        session.add_event_end(ActivityKind::Default, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,40 @@
+use structopt::StructOpt;
+
+use volta_core::corepack::find_corepack_shims;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Doctor {}
+
+impl Command for Doctor {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Doctor);
+
+        let mut problems = 0;
+
+        let corepack_shims = find_corepack_shims(session)?;
+        if !corepack_shims.is_empty() {
+            problems += 1;
+            println!("warning: corepack shims can run instead of the tools Volta manages:");
+            for shim in corepack_shims {
+                println!("    {}", shim.display());
+            }
+            println!(
+                "Run `corepack disable` to remove them, and use `volta install` or `volta pin` to select a package manager."
+            );
+        }
+
+        let exit_code = if problems == 0 {
+            println!("No problems found.");
+            ExitCode::Success
+        } else {
+            ExitCode::EnvironmentError
+        };
+
+        session.add_event_end(ActivityKind::Doctor, exit_code);
+        Ok(exit_code)
+    }
+}
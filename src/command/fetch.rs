@@ -10,22 +10,63 @@ use crate::command::Command;
 #[derive(StructOpt)]
 pub(crate) struct Fetch {
     /// Tools to fetch, like `node`, `yarn@latest` or `your-package@^14.4.3`.
-    #[structopt(name = "tool[@version]", required = true, min_values = 1)]
+    #[structopt(
+        name = "tool[@version]",
+        required_unless = "pinned",
+        conflicts_with = "pinned",
+        min_values = 1
+    )]
     tools: Vec<String>,
+
+    /// Fetches the tools pinned in the current project, as its shims would
+    #[structopt(long = "pinned")]
+    pinned: bool,
+
+    /// Also fetches the headers of Node, so that node-gyp can build native modules offline
+    #[structopt(long = "headers")]
+    headers: bool,
 }
 
 impl Command for Fetch {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Fetch);
 
-        for tool in ToolSpec::from_strings(&self.tools, "fetch")? {
+        if self.pinned {
+            session.fetch_pinned()?;
+            session.add_event_end(ActivityKind::Fetch, ExitCode::Success);
+            return Ok(ExitCode::Success);
+        }
+
+        let tools = ToolSpec::from_strings(&self.tools, "fetch")?;
+        if self.headers {
+            let not_node = tools.iter().find(|tool| match tool {
+                ToolSpec::Node(_) => false,
+                _ => true,
+            });
+            if let Some(tool) = not_node {
+                throw!(ErrorDetails::HeadersRequireNode {
+                    tool: tool.to_string(),
+                });
+            }
+        }
+
+        for tool in tools {
             match tool {
                 ToolSpec::Node(version) => {
-                    session.fetch_node(&version)?;
+                    let node = session.fetch_node(&version)?.into_version();
+                    if self.headers {
+                        session.fetch_node_headers(&node.runtime)?;
+                    }
                 }
                 ToolSpec::Yarn(version) => {
                     session.fetch_yarn(&version)?;
                 }
+                ToolSpec::Deno(version) => {
+                    session.fetch_deno(&version)?;
+                }
+                ToolSpec::Bun(version) => {
+                    session.fetch_bun(&version)?;
+                }
                 ToolSpec::Npm(_version) => {
                     // ISSUE(#292): Implement install for npm
                     throw!(ErrorDetails::Unimplemented {
@@ -35,6 +76,9 @@ impl Command for Fetch {
                 ToolSpec::Package(name, version) => {
                     session.fetch_package(&name, &version)?;
                 }
+                ToolSpec::PackageSource(source) => {
+                    session.fetch_package_source(&source)?;
+                }
             }
         }
 
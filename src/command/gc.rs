@@ -0,0 +1,35 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::{print_plan, Command};
+
+#[derive(StructOpt)]
+pub(crate) struct Gc {
+    /// Which archives to keep instead of the `keep-archives` policy: "always", "never", or
+    /// "last-N" for the N most recently downloaded archives of each tool
+    #[structopt(long = "keep", value_name = "policy")]
+    keep: Option<String>,
+}
+
+impl Command for Gc {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Gc);
+
+        let changes = session.plan_gc(self.keep.as_ref().map(String::as_str))?;
+
+        if session.is_dry_run() {
+            print_plan(&changes);
+        } else if changes.is_empty() {
+            println!("No cached archives to remove.");
+        } else {
+            let count = changes.len();
+            session.apply(changes)?;
+            println!("Removed {} cached archive(s).", count);
+        }
+
+        session.add_event_end(ActivityKind::Gc, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,143 @@
+use std::io::{self, Write};
+
+use console::Term;
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::shell::{setup_profiles, ProfileShell};
+use volta_core::tool::ToolSpec;
+use volta_core::version::{is_range, VersionSpec};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Init {
+    /// The version of Node to set as your default, like `lts` or `20` (defaults to `lts`)
+    #[structopt(long = "node", value_name = "version")]
+    node: Option<String>,
+
+    /// Also sets a default version of Yarn, like `latest` or `1.22`
+    #[structopt(long = "yarn", value_name = "version")]
+    yarn: Option<String>,
+
+    /// Also installs a version of pnpm, like `latest` or `8`
+    #[structopt(long = "pnpm", value_name = "version")]
+    pnpm: Option<String>,
+
+    /// Doesn't add Volta to your shell profiles
+    #[structopt(long = "no-setup")]
+    no_setup: bool,
+
+    /// Doesn't pin the tools in the project in the current directory
+    #[structopt(long = "no-pin")]
+    no_pin: bool,
+
+    /// Accepts the defaults instead of asking, for use in scripts
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
+}
+
+/// Asks questions when there is someone to answer them, or otherwise takes the default answers.
+struct Prompt {
+    interactive: bool,
+}
+
+impl Prompt {
+    /// Asks for a version, returning the default if nothing is entered.
+    fn version(&self, question: &str, default: &str) -> String {
+        match self.ask(&format!("{} [{}]", question, default)) {
+            Some(ref answer) if !answer.is_empty() => answer.clone(),
+            _ => default.to_string(),
+        }
+    }
+
+    fn confirm(&self, question: &str, default: bool) -> bool {
+        let choices = if default { "Y/n" } else { "y/N" };
+        match self.ask(&format!("{} [{}]", question, choices)) {
+            Some(ref answer) if !answer.is_empty() => {
+                let answer = answer.to_lowercase();
+                answer == "y" || answer == "yes"
+            }
+            _ => default,
+        }
+    }
+
+    /// Reads an answer from stdin, or returns `None` if there is no one to ask.
+    fn ask(&self, question: &str) -> Option<String> {
+        if !self.interactive {
+            return None;
+        }
+
+        eprint!("{} ", question);
+        let _ = io::stderr().flush();
+
+        let mut answer = String::new();
+        match io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(answer.trim().to_string()),
+        }
+    }
+}
+
+impl Command for Init {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Init);
+
+        let prompt = Prompt {
+            interactive: !self.yes && Term::stderr().is_term(),
+        };
+
+        let node = match self.node {
+            Some(node) => node,
+            None => prompt.version("Which version of Node should be your default?", "lts"),
+        };
+        let yarn = match self.yarn {
+            Some(yarn) => Some(yarn),
+            None if prompt.confirm("Install Yarn?", false) => {
+                Some(prompt.version("Which version of Yarn?", "latest"))
+            }
+            None => None,
+        };
+        let pnpm = match self.pnpm {
+            Some(pnpm) => Some(pnpm),
+            None if prompt.confirm("Install pnpm?", false) => {
+                Some(prompt.version("Which version of pnpm?", "latest"))
+            }
+            None => None,
+        };
+
+        let mut tools = vec![format!("node@{}", node)];
+        tools.extend(yarn.as_ref().map(|yarn| format!("yarn@{}", yarn)));
+        tools.extend(pnpm.as_ref().map(|pnpm| format!("pnpm@{}", pnpm)));
+
+        let tools = ToolSpec::from_strings(&tools, "init")?;
+        for tool in session.fetch_concurrently(tools)? {
+            tool.install(session)?;
+        }
+
+        if !self.no_setup && prompt.confirm("Add Volta to your shell profiles?", true) {
+            let shells = ProfileShell::detect()?;
+            setup_profiles(&shells)?;
+        }
+
+        // pnpm is installed as a package, which can't be pinned
+        if !self.no_pin
+            && session.project()?.is_some()
+            && prompt.confirm("Pin these versions in the project in this directory?", true)
+        {
+            let range = if is_range(&node) {
+                Some(node.clone())
+            } else {
+                None
+            };
+            session.pin_node(&VersionSpec::parse(&node)?, range)?;
+            if let Some(ref yarn) = yarn {
+                session.pin_yarn(&VersionSpec::parse(yarn)?)?;
+            }
+        }
+
+        session.add_event_end(ActivityKind::Init, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use structopt::StructOpt;
+
+use volta_core::path;
+use volta_core::platform::PlatformSpec;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible, ResultExt};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Inspect {
+    /// Prints the report as JSON, for editors and other tools
+    #[structopt(long = "json")]
+    json: bool,
+}
+
+/// Everything Volta knows about the toolchain for the current directory.
+#[derive(Serialize)]
+struct Inspection {
+    project_root: Option<PathBuf>,
+    project_platform: Option<Platform>,
+    project_bins: Vec<String>,
+    user_platform: Option<Platform>,
+    /// Which platform is in use: "project", "user", or neither.
+    active_platform: Option<&'static str>,
+    images: Option<Images>,
+}
+
+#[derive(Serialize)]
+struct Platform {
+    node: String,
+    npm: Option<String>,
+    yarn: Option<String>,
+    arch: Option<String>,
+}
+
+impl From<&PlatformSpec> for Platform {
+    fn from(spec: &PlatformSpec) -> Self {
+        Platform {
+            node: spec.node_runtime.to_string(),
+            npm: spec.npm.as_ref().map(ToString::to_string),
+            yarn: spec.yarn.as_ref().map(ToString::to_string),
+            arch: spec.arch.clone(),
+        }
+    }
+}
+
+/// The image directories of the active platform.
+#[derive(Serialize)]
+struct Images {
+    node: PathBuf,
+    yarn: Option<PathBuf>,
+}
+
+impl Command for Inspect {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Inspect);
+
+        let inspection = inspect(session)?;
+
+        if self.json {
+            let json = serde_json::to_string_pretty(&inspection).unknown()?;
+            println!("{}", json);
+        } else {
+            print_inspection(&inspection);
+        }
+
+        session.add_event_end(ActivityKind::Inspect, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+fn inspect(session: &Session) -> Fallible<Inspection> {
+    let project = session.project()?;
+    let project_platform = session.project_platform()?;
+    let user_platform = session.user_platform()?;
+
+    let (active_platform, active_spec) = match (&project_platform, &user_platform) {
+        (Some(spec), _) => (Some("project"), Some(spec)),
+        (None, Some(spec)) => (Some("user"), Some(spec)),
+        (None, None) => (None, None),
+    };
+
+    let images = match active_spec.and_then(|spec| spec.resolve_image()) {
+        Some(image) => {
+            let node = image.node_dir()?;
+            let yarn = match image.yarn {
+                Some(ref version) => Some(path::yarn_image_dir(&version.to_string())?),
+                None => None,
+            };
+            Some(Images { node, yarn })
+        }
+        None => None,
+    };
+
+    let project_bins = match project {
+        Some(ref project) => project.local_bins()?,
+        None => Vec::new(),
+    };
+
+    Ok(Inspection {
+        project_root: project.map(|project| project.project_root().to_path_buf()),
+        project_platform: project_platform.map(|spec| Platform::from(&*spec)),
+        project_bins,
+        user_platform: user_platform.map(|spec| Platform::from(&*spec)),
+        active_platform,
+        images,
+    })
+}
+
+fn print_inspection(inspection: &Inspection) {
+    match inspection.project_root {
+        Some(ref root) => println!("project: {}", root.display()),
+        None => println!("project: none"),
+    }
+    print_platform(
+        "project platform",
+        &inspection.project_platform,
+        inspection.active_platform == Some("project"),
+    );
+    if !inspection.project_bins.is_empty() {
+        println!(
+            "project executables: {}",
+            inspection.project_bins.join(", ")
+        );
+    }
+    print_platform(
+        "user platform",
+        &inspection.user_platform,
+        inspection.active_platform == Some("user"),
+    );
+
+    if let Some(ref images) = inspection.images {
+        println!("node image: {}", images.node.display());
+        if let Some(ref yarn) = images.yarn {
+            println!("yarn image: {}", yarn.display());
+        }
+    }
+}
+
+fn print_platform(label: &str, platform: &Option<Platform>, active: bool) {
+    let platform = match platform {
+        Some(platform) => platform,
+        None => {
+            println!("{}: none", label);
+            return;
+        }
+    };
+
+    let mut tools = vec![format!("node v{}", platform.node)];
+    if let Some(ref arch) = platform.arch {
+        tools[0].push_str(&format!(" ({})", arch));
+    }
+    if let Some(ref npm) = platform.npm {
+        tools.push(format!("npm v{}", npm));
+    }
+    if let Some(ref yarn) = platform.yarn {
+        tools.push(format!("yarn v{}", yarn));
+    }
+    println!(
+        "{}: {}{}",
+        label,
+        tools.join(", "),
+        if active { " (active)" } else { "" }
+    );
+}
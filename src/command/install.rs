@@ -1,24 +1,94 @@
 use structopt::StructOpt;
 
+use volta_core::error::ErrorDetails;
 use volta_core::session::{ActivityKind, Session};
 use volta_core::tool::ToolSpec;
-use volta_fail::{ExitCode, Fallible};
+use volta_fail::{throw, ExitCode, Fallible};
 
-use crate::command::Command;
+use crate::command::{print_plan, Command};
+use crate::select::pick_versions;
 
 #[derive(StructOpt)]
 pub(crate) struct Install {
-    /// Tools to install, like `node`, `yarn@latest` or `your-package@^14.4.3`.
+    /// Tools to install, like `node`, `yarn@latest` or `your-package@^14.4.3`. Packages can also
+    /// be installed from git or local paths, like `github:owner/repo#v1.0.0` or `./your-package`.
     #[structopt(name = "tool[@version]", required = true, min_values = 1)]
     tools: Vec<String>,
+
+    /// The architecture of Node to install, if not the native one (e.g. `x64` on Apple Silicon).
+    #[structopt(long = "arch")]
+    arch: Option<String>,
+
+    /// Fetches the tools without changing your default versions, which can be changed later with
+    /// `volta default`.
+    #[structopt(long = "no-default")]
+    no_default: bool,
+
+    /// Don't run lifecycle scripts (like `postinstall`) when installing packages, regardless of
+    /// the policy set in `VOLTA_SCRIPT_POLICY` ("allow", "deny", or "prompt").
+    #[structopt(long = "ignore-scripts")]
+    ignore_scripts: bool,
+
+    /// Doesn't ask which version to use for Node or Yarn given without a version, and uses the
+    /// latest (the latest LTS, for Node) instead
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
 }
 
 impl Command for Install {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Install);
 
-        for tool in ToolSpec::from_strings(&self.tools, "install")? {
-            tool.install(session)?;
+        if self.ignore_scripts {
+            session.ignore_scripts();
+        }
+
+        let tools = ToolSpec::from_strings(&self.tools, "install")?;
+        if self.arch.is_some() {
+            let not_node = tools.iter().find(|tool| match tool {
+                ToolSpec::Node(_) => false,
+                _ => true,
+            });
+            if let Some(tool) = not_node {
+                throw!(ErrorDetails::ArchRequiresNode {
+                    tool: tool.to_string(),
+                });
+            }
+        }
+        let tools = pick_versions(&self.tools, tools, self.yes, session)?;
+
+        // Node, Yarn, Deno, and Bun are installed by making the changes they list, so that a dry
+        // run lists exactly what would be done. What a package installs isn't known until it has
+        // been unpacked, so packages are only listed for a dry run.
+        let arch = self.arch.as_ref().map(String::as_str);
+        let mut changes = Vec::new();
+        let mut packages = Vec::new();
+        for tool in tools {
+            match tool {
+                ToolSpec::Package(..) | ToolSpec::PackageSource(_) if !session.is_dry_run() => {
+                    packages.push(tool)
+                }
+                tool => {
+                    let planned = tool.plan_install(session, !self.no_default, arch)?;
+                    if planned.is_empty() && !session.is_dry_run() {
+                        if self.no_default {
+                            println!("{} is already installed", tool);
+                        } else {
+                            println!("{} is already installed as the default", tool);
+                        }
+                    }
+                    changes.extend(planned);
+                }
+            }
+        }
+
+        if session.is_dry_run() {
+            print_plan(&changes);
+        } else {
+            session.apply(changes)?;
+            for package in packages {
+                package.install(session)?;
+            }
         }
 
         session.add_event_end(ActivityKind::Install, ExitCode::Success);
@@ -0,0 +1,20 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Link {}
+
+impl Command for Link {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Link);
+
+        session.link_package()?;
+
+        session.add_event_end(ActivityKind::Link, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,369 @@
+use std::path::PathBuf;
+
+use semver::Version;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use volta_core::platform::{self, MergedPlatform, PlatformSpec};
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible, ResultExt};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct List {
+    /// Lists only the versions pinned in the current project
+    #[structopt(long = "project", raw(conflicts_with_all = r#"&["default", "all"]"#))]
+    project: bool,
+
+    /// Lists only your default versions
+    #[structopt(long = "default", conflicts_with = "all")]
+    default: bool,
+
+    /// Lists the versions from both the project and your defaults, including any that aren't
+    /// active
+    #[structopt(long = "all")]
+    all: bool,
+
+    /// Prints the list as JSON, for scripts and other tools
+    #[structopt(long = "json")]
+    json: bool,
+
+    #[structopt(subcommand)]
+    command: Option<ListCommand>,
+}
+
+#[derive(StructOpt)]
+enum ListCommand {
+    /// Lists the installed packages, with the platform each one runs with
+    #[structopt(name = "packages", author = "", version = "")]
+    Packages {
+        /// Prints the list as JSON, for scripts and other tools
+        #[structopt(long = "json")]
+        json: bool,
+    },
+}
+
+/// The order that tools are listed in.
+const TOOLS: &[&str] = &["node", "npm", "yarn", "deno", "bun"];
+
+/// A version of a tool, as it is listed.
+#[derive(Serialize)]
+struct Tool {
+    name: &'static str,
+    /// The version, which is missing for tools that run from `PATH`, and for the npm bundled with
+    /// a version of Node that hasn't been fetched.
+    version: Option<String>,
+    source: Source,
+    /// Whether this is the version that runs in the current directory.
+    active: bool,
+}
+
+/// Where a version of a tool comes from.
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Source {
+    /// Pinned in the project whose pin file is at `path`.
+    Project { path: PathBuf },
+    /// The user's default version.
+    Default,
+    /// Not set anywhere, so the tool runs from `PATH` without Volta.
+    Passthrough,
+}
+
+/// Which of the versions of the tools are listed.
+#[derive(Clone, Copy)]
+enum Filter {
+    Active,
+    Project,
+    Default,
+    All,
+}
+
+impl Filter {
+    fn includes(self, tool: &Tool) -> bool {
+        match (self, &tool.source) {
+            (Filter::Active, _) => tool.active,
+            (Filter::Project, Source::Project { .. }) => true,
+            (Filter::Default, Source::Default) => true,
+            (Filter::All, _) => true,
+            _ => false,
+        }
+    }
+}
+
+/// An installed package, as it is listed.
+#[derive(Serialize)]
+struct Package {
+    name: String,
+    version: String,
+    /// The platform the package was installed with, which its executables run with.
+    platform: Platform,
+    bins: Vec<String>,
+    /// When the package was installed, which isn't known for packages installed by older
+    /// versions of Volta.
+    installed: Option<String>,
+    /// What the package was installed from, like `typescript@^3` or a Git URL.
+    spec: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Platform {
+    node: String,
+    /// The version of npm, which is missing if the package uses the npm bundled with a version of
+    /// Node that hasn't been fetched.
+    npm: Option<String>,
+    yarn: Option<String>,
+}
+
+impl From<&PlatformSpec> for Platform {
+    fn from(spec: &PlatformSpec) -> Self {
+        let npm = match spec.npm {
+            Some(ref npm) => Some(npm.clone()),
+            None => spec.resolve_image().map(|image| image.node.npm),
+        };
+
+        Platform {
+            node: spec.node_runtime.to_string(),
+            npm: npm.as_ref().map(ToString::to_string),
+            yarn: spec.yarn.as_ref().map(ToString::to_string),
+        }
+    }
+}
+
+impl Command for List {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::List);
+
+        match self.command {
+            None => {
+                let filter = match (self.project, self.default, self.all) {
+                    (true, _, _) => Filter::Project,
+                    (_, true, _) => Filter::Default,
+                    (_, _, true) => Filter::All,
+                    _ => Filter::Active,
+                };
+                let tools: Vec<Tool> = list_tools(session)?
+                    .into_iter()
+                    .filter(|tool| filter.includes(tool))
+                    .collect();
+
+                if self.json {
+                    let json = serde_json::to_string_pretty(&tools).unknown()?;
+                    println!("{}", json);
+                } else if tools.is_empty() {
+                    match filter {
+                        Filter::Project => println!("No tools are pinned in this project."),
+                        _ => println!("No default versions are set."),
+                    }
+                } else {
+                    for tool in &tools {
+                        print_tool(tool, filter);
+                    }
+                }
+            }
+            Some(ListCommand::Packages { json }) => {
+                let packages: Vec<Package> = session
+                    .installed_packages()?
+                    .into_iter()
+                    .map(|config| Package {
+                        platform: Platform::from(&config.platform),
+                        name: config.name,
+                        version: config.version.to_string(),
+                        bins: config.bins,
+                        installed: config.installed,
+                        spec: config.spec,
+                    })
+                    .collect();
+
+                if json {
+                    let json = serde_json::to_string_pretty(&packages).unknown()?;
+                    println!("{}", json);
+                } else if packages.is_empty() {
+                    println!("No packages are installed.");
+                } else {
+                    for package in &packages {
+                        print_package(package);
+                    }
+                }
+            }
+        }
+
+        session.add_event_end(ActivityKind::List, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Finds the versions of each tool from the project and from the user defaults, and which of them
+/// run in the current directory.
+fn list_tools(session: &Session) -> Fallible<Vec<Tool>> {
+    let project = session.project()?;
+    let project_source = project.as_ref().map(|project| Source::Project {
+        path: project.project_root().join(project.manifest_file_name()),
+    });
+
+    let mut tools = Vec::new();
+
+    // The tools that the project pins are merged over the default platform, tool by tool
+    match session.merged_platform()? {
+        Some(merged) => {
+            let source = |name: &str| match merged_source(&merged, name) {
+                platform::Source::Project => project_source.clone().unwrap_or(Source::Default),
+                platform::Source::Default => Source::Default,
+            };
+            for (name, version) in platform_tools(&merged.spec) {
+                tools.push(Tool {
+                    name,
+                    version,
+                    source: source(name),
+                    active: true,
+                });
+            }
+
+            // The default versions of the tools that the project pins don't run here
+            if let Some(platform) = session.user_platform()? {
+                for (name, version) in platform_tools(&platform) {
+                    if merged_source(&merged, name) == platform::Source::Project {
+                        tools.push(Tool {
+                            name,
+                            version,
+                            source: Source::Default,
+                            active: false,
+                        });
+                    }
+                }
+            }
+        }
+        None => {
+            for &name in &["node", "npm", "yarn"] {
+                tools.push(passthrough(name));
+            }
+        }
+    }
+
+    let manifest = project.as_ref().map(|project| project.manifest());
+    push_runtime(
+        &mut tools,
+        "deno",
+        manifest.and_then(|manifest| manifest.deno()),
+        session.user_deno()?,
+        &project_source,
+    );
+    push_runtime(
+        &mut tools,
+        "bun",
+        manifest.and_then(|manifest| manifest.bun()),
+        session.user_bun()?,
+        &project_source,
+    );
+
+    tools.sort_by_key(|tool| TOOLS.iter().position(|name| *name == tool.name));
+    Ok(tools)
+}
+
+/// The versions of Node, npm, and Yarn in a platform.
+fn platform_tools(platform: &PlatformSpec) -> Vec<(&'static str, Option<String>)> {
+    let npm = match platform.npm {
+        Some(ref npm) => Some(npm.clone()),
+        None => platform.resolve_image().map(|image| image.node.npm),
+    };
+
+    let mut tools = vec![
+        ("node", Some(platform.node_runtime.to_string())),
+        ("npm", npm.as_ref().map(ToString::to_string)),
+    ];
+    if let Some(ref yarn) = platform.yarn {
+        tools.push(("yarn", Some(yarn.to_string())));
+    }
+    tools
+}
+
+/// Where a tool in a merged platform comes from.
+fn merged_source(merged: &MergedPlatform, name: &str) -> platform::Source {
+    match name {
+        "node" => merged.node,
+        "npm" => merged.npm,
+        _ => merged.yarn,
+    }
+}
+
+/// Adds the versions of a runtime like Deno, which is pinned separately from the platform.
+fn push_runtime(
+    tools: &mut Vec<Tool>,
+    name: &'static str,
+    project_version: Option<Version>,
+    user_version: Option<Version>,
+    project_source: &Option<Source>,
+) {
+    let pinned = project_version.is_some();
+    if let (Some(version), Some(source)) = (project_version, project_source) {
+        tools.push(Tool {
+            name,
+            version: Some(version.to_string()),
+            source: source.clone(),
+            active: true,
+        });
+    }
+
+    match user_version {
+        Some(version) => tools.push(Tool {
+            name,
+            version: Some(version.to_string()),
+            source: Source::Default,
+            active: !pinned,
+        }),
+        None if !pinned => tools.push(passthrough(name)),
+        None => {}
+    }
+}
+
+fn passthrough(name: &'static str) -> Tool {
+    Tool {
+        name,
+        version: None,
+        source: Source::Passthrough,
+        active: true,
+    }
+}
+
+fn print_tool(tool: &Tool, filter: Filter) {
+    let version = match (&tool.version, &tool.source) {
+        (Some(version), _) => version.as_str(),
+        (None, Source::Passthrough) => "system",
+        (None, _) => "bundled",
+    };
+    let source = match tool.source {
+        Source::Project { ref path } => format!("project ({})", path.display()),
+        Source::Default => "default".to_string(),
+        Source::Passthrough => "passthrough".to_string(),
+    };
+    let active = match filter {
+        Filter::All if tool.active => " (active)",
+        _ => "",
+    };
+
+    println!("{:<6} {:<10} {}{}", tool.name, version, source, active);
+}
+
+fn print_package(package: &Package) {
+    println!("{}@{}", package.name, package.version);
+
+    let mut platform = format!("node {}", package.platform.node);
+    if let Some(ref npm) = package.platform.npm {
+        platform.push_str(&format!(", npm {}", npm));
+    }
+    if let Some(ref yarn) = package.platform.yarn {
+        platform.push_str(&format!(", yarn {}", yarn));
+    }
+    println!("    platform: {}", platform);
+
+    if !package.bins.is_empty() {
+        println!("    bins: {}", package.bins.join(", "));
+    }
+    if let Some(ref installed) = package.installed {
+        println!("    installed: {}", installed);
+    }
+    if let Some(ref spec) = package.spec {
+        println!("    spec: {}", spec);
+    }
+}
@@ -0,0 +1,208 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command as Process, Output};
+use std::thread;
+
+use serde::Serialize;
+use structopt::StructOpt;
+
+use volta_core::error::ErrorDetails;
+use volta_core::platform::{PlatformSpec, Source};
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool::image_command;
+use volta_core::version::VersionSpec;
+use volta_fail::{ExitCode, Fallible, ResultExt};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Matrix {
+    /// The versions of Node to run the command with, separated by commas, like `16,18,20`
+    #[structopt(
+        long = "node",
+        value_name = "versions",
+        required = true,
+        raw(use_delimiter = "true")
+    )]
+    node: Vec<String>,
+
+    /// Runs the command with every version at once, printing each one's output when it finishes
+    #[structopt(long = "parallel")]
+    parallel: bool,
+
+    /// Writes a JSON report of the results to the given file
+    #[structopt(long = "report", value_name = "file", parse(from_os_str))]
+    report: Option<PathBuf>,
+
+    /// The command to run, like `npm test`
+    #[structopt(
+        name = "command",
+        required = true,
+        raw(last = "true"),
+        parse(from_os_str)
+    )]
+    command: Vec<OsString>,
+}
+
+/// The outcome of running the command with one version of Node.
+#[derive(Serialize)]
+struct MatrixResult {
+    node: String,
+    /// The exit code of the command, which is missing if it was ended by a signal.
+    exit_code: Option<i32>,
+    success: bool,
+}
+
+/// The report written by `--report`.
+#[derive(Serialize)]
+struct MatrixReport {
+    command: Vec<String>,
+    results: Vec<MatrixResult>,
+}
+
+impl Command for Matrix {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Matrix);
+
+        let jobs = self.prepare(session)?;
+        let results = if self.parallel {
+            run_parallel(jobs)?
+        } else {
+            run_sequential(jobs)?
+        };
+
+        println!();
+        for result in &results {
+            match (result.success, result.exit_code) {
+                (true, _) => println!("node@{}: passed", result.node),
+                (false, Some(code)) => {
+                    println!("node@{}: failed (exit code {})", result.node, code)
+                }
+                (false, None) => println!("node@{}: failed (terminated)", result.node),
+            }
+        }
+
+        let exit_code = exit_code_for(&results);
+
+        if let Some(ref file) = self.report {
+            let report = MatrixReport {
+                command: self
+                    .command
+                    .iter()
+                    .map(|arg| arg.to_string_lossy().into_owned())
+                    .collect(),
+                results,
+            };
+            write_report(file, &report)?;
+        }
+
+        session.add_event_end(ActivityKind::Matrix, exit_code);
+        Ok(exit_code)
+    }
+}
+
+impl Matrix {
+    /// Fetches every requested version of Node up front, so that the command isn't run with
+    /// any of them unless all of them are available.
+    fn prepare(&self, session: &mut Session) -> Fallible<Vec<(String, Process)>> {
+        // The npm that the project pins is kept for each version of Node, while npm from the
+        // default platform goes with the default Node, so the bundled npm is used instead
+        let (npm, yarn) = match session.merged_platform()? {
+            Some(merged) => {
+                let npm = if merged.npm == Source::Project {
+                    merged.spec.npm.clone()
+                } else {
+                    None
+                };
+                (npm, merged.spec.yarn.clone())
+            }
+            None => (None, None),
+        };
+        let (exe, args) = self.command.split_first().expect("command is required");
+
+        let mut jobs = Vec::new();
+        for spec in &self.node {
+            let version_spec = VersionSpec::parse(spec)?;
+            let node = session.fetch_node(&version_spec)?.into_version();
+
+            let platform = PlatformSpec {
+                node_runtime: node.runtime,
+                npm: npm.clone(),
+                yarn: yarn.clone(),
+                arch: None,
+            };
+            let image = platform.checkout(session)?;
+            let command = image_command(exe, args.iter().cloned(), &image)?;
+
+            jobs.push((image.node.runtime.to_string(), command));
+        }
+
+        Ok(jobs)
+    }
+}
+
+fn run_sequential(jobs: Vec<(String, Process)>) -> Fallible<Vec<MatrixResult>> {
+    let mut results = Vec::new();
+
+    for (node, mut command) in jobs {
+        println!("Running with node@{}", node);
+        let status = command
+            .status()
+            .with_context(|_| ErrorDetails::BinaryExecError)?;
+
+        results.push(MatrixResult {
+            node,
+            exit_code: status.code(),
+            success: status.success(),
+        });
+    }
+
+    Ok(results)
+}
+
+/// Runs every job at once. Their output is captured and printed in order once each finishes, so
+/// that the output of different versions isn't interleaved.
+fn run_parallel(jobs: Vec<(String, Process)>) -> Fallible<Vec<MatrixResult>> {
+    let handles: Vec<_> = jobs
+        .into_iter()
+        .map(|(node, mut command)| (node, thread::spawn(move || command.output())))
+        .collect();
+
+    let mut results = Vec::new();
+    for (node, handle) in handles {
+        let output: Output = handle
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::Other, "job panicked")))
+            .with_context(|_| ErrorDetails::BinaryExecError)?;
+
+        println!("Output with node@{}", node);
+        io::stdout().write_all(&output.stdout).unknown()?;
+        io::stderr().write_all(&output.stderr).unknown()?;
+
+        results.push(MatrixResult {
+            node,
+            exit_code: output.status.code(),
+            success: output.status.success(),
+        });
+    }
+
+    Ok(results)
+}
+
+fn write_report(file: &Path, report: &MatrixReport) -> Fallible<()> {
+    let json = serde_json::to_string_pretty(report).unknown()?;
+    fs::write(file, json).with_context(|_| ErrorDetails::WriteMatrixReportError {
+        file: file.to_path_buf(),
+    })
+}
+
+/// The command as a whole only succeeds if it succeeded with every version of Node.
+fn exit_code_for(results: &[MatrixResult]) -> ExitCode {
+    if results.iter().all(|result| result.success) {
+        ExitCode::Success
+    } else {
+        ExitCode::ExecutionFailure
+    }
+}
@@ -0,0 +1,34 @@
+use structopt::StructOpt;
+
+use volta_core::migrate::{migrate, pending_migrations};
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Migrate {}
+
+impl Command for Migrate {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Migrate);
+
+        if session.is_dry_run() {
+            let pending = pending_migrations()?;
+            if pending.is_empty() {
+                println!("The Volta directory is up to date.");
+            }
+            for migration in pending {
+                println!("layout v{}: {}", migration.to, migration.description);
+                for change in migration.changes {
+                    println!("    {}", change);
+                }
+            }
+        } else {
+            migrate(true)?;
+        }
+
+        session.add_event_end(ActivityKind::Migrate, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
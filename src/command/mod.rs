@@ -1,26 +1,71 @@
 pub(crate) mod activate;
+pub(crate) mod audit;
 pub(crate) mod completions;
+pub(crate) mod config;
 pub(crate) mod current;
 pub(crate) mod deactivate;
+pub(crate) mod default;
+pub(crate) mod doctor;
 pub(crate) mod fetch;
+pub(crate) mod gc;
+pub(crate) mod init;
+pub(crate) mod inspect;
 pub(crate) mod install;
+pub(crate) mod link;
+pub(crate) mod list;
+pub(crate) mod matrix;
+pub(crate) mod migrate;
 pub(crate) mod pin;
+pub(crate) mod prefer;
+pub(crate) mod provision;
+pub(crate) mod rebuild;
+pub(crate) mod run;
+pub(crate) mod self_update;
+pub(crate) mod send_events;
+pub(crate) mod setup;
+pub(crate) mod shim;
 pub(crate) mod uninstall;
+pub(crate) mod unlink;
+pub(crate) mod upgrade;
+pub(crate) mod verify;
 #[macro_use]
 pub(crate) mod r#use;
 pub(crate) mod which;
 
 pub(crate) use self::which::Which;
 pub(crate) use activate::Activate;
+pub(crate) use audit::Audit;
 pub(crate) use completions::Completions;
+pub(crate) use config::Config;
 pub(crate) use current::Current;
 pub(crate) use deactivate::Deactivate;
+pub(crate) use default::Default;
+pub(crate) use doctor::Doctor;
 pub(crate) use fetch::Fetch;
+pub(crate) use gc::Gc;
+pub(crate) use init::Init;
+pub(crate) use inspect::Inspect;
 pub(crate) use install::Install;
+pub(crate) use link::Link;
+pub(crate) use list::List;
+pub(crate) use matrix::Matrix;
+pub(crate) use migrate::Migrate;
 pub(crate) use pin::Pin;
+pub(crate) use prefer::Prefer;
+pub(crate) use provision::Provision;
 pub(crate) use r#use::Use;
+pub(crate) use rebuild::Rebuild;
+pub(crate) use run::Run;
+pub(crate) use self_update::SelfUpdate;
+pub(crate) use send_events::SendEvents;
+pub(crate) use setup::Setup;
+pub(crate) use shim::Shim;
 pub(crate) use uninstall::Uninstall;
+pub(crate) use unlink::Unlink;
+pub(crate) use upgrade::Upgrade;
+pub(crate) use verify::Verify;
 
+use volta_core::plan::Change;
 use volta_core::session::Session;
 use volta_fail::{ExitCode, Fallible};
 
@@ -31,3 +76,13 @@ pub(crate) trait Command: Sized {
     /// should return `e.exit_code()`.
     fn run(self, session: &mut Session) -> Fallible<ExitCode>;
 }
+
+/// Prints the changes that a command run with `--dry-run` would make.
+fn print_plan(changes: &[Change]) {
+    if changes.is_empty() {
+        println!("Nothing would change.");
+    }
+    for change in changes {
+        println!("would {}", change);
+    }
+}
@@ -3,36 +3,166 @@ use structopt::StructOpt;
 use volta_core::error::ErrorDetails;
 use volta_core::session::{ActivityKind, Session};
 use volta_core::tool::ToolSpec;
+use volta_core::version::is_range;
 use volta_fail::{throw, ExitCode, Fallible};
 
-use crate::command::Command;
+use crate::command::{print_plan, Command};
+use crate::select::pick_versions;
 
 #[derive(StructOpt)]
 pub(crate) struct Pin {
-    /// Tools to pin, like `node@lts` or `yarn@^1.14`.
-    #[structopt(name = "tool[@version]", required = true, min_values = 1)]
+    /// Tools to pin, like `node@lts`, `yarn@^1.14`, `deno@1.40` or `bun@1.0`.
+    ///
+    /// Pinning Node to a range, like `node@^18`, keeps the range in package.json and locks the
+    /// version it resolves to, so everyone working on the project gets the same version.
+    #[structopt(
+        name = "tool[@version]",
+        raw(required_unless_one = r#"&["update", "with_hash"]"#),
+        conflicts_with = "update",
+        min_values = 1
+    )]
     tools: Vec<String>,
+
+    /// Locks the latest version of Node that satisfies the pinned range
+    #[structopt(long = "update")]
+    update: bool,
+
+    /// Also pins the SHA-256 of the archives that the pinned Node and Yarn are installed from,
+    /// so that they are never installed from an archive with different contents
+    #[structopt(long = "with-hash")]
+    with_hash: bool,
+
+    /// Also pins the tools in each of the project's workspace packages
+    #[structopt(long = "workspaces", conflicts_with = "update")]
+    workspaces: bool,
+
+    /// Doesn't ask which version to use for Node or Yarn given without a version, and uses the
+    /// latest (the latest LTS, for Node) instead
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
 }
 
 impl Command for Pin {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Pin);
 
-        for tool in ToolSpec::from_strings(&self.tools, "pin")? {
+        if session.is_dry_run() {
+            return self.plan(session);
+        }
+
+        if self.update {
+            session.update_node_pin()?;
+            if self.with_hash {
+                session.pin_sha256()?;
+            }
+            session.add_event_end(ActivityKind::Pin, ExitCode::Success);
+            return Ok(ExitCode::Success);
+        }
+
+        let node_range = node_range(&self.tools);
+        let mut tools = ToolSpec::from_strings(&self.tools, "pin")?;
+
+        // Find the workspaces up front, so nothing is pinned if they can't be found
+        let workspace_roots = if self.workspaces {
+            session.workspace_roots()?
+        } else {
+            Vec::new()
+        };
+
+        // Pinning fails outside of a project, so there is no reason to fetch anything there
+        if session.project()?.is_some() {
+            tools = pick_versions(&self.tools, tools, self.yes, session)?;
+            tools = session.fetch_concurrently(tools)?;
+        }
+
+        for tool in &tools {
             match tool {
-                ToolSpec::Node(version) => session.pin_node(&version)?,
-                ToolSpec::Yarn(version) => session.pin_yarn(&version)?,
-                // ISSUE(#292): Implement install for npm
-                ToolSpec::Npm(_version) => throw!(ErrorDetails::Unimplemented {
-                    feature: "Pinning npm".into()
-                }),
-                ToolSpec::Package(name, _version) => {
-                    throw!(ErrorDetails::CannotPinPackage { package: name })
-                }
+                ToolSpec::Node(version) => session.pin_node(version, node_range.clone())?,
+                ToolSpec::Yarn(version) => session.pin_yarn(version)?,
+                ToolSpec::Deno(version) => session.pin_deno(version)?,
+                ToolSpec::Bun(version) => session.pin_bun(version)?,
+                tool => throw!(cannot_pin(tool)),
             }
         }
 
+        if self.workspaces {
+            session.pin_workspaces(&workspace_roots, &tools)?;
+        }
+
+        if self.with_hash {
+            session.pin_sha256()?;
+        }
+
+        session.add_event_end(ActivityKind::Pin, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+impl Pin {
+    /// Lists the changes that pinning would make, without making them.
+    fn plan(self, session: &mut Session) -> Fallible<ExitCode> {
+        if self.with_hash {
+            throw!(ErrorDetails::DryRunUnsupported {
+                action: "Pinning with --with-hash".into()
+            });
+        }
+        if self.workspaces {
+            throw!(ErrorDetails::DryRunUnsupported {
+                action: "Pinning with --workspaces".into()
+            });
+        }
+
+        let changes = if self.update {
+            session.plan_update_node_pin()?
+        } else {
+            let node_range = node_range(&self.tools);
+            let mut tools = ToolSpec::from_strings(&self.tools, "pin")?;
+            if session.project()?.is_some() {
+                tools = pick_versions(&self.tools, tools, self.yes, session)?;
+            }
+            if let Some(tool) = tools.iter().find(|tool| !can_pin(tool)) {
+                throw!(cannot_pin(tool));
+            }
+            session.plan_pin(&tools, node_range)?
+        };
+
+        print_plan(&changes);
         session.add_event_end(ActivityKind::Pin, ExitCode::Success);
         Ok(ExitCode::Success)
     }
 }
+
+/// Whether a tool can be pinned in a project.
+fn can_pin(tool: &ToolSpec) -> bool {
+    match tool {
+        ToolSpec::Node(_) | ToolSpec::Yarn(_) | ToolSpec::Deno(_) | ToolSpec::Bun(_) => true,
+        _ => false,
+    }
+}
+
+/// The error for a tool that can't be pinned in a project.
+fn cannot_pin(tool: &ToolSpec) -> ErrorDetails {
+    match tool {
+        // ISSUE(#292): Implement install for npm
+        ToolSpec::Npm(_version) => ErrorDetails::Unimplemented {
+            feature: "Pinning npm".into(),
+        },
+        ToolSpec::Package(name, _version) => ErrorDetails::CannotPinPackage {
+            package: name.clone(),
+        },
+        ToolSpec::PackageSource(source) => ErrorDetails::CannotPinPackage {
+            package: source.to_string(),
+        },
+        tool => unreachable!("{} can be pinned", tool),
+    }
+}
+
+/// Finds the range that Node was requested with, if it was requested as a range like `node@^18`
+/// rather than as a version.
+fn node_range(tools: &[String]) -> Option<String> {
+    tools
+        .iter()
+        .filter(|tool| tool.starts_with("node@"))
+        .map(|tool| tool["node@".len()..].to_string())
+        .find(|version| is_range(version))
+}
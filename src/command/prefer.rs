@@ -0,0 +1,27 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Prefer {
+    /// The installed package that should provide the executables
+    package: String,
+
+    /// The executables to take over, e.g. `tsc`. Defaults to all of the package's executables.
+    #[structopt(name = "executable")]
+    bins: Vec<String>,
+}
+
+impl Command for Prefer {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Prefer);
+
+        session.prefer_package(&self.package, &self.bins)?;
+
+        session.add_event_end(ActivityKind::Prefer, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use structopt::StructOpt;
+
+use volta_core::error::ErrorDetails;
+use volta_core::manifest::Manifest;
+use volta_core::platform::PlatformSpec;
+use volta_core::provision::provision;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{throw, ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Provision {
+    /// The package.json, or project directory, whose pinned tools to install (defaults to the
+    /// project in the current directory)
+    #[structopt(long = "from", value_name = "path", parse(from_os_str))]
+    from: Option<PathBuf>,
+
+    /// The prefix to install the tools into without shims, like `/usr/local` in a Docker image
+    #[structopt(long = "dest", value_name = "dir", parse(from_os_str))]
+    dest: PathBuf,
+}
+
+impl Command for Provision {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Provision);
+
+        let platform = match self.from {
+            Some(ref from) => manifest_platform(from)?,
+            None => match session.project()? {
+                Some(project) => project.platform(),
+                None => throw!(ErrorDetails::NotInPackage),
+            },
+        };
+        let platform = match platform {
+            Some(platform) => platform,
+            None => throw!(ErrorDetails::NoPinnedPlatform {
+                action: "provision the pinned tools".into()
+            }),
+        };
+
+        provision(session, &platform, &self.dest)?;
+
+        session.add_event_end(ActivityKind::Provision, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Reads the platform pinned in a package.json, or in the manifest of a project directory.
+fn manifest_platform(from: &Path) -> Fallible<Option<Rc<PlatformSpec>>> {
+    let project_root = if from.is_dir() {
+        from
+    } else {
+        from.parent().unwrap_or_else(|| Path::new(""))
+    };
+    Ok(Manifest::for_dir(project_root)?.platform())
+}
@@ -0,0 +1,26 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Rebuild {
+    /// Packages to rebuild, like `node-sass` or `@your-org/cli`
+    #[structopt(name = "package", required = true, min_values = 1)]
+    packages: Vec<String>,
+}
+
+impl Command for Rebuild {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Rebuild);
+
+        for package in &self.packages {
+            session.rebuild_package(package)?;
+        }
+
+        session.add_event_end(ActivityKind::Rebuild, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
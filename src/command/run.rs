@@ -0,0 +1,36 @@
+use std::ffi::OsString;
+
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool::run_project_command;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Run {
+    /// The command to run from `commands` in the project's `volta` section, like `test`
+    #[structopt(name = "command")]
+    command: String,
+
+    /// Arguments to pass to the command, after `--`
+    #[structopt(name = "args", raw(last = "true"), parse(from_os_str))]
+    args: Vec<OsString>,
+}
+
+impl Command for Run {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Run);
+
+        let status = run_project_command(&self.command, self.args, session)?;
+        let exit_code = if status.success() {
+            ExitCode::Success
+        } else {
+            ExitCode::UnknownError
+        };
+
+        session.add_event_end(ActivityKind::Run, exit_code);
+        Ok(exit_code)
+    }
+}
@@ -0,0 +1,30 @@
+use structopt::StructOpt;
+
+use volta_core::self_update::{check_for_update, self_update};
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct SelfUpdate {
+    /// Only reports whether a newer version is available, without installing it
+    #[structopt(long = "check")]
+    check: bool,
+}
+
+impl Command for SelfUpdate {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::SelfUpdate);
+
+        let current = env!("CARGO_PKG_VERSION");
+        if self.check {
+            check_for_update(current)?;
+        } else {
+            self_update(current)?;
+        }
+
+        session.add_event_end(ActivityKind::SelfUpdate, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,23 @@
+use structopt::StructOpt;
+
+use volta_core::event_sink::EventSink;
+use volta_core::session::Session;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+/// Sends the queued events to the `publish` events hook's URL. Shims start this in the background
+/// once the queue holds a full batch.
+#[derive(StructOpt)]
+pub(crate) struct SendEvents {
+    /// The URL to send the events to
+    #[structopt(long = "url")]
+    url: String,
+}
+
+impl Command for SendEvents {
+    fn run(self, _session: &mut Session) -> Fallible<ExitCode> {
+        EventSink::new(&self.url).flush()?;
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,108 @@
+use structopt::StructOpt;
+
+use volta_core::path;
+use volta_core::plan::Change;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::shell::{
+    plan_remove_profiles, plan_setup_profiles, remove_profiles, setup_profiles, ProfileShell,
+};
+use volta_core::shim;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::{print_plan, Command};
+
+#[derive(StructOpt)]
+pub(crate) struct Setup {
+    /// Sets up only the given shell, creating its profile if needed (may be repeated)
+    #[structopt(
+        long = "shell",
+        number_of_values = 1,
+        raw(possible_values = "&ProfileShell::NAMES"),
+        case_insensitive = true
+    )]
+    shells: Vec<ProfileShell>,
+
+    /// Removes Volta from your shell profiles instead
+    #[structopt(long = "remove")]
+    remove: bool,
+
+    /// Repairs the shims for installed packages instead, adding missing and removing orphaned ones
+    #[structopt(
+        long = "repair-shims",
+        raw(conflicts_with_all = r#"&["shells", "remove"]"#)
+    )]
+    repair_shims: bool,
+}
+
+impl Command for Setup {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Setup);
+
+        if session.is_dry_run() {
+            print_plan(&self.plan()?);
+        } else if self.repair_shims {
+            let reconciled = shim::reconcile()?;
+            for name in &reconciled.created {
+                println!("created shim for {}", name);
+            }
+            for name in &reconciled.removed {
+                println!("removed shim for {}", name);
+            }
+            if reconciled.created.is_empty() && reconciled.removed.is_empty() {
+                println!("All shims are up to date.");
+            }
+        } else if self.remove {
+            remove_profiles(&self.shells_to_remove())?;
+        } else {
+            shim::create_built_in()?;
+            setup_profiles(&self.shells_to_set_up()?)?;
+        }
+
+        session.add_event_end(ActivityKind::Setup, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+impl Setup {
+    /// Lists the changes that setting up would make, without making them.
+    fn plan(&self) -> Fallible<Vec<Change>> {
+        if self.repair_shims {
+            let reconciled = shim::plan_reconcile()?;
+            let mut changes = Vec::new();
+            for name in &reconciled.removed {
+                changes.push(Change::Remove(path::shim_file(name)?));
+            }
+            for name in &reconciled.created {
+                changes.push(Change::Write(path::shim_file(name)?));
+            }
+            Ok(changes)
+        } else if self.remove {
+            plan_remove_profiles(&self.shells_to_remove())
+        } else {
+            let mut changes = Vec::new();
+            for name in shim::missing_built_in()? {
+                changes.push(Change::Write(path::shim_file(name)?));
+            }
+            changes.extend(plan_setup_profiles(&self.shells_to_set_up()?)?);
+            Ok(changes)
+        }
+    }
+
+    /// The shells to remove Volta from: the given ones, or else every shell.
+    fn shells_to_remove(&self) -> Vec<ProfileShell> {
+        if self.shells.is_empty() {
+            ProfileShell::ALL.to_vec()
+        } else {
+            self.shells.clone()
+        }
+    }
+
+    /// The shells to set Volta up in: the given ones, or else those with a profile.
+    fn shells_to_set_up(&self) -> Fallible<Vec<ProfileShell>> {
+        if self.shells.is_empty() {
+            ProfileShell::detect()
+        } else {
+            Ok(self.shells.clone())
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::shim::{self, ShimResult};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Shim {
+    #[structopt(subcommand)]
+    command: ShimCommand,
+}
+
+#[derive(StructOpt)]
+enum ShimCommand {
+    /// Creates shims that run executables through Volta's project and user toolchains
+    #[structopt(name = "add", author = "", version = "")]
+    Add {
+        /// The executables to create shims for
+        #[structopt(raw(required = "true"))]
+        names: Vec<String>,
+    },
+
+    /// Removes the shims for executables
+    #[structopt(name = "remove", author = "", version = "")]
+    Remove {
+        /// The executables to remove the shims for
+        #[structopt(raw(required = "true"))]
+        names: Vec<String>,
+    },
+
+    /// Lists all shims, along with the packages that provide them
+    #[structopt(name = "list", author = "", version = "")]
+    List,
+}
+
+impl Command for Shim {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Shim);
+
+        match self.command {
+            ShimCommand::Add { names } => {
+                for name in names {
+                    match shim::add(&name)? {
+                        ShimResult::AlreadyExists => println!("shim for {} already exists", name),
+                        _ => println!("created shim for {}", name),
+                    }
+                }
+            }
+            ShimCommand::Remove { names } => {
+                for name in names {
+                    match shim::remove(&name)? {
+                        ShimResult::DoesntExist => println!("there is no shim for {}", name),
+                        _ => println!("removed shim for {}", name),
+                    }
+                }
+            }
+            ShimCommand::List => {
+                let shims = shim::list()?;
+                let width = shims.iter().map(|shim| shim.name.len()).max().unwrap_or(0);
+                for shim in shims {
+                    let source = match shim.package {
+                        Some(package) => package,
+                        None if shim.built_in => "(built-in)".to_string(),
+                        None => "(no package)".to_string(),
+                    };
+                    println!("{:width$}  {}", shim.name, source, width = width);
+                }
+            }
+        }
+
+        session.add_event_end(ActivityKind::Shim, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -5,11 +5,11 @@ use volta_core::tool::ToolSpec;
 use volta_core::version::VersionSpec;
 use volta_fail::{ExitCode, Fallible};
 
-use crate::command::Command;
+use crate::command::{print_plan, Command};
 
 #[derive(StructOpt)]
 pub(crate) struct Uninstall {
-    /// The tool to uninstall, e.g. `node`, `npm`, `yarn`, or <package>
+    /// The tool to uninstall, e.g. `deno`, `bun`, or <package>
     tool: String,
 }
 
@@ -20,7 +20,11 @@ impl Command for Uninstall {
         let version = VersionSpec::default();
         let tool = ToolSpec::from_str_and_version(&self.tool, version);
 
-        tool.uninstall(session)?;
+        if session.is_dry_run() {
+            print_plan(&tool.plan_uninstall(session)?);
+        } else {
+            tool.uninstall(session)?;
+        }
 
         session.add_event_end(ActivityKind::Uninstall, ExitCode::Success);
         Ok(ExitCode::Success)
@@ -0,0 +1,23 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Unlink {
+    /// The linked package to remove. Defaults to the package in the current directory.
+    package: Option<String>,
+}
+
+impl Command for Unlink {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Unlink);
+
+        session.unlink_package(self.package.as_ref().map(String::as_str))?;
+
+        session.add_event_end(ActivityKind::Unlink, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,39 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Upgrade {
+    /// Packages to upgrade, like `typescript` or `@your-org/cli`
+    #[structopt(
+        name = "package",
+        required_unless = "all",
+        conflicts_with = "all",
+        min_values = 1
+    )]
+    packages: Vec<String>,
+
+    /// Upgrades every installed package
+    #[structopt(long = "all")]
+    all: bool,
+}
+
+impl Command for Upgrade {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Upgrade);
+
+        if self.all {
+            session.upgrade_all_packages()?;
+        } else {
+            for package in &self.packages {
+                session.upgrade_package(package)?;
+            }
+        }
+
+        session.add_event_end(ActivityKind::Upgrade, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,97 @@
+use structopt::StructOpt;
+
+use volta_core::checksum::{installed_images, verify_image, InstalledImage};
+use volta_core::error::ErrorDetails;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool::ToolSpec;
+use volta_core::version::VersionSpec;
+use volta_fail::{throw, ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Verify {
+    /// The tool to check, like `node` or `yarn@1.22`; if none is given, every installed version of
+    /// Node and Yarn is checked
+    #[structopt(value_name = "tool[@version]")]
+    tool: Option<String>,
+}
+
+impl Command for Verify {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Verify);
+
+        let filter = match self.tool {
+            Some(ref tool) => Some(ImageFilter::parse(tool)?),
+            None => None,
+        };
+        let images: Vec<InstalledImage> = installed_images()?
+            .into_iter()
+            .filter(|image| filter.as_ref().map_or(true, |filter| filter.matches(image)))
+            .collect();
+
+        if images.is_empty() {
+            println!("No matching tools are installed.");
+        }
+
+        let mut exit_code = ExitCode::Success;
+        for image in &images {
+            let name = format!("{}@{}", image.tool, image.version);
+
+            match verify_image(&image.dir)? {
+                None => println!("{}: no checksums were recorded when it was installed", name),
+                Some(ref mismatches) if mismatches.is_empty() => println!("{}: ok", name),
+                Some(mismatches) => {
+                    println!("{}: files have changed since it was installed", name);
+                    for file in &mismatches.changed {
+                        println!("    changed: {}", file);
+                    }
+                    for file in &mismatches.missing {
+                        println!("    missing: {}", file);
+                    }
+                    for file in &mismatches.added {
+                        println!("    added: {}", file);
+                    }
+                    exit_code = ExitCode::UnknownError;
+                }
+            }
+        }
+
+        session.add_event_end(ActivityKind::Verify, exit_code);
+        Ok(exit_code)
+    }
+}
+
+/// Which images to check. Versions like `latest` that can't be compared with an installed
+/// version check every installed version of the tool.
+struct ImageFilter {
+    tool: &'static str,
+    version: VersionSpec,
+}
+
+impl ImageFilter {
+    fn parse(tool: &str) -> Fallible<Self> {
+        match ToolSpec::try_from_str(tool)? {
+            ToolSpec::Node(version) => Ok(ImageFilter {
+                tool: "node",
+                version,
+            }),
+            ToolSpec::Yarn(version) => Ok(ImageFilter {
+                tool: "yarn",
+                version,
+            }),
+            _ => throw!(ErrorDetails::Unimplemented {
+                feature: format!("Verifying {}", tool),
+            }),
+        }
+    }
+
+    fn matches(&self, image: &InstalledImage) -> bool {
+        image.tool == self.tool
+            && match self.version {
+                VersionSpec::Exact(ref version) => &image.version == version,
+                VersionSpec::Semver(ref requirement) => requirement.matches(&image.version),
+                _ => true,
+            }
+    }
+}
@@ -7,7 +7,8 @@ use which::which_in;
 use volta_core::error::ErrorDetails;
 use volta_core::platform::System;
 use volta_core::session::{ActivityKind, Session};
-use volta_fail::{ExitCode, Fallible, ResultExt};
+use volta_core::tool::explain_tool;
+use volta_fail::{throw, ExitCode, Fallible, ResultExt};
 
 use crate::command::Command;
 
@@ -15,6 +16,14 @@ use crate::command::Command;
 pub(crate) struct Which {
     /// The binary to find, e.g. `node` or `npm`
     binary: String,
+
+    /// Lists every installed package that provides the binary, starting with the one in use
+    #[structopt(long = "all", short = "a")]
+    all: bool,
+
+    /// Explains how Volta decides which binary to run, before printing its path
+    #[structopt(long = "explain")]
+    explain: bool,
 }
 
 impl Command for Which {
@@ -27,6 +36,27 @@ impl Command for Which {
 
         let bin = OsString::from(self.binary.as_str());
 
+        if self.explain {
+            for step in explain_tool(&bin, session)? {
+                println!("{}", step);
+            }
+        }
+
+        if self.all {
+            let paths = session.package_bin_paths(&self.binary)?;
+            if paths.is_empty() {
+                throw!(ErrorDetails::NoPackageProvidesBin { bin: self.binary });
+            }
+
+            for path in &paths {
+                println!("{}", path.to_string_lossy());
+            }
+
+            let exit_code = ExitCode::Success;
+            session.add_event_end(ActivityKind::Which, exit_code);
+            return Ok(exit_code);
+        }
+
         let user_tool = session.get_user_tool(&bin)?;
         let project_bin_path =
             session
@@ -1,34 +1,81 @@
 #[macro_use]
 mod command;
 mod cli;
+mod select;
+
+use std::env;
 
 use structopt::StructOpt;
 
-use volta_core::error::report_error;
+use volta_core::env::{LIMIT_RATE, PROFILE};
+use volta_core::error::{report_error, ErrorFormat};
 use volta_core::log::{LogContext, LogVerbosity, Logger};
 use volta_core::session::{ActivityKind, Session};
+use volta_core::style::disable_progress;
+use volta_core::update_check::UpdateCheck;
 
 /// The entry point for the `volta` CLI.
 pub fn main() {
     let volta = cli::Volta::from_args();
-    let verbosity = match (&volta.verbose, &volta.quiet) {
-        (false, false) => LogVerbosity::Default,
-        (true, false) => LogVerbosity::Verbose,
-        (false, true) => LogVerbosity::Quiet,
-        (true, true) => unreachable!(
+    let verbosity = match (volta.verbose, volta.quiet) {
+        (0, false) => LogVerbosity::Default,
+        (1, false) => LogVerbosity::Verbose,
+        (_, false) => LogVerbosity::VeryVerbose,
+        (0, true) => LogVerbosity::Quiet,
+        (_, true) => unreachable!(
             "StructOpt should prevent the user from providing both --verbose and --quiet"
         ),
     };
-    Logger::init(LogContext::Volta, verbosity).expect("Only a single logger should be initialized");
+    let error_format = if volta.json_errors {
+        ErrorFormat::Json
+    } else {
+        ErrorFormat::from_env()
+    };
+    Logger::init(LogContext::Volta, verbosity, error_format)
+        .expect("Only a single logger should be initialized");
+
+    if volta.no_progress {
+        disable_progress();
+    }
+
+    // Setting the variable also applies the profile to any tools that this command launches
+    if let Some(ref profile) = volta.profile {
+        env::set_var(PROFILE, profile);
+    }
+
+    // Likewise, the limit also applies to any downloads by tools that this command launches
+    if let Some(ref limit_rate) = volta.limit_rate {
+        env::set_var(LIMIT_RATE, limit_rate);
+    }
+
+    // Shell prompts run `volta current --porcelain` on every render, so it skips the update
+    // check and the event log, which would otherwise read the hooks. `volta send-events` skips
+    // them too, since it runs in the background and shouldn't queue events of its own
+    let porcelain = match volta.command {
+        Some(cli::Subcommand::Current(ref current)) => current.porcelain,
+        Some(cli::Subcommand::SendEvents(_)) => true,
+        _ => false,
+    };
+
+    // The notice would be stale right after updating, and isn't wanted with `--quiet`
+    let notify = match volta.command {
+        Some(cli::Subcommand::SelfUpdate(_)) => false,
+        _ => !volta.quiet && !porcelain,
+    };
+    let update_check = UpdateCheck::start(env!("CARGO_PKG_VERSION"), notify);
 
     let mut session = Session::new();
     session.add_event_start(ActivityKind::Volta);
     let exit_code = volta.run(&mut session).unwrap_or_else(|err| {
-        report_error(env!("CARGO_PKG_VERSION"), &err);
+        report_error(env!("CARGO_PKG_VERSION"), &err, error_format);
         session.add_event_error(ActivityKind::Volta, &err);
         err.exit_code()
     });
 
     session.add_event_end(ActivityKind::Volta, exit_code);
+    update_check.show();
+    if porcelain {
+        exit_code.exit();
+    }
     session.exit(exit_code);
 }
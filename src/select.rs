@@ -0,0 +1,158 @@
+//! A list that is drawn in the terminal for the user to choose an item from with the arrow keys,
+//! and the version pickers that `volta install` and `volta pin` show with it.
+
+use std::io;
+
+use console::{style, Key, Term};
+use semver::Version;
+
+use volta_core::session::Session;
+use volta_core::tool::ToolSpec;
+use volta_core::version::VersionSpec;
+use volta_fail::Fallible;
+
+/// Asks the user to choose one of `items`, starting from the one at `default`. Returns `None` if
+/// there is no terminal to ask with, so that the caller can fall back to a default.
+pub(crate) fn select(prompt: &str, items: &[String], default: usize) -> Option<usize> {
+    let term = Term::stderr();
+    if items.is_empty() || !term.is_term() {
+        return None;
+    }
+
+    // Keys can't be read when stdin isn't the terminal, e.g. when input is piped in
+    select_with(&term, prompt, items, default).ok()
+}
+
+fn select_with(term: &Term, prompt: &str, items: &[String], default: usize) -> io::Result<usize> {
+    let mut selected = default.min(items.len() - 1);
+
+    term.write_line(&format!(
+        "{} {}",
+        prompt,
+        style("(use the arrow keys)").dim()
+    ))?;
+    loop {
+        for (index, item) in items.iter().enumerate() {
+            if index == selected {
+                term.write_line(&format!("{} {}", style(">").cyan().bold(), item))?;
+            } else {
+                term.write_line(&format!("  {}", item))?;
+            }
+        }
+
+        let key = term.read_key();
+        term.clear_last_lines(items.len())?;
+        let key = match key {
+            Ok(key) => key,
+            Err(error) => {
+                term.clear_last_lines(1)?;
+                return Err(error);
+            }
+        };
+        match key {
+            Key::ArrowUp | Key::Char('k') => selected = (selected + items.len() - 1) % items.len(),
+            Key::ArrowDown | Key::Char('j') => selected = (selected + 1) % items.len(),
+            Key::Enter => break,
+            _ => {}
+        }
+    }
+
+    term.clear_last_lines(1)?;
+    term.write_line(&format!("{} {}", prompt, style(&items[selected]).cyan()))?;
+    Ok(selected)
+}
+
+/// Lets the user choose the version of Node or Yarn when one was asked for without a version,
+/// like `volta install node`, from the newest version of each recent release line. The user is
+/// only asked while setting up, before they have a default platform, and never with `--yes`,
+/// with `--dry-run`, or without a terminal to ask with. Otherwise, the tools keep the version
+/// they were parsed with (the latest LTS).
+pub(crate) fn pick_versions(
+    tool_strs: &[String],
+    tools: Vec<ToolSpec>,
+    yes: bool,
+    session: &Session,
+) -> Fallible<Vec<ToolSpec>> {
+    if !may_ask(yes, session.is_dry_run(), Term::stderr().is_term())
+        || session.user_platform()?.is_some()
+    {
+        return Ok(tools);
+    }
+
+    let bare = |name: &str| tool_strs.iter().any(|tool| tool == name);
+    let mut picked = Vec::with_capacity(tools.len());
+
+    for tool in tools {
+        picked.push(match tool {
+            ToolSpec::Node(version) if bare("node") => {
+                let choices = session.node_choices()?;
+                let (items, default) = node_items(&choices);
+
+                match select("Which version of Node?", &items, default) {
+                    Some(index) => ToolSpec::Node(VersionSpec::Exact(choices[index].0.clone())),
+                    None => ToolSpec::Node(version),
+                }
+            }
+            ToolSpec::Yarn(version) if bare("yarn") => {
+                let choices = session.yarn_choices()?;
+                let items: Vec<String> = choices.iter().map(ToString::to_string).collect();
+
+                match select("Which version of Yarn?", &items, 0) {
+                    Some(index) => ToolSpec::Yarn(VersionSpec::Exact(choices[index].clone())),
+                    None => ToolSpec::Yarn(version),
+                }
+            }
+            tool => tool,
+        });
+    }
+
+    Ok(picked)
+}
+
+/// Whether the user may be asked to choose a version at all.
+fn may_ask(yes: bool, dry_run: bool, is_term: bool) -> bool {
+    !yes && !dry_run && is_term
+}
+
+/// The items for choosing a version of Node, labelled with their release lines, and the index of
+/// the one to start from, which is the newest LTS.
+fn node_items(choices: &[(Version, bool)]) -> (Vec<String>, usize) {
+    let items = choices
+        .iter()
+        .map(|(version, lts)| {
+            let line = if *lts { "LTS" } else { "Current" };
+            format!("{} {}", version, style(line).dim())
+        })
+        .collect();
+    let default = choices.iter().position(|(_, lts)| *lts).unwrap_or(0);
+    (items, default)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn asks_only_when_allowed() {
+        assert!(may_ask(false, false, true));
+        assert!(!may_ask(true, false, true));
+        assert!(!may_ask(false, true, true));
+        assert!(!may_ask(false, false, false));
+    }
+
+    #[test]
+    fn starts_from_the_newest_lts() {
+        let choices = vec![
+            (Version::new(21, 1, 0), false),
+            (Version::new(20, 9, 0), true),
+            (Version::new(18, 18, 2), true),
+        ];
+        let (items, default) = node_items(&choices);
+        assert_eq!(items.len(), 3);
+        assert!(items[0].starts_with("21.1.0 "));
+        assert_eq!(default, 1);
+
+        let (_, default) = node_items(&[(Version::new(21, 1, 0), false)]);
+        assert_eq!(default, 0);
+    }
+}
@@ -1,18 +1,23 @@
-use volta_core::error::report_error;
+use volta_core::error::{report_error, ErrorFormat};
 use volta_core::log::{LogContext, LogVerbosity, Logger};
 use volta_core::session::{ActivityKind, Session};
 use volta_core::tool::execute_tool;
+use volta_core::trace::write_trace;
 use volta_fail::ExitCode;
 
 pub fn main() {
-    Logger::init(LogContext::Shim, LogVerbosity::Default)
+    let format = ErrorFormat::from_env();
+    Logger::init(LogContext::Shim, LogVerbosity::Default, format)
         .expect("Only a single Logger should be initialized");
 
     let mut session = Session::new();
 
     session.add_event_start(ActivityKind::Tool);
 
-    match execute_tool(&mut session) {
+    let result = execute_tool(&mut session);
+    write_trace();
+
+    match result {
         Ok(status) if status.success() => {
             session.add_event_end(ActivityKind::Tool, ExitCode::Success);
             session.exit(ExitCode::Success);
@@ -24,7 +29,7 @@ pub fn main() {
             session.exit_tool(code);
         }
         Err(err) => {
-            report_error(env!("CARGO_PKG_VERSION"), &err);
+            report_error(env!("CARGO_PKG_VERSION"), &err, format);
             session.add_event_error(ActivityKind::Tool, &err);
             session.exit(ExitCode::ExecutionFailure);
         }
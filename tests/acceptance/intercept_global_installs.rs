@@ -14,6 +14,18 @@ const PACKAGE_JSON: &'static str = r#"{
     }
 }"#;
 
+const WARN_HOOKS: &'static str = r#"{
+    "volta": {
+        "globalInstalls": "warn"
+    }
+}"#;
+
+const PASSTHROUGH_HOOKS: &'static str = r#"{
+    "volta": {
+        "globalInstalls": "passthrough"
+    }
+}"#;
+
 #[test]
 fn npm_prevents_global_install() {
     let s = sandbox().package_json(PACKAGE_JSON).build();
@@ -127,3 +139,39 @@ fn yarn_allows_global_add_with_env_variable() {
             .with_stderr_contains("[..]Could not download node version[..]")
     );
 }
+
+#[test]
+fn npm_warns_about_global_install_with_warn_policy() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .project_hooks(WARN_HOOKS)
+        .build();
+
+    // Since we are using a fake Node version, we expect to get an error about being unable to download
+    assert_that!(
+        s.npm("i -g ember-cli"),
+        execs()
+            .with_status(ExitCode::ExecutionFailure as i32)
+            .with_stderr_contains("[..]npm is installing packages globally[..]")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]Could not download node version[..]")
+    );
+}
+
+#[test]
+fn yarn_allows_global_add_with_passthrough_policy() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .project_hooks(PASSTHROUGH_HOOKS)
+        .build();
+
+    // Since we are using a fake Yarn/Node version, we expect to get an error about being unable to download
+    assert_that!(
+        s.yarn("global add ember-cli"),
+        execs()
+            .with_status(ExitCode::ExecutionFailure as i32)
+            .with_stderr_does_not_contain("[..]installing packages globally[..]")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]Could not download node version[..]")
+    );
+}
@@ -0,0 +1,157 @@
+use crate::support::sandbox::{sandbox, DistroMetadata, Sandbox, YarnFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON_WITH_PINNED_NODE: &'static str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "1.2.3"
+  }
+}"#;
+
+const YARN_VERSION_INFO: &'static str = r#"[
+{"tag_name":"v1.2.42","assets":[{"name":"yarn-v1.2.42.tar.gz"}]},
+{"tag_name":"v1.4.159","assets":[{"name":"yarn-v1.4.159.tar.gz"}]}
+]"#;
+
+const YARN_VERSION_FIXTURES: [DistroMetadata; 2] = [
+    DistroMetadata {
+        version: "1.4.159",
+        compressed_size: 177,
+        uncompressed_size: Some(0x00280000),
+    },
+    DistroMetadata {
+        version: "1.2.42",
+        compressed_size: 174,
+        uncompressed_size: Some(0x00280000),
+    },
+];
+
+#[test]
+fn keeps_archives_by_default() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_PINNED_NODE)
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin yarn@1.4"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert!(Sandbox::yarn_image_exists("1.4.159"));
+    assert!(Sandbox::yarn_inventory_tarball_exists("1.4.159"));
+}
+
+#[test]
+fn removes_archives_when_never_kept() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_PINNED_NODE)
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .env("VOLTA_KEEP_ARCHIVES", "never")
+        .build();
+
+    assert_that!(
+        s.volta("pin yarn@1.4"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert!(Sandbox::yarn_image_exists("1.4.159"));
+    assert!(!Sandbox::yarn_inventory_tarball_exists("1.4.159"));
+
+    // The image is still found without its archive, so it isn't fetched again
+    assert_that!(
+        s.volta("pin yarn@1.4"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert!(!Sandbox::yarn_inventory_tarball_exists("1.4.159"));
+}
+
+#[test]
+fn keeps_the_last_archives() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_PINNED_NODE)
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .env("VOLTA_KEEP_ARCHIVES", "last-1")
+        .build();
+
+    assert_that!(
+        s.volta("pin yarn@1.2"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert_that!(
+        s.volta("pin yarn@1.4"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert!(!Sandbox::yarn_inventory_tarball_exists("1.2.42"));
+    assert!(Sandbox::yarn_inventory_tarball_exists("1.4.159"));
+    assert!(Sandbox::yarn_image_exists("1.2.42"));
+}
+
+#[test]
+fn rejects_unknown_archive_policies() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_PINNED_NODE)
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .env("VOLTA_KEEP_ARCHIVES", "sometimes")
+        .build();
+
+    assert_that!(
+        s.volta("pin yarn@1.4"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Unrecognized archive policy \"sometimes\"")
+    );
+}
+
+#[test]
+fn gc_removes_archives_beyond_the_policy() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_PINNED_NODE)
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin yarn@1.2"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert_that!(
+        s.volta("pin yarn@1.4"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    // Every archive is kept by default
+    assert_that!(
+        s.volta("gc"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("No cached archives to remove.")
+    );
+
+    assert_that!(
+        s.volta("--dry-run gc --keep last-1"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("would remove [..]yarn-v1.2.42.tar.gz")
+    );
+    assert!(Sandbox::yarn_inventory_tarball_exists("1.2.42"));
+
+    assert_that!(
+        s.volta("gc --keep last-1"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("Removed 1 cached archive(s).")
+    );
+    assert!(!Sandbox::yarn_inventory_tarball_exists("1.2.42"));
+    assert!(Sandbox::yarn_inventory_tarball_exists("1.4.159"));
+    assert!(Sandbox::yarn_image_exists("1.2.42"));
+}
@@ -0,0 +1,76 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const SHIM_DEPTH: &'static str = "_VOLTA_SHIM_DEPTH";
+
+#[cfg(unix)]
+fn print_env_script() -> String {
+    use volta_core::env::{NODE_VERSION, PROJECT_ROOT, TOOL_SOURCE};
+
+    format!(
+        "#!/bin/sh\necho \"source=${} node=${} root=${} depth=${}\"\n",
+        TOOL_SOURCE, NODE_VERSION, PROJECT_ROOT, SHIM_DEPTH
+    )
+}
+
+#[test]
+#[cfg(unix)]
+fn describes_passthrough_launches() {
+    let s = sandbox()
+        .path_executable("npm", &print_env_script())
+        .build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("source=passthrough node= root= depth=1")
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn describes_the_project_root() {
+    let s = sandbox()
+        .package_json(r#"{ "name": "test-package" }"#)
+        .path_executable("npm", &print_env_script())
+        .build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs().with_status(0).with_stdout_contains(format!(
+            "source=passthrough node= root={} depth=1",
+            s.root().display()
+        ))
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn counts_nested_shims() {
+    let s = sandbox()
+        .env(SHIM_DEPTH, "4")
+        .path_executable("npm", &print_env_script())
+        .build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs().with_status(0).with_stdout_contains("[..]depth=5")
+    );
+}
+
+#[test]
+fn stops_shims_that_run_each_other() {
+    let s = sandbox().env(SHIM_DEPTH, "32").build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(ExitCode::ExecutionFailure as i32)
+            .with_stderr_contains("[..]Volta's shim for 'npm' was run too many times in a row.")
+    );
+}
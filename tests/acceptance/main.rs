@@ -3,8 +3,30 @@ mod support;
 // test files
 
 mod intercept_global_installs;
+mod keep_archives;
+mod launch_env;
+mod network_failures;
+mod partial_pins;
+mod passthrough;
+mod profiles;
+mod trace;
 mod verbose_errors;
+mod volta_audit;
+mod volta_config;
 mod volta_current;
 mod volta_deactivate;
+mod volta_default;
+mod volta_fetch;
+mod volta_init;
+mod volta_install;
+mod volta_list;
+#[cfg(unix)]
+mod volta_matrix;
 mod volta_pin;
+mod volta_prefer;
+mod volta_provision;
+mod volta_run;
+mod volta_shim;
 mod volta_uninstall;
+mod volta_upgrade;
+mod volta_which;
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use crate::support::sandbox::{sandbox, serve_distros, DistroFixture, DistroMetadata, NodeFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+use test_support::mock_server::{MockServer, Reply};
+
+use volta_core::env::NETWORK_TIMEOUT;
+use volta_fail::ExitCode;
+
+const BASIC_PACKAGE_JSON: &'static str = r#"{
+  "name": "test-package"
+}"#;
+
+const NODE_VERSION_INFO: &'static str = r#"[
+{"version":"v6.19.62","npm":"3.10.1066","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]}
+]
+"#;
+
+const NODE_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "6.19.62",
+    compressed_size: 0,
+    uncompressed_size: None,
+}];
+
+#[test]
+fn node_index_server_error() {
+    let server = MockServer::start();
+    server.mock("GET", "/node-dist/index.json", Reply::status(500));
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .mock_server(&server)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]Could not download Node version registry")
+    );
+    assert_eq!(server.hits("/node-dist/index.json"), 1);
+}
+
+#[test]
+fn node_index_truncated() {
+    let server = MockServer::start();
+    server.mock(
+        "GET",
+        "/node-dist/index.json",
+        Reply::json(NODE_VERSION_INFO).truncate(20),
+    );
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .mock_server(&server)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs().with_status(ExitCode::NetworkError as i32)
+    );
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON);
+}
+
+#[test]
+fn node_archive_truncated() {
+    let server = MockServer::start();
+    server.node_index(NODE_VERSION_INFO);
+    serve_distros::<NodeFixture>(&server, &NODE_VERSION_FIXTURES);
+    // replace the archive with one that is cut off partway
+    let fixture: NodeFixture = NODE_VERSION_FIXTURES[0].clone().into();
+    let archive = std::fs::read(fixture.fixture_path()).unwrap();
+    let len = archive.len() / 2;
+    server.mock(
+        "GET",
+        &fixture.server_path(),
+        Reply::ok(archive).truncate(len),
+    );
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .mock_server(&server)
+        .build();
+
+    // a body that is shorter than its Content-Length is a network failure, not a bad archive
+    assert_that!(
+        s.volta("pin node@6"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]Could not download Node v6.19.62")
+    );
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON);
+}
+
+#[test]
+fn node_index_timeout() {
+    let server = MockServer::start();
+    server.mock("GET", "/node-dist/index.json", Reply::hang());
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .mock_server(&server)
+        .env(NETWORK_TIMEOUT, "1")
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]Could not download Node version registry")
+    );
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON);
+}
+
+#[test]
+fn node_archive_timeout() {
+    let server = MockServer::start();
+    server.node_index(NODE_VERSION_INFO);
+    let fixture: NodeFixture = NODE_VERSION_FIXTURES[0].clone().into();
+    server.mock("GET", &fixture.server_path(), Reply::hang());
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .mock_server(&server)
+        .env(NETWORK_TIMEOUT, "1")
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]Could not download node version 6.19.62")
+    );
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON);
+}
+
+#[test]
+fn slow_node_index() {
+    let server = MockServer::start();
+    server.mock(
+        "GET",
+        "/node-dist/index.json",
+        Reply::json(NODE_VERSION_INFO).delay(Duration::from_millis(500)),
+    );
+    serve_distros::<NodeFixture>(&server, &NODE_VERSION_FIXTURES);
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .mock_server(&server)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert!(s.read_package_json().contains(r#""node": "6.19.62""#));
+}
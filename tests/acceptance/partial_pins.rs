@@ -0,0 +1,135 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_core::env::PARTIAL_PINS;
+use volta_fail::ExitCode;
+
+const YARN_ONLY_PACKAGE_JSON: &'static str = r#"{
+  "name": "test-package",
+  "volta": {
+    "yarn": "1.19.0"
+  }
+}"#;
+
+const NODE_ONLY_PACKAGE_JSON: &'static str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "4.1.0",
+    "npm": "2.14.3"
+  }
+}"#;
+
+const DEFAULT_PLATFORM: &'static str =
+    r#"{"node":{"runtime":"9.11.2","npm":"5.6.0"},"yarn":"1.22.19"}"#;
+
+#[test]
+fn explains_yarn_pinned_without_node() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .build();
+
+    // Nothing has been fetched, so there is no yarn to find after explaining
+    assert_that!(
+        s.volta("which --explain yarn"),
+        execs()
+            .with_status(ExitCode::UnknownError as i32)
+            .with_stdout_contains(
+                "The project pins yarn@1.19.0 without pinning Node, which is allowed with a warning (see VOLTA_PARTIAL_PINS)"
+            )
+            .with_stdout_contains(
+                "Merging the project's pins with your default platform: node v9.11.2 from your default, npm v5.6.0 from your default, yarn v1.19.0 from the project (not fetched yet)"
+            )
+    );
+}
+
+#[test]
+fn explains_default_yarn_in_pinned_project() {
+    let s = sandbox()
+        .package_json(NODE_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .build();
+
+    assert_that!(
+        s.volta("which --explain yarn"),
+        execs()
+            .with_status(ExitCode::UnknownError as i32)
+            .with_stdout_contains(
+                "Merging the project's pins with your default platform: node v4.1.0 from the project, npm v2.14.3 from the project, yarn v1.22.19 from your default (not fetched yet)"
+            )
+            .with_stdout_does_not_contain("[..]without pinning Node[..]")
+    );
+}
+
+#[test]
+fn fails_when_partial_pins_are_denied() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .env(PARTIAL_PINS, "deny")
+        .build();
+
+    assert_that!(
+        s.yarn("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "[..]This project's package.json pins yarn@1.19.0 without pinning Node."
+            )
+    );
+}
+
+#[test]
+fn rejects_unknown_partial_pin_policies() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .env(PARTIAL_PINS, "sometimes")
+        .build();
+
+    assert_that!(
+        s.yarn("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Unrecognized partial pin policy \"sometimes\"")
+    );
+}
+
+#[test]
+fn lists_yarn_pinned_without_node() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .build();
+    let pin_file = s.root().join("package.json");
+
+    assert_that!(
+        s.volta("list --all"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("node   9.11.2     default (active)")
+            .with_stdout_contains(format!(
+                "yarn   1.19.0     project ({}) (active)",
+                pin_file.display()
+            ))
+            .with_stdout_contains("yarn   1.22.19    default")
+            .with_stdout_does_not_contain("1.22.19    default (active)")
+    );
+}
+
+#[test]
+fn porcelain_merges_yarn_pinned_without_node() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .build();
+
+    assert_that!(
+        s.volta("current --porcelain"),
+        execs()
+            .with_status(0)
+            .with_stdout("node:9.11.2 yarn:1.19.0 source:project")
+    );
+}
@@ -0,0 +1,58 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_core::env::PASSTHROUGH;
+use volta_fail::ExitCode;
+
+#[test]
+fn passes_through_by_default() {
+    let s = sandbox().build();
+
+    // The sandbox's PATH is empty, so the tool that is passed through to isn't found
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_does_not_contain("[..]No platform is set for npm[..]")
+    );
+}
+
+#[test]
+fn warns_when_passing_through() {
+    let s = sandbox().env(PASSTHROUGH, "warn").build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "[..]No platform is set for npm, so it will run from your PATH without Volta."
+            )
+    );
+}
+
+#[test]
+fn fails_when_passthrough_is_denied() {
+    let s = sandbox().env(PASSTHROUGH, "deny").build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Node is not available.")
+    );
+}
+
+#[test]
+fn rejects_unknown_passthrough_policies() {
+    let s = sandbox().env(PASSTHROUGH, "sometimes").build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Unrecognized passthrough policy \"sometimes\"")
+    );
+}
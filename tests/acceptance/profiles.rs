@@ -0,0 +1,87 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_core::env::PROFILE;
+use volta_fail::ExitCode;
+
+const YARN_ONLY_PACKAGE_JSON: &'static str = r#"{
+  "name": "test-package",
+  "volta": {
+    "yarn": "1.19.0"
+  }
+}"#;
+
+const DEFAULT_PLATFORM: &'static str =
+    r#"{"node":{"runtime":"9.11.2","npm":"5.6.0"},"yarn":"1.22.19"}"#;
+
+const WORK_PLATFORM: &'static str =
+    r#"{"node":{"runtime":"10.99.1040","npm":"6.2.26"},"yarn":"1.7.71"}"#;
+
+const HOME_PLATFORM: &'static str = r#"{"node":{"runtime":"8.9.4","npm":"5.6.0"},"yarn":"1.2.0"}"#;
+
+#[test]
+fn partial_pins_merge_with_the_profile_default() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .profile_platform("work", WORK_PLATFORM)
+        .build();
+
+    assert_that!(
+        s.volta("--profile work which --explain yarn"),
+        execs()
+            .with_status(ExitCode::UnknownError as i32)
+            .with_stdout_contains(
+                "Merging the project's pins with your default platform: node v10.99.1040 from your default, npm v6.2.26 from your default, yarn v1.19.0 from the project (not fetched yet)"
+            )
+            .with_stdout_does_not_contain("[..]v9.11.2[..]")
+    );
+}
+
+#[test]
+fn profile_without_a_default_does_not_use_the_shared_one() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .build();
+
+    assert_that!(
+        s.volta("--profile work which --explain yarn"),
+        execs().with_stdout_does_not_contain("[..]v9.11.2[..]")
+    );
+}
+
+#[test]
+fn profile_flag_overrides_the_environment() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .profile_platform("work", WORK_PLATFORM)
+        .profile_platform("home", HOME_PLATFORM)
+        .env(PROFILE, "home")
+        .build();
+
+    assert_that!(
+        s.volta("which --explain yarn"),
+        execs().with_stdout_contains("[..]node v8.9.4 from your default[..]")
+    );
+    assert_that!(
+        s.volta("--profile work which --explain yarn"),
+        execs()
+            .with_stdout_contains("[..]node v10.99.1040 from your default[..]")
+            .with_stdout_does_not_contain("[..]v8.9.4[..]")
+    );
+}
+
+#[test]
+fn rejects_invalid_profile_names() {
+    let s = sandbox().platform(DEFAULT_PLATFORM).build();
+
+    assert_that!(
+        s.volta("--profile ../escape list node"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]Invalid profile name \"../escape\".")
+    );
+}
@@ -7,9 +7,11 @@ use std::time::{Duration, SystemTime};
 
 use reqwest::hyper_011::header::HttpDate;
 
+use test_support::mock_server::{MockServer, Reply};
 use test_support::{self, ok_or_panic, paths, paths::PathExt, process::ProcessBuilder};
 
-use volta_core::path::{archive_extension, create_file_symlink, ARCH, OS};
+use volta_core::env::MOCK_SERVER;
+use volta_core::path::{archive_extension, create_dir_symlink, create_file_symlink, ARCH, OS};
 
 #[cfg(feature = "mock-network")]
 use mockito::{self, mock, Matcher};
@@ -113,6 +115,7 @@ impl FileBuilder {
 pub struct SandboxBuilder {
     root: Sandbox,
     files: Vec<FileBuilder>,
+    executables: Vec<FileBuilder>,
     caches: Vec<CacheBuilder>,
     path_dirs: Vec<PathBuf>,
 }
@@ -205,6 +208,7 @@ impl SandboxBuilder {
                 path: OsString::new(),
             },
             files: vec![],
+            executables: vec![],
             caches: vec![],
             path_dirs: vec![volta_bin_dir()],
         }
@@ -229,6 +233,13 @@ impl SandboxBuilder {
         self
     }
 
+    /// Set the project's .volta/hooks.json for the sandbox (chainable)
+    pub fn project_hooks(mut self, contents: &str) -> Self {
+        let hooks_file = project_hooks_file(self.root());
+        self.files.push(FileBuilder::new(hooks_file, contents));
+        self
+    }
+
     /// Set the platform.json for the sandbox (chainable)
     pub fn platform(mut self, contents: &str) -> Self {
         self.files
@@ -236,6 +247,27 @@ impl SandboxBuilder {
         self
     }
 
+    /// Set the platform.json of a profile for the sandbox (chainable)
+    pub fn profile_platform(mut self, profile: &str, contents: &str) -> Self {
+        self.files
+            .push(FileBuilder::new(profile_platform_file(profile), contents));
+        self
+    }
+
+    /// Set the config.toml for the sandbox (chainable)
+    pub fn config(mut self, contents: &str) -> Self {
+        self.files.push(FileBuilder::new(config_file(), contents));
+        self
+    }
+
+    /// Set the version policy for the sandbox, and point VOLTA_POLICY at it (chainable)
+    pub fn policy(mut self, contents: &str) -> Self {
+        let policy_file = policy_file();
+        self.files
+            .push(FileBuilder::new(policy_file.clone(), contents));
+        self.env("VOLTA_POLICY", &policy_file.to_string_lossy())
+    }
+
     /// Set the shell for the sandbox (chainable)
     pub fn volta_shell(self, shell_name: &str) -> Self {
         self.env("VOLTA_SHELL", shell_name)
@@ -253,6 +285,17 @@ impl SandboxBuilder {
         self
     }
 
+    /// Add an executable script to a directory on the PATH (chainable)
+    pub fn path_executable(mut self, name: &str, contents: &str) -> Self {
+        let dir = self.root().join("path");
+        self.executables
+            .push(FileBuilder::new(dir.join(name), contents));
+        if !self.path_dirs.contains(&dir) {
+            self.path_dirs.push(dir);
+        }
+        self
+    }
+
     /// Setup mock to return the available node versions (chainable)
     pub fn node_available_versions(mut self, body: &str) -> Self {
         let mock = mock("GET", "/node-dist/index.json")
@@ -298,6 +341,18 @@ impl SandboxBuilder {
         self
     }
 
+    /// Setup mock to return the security advisories for packages (chainable)
+    pub fn advisories(mut self, body: &str) -> Self {
+        let mock = mock("POST", "/registry/-/npm/v1/security/advisories/bulk")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+        self.root.mocks.push(mock);
+
+        self
+    }
+
     /// Setup mock to return a 404 for any GET request
     /// Note: Mocks are matched in reverse order, so any created _after_ this will work
     ///       While those created before will not
@@ -349,6 +404,11 @@ impl SandboxBuilder {
         this
     }
 
+    /// Send the sandbox's requests to a mock server instead of to mockito (chainable)
+    pub fn mock_server(self, server: &MockServer) -> Self {
+        self.env(MOCK_SERVER, server.url())
+    }
+
     /// Set a package config file for the sandbox (chainable)
     pub fn package_config(mut self, name: &str, contents: &str) -> Self {
         let package_cfg_file = package_config_file(name);
@@ -383,6 +443,30 @@ impl SandboxBuilder {
         self
     }
 
+    /// Set an unpackaged package that provides the specified executables for the sandbox (chainable)
+    pub fn package_image_with_bins(mut self, name: &str, version: &str, bins: &[&str]) -> Self {
+        let package_img_dir = package_image_dir(name, version);
+        let bin_map = bins
+            .iter()
+            .map(|bin| format!(r#""{}":"./{}.js""#, bin, bin))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.files.push(FileBuilder::new(
+            package_img_dir.join("package.json"),
+            &format!(
+                r#"{{"name":"{}","version":"{}","bin":{{{}}}}}"#,
+                name, version, bin_map
+            ),
+        ));
+        for bin in bins {
+            self.files.push(FileBuilder::new(
+                package_img_dir.join(format!("{}.js", bin)),
+                "#!/usr/bin/env node\n",
+            ));
+        }
+        self
+    }
+
     /// Set cached package tarballs for the sandbox (chainable)
     pub fn package_inventory(mut self, name: &str, version: &str) -> Self {
         let pkg_inventory_dir = package_inventory_dir();
@@ -395,6 +479,26 @@ impl SandboxBuilder {
         self
     }
 
+    /// Set an unpacked runtime, like Deno, for the sandbox (chainable)
+    pub fn runtime_image(mut self, tool: &str, version: &str) -> Self {
+        let bin = runtime_image_dir(tool, version).join("bin").join(format!(
+            "{}{}",
+            tool,
+            env::consts::EXE_SUFFIX
+        ));
+        self.files
+            .push(FileBuilder::new(bin, "contents don't matter"));
+        self
+    }
+
+    /// Set a cached runtime archive, like Deno's, for the sandbox (chainable)
+    pub fn runtime_inventory(mut self, tool: &str, version: &str) -> Self {
+        let archive = runtime_inventory_dir(tool).join(format!("{}-v{}.zip", tool, version));
+        self.files
+            .push(FileBuilder::new(archive, "archive contents"));
+        self
+    }
+
     /// Create the project
     pub fn build(mut self) -> Sandbox {
         // First, clean the directory if it already exists
@@ -424,6 +528,16 @@ impl SandboxBuilder {
             file_builder.build();
         }
 
+        // write executables
+        for file_builder in self.executables {
+            file_builder.build();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                ok_or_panic! { fs::set_permissions(&file_builder.path, fs::Permissions::from_mode(0o755)) };
+            }
+        }
+
         // join dirs for the path (volta bin path is already first)
         self.root.path = env::join_paths(self.path_dirs.iter()).unwrap();
 
@@ -436,6 +550,15 @@ impl SandboxBuilder {
     }
 }
 
+/// Serves the archives of some distros from a mock server
+pub fn serve_distros<T: DistroFixture>(server: &MockServer, fixtures: &[DistroMetadata]) {
+    for fixture in fixtures {
+        let fixture: T = fixture.clone().into();
+        let archive = fs::read(fixture.fixture_path()).unwrap();
+        server.mock("GET", &fixture.server_path(), Reply::ok(archive));
+    }
+}
+
 // files and dirs in the sandbox
 
 fn home_dir() -> PathBuf {
@@ -495,6 +618,9 @@ fn package_json_file(mut root: PathBuf) -> PathBuf {
     root.push("package.json");
     root
 }
+fn project_hooks_file(root: PathBuf) -> PathBuf {
+    root.join(".volta").join("hooks.json")
+}
 fn package_config_file(name: &str) -> PathBuf {
     user_dir().join("packages").join(format!("{}.json", name))
 }
@@ -504,12 +630,31 @@ fn binary_config_file(name: &str) -> PathBuf {
 fn shim_file(name: &str) -> PathBuf {
     volta_bin_dir().join(format!("{}{}", name, env::consts::EXE_SUFFIX))
 }
+fn runtime_image_dir(tool: &str, version: &str) -> PathBuf {
+    image_dir().join(tool).join(version)
+}
+fn runtime_inventory_dir(tool: &str) -> PathBuf {
+    inventory_dir().join(tool)
+}
 fn package_image_dir(name: &str, version: &str) -> PathBuf {
     image_dir().join("packages").join(name).join(version)
 }
+fn config_file() -> PathBuf {
+    volta_home().join("config.toml")
+}
+fn policy_file() -> PathBuf {
+    volta_home().join("policy.json")
+}
 fn user_platform_file() -> PathBuf {
     user_dir().join("platform.json")
 }
+fn profile_platform_file(profile: &str) -> PathBuf {
+    volta_tools_dir()
+        .join("profiles")
+        .join(profile)
+        .join("user")
+        .join("platform.json")
+}
 
 pub struct Sandbox {
     root: PathBuf,
@@ -525,6 +670,13 @@ impl Sandbox {
         self.root.clone()
     }
 
+    /// Makes the image of a package a link to a source directory, as `volta link` does
+    pub fn link_package_image(&self, name: &str, version: &str, source: &Path) {
+        let image_dir = package_image_dir(name, version);
+        ok_or_panic! { fs::create_dir_all(image_dir.parent().unwrap()) };
+        ok_or_panic! { create_dir_symlink(source.to_path_buf(), image_dir) };
+    }
+
     /// Create a `ProcessBuilder` to run a program in the project.
     /// Example:
     ///         assert_that(
@@ -596,6 +748,14 @@ impl Sandbox {
         read_file_to_string(package_file)
     }
 
+    pub fn read_config(&self) -> String {
+        read_file_to_string(config_file())
+    }
+
+    pub fn read_platform(&self) -> String {
+        read_file_to_string(user_platform_file())
+    }
+
     pub fn read_postscript(&self) -> String {
         let postscript_file = volta_postscript();
         read_file_to_string(postscript_file)
@@ -605,6 +765,14 @@ impl Sandbox {
         fs::read_dir(volta_log_dir()).ok()
     }
 
+    pub fn read_trace_dir(&self) -> Option<fs::ReadDir> {
+        fs::read_dir(volta_log_dir().join("traces")).ok()
+    }
+
+    pub fn read_bin_config(&self, name: &str) -> String {
+        read_file_to_string(binary_config_file(name))
+    }
+
     // check that files in the sandbox exist
 
     pub fn package_config_exists(name: &str) -> bool {
@@ -620,6 +788,22 @@ impl Sandbox {
         let package_img_dir = package_image_dir(name, version);
         package_img_dir.join("package.json").exists()
     }
+    pub fn yarn_inventory_tarball_exists(version: &str) -> bool {
+        yarn_inventory_dir()
+            .join(format!("yarn-v{}.tar.gz", version))
+            .exists()
+    }
+    pub fn yarn_image_exists(version: &str) -> bool {
+        image_dir().join("yarn").join(version).is_dir()
+    }
+    pub fn runtime_image_exists(tool: &str, version: &str) -> bool {
+        runtime_image_dir(tool, version).is_dir()
+    }
+    pub fn runtime_inventory_exists(tool: &str, version: &str) -> bool {
+        runtime_inventory_dir(tool)
+            .join(format!("{}-v{}.zip", tool, version))
+            .exists()
+    }
     pub fn pkg_inventory_tarball_exists(name: &str, version: &str) -> bool {
         let pkg_inventory_dir = package_inventory_dir();
         pkg_inventory_dir
@@ -0,0 +1,50 @@
+use std::fs::read_to_string;
+
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_core::env::TRACE;
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON: &'static str = r#"{
+    "name": "text-package",
+    "volta": {
+        "node": "10.22.123"
+    }
+}"#;
+
+#[test]
+fn writes_trace_when_enabled() {
+    let s = sandbox().package_json(PACKAGE_JSON).env(TRACE, "1").build();
+
+    assert_that!(
+        s.npm("install ember-cli --global"),
+        execs().with_status(ExitCode::ExecutionFailure as i32)
+    );
+
+    let traces: Vec<_> = s
+        .read_trace_dir()
+        .expect("Could not read trace directory")
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(traces.len(), 1);
+
+    let trace = read_to_string(&traces[0]).unwrap();
+    assert!(trace.contains(r#""traceEvents":["#));
+    assert!(trace.contains(r#""name":"npm""#));
+    assert!(trace.contains(r#""name":"detect project""#));
+}
+
+#[test]
+fn writes_no_trace_by_default() {
+    let s = sandbox().package_json(PACKAGE_JSON).build();
+
+    assert_that!(
+        s.npm("install ember-cli --global"),
+        execs().with_status(ExitCode::ExecutionFailure as i32)
+    );
+
+    assert!(s.read_trace_dir().is_none());
+}
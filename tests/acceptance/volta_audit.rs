@@ -0,0 +1,82 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+const PKG_CONFIG_COWSAY: &'static str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
+const COWSAY_ADVISORIES: &'static str = r#"{
+  "cowsay": [
+    {
+      "id": 1234,
+      "title": "Command Injection",
+      "url": "https://github.com/advisories/GHSA-0000-0000-0000",
+      "severity": "critical",
+      "vulnerable_versions": "<1.5.0"
+    }
+  ]
+}"#;
+
+#[test]
+fn audit_without_vulnerabilities() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_COWSAY)
+        .package_image("cowsay", "1.4.0")
+        .advisories("{}")
+        .build();
+
+    assert_that!(
+        s.volta("audit"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("No known vulnerabilities were found in the installed packages.")
+    );
+}
+
+#[test]
+fn audit_reports_vulnerabilities() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_COWSAY)
+        .package_image("cowsay", "1.4.0")
+        .advisories(COWSAY_ADVISORIES)
+        .build();
+
+    assert_that!(
+        s.volta("audit"),
+        execs()
+            .with_status(1)
+            .with_stdout_contains("cowsay@1.4.0")
+            .with_stdout_contains("    critical: Command Injection (cowsay@1.4.0)")
+            .with_stdout_contains("        affects: <1.5.0")
+    );
+}
+
+#[test]
+fn audit_as_json() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_COWSAY)
+        .package_image("cowsay", "1.4.0")
+        .advisories(COWSAY_ADVISORIES)
+        .build();
+
+    assert_that!(
+        s.volta("audit --json"),
+        execs()
+            .with_status(1)
+            .with_stdout_contains(r#"    "name": "cowsay","#)
+            .with_stdout_contains(r#"          "severity": "critical","#)
+    );
+}
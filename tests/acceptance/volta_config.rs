@@ -0,0 +1,145 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_core::env::PARTIAL_PINS;
+use volta_fail::ExitCode;
+
+const YARN_ONLY_PACKAGE_JSON: &'static str = r#"{
+  "name": "test-package",
+  "volta": {
+    "yarn": "1.19.0"
+  }
+}"#;
+
+const DEFAULT_PLATFORM: &'static str =
+    r#"{"node":{"runtime":"9.11.2","npm":"5.6.0"},"yarn":"1.22.19"}"#;
+
+#[test]
+fn sets_and_gets_settings() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("config set limit-rate 500k"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("set limit-rate to 500k")
+    );
+    assert_that!(
+        s.volta("config set progress false"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert_eq!(
+        s.read_config(),
+        "# Volta settings (see `volta config list`)\nversion = 1\nlimit-rate = \"500k\"\nprogress = false\n"
+    );
+
+    assert_that!(
+        s.volta("config get limit-rate"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("\"500k\"")
+    );
+    assert_that!(
+        s.volta("config list"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("progress[..]false[..][VOLTA_NO_PROGRESS]")
+            .with_stdout_contains("tmp-dir[..](unset)[..][VOLTA_TMPDIR]")
+            .with_stdout_contains("timeout[..](unset)[..][VOLTA_NETWORK_TIMEOUT]")
+    );
+}
+
+#[test]
+fn rejects_unknown_settings_and_values() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("config set offline true"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]Unknown setting `offline`")
+    );
+    assert_that!(
+        s.volta("config set passthrough sometimes"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains(
+                "[..]Invalid value \"sometimes\" for `passthrough`: expected \"allow\", \"warn\", or \"deny\""
+            )
+    );
+}
+
+#[test]
+fn reports_the_line_of_an_invalid_setting() {
+    let s = sandbox()
+        .config("version = 1\n\nprogress = \"off\"\n")
+        .build();
+
+    assert_that!(
+        s.volta("list"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "[..]Invalid setting `progress` on line 3 of [..]config.toml[..]"
+            )
+    );
+}
+
+#[test]
+fn applies_settings_to_shims() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .config("version = 1\npartial-pins = \"deny\"\n")
+        .build();
+
+    assert_that!(
+        s.yarn("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "[..]This project's package.json pins yarn@1.19.0 without pinning Node."
+            )
+    );
+}
+
+#[test]
+fn shims_ignore_invalid_settings() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .config("version = 1\nprogres = false\n")
+        .env(PARTIAL_PINS, "deny")
+        .build();
+
+    assert_that!(
+        s.yarn("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Ignoring the Volta settings: Invalid setting `progres`[..]")
+            .with_stderr_contains(
+                "[..]This project's package.json pins yarn@1.19.0 without pinning Node."
+            )
+    );
+}
+
+#[test]
+fn environment_overrides_settings() {
+    let s = sandbox()
+        .package_json(YARN_ONLY_PACKAGE_JSON)
+        .platform(DEFAULT_PLATFORM)
+        .config("version = 1\npartial-pins = \"deny\"\n")
+        .env(PARTIAL_PINS, "allow")
+        .build();
+
+    assert_that!(
+        s.volta("which --explain yarn"),
+        execs()
+            .with_status(ExitCode::UnknownError as i32)
+            .with_stdout_contains(
+                "The project pins yarn@1.19.0 without pinning Node, which is allowed (see VOLTA_PARTIAL_PINS)"
+            )
+    );
+}
@@ -104,3 +104,46 @@ fn no_project_with_user_node_default() {
             .with_stdout_contains("user: v9.11.2 (active)")
     );
 }
+
+#[test]
+fn porcelain_pinned_project() {
+    let s = sandbox()
+        .package_json(&package_json_with_pinned_node("4.1.0", "2.14.3"))
+        .platform(r#"{"node":{"runtime":"9.11.2","npm":"5.6.0"},"yarn":"1.22.19"}"#)
+        .build();
+
+    assert_that!(
+        s.volta("current --porcelain"),
+        execs()
+            .with_status(0)
+            .with_stdout("node:4.1.0 yarn:1.22.19 source:project")
+    );
+}
+
+#[test]
+fn porcelain_user_default() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .platform(r#"{"node":{"runtime":"9.11.2","npm":"5.6.0"},"yarn":"1.22.19"}"#)
+        .build();
+
+    assert_that!(
+        s.volta("current --porcelain"),
+        execs()
+            .with_status(0)
+            .with_stdout("node:9.11.2 yarn:1.22.19 source:default")
+    );
+}
+
+#[test]
+fn porcelain_no_platform() {
+    let s = sandbox().package_json(BASIC_PACKAGE_JSON).build();
+
+    assert_that!(
+        s.volta("current --porcelain"),
+        execs()
+            .with_status(ExitCode::NoVersionMatch as i32)
+            .with_stdout("")
+            .with_stderr("")
+    );
+}
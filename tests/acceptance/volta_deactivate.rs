@@ -19,3 +19,37 @@ fn deactivate_bash() {
         "export PATH='/usr/bin:/usr/local/bin'\nunset VOLTA_HOME\n",
     )
 }
+
+#[test]
+#[cfg(unix)]
+fn deactivate_powershell() {
+    let s = sandbox()
+        .volta_shell("pwsh")
+        .path_dir("/usr/bin")
+        .path_dir("/usr/local/bin")
+        .build();
+
+    assert_that!(s.volta("deactivate"), execs().with_status(0));
+
+    assert_eq!(
+        s.read_postscript(),
+        "$env:PATH = '/usr/bin:/usr/local/bin'\nRemove-Item Env:VOLTA_HOME -ErrorAction SilentlyContinue\n",
+    )
+}
+
+#[test]
+#[cfg(unix)]
+fn deactivate_nushell() {
+    let s = sandbox()
+        .volta_shell("nu")
+        .path_dir("/usr/bin")
+        .path_dir("/usr/local/bin")
+        .build();
+
+    assert_that!(s.volta("deactivate"), execs().with_status(0));
+
+    assert_eq!(
+        s.read_postscript(),
+        "{\"set\":{\"PATH\":[\"/usr/bin\",\"/usr/local/bin\"]},\"unset\":[\"VOLTA_HOME\"]}\n",
+    )
+}
@@ -0,0 +1,34 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+#[test]
+fn default_npm_requires_default_node() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("default npm@6"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "[..]Cannot set a default npm because there is no default Node version."
+            )
+    );
+}
+
+#[test]
+fn default_package_is_rejected() {
+    let s = sandbox()
+        .platform(r#"{"node":{"runtime":"9.11.2","npm":"5.6.0"}}"#)
+        .build();
+
+    assert_that!(
+        s.volta("default ember-cli@3"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]Only node, npm, yarn, deno, and bun have a default version")
+    );
+}
@@ -0,0 +1,46 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON_WITHOUT_PINS: &str = r#"{
+    "name": "without-pins"
+}"#;
+
+#[test]
+fn fetch_pinned_outside_project() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("fetch --pinned"),
+        execs().with_status(ExitCode::ConfigurationError as i32)
+    );
+}
+
+#[test]
+fn fetch_pinned_without_node() {
+    let s = sandbox().package_json(PACKAGE_JSON_WITHOUT_PINS).build();
+
+    assert_that!(
+        s.volta("fetch --pinned"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "[..]Cannot fetch the pinned tools because the Node version is not pinned in this project."
+            )
+    );
+}
+
+#[test]
+fn rejects_headers_for_other_tools() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("fetch node@10.16.0 yarn@1.17.3 --headers"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]Cannot fetch headers for yarn version 1.17.3.")
+    );
+}
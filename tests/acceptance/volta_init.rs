@@ -0,0 +1,159 @@
+use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const BASIC_PACKAGE_JSON: &'static str = r#"{
+  "name": "test-package"
+}"#;
+
+fn package_json_with_pinned_node(node: &str) -> String {
+    format!(
+        r#"{{
+  "name": "test-package",
+  "volta": {{
+    "node": "{}"
+  }}
+}}"#,
+        node
+    )
+}
+
+const NODE_VERSION_INFO: &'static str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]},
+{"version":"v9.27.6","npm":"5.6.17","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]},
+{"version":"v8.9.10","npm":"5.6.7","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]},
+{"version":"v6.19.62","npm":"3.10.1066","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]}
+]
+"#;
+
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "macos")] {
+        const NODE_VERSION_FIXTURES: [DistroMetadata; 4] = [
+            DistroMetadata {
+                version: "10.99.1040",
+                compressed_size: 273,
+                uncompressed_size: Some(0x00280000),
+            },
+            DistroMetadata {
+                version: "9.27.6",
+                compressed_size: 272,
+                uncompressed_size: Some(0x00280000),
+            },
+            DistroMetadata {
+                version: "8.9.10",
+                compressed_size: 272,
+                uncompressed_size: Some(0x00280000),
+            },
+            DistroMetadata {
+                version: "6.19.62",
+                compressed_size: 273,
+                uncompressed_size: Some(0x00280000),
+            },
+        ];
+    } else if #[cfg(target_os = "linux")] {
+        const NODE_VERSION_FIXTURES: [DistroMetadata; 4] = [
+            DistroMetadata {
+                version: "10.99.1040",
+                compressed_size: 273,
+                uncompressed_size: Some(0x00280000),
+            },
+            DistroMetadata {
+                version: "9.27.6",
+                compressed_size: 272,
+                uncompressed_size: Some(0x00280000),
+            },
+            DistroMetadata {
+                version: "8.9.10",
+                compressed_size: 270,
+                uncompressed_size: Some(0x00280000),
+            },
+            DistroMetadata {
+                version: "6.19.62",
+                compressed_size: 273,
+                uncompressed_size: Some(0x00280000),
+            },
+        ];
+    } else if #[cfg(target_os = "windows")] {
+        const NODE_VERSION_FIXTURES: [DistroMetadata; 4] = [
+            DistroMetadata {
+                version: "10.99.1040",
+                compressed_size: 1096,
+                uncompressed_size: None,
+            },
+            DistroMetadata {
+                version: "9.27.6",
+                compressed_size: 1068,
+                uncompressed_size: None,
+            },
+            DistroMetadata {
+                version: "8.9.10",
+                compressed_size: 1055,
+                uncompressed_size: None,
+            },
+            DistroMetadata {
+                version: "6.19.62",
+                compressed_size: 1056,
+                uncompressed_size: None,
+            },
+        ];
+    } else {
+        compile_error!("Unsupported target_os for tests (expected 'macos', 'linux', or 'windows').");
+    }
+}
+
+#[test]
+fn init_pins_node_in_project() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("init --yes --no-setup --node 6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node("6.19.62"),
+    )
+}
+
+#[test]
+fn init_defaults_to_lts() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("init --yes --no-setup"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node("10.99.1040"),
+    )
+}
+
+#[test]
+fn init_without_pin() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("init --yes --no-setup --no-pin --node 6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON)
+}
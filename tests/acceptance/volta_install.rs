@@ -0,0 +1,64 @@
+use crate::support::sandbox::{sandbox, Sandbox};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const NODE_VERSION_INFO: &'static str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]}
+]
+"#;
+
+#[test]
+fn rejects_arch_for_other_tools() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("install node@10.16.0 yarn@1.17.3 --arch x64"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains(
+                "[..]Cannot install yarn version 1.17.3 for another architecture."
+            )
+    );
+}
+
+#[test]
+fn install_node_creates_built_in_shims() {
+    let s = sandbox().node_available_versions(NODE_VERSION_INFO).build();
+
+    assert_that!(
+        s.volta("install --dry-run node@10.99.1040"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("would write [..]corepack[..]")
+            .with_stdout_contains("would write [..]npx[..]")
+    );
+    assert!(!Sandbox::shim_exists("corepack"));
+}
+
+#[test]
+fn install_bare_node_without_a_terminal_uses_lts() {
+    let s = sandbox().node_available_versions(NODE_VERSION_INFO).build();
+
+    assert_that!(
+        s.volta("install --dry-run node"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("would download node@10.99.1040[..]")
+    );
+}
+
+#[test]
+fn install_bare_node_with_yes() {
+    let s = sandbox().node_available_versions(NODE_VERSION_INFO).build();
+
+    assert_that!(
+        s.volta("install --dry-run --yes node"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("would download node@10.99.1040[..]")
+            .with_stdout_contains("would set node@10.99.1040 as the default in [..]platform.json")
+    );
+}
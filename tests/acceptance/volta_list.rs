@@ -0,0 +1,225 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PKG_CONFIG_COWSAY: &'static str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay",
+    "cowthink"
+  ],
+  "spec": "cowsay@^1",
+  "installed": "2019-06-01T12:00:00+00:00"
+}"#;
+
+#[test]
+fn list_packages_without_packages() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("list packages"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("No packages are installed.")
+    );
+}
+
+#[test]
+fn list_packages_with_platform() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_COWSAY)
+        .build();
+
+    assert_that!(
+        s.volta("list packages"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("cowsay@1.4.0")
+            .with_stdout_contains("    platform: node 11.10.1, npm 6.7.0")
+            .with_stdout_contains("    bins: cowsay, cowthink")
+    );
+}
+
+#[test]
+fn list_packages_as_json() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_COWSAY)
+        .build();
+
+    assert_that!(
+        s.volta("list packages --json"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains(r#"    "name": "cowsay","#)
+            .with_stdout_contains(r#"      "node": "11.10.1","#)
+            .with_stdout_contains(r#"      "npm": "6.7.0","#)
+            .with_stdout_contains(r#"    "installed": "2019-06-01T12:00:00+00:00","#)
+            .with_stdout_contains(r#"    "spec": "cowsay@^1""#)
+    );
+}
+
+const PROJECT_PINNING_NODE: &'static str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "4.1.0",
+    "npm": "2.14.3"
+  }
+}"#;
+
+const USER_PLATFORM: &'static str =
+    r#"{"node":{"runtime":"9.11.2","npm":"5.6.0"},"yarn":"1.12.3"}"#;
+
+#[test]
+fn list_tools_from_project() {
+    let s = sandbox()
+        .package_json(PROJECT_PINNING_NODE)
+        .platform(USER_PLATFORM)
+        .build();
+    let pin_file = s.root().join("package.json");
+
+    assert_that!(
+        s.volta("list"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains(format!(
+                "node   4.1.0      project ({})",
+                pin_file.display()
+            ))
+            .with_stdout_contains(format!(
+                "npm    2.14.3     project ({})",
+                pin_file.display()
+            ))
+            .with_stdout_contains("yarn   1.12.3     default")
+            .with_stdout_contains("deno   system     passthrough")
+            .with_stdout_does_not_contain("9.11.2")
+    );
+}
+
+#[test]
+fn list_tools_from_defaults() {
+    let s = sandbox().platform(USER_PLATFORM).build();
+
+    assert_that!(
+        s.volta("list"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("node   9.11.2     default")
+            .with_stdout_contains("npm    5.6.0      default")
+            .with_stdout_contains("yarn   1.12.3     default")
+    );
+}
+
+#[test]
+fn list_tools_without_versions() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("list"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("node   system     passthrough")
+            .with_stdout_contains("npm    system     passthrough")
+            .with_stdout_contains("yarn   system     passthrough")
+    );
+}
+
+#[test]
+fn list_only_default_tools() {
+    let s = sandbox()
+        .package_json(PROJECT_PINNING_NODE)
+        .platform(USER_PLATFORM)
+        .build();
+
+    assert_that!(
+        s.volta("list --default"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("node   9.11.2     default")
+            .with_stdout_does_not_contain("4.1.0")
+            .with_stdout_does_not_contain("passthrough")
+    );
+}
+
+#[test]
+fn list_only_project_tools_without_project() {
+    let s = sandbox().platform(USER_PLATFORM).build();
+
+    assert_that!(
+        s.volta("list --project"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("No tools are pinned in this project.")
+    );
+}
+
+#[test]
+fn list_all_tools() {
+    let s = sandbox()
+        .package_json(PROJECT_PINNING_NODE)
+        .platform(USER_PLATFORM)
+        .build();
+    let pin_file = s.root().join("package.json");
+
+    assert_that!(
+        s.volta("list --all"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains(format!(
+                "node   4.1.0      project ({}) (active)",
+                pin_file.display()
+            ))
+            .with_stdout_contains("node   9.11.2     default")
+            .with_stdout_does_not_contain("9.11.2     default (active)")
+            .with_stdout_contains("yarn   1.12.3     default")
+    );
+}
+
+#[test]
+fn list_tools_as_json() {
+    let s = sandbox().platform(USER_PLATFORM).build();
+
+    assert_that!(
+        s.volta("list --json"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains(r#"    "name": "node","#)
+            .with_stdout_contains(r#"    "version": "9.11.2","#)
+            .with_stdout_contains(r#"      "type": "default""#)
+            .with_stdout_contains(r#"    "active": true"#)
+    );
+}
+
+#[test]
+fn list_rejects_dry_run() {
+    let s = sandbox().platform(USER_PLATFORM).build();
+
+    assert_that!(
+        s.volta("list --dry-run"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]This command can't be previewed with --dry-run.")
+    );
+}
+
+#[test]
+fn version_allows_dry_run() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("--dry-run --version"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..].[..].[..]")
+    );
+}
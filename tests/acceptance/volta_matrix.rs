@@ -0,0 +1,70 @@
+// The matrix runs shell scripts as its commands, so these tests only run on Unix
+
+use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_core::env::NODE_VERSION;
+use volta_fail::ExitCode;
+
+const NODE_VERSION_INFO: &'static str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]},
+{"version":"v6.19.62","npm":"3.10.1066","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]}
+]
+"#;
+
+const NODE_VERSION_FIXTURES: [DistroMetadata; 2] = [
+    DistroMetadata {
+        version: "10.99.1040",
+        compressed_size: 273,
+        uncompressed_size: Some(0x00280000),
+    },
+    DistroMetadata {
+        version: "6.19.62",
+        compressed_size: 273,
+        uncompressed_size: Some(0x00280000),
+    },
+];
+
+/// A command that only passes with Node 10.99.1040.
+fn check_node_script() -> String {
+    format!(
+        "#!/bin/sh\necho \"checking node=${}\"\n[ \"${}\" = \"10.99.1040\" ]\n",
+        NODE_VERSION, NODE_VERSION
+    )
+}
+
+#[test]
+fn passes_when_every_version_passes() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .path_executable("check-node", &check_node_script())
+        .build();
+
+    assert_that!(
+        s.volta("matrix --node 10.99.1040 -- check-node"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("checking node=10.99.1040")
+            .with_stdout_contains("node@10.99.1040: passed")
+    );
+}
+
+#[test]
+fn fails_when_any_version_fails() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .path_executable("check-node", &check_node_script())
+        .build();
+
+    assert_that!(
+        s.volta("matrix --node 10.99.1040,6.19.62 -- check-node"),
+        execs()
+            .with_status(ExitCode::ExecutionFailure as i32)
+            .with_stdout_contains("node@10.99.1040: passed")
+            .with_stdout_contains("node@6.19.62: failed (exit code 1)")
+    );
+}
@@ -1,4 +1,4 @@
-use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture, YarnFixture};
+use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture, Sandbox, YarnFixture};
 use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
 use test_support::matchers::execs;
@@ -214,6 +214,99 @@ fn pin_node() {
     )
 }
 
+#[test]
+fn pin_node_dry_run() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin --dry-run node@6"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("would download node@6.19.62 into [..]")
+            .with_stdout_contains("would set `volta.node` to \"6.19.62\" in [..]package.json")
+    );
+
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON);
+}
+
+#[test]
+fn pin_with_hash_dry_run_is_unsupported() {
+    let s = sandbox().package_json(BASIC_PACKAGE_JSON).build();
+
+    assert_that!(
+        s.volta("pin --dry-run --with-hash node@6"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains(
+                "[..]Pinning with --with-hash can't be previewed with --dry-run."
+            )
+    );
+}
+
+#[test]
+fn pin_node_outside_policy() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .policy(r#"{ "node": { "versions": ">=8" } }"#)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]node@6.19.62 is not allowed by the version policy in [..], which only allows versions >=8[..]")
+    );
+
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON);
+}
+
+#[test]
+fn pin_node_lts_policy() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .policy(r#"{ "node": { "lts": true } }"#)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@9"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]which only allows LTS versions[..]")
+    );
+
+    assert_that!(
+        s.volta("pin node@10"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node("10.99.1040"),
+    )
+}
+
+#[test]
+fn pin_node_missing_policy() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .env("VOLTA_POLICY", "/nonexistent/policy.json")
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs().with_status(ExitCode::FileSystemError as i32)
+    );
+}
+
 #[test]
 fn pin_node_reports_info() {
     let s = sandbox()
@@ -403,6 +496,50 @@ fn pin_yarn_missing_release() {
     )
 }
 
+#[test]
+fn pin_node_and_yarn() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@10 yarn@1.4"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node_yarn("10.99.1040", "1.4.159"),
+    );
+    assert!(Sandbox::runtime_image_exists("node", "10.99.1040"));
+    assert!(Sandbox::yarn_image_exists("1.4.159"));
+}
+
+#[test]
+fn pin_node_and_missing_yarn() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .mock_not_found()
+        .build();
+
+    assert_that!(
+        s.volta("pin node@10 yarn@1.3.1"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]Could not download yarn version 1.3.1")
+    );
+
+    // Nothing is pinned, but the fetch of Node still finishes
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON);
+    assert!(Sandbox::runtime_image_exists("node", "10.99.1040"));
+}
+
 #[test]
 fn pin_yarn_leaves_npm() {
     let s = sandbox()
@@ -0,0 +1,141 @@
+use std::fs;
+
+use crate::support::sandbox::{sandbox, Sandbox};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PLATFORM: &'static str = r#"{"node":{"runtime":"10.99.1040","npm":null},"yarn":null}"#;
+
+const NODE_VERSION_INFO: &'static str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip"]}
+]
+"#;
+
+fn pkg_config(name: &str, version: &str, bins: &[&str]) -> String {
+    format!(
+        r#"{{
+  "name": "{}",
+  "version": "{}",
+  "platform": {{
+    "node": {{
+      "runtime": "10.99.1040",
+      "npm": null
+    }},
+    "yarn": null
+  }},
+  "bins": [{}]
+}}"#,
+        name,
+        version,
+        bins.iter()
+            .map(|bin| format!(r#""{}""#, bin))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+fn bin_config(name: &str, package: &str, version: &str) -> String {
+    format!(
+        r#"{{
+  "name": "{}",
+  "package": "{}",
+  "version": "{}",
+  "path": "./{}.js",
+  "platform": {{
+    "node": {{
+      "runtime": "10.99.1040",
+      "npm": null
+    }},
+    "yarn": null
+  }}
+}}"#,
+        name, package, version, name
+    )
+}
+
+#[test]
+fn link_package_with_conflicting_bin_warns_and_keeps_both() {
+    let s = sandbox()
+        .platform(PLATFORM)
+        .node_available_versions(NODE_VERSION_INFO)
+        .package_config(
+            "cowsay",
+            &pkg_config("cowsay", "1.4.0", &["cowsay", "cowthink"]),
+        )
+        .package_image_with_bins("cowsay", "1.4.0", &["cowsay", "cowthink"])
+        .binary_config("cowsay", &bin_config("cowsay", "cowsay", "1.4.0"))
+        .binary_config("cowthink", &bin_config("cowthink", "cowsay", "1.4.0"))
+        .shim("cowsay")
+        .shim("cowthink")
+        .package_json(r#"{"name":"moosay","version":"1.0.0","bin":{"cowsay":"./cowsay.js"}}"#)
+        .build();
+    fs::write(s.root().join("cowsay.js"), "#!/usr/bin/env node\n").unwrap();
+
+    assert_that!(
+        s.volta("link"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stderr_contains(
+                "[..]Executable 'cowsay' is also provided by 'cowsay', and will now run from 'moosay'."
+            )
+            .with_stdout_contains("[..]linked moosay@1.0.0 with executables: cowsay")
+    );
+
+    assert!(Sandbox::package_config_exists("cowsay"));
+    assert!(Sandbox::package_config_exists("moosay"));
+    assert!(s
+        .read_bin_config("cowsay")
+        .contains(r#""package": "moosay""#));
+    assert!(s
+        .read_bin_config("cowthink")
+        .contains(r#""package": "cowsay""#));
+}
+
+#[test]
+fn prefer_takes_over_executable() {
+    let s = sandbox()
+        .package_config(
+            "cowsay",
+            &pkg_config("cowsay", "1.4.0", &["cowsay", "cowthink"]),
+        )
+        .package_image_with_bins("cowsay", "1.4.0", &["cowsay", "cowthink"])
+        .package_config("moosay", &pkg_config("moosay", "1.0.0", &["cowsay"]))
+        .package_image_with_bins("moosay", "1.0.0", &["cowsay"])
+        .binary_config("cowsay", &bin_config("cowsay", "cowsay", "1.4.0"))
+        .binary_config("cowthink", &bin_config("cowthink", "cowsay", "1.4.0"))
+        .shim("cowsay")
+        .shim("cowthink")
+        .build();
+
+    assert_that!(
+        s.volta("prefer moosay"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]moosay now provides executables: cowsay")
+    );
+
+    assert!(s
+        .read_bin_config("cowsay")
+        .contains(r#""package": "moosay""#));
+    assert!(s
+        .read_bin_config("cowthink")
+        .contains(r#""package": "cowsay""#));
+}
+
+#[test]
+fn prefer_unknown_executable() {
+    let s = sandbox()
+        .package_config("moosay", &pkg_config("moosay", "1.0.0", &["cowsay"]))
+        .package_image_with_bins("moosay", "1.0.0", &["cowsay"])
+        .binary_config("cowsay", &bin_config("cowsay", "moosay", "1.0.0"))
+        .shim("cowsay")
+        .build();
+
+    assert_that!(
+        s.volta("prefer moosay cowthink"),
+        execs().with_status(ExitCode::InvalidArguments as i32)
+    );
+}
@@ -0,0 +1,84 @@
+use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture, YarnFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON_WITHOUT_PINS: &str = r#"{
+    "name": "without-pins"
+}"#;
+
+const PACKAGE_JSON_WITH_PINS: &str = r#"{
+    "name": "with-pins",
+    "volta": {
+        "node": "6.19.62",
+        "yarn": "1.2.42"
+    }
+}"#;
+
+const NODE_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "6.19.62",
+    compressed_size: 273,
+    uncompressed_size: Some(0x00280000),
+}];
+
+const YARN_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "1.2.42",
+    compressed_size: 174,
+    uncompressed_size: Some(0x00280000),
+}];
+
+#[test]
+fn provision_outside_project() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("provision --dest prefix"),
+        execs().with_status(ExitCode::ConfigurationError as i32)
+    );
+}
+
+#[test]
+fn provision_without_node() {
+    let s = sandbox().package_json(PACKAGE_JSON_WITHOUT_PINS).build();
+
+    assert_that!(
+        s.volta("provision --from package.json --dest prefix"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "[..]Cannot provision the pinned tools because the Node version is not pinned in this project."
+            )
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn provision_pinned_platform() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_PINS)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+    let prefix = s.root().join("prefix");
+
+    assert_that!(
+        s.volta(&format!(
+            "provision --from package.json --dest {}",
+            prefix.display()
+        )),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]provisioned node@6.19.62, npm@[..], yarn@1.2.42 into [..]")
+    );
+
+    let modules = prefix.join("lib").join("node_modules");
+    assert!(modules.join("npm").join("package.json").is_file());
+    assert!(modules.join("yarn").join("README").is_file());
+    assert_eq!(
+        std::fs::read_link(prefix.join("bin").join("yarn")).unwrap(),
+        std::path::PathBuf::from("../lib/node_modules/yarn/bin/yarn.js")
+    );
+    assert!(!prefix.join(".volta-checksums.json").exists());
+}
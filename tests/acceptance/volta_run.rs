@@ -0,0 +1,40 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON_WITH_COMMANDS: &str = r#"{
+    "name": "with-commands",
+    "volta": {
+        "node": "10.99.1040",
+        "commands": {
+            "lint": "eslint .",
+            "test": "jest --ci"
+        }
+    }
+}"#;
+
+#[test]
+fn run_unknown_command() {
+    let s = sandbox().package_json(PACKAGE_JSON_WITH_COMMANDS).build();
+
+    assert_that!(
+        s.volta("run build"),
+        execs()
+            .with_status(ExitCode::ExecutableNotFound as i32)
+            .with_stderr_contains("[..]Could not find the command \"build\" in this project.")
+            .with_stderr_contains("[..]The project's commands are: lint, test")
+    );
+}
+
+#[test]
+fn run_outside_project() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("run test"),
+        execs().with_status(ExitCode::ConfigurationError as i32)
+    );
+}
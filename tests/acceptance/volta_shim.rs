@@ -0,0 +1,88 @@
+use crate::support::sandbox::{sandbox, Sandbox};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const BIN_CONFIG: &'static str = r#"{
+  "name": "cowsay",
+  "package": "cowsay",
+  "version": "1.4.0",
+  "path": "./cli.js",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  }
+}"#;
+
+#[test]
+fn lists_shims_with_their_packages() {
+    let s = sandbox()
+        .binary_config("cowsay", BIN_CONFIG)
+        .shim("cowsay")
+        .shim("node")
+        .shim("tsc")
+        .build();
+
+    assert_that!(
+        s.volta("shim list"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("cowsay  cowsay")
+            .with_stdout_contains("node    (built-in)")
+            .with_stdout_contains("tsc     (no package)")
+    );
+}
+
+#[test]
+fn adds_and_removes_shims() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("shim add tsc"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("created shim for tsc")
+    );
+    assert_that!(
+        s.volta("shim add tsc"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("shim for tsc already exists")
+    );
+    assert_that!(
+        s.volta("shim list"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("tsc  (no package)")
+    );
+    assert_that!(
+        s.volta("shim remove tsc"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("removed shim for tsc")
+    );
+    assert_that!(
+        s.volta("shim remove tsc"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("there is no shim for tsc")
+    );
+}
+
+#[test]
+fn cannot_remove_built_in_shims() {
+    let s = sandbox().shim("node").build();
+
+    assert_that!(
+        s.volta("shim remove node"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]Cannot remove built-in shim for \"node\"")
+    );
+    assert!(Sandbox::shim_exists("node"));
+}
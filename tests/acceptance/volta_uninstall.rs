@@ -1,3 +1,5 @@
+use std::fs;
+
 use crate::support::sandbox::{sandbox, Sandbox};
 use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
@@ -32,6 +34,21 @@ const PKG_CONFIG_NO_BINS: &'static str = r#"{
   "bins": []
 }"#;
 
+const PKG_CONFIG_MOOSAY: &'static str = r#"{
+  "name": "moosay",
+  "version": "1.0.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
 fn bin_config(name: &str) -> String {
     format!(
         r#"{{
@@ -102,6 +119,36 @@ fn uninstall_package_basic() {
     assert!(Sandbox::pkg_inventory_shasum_exists("cowsay", "1.4.0"));
 }
 
+#[test]
+fn uninstall_package_dry_run() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_BASIC)
+        .binary_config("cowsay", &bin_config("cowsay"))
+        .binary_config("cowthink", &bin_config("cowthink"))
+        .shim("cowsay")
+        .shim("cowthink")
+        .package_image("cowsay", "1.4.0")
+        .package_inventory("cowsay", "1.4.0")
+        .build();
+
+    assert_that!(
+        s.volta("uninstall --dry-run cowsay"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("would remove [..]cowsay.json")
+            .with_stdout_contains("would remove [..]cowthink.json")
+            .with_stdout_does_not_contain("[..]uninstalled")
+    );
+
+    // nothing is deleted
+    assert!(Sandbox::package_config_exists("cowsay"));
+    assert!(Sandbox::bin_config_exists("cowsay"));
+    assert!(Sandbox::bin_config_exists("cowthink"));
+    assert!(Sandbox::shim_exists("cowsay"));
+    assert!(Sandbox::shim_exists("cowthink"));
+    assert!(Sandbox::package_image_exists("cowsay", "1.4.0"));
+}
+
 #[test]
 fn uninstall_package_no_bins() {
     // the package doesn't contain any executables, it should uninstall without error
@@ -194,3 +241,135 @@ fn uninstall_package_orphaned_bins() {
     assert!(!Sandbox::shim_exists("cowsay"));
     assert!(!Sandbox::shim_exists("cowthink"));
 }
+
+#[test]
+fn uninstall_deno() {
+    let s = sandbox()
+        .platform(r#"{"node":null,"yarn":null,"deno":"1.40.0"}"#)
+        .runtime_image("deno", "1.40.0")
+        .runtime_image("deno", "1.39.4")
+        .runtime_inventory("deno", "1.40.0")
+        .build();
+
+    assert_that!(
+        s.volta("uninstall deno"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("[..]deno uninstalled")
+    );
+
+    assert!(!s.read_platform().contains("deno"));
+    assert!(!Sandbox::runtime_image_exists("deno", "1.40.0"));
+    assert!(!Sandbox::runtime_image_exists("deno", "1.39.4"));
+    assert!(!Sandbox::runtime_inventory_exists("deno", "1.40.0"));
+}
+
+#[test]
+fn uninstall_deno_dry_run() {
+    let s = sandbox()
+        .platform(r#"{"node":null,"yarn":null,"deno":"1.40.0"}"#)
+        .runtime_image("deno", "1.40.0")
+        .build();
+
+    assert_that!(
+        s.volta("uninstall --dry-run deno"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("would remove [..]deno")
+            .with_stdout_contains("would write [..]platform.json")
+            .with_stdout_does_not_contain("[..]uninstalled")
+    );
+
+    assert!(s.read_platform().contains("deno"));
+    assert!(Sandbox::runtime_image_exists("deno", "1.40.0"));
+}
+
+#[test]
+fn uninstall_bun() {
+    let s = sandbox()
+        .platform(r#"{"node":null,"yarn":null,"bun":"1.0.25"}"#)
+        .runtime_image("bun", "1.0.25")
+        .runtime_inventory("bun", "1.0.25")
+        .build();
+
+    assert_that!(
+        s.volta("uninstall bun"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("[..]bun uninstalled")
+    );
+
+    assert!(!s.read_platform().contains("bun"));
+    assert!(!Sandbox::runtime_image_exists("bun", "1.0.25"));
+    assert!(!Sandbox::runtime_inventory_exists("bun", "1.0.25"));
+}
+
+#[test]
+fn uninstall_linked_package_keeps_source() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_BASIC)
+        .binary_config("cowsay", &bin_config("cowsay"))
+        .binary_config("cowthink", &bin_config("cowthink"))
+        .shim("cowsay")
+        .shim("cowthink")
+        .build();
+    let source = s.root().join("cowsay-source");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(
+        source.join("package.json"),
+        r#"{"name":"cowsay","version":"1.4.0"}"#,
+    )
+    .unwrap();
+    s.link_package_image("cowsay", "1.4.0", &source);
+
+    assert_that!(s.volta("uninstall cowsay"), execs().with_status(0));
+
+    assert!(!Sandbox::package_config_exists("cowsay"));
+    assert!(!Sandbox::package_image_exists("cowsay", "1.4.0"));
+    assert!(source.join("package.json").exists());
+}
+
+#[test]
+fn uninstall_linked_package_with_missing_source() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_BASIC)
+        .binary_config("cowsay", &bin_config("cowsay"))
+        .binary_config("cowthink", &bin_config("cowthink"))
+        .shim("cowsay")
+        .shim("cowthink")
+        .build();
+    let source = s.root().join("cowsay-source");
+    fs::create_dir_all(&source).unwrap();
+    s.link_package_image("cowsay", "1.4.0", &source);
+    fs::remove_dir(&source).unwrap();
+
+    assert_that!(s.volta("uninstall cowsay"), execs().with_status(0));
+
+    assert!(!Sandbox::package_config_exists("cowsay"));
+    assert!(!Sandbox::shim_exists("cowsay"));
+}
+
+#[test]
+fn uninstall_hands_executable_over_to_other_package() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_BASIC)
+        .package_image_with_bins("cowsay", "1.4.0", &["cowsay", "cowthink"])
+        .package_config("moosay", PKG_CONFIG_MOOSAY)
+        .package_image_with_bins("moosay", "1.0.0", &["cowsay"])
+        .binary_config("cowsay", &bin_config("cowsay"))
+        .binary_config("cowthink", &bin_config("cowthink"))
+        .shim("cowsay")
+        .shim("cowthink")
+        .build();
+
+    assert_that!(s.volta("uninstall cowsay"), execs().with_status(0));
+
+    assert!(!Sandbox::package_config_exists("cowsay"));
+    assert!(Sandbox::package_config_exists("moosay"));
+    assert!(s
+        .read_bin_config("cowsay")
+        .contains(r#""package": "moosay""#));
+    assert!(Sandbox::shim_exists("cowsay"));
+    assert!(!Sandbox::bin_config_exists("cowthink"));
+    assert!(!Sandbox::shim_exists("cowthink"));
+}
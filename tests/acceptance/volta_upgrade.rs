@@ -0,0 +1,44 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+fn package_config(name: &str) -> String {
+    format!(
+        r#"{{
+  "name": "{0}",
+  "version": "1.0.0",
+  "platform": {{
+    "node": {{
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    }},
+    "yarn": null
+  }},
+  "bins": [
+    "{0}"
+  ],
+  "spec": "file:/nonexistent/{0}"
+}}"#,
+        name
+    )
+}
+
+#[test]
+fn upgrade_all_reports_every_failure() {
+    let s = sandbox()
+        .package_config("cowsay", &package_config("cowsay"))
+        .package_config("typescript", &package_config("typescript"))
+        .build();
+
+    assert_that!(
+        s.volta("upgrade --all"),
+        execs()
+            .with_status(ExitCode::UnknownError as i32)
+            .with_stderr_contains("[..]Could not upgrade `cowsay`[..]")
+            .with_stderr_contains("[..]Could not upgrade `typescript`[..]")
+            .with_stderr_contains("[..]Could not upgrade: cowsay, typescript")
+    );
+}
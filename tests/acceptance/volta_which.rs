@@ -0,0 +1,97 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PKG_CONFIG_COWSAY: &'static str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay",
+    "cowthink"
+  ]
+}"#;
+
+const PKG_CONFIG_MOOSAY: &'static str = r#"{
+  "name": "moosay",
+  "version": "1.0.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
+const BIN_CONFIG_COWSAY_FROM_MOOSAY: &'static str = r#"{
+  "name": "cowsay",
+  "package": "moosay",
+  "version": "1.0.0",
+  "path": "./cowsay.js",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  }
+}"#;
+
+#[test]
+fn which_all_without_packages() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("which --all cowsay"),
+        execs()
+            .with_status(ExitCode::ExecutableNotFound as i32)
+            .with_stderr_contains("[..]No installed package provides the executable 'cowsay'")
+    );
+}
+
+#[test]
+fn which_all_without_matching_package() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_COWSAY)
+        .package_image("cowsay", "1.4.0")
+        .build();
+
+    assert_that!(
+        s.volta("which --all tsc"),
+        execs()
+            .with_status(ExitCode::ExecutableNotFound as i32)
+            .with_stdout_does_not_contain("[..]cowsay[..]")
+            .with_stderr_contains("[..]No installed package provides the executable 'tsc'")
+    );
+}
+
+#[test]
+fn which_all_lists_every_package_starting_with_the_provider() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_COWSAY)
+        .package_image_with_bins("cowsay", "1.4.0", &["cowsay", "cowthink"])
+        .package_config("moosay", PKG_CONFIG_MOOSAY)
+        .package_image_with_bins("moosay", "1.0.0", &["cowsay"])
+        .binary_config("cowsay", BIN_CONFIG_COWSAY_FROM_MOOSAY)
+        .build();
+
+    assert_that!(
+        s.volta("which --all cowsay"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout("[..]moosay[..]1.0.0[..]cowsay.js\n[..]cowsay[..]1.4.0[..]cowsay.js")
+    );
+}
@@ -196,14 +196,14 @@ impl TempProject {
 
     /// Verify that the input Node version has been fetched.
     pub fn node_version_is_fetched(&self, version: &str) -> bool {
-        let distro_file_name = path::node_distro_file_name(version);
+        let distro_file_name = path::node_distro_file_name(version, None);
         let inventory_dir = ok_or_panic! { path::node_inventory_dir() };
         inventory_dir.join(distro_file_name).exists()
     }
 
     /// Verify that the input Node version has been unpacked.
     pub fn node_version_is_unpacked(&self, version: &str, npm_version: &str) -> bool {
-        let unpack_dir = ok_or_panic! { path::node_image_bin_dir(version, npm_version) };
+        let unpack_dir = ok_or_panic! { path::node_image_bin_dir(version, npm_version, None) };
         unpack_dir.exists()
     }
 